@@ -1,5 +1,6 @@
 use serde::Deserialize;
 use simples3_core::storage::MetadataStore;
+use std::path::Path;
 use tabled::{Table, Tabled};
 
 #[derive(Tabled, Deserialize)]
@@ -73,6 +74,20 @@ pub fn set_anonymous_offline(store: &MetadataStore, name: &str, enabled: bool) {
     }
 }
 
+pub fn set_anonymous_list_offline(store: &MetadataStore, name: &str, enabled: bool) {
+    match store.set_bucket_anonymous_list_public(name, enabled) {
+        Ok(()) => println!(
+            "Anonymous list of public objects on '{}' set to {}.",
+            name,
+            if enabled { "enabled" } else { "disabled" }
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 // --- Online (HTTP to server) ---
 
 pub async fn create_online(client: &reqwest::Client, base: &str, name: &str) {
@@ -84,10 +99,10 @@ pub async fn create_online(client: &reqwest::Client, base: &str, name: &str) {
         Ok(r) if r.status().is_success() => println!("Bucket '{}' created.", name),
         Ok(r) => {
             eprintln!("Error: server returned {}", r.status());
-            if let Ok(body) = r.text().await {
-                if !body.is_empty() {
-                    eprintln!("{}", body);
-                }
+            if let Ok(body) = r.text().await
+                && !body.is_empty()
+            {
+                eprintln!("{}", body);
             }
             std::process::exit(1);
         }
@@ -132,10 +147,10 @@ pub async fn delete_online(client: &reqwest::Client, base: &str, name: &str) {
         Ok(r) if r.status().is_success() => println!("Bucket '{}' deleted.", name),
         Ok(r) => {
             eprintln!("Error: server returned {}", r.status());
-            if let Ok(body) = r.text().await {
-                if !body.is_empty() {
-                    eprintln!("{}", body);
-                }
+            if let Ok(body) = r.text().await
+                && !body.is_empty()
+            {
+                eprintln!("{}", body);
             }
             std::process::exit(1);
         }
@@ -165,10 +180,10 @@ pub async fn set_anonymous_online(
         ),
         Ok(r) => {
             eprintln!("Error: server returned {}", r.status());
-            if let Ok(body) = r.text().await {
-                if !body.is_empty() {
-                    eprintln!("{}", body);
-                }
+            if let Ok(body) = r.text().await
+                && !body.is_empty()
+            {
+                eprintln!("{}", body);
             }
             std::process::exit(1);
         }
@@ -178,3 +193,57 @@ pub async fn set_anonymous_online(
         }
     }
 }
+
+pub async fn set_anonymous_list_online(
+    client: &reqwest::Client,
+    base: &str,
+    name: &str,
+    enabled: bool,
+) {
+    let resp = client
+        .put(format!("{}/_admin/buckets/{}/anonymous-list", base, name))
+        .json(&serde_json::json!({ "enabled": enabled }))
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => println!(
+            "Anonymous list of public objects on '{}' set to {}.",
+            name,
+            if enabled { "enabled" } else { "disabled" }
+        ),
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            if let Ok(body) = r.text().await
+                && !body.is_empty()
+            {
+                eprintln!("{}", body);
+            }
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// --- Bucket policy (offline only: PutBucketPolicy is an S3 API call) ---
+
+pub fn set_policy_offline(store: &MetadataStore, name: &str, file: &Path) {
+    let data = match std::fs::read(file) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Error: failed to read '{}': {}", file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let result = simples3_core::s3::policy::parse_bucket_policy(&data)
+        .and_then(|policy| store.put_bucket_policy_by(name, &policy, Some("cli")));
+    match result {
+        Ok(()) => println!("Policy of '{}' set from {}.", name, file.display()),
+        Err(e) => {
+            eprintln!("Error: {}", super::error_detail(&e));
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use serde::Deserialize;
+use simples3_core::auth::credential_export::{self, CredentialExport, ImportReport};
 use simples3_core::auth::credentials;
+use simples3_core::s3::types::{CredentialRateLimit, Permission};
 use simples3_core::storage::MetadataStore;
+use std::path::Path;
 use tabled::{Table, Tabled};
 
 #[derive(Tabled, Deserialize)]
@@ -13,6 +16,45 @@ struct CredentialRow {
     created: String,
     #[tabled(rename = "Active")]
     active: bool,
+    #[tabled(rename = "Permissions", display_with = "display_permissions")]
+    #[serde(default)]
+    permissions: Option<Vec<Permission>>,
+    #[tabled(rename = "Rate Limit", display_with = "display_rate_limit")]
+    #[serde(default)]
+    rate_limit: Option<CredentialRateLimit>,
+    #[tabled(rename = "Bucket", display_with = "display_bucket")]
+    #[serde(default)]
+    bucket: Option<String>,
+}
+
+fn display_bucket(bucket: &Option<String>) -> String {
+    bucket.clone().unwrap_or_else(|| "all".into())
+}
+
+fn display_permissions(permissions: &Option<Vec<Permission>>) -> String {
+    match permissions {
+        Some(list) => list.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(","),
+        None => "all".into(),
+    }
+}
+
+fn display_rate_limit(limit: &Option<CredentialRateLimit>) -> String {
+    let limit = limit.unwrap_or_default();
+    let mut parts = Vec::new();
+    if let Some(rps) = limit.requests_per_sec {
+        parts.push(format!("{} req/s", rps));
+    }
+    if let Some(bps) = limit.bytes_per_sec {
+        parts.push(format!("{} B/s", bps));
+    }
+    if parts.is_empty() { "-".into() } else { parts.join(", ") }
+}
+
+fn print_rate_limit(access_key_id: &str, limit: &CredentialRateLimit) {
+    match display_rate_limit(&Some(*limit)).as_str() {
+        "-" => println!("Credential '{}' is no longer rate limited.", access_key_id),
+        limits => println!("Credential '{}' limited to {}.", access_key_id, limits),
+    }
 }
 
 #[derive(Deserialize)]
@@ -21,13 +63,82 @@ struct CreatedCredential {
     secret_access_key: String,
 }
 
+fn write_export(export: &CredentialExport, output: Option<&Path>) {
+    let json = serde_json::to_string_pretty(export).expect("Failed to serialize export");
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, json) {
+                eprintln!("Error: cannot write {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+            let count = export.credentials.as_ref().map(|c| c.len().to_string());
+            eprintln!(
+                "Exported {} credentials to {}{}",
+                count.as_deref().unwrap_or("the"),
+                path.display(),
+                if export.encrypted.is_some() { " (encrypted)" } else { "" }
+            );
+        }
+        None => println!("{}", json),
+    }
+}
+
+fn read_export(file: &Path) -> CredentialExport {
+    let data = match std::fs::read(file) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Error: cannot read {}: {}", file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    match serde_json::from_slice(&data) {
+        Ok(export) => export,
+        Err(e) => {
+            eprintln!("Error: {} is not a credential export: {}", file.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn print_import_report(report: &ImportReport) {
+    println!(
+        "Imported credentials: {} created, {} updated, {} unchanged.",
+        report.created.len(),
+        report.updated.len(),
+        report.unchanged.len()
+    );
+    if !report.conflicts.is_empty() {
+        println!(
+            "Kept {} existing credentials that differ from the export (use --replace to overwrite): {}",
+            report.conflicts.len(),
+            report.conflicts.join(", ")
+        );
+    }
+}
+
 // --- Offline (direct sled) ---
 
-pub fn create_offline(store: &MetadataStore, description: &str) {
+pub fn create_offline(
+    store: &MetadataStore,
+    description: &str,
+    permissions: Option<Vec<Permission>>,
+    bucket: Option<&str>,
+) {
     let access_key_id = credentials::generate_access_key_id();
     let secret_access_key = credentials::generate_secret_access_key();
 
-    match store.create_credential(&access_key_id, &secret_access_key, description) {
+    let created = store
+        .create_credential(&access_key_id, &secret_access_key, description)
+        .and_then(|record| match permissions {
+            Some(list) => store
+                .set_credential_permissions(&record.access_key_id, Some(list.into_iter().collect())),
+            None => Ok(record),
+        })
+        .and_then(|record| match bucket {
+            Some(bucket) => store.set_credential_bucket(&record.access_key_id, Some(bucket)),
+            None => Ok(record),
+        });
+    match created {
         Ok(record) => {
             println!("Credential created:");
             println!("  Access Key ID:     {}", record.access_key_id);
@@ -56,6 +167,9 @@ pub fn list_offline(store: &MetadataStore) {
                     description: c.description,
                     created: c.created.to_rfc3339(),
                     active: c.active,
+                    permissions: c.permissions.map(|p| p.into_iter().collect()),
+                    rate_limit: c.rate_limit,
+                    bucket: c.bucket,
                 })
                 .collect();
             println!("{}", Table::new(rows));
@@ -77,12 +191,54 @@ pub fn revoke_offline(store: &MetadataStore, access_key_id: &str) {
     }
 }
 
+pub fn rate_limit_offline(store: &MetadataStore, access_key_id: &str, limit: CredentialRateLimit) {
+    match store.set_credential_rate_limit(access_key_id, Some(limit)) {
+        Ok(_) => print_rate_limit(access_key_id, &limit),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn export_offline(store: &MetadataStore, output: Option<&Path>, passphrase: Option<&str>) {
+    match store
+        .list_credentials()
+        .and_then(|creds| credential_export::export(creds, passphrase))
+    {
+        Ok(export) => write_export(&export, output),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn import_offline(store: &MetadataStore, file: &Path, passphrase: Option<&str>, replace: bool) {
+    let imported = read_export(file)
+        .open(passphrase)
+        .and_then(|creds| credential_export::import(store, &creds, replace));
+    match imported {
+        Ok(report) => print_import_report(&report),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 // --- Online (HTTP to server) ---
 
-pub async fn create_online(client: &reqwest::Client, base: &str, description: &str) {
+pub async fn create_online(
+    client: &reqwest::Client,
+    base: &str,
+    description: &str,
+    permissions: Option<Vec<Permission>>,
+    bucket: Option<&str>,
+) {
     let resp = client
         .post(format!("{}/_admin/credentials", base))
-        .json(&serde_json::json!({ "description": description }))
+        .json(&serde_json::json!({ "description": description, "permissions": permissions, "bucket": bucket }))
         .send()
         .await;
     match resp {
@@ -155,3 +311,79 @@ pub async fn revoke_online(client: &reqwest::Client, base: &str, access_key_id:
         }
     }
 }
+
+pub async fn rate_limit_online(client: &reqwest::Client, base: &str, access_key_id: &str, limit: CredentialRateLimit) {
+    let resp = client
+        .put(format!("{}/_admin/credentials/{}/rate-limit", base, access_key_id))
+        .json(&serde_json::json!({ "rate_limit": limit }))
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => print_rate_limit(access_key_id, &limit),
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn export_online(client: &reqwest::Client, base: &str, output: Option<&Path>, passphrase: Option<&str>) {
+    let resp = client
+        .post(format!("{}/_admin/credentials/export", base))
+        .json(&serde_json::json!({ "passphrase": passphrase }))
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => match r.json::<CredentialExport>().await {
+            Ok(export) => write_export(&export, output),
+            Err(e) => {
+                eprintln!("Error parsing response: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub async fn import_online(
+    client: &reqwest::Client,
+    base: &str,
+    file: &Path,
+    passphrase: Option<&str>,
+    replace: bool,
+) {
+    let export = read_export(file);
+    let resp = client
+        .post(format!("{}/_admin/credentials/import", base))
+        .json(&serde_json::json!({ "export": export, "passphrase": passphrase, "replace": replace }))
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => match r.json::<ImportReport>().await {
+            Ok(report) => print_import_report(&report),
+            Err(e) => {
+                eprintln!("Error parsing response: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use simples3_core::Config;
+use simples3_core::s3::xml;
+use simples3_core::storage::MetadataStore;
+use std::path::Path;
+
+// --- Offline (direct sled) ---
+
+/// Set a bucket's lifecycle configuration from an S3 `LifecycleConfiguration`
+/// XML file, validated as PutBucketLifecycleConfiguration does. Transition
+/// storage classes must name a backend in `SIMPLES3_STORAGE_BACKENDS`.
+pub fn set_offline(store: &MetadataStore, config: &Config, bucket: &str, file: &Path) {
+    let data = match std::fs::read(file) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Error: failed to read '{}': {}", file.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let lifecycle = match xml::parse_lifecycle_configuration_xml(&data) {
+        Ok(l) => l,
+        Err(e) => {
+            eprintln!("Error: {}", super::error_detail(&e));
+            std::process::exit(1);
+        }
+    };
+    let unknown_class = lifecycle
+        .rules
+        .iter()
+        .flat_map(|r| &r.transitions)
+        .find(|t| !config.storage_backends.iter().any(|(name, _)| *name == t.storage_class));
+    if let Some(transition) = unknown_class {
+        eprintln!(
+            "Error: unknown storage class '{}'; set SIMPLES3_STORAGE_BACKENDS as the server does",
+            transition.storage_class
+        );
+        std::process::exit(1);
+    }
+    match store.put_lifecycle_configuration_by(bucket, &lifecycle, Some("cli")) {
+        Ok(()) => println!(
+            "Lifecycle configuration of '{}' set ({} rule{}).",
+            bucket,
+            lifecycle.rules.len(),
+            if lifecycle.rules.len() == 1 { "" } else { "s" }
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
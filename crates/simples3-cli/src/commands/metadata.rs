@@ -0,0 +1,118 @@
+use simples3_core::Config;
+use simples3_core::storage::MetadataStore;
+use simples3_core::storage::maintenance::{self, MetadataReport};
+use tabled::{Table, Tabled};
+
+#[derive(Tabled)]
+struct TreeRow {
+    #[tabled(rename = "Tree")]
+    name: String,
+    #[tabled(rename = "Keys")]
+    keys: u64,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+/// `bytes` with a binary unit, e.g. `1.5 MiB`.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn print_report(report: MetadataReport) {
+    let live: u64 = report.trees.iter().map(|t| t.bytes).sum();
+    let rows: Vec<TreeRow> = report
+        .trees
+        .into_iter()
+        .map(|t| TreeRow {
+            name: t.name,
+            keys: t.keys,
+            size: human_size(t.bytes),
+        })
+        .collect();
+    if rows.is_empty() {
+        println!("The metadata store is empty.");
+    } else {
+        println!("{}", Table::new(rows));
+    }
+    println!(
+        "Live entries: {}, size on disk: {}",
+        human_size(live),
+        human_size(report.size_on_disk)
+    );
+}
+
+// --- Offline (direct sled) ---
+
+pub fn report_offline(store: &MetadataStore) {
+    match store.metadata_report() {
+        Ok(report) => print_report(report),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Rewrite the metadata store with only its live entries. Runs before the
+/// store is opened, with the server stopped.
+pub fn compact(config: &Config) {
+    match maintenance::compact(&config.metadata_dir) {
+        Ok(report) => println!(
+            "Compacted {}: {} -> {}",
+            config.metadata_dir.display(),
+            human_size(report.size_before),
+            human_size(report.size_after)
+        ),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// --- Online (HTTP admin API) ---
+
+pub async fn report_online(client: &reqwest::Client, base: &str) {
+    let resp = client.get(format!("{}/_admin/metadata", base)).send().await;
+    match resp {
+        Ok(r) if r.status().is_success() => match r.json::<MetadataReport>().await {
+            Ok(report) => print_report(report),
+            Err(e) => {
+                eprintln!("Error: invalid response: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
@@ -1,2 +1,20 @@
 pub mod bucket;
 pub mod credentials;
+pub mod lifecycle;
+pub mod metadata;
+pub mod policy;
+pub mod restore;
+pub mod s3;
+pub mod sync;
+pub mod verify;
+
+use simples3_core::S3Error;
+
+/// Error text for the user: validation errors carry their reason, which the
+/// S3 error message leaves out.
+pub fn error_detail(error: &S3Error) -> String {
+    match error {
+        S3Error::InvalidArgument(reason) => reason.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,60 @@
+use simples3_core::Config;
+use simples3_core::auth::simulate::{self, SimulateRequest, SimulateResult};
+use simples3_core::storage::MetadataStore;
+
+fn print_result(result: &SimulateResult) {
+    println!("Decision:  {}", if result.allowed { "ALLOWED" } else { "DENIED" });
+    println!("Action:    {}", result.action);
+    println!("Reason:    {}", result.reason);
+    println!("Policy:    {}", result.policy_decision.as_deref().unwrap_or("(no bucket policy)"));
+    match &result.matched_statement {
+        Some(statement) => println!(
+            "Statement: #{}{} ({:?})",
+            statement.index,
+            statement.sid.as_ref().map(|sid| format!(" \"{}\"", sid)).unwrap_or_default(),
+            statement.effect
+        ),
+        None => println!("Statement: (none matched)"),
+    }
+}
+
+// --- Offline (direct sled) ---
+
+/// Evaluate the request as the server would, with `SIMPLES3_ANONYMOUS_GLOBAL`
+/// taken from the environment.
+pub fn simulate_offline(store: &MetadataStore, config: &Config, request: &SimulateRequest) {
+    match simulate::simulate(store, config.anonymous_global, chrono::Utc::now(), request) {
+        Ok(result) => print_result(&result),
+        Err(e) => {
+            eprintln!("Error: {}", super::error_detail(&e));
+            std::process::exit(1);
+        }
+    }
+}
+
+// --- Online (HTTP to server) ---
+
+pub async fn simulate_online(client: &reqwest::Client, base: &str, request: &SimulateRequest) {
+    let resp = client
+        .post(format!("{}/_admin/simulate", base))
+        .json(request)
+        .send()
+        .await;
+    match resp {
+        Ok(r) if r.status().is_success() => match r.json::<SimulateResult>().await {
+            Ok(result) => print_result(&result),
+            Err(e) => {
+                eprintln!("Error parsing response: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Ok(r) => {
+            eprintln!("Error: server returned {}", r.status());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
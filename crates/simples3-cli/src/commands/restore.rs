@@ -0,0 +1,55 @@
+use simples3_core::Config;
+use simples3_core::storage::snapshot;
+use simples3_core::storage::{MetadataStore, StorageRouter};
+use std::path::Path;
+
+/// Rebuild the metadata store from a shipped snapshot directory.
+pub fn restore(config: &Config, from: &Path, manifest: Option<&str>, verify_data: bool) {
+    let manifest = match snapshot::read_manifest(from, manifest) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = snapshot::restore(from, &manifest, &config.metadata_dir) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    println!(
+        "Restored snapshot from {} ({} trees, schema version {}) into {}",
+        manifest.created.to_rfc3339(),
+        manifest.trees.len(),
+        manifest.schema_version,
+        config.metadata_dir.display()
+    );
+
+    if !verify_data {
+        return;
+    }
+    let entries = match snapshot::read_data_manifest(from, &manifest) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let store = MetadataStore::open(&config.metadata_dir).expect("Failed to open restored metadata store");
+    let router = StorageRouter::from_config(config, store).expect("Failed to start IO pool");
+    let mut missing = 0;
+    for entry in &entries {
+        let present = router
+            .backend(&entry.backend)
+            .map(|fs| fs.object_path(&entry.bucket, &entry.key))
+            .and_then(|path| std::fs::metadata(path).ok())
+            .is_some_and(|m| m.len() == entry.size);
+        if !present {
+            missing += 1;
+            println!("  missing: {}/{} (backend '{}')", entry.bucket, entry.key, entry.backend);
+        }
+    }
+    println!("{} of {} data files present.", entries.len() - missing, entries.len());
+    if missing > 0 {
+        std::process::exit(1);
+    }
+}
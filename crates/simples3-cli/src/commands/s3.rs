@@ -0,0 +1,91 @@
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use simples3_core::auth::sigv4::Signer;
+
+/// Characters escaped in a key when it is put in a request path: all but the
+/// unreserved ones and `/`.
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~').remove(b'/');
+
+/// Characters escaped in a query parameter value: all but the unreserved ones.
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// An S3 API endpoint and the access key used to sign requests to it, for
+/// the commands that work on object data rather than through the admin API.
+pub struct S3Endpoint {
+    pub endpoint: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub region: String,
+}
+
+impl S3Endpoint {
+    /// URL and signed host and path of `key` in `bucket`, or of the bucket
+    /// itself when `key` is empty.
+    fn location(&self, bucket: &str, key: &str, query: &str) -> Result<(reqwest::Url, String, String), String> {
+        let endpoint = reqwest::Url::parse(&self.endpoint).map_err(|e| e.to_string())?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(h), Some(p)) => format!("{}:{}", h, p),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return Err("Endpoint has no host".into()),
+        };
+        let base = endpoint.path().trim_end_matches('/');
+        let path = match key {
+            "" => format!("{}/{}", base, bucket),
+            key => format!("{}/{}/{}", base, bucket, utf8_percent_encode(key, KEY_ENCODE_SET)),
+        };
+        let mut url = endpoint.join(&path).map_err(|e| e.to_string())?;
+        url.set_query(Some(query).filter(|q| !q.is_empty()));
+        Ok((url, host, path))
+    }
+
+    /// A request signed with SigV4. `query` pairs are encoded here.
+    pub fn request(
+        &self,
+        client: &reqwest::Client,
+        method: reqwest::Method,
+        bucket: &str,
+        key: &str,
+        query: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let query = query
+            .iter()
+            .map(|(name, value)| match *value {
+                "" => name.to_string(),
+                value => format!("{}={}", name, utf8_percent_encode(value, QUERY_ENCODE_SET)),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let (url, host, path) = self.location(bucket, key, &query)?;
+        let signer = Signer {
+            access_key: &self.access_key,
+            secret_key: &self.secret_key,
+            region: &self.region,
+        };
+        let headers = signer.sign_with_query(method.as_str(), &host, &path, &query, body, chrono::Utc::now());
+        Ok(headers
+            .into_iter()
+            .fold(client.request(method, url), |request, (name, value)| request.header(name, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_encodes_key() {
+        let s3 = S3Endpoint {
+            endpoint: "http://localhost:9000/".into(),
+            access_key: "AKID".into(),
+            secret_key: "SECRET".into(),
+            region: "us-east-1".into(),
+        };
+        let (url, host, path) = s3.location("backup", "laptop/photos/a b+c.jpg", "").unwrap();
+        assert_eq!(host, "localhost:9000");
+        assert_eq!(path, "/backup/laptop/photos/a%20b%2Bc.jpg");
+        assert_eq!(url.as_str(), "http://localhost:9000/backup/laptop/photos/a%20b%2Bc.jpg");
+        let (url, _, path) = s3.location("backup", "", "list-type=2&prefix=a%2Fb").unwrap();
+        assert_eq!(path, "/backup");
+        assert_eq!(url.as_str(), "http://localhost:9000/backup?list-type=2&prefix=a%2Fb");
+    }
+}
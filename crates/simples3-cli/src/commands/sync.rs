@@ -0,0 +1,227 @@
+use super::s3::S3Endpoint;
+use md5::{Digest, Md5};
+use notify::{EventKind, RecursiveMode, Watcher};
+use simples3_core::s3::policy::string_like_match;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where and how `sync` uploads.
+pub struct SyncTarget {
+    pub s3: S3Endpoint,
+    pub bucket: String,
+    /// Prepended to the relative path of each file to form its key.
+    pub prefix: String,
+    /// Patterns (`*`, `?`) matched against the relative path and each of
+    /// its components; matching files and directories are skipped.
+    pub exclude: Vec<String>,
+}
+
+impl SyncTarget {
+    fn excluded(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|pattern| {
+            string_like_match(pattern, relative) || relative.split('/').any(|part| string_like_match(pattern, part))
+        })
+    }
+
+    fn key(&self, relative: &str) -> String {
+        format!("{}{}", self.prefix, relative)
+    }
+
+    /// Upload `file` as `key`, unless `skip_unchanged` and the stored object
+    /// already has the file's MD5 as its ETag. Returns whether it uploaded.
+    async fn upload(&self, client: &reqwest::Client, file: &Path, key: &str, skip_unchanged: bool) -> Result<bool, String> {
+        let data = std::fs::read(file).map_err(|e| e.to_string())?;
+        if skip_unchanged {
+            let etag = format!("\"{}\"", hex::encode(Md5::digest(&data)));
+            let resp = self
+                .s3
+                .request(client, reqwest::Method::HEAD, &self.bucket, key, &[], &[])?
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if resp.status().is_success() && resp.headers().get("etag").is_some_and(|v| *v == etag.as_str()) {
+                return Ok(false);
+            }
+        }
+        let resp = self
+            .s3
+            .request(client, reqwest::Method::PUT, &self.bucket, key, &[], &data)?
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("PUT returned {}", resp.status()));
+        }
+        Ok(true)
+    }
+}
+
+/// Path of `path` relative to `root`, with `/` separators.
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Regular files under `dir`, leaving out excluded paths. Symbolic links
+/// are not followed.
+fn collect_files(root: &Path, dir: &Path, target: &SyncTarget, files: &mut BTreeSet<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: cannot read '{}': {}", dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if relative_path(root, &path).is_none_or(|relative| target.excluded(&relative)) {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => collect_files(root, &path, target, files),
+            Ok(t) if t.is_file() => {
+                files.insert(path);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Upload `files`, reporting each; returns how many failed.
+async fn upload_all(
+    client: &reqwest::Client,
+    root: &Path,
+    target: &SyncTarget,
+    files: &BTreeSet<PathBuf>,
+    skip_unchanged: bool,
+) -> usize {
+    let mut failed = 0;
+    for file in files {
+        let Some(relative) = relative_path(root, file) else {
+            continue;
+        };
+        let key = target.key(&relative);
+        match target.upload(client, file, &key, skip_unchanged).await {
+            Ok(true) => println!("  uploaded: {} -> {}/{}", relative, target.bucket, key),
+            Ok(false) => {}
+            Err(e) => {
+                failed += 1;
+                eprintln!("  failed:   {} ({})", relative, e);
+            }
+        }
+    }
+    failed
+}
+
+/// Upload the files of `dir` that are missing or different in the bucket;
+/// with `watch`, keep uploading files as they are created or changed, once
+/// no further change has been seen for `debounce`.
+pub async fn sync(dir: &Path, target: &SyncTarget, watch: bool, debounce: Duration) {
+    let root = match dir.canonicalize() {
+        Ok(root) if root.is_dir() => root,
+        Ok(_) => {
+            eprintln!("Error: '{}' is not a directory", dir.display());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Error: '{}': {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let client = reqwest::Client::new();
+
+    // Watch before the first pass so changes made during it are not missed
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = None;
+    if watch {
+        let handler = move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            {
+                for path in event.paths {
+                    let _ = sender.send(path);
+                }
+            }
+        };
+        let started = notify::recommended_watcher(handler).and_then(|mut w| {
+            w.watch(&root, RecursiveMode::Recursive)?;
+            Ok(w)
+        });
+        match started {
+            Ok(w) => watcher = Some(w),
+            Err(e) => {
+                eprintln!("Error: cannot watch '{}': {}", root.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let mut files = BTreeSet::new();
+    collect_files(&root, &root, target, &mut files);
+    println!("Syncing {} file(s) from {} to {}/{}", files.len(), root.display(), target.bucket, target.prefix);
+    let failed = upload_all(&client, &root, target, &files, true).await;
+    if watcher.is_none() {
+        if failed > 0 {
+            eprintln!("{} file(s) failed to upload.", failed);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    println!("Watching {} for changes (Ctrl-C to stop)", root.display());
+    while let Some(first) = receiver.recv().await {
+        let mut changed = BTreeSet::from([first]);
+        // Wait for a quiet period so a file being written is uploaded once
+        while let Ok(Some(path)) = tokio::time::timeout(debounce, receiver.recv()).await {
+            changed.insert(path);
+        }
+        let mut files = BTreeSet::new();
+        for path in changed {
+            if relative_path(&root, &path).is_none_or(|relative| target.excluded(&relative)) {
+                continue;
+            }
+            match std::fs::symlink_metadata(&path) {
+                Ok(m) if m.is_dir() => collect_files(&root, &path, target, &mut files),
+                Ok(m) if m.is_file() => {
+                    files.insert(path);
+                }
+                // Removed again before the quiet period ended
+                _ => {}
+            }
+        }
+        upload_all(&client, &root, target, &files, false).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(exclude: &[&str]) -> SyncTarget {
+        SyncTarget {
+            s3: S3Endpoint {
+                endpoint: "http://localhost:9000/".into(),
+                access_key: "AKID".into(),
+                secret_key: "SECRET".into(),
+                region: "us-east-1".into(),
+            },
+            bucket: "backup".into(),
+            prefix: "laptop/".into(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_exclusion_rules() {
+        let target = target(&[".git", "*.tmp", "build/*"]);
+        assert!(target.excluded(".git"));
+        assert!(target.excluded("src/.git/config"));
+        assert!(target.excluded("notes/draft.tmp"));
+        assert!(target.excluded("build/out.bin"));
+        assert!(!target.excluded("src/main.rs"));
+        assert!(!target.excluded("rebuild/out.bin"));
+    }
+}
@@ -0,0 +1,401 @@
+use super::s3::S3Endpoint;
+use md5::{Digest, Md5};
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use serde::{Deserialize, Serialize};
+use simples3_core::s3::checksum::ChecksumAlgorithm;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How `verify` prints its report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    /// One tab-separated `status key detail` line per problem, summary on stderr
+    Text,
+    /// A single JSON document with the counts and every problem
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Problem {
+    /// Expected but not in the bucket.
+    Missing,
+    /// In the bucket under the prefix but not expected.
+    Extra,
+    SizeMismatch,
+    EtagMismatch,
+    /// The local file could not be read.
+    Unreadable,
+}
+
+impl Problem {
+    fn as_str(self) -> &'static str {
+        match self {
+            Problem::Missing => "missing",
+            Problem::Extra => "extra",
+            Problem::SizeMismatch => "size_mismatch",
+            Problem::EtagMismatch => "etag_mismatch",
+            Problem::Unreadable => "unreadable",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Mismatch {
+    status: Problem,
+    key: String,
+    expected: Option<String>,
+    actual: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VerifyReport {
+    bucket: String,
+    prefix: String,
+    checked: usize,
+    matched: usize,
+    mismatches: Vec<Mismatch>,
+}
+
+/// What an object is expected to be: from a local file, or from the size
+/// and ETag (or MD5) columns of a manifest.
+enum Expected {
+    File(PathBuf),
+    Listed { size: Option<u64>, etag: Option<String> },
+}
+
+#[derive(Debug, Deserialize)]
+struct PartManifest {
+    parts: Vec<PartRange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PartRange {
+    size: u64,
+}
+
+struct RemoteObject {
+    size: u64,
+    /// Without the surrounding quotes.
+    etag: String,
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+/// Split one CSV line, honouring double-quoted fields.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Objects listed in a CSV manifest with a header row naming its columns:
+/// `key` and any of `size`, `etag` and `md5`. Other columns are ignored.
+fn read_manifest(path: &Path, prefix: &str) -> Result<BTreeMap<String, Expected>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = csv_fields(lines.next().unwrap_or_default())
+        .into_iter()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let key_column = column("key").ok_or("manifest has no 'key' column")?;
+    let (size_column, etag_column) = (column("size"), column("etag").or(column("md5")));
+
+    let mut expected = BTreeMap::new();
+    for (n, line) in lines.enumerate() {
+        let fields = csv_fields(line);
+        let field = |i: Option<usize>| i.and_then(|i| fields.get(i)).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let key = field(Some(key_column)).ok_or_else(|| format!("manifest line {} has no key", n + 2))?;
+        if !key.starts_with(prefix) {
+            continue;
+        }
+        let size = match field(size_column) {
+            Some(s) => Some(s.parse().map_err(|_| format!("manifest line {}: invalid size '{}'", n + 2, s))?),
+            None => None,
+        };
+        let etag = field(etag_column).map(|e| e.trim_matches('"').to_string());
+        expected.insert(key.to_string(), Expected::Listed { size, etag });
+    }
+    Ok(expected)
+}
+
+/// Files under `dir`, keyed by `prefix` followed by their relative path.
+fn collect_local(root: &Path, dir: &Path, prefix: &str, expected: &mut BTreeMap<String, Expected>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => fail(format!("cannot read '{}': {}", dir.display(), e)),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => collect_local(root, &path, prefix, expected),
+            Ok(t) if t.is_file() => {
+                let relative: Vec<_> = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                    .collect();
+                expected.insert(format!("{}{}", prefix, relative.join("/")), Expected::File(path));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Text content of the ListObjectsV2 elements this command uses.
+fn parse_list_page(xml: &str, objects: &mut BTreeMap<String, RemoteObject>) -> Result<Option<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut element = String::new();
+    let (mut key, mut etag, mut size) = (String::new(), String::new(), 0);
+    let mut next_token = None;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) => element = String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+            Event::Text(t) => {
+                let text = t.unescape().map_err(|e| e.to_string())?.into_owned();
+                match element.as_str() {
+                    "Key" => key = text,
+                    "ETag" => etag = text.trim_matches('"').to_string(),
+                    "Size" => size = text.parse().map_err(|_| format!("invalid Size '{}'", text))?,
+                    "NextContinuationToken" => next_token = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"Contents" {
+                    // Keys are stored as they appeared in the request path
+                    let key = percent_encoding::percent_decode_str(&std::mem::take(&mut key))
+                        .decode_utf8_lossy()
+                        .into_owned();
+                    objects.insert(key, RemoteObject { size, etag: std::mem::take(&mut etag) });
+                }
+                element.clear();
+            }
+            Event::Eof => return Ok(next_token),
+            _ => {}
+        }
+    }
+}
+
+/// Every object under `prefix`, by key.
+async fn list_remote(
+    client: &reqwest::Client,
+    s3: &S3Endpoint,
+    bucket: &str,
+    prefix: &str,
+) -> Result<BTreeMap<String, RemoteObject>, String> {
+    let mut objects = BTreeMap::new();
+    let mut token: Option<String> = None;
+    loop {
+        let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+        if let Some(token) = &token {
+            query.push(("continuation-token", token));
+        }
+        let resp = s3
+            .request(client, reqwest::Method::GET, bucket, "", &query, &[])?
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !resp.status().is_success() {
+            return Err(format!("ListObjectsV2 returned {}", resp.status()));
+        }
+        let body = resp.text().await.map_err(|e| e.to_string())?;
+        match parse_list_page(&body, &mut objects)? {
+            Some(next) => token = Some(next),
+            None => return Ok(objects),
+        }
+    }
+}
+
+/// ETag the object would have if uploaded from `data` in parts of `sizes`:
+/// the MD5 of the part MD5s, suffixed with the part count.
+fn multipart_etag(data: &[u8], sizes: &[u64]) -> String {
+    let mut combined = Vec::new();
+    let mut offset = 0usize;
+    for &size in sizes {
+        let end = (offset + size as usize).min(data.len());
+        combined.extend_from_slice(&Md5::digest(&data[offset..end]));
+        offset = end;
+    }
+    format!("{}-{}", hex::encode(Md5::digest(&combined)), sizes.len())
+}
+
+/// Whether a stored additional checksum of the object matches `data`. Used
+/// when the ETag is not an MD5, e.g. above the server's MD5 skip threshold.
+async fn checksum_matches(client: &reqwest::Client, s3: &S3Endpoint, bucket: &str, key: &str, data: &[u8]) -> bool {
+    let Ok(request) = s3.request(client, reqwest::Method::HEAD, bucket, key, &[], &[]) else {
+        return false;
+    };
+    let Ok(resp) = request.header("x-amz-checksum-mode", "ENABLED").send().await else {
+        return false;
+    };
+    ChecksumAlgorithm::ALL.into_iter().any(|algorithm| {
+        resp.headers()
+            .get(algorithm.header())
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|stored| !stored.contains('-') && stored == algorithm.compute(data))
+    })
+}
+
+/// Compare a local file with its object; `None` when they match.
+async fn check_file(
+    client: &reqwest::Client,
+    s3: &S3Endpoint,
+    bucket: &str,
+    key: &str,
+    file: &Path,
+    remote: &RemoteObject,
+) -> Option<(Problem, Option<String>, Option<String>)> {
+    let data = match std::fs::read(file) {
+        Ok(data) => data,
+        Err(e) => return Some((Problem::Unreadable, None, Some(e.to_string()))),
+    };
+    if data.len() as u64 != remote.size {
+        return Some((Problem::SizeMismatch, Some(data.len().to_string()), Some(remote.size.to_string())));
+    }
+    let etag = if remote.etag.contains('-') {
+        // Multipart: recompute along the part boundaries the object was uploaded with
+        let manifest = async {
+            let resp = s3
+                .request(client, reqwest::Method::GET, bucket, key, &[("x-simples3-parts", "")], &[])
+                .ok()?
+                .send()
+                .await
+                .ok()?;
+            resp.json::<PartManifest>().await.ok()
+        };
+        match manifest.await {
+            Some(m) => multipart_etag(&data, &m.parts.iter().map(|p| p.size).collect::<Vec<_>>()),
+            None => hex::encode(Md5::digest(&data)),
+        }
+    } else {
+        hex::encode(Md5::digest(&data))
+    };
+    if etag == remote.etag || checksum_matches(client, s3, bucket, key, &data).await {
+        return None;
+    }
+    Some((Problem::EtagMismatch, Some(etag), Some(remote.etag.clone())))
+}
+
+/// Compare the objects of `bucket` under `prefix` with a local directory
+/// (mirroring the prefix) or a CSV manifest (`.csv`) of full keys, print
+/// every difference and exit non-zero if an expected object is missing or
+/// different. Objects only in the bucket are reported as `extra`.
+pub async fn verify(s3: &S3Endpoint, target: &str, against: &Path, format: ReportFormat) {
+    let (bucket, prefix) = target.split_once('/').unwrap_or((target, ""));
+    let mut expected = BTreeMap::new();
+    if against.is_dir() {
+        collect_local(against, against, prefix, &mut expected);
+    } else {
+        expected = read_manifest(against, prefix).unwrap_or_else(|e| fail(e));
+    }
+
+    let client = reqwest::Client::new();
+    let mut remote = list_remote(&client, s3, bucket, prefix).await.unwrap_or_else(|e| fail(e));
+    let mut report = VerifyReport {
+        bucket: bucket.to_string(),
+        prefix: prefix.to_string(),
+        checked: expected.len(),
+        matched: 0,
+        mismatches: Vec::new(),
+    };
+    for (key, expectation) in &expected {
+        let Some(object) = remote.remove(key) else {
+            report.mismatches.push(Mismatch { status: Problem::Missing, key: key.clone(), expected: None, actual: None });
+            continue;
+        };
+        let problem = match expectation {
+            Expected::File(file) => check_file(&client, s3, bucket, key, file, &object).await,
+            Expected::Listed { size, etag } => match (size, etag) {
+                (Some(size), _) if *size != object.size => {
+                    Some((Problem::SizeMismatch, Some(size.to_string()), Some(object.size.to_string())))
+                }
+                (_, Some(etag)) if *etag != object.etag => {
+                    Some((Problem::EtagMismatch, Some(etag.clone()), Some(object.etag.clone())))
+                }
+                _ => None,
+            },
+        };
+        match problem {
+            Some((status, expected, actual)) => {
+                report.mismatches.push(Mismatch { status, key: key.clone(), expected, actual })
+            }
+            None => report.matched += 1,
+        }
+    }
+    for key in remote.into_keys() {
+        report.mismatches.push(Mismatch { status: Problem::Extra, key, expected: None, actual: None });
+    }
+
+    match format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default()),
+        ReportFormat::Text => {
+            for m in &report.mismatches {
+                let detail = match (&m.expected, &m.actual) {
+                    (Some(expected), Some(actual)) => format!("expected={} actual={}", expected, actual),
+                    (None, Some(actual)) => actual.clone(),
+                    _ => String::new(),
+                };
+                println!("{}\t{}\t{}", m.status.as_str(), m.key, detail);
+            }
+            eprintln!(
+                "{} of {} object(s) verified, {} problem(s).",
+                report.matched,
+                report.checked,
+                report.mismatches.len()
+            );
+        }
+    }
+    if report.mismatches.iter().any(|m| m.status != Problem::Extra) {
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_fields() {
+        assert_eq!(csv_fields("a,\"b,c\",\"say \"\"hi\"\"\","), vec!["a", "b,c", "say \"hi\"", ""]);
+    }
+
+    #[test]
+    fn test_multipart_etag() {
+        let data = b"hello world";
+        let single = format!("{}-1", hex::encode(Md5::digest(Md5::digest(data))));
+        assert_eq!(multipart_etag(data, &[11]), single);
+        assert_ne!(multipart_etag(data, &[5, 6]), single);
+        assert!(multipart_etag(data, &[5, 6]).ends_with("-2"));
+    }
+
+    #[test]
+    fn test_parse_list_page() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult><Name>b</Name><Contents><Key>a&amp;b%20c.txt</Key><ETag>"abc"</ETag><Size>3</Size></Contents><Contents><Key>c.txt</Key><ETag>"def-2"</ETag><Size>10</Size></Contents><IsTruncated>true</IsTruncated><NextContinuationToken>tok</NextContinuationToken></ListBucketResult>"#;
+        let mut objects = BTreeMap::new();
+        assert_eq!(parse_list_page(xml, &mut objects).unwrap().as_deref(), Some("tok"));
+        assert_eq!(objects["a&b c.txt"].etag, "abc");
+        assert_eq!(objects["c.txt"].size, 10);
+    }
+}
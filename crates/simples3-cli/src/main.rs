@@ -1,5 +1,10 @@
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand};
 use simples3_core::Config;
+use simples3_core::auth::simulate::SimulateRequest;
+use simples3_core::s3::types::{CredentialRateLimit, Permission};
+use std::net::IpAddr;
+use std::path::PathBuf;
 
 mod commands;
 
@@ -39,6 +44,95 @@ enum Commands {
         #[command(subcommand)]
         action: CredentialAction,
     },
+    /// Bucket policy debugging
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+    /// Bucket lifecycle configuration (offline only)
+    Lifecycle {
+        #[command(subcommand)]
+        action: LifecycleAction,
+    },
+    /// Metadata store diagnostics and maintenance
+    Metadata {
+        #[command(subcommand)]
+        action: MetadataAction,
+    },
+    /// Restore the metadata store from a shipped snapshot directory.
+    /// Run with the server stopped, into an empty metadata directory.
+    Restore {
+        /// Directory holding the shipped snapshot (latest.json, trees/, data/)
+        #[arg(long)]
+        from: String,
+        /// Manifest to restore instead of latest.json, e.g. manifests/<timestamp>.json
+        #[arg(long)]
+        manifest: Option<String>,
+        /// Check that every object in the data manifest exists on disk
+        #[arg(long)]
+        verify_data: bool,
+    },
+    /// Upload the files of a local directory to a bucket over the S3 API,
+    /// skipping files already stored unchanged
+    Sync {
+        /// Local directory to upload
+        dir: PathBuf,
+        /// Bucket to upload into
+        bucket: String,
+        /// Key prefix for the uploaded files
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Keep running and upload files as they are created or changed
+        #[arg(long)]
+        watch: bool,
+        /// Milliseconds without further changes before changed files are uploaded
+        #[arg(long, default_value_t = 1000)]
+        debounce_ms: u64,
+        /// Skip files and directories whose relative path or name matches (`*`, `?`); repeatable
+        #[arg(long)]
+        exclude: Vec<String>,
+        #[command(flatten)]
+        s3: S3Args,
+    },
+    /// Compare the objects of a bucket with a local directory or a CSV
+    /// manifest and report missing and different objects
+    Verify {
+        /// Bucket, optionally followed by `/prefix`
+        target: String,
+        /// Local directory mirroring the prefix, or CSV manifest with a `key` column and
+        /// optional `size` and `etag` (or `md5`) columns
+        #[arg(long)]
+        against: PathBuf,
+        #[arg(long, value_enum, default_value = "text")]
+        format: commands::verify::ReportFormat,
+        #[command(flatten)]
+        s3: S3Args,
+    },
+}
+
+/// S3 API endpoint and access key of the commands working on object data.
+#[derive(Args)]
+struct S3Args {
+    /// S3 API URL
+    #[arg(long, default_value = "http://localhost:9000", env = "SIMPLES3_ENDPOINT")]
+    endpoint: String,
+    #[arg(long, env = "AWS_ACCESS_KEY_ID")]
+    access_key: String,
+    #[arg(long, env = "AWS_SECRET_ACCESS_KEY", hide_env_values = true)]
+    secret_key: String,
+    #[arg(long, default_value = "us-east-1", env = "AWS_REGION")]
+    region: String,
+}
+
+impl From<S3Args> for commands::s3::S3Endpoint {
+    fn from(args: S3Args) -> Self {
+        Self {
+            endpoint: args.endpoint,
+            access_key: args.access_key,
+            secret_key: args.secret_key,
+            region: args.region,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -55,6 +149,62 @@ enum BucketAction {
         #[command(subcommand)]
         setting: BucketConfigSetting,
     },
+    /// Bucket policy (offline only)
+    Policy {
+        #[command(subcommand)]
+        action: BucketPolicyAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum BucketPolicyAction {
+    /// Set a bucket's policy from a JSON policy document
+    Set { name: String, file: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Show whether a request would be allowed, and which policy statement decided
+    Simulate {
+        #[arg(long)]
+        bucket: String,
+        #[arg(long)]
+        key: Option<String>,
+        /// Policy action (s3:GetObject) or S3 operation name (GetObject)
+        #[arg(long)]
+        action: String,
+        /// Access key ID making the request; omit for an anonymous request
+        #[arg(long)]
+        principal: Option<String>,
+        /// Client address for aws:SourceIp conditions
+        #[arg(long)]
+        source_ip: Option<IpAddr>,
+        /// Simulate a request over TLS, for aws:SecureTransport conditions
+        #[arg(long)]
+        secure_transport: bool,
+        /// Value of the s3:prefix condition key
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Evaluation time (RFC 3339) for date conditions, instead of now
+        #[arg(long)]
+        current_time: Option<DateTime<Utc>>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LifecycleAction {
+    /// Set a bucket's lifecycle configuration from an S3 LifecycleConfiguration XML file
+    Set { bucket: String, file: PathBuf },
+}
+
+#[derive(Subcommand)]
+enum MetadataAction {
+    /// Show the key count and size of every tree (objects per bucket,
+    /// multipart uploads, tagging, ...) and the size on disk
+    Report,
+    /// Rewrite the store with only its live entries to reclaim disk space
+    /// (offline only, with the server stopped)
+    Compact,
 }
 
 #[derive(Subcommand)]
@@ -64,6 +214,11 @@ enum BucketConfigSetting {
         #[arg(value_parser = clap::value_parser!(bool))]
         value: bool,
     },
+    /// Allow anonymous listing of public objects (true or false)
+    AnonymousList {
+        #[arg(value_parser = clap::value_parser!(bool))]
+        value: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -72,17 +227,90 @@ enum CredentialAction {
     Create {
         #[arg(long, default_value = "")]
         description: String,
+        /// Restrict the key to these operations (read, write, delete, tagging, bucket_admin)
+        #[arg(long, value_delimiter = ',')]
+        permissions: Option<Vec<Permission>>,
+        /// Bind the key to this bucket; it cannot access any other
+        #[arg(long)]
+        bucket: Option<String>,
     },
     /// List all credentials
     List,
     /// Revoke an access key
     Revoke { access_key_id: String },
+    /// Throttle an access key; without limits, lift its throttling
+    RateLimit {
+        access_key_id: String,
+        /// Requests per second
+        #[arg(long)]
+        requests_per_sec: Option<u32>,
+        /// Request and response body bytes per second
+        #[arg(long)]
+        bytes_per_sec: Option<u64>,
+    },
+    /// Export every credential, secrets included, for another instance
+    Export {
+        /// File to write the export to, instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Encrypt the export with this passphrase
+        #[arg(long, env = "SIMPLES3_CREDENTIALS_PASSPHRASE")]
+        passphrase: Option<String>,
+    },
+    /// Import exported credentials, keeping their access key IDs
+    Import {
+        file: PathBuf,
+        /// Passphrase of an encrypted export
+        #[arg(long, env = "SIMPLES3_CREDENTIALS_PASSPHRASE")]
+        passphrase: Option<String>,
+        /// Overwrite existing credentials that differ from the export
+        #[arg(long)]
+        replace: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
+    // Restore always works on the local store, and must run before it is created
+    if let Commands::Restore { from, manifest, verify_data } = &cli.command {
+        let mut config = Config::from_env();
+        if let Some(metadata_dir) = &cli.metadata_dir {
+            config.metadata_dir = metadata_dir.into();
+        }
+        commands::restore::restore(&config, from.as_ref(), manifest.as_deref(), *verify_data);
+        return;
+    }
+
+    // Compaction replaces the store directory, so it must not be open
+    if let Commands::Metadata { action: MetadataAction::Compact } = &cli.command {
+        if !cli.offline {
+            eprintln!("Error: 'metadata compact' needs --offline; stop the server first");
+            std::process::exit(1);
+        }
+        let mut config = Config::from_env();
+        if let Some(metadata_dir) = &cli.metadata_dir {
+            config.metadata_dir = metadata_dir.into();
+        }
+        commands::metadata::compact(&config);
+        return;
+    }
+
+    // Sync and verify talk to the S3 API, not the admin API or the local store
+    match cli.command {
+        Commands::Sync { dir, bucket, prefix, watch, debounce_ms, exclude, s3 } => {
+            let target = commands::sync::SyncTarget { s3: s3.into(), bucket, prefix, exclude };
+            commands::sync::sync(&dir, &target, watch, std::time::Duration::from_millis(debounce_ms)).await;
+            return;
+        }
+        Commands::Verify { target, against, format, s3 } => {
+            commands::verify::verify(&s3.into(), &target, &against, format).await;
+            return;
+        }
+        _ => {}
+    }
+
     if cli.offline {
         run_offline(cli);
     } else {
@@ -100,6 +328,9 @@ fn run_offline(cli: Cli) {
 
     let store = simples3_core::storage::MetadataStore::open(&config.metadata_dir)
         .expect("Failed to open metadata store");
+    store
+        .run_migrations(&config.metadata_dir)
+        .expect("Failed to migrate metadata store");
 
     match cli.command {
         Commands::Bucket { action } => match action {
@@ -110,17 +341,42 @@ fn run_offline(cli: Cli) {
                 BucketConfigSetting::Anonymous { value } => {
                     commands::bucket::set_anonymous_offline(&store, &name, value)
                 }
+                BucketConfigSetting::AnonymousList { value } => {
+                    commands::bucket::set_anonymous_list_offline(&store, &name, value)
+                }
             },
+            BucketAction::Policy { action: BucketPolicyAction::Set { name, file } } => {
+                commands::bucket::set_policy_offline(&store, &name, &file)
+            }
         },
         Commands::Credentials { action } => match action {
-            CredentialAction::Create { description } => {
-                commands::credentials::create_offline(&store, &description)
+            CredentialAction::Create { description, permissions, bucket } => {
+                commands::credentials::create_offline(&store, &description, permissions, bucket.as_deref())
             }
             CredentialAction::List => commands::credentials::list_offline(&store),
             CredentialAction::Revoke { access_key_id } => {
                 commands::credentials::revoke_offline(&store, &access_key_id)
             }
+            CredentialAction::RateLimit { access_key_id, requests_per_sec, bytes_per_sec } => {
+                let limit = CredentialRateLimit { requests_per_sec, bytes_per_sec };
+                commands::credentials::rate_limit_offline(&store, &access_key_id, limit)
+            }
+            CredentialAction::Export { output, passphrase } => {
+                commands::credentials::export_offline(&store, output.as_deref(), passphrase.as_deref())
+            }
+            CredentialAction::Import { file, passphrase, replace } => {
+                commands::credentials::import_offline(&store, &file, passphrase.as_deref(), replace)
+            }
         },
+        Commands::Policy { action } => {
+            commands::policy::simulate_offline(&store, &config, &simulate_request(action))
+        }
+        Commands::Lifecycle { action: LifecycleAction::Set { bucket, file } } => {
+            commands::lifecycle::set_offline(&store, &config, &bucket, &file)
+        }
+        Commands::Metadata { action: MetadataAction::Report } => commands::metadata::report_offline(&store),
+        Commands::Metadata { action: MetadataAction::Compact } => unreachable!("handled in main"),
+        Commands::Restore { .. } | Commands::Sync { .. } | Commands::Verify { .. } => unreachable!("handled in main"),
     }
 }
 
@@ -155,16 +411,63 @@ async fn run_online(cli: Cli) {
                 BucketConfigSetting::Anonymous { value } => {
                     commands::bucket::set_anonymous_online(&client, &base, &name, value).await
                 }
+                BucketConfigSetting::AnonymousList { value } => {
+                    commands::bucket::set_anonymous_list_online(&client, &base, &name, value).await
+                }
             },
+            BucketAction::Policy { .. } => offline_only("bucket policy"),
         },
         Commands::Credentials { action } => match action {
-            CredentialAction::Create { description } => {
-                commands::credentials::create_online(&client, &base, &description).await
+            CredentialAction::Create { description, permissions, bucket } => {
+                commands::credentials::create_online(&client, &base, &description, permissions, bucket.as_deref()).await
             }
             CredentialAction::List => commands::credentials::list_online(&client, &base).await,
             CredentialAction::Revoke { access_key_id } => {
                 commands::credentials::revoke_online(&client, &base, &access_key_id).await
             }
+            CredentialAction::RateLimit { access_key_id, requests_per_sec, bytes_per_sec } => {
+                let limit = CredentialRateLimit { requests_per_sec, bytes_per_sec };
+                commands::credentials::rate_limit_online(&client, &base, &access_key_id, limit).await
+            }
+            CredentialAction::Export { output, passphrase } => {
+                commands::credentials::export_online(&client, &base, output.as_deref(), passphrase.as_deref()).await
+            }
+            CredentialAction::Import { file, passphrase, replace } => {
+                commands::credentials::import_online(&client, &base, &file, passphrase.as_deref(), replace).await
+            }
         },
+        Commands::Policy { action } => {
+            commands::policy::simulate_online(&client, &base, &simulate_request(action)).await
+        }
+        Commands::Lifecycle { .. } => offline_only("lifecycle"),
+        Commands::Metadata { action: MetadataAction::Report } => {
+            commands::metadata::report_online(&client, &base).await
+        }
+        Commands::Metadata { action: MetadataAction::Compact } => unreachable!("handled in main"),
+        Commands::Restore { .. } | Commands::Sync { .. } | Commands::Verify { .. } => unreachable!("handled in main"),
     }
 }
+
+fn simulate_request(action: PolicyAction) -> SimulateRequest {
+    let PolicyAction::Simulate {
+        bucket,
+        key,
+        action,
+        principal,
+        source_ip,
+        secure_transport,
+        prefix,
+        current_time,
+    } = action;
+    SimulateRequest { principal, action, bucket, key, source_ip, secure_transport, prefix, current_time }
+}
+
+/// Commands backed by S3 API calls have no admin endpoint; while the server
+/// runs, use an S3 client instead.
+fn offline_only(command: &str) -> ! {
+    eprintln!(
+        "Error: '{}' commands need --offline; use an S3 client while the server is running",
+        command
+    );
+    std::process::exit(1);
+}
@@ -0,0 +1,222 @@
+//! Credential export files, for keeping access keys in sync between
+//! instances without copying the metadata directory.
+//!
+//! An export is a JSON document listing the credentials with their secrets,
+//! permissions and status. With a passphrase the list is encrypted with
+//! AES-256-GCM under a PBKDF2-SHA256 key, and only the salt, nonce and
+//! ciphertext are stored.
+
+use crate::S3Error;
+use crate::s3::types::AccessKeyRecord;
+use crate::storage::MetadataStore;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chrono::{DateTime, Utc};
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+
+pub const EXPORT_FORMAT: &str = "simples3-credentials";
+const EXPORT_VERSION: u32 = 1;
+const KDF: &str = "pbkdf2-sha256";
+const KDF_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialExport {
+    pub format: String,
+    pub version: u32,
+    pub exported: DateTime<Utc>,
+    /// The credentials of an unencrypted export.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<Vec<AccessKeyRecord>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted: Option<EncryptedCredentials>,
+}
+
+/// The credential list encrypted with a passphrase. Binary fields are base64.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedCredentials {
+    pub kdf: String,
+    pub iterations: u32,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// What importing an export did, by access key ID.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    /// Existing credentials that differ from the export, left as they are
+    /// unless the import replaces them.
+    pub conflicts: Vec<String>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey, S3Error> {
+    let iterations =
+        NonZeroU32::new(iterations).ok_or_else(|| S3Error::InvalidArgument("Invalid KDF iterations".into()))?;
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| S3Error::InternalError("Invalid key".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Build an export of `credentials`, encrypted when a passphrase is given.
+pub fn export(credentials: Vec<AccessKeyRecord>, passphrase: Option<&str>) -> Result<CredentialExport, S3Error> {
+    let mut export = CredentialExport {
+        format: EXPORT_FORMAT.into(),
+        version: EXPORT_VERSION,
+        exported: Utc::now(),
+        credentials: None,
+        encrypted: None,
+    };
+    let Some(passphrase) = passphrase else {
+        export.credentials = Some(credentials);
+        return Ok(export);
+    };
+    if passphrase.is_empty() {
+        return Err(S3Error::InvalidArgument("The passphrase must not be empty".into()));
+    }
+
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| S3Error::InternalError("No randomness available".into()))?;
+    rng.fill(&mut nonce).map_err(|_| S3Error::InternalError("No randomness available".into()))?;
+    let key = derive_key(passphrase, &salt, KDF_ITERATIONS)?;
+    let mut data = serde_json::to_vec(&credentials).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(EXPORT_FORMAT), &mut data)
+        .map_err(|_| S3Error::InternalError("Encryption failed".into()))?;
+    export.encrypted = Some(EncryptedCredentials {
+        kdf: KDF.into(),
+        iterations: KDF_ITERATIONS,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(data),
+    });
+    Ok(export)
+}
+
+impl CredentialExport {
+    /// The exported credentials, decrypted with `passphrase` if needed.
+    pub fn open(self, passphrase: Option<&str>) -> Result<Vec<AccessKeyRecord>, S3Error> {
+        if self.format != EXPORT_FORMAT || self.version != EXPORT_VERSION {
+            return Err(S3Error::InvalidArgument(format!(
+                "Not a version {} {} export",
+                EXPORT_VERSION, EXPORT_FORMAT
+            )));
+        }
+        match (self.credentials, self.encrypted) {
+            (Some(credentials), None) => Ok(credentials),
+            (None, Some(encrypted)) => {
+                let passphrase = passphrase
+                    .ok_or_else(|| S3Error::InvalidArgument("The export is encrypted; a passphrase is required".into()))?;
+                if encrypted.kdf != KDF {
+                    return Err(S3Error::InvalidArgument(format!("Unsupported KDF '{}'", encrypted.kdf)));
+                }
+                let decode = |field: &str| {
+                    STANDARD
+                        .decode(field)
+                        .map_err(|_| S3Error::InvalidArgument("Malformed encrypted export".into()))
+                };
+                let salt = decode(&encrypted.salt)?;
+                let nonce = Nonce::try_assume_unique_for_key(&decode(&encrypted.nonce)?)
+                    .map_err(|_| S3Error::InvalidArgument("Malformed encrypted export".into()))?;
+                let mut data = decode(&encrypted.ciphertext)?;
+                let key = derive_key(passphrase, &salt, encrypted.iterations)?;
+                let plain = key
+                    .open_in_place(nonce, Aad::from(EXPORT_FORMAT), &mut data)
+                    .map_err(|_| S3Error::InvalidArgument("Wrong passphrase or corrupted export".into()))?;
+                serde_json::from_slice(plain).map_err(|_| S3Error::InvalidArgument("Malformed encrypted export".into()))
+            }
+            _ => Err(S3Error::InvalidArgument(
+                "An export holds either credentials or encrypted credentials".into(),
+            )),
+        }
+    }
+}
+
+/// Store `credentials` with their access key IDs. Existing credentials that
+/// differ are only overwritten with `replace`.
+pub fn import(store: &MetadataStore, credentials: &[AccessKeyRecord], replace: bool) -> Result<ImportReport, S3Error> {
+    let mut report = ImportReport::default();
+    for record in credentials {
+        if record.access_key_id.is_empty() || record.secret_access_key.is_empty() {
+            return Err(S3Error::InvalidArgument("Exported credentials need an access key ID and a secret".into()));
+        }
+    }
+    for record in credentials {
+        let id = record.access_key_id.clone();
+        match store.find_credential(&id)? {
+            None => {
+                store.put_credential(record)?;
+                report.created.push(id);
+            }
+            Some(existing) if existing == *record => report.unchanged.push(id),
+            Some(_) if replace => {
+                store.put_credential(record)?;
+                report.updated.push(id);
+            }
+            Some(_) => report.conflicts.push(id),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(id: &str, secret: &str) -> AccessKeyRecord {
+        AccessKeyRecord {
+            bucket: None,
+            access_key_id: id.into(),
+            secret_access_key: secret.into(),
+            description: "exported".into(),
+            created: Utc::now(),
+            active: true,
+            permissions: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_export_roundtrip() {
+        let credentials = vec![record("AKID1", "SECRET1"), record("AKID2", "SECRET2")];
+        let plain = export(credentials.clone(), None).unwrap();
+        assert_eq!(plain.clone().open(None).unwrap(), credentials);
+
+        let sealed = export(credentials.clone(), Some("correct horse")).unwrap();
+        let json = serde_json::to_string(&sealed).unwrap();
+        assert!(!json.contains("SECRET1"));
+        let sealed: CredentialExport = serde_json::from_str(&json).unwrap();
+        assert!(matches!(sealed.clone().open(None), Err(S3Error::InvalidArgument(_))));
+        assert!(matches!(sealed.clone().open(Some("wrong")), Err(S3Error::InvalidArgument(_))));
+        assert_eq!(sealed.open(Some("correct horse")).unwrap(), credentials);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = TempDir::new().unwrap();
+        let store = MetadataStore::open(dir.path()).unwrap();
+        store.create_credential("AKID1", "OLD", "").unwrap();
+        let same = store.create_credential("AKID2", "SECRET2", "").unwrap();
+
+        let credentials = vec![record("AKID1", "NEW"), same, record("AKID3", "SECRET3")];
+        let report = import(&store, &credentials, false).unwrap();
+        assert_eq!(report.created, vec!["AKID3"]);
+        assert_eq!(report.unchanged, vec!["AKID2"]);
+        assert_eq!(report.conflicts, vec!["AKID1"]);
+        assert_eq!(store.get_credential("AKID1").unwrap().secret_access_key, "OLD");
+        assert_eq!(store.get_credential("AKID3").unwrap().secret_access_key, "SECRET3");
+
+        let report = import(&store, &credentials, true).unwrap();
+        assert_eq!(report.updated, vec!["AKID1"]);
+        assert_eq!(store.get_credential("AKID1").unwrap().secret_access_key, "NEW");
+    }
+}
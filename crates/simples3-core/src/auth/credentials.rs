@@ -5,6 +5,13 @@ pub fn generate_access_key_id() -> String {
     format!("AKID{}", &id[..16].to_uppercase())
 }
 
+/// A bucket administration token: `s3bt_` followed by 40 hex digits.
+pub fn generate_bucket_admin_token() -> String {
+    let s1 = Uuid::new_v4().simple().to_string();
+    let s2 = Uuid::new_v4().simple().to_string();
+    format!("s3bt_{}{}", &s1[..20], &s2[..20])
+}
+
 pub fn generate_secret_access_key() -> String {
     let s1 = Uuid::new_v4().to_string().replace("-", "");
     let s2 = Uuid::new_v4().to_string().replace("-", "");
@@ -1,2 +1,6 @@
+pub mod credential_export;
 pub mod credentials;
+pub mod presigned_post;
+pub mod sigv2;
 pub mod sigv4;
+pub mod simulate;
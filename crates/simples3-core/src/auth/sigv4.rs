@@ -95,6 +95,32 @@ pub fn canonical_request(
     )
 }
 
+/// Sort the raw query string by parameter name, keeping the client's
+/// percent-encoding as-is.
+pub fn canonical_query_string(raw_query: &str) -> String {
+    let mut pairs: Vec<(&str, &str)> = raw_query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let mut kv = p.splitn(2, '=');
+            (kv.next().unwrap_or(""), kv.next().unwrap_or(""))
+        })
+        .collect();
+    pairs.sort_by(|a, b| a.0.cmp(b.0));
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Build the string to sign for a canonical request.
+pub fn string_to_sign(amz_date: &str, date: &str, region: &str, canonical_request: &str) -> String {
+    let hash_canon = hex::encode(Sha256::digest(canonical_request.as_bytes()));
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hash_canon)
+}
+
 /// Verify a SigV4 request. Returns Ok(access_key_id) on success.
 pub fn verify_signature(
     method: &str,
@@ -106,14 +132,11 @@ pub fn verify_signature(
     payload_hash: &str,
 ) -> Result<(), S3Error> {
     let canon = canonical_request(method, uri, query_string, headers, &auth.signed_headers, payload_hash);
-
-    let hash_canon = hex::encode(Sha256::digest(canon.as_bytes()));
-
-    let scope = format!("{}/{}/s3/aws4_request", auth.date, auth.region);
-    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}",
-        headers.get("x-amz-date").unwrap_or(&String::new()),
-        scope,
-        hash_canon
+    let string_to_sign = string_to_sign(
+        headers.get("x-amz-date").map(|s| s.as_str()).unwrap_or(""),
+        &auth.date,
+        &auth.region,
+        &canon,
     );
 
     let key = signing_key(secret_key, &auth.date, &auth.region);
@@ -127,6 +150,7 @@ pub fn verify_signature(
 }
 
 /// Verify a presigned URL signature.
+#[allow(clippy::too_many_arguments)]
 pub fn verify_presigned_signature(
     method: &str,
     uri: &str,
@@ -147,13 +171,7 @@ pub fn verify_presigned_signature(
         signed_headers,
         "UNSIGNED-PAYLOAD",
     );
-
-    let hash_canon = hex::encode(Sha256::digest(canon.as_bytes()));
-    let scope = format!("{}/{}/s3/aws4_request", date, region);
-    let string_to_sign = format!(
-        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
-        amz_date, scope, hash_canon
-    );
+    let string_to_sign = string_to_sign(amz_date, date, region, &canon);
 
     let key = signing_key(secret_key, date, region);
     let computed = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
@@ -165,8 +183,78 @@ pub fn verify_presigned_signature(
     }
 }
 
+/// Credentials for signing outgoing requests to an S3-compatible endpoint.
+pub struct Signer<'a> {
+    pub access_key: &'a str,
+    pub secret_key: &'a str,
+    pub region: &'a str,
+}
+
+impl Signer<'_> {
+    /// Headers to add to a request without query parameters:
+    /// `x-amz-content-sha256`, `x-amz-date` and `authorization`. `path` must be
+    /// URI-encoded exactly as it is sent.
+    pub fn sign(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        body: &[u8],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(&'static str, String)> {
+        self.sign_with_query(method, host, path, "", body, now)
+    }
+
+    /// Same as [`Signer::sign`], for a request with the raw query string
+    /// `query`, which must be encoded exactly as it is sent.
+    pub fn sign_with_query(
+        &self,
+        method: &str,
+        host: &str,
+        path: &str,
+        query: &str,
+        body: &[u8],
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Vec<(&'static str, String)> {
+        let date = now.format("%Y%m%d").to_string();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let payload_hash = hex::encode(Sha256::digest(body));
+
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), host.to_string());
+        headers.insert("x-amz-content-sha256".to_string(), payload_hash.clone());
+        headers.insert("x-amz-date".to_string(), amz_date.clone());
+        let signed_headers: Vec<String> = headers.keys().cloned().collect();
+
+        let canonical = canonical_request(
+            method,
+            path,
+            &canonical_query_string(query),
+            &headers,
+            &signed_headers,
+            &payload_hash,
+        );
+        let string_to_sign = string_to_sign(&amz_date, &date, self.region, &canonical);
+        let key = signing_key(self.secret_key, &date, self.region);
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}/{}/s3/aws4_request, SignedHeaders={}, Signature={}",
+            self.access_key,
+            date,
+            self.region,
+            signed_headers.join(";"),
+            signature
+        );
+        vec![
+            ("x-amz-content-sha256", payload_hash),
+            ("x-amz-date", amz_date),
+            ("authorization", authorization),
+        ]
+    }
+}
+
 /// Constant-time byte comparison to prevent timing attacks.
-fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
     if a.len() != b.len() {
         return false;
     }
@@ -181,6 +269,28 @@ fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_signer_roundtrip() {
+        let signer = Signer {
+            access_key: "AKID",
+            secret_key: "SECRET",
+            region: "eu-west-1",
+        };
+        let path = "/bucket/photos/a%20b.jpg";
+        let signed = signer.sign("PUT", "remote:9000", path, b"data", chrono::Utc::now());
+
+        let header = |name: &str| signed.iter().find(|(n, _)| *n == name).unwrap().1.clone();
+        let auth = parse_auth_header(&header("authorization")).unwrap();
+        assert_eq!(auth.access_key_id, "AKID");
+        assert_eq!(auth.region, "eu-west-1");
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), "remote:9000".to_string());
+        headers.insert("x-amz-content-sha256".to_string(), header("x-amz-content-sha256"));
+        headers.insert("x-amz-date".to_string(), header("x-amz-date"));
+        verify_signature("PUT", path, "", &headers, &auth, "SECRET", &header("x-amz-content-sha256")).unwrap();
+        assert!(verify_signature("PUT", path, "", &headers, &auth, "OTHER", &header("x-amz-content-sha256")).is_err());
+    }
+
     #[test]
     fn test_parse_auth_header() {
         let header = "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature=aaaa";
@@ -192,6 +302,15 @@ mod tests {
         assert_eq!(auth.signature, "aaaa");
     }
 
+    #[test]
+    fn test_canonical_query_string_sorts_and_keeps_encoding() {
+        assert_eq!(canonical_query_string(""), "");
+        assert_eq!(
+            canonical_query_string("prefix=a%2Fb&list-type=2&acl"),
+            "acl=&list-type=2&prefix=a%2Fb"
+        );
+    }
+
     #[test]
     fn test_sigv4_valid_signature() {
         // Build a request and verify our own signature computation
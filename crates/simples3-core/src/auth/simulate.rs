@@ -0,0 +1,120 @@
+//! Policy simulation: would a hypothetical request be allowed?
+//!
+//! Shared by the admin API (`POST /_admin/simulate`) and the CLI's offline
+//! `policy simulate`, so both answer exactly as the S3 auth middleware would.
+//! Bucket and object ACLs are not considered.
+
+use crate::S3Error;
+use crate::s3::policy::{self, PolicyDecision, PolicyEvaluation, RequestContext};
+use crate::s3::types::PolicyEffect;
+use crate::storage::MetadataStore;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulateRequest {
+    /// Access key ID to simulate; omitted for an anonymous request.
+    pub principal: Option<String>,
+    /// Policy action (`s3:GetObject`) or S3 operation name (`GetObject`).
+    pub action: String,
+    pub bucket: String,
+    pub key: Option<String>,
+    pub source_ip: Option<IpAddr>,
+    #[serde(default)]
+    pub secure_transport: bool,
+    /// Value of the `s3:prefix` condition key.
+    pub prefix: Option<String>,
+    /// Evaluation time for date conditions; defaults to now.
+    pub current_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulateResult {
+    pub allowed: bool,
+    pub reason: String,
+    pub action: String,
+    /// `ExplicitAllow`, `ExplicitDeny` or `ImplicitDeny`; absent when the
+    /// bucket has no policy.
+    pub policy_decision: Option<String>,
+    pub matched_statement: Option<MatchedStatement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedStatement {
+    pub index: usize,
+    pub sid: Option<String>,
+    pub effect: PolicyEffect,
+}
+
+/// Evaluate `request` against the bucket policy in `store`. `now` is used
+/// when the request gives no `current_time`.
+pub fn simulate(
+    store: &MetadataStore,
+    anonymous_global: bool,
+    now: DateTime<Utc>,
+    request: &SimulateRequest,
+) -> Result<SimulateResult, S3Error> {
+    let policy = match store.get_bucket_policy(&request.bucket) {
+        Ok(p) => Some(p),
+        Err(S3Error::NoSuchBucketPolicy) => None,
+        Err(e) => return Err(e),
+    };
+    let action = if request.action.contains(':') {
+        request.action.clone()
+    } else {
+        policy::operation_to_s3_action(&request.action).to_string()
+    };
+    let ctx = RequestContext {
+        source_ip: request.source_ip,
+        current_time: request.current_time.unwrap_or(now),
+        secure_transport: request.secure_transport,
+        s3_prefix: request.prefix.clone(),
+    };
+    let evaluation = policy.as_ref().map(|p| {
+        policy::explain_policy(
+            p,
+            &action,
+            &request.bucket,
+            request.key.as_deref(),
+            request.principal.as_deref(),
+            Some(&ctx),
+        )
+    });
+    let decision = evaluation.as_ref().map(|e| e.decision.clone());
+
+    let (allowed, reason) = match &request.principal {
+        Some(access_key_id) => match store.get_credential(access_key_id) {
+            Err(_) => (false, "Unknown access key"),
+            Ok(credential) if !credential.active => (false, "Access key is revoked"),
+            Ok(_) if decision == Some(PolicyDecision::ExplicitDeny) => (false, "Denied by a bucket policy statement"),
+            Ok(_) => (true, "Authenticated requests are allowed unless a bucket policy denies them"),
+        },
+        None if anonymous_global => (true, "Anonymous access is enabled globally"),
+        None => match decision {
+            Some(PolicyDecision::ExplicitAllow) => (true, "Allowed by a bucket policy statement"),
+            Some(PolicyDecision::ExplicitDeny) => (false, "Denied by a bucket policy statement"),
+            _ => (false, "Anonymous requests need a bucket policy Allow statement"),
+        },
+    };
+
+    let matched_statement = match (&policy, &evaluation) {
+        (Some(policy), Some(PolicyEvaluation { statement: Some(index), .. })) => {
+            let statement = &policy.statements[*index];
+            Some(MatchedStatement {
+                index: *index,
+                sid: statement.sid.clone(),
+                effect: statement.effect.clone(),
+            })
+        }
+        _ => None,
+    };
+
+    Ok(SimulateResult {
+        allowed,
+        reason: reason.to_string(),
+        action,
+        policy_decision: decision.map(|d| format!("{:?}", d)),
+        matched_statement,
+    })
+}
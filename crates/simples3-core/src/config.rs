@@ -1,7 +1,13 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+/// Server settings. Built from defaults, an optional config file, the
+/// environment and command-line flags by [`ConfigBuilder`]. Serializes to the
+/// effective configuration with secrets masked.
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     pub bind: String,
     pub data_dir: PathBuf,
@@ -12,66 +18,358 @@ pub struct Config {
     pub anonymous_global: bool,
     pub admin_enabled: bool,
     pub admin_bind: String,
+    #[serde(serialize_with = "mask_secret")]
     pub admin_token: Option<String>,
     pub multipart_ttl_secs: u64,
     pub multipart_cleanup_interval_secs: u64,
     pub lifecycle_scan_interval_secs: u64,
+    /// Seconds between passes removing tags of objects that no longer exist.
+    pub tagging_gc_interval_secs: u64,
     pub cors_origins: Option<Vec<String>>,
     pub max_object_size: usize,
+    /// Size of the dedicated blocking pool for object data IO; `0` shares the
+    /// server runtime's blocking pool.
+    pub io_threads: usize,
     pub max_xml_body_size: usize,
     pub max_policy_body_size: usize,
+    /// Request body bytes buffered in memory past which new requests get
+    /// `503 SlowDown`; `0` disables the limit.
+    pub buffered_bytes_high_watermark: u64,
+    /// Uploads and downloads of object data handled at once before new ones
+    /// get `503 SlowDown`; `0` disables the limit.
+    pub max_concurrent_transfers: usize,
+    /// Sustained S3 requests per second accepted from all clients together
+    /// before `503 SlowDown`; `0` disables the limit.
+    pub rate_limit_rps: u32,
+    /// Requests a burst may take above the sustained rate; `0` means the
+    /// rate itself.
+    pub rate_limit_burst: u32,
+    /// Like `rate_limit_rps`, for each client IP address.
+    pub rate_limit_per_ip_rps: u32,
+    pub rate_limit_per_ip_burst: u32,
+    /// Seconds a read-only S3 request may take to produce its response
+    /// before `400 RequestTimeout`; `0` (the default) disables the timeout.
+    pub read_timeout_secs: u64,
+    /// Like `read_timeout_secs`, for requests that write. It caps the whole
+    /// request, including the time to receive an upload's body, so it must
+    /// leave room for the largest upload over the slowest client link.
+    pub write_timeout_secs: u64,
+    /// Seconds to wait on shutdown for S3 requests in flight to finish.
+    pub shutdown_timeout_secs: u64,
+    pub bucket_delete_grace_secs: u64,
+    pub list_cache_entries: usize,
+    /// Read the bucket records and settings at startup and after a reload,
+    /// so the first request to each bucket does not wait on the disk.
+    pub warm_up: bool,
+    /// Off by default even on Windows: it changes the file name of existing
+    /// objects, so it is only safe to enable on a new data directory.
+    pub escape_object_paths: bool,
+    /// Objects and parts above this many bytes get an ETag derived from size
+    /// and modification time instead of their MD5.
+    pub md5_skip_threshold: Option<u64>,
+    pub security_webhook_interval_secs: u64,
+    /// Seconds between writes of buffered server access log records.
+    pub access_log_interval_secs: u64,
+    /// File every S3 request is logged to, or `-` for stdout; `None`
+    /// disables the server-wide access log.
+    pub access_log: Option<String>,
+    /// `clf` or `json`.
+    pub access_log_format: String,
+    /// Size past which the access log file is rotated; `0` never rotates.
+    pub access_log_max_size: u64,
+    /// Rotated access log files kept, as `<file>.1` (newest) to `<file>.N`.
+    pub access_log_max_files: usize,
+    /// Seconds between passes of the replication worker over queued objects.
+    pub replication_interval_secs: u64,
+    /// Seconds between usage flushes and samples of stored bytes.
+    pub usage_interval_secs: u64,
+    /// Seconds between checks of bucket usage alert thresholds.
+    pub usage_alert_interval_secs: u64,
+    pub accepted_regions: Option<Vec<String>>,
+    /// Named extra data directories that bucket storage routes can point key prefixes at.
+    pub storage_backends: Vec<(String, PathBuf)>,
+    /// Content scanner for new objects: `clamd://host:port` or an
+    /// `http(s)://` webhook.
+    pub scan_endpoint: Option<String>,
+    /// Command producing image renditions, e.g.
+    /// `convert - -thumbnail {width}x{height} -`.
+    pub rendition_command: Option<String>,
+    /// PEM certificate chain and private key. When both are set, the S3 and
+    /// admin listeners serve HTTPS only.
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    /// Obtain and renew the TLS certificate of `hostname` from the ACME CA
+    /// at `acme_directory` instead of reading `tls_cert` and `tls_key`.
+    pub acme_enabled: bool,
+    pub acme_directory: String,
+    /// Contact address given to the CA for expiry notices.
+    pub acme_email: Option<String>,
+    /// PEM CA certificates. When set, the admin listener requires a client
+    /// certificate signed by one of them, which authenticates admin requests
+    /// in place of `admin_token`.
+    pub admin_client_ca: Option<PathBuf>,
+    /// Allow freezing the server clock through the admin API. For
+    /// integration tests only: presigned URLs and lifecycle rules follow it.
+    pub clock_control: bool,
+    /// Seconds between health probes of the metadata store; `0` disables
+    /// them, and with them the degraded mode.
+    pub metadata_probe_interval_secs: u64,
+    /// Consecutive failed probes after which the server is degraded.
+    pub metadata_failure_threshold: u32,
+    /// Where metadata snapshots are shipped: a directory path or `s3://bucket/prefix`.
+    pub snapshot_target: Option<String>,
+    pub snapshot_interval_secs: u64,
+    pub snapshot_s3_endpoint: Option<String>,
+    pub snapshot_s3_region: String,
+    pub snapshot_s3_access_key: Option<String>,
+    #[serde(serialize_with = "mask_secret")]
+    pub snapshot_s3_secret_key: Option<String>,
 }
 
 impl Config {
     pub fn from_env() -> Self {
-        Self {
-            bind: env::var("SIMPLES3_BIND").unwrap_or_else(|_| "0.0.0.0:9000".into()),
-            data_dir: PathBuf::from(env::var("SIMPLES3_DATA_DIR").unwrap_or_else(|_| "./data".into())),
-            metadata_dir: PathBuf::from(
-                env::var("SIMPLES3_METADATA_DIR").unwrap_or_else(|_| "./metadata".into()),
-            ),
-            hostname: env::var("SIMPLES3_HOSTNAME").unwrap_or_else(|_| "s3.localhost".into()),
-            region: env::var("SIMPLES3_REGION").unwrap_or_else(|_| "us-east-1".into()),
-            log_level: env::var("SIMPLES3_LOG_LEVEL").unwrap_or_else(|_| "info".into()),
-            anonymous_global: env::var("SIMPLES3_ANONYMOUS_GLOBAL")
-                .map(|v| v == "true" || v == "1")
-                .unwrap_or(false),
-            admin_enabled: env::var("SIMPLES3_ADMIN_ENABLED")
-                .map(|v| v != "false" && v != "0")
-                .unwrap_or(true),
-            admin_bind: env::var("SIMPLES3_ADMIN_BIND")
-                .unwrap_or_else(|_| "127.0.0.1:9001".into()),
-            admin_token: env::var("SIMPLES3_ADMIN_TOKEN")
-                .ok()
-                .filter(|s| !s.is_empty()),
-            multipart_ttl_secs: env::var("SIMPLES3_MULTIPART_TTL")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(86400),
-            multipart_cleanup_interval_secs: env::var("SIMPLES3_MULTIPART_CLEANUP_INTERVAL")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(3600),
-            lifecycle_scan_interval_secs: env::var("SIMPLES3_LIFECYCLE_SCAN_INTERVAL")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(3600),
-            cors_origins: env::var("SIMPLES3_CORS_ORIGINS")
-                .ok()
-                .filter(|s| !s.is_empty())
-                .map(|s| s.split(',').map(|o| o.trim().to_string()).collect()),
-            max_object_size: env::var("SIMPLES3_MAX_OBJECT_SIZE")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(5 * 1024 * 1024 * 1024),
-            max_xml_body_size: env::var("SIMPLES3_MAX_XML_BODY_SIZE")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(256 * 1024),
-            max_policy_body_size: env::var("SIMPLES3_MAX_POLICY_BODY_SIZE")
-                .ok()
-                .and_then(|v| v.parse().ok())
-                .unwrap_or(20 * 1024),
+        let mut config = Self::default();
+        config.apply_env();
+        config
+    }
+
+    /// Override fields with the `SIMPLES3_*` variables that are set. Values
+    /// that do not parse leave the field unchanged.
+    fn apply_env(&mut self) {
+        set_string(&mut self.bind, "SIMPLES3_BIND");
+        set_path(&mut self.data_dir, "SIMPLES3_DATA_DIR");
+        set_path(&mut self.metadata_dir, "SIMPLES3_METADATA_DIR");
+        set_string(&mut self.hostname, "SIMPLES3_HOSTNAME");
+        set_string(&mut self.region, "SIMPLES3_REGION");
+        set_string(&mut self.log_level, "SIMPLES3_LOG_LEVEL");
+        if let Ok(v) = env::var("SIMPLES3_ANONYMOUS_GLOBAL") {
+            self.anonymous_global = v == "true" || v == "1";
+        }
+        if let Ok(v) = env::var("SIMPLES3_ADMIN_ENABLED") {
+            self.admin_enabled = v != "false" && v != "0";
+        }
+        set_string(&mut self.admin_bind, "SIMPLES3_ADMIN_BIND");
+        set_optional(&mut self.admin_token, "SIMPLES3_ADMIN_TOKEN");
+        set_parsed(&mut self.multipart_ttl_secs, "SIMPLES3_MULTIPART_TTL");
+        set_parsed(&mut self.multipart_cleanup_interval_secs, "SIMPLES3_MULTIPART_CLEANUP_INTERVAL");
+        set_parsed(&mut self.lifecycle_scan_interval_secs, "SIMPLES3_LIFECYCLE_SCAN_INTERVAL");
+        set_parsed(&mut self.tagging_gc_interval_secs, "SIMPLES3_TAGGING_GC_INTERVAL");
+        set_list(&mut self.cors_origins, "SIMPLES3_CORS_ORIGINS");
+        set_parsed(&mut self.max_object_size, "SIMPLES3_MAX_OBJECT_SIZE");
+        set_parsed(&mut self.io_threads, "SIMPLES3_IO_THREADS");
+        set_parsed(&mut self.max_xml_body_size, "SIMPLES3_MAX_XML_BODY_SIZE");
+        set_parsed(&mut self.max_policy_body_size, "SIMPLES3_MAX_POLICY_BODY_SIZE");
+        set_parsed(&mut self.buffered_bytes_high_watermark, "SIMPLES3_BUFFERED_BYTES_HIGH_WATERMARK");
+        set_parsed(&mut self.max_concurrent_transfers, "SIMPLES3_MAX_CONCURRENT_TRANSFERS");
+        set_parsed(&mut self.rate_limit_rps, "SIMPLES3_RATE_LIMIT_RPS");
+        set_parsed(&mut self.rate_limit_burst, "SIMPLES3_RATE_LIMIT_BURST");
+        set_parsed(&mut self.rate_limit_per_ip_rps, "SIMPLES3_RATE_LIMIT_PER_IP_RPS");
+        set_parsed(&mut self.rate_limit_per_ip_burst, "SIMPLES3_RATE_LIMIT_PER_IP_BURST");
+        set_parsed(&mut self.read_timeout_secs, "SIMPLES3_READ_TIMEOUT");
+        set_parsed(&mut self.write_timeout_secs, "SIMPLES3_WRITE_TIMEOUT");
+        set_parsed(&mut self.shutdown_timeout_secs, "SIMPLES3_SHUTDOWN_TIMEOUT");
+        set_parsed(&mut self.bucket_delete_grace_secs, "SIMPLES3_BUCKET_DELETE_GRACE");
+        set_parsed(&mut self.list_cache_entries, "SIMPLES3_LIST_CACHE_ENTRIES");
+        if let Ok(v) = env::var("SIMPLES3_WARM_UP") {
+            self.warm_up = v == "true" || v == "1";
+        }
+        if let Ok(v) = env::var("SIMPLES3_ESCAPE_OBJECT_PATHS") {
+            self.escape_object_paths = v == "true" || v == "1";
+        }
+        if let Some(v) = env::var("SIMPLES3_MD5_SKIP_THRESHOLD").ok().and_then(|v| v.parse().ok()) {
+            self.md5_skip_threshold = Some(v);
+        }
+        set_parsed(&mut self.security_webhook_interval_secs, "SIMPLES3_SECURITY_WEBHOOK_INTERVAL");
+        set_parsed(&mut self.access_log_interval_secs, "SIMPLES3_ACCESS_LOG_INTERVAL");
+        set_parsed(&mut self.replication_interval_secs, "SIMPLES3_REPLICATION_INTERVAL");
+        set_optional(&mut self.access_log, "SIMPLES3_ACCESS_LOG");
+        set_string(&mut self.access_log_format, "SIMPLES3_ACCESS_LOG_FORMAT");
+        set_parsed(&mut self.access_log_max_size, "SIMPLES3_ACCESS_LOG_MAX_SIZE");
+        set_parsed(&mut self.access_log_max_files, "SIMPLES3_ACCESS_LOG_MAX_FILES");
+        set_parsed(&mut self.usage_interval_secs, "SIMPLES3_USAGE_INTERVAL");
+        set_parsed(&mut self.usage_alert_interval_secs, "SIMPLES3_USAGE_ALERT_INTERVAL");
+        set_list(&mut self.accepted_regions, "SIMPLES3_ACCEPTED_REGIONS");
+        if let Ok(v) = env::var("SIMPLES3_STORAGE_BACKENDS") {
+            self.storage_backends = parse_storage_backends(&v);
+        }
+        set_optional(&mut self.scan_endpoint, "SIMPLES3_SCAN_ENDPOINT");
+        set_optional(&mut self.rendition_command, "SIMPLES3_RENDITION_COMMAND");
+        set_optional_path(&mut self.tls_cert, "SIMPLES3_TLS_CERT");
+        set_optional_path(&mut self.tls_key, "SIMPLES3_TLS_KEY");
+        if let Ok(v) = env::var("SIMPLES3_ACME_ENABLED") {
+            self.acme_enabled = v == "true" || v == "1";
+        }
+        set_string(&mut self.acme_directory, "SIMPLES3_ACME_DIRECTORY");
+        set_optional(&mut self.acme_email, "SIMPLES3_ACME_EMAIL");
+        set_optional_path(&mut self.admin_client_ca, "SIMPLES3_ADMIN_CLIENT_CA");
+        if let Ok(v) = env::var("SIMPLES3_CLOCK_CONTROL") {
+            self.clock_control = v == "true" || v == "1";
+        }
+        set_parsed(&mut self.metadata_probe_interval_secs, "SIMPLES3_METADATA_PROBE_INTERVAL");
+        set_parsed(&mut self.metadata_failure_threshold, "SIMPLES3_METADATA_FAILURE_THRESHOLD");
+        set_optional(&mut self.snapshot_target, "SIMPLES3_SNAPSHOT_TARGET");
+        set_parsed(&mut self.snapshot_interval_secs, "SIMPLES3_SNAPSHOT_INTERVAL");
+        set_optional(&mut self.snapshot_s3_endpoint, "SIMPLES3_SNAPSHOT_S3_ENDPOINT");
+        set_string(&mut self.snapshot_s3_region, "SIMPLES3_SNAPSHOT_S3_REGION");
+        set_optional(&mut self.snapshot_s3_access_key, "SIMPLES3_SNAPSHOT_S3_ACCESS_KEY");
+        set_optional(&mut self.snapshot_s3_secret_key, "SIMPLES3_SNAPSHOT_S3_SECRET_KEY");
+    }
+
+    /// Override fields with the keys present in a server config file.
+    fn apply_file(&mut self, file: ConfigFile) {
+        macro_rules! apply {
+            ($($field:ident),* $(,)?) => {
+                $(if let Some(v) = file.$field {
+                    self.$field = v;
+                })*
+            };
+        }
+        macro_rules! apply_optional {
+            ($($field:ident),* $(,)?) => {
+                $(if file.$field.is_some() {
+                    self.$field = file.$field;
+                })*
+            };
+        }
+        apply!(
+            bind,
+            data_dir,
+            metadata_dir,
+            hostname,
+            region,
+            log_level,
+            anonymous_global,
+            admin_enabled,
+            admin_bind,
+            multipart_ttl_secs,
+            multipart_cleanup_interval_secs,
+            lifecycle_scan_interval_secs,
+            tagging_gc_interval_secs,
+            max_object_size,
+            io_threads,
+            max_xml_body_size,
+            max_policy_body_size,
+            buffered_bytes_high_watermark,
+            max_concurrent_transfers,
+            rate_limit_rps,
+            rate_limit_burst,
+            rate_limit_per_ip_rps,
+            rate_limit_per_ip_burst,
+            read_timeout_secs,
+            write_timeout_secs,
+            shutdown_timeout_secs,
+            bucket_delete_grace_secs,
+            list_cache_entries,
+            warm_up,
+            escape_object_paths,
+            security_webhook_interval_secs,
+            access_log_interval_secs,
+            access_log_format,
+            access_log_max_size,
+            access_log_max_files,
+            replication_interval_secs,
+            usage_interval_secs,
+            usage_alert_interval_secs,
+            acme_enabled,
+            acme_directory,
+            clock_control,
+            metadata_probe_interval_secs,
+            metadata_failure_threshold,
+            snapshot_interval_secs,
+            snapshot_s3_region,
+        );
+        apply_optional!(
+            admin_token,
+            cors_origins,
+            md5_skip_threshold,
+            access_log,
+            accepted_regions,
+            scan_endpoint,
+            rendition_command,
+            tls_cert,
+            tls_key,
+            acme_email,
+            admin_client_ca,
+            snapshot_target,
+            snapshot_s3_endpoint,
+            snapshot_s3_access_key,
+            snapshot_s3_secret_key,
+        );
+        if let Some(backends) = file.storage_backends {
+            self.storage_backends = backends.into_iter().collect();
+        }
+    }
+
+    /// Every problem with the settings, or an empty list when they are usable.
+    /// Directories are not looked at; see [`ConfigBuilder::build`].
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let bind_port = check_address("bind", &self.bind, &mut errors);
+        if self.admin_enabled {
+            check_address("admin_bind", &self.admin_bind, &mut errors);
+            // Port 0 picks a free port for each listener, so the two never clash
+            if bind_port.is_some_and(|p| p != 0) && self.admin_bind == self.bind {
+                errors.push(format!("bind and admin_bind are both {}", self.bind));
+            }
+        }
+        if self.hostname.is_empty() {
+            errors.push("hostname must not be empty".into());
+        }
+        if self.region.is_empty() {
+            errors.push("region must not be empty".into());
+        }
+        if self.data_dir == self.metadata_dir {
+            errors.push(format!(
+                "data_dir and metadata_dir must be different directories (both {})",
+                self.data_dir.display()
+            ));
+        }
+        let mut names = std::collections::HashSet::new();
+        for (name, path) in &self.storage_backends {
+            if !names.insert(name) {
+                errors.push(format!("storage backend '{}' is defined more than once", name));
+            }
+            if *path == self.data_dir || *path == self.metadata_dir {
+                errors.push(format!(
+                    "storage backend '{}' must not use the data or metadata directory",
+                    name
+                ));
+            }
+        }
+        if self.tls_cert.is_some() != self.tls_key.is_some() {
+            errors.push("tls_cert and tls_key must be set together".into());
+        }
+        if self.acme_enabled && self.tls_cert.is_some() {
+            errors.push("acme_enabled and tls_cert cannot be used together".into());
+        }
+        if self.admin_client_ca.is_some() && self.url_scheme() != "https" {
+            errors.push("admin_client_ca requires tls_cert or acme_enabled".into());
+        }
+        if !matches!(self.access_log_format.as_str(), "clf" | "json") {
+            errors.push(format!("access_log_format must be clf or json, not '{}'", self.access_log_format));
+        }
+        if self.snapshot_s3_access_key.is_some() != self.snapshot_s3_secret_key.is_some() {
+            errors.push("snapshot_s3_access_key and snapshot_s3_secret_key must be set together".into());
+        }
+        if self.snapshot_s3_endpoint.is_some()
+            && !self.snapshot_target.as_deref().is_some_and(|t| t.starts_with("s3://"))
+        {
+            errors.push("snapshot_s3_endpoint requires an s3:// snapshot_target".into());
+        }
+        errors
+    }
+
+    /// Scheme of the URLs clients reach the server at.
+    pub fn url_scheme(&self) -> &'static str {
+        if self.tls_cert.is_some() || self.acme_enabled { "https" } else { "http" }
+    }
+
+    /// Whether a SigV4 credential scope region is accepted. Without an explicit
+    /// list only `region` is; a `*` entry accepts any region.
+    pub fn accepts_region(&self, region: &str) -> bool {
+        match &self.accepted_regions {
+            Some(regions) => regions.iter().any(|r| r == "*" || r == region),
+            None => region == self.region,
         }
     }
 }
@@ -92,10 +390,482 @@ impl Default for Config {
             multipart_ttl_secs: 86400,
             multipart_cleanup_interval_secs: 3600,
             lifecycle_scan_interval_secs: 3600,
+            tagging_gc_interval_secs: 86400,
             cors_origins: None,
             max_object_size: 5 * 1024 * 1024 * 1024,
+            io_threads: 0,
             max_xml_body_size: 256 * 1024,
             max_policy_body_size: 20 * 1024,
+            buffered_bytes_high_watermark: 0,
+            max_concurrent_transfers: 0,
+            rate_limit_rps: 0,
+            rate_limit_burst: 0,
+            rate_limit_per_ip_rps: 0,
+            rate_limit_per_ip_burst: 0,
+            read_timeout_secs: 0,
+            write_timeout_secs: 0,
+            shutdown_timeout_secs: 30,
+            bucket_delete_grace_secs: 0,
+            list_cache_entries: 256,
+            warm_up: false,
+            escape_object_paths: false,
+            md5_skip_threshold: None,
+            security_webhook_interval_secs: 60,
+            access_log_interval_secs: 300,
+            access_log: None,
+            access_log_format: "clf".into(),
+            access_log_max_size: 100 * 1024 * 1024,
+            access_log_max_files: 5,
+            replication_interval_secs: 5,
+            usage_interval_secs: 300,
+            usage_alert_interval_secs: 60,
+            accepted_regions: None,
+            storage_backends: Vec::new(),
+            scan_endpoint: None,
+            rendition_command: None,
+            tls_cert: None,
+            tls_key: None,
+            acme_enabled: false,
+            acme_directory: "https://acme-v02.api.letsencrypt.org/directory".into(),
+            acme_email: None,
+            admin_client_ca: None,
+            clock_control: false,
+            metadata_probe_interval_secs: 5,
+            metadata_failure_threshold: 3,
+            snapshot_target: None,
+            snapshot_interval_secs: 3600,
+            snapshot_s3_endpoint: None,
+            snapshot_s3_region: "us-east-1".into(),
+            snapshot_s3_access_key: None,
+            snapshot_s3_secret_key: None,
+        }
+    }
+}
+
+/// Parse `name=path` pairs separated by commas, skipping malformed entries.
+fn parse_storage_backends(value: &str) -> Vec<(String, PathBuf)> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (name, path) = entry.split_once('=')?;
+            let (name, path) = (name.trim(), path.trim());
+            if name.is_empty() || path.is_empty() {
+                return None;
+            }
+            Some((name.to_string(), PathBuf::from(path)))
+        })
+        .collect()
+}
+
+/// Keys accepted in a server config file (`--config`): every setting, under
+/// the name of its [`Config`] field. Each one overrides the default and is
+/// itself overridden by the environment and command-line flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub bind: Option<String>,
+    pub data_dir: Option<PathBuf>,
+    pub metadata_dir: Option<PathBuf>,
+    pub hostname: Option<String>,
+    pub region: Option<String>,
+    pub log_level: Option<String>,
+    pub anonymous_global: Option<bool>,
+    pub admin_enabled: Option<bool>,
+    pub admin_bind: Option<String>,
+    pub admin_token: Option<String>,
+    pub multipart_ttl_secs: Option<u64>,
+    pub multipart_cleanup_interval_secs: Option<u64>,
+    pub lifecycle_scan_interval_secs: Option<u64>,
+    pub tagging_gc_interval_secs: Option<u64>,
+    pub cors_origins: Option<Vec<String>>,
+    pub max_object_size: Option<usize>,
+    pub io_threads: Option<usize>,
+    pub max_xml_body_size: Option<usize>,
+    pub max_policy_body_size: Option<usize>,
+    pub buffered_bytes_high_watermark: Option<u64>,
+    pub max_concurrent_transfers: Option<usize>,
+    pub rate_limit_rps: Option<u32>,
+    pub rate_limit_burst: Option<u32>,
+    pub rate_limit_per_ip_rps: Option<u32>,
+    pub rate_limit_per_ip_burst: Option<u32>,
+    pub read_timeout_secs: Option<u64>,
+    pub write_timeout_secs: Option<u64>,
+    pub shutdown_timeout_secs: Option<u64>,
+    pub bucket_delete_grace_secs: Option<u64>,
+    pub list_cache_entries: Option<usize>,
+    pub warm_up: Option<bool>,
+    pub escape_object_paths: Option<bool>,
+    pub md5_skip_threshold: Option<u64>,
+    pub security_webhook_interval_secs: Option<u64>,
+    pub access_log_interval_secs: Option<u64>,
+    pub access_log: Option<String>,
+    pub access_log_format: Option<String>,
+    pub access_log_max_size: Option<u64>,
+    pub access_log_max_files: Option<usize>,
+    pub replication_interval_secs: Option<u64>,
+    pub usage_interval_secs: Option<u64>,
+    pub usage_alert_interval_secs: Option<u64>,
+    pub accepted_regions: Option<Vec<String>>,
+    /// Backend name to data directory.
+    pub storage_backends: Option<BTreeMap<String, PathBuf>>,
+    pub scan_endpoint: Option<String>,
+    pub rendition_command: Option<String>,
+    pub tls_cert: Option<PathBuf>,
+    pub tls_key: Option<PathBuf>,
+    pub acme_enabled: Option<bool>,
+    pub acme_directory: Option<String>,
+    pub acme_email: Option<String>,
+    pub admin_client_ca: Option<PathBuf>,
+    pub clock_control: Option<bool>,
+    pub metadata_probe_interval_secs: Option<u64>,
+    pub metadata_failure_threshold: Option<u32>,
+    pub snapshot_target: Option<String>,
+    pub snapshot_interval_secs: Option<u64>,
+    pub snapshot_s3_endpoint: Option<String>,
+    pub snapshot_s3_region: Option<String>,
+    pub snapshot_s3_access_key: Option<String>,
+    pub snapshot_s3_secret_key: Option<String>,
+}
+
+/// Settings given on the command line, applied last.
+#[derive(Debug, Default)]
+pub struct ConfigOverrides {
+    pub bind: Option<String>,
+    pub data_dir: Option<PathBuf>,
+    pub metadata_dir: Option<PathBuf>,
+    pub hostname: Option<String>,
+    pub region: Option<String>,
+    pub admin_bind: Option<String>,
+}
+
+/// What [`ConfigBuilder::build`] does about data, metadata and storage
+/// backend directories that do not exist.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DirPolicy {
+    /// Create missing directories.
+    #[default]
+    Create,
+    /// Reject the configuration if any directory is missing.
+    MustExist,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+    #[error("Invalid configuration:\n{}", .0.iter().map(|e| format!("  - {}", e)).collect::<Vec<_>>().join("\n"))]
+    Invalid(Vec<String>),
+}
+
+/// Layers configuration sources, lowest precedence first: defaults, a config
+/// file, the environment, then command-line overrides. Call the layers in
+/// that order.
+#[derive(Debug, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+    dir_policy: DirPolicy,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let file: ConfigFile = toml::from_str(&content).map_err(|e| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })?;
+        self.config.apply_file(file);
+        Ok(self)
+    }
+
+    pub fn env(mut self) -> Self {
+        self.config.apply_env();
+        self
+    }
+
+    pub fn overrides(mut self, overrides: ConfigOverrides) -> Self {
+        let config = &mut self.config;
+        if let Some(bind) = overrides.bind {
+            config.bind = bind;
+        }
+        if let Some(data_dir) = overrides.data_dir {
+            config.data_dir = data_dir;
+        }
+        if let Some(metadata_dir) = overrides.metadata_dir {
+            config.metadata_dir = metadata_dir;
         }
+        if let Some(hostname) = overrides.hostname {
+            config.hostname = hostname;
+        }
+        if let Some(region) = overrides.region {
+            config.region = region;
+        }
+        if let Some(admin_bind) = overrides.admin_bind {
+            config.admin_bind = admin_bind;
+        }
+        self
+    }
+
+    pub fn dir_policy(mut self, policy: DirPolicy) -> Self {
+        self.dir_policy = policy;
+        self
+    }
+
+    /// Validate the layered settings and prepare the directories they name
+    /// according to the [`DirPolicy`]. All problems are reported at once.
+    pub fn build(self) -> Result<Config, ConfigError> {
+        let config = self.config;
+        let mut errors = config.validate();
+        if errors.is_empty() {
+            let dirs = [("data_dir", &config.data_dir), ("metadata_dir", &config.metadata_dir)]
+                .into_iter()
+                .chain(config.storage_backends.iter().map(|(_, dir)| ("storage backend", dir)));
+            for (name, dir) in dirs {
+                match self.dir_policy {
+                    DirPolicy::Create => {
+                        if let Err(e) = std::fs::create_dir_all(dir) {
+                            errors.push(format!("cannot create {} {}: {}", name, dir.display(), e));
+                        }
+                    }
+                    DirPolicy::MustExist => {
+                        if !dir.is_dir() {
+                            errors.push(format!("{} {} does not exist", name, dir.display()));
+                        }
+                    }
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+}
+
+/// Check a `host:port` listen address, returning its port.
+fn check_address(field: &str, value: &str, errors: &mut Vec<String>) -> Option<u16> {
+    let parsed = value
+        .rsplit_once(':')
+        .filter(|(host, _)| !host.is_empty())
+        .and_then(|(_, port)| port.parse::<u16>().ok());
+    if parsed.is_none() {
+        errors.push(format!("{} must be host:port, got '{}'", field, value));
+    }
+    parsed
+}
+
+fn mask_secret<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.as_ref().map(|_| "********").serialize(serializer)
+}
+
+fn set_string(field: &mut String, var: &str) {
+    if let Ok(v) = env::var(var) {
+        *field = v;
+    }
+}
+
+fn set_path(field: &mut PathBuf, var: &str) {
+    if let Ok(v) = env::var(var) {
+        *field = PathBuf::from(v);
+    }
+}
+
+fn set_parsed<T: FromStr>(field: &mut T, var: &str) {
+    if let Some(v) = env::var(var).ok().and_then(|v| v.parse().ok()) {
+        *field = v;
+    }
+}
+
+fn set_optional(field: &mut Option<String>, var: &str) {
+    if let Some(v) = env::var(var).ok().filter(|s| !s.is_empty()) {
+        *field = Some(v);
+    }
+}
+
+fn set_optional_path(field: &mut Option<PathBuf>, var: &str) {
+    if let Some(v) = env::var(var).ok().filter(|s| !s.is_empty()) {
+        *field = Some(PathBuf::from(v));
+    }
+}
+
+/// Comma-separated list, trimmed.
+fn set_list(field: &mut Option<Vec<String>>, var: &str) {
+    if let Some(v) = env::var(var).ok().filter(|s| !s.is_empty()) {
+        *field = Some(v.split(',').map(|s| s.trim().to_string()).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn builder_in(dir: &TempDir) -> ConfigBuilder {
+        ConfigBuilder::new().overrides(ConfigOverrides {
+            data_dir: Some(dir.path().join("data")),
+            metadata_dir: Some(dir.path().join("metadata")),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_file_layer_and_overrides() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.toml");
+        std::fs::write(
+            &path,
+            r#"
+bind = "127.0.0.1:9100"
+region = "eu-west-1"
+admin_token = "secret"
+cors_origins = ["https://app.example.com"]
+rate_limit_rps = 20
+
+[storage_backends]
+cold = "/mnt/cold"
+"#,
+        )
+        .unwrap();
+
+        let config = ConfigBuilder::new()
+            .file(&path)
+            .unwrap()
+            .overrides(ConfigOverrides {
+                region: Some("us-west-2".into()),
+                ..Default::default()
+            })
+            .config;
+        assert_eq!(config.bind, "127.0.0.1:9100");
+        assert_eq!(config.region, "us-west-2");
+        assert_eq!(config.hostname, "s3.localhost");
+        assert_eq!(config.admin_token.as_deref(), Some("secret"));
+        assert_eq!(config.cors_origins.unwrap(), vec!["https://app.example.com"]);
+        assert_eq!(config.rate_limit_rps, 20);
+        assert_eq!(config.storage_backends, vec![("cold".to_string(), PathBuf::from("/mnt/cold"))]);
+    }
+
+    #[test]
+    fn test_file_covers_every_setting() {
+        // Every serialized field is a config file key; `deny_unknown_fields`
+        // fails on a field without one
+        let mut values = serde_json::to_value(Config::default()).unwrap();
+        let fields = values.as_object_mut().unwrap();
+        fields.remove("storage_backends");
+        fields.insert("read_timeout_secs".into(), 7.into());
+        fields.insert("tls_cert".into(), "/etc/simples3/cert.pem".into());
+        let file: ConfigFile = serde_json::from_value(values).unwrap();
+
+        let mut config = Config::default();
+        config.apply_file(file);
+        assert_eq!(config.read_timeout_secs, 7);
+        assert_eq!(config.tls_cert, Some(PathBuf::from("/etc/simples3/cert.pem")));
+        assert_eq!(config.acme_directory, Config::default().acme_directory);
+    }
+
+    #[test]
+    fn test_file_rejects_unknown_keys() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("server.toml");
+        std::fs::write(&path, "bnid = \"0.0.0.0:9000\"\n").unwrap();
+        let err = ConfigBuilder::new().file(&path).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+        assert!(err.to_string().contains("bnid"));
+    }
+
+    #[test]
+    fn test_build_creates_directories() {
+        let dir = TempDir::new().unwrap();
+        let config = builder_in(&dir).build().unwrap();
+        assert!(config.data_dir.is_dir());
+        assert!(config.metadata_dir.is_dir());
+    }
+
+    #[test]
+    fn test_build_requires_existing_directories() {
+        let dir = TempDir::new().unwrap();
+        let err = builder_in(&dir).dir_policy(DirPolicy::MustExist).build().unwrap_err();
+        let ConfigError::Invalid(errors) = err else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(!dir.path().join("data").exists());
+    }
+
+    #[test]
+    fn test_validate_reports_all_errors() {
+        let config = Config {
+            bind: "0.0.0.0:http".into(),
+            admin_bind: "9001".into(),
+            metadata_dir: PathBuf::from("./data"),
+            snapshot_s3_access_key: Some("AKID".into()),
+            snapshot_s3_endpoint: Some("https://s3.example.com".into()),
+            tls_cert: Some(PathBuf::from("cert.pem")),
+            ..Default::default()
+        };
+        let errors = config.validate();
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors[0].starts_with("bind must be host:port"));
+        assert!(errors[1].starts_with("admin_bind must be host:port"));
+
+        let clash = Config {
+            admin_bind: "0.0.0.0:9000".into(),
+            ..Default::default()
+        };
+        assert_eq!(clash.validate(), vec!["bind and admin_bind are both 0.0.0.0:9000"]);
+        let disabled = Config {
+            admin_enabled: false,
+            ..clash
+        };
+        assert!(disabled.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_admin_client_ca() {
+        let config = Config {
+            admin_client_ca: Some(PathBuf::from("ca.pem")),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), vec!["admin_client_ca requires tls_cert or acme_enabled"]);
+        let config = Config {
+            tls_cert: Some(PathBuf::from("cert.pem")),
+            tls_key: Some(PathBuf::from("key.pem")),
+            ..config
+        };
+        assert!(config.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_access_log_format() {
+        let config = Config {
+            access_log_format: "xml".into(),
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), vec!["access_log_format must be clf or json, not 'xml'"]);
+    }
+
+    #[test]
+    fn test_effective_config_masks_secrets() {
+        let config = Config {
+            admin_token: Some("token".into()),
+            snapshot_s3_secret_key: Some("secret".into()),
+            ..Default::default()
+        };
+        let value = serde_json::to_value(&config).unwrap();
+        assert_eq!(value["admin_token"], "********");
+        assert_eq!(value["snapshot_s3_secret_key"], "********");
+        assert!(value["snapshot_s3_access_key"].is_null());
+        assert_eq!(value["bind"], "0.0.0.0:9000");
     }
 }
@@ -16,10 +16,24 @@ pub enum S3Error {
     BucketAlreadyExists,
     #[error("The bucket you tried to delete is not empty")]
     BucketNotEmpty,
+    #[error("The bucket you tried to delete has deletion protection enabled")]
+    BucketDeletionProtected,
+    #[error("The bucket is frozen and does not accept writes")]
+    BucketFrozen,
+    #[error("The key is leased by another holder")]
+    LeaseHeld,
+    #[error("The key is not leased")]
+    NoSuchLease,
+    #[error("A conflicting operation is in progress against this bucket name. Please try again later.")]
+    OperationAborted,
     #[error("Access Denied")]
     AccessDenied,
     #[error("The request signature we calculated does not match the signature you provided")]
     SignatureDoesNotMatch,
+    #[error("The difference between the request time and the server's time is too large.")]
+    RequestTimeTooSkewed,
+    #[error("The authorization header is malformed; the region '{received}' is wrong; expecting '{expected}'")]
+    AuthorizationHeaderMalformed { received: String, expected: String },
     #[error("Invalid part")]
     InvalidPart,
     #[error("Invalid part order")]
@@ -30,8 +44,40 @@ pub enum S3Error {
     NoSuchBucketPolicy,
     #[error("The CORS configuration does not exist for this bucket")]
     NoSuchCORSConfiguration,
+    #[error("The specified bucket does not have a website configuration")]
+    NoSuchWebsiteConfiguration,
+    #[error("Object Lock configuration does not exist for this bucket")]
+    ObjectLockConfigurationNotFound,
+    #[error("The specified object does not have a ObjectLock configuration")]
+    NoSuchObjectLockConfiguration,
+    #[error("The XML you provided was not well-formed or did not validate against our published schema")]
+    MalformedXML,
+    #[error("Your proposed upload exceeds the maximum allowed size")]
+    EntityTooLarge,
+    #[error("The {0} you specified did not match the calculated checksum.")]
+    BadDigest(String),
     #[error("Invalid argument")]
     InvalidArgument(String),
+    #[error("At least one of the pre-conditions you specified did not hold")]
+    PreconditionFailed,
+    #[error("The requested range is not satisfiable")]
+    InvalidRange { size: u64 },
+    #[error("The operation is not valid for the object's storage class")]
+    InvalidObjectState,
+    #[error("Please reduce your request rate.")]
+    SlowDown,
+    #[error("Service is unable to handle request.")]
+    ServiceUnavailable,
+    #[error("Your socket connection to the server was not read from or written to within the timeout period.")]
+    RequestTimeout,
+    #[error("The object failed a content scan and is quarantined")]
+    ObjectQuarantined,
+    #[error("The object is held for moderation")]
+    ObjectUnderModeration,
+    #[error("The object is pending moderation review")]
+    ObjectPendingReview,
+    #[error("This functionality is not enabled on this server")]
+    NotImplemented,
     #[error("Internal server error")]
     InternalError(String),
 }
@@ -44,14 +90,37 @@ impl S3Error {
             S3Error::NoSuchUpload => "NoSuchUpload",
             S3Error::BucketAlreadyExists => "BucketAlreadyOwnedByYou",
             S3Error::BucketNotEmpty => "BucketNotEmpty",
+            S3Error::BucketDeletionProtected => "BucketDeletionProtected",
+            S3Error::BucketFrozen => "BucketFrozen",
+            S3Error::LeaseHeld => "LeaseHeld",
+            S3Error::NoSuchLease => "NoSuchLease",
+            S3Error::OperationAborted => "OperationAborted",
             S3Error::AccessDenied => "AccessDenied",
             S3Error::SignatureDoesNotMatch => "SignatureDoesNotMatch",
+            S3Error::RequestTimeTooSkewed => "RequestTimeTooSkewed",
+            S3Error::AuthorizationHeaderMalformed { .. } => "AuthorizationHeaderMalformed",
             S3Error::InvalidPart => "InvalidPart",
             S3Error::InvalidPartOrder => "InvalidPartOrder",
             S3Error::NoSuchLifecycleConfiguration => "NoSuchLifecycleConfiguration",
             S3Error::NoSuchBucketPolicy => "NoSuchBucketPolicy",
             S3Error::NoSuchCORSConfiguration => "NoSuchCORSConfiguration",
+            S3Error::NoSuchWebsiteConfiguration => "NoSuchWebsiteConfiguration",
+            S3Error::ObjectLockConfigurationNotFound => "ObjectLockConfigurationNotFoundError",
+            S3Error::NoSuchObjectLockConfiguration => "NoSuchObjectLockConfiguration",
+            S3Error::MalformedXML => "MalformedXML",
+            S3Error::EntityTooLarge => "EntityTooLarge",
+            S3Error::BadDigest(_) => "BadDigest",
             S3Error::InvalidArgument(_) => "InvalidArgument",
+            S3Error::PreconditionFailed => "PreconditionFailed",
+            S3Error::InvalidRange { .. } => "InvalidRange",
+            S3Error::InvalidObjectState => "InvalidObjectState",
+            S3Error::SlowDown => "SlowDown",
+            S3Error::ServiceUnavailable => "ServiceUnavailable",
+            S3Error::RequestTimeout => "RequestTimeout",
+            S3Error::ObjectQuarantined => "ObjectQuarantined",
+            S3Error::ObjectUnderModeration => "ObjectUnderModeration",
+            S3Error::ObjectPendingReview => "ObjectPendingReview",
+            S3Error::NotImplemented => "NotImplemented",
             S3Error::InternalError(_) => "InternalError",
         }
     }
@@ -63,26 +132,102 @@ impl S3Error {
             | S3Error::NoSuchUpload
             | S3Error::NoSuchLifecycleConfiguration
             | S3Error::NoSuchBucketPolicy
-            | S3Error::NoSuchCORSConfiguration => StatusCode::NOT_FOUND,
+            | S3Error::NoSuchCORSConfiguration
+            | S3Error::NoSuchWebsiteConfiguration
+            | S3Error::NoSuchLease
+            | S3Error::ObjectLockConfigurationNotFound
+            | S3Error::NoSuchObjectLockConfiguration => StatusCode::NOT_FOUND,
             S3Error::BucketAlreadyExists => StatusCode::CONFLICT,
             S3Error::BucketNotEmpty => StatusCode::CONFLICT,
-            S3Error::AccessDenied | S3Error::SignatureDoesNotMatch => StatusCode::FORBIDDEN,
-            S3Error::InvalidPart | S3Error::InvalidPartOrder | S3Error::InvalidArgument(_) => {
-                StatusCode::BAD_REQUEST
-            }
+            S3Error::BucketDeletionProtected => StatusCode::CONFLICT,
+            S3Error::BucketFrozen => StatusCode::CONFLICT,
+            S3Error::LeaseHeld => StatusCode::CONFLICT,
+            S3Error::OperationAborted => StatusCode::CONFLICT,
+            S3Error::AccessDenied
+            | S3Error::SignatureDoesNotMatch
+            | S3Error::RequestTimeTooSkewed
+            | S3Error::InvalidObjectState
+            | S3Error::ObjectQuarantined
+            | S3Error::ObjectUnderModeration
+            | S3Error::ObjectPendingReview => StatusCode::FORBIDDEN,
+            S3Error::InvalidPart
+            | S3Error::InvalidPartOrder
+            | S3Error::AuthorizationHeaderMalformed { .. }
+            | S3Error::MalformedXML
+            | S3Error::EntityTooLarge
+            | S3Error::BadDigest(_)
+            | S3Error::InvalidArgument(_)
+            | S3Error::RequestTimeout => StatusCode::BAD_REQUEST,
+            S3Error::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            S3Error::InvalidRange { .. } => StatusCode::RANGE_NOT_SATISFIABLE,
+            S3Error::SlowDown | S3Error::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            S3Error::NotImplemented => StatusCode::NOT_IMPLEMENTED,
             S3Error::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
-    pub fn to_xml(&self) -> String {
+    pub fn to_xml(&self, context: &ErrorContext) -> String {
+        self.info().to_xml(context)
+    }
+
+    pub fn info(&self) -> S3ErrorInfo {
+        let region = match self {
+            S3Error::AuthorizationHeaderMalformed { expected, .. } => Some(expected.clone()),
+            _ => None,
+        };
+        S3ErrorInfo {
+            code: self.code().to_string(),
+            message: self.to_string(),
+            region,
+        }
+    }
+}
+
+/// Request details echoed in error bodies, as AWS does: the requested
+/// resource (`/bucket/key`) and the request IDs.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContext {
+    pub resource: Option<String>,
+    pub request_id: Option<String>,
+    pub host_id: Option<String>,
+}
+
+/// Code and message of an error response, attached to the response
+/// extensions so middleware can re-render the body in another format or
+/// with the request's context.
+#[derive(Debug, Clone)]
+pub struct S3ErrorInfo {
+    pub code: String,
+    pub message: String,
+    /// Expected region of `AuthorizationHeaderMalformed`.
+    pub region: Option<String>,
+}
+
+impl S3ErrorInfo {
+    pub fn to_xml(&self, context: &ErrorContext) -> String {
         let mut writer = Writer::new(Cursor::new(Vec::new()));
         writer
             .create_element("Error")
             .write_inner_content(|w| {
                 w.create_element("Code")
-                    .write_text_content(BytesText::new(self.code()))?;
+                    .write_text_content(BytesText::new(&self.code))?;
                 w.create_element("Message")
-                    .write_text_content(BytesText::new(&self.to_string()))?;
+                    .write_text_content(BytesText::new(&self.message))?;
+                // SDKs read the expected region from the body to retry against it
+                if let Some(ref region) = self.region {
+                    w.create_element("Region")
+                        .write_text_content(BytesText::new(region))?;
+                }
+                let fields = [
+                    ("Resource", &context.resource),
+                    ("RequestId", &context.request_id),
+                    ("HostId", &context.host_id),
+                ];
+                for (name, value) in fields {
+                    if let Some(value) = value {
+                        w.create_element(name).write_text_content(BytesText::new(value))?;
+                    }
+                }
                 Ok(())
             })
             .unwrap();
@@ -98,7 +243,20 @@ impl IntoResponse for S3Error {
         if let S3Error::InternalError(ref detail) = self {
             tracing::error!(detail = %detail, "Internal server error");
         }
-        let body = self.to_xml();
-        (status, [("content-type", "application/xml")], body).into_response()
+        let info = self.info();
+        let body = info.to_xml(&ErrorContext::default());
+        let mut response = (status, [("content-type", "application/xml")], body).into_response();
+        if let S3Error::AuthorizationHeaderMalformed { expected, .. } = &self
+            && let Ok(value) = http::HeaderValue::from_str(expected)
+        {
+            response.headers_mut().insert("x-amz-bucket-region", value);
+        }
+        if let S3Error::InvalidRange { size } = &self
+            && let Ok(value) = http::HeaderValue::from_str(&format!("bytes */{}", size))
+        {
+            response.headers_mut().insert("content-range", value);
+        }
+        response.extensions_mut().insert(info);
+        response
     }
 }
@@ -1,7 +1,8 @@
 use crate::error::S3Error;
 use crate::storage::MetadataStore;
 use serde::Deserialize;
-use std::path::Path;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize)]
 pub struct InitConfig {
@@ -24,21 +25,199 @@ pub struct InitBucket {
 
 #[derive(Debug, Deserialize)]
 pub struct InitCredential {
+    /// Required unless `generate` is set, in which case a random ID is used when empty.
+    #[serde(default)]
     pub access_key_id: String,
+    /// Required unless `generate` is set.
+    #[serde(default)]
     pub secret_access_key: String,
     #[serde(default)]
     pub description: String,
+    /// Create a random key pair on first boot and write it to `secret_file`.
+    #[serde(default)]
+    pub generate: bool,
+    #[serde(default)]
+    pub secret_file: Option<PathBuf>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InitError {
+    #[error("Failed to read init config file '{}': {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{}:{line}:{column}: {message}", path.display())]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("{}: {} invalid entr{}:\n{}", path.display(), errors.len(), if errors.len() == 1 { "y" } else { "ies" }, format_errors(errors))]
+    Invalid {
+        path: PathBuf,
+        errors: Vec<ValidationError>,
+    },
+    #[error("Failed to create bucket '{bucket}': {source}")]
+    Bucket { bucket: String, source: S3Error },
+    #[error("Failed to create credential '{access_key_id}': {source}")]
+    Credential { access_key_id: String, source: S3Error },
+    #[error("Failed to write generated credential to '{}': {source}", path.display())]
+    SecretFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// A problem with one `[[buckets]]` or `[[credentials]]` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// Entry path, e.g. `buckets[1]`.
+    pub entry: String,
+    /// 1-based line of the entry in the file, when loaded from one.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}: {}", line, self.entry, self.message),
+            None => write!(f, "{}: {}", self.entry, self.message),
+        }
+    }
+}
+
+fn format_errors(errors: &[ValidationError]) -> String {
+    errors.iter().map(|e| format!("  {}", e)).collect::<Vec<_>>().join("\n")
+}
+
+/// Entry positions, read in a second pass so `InitConfig` keeps plain types.
+#[derive(Deserialize)]
+struct EntrySpans {
+    #[serde(default)]
+    buckets: Vec<toml::Spanned<toml::Table>>,
+    #[serde(default)]
+    credentials: Vec<toml::Spanned<toml::Table>>,
+}
+
+fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+/// Load and validate an init config file. Parse errors carry the line and
+/// column; validation reports every invalid entry at once.
+pub fn load(path: &Path) -> Result<InitConfig, InitError> {
+    let content = std::fs::read_to_string(path).map_err(|source| InitError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |e: toml::de::Error| {
+        let (line, column) = e.span().map_or((1, 1), |span| line_col(&content, span.start));
+        InitError::Parse {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message: e.message().to_string(),
+        }
+    };
+    let config: InitConfig = toml::from_str(&content).map_err(parse_error)?;
+    let spans: EntrySpans = toml::from_str(&content).map_err(parse_error)?;
+
+    let mut errors = validate(&config);
+    for error in &mut errors {
+        let (section, index) = error.entry.trim_end_matches(']').split_once('[').unwrap_or_default();
+        let entries = if section == "buckets" { &spans.buckets } else { &spans.credentials };
+        error.line = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| entries.get(i))
+            .map(|entry| line_col(&content, entry.span().start).0);
+    }
+    if !errors.is_empty() {
+        return Err(InitError::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        });
+    }
+    Ok(config)
 }
 
-pub fn load(path: &Path) -> Result<InitConfig, String> {
-    let content = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read init config file '{}': {}", path.display(), e))?;
-    toml::from_str(&content)
-        .map_err(|e| format!("Failed to parse init config file '{}': {}", path.display(), e))
+/// Check every entry against bucket naming and credential rules, and for
+/// duplicates within the file.
+pub fn validate(config: &InitConfig) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut error = |entry: String, message: String| {
+        errors.push(ValidationError { entry, line: None, message });
+    };
+
+    let mut names = std::collections::HashSet::new();
+    for (i, bucket) in config.buckets.iter().enumerate() {
+        let entry = format!("buckets[{}]", i);
+        if let Err(S3Error::InvalidArgument(msg)) = crate::storage::metadata::validate_bucket_name(&bucket.name) {
+            error(entry.clone(), format!("invalid bucket name '{}': {}", bucket.name, msg));
+        }
+        if !names.insert(bucket.name.as_str()) {
+            error(entry.clone(), format!("duplicate bucket name '{}'", bucket.name));
+        }
+        if let Some(origins) = &bucket.cors_origins
+            && origins.is_empty()
+        {
+            error(entry, "cors_origins must not be empty".into());
+        }
+    }
+
+    let mut ids = std::collections::HashSet::new();
+    for (i, cred) in config.credentials.iter().enumerate() {
+        let entry = format!("credentials[{}]", i);
+        if cred.generate {
+            if !cred.secret_access_key.is_empty() {
+                error(entry.clone(), "secret_access_key cannot be combined with generate = true".into());
+            }
+            if cred.secret_file.is_none() {
+                error(entry.clone(), "secret_file is required with generate = true".into());
+            }
+            if cred.access_key_id.is_empty() {
+                continue;
+            }
+        } else if cred.secret_file.is_some() {
+            error(entry.clone(), "secret_file is only used with generate = true".into());
+        }
+        let id = &cred.access_key_id;
+        if id.len() < 3 || id.len() > 128 {
+            error(entry.clone(), format!("access_key_id '{}' must be between 3 and 128 characters", id));
+        } else if !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+            error(
+                entry.clone(),
+                format!("access_key_id '{}' must contain only letters, digits, '_', '-' and '.'", id),
+            );
+        }
+        if !ids.insert(id.as_str()) {
+            error(entry.clone(), format!("duplicate access_key_id '{}'", id));
+        }
+        let secret = &cred.secret_access_key;
+        if cred.generate {
+            continue;
+        }
+        if secret.len() < 6 || secret.len() > 128 {
+            error(entry.clone(), "secret_access_key must be between 6 and 128 characters".into());
+        } else if !secret.chars().all(|c| c.is_ascii_graphic()) {
+            error(entry, "secret_access_key must contain only printable ASCII without spaces".into());
+        }
+    }
+    errors
 }
 
-pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), String> {
+pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), InitError> {
     for bucket in &config.buckets {
+        let bucket_error = |source| InitError::Bucket {
+            bucket: bucket.name.clone(),
+            source,
+        };
         match metadata.create_bucket(&bucket.name) {
             Ok(_) => {
                 tracing::info!(bucket = %bucket.name, "Init: created bucket");
@@ -46,30 +225,18 @@ pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), String
             Err(S3Error::BucketAlreadyExists) => {
                 tracing::debug!(bucket = %bucket.name, "Init: bucket already exists, skipping");
             }
-            Err(e) => {
-                return Err(format!("Failed to create bucket '{}': {}", bucket.name, e));
-            }
+            Err(e) => return Err(bucket_error(e)),
         }
         if bucket.anonymous_read {
             metadata
                 .set_bucket_anonymous_read(&bucket.name, true)
-                .map_err(|e| {
-                    format!(
-                        "Failed to set anonymous read on bucket '{}': {}",
-                        bucket.name, e
-                    )
-                })?;
+                .map_err(bucket_error)?;
             tracing::info!(bucket = %bucket.name, "Init: enabled anonymous read");
         }
         if bucket.anonymous_list_public {
             metadata
                 .set_bucket_anonymous_list_public(&bucket.name, true)
-                .map_err(|e| {
-                    format!(
-                        "Failed to set anonymous list public on bucket '{}': {}",
-                        bucket.name, e
-                    )
-                })?;
+                .map_err(bucket_error)?;
             tracing::info!(bucket = %bucket.name, "Init: enabled anonymous list public");
         }
         if let Some(ref origins) = bucket.cors_origins {
@@ -89,17 +256,16 @@ pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), String
             };
             metadata
                 .put_cors_configuration(&bucket.name, &cors_config)
-                .map_err(|e| {
-                    format!(
-                        "Failed to set CORS on bucket '{}': {}",
-                        bucket.name, e
-                    )
-                })?;
+                .map_err(bucket_error)?;
             tracing::info!(bucket = %bucket.name, "Init: configured CORS");
         }
     }
 
     for cred in &config.credentials {
+        if cred.generate {
+            apply_generated_credential(cred, metadata)?;
+            continue;
+        }
         match metadata.create_credential(
             &cred.access_key_id,
             &cred.secret_access_key,
@@ -111,11 +277,11 @@ pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), String
             Err(S3Error::InvalidArgument(_)) => {
                 tracing::debug!(access_key_id = %cred.access_key_id, "Init: credential already exists, skipping");
             }
-            Err(e) => {
-                return Err(format!(
-                    "Failed to create credential '{}': {}",
-                    cred.access_key_id, e
-                ));
+            Err(source) => {
+                return Err(InitError::Credential {
+                    access_key_id: cred.access_key_id.clone(),
+                    source,
+                });
             }
         }
     }
@@ -123,6 +289,60 @@ pub fn apply(config: &InitConfig, metadata: &MetadataStore) -> Result<(), String
     Ok(())
 }
 
+/// Create a random key pair and write it to `secret_file` as
+/// `AWS_ACCESS_KEY_ID=` / `AWS_SECRET_ACCESS_KEY=` lines. An existing file
+/// means the credential was already generated, so this runs once.
+fn apply_generated_credential(cred: &InitCredential, metadata: &MetadataStore) -> Result<(), InitError> {
+    use crate::auth::credentials;
+    use std::io::Write;
+
+    let Some(path) = &cred.secret_file else {
+        return Ok(());
+    };
+    if path.exists() {
+        tracing::debug!(path = %path.display(), "Init: generated credential already written, skipping");
+        return Ok(());
+    }
+    let access_key_id = if cred.access_key_id.is_empty() {
+        credentials::generate_access_key_id()
+    } else {
+        cred.access_key_id.clone()
+    };
+    if metadata.get_credential(&access_key_id).is_ok() {
+        tracing::warn!(
+            access_key_id = %access_key_id,
+            path = %path.display(),
+            "Init: credential exists but its secret file is missing; the secret cannot be recovered"
+        );
+        return Ok(());
+    }
+    let secret_access_key = credentials::generate_secret_access_key();
+
+    // Write the file first: a credential whose secret was never saved is useless
+    let secret_error = |source| InitError::SecretFile { path: path.clone(), source };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(secret_error)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path).map_err(secret_error)?;
+    writeln!(file, "AWS_ACCESS_KEY_ID={}\nAWS_SECRET_ACCESS_KEY={}", access_key_id, secret_access_key)
+        .map_err(secret_error)?;
+
+    if let Err(source) = metadata.create_credential(&access_key_id, &secret_access_key, &cred.description) {
+        let _ = std::fs::remove_file(path);
+        return Err(InitError::Credential { access_key_id, source });
+    }
+    tracing::info!(
+        access_key_id = %access_key_id,
+        path = %path.display(),
+        "Init: generated credential"
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,6 +415,8 @@ description = "Development"
                 access_key_id: "AKID1".into(),
                 secret_access_key: "SECRET1".into(),
                 description: "test".into(),
+                generate: false,
+                secret_file: None,
             }],
         };
         apply(&config, &store).unwrap();
@@ -220,6 +442,8 @@ description = "Development"
                 access_key_id: "AKID_IDEM".into(),
                 secret_access_key: "SECRET".into(),
                 description: "idem".into(),
+                generate: false,
+                secret_file: None,
             }],
         };
         apply(&config, &store).unwrap();
@@ -268,4 +492,113 @@ description = "Development"
         assert_eq!(cors.rules.len(), 1);
         assert_eq!(cors.rules[0].allowed_origins, vec!["https://example.com"]);
     }
+
+    fn write_config(content: &str) -> tempfile::NamedTempFile {
+        use std::io::Write;
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_load_reports_parse_position() {
+        let file = write_config("[[buckets]]\nname = \"ok-bucket\"\nanonymous_read = maybe\n");
+        match load(file.path()) {
+            Err(InitError::Parse { line, column, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(column, 18);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_load_reports_every_invalid_entry() {
+        let file = write_config(
+            r#"
+[[buckets]]
+name = "good-bucket"
+
+[[buckets]]
+name = "Bad_Bucket"
+
+[[buckets]]
+name = "good-bucket"
+
+[[credentials]]
+access_key_id = "AKID/SLASH"
+secret_access_key = "has space in it"
+"#,
+        );
+        let Err(InitError::Invalid { errors, .. }) = load(file.path()) else {
+            panic!("expected validation errors");
+        };
+        let summary: Vec<(&str, Option<usize>)> = errors.iter().map(|e| (e.entry.as_str(), e.line)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("buckets[1]", Some(5)),
+                ("buckets[2]", Some(8)),
+                ("credentials[0]", Some(11)),
+                ("credentials[0]", Some(11)),
+            ]
+        );
+        assert!(errors[1].message.contains("duplicate"));
+    }
+
+    #[test]
+    fn test_apply_generated_credential_once() {
+        let (store, dir) = temp_store();
+        let secret_file = dir.path().join("secrets/ci.env");
+        let config = InitConfig {
+            buckets: vec![],
+            credentials: vec![InitCredential {
+                access_key_id: String::new(),
+                secret_access_key: String::new(),
+                description: "generated".into(),
+                generate: true,
+                secret_file: Some(secret_file.clone()),
+            }],
+        };
+        assert!(validate(&config).is_empty());
+        apply(&config, &store).unwrap();
+
+        let content = std::fs::read_to_string(&secret_file).unwrap();
+        let value = |name: &str| {
+            content
+                .lines()
+                .find_map(|l| l.strip_prefix(&format!("{}=", name)))
+                .unwrap()
+                .to_string()
+        };
+        let cred = store.get_credential(&value("AWS_ACCESS_KEY_ID")).unwrap();
+        assert_eq!(cred.secret_access_key, value("AWS_SECRET_ACCESS_KEY"));
+        assert_eq!(cred.description, "generated");
+
+        // Second boot leaves both the file and the store alone
+        apply(&config, &store).unwrap();
+        assert_eq!(std::fs::read_to_string(&secret_file).unwrap(), content);
+        assert_eq!(store.list_credentials().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_validate_generated_credential() {
+        let file = write_config(
+            r#"
+[[credentials]]
+generate = true
+
+[[credentials]]
+generate = true
+secret_access_key = "chosen-secret"
+secret_file = "/tmp/x.env"
+"#,
+        );
+        let Err(InitError::Invalid { errors, .. }) = load(file.path()) else {
+            panic!("expected validation errors");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("secret_file is required"));
+        assert!(errors[1].message.contains("cannot be combined"));
+    }
 }
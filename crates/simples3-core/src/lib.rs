@@ -1,9 +1,10 @@
 pub mod auth;
+pub mod clock;
 pub mod config;
 pub mod error;
 pub mod init;
 pub mod s3;
 pub mod storage;
 
-pub use config::Config;
+pub use config::{Config, ConfigBuilder};
 pub use error::S3Error;
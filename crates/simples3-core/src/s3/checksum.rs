@@ -0,0 +1,252 @@
+//! Additional upload checksums (`x-amz-checksum-*`).
+//!
+//! Values are base64-encoded digests, as on the wire. Multipart objects get a
+//! composite checksum: the digest of the concatenated part digests, suffixed
+//! with `-<part count>`.
+
+use crate::error::S3Error;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ChecksumAlgorithm {
+    Crc32,
+    Crc32c,
+    Sha1,
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 4] = [
+        ChecksumAlgorithm::Crc32,
+        ChecksumAlgorithm::Crc32c,
+        ChecksumAlgorithm::Sha1,
+        ChecksumAlgorithm::Sha256,
+    ];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| a.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "CRC32",
+            ChecksumAlgorithm::Crc32c => "CRC32C",
+            ChecksumAlgorithm::Sha1 => "SHA1",
+            ChecksumAlgorithm::Sha256 => "SHA256",
+        }
+    }
+
+    /// Header carrying the checksum value, e.g. `x-amz-checksum-crc32`.
+    pub fn header(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "x-amz-checksum-crc32",
+            ChecksumAlgorithm::Crc32c => "x-amz-checksum-crc32c",
+            ChecksumAlgorithm::Sha1 => "x-amz-checksum-sha1",
+            ChecksumAlgorithm::Sha256 => "x-amz-checksum-sha256",
+        }
+    }
+
+    /// XML element carrying the checksum value, e.g. `ChecksumCRC32`.
+    pub fn xml_element(self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc32 => "ChecksumCRC32",
+            ChecksumAlgorithm::Crc32c => "ChecksumCRC32C",
+            ChecksumAlgorithm::Sha1 => "ChecksumSHA1",
+            ChecksumAlgorithm::Sha256 => "ChecksumSHA256",
+        }
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            ChecksumAlgorithm::Crc32 => crc32fast::hash(data).to_be_bytes().to_vec(),
+            ChecksumAlgorithm::Crc32c => crc32c(data).to_be_bytes().to_vec(),
+            ChecksumAlgorithm::Sha1 => Sha1::digest(data).to_vec(),
+            ChecksumAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+        }
+    }
+
+    /// Base64-encoded checksum of `data`.
+    pub fn compute(self, data: &[u8]) -> String {
+        BASE64.encode(self.digest(data))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub value: String,
+}
+
+/// A checksum requested on an upload: the algorithm, plus the value to
+/// verify against when the client sent one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestedChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub expected: Option<String>,
+}
+
+impl RequestedChecksum {
+    /// Read `x-amz-checksum-<algorithm>` and `x-amz-sdk-checksum-algorithm`.
+    /// `default` applies when the request names no algorithm, e.g. the one
+    /// chosen when a multipart upload was created.
+    pub fn from_headers(
+        headers: &HeaderMap,
+        default: Option<ChecksumAlgorithm>,
+    ) -> Result<Option<Self>, S3Error> {
+        let mut sent = ChecksumAlgorithm::ALL
+            .into_iter()
+            .filter_map(|a| headers.get(a.header()).map(|v| (a, v)));
+        let value = sent.next();
+        if sent.next().is_some() {
+            return Err(S3Error::InvalidArgument(
+                "Expecting a single x-amz-checksum- header".into(),
+            ));
+        }
+        let value = match value {
+            Some((algorithm, v)) => match v.to_str() {
+                Ok(v) => Some((algorithm, v.to_string())),
+                Err(_) => return Err(S3Error::InvalidArgument(format!("Invalid {} header", algorithm.header()))),
+            },
+            None => None,
+        };
+
+        let named = match headers.get("x-amz-sdk-checksum-algorithm") {
+            Some(v) => Some(
+                v.to_str()
+                    .ok()
+                    .and_then(ChecksumAlgorithm::parse)
+                    .ok_or_else(|| S3Error::InvalidArgument("Unsupported checksum algorithm".into()))?,
+            ),
+            None => None,
+        };
+
+        let requested = match (named, value) {
+            (Some(named), Some((algorithm, _))) if named != algorithm => {
+                return Err(S3Error::InvalidArgument(format!(
+                    "Value for x-amz-sdk-checksum-algorithm header is invalid: expected {}",
+                    algorithm.name()
+                )));
+            }
+            (_, Some((algorithm, expected))) => Some(Self { algorithm, expected: Some(expected) }),
+            (Some(algorithm), None) => Some(Self { algorithm, expected: None }),
+            (None, None) => default.map(|algorithm| Self { algorithm, expected: None }),
+        };
+        if let (Some(requested), Some(default)) = (&requested, default)
+            && requested.algorithm != default
+        {
+            return Err(S3Error::InvalidArgument(format!(
+                "Checksum type mismatch: the upload was created with {}",
+                default.name()
+            )));
+        }
+        Ok(requested)
+    }
+
+    /// Compute the checksum of `data`, failing with `BadDigest` when it does
+    /// not match the value the client sent.
+    pub fn verify(&self, data: &[u8]) -> Result<Checksum, S3Error> {
+        let value = self.algorithm.compute(data);
+        if let Some(expected) = &self.expected
+            && *expected != value
+        {
+            return Err(S3Error::BadDigest(self.algorithm.name().into()));
+        }
+        Ok(Checksum { algorithm: self.algorithm, value })
+    }
+}
+
+/// Composite checksum of a multipart object, or `None` unless every part
+/// carries a checksum of the same algorithm.
+pub fn composite(parts: &[Option<&Checksum>]) -> Option<Checksum> {
+    let algorithm = parts.first()?.as_ref()?.algorithm;
+    let mut digests = Vec::new();
+    for part in parts {
+        let part = part.as_ref().filter(|c| c.algorithm == algorithm)?;
+        digests.extend(BASE64.decode(&part.value).ok()?);
+    }
+    Some(Checksum {
+        algorithm,
+        value: format!("{}-{}", algorithm.compute(&digests), parts.len()),
+    })
+}
+
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32C (Castagnoli).
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc = CRC32C_TABLE[((crc ^ b as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_algorithms() {
+        // Standard check values for "123456789"
+        assert_eq!(crc32fast::hash(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(ChecksumAlgorithm::Crc32.compute(b"123456789"), "y/Q5Jg==");
+        assert_eq!(ChecksumAlgorithm::Sha256.compute(b""), "47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=");
+        assert_eq!(ChecksumAlgorithm::parse("crc32c"), Some(ChecksumAlgorithm::Crc32c));
+        assert_eq!(ChecksumAlgorithm::parse("md5"), None);
+    }
+
+    #[test]
+    fn test_requested_checksum_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(RequestedChecksum::from_headers(&headers, None).unwrap(), None);
+
+        headers.insert("x-amz-checksum-sha1", "abc".parse().unwrap());
+        let requested = RequestedChecksum::from_headers(&headers, None).unwrap().unwrap();
+        assert_eq!(requested.algorithm, ChecksumAlgorithm::Sha1);
+        assert!(matches!(requested.verify(b"data"), Err(S3Error::BadDigest(_))));
+
+        headers.insert("x-amz-checksum-crc32", "abc".parse().unwrap());
+        assert!(RequestedChecksum::from_headers(&headers, None).is_err());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-amz-sdk-checksum-algorithm", "CRC32C".parse().unwrap());
+        assert!(RequestedChecksum::from_headers(&headers, Some(ChecksumAlgorithm::Sha256)).is_err());
+        let requested = RequestedChecksum::from_headers(&headers, None).unwrap().unwrap();
+        assert_eq!(requested.verify(b"123456789").unwrap().value, ChecksumAlgorithm::Crc32c.compute(b"123456789"));
+    }
+
+    #[test]
+    fn test_composite_checksum() {
+        let a = Checksum { algorithm: ChecksumAlgorithm::Crc32, value: ChecksumAlgorithm::Crc32.compute(b"a") };
+        let b = Checksum { algorithm: ChecksumAlgorithm::Crc32, value: ChecksumAlgorithm::Crc32.compute(b"b") };
+        let composite = composite(&[Some(&a), Some(&b)]).unwrap();
+        assert!(composite.value.ends_with("-2"));
+
+        let mut digests = BASE64.decode(&a.value).unwrap();
+        digests.extend(BASE64.decode(&b.value).unwrap());
+        assert_eq!(composite.value, format!("{}-2", ChecksumAlgorithm::Crc32.compute(&digests)));
+
+        assert_eq!(super::composite(&[Some(&a), None]), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! Conditional and ranged reads (`If-*` and `Range` request headers).
+//!
+//! Preconditions follow RFC 9110: `If-Match` takes precedence over
+//! `If-Unmodified-Since`, and `If-None-Match` over `If-Modified-Since`.
+//! Dates compare at one-second resolution, the precision of `Last-Modified`.
+
+use crate::error::S3Error;
+use chrono::{DateTime, Utc};
+use http::HeaderMap;
+
+/// Outcome of evaluating the `If-*` headers of a GET or HEAD request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precondition {
+    /// No condition failed; serve the object.
+    Proceed,
+    /// `If-None-Match` or `If-Modified-Since` matched: answer `304`.
+    NotModified,
+    /// `If-Match` or `If-Unmodified-Since` failed: answer `412`.
+    Failed,
+}
+
+pub fn evaluate_preconditions(headers: &HeaderMap, etag: &str, last_modified: DateTime<Utc>) -> Precondition {
+    let modified = last_modified.timestamp();
+    if let Some(value) = header(headers, "if-match") {
+        if !etag_matches(value, etag) {
+            return Precondition::Failed;
+        }
+    } else if let Some(since) = header(headers, "if-unmodified-since").and_then(parse_http_date)
+        && modified > since.timestamp()
+    {
+        return Precondition::Failed;
+    }
+
+    if let Some(value) = header(headers, "if-none-match") {
+        if etag_matches(value, etag) {
+            return Precondition::NotModified;
+        }
+    } else if let Some(since) = header(headers, "if-modified-since").and_then(parse_http_date)
+        && modified <= since.timestamp()
+    {
+        return Precondition::NotModified;
+    }
+    Precondition::Proceed
+}
+
+/// Inclusive byte range of an object, resolved against its size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range; never zero.
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// `Content-Range` header value for an object of `size` bytes.
+    pub fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// Resolve the `Range` header against an object of `size` bytes.
+///
+/// Like S3, a header that is not a single `bytes=` range is ignored and the
+/// whole object is served; a well-formed range that starts past the end of
+/// the object is rejected with `InvalidRange`.
+pub fn requested_range(headers: &HeaderMap, size: u64) -> Result<Option<ByteRange>, S3Error> {
+    let Some(spec) = header(headers, "range").and_then(|v| v.trim().strip_prefix("bytes=")) else {
+        return Ok(None);
+    };
+    if spec.contains(',') {
+        return Ok(None);
+    }
+    let Some((first, last)) = spec.split_once('-') else {
+        return Ok(None);
+    };
+    let (first, last) = (first.trim(), last.trim());
+    let range = if first.is_empty() {
+        // Suffix range: the last N bytes
+        let Ok(suffix) = last.parse::<u64>() else {
+            return Ok(None);
+        };
+        if suffix == 0 || size == 0 {
+            return Err(S3Error::InvalidRange { size });
+        }
+        ByteRange {
+            start: size.saturating_sub(suffix),
+            end: size - 1,
+        }
+    } else {
+        let Ok(start) = first.parse::<u64>() else {
+            return Ok(None);
+        };
+        let end = if last.is_empty() {
+            u64::MAX
+        } else {
+            match last.parse::<u64>() {
+                Ok(end) if end >= start => end,
+                _ => return Ok(None),
+            }
+        };
+        if start >= size {
+            return Err(S3Error::InvalidRange { size });
+        }
+        ByteRange {
+            start,
+            end: end.min(size - 1),
+        }
+    };
+    Ok(Some(range))
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// Whether a comma-separated list of entity tags (or `*`) names `etag`.
+/// Weak tags compare equal to their strong counterpart.
+fn etag_matches(list: &str, etag: &str) -> bool {
+    list.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.trim_start_matches("W/").trim_matches('"') == etag
+    })
+}
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_preconditions() {
+        let modified = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let check = |pairs: &[(&'static str, &str)]| evaluate_preconditions(&headers(pairs), "abc", modified);
+
+        assert_eq!(check(&[]), Precondition::Proceed);
+        assert_eq!(check(&[("if-match", "\"abc\"")]), Precondition::Proceed);
+        assert_eq!(check(&[("if-match", "\"other\", \"abc\"")]), Precondition::Proceed);
+        assert_eq!(check(&[("if-match", "*")]), Precondition::Proceed);
+        assert_eq!(check(&[("if-match", "\"other\"")]), Precondition::Failed);
+        assert_eq!(check(&[("if-none-match", "\"abc\"")]), Precondition::NotModified);
+        assert_eq!(check(&[("if-none-match", "W/\"abc\"")]), Precondition::NotModified);
+        assert_eq!(check(&[("if-none-match", "\"other\"")]), Precondition::Proceed);
+
+        let before = "Sun, 01 Mar 2026 11:00:00 GMT";
+        let after = "Sun, 01 Mar 2026 13:00:00 GMT";
+        assert_eq!(check(&[("if-unmodified-since", before)]), Precondition::Failed);
+        assert_eq!(check(&[("if-unmodified-since", after)]), Precondition::Proceed);
+        assert_eq!(check(&[("if-modified-since", after)]), Precondition::NotModified);
+        assert_eq!(check(&[("if-modified-since", before)]), Precondition::Proceed);
+
+        // Entity tags take precedence over dates
+        assert_eq!(check(&[("if-match", "\"abc\""), ("if-unmodified-since", before)]), Precondition::Proceed);
+        assert_eq!(check(&[("if-none-match", "\"other\""), ("if-modified-since", after)]), Precondition::Proceed);
+        // Unparseable dates are ignored
+        assert_eq!(check(&[("if-modified-since", "yesterday")]), Precondition::Proceed);
+    }
+
+    #[test]
+    fn test_requested_range() {
+        let range = |value: &str, size: u64| requested_range(&headers(&[("range", value)]), size);
+
+        assert_eq!(range("bytes=0-9", 100).unwrap(), Some(ByteRange { start: 0, end: 9 }));
+        assert_eq!(range("bytes=90-", 100).unwrap(), Some(ByteRange { start: 90, end: 99 }));
+        assert_eq!(range("bytes=90-500", 100).unwrap(), Some(ByteRange { start: 90, end: 99 }));
+        assert_eq!(range("bytes=-10", 100).unwrap(), Some(ByteRange { start: 90, end: 99 }));
+        assert_eq!(range("bytes=-500", 100).unwrap(), Some(ByteRange { start: 0, end: 99 }));
+        assert_eq!(range("bytes=10-19", 100).unwrap().unwrap().content_range(100), "bytes 10-19/100");
+        assert_eq!(range("bytes=10-19", 100).unwrap().unwrap().length(), 10);
+
+        // Ignored: whole object served
+        assert_eq!(range("items=0-9", 100).unwrap(), None);
+        assert_eq!(range("bytes=0-9,20-29", 100).unwrap(), None);
+        assert_eq!(range("bytes=9-0", 100).unwrap(), None);
+        assert_eq!(range("bytes=a-b", 100).unwrap(), None);
+        assert_eq!(requested_range(&HeaderMap::new(), 100).unwrap(), None);
+
+        // Unsatisfiable
+        assert!(matches!(range("bytes=100-", 100), Err(S3Error::InvalidRange { size: 100 })));
+        assert!(matches!(range("bytes=-0", 100), Err(S3Error::InvalidRange { .. })));
+        assert!(matches!(range("bytes=0-0", 0), Err(S3Error::InvalidRange { size: 0 })));
+    }
+}
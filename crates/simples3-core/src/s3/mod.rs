@@ -1,4 +1,7 @@
+pub mod checksum;
+pub mod conditional;
 pub mod policy;
 pub mod request;
 pub mod types;
+pub mod website;
 pub mod xml;
@@ -1,3 +1,4 @@
+use crate::error::S3Error;
 use crate::s3::types::{BucketPolicy, OneOrMany, PolicyCondition, PolicyEffect, PolicyPrincipal};
 use chrono::{DateTime, Utc};
 use std::net::IpAddr;
@@ -26,6 +27,15 @@ pub fn operation_to_s3_action(op_name: &str) -> &str {
         "DeleteObjectTagging" => "s3:DeleteObjectTagging",
         "PutObjectAcl" => "s3:PutObjectAcl",
         "GetObjectAcl" => "s3:GetObjectAcl",
+        "PutBucketAcl" => "s3:PutBucketAcl",
+        "GetBucketAcl" => "s3:GetBucketAcl",
+        "PutObjectLockConfiguration" => "s3:PutBucketObjectLockConfiguration",
+        "GetObjectLockConfiguration" => "s3:GetBucketObjectLockConfiguration",
+        "PutObjectRetention" => "s3:PutObjectRetention",
+        "GetObjectRetention" => "s3:GetObjectRetention",
+        "PutObjectLegalHold" => "s3:PutObjectLegalHold",
+        "GetObjectLegalHold" => "s3:GetObjectLegalHold",
+        "RestoreObject" => "s3:RestoreObject",
         "CreateMultipartUpload" => "s3:PutObject",
         "UploadPart" => "s3:PutObject",
         "CompleteMultipartUpload" => "s3:PutObject",
@@ -52,6 +62,26 @@ pub struct RequestContext {
     pub s3_prefix: Option<String>,
 }
 
+/// A policy decision together with the index of the statement that made it:
+/// the matching Deny, or the first matching Allow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyEvaluation {
+    pub decision: PolicyDecision,
+    pub statement: Option<usize>,
+}
+
+/// Parse and validate a bucket policy document as PutBucketPolicy accepts it.
+pub fn parse_bucket_policy(data: &[u8]) -> Result<BucketPolicy, S3Error> {
+    let policy: BucketPolicy = serde_json::from_slice(data)
+        .map_err(|e| S3Error::InvalidArgument(format!("Invalid policy JSON: {}", e)))?;
+    if policy.statements.is_empty() {
+        return Err(S3Error::InvalidArgument(
+            "Policy must contain at least one statement".to_string(),
+        ));
+    }
+    Ok(policy)
+}
+
 pub fn evaluate_policy(
     policy: &BucketPolicy,
     s3_action: &str,
@@ -60,9 +90,21 @@ pub fn evaluate_policy(
     principal_id: Option<&str>,
     context: Option<&RequestContext>,
 ) -> PolicyDecision {
-    let mut has_allow = false;
+    explain_policy(policy, s3_action, bucket, key, principal_id, context).decision
+}
+
+/// Same as [`evaluate_policy`], also reporting which statement decided.
+pub fn explain_policy(
+    policy: &BucketPolicy,
+    s3_action: &str,
+    bucket: &str,
+    key: Option<&str>,
+    principal_id: Option<&str>,
+    context: Option<&RequestContext>,
+) -> PolicyEvaluation {
+    let mut first_allow = None;
 
-    for statement in &policy.statements {
+    for (index, statement) in policy.statements.iter().enumerate() {
         if !principal_matches(&statement.principal, principal_id) {
             continue;
         }
@@ -89,15 +131,27 @@ pub fn evaluate_policy(
         }
 
         match statement.effect {
-            PolicyEffect::Deny => return PolicyDecision::ExplicitDeny,
-            PolicyEffect::Allow => has_allow = true,
+            PolicyEffect::Deny => {
+                return PolicyEvaluation {
+                    decision: PolicyDecision::ExplicitDeny,
+                    statement: Some(index),
+                };
+            }
+            PolicyEffect::Allow => {
+                first_allow.get_or_insert(index);
+            }
         }
     }
 
-    if has_allow {
-        PolicyDecision::ExplicitAllow
-    } else {
-        PolicyDecision::ImplicitDeny
+    match first_allow {
+        Some(index) => PolicyEvaluation {
+            decision: PolicyDecision::ExplicitAllow,
+            statement: Some(index),
+        },
+        None => PolicyEvaluation {
+            decision: PolicyDecision::ImplicitDeny,
+            statement: None,
+        },
     }
 }
 
@@ -153,8 +207,8 @@ fn eval_string_like(cond_key: &str, values: &[&str], ctx: &RequestContext) -> bo
     }
 }
 
-fn string_like_match(pattern: &str, value: &str) -> bool {
-    // Simple glob: * matches any sequence, ? matches single char
+/// `StringLike` matching: `*` matches any sequence, `?` a single character.
+pub fn string_like_match(pattern: &str, value: &str) -> bool {
     string_like_match_recursive(&pattern.chars().collect::<Vec<_>>(), &value.chars().collect::<Vec<_>>(), 0, 0)
 }
 
@@ -264,10 +318,10 @@ fn action_matches(actions: &OneOrMany<String>, s3_action: &str) -> bool {
             return true;
         }
         // Prefix wildcard: "s3:Get*" matches "s3:GetObject"
-        if let Some(prefix) = action.strip_suffix('*') {
-            if s3_action.starts_with(prefix) {
-                return true;
-            }
+        if let Some(prefix) = action.strip_suffix('*')
+            && s3_action.starts_with(prefix)
+        {
+            return true;
         }
     }
     false
@@ -316,6 +370,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_bucket_policy() {
+        let policy = parse_bucket_policy(
+            br#"{"Version":"2012-10-17","Statement":[{"Effect":"Allow","Principal":"*","Action":"s3:GetObject","Resource":"arn:aws:s3:::b/*"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(policy.statements.len(), 1);
+
+        assert!(matches!(parse_bucket_policy(b"not json"), Err(S3Error::InvalidArgument(_))));
+        assert!(matches!(
+            parse_bucket_policy(br#"{"Version":"2012-10-17","Statement":[]}"#),
+            Err(S3Error::InvalidArgument(_))
+        ));
+    }
+
     fn allow_anonymous_get() -> PolicyStatement {
         PolicyStatement {
             sid: Some("AllowAnon".into()),
@@ -349,6 +418,12 @@ mod tests {
         ]);
         let decision = evaluate_policy(&policy, "s3:GetObject", "mybucket", Some("file.txt"), None, None);
         assert_eq!(decision, PolicyDecision::ExplicitDeny);
+
+        // The Deny statement is reported, not the earlier Allow
+        let explained = explain_policy(&policy, "s3:GetObject", "mybucket", Some("file.txt"), None, None);
+        assert_eq!(explained.statement, Some(1));
+        let explained = explain_policy(&make_policy(vec![allow_anonymous_get()]), "s3:GetObject", "mybucket", Some("a"), None, None);
+        assert_eq!(explained, PolicyEvaluation { decision: PolicyDecision::ExplicitAllow, statement: Some(0) });
     }
 
     #[test]
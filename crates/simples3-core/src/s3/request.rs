@@ -1,3 +1,4 @@
+use crate::s3::types::Permission;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -31,6 +32,22 @@ pub enum S3Operation {
     PutBucketCors { bucket: String },
     GetBucketCors { bucket: String },
     DeleteBucketCors { bucket: String },
+    PutBucketWebsite { bucket: String },
+    GetBucketWebsite { bucket: String },
+    DeleteBucketWebsite { bucket: String },
+    PutBucketLogging { bucket: String },
+    GetBucketLogging { bucket: String },
+    PutBucketNotification { bucket: String },
+    GetBucketNotification { bucket: String },
+    PutBucketAcl { bucket: String },
+    GetBucketAcl { bucket: String },
+    PutObjectLockConfiguration { bucket: String },
+    GetObjectLockConfiguration { bucket: String },
+    PutObjectRetention { bucket: String, key: String },
+    GetObjectRetention { bucket: String, key: String },
+    PutObjectLegalHold { bucket: String, key: String },
+    GetObjectLegalHold { bucket: String, key: String },
+    RestoreObject { bucket: String, key: String },
 }
 
 impl S3Operation {
@@ -54,7 +71,12 @@ impl S3Operation {
             | S3Operation::GetObjectTagging { bucket, .. }
             | S3Operation::DeleteObjectTagging { bucket, .. }
             | S3Operation::PutObjectAcl { bucket, .. }
-            | S3Operation::GetObjectAcl { bucket, .. } => Some(bucket),
+            | S3Operation::GetObjectAcl { bucket, .. }
+            | S3Operation::PutObjectRetention { bucket, .. }
+            | S3Operation::GetObjectRetention { bucket, .. }
+            | S3Operation::PutObjectLegalHold { bucket, .. }
+            | S3Operation::GetObjectLegalHold { bucket, .. }
+            | S3Operation::RestoreObject { bucket, .. } => Some(bucket),
             S3Operation::DeleteObjects { bucket }
             | S3Operation::PutBucketLifecycleConfiguration { bucket }
             | S3Operation::GetBucketLifecycleConfiguration { bucket }
@@ -64,7 +86,18 @@ impl S3Operation {
             | S3Operation::DeleteBucketPolicy { bucket }
             | S3Operation::PutBucketCors { bucket }
             | S3Operation::GetBucketCors { bucket }
-            | S3Operation::DeleteBucketCors { bucket } => Some(bucket),
+            | S3Operation::DeleteBucketCors { bucket }
+            | S3Operation::PutBucketWebsite { bucket }
+            | S3Operation::GetBucketWebsite { bucket }
+            | S3Operation::DeleteBucketWebsite { bucket }
+            | S3Operation::PutBucketLogging { bucket }
+            | S3Operation::GetBucketLogging { bucket }
+            | S3Operation::PutBucketNotification { bucket }
+            | S3Operation::GetBucketNotification { bucket }
+            | S3Operation::PutBucketAcl { bucket }
+            | S3Operation::GetBucketAcl { bucket }
+            | S3Operation::PutObjectLockConfiguration { bucket }
+            | S3Operation::GetObjectLockConfiguration { bucket } => Some(bucket),
         }
     }
 
@@ -99,9 +132,88 @@ impl S3Operation {
             S3Operation::PutBucketCors { .. } => "PutBucketCors",
             S3Operation::GetBucketCors { .. } => "GetBucketCors",
             S3Operation::DeleteBucketCors { .. } => "DeleteBucketCors",
+            S3Operation::PutBucketWebsite { .. } => "PutBucketWebsite",
+            S3Operation::GetBucketWebsite { .. } => "GetBucketWebsite",
+            S3Operation::DeleteBucketWebsite { .. } => "DeleteBucketWebsite",
+            S3Operation::PutBucketLogging { .. } => "PutBucketLogging",
+            S3Operation::GetBucketLogging { .. } => "GetBucketLogging",
+            S3Operation::PutBucketNotification { .. } => "PutBucketNotification",
+            S3Operation::GetBucketNotification { .. } => "GetBucketNotification",
+            S3Operation::PutBucketAcl { .. } => "PutBucketAcl",
+            S3Operation::GetBucketAcl { .. } => "GetBucketAcl",
+            S3Operation::PutObjectLockConfiguration { .. } => "PutObjectLockConfiguration",
+            S3Operation::GetObjectLockConfiguration { .. } => "GetObjectLockConfiguration",
+            S3Operation::PutObjectRetention { .. } => "PutObjectRetention",
+            S3Operation::GetObjectRetention { .. } => "GetObjectRetention",
+            S3Operation::PutObjectLegalHold { .. } => "PutObjectLegalHold",
+            S3Operation::GetObjectLegalHold { .. } => "GetObjectLegalHold",
+            S3Operation::RestoreObject { .. } => "RestoreObject",
         }
     }
 
+    /// Permission a credential needs for this operation.
+    pub fn permission(&self) -> Permission {
+        match self {
+            S3Operation::ListBuckets
+            | S3Operation::HeadBucket { .. }
+            | S3Operation::ListObjectsV2 { .. }
+            | S3Operation::GetObject { .. }
+            | S3Operation::HeadObject { .. }
+            | S3Operation::ListParts { .. }
+            | S3Operation::GetObjectTagging { .. }
+            | S3Operation::GetObjectAcl { .. }
+            | S3Operation::GetObjectRetention { .. }
+            | S3Operation::GetObjectLegalHold { .. } => Permission::Read,
+            S3Operation::PutObject { .. }
+            | S3Operation::CreateMultipartUpload { .. }
+            | S3Operation::UploadPart { .. }
+            | S3Operation::CompleteMultipartUpload { .. }
+            | S3Operation::AbortMultipartUpload { .. }
+            | S3Operation::PutObjectAcl { .. }
+            | S3Operation::RestoreObject { .. } => Permission::Write,
+            S3Operation::DeleteObject { .. } | S3Operation::DeleteObjects { .. } => Permission::Delete,
+            S3Operation::PutObjectTagging { .. } | S3Operation::DeleteObjectTagging { .. } => {
+                Permission::Tagging
+            }
+            S3Operation::CreateBucket { .. }
+            | S3Operation::DeleteBucket { .. }
+            | S3Operation::PutBucketLifecycleConfiguration { .. }
+            | S3Operation::GetBucketLifecycleConfiguration { .. }
+            | S3Operation::DeleteBucketLifecycleConfiguration { .. }
+            | S3Operation::PutBucketPolicy { .. }
+            | S3Operation::GetBucketPolicy { .. }
+            | S3Operation::DeleteBucketPolicy { .. }
+            | S3Operation::PutBucketCors { .. }
+            | S3Operation::GetBucketCors { .. }
+            | S3Operation::DeleteBucketCors { .. }
+            | S3Operation::PutBucketWebsite { .. }
+            | S3Operation::GetBucketWebsite { .. }
+            | S3Operation::DeleteBucketWebsite { .. }
+            | S3Operation::PutBucketLogging { .. }
+            | S3Operation::GetBucketLogging { .. }
+            | S3Operation::PutBucketNotification { .. }
+            | S3Operation::GetBucketNotification { .. }
+            | S3Operation::PutBucketAcl { .. }
+            | S3Operation::GetBucketAcl { .. }
+            | S3Operation::PutObjectLockConfiguration { .. }
+            | S3Operation::GetObjectLockConfiguration { .. }
+            | S3Operation::PutObjectRetention { .. }
+            | S3Operation::PutObjectLegalHold { .. } => Permission::BucketAdmin,
+        }
+    }
+
+    /// Whether the operation moves object data, as counted against the
+    /// concurrent transfer limit.
+    pub fn is_transfer(&self) -> bool {
+        matches!(
+            self,
+            S3Operation::PutObject { .. }
+                | S3Operation::GetObject { .. }
+                | S3Operation::UploadPart { .. }
+                | S3Operation::CompleteMultipartUpload { .. }
+        )
+    }
+
     pub fn is_read_only(&self) -> bool {
         matches!(
             self,
@@ -116,6 +228,13 @@ impl S3Operation {
                 | S3Operation::GetBucketLifecycleConfiguration { .. }
                 | S3Operation::GetBucketPolicy { .. }
                 | S3Operation::GetBucketCors { .. }
+                | S3Operation::GetBucketWebsite { .. }
+                | S3Operation::GetBucketLogging { .. }
+                | S3Operation::GetBucketNotification { .. }
+                | S3Operation::GetBucketAcl { .. }
+                | S3Operation::GetObjectLockConfiguration { .. }
+                | S3Operation::GetObjectRetention { .. }
+                | S3Operation::GetObjectLegalHold { .. }
         )
     }
 }
@@ -165,6 +284,34 @@ pub fn parse_s3_operation(
             };
         }
 
+        // Website configuration
+        if query.contains_key("website") {
+            return match *method {
+                http::Method::PUT => Some(S3Operation::PutBucketWebsite { bucket }),
+                http::Method::GET => Some(S3Operation::GetBucketWebsite { bucket }),
+                http::Method::DELETE => Some(S3Operation::DeleteBucketWebsite { bucket }),
+                _ => None,
+            };
+        }
+
+        // Server access logging
+        if query.contains_key("logging") {
+            return match *method {
+                http::Method::PUT => Some(S3Operation::PutBucketLogging { bucket }),
+                http::Method::GET => Some(S3Operation::GetBucketLogging { bucket }),
+                _ => None,
+            };
+        }
+
+        // Event notifications
+        if query.contains_key("notification") {
+            return match *method {
+                http::Method::PUT => Some(S3Operation::PutBucketNotification { bucket }),
+                http::Method::GET => Some(S3Operation::GetBucketNotification { bucket }),
+                _ => None,
+            };
+        }
+
         // Bucket policy
         if query.contains_key("policy") {
             return match *method {
@@ -175,6 +322,24 @@ pub fn parse_s3_operation(
             };
         }
 
+        // Bucket ACL
+        if query.contains_key("acl") {
+            return match *method {
+                http::Method::PUT => Some(S3Operation::PutBucketAcl { bucket }),
+                http::Method::GET => Some(S3Operation::GetBucketAcl { bucket }),
+                _ => None,
+            };
+        }
+
+        // Object Lock configuration
+        if query.contains_key("object-lock") {
+            return match *method {
+                http::Method::PUT => Some(S3Operation::PutObjectLockConfiguration { bucket }),
+                http::Method::GET => Some(S3Operation::GetObjectLockConfiguration { bucket }),
+                _ => None,
+            };
+        }
+
         if query.contains_key("delete") && *method == http::Method::POST {
             return Some(S3Operation::DeleteObjects { bucket });
         }
@@ -243,6 +408,28 @@ pub fn parse_s3_operation(
         };
     }
 
+    // Object retention
+    if query.contains_key("retention") {
+        return match *method {
+            http::Method::PUT => Some(S3Operation::PutObjectRetention { bucket, key }),
+            http::Method::GET => Some(S3Operation::GetObjectRetention { bucket, key }),
+            _ => None,
+        };
+    }
+
+    // Object legal hold
+    if query.contains_key("legal-hold") {
+        return match *method {
+            http::Method::PUT => Some(S3Operation::PutObjectLegalHold { bucket, key }),
+            http::Method::GET => Some(S3Operation::GetObjectLegalHold { bucket, key }),
+            _ => None,
+        };
+    }
+
+    if query.contains_key("restore") && method == http::Method::POST {
+        return Some(S3Operation::RestoreObject { bucket, key });
+    }
+
     // Tagging operations
     if query.contains_key("tagging") {
         return match *method {
@@ -271,6 +458,19 @@ mod tests {
         pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
     }
 
+    #[test]
+    fn test_operation_permissions() {
+        let op = |method, path: &str, q: &[(&str, &str)]| parse_s3_operation(&method, path, &query(q)).unwrap();
+        assert_eq!(op(http::Method::GET, "/b/k", &[]).permission(), Permission::Read);
+        assert_eq!(op(http::Method::PUT, "/b/k", &[]).permission(), Permission::Write);
+        assert_eq!(op(http::Method::DELETE, "/b/k", &[]).permission(), Permission::Delete);
+        assert_eq!(op(http::Method::PUT, "/b/k", &[("tagging", "")]).permission(), Permission::Tagging);
+        assert_eq!(op(http::Method::GET, "/b/k", &[("tagging", "")]).permission(), Permission::Read);
+        assert_eq!(op(http::Method::PUT, "/b", &[("policy", "")]).permission(), Permission::BucketAdmin);
+        assert_eq!(op(http::Method::PUT, "/b", &[]).permission(), Permission::BucketAdmin);
+        assert_eq!(op(http::Method::POST, "/b/k", &[("restore", "")]).permission(), Permission::Write);
+    }
+
     #[test]
     fn test_parse_list_buckets() {
         let op = parse_s3_operation(&http::Method::GET, "/", &HashMap::new());
@@ -440,6 +640,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_bucket_acl() {
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket", &query(&[("acl", "")]));
+        assert_eq!(op, Some(S3Operation::PutBucketAcl { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::GET, "/mybucket/", &query(&[("acl", "")]));
+        assert_eq!(op, Some(S3Operation::GetBucketAcl { bucket: "mybucket".into() }));
+    }
+
+    #[test]
+    fn test_parse_object_lock() {
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket", &query(&[("object-lock", "")]));
+        assert_eq!(op, Some(S3Operation::PutObjectLockConfiguration { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::GET, "/mybucket/k", &query(&[("retention", "")]));
+        assert_eq!(
+            op,
+            Some(S3Operation::GetObjectRetention {
+                bucket: "mybucket".into(),
+                key: "k".into()
+            })
+        );
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket/k", &query(&[("legal-hold", "")]));
+        assert_eq!(
+            op,
+            Some(S3Operation::PutObjectLegalHold {
+                bucket: "mybucket".into(),
+                key: "k".into()
+            })
+        );
+    }
+
     #[test]
     fn test_parse_put_lifecycle() {
         let op = parse_s3_operation(
@@ -529,4 +759,33 @@ mod tests {
         );
         assert_eq!(op, Some(S3Operation::DeleteBucketCors { bucket: "mybucket".into() }));
     }
+
+    #[test]
+    fn test_parse_website() {
+        let q = query(&[("website", "")]);
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::PutBucketWebsite { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::GET, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::GetBucketWebsite { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::DELETE, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::DeleteBucketWebsite { bucket: "mybucket".into() }));
+    }
+
+    #[test]
+    fn test_parse_logging() {
+        let q = query(&[("logging", "")]);
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::PutBucketLogging { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::GET, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::GetBucketLogging { bucket: "mybucket".into() }));
+    }
+
+    #[test]
+    fn test_parse_notification() {
+        let q = query(&[("notification", "")]);
+        let op = parse_s3_operation(&http::Method::PUT, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::PutBucketNotification { bucket: "mybucket".into() }));
+        let op = parse_s3_operation(&http::Method::GET, "/mybucket", &q);
+        assert_eq!(op, Some(S3Operation::GetBucketNotification { bucket: "mybucket".into() }));
+    }
 }
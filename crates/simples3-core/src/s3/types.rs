@@ -1,6 +1,7 @@
+use super::checksum::{Checksum, ChecksumAlgorithm};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BucketMeta {
@@ -9,6 +10,46 @@ pub struct BucketMeta {
     pub anonymous_read: bool,
     #[serde(default)]
     pub anonymous_list_public: bool,
+    /// DeleteBucket fails while set; only cleared through the admin API.
+    #[serde(default)]
+    pub deletion_protected: bool,
+    /// Set while the bucket is frozen through the admin API.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<BucketFreeze>,
+}
+
+/// A frozen bucket rejects every write until it is thawed. Its objects are
+/// recorded in a manifest of `ArchiveEntry`s.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketFreeze {
+    pub frozen_at: DateTime<Utc>,
+    /// Whether the object data was packed into archive segments.
+    pub compacted: bool,
+}
+
+/// Manifest entry of an object in a frozen bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    pub key: String,
+    pub size: u64,
+    pub etag: String,
+    /// Hex SHA-256 of the object data, computed when the bucket was frozen.
+    pub sha256: String,
+    /// Archive segment holding the data once compacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<u32>,
+    /// Offset of the data in `segment`.
+    #[serde(default)]
+    pub offset: u64,
+}
+
+/// A deleted bucket whose name stays reserved until `purge_after`, during
+/// which an admin can restore it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedBucket {
+    pub meta: BucketMeta,
+    pub deleted_at: DateTime<Utc>,
+    pub purge_after: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +62,36 @@ pub struct ObjectMeta {
     pub last_modified: DateTime<Utc>,
     #[serde(default)]
     pub public: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<Checksum>,
+    /// Object Lock legal hold: blocks deletes and overwrites until turned off,
+    /// regardless of any retention date.
+    #[serde(default)]
+    pub legal_hold: bool,
+    /// Storage class a lifecycle transition moved the object to. The object's
+    /// data then lives on the storage backend of that name; `None` is
+    /// `STANDARD`, stored where the bucket's storage routes put the key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+}
+
+/// Storage class of objects that have not been transitioned.
+pub const STANDARD_STORAGE_CLASS: &str = "STANDARD";
+
+/// Temporary copy of a transitioned object on the backend its bucket's
+/// storage routes give the key, made by RestoreObject. It only serves reads
+/// of the object version with `etag`, and is removed once `expiry` passes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectRestore {
+    pub etag: String,
+    pub expiry: DateTime<Utc>,
+}
+
+impl ObjectRestore {
+    /// Whether this copy can serve reads of `meta` at `now`.
+    pub fn serves(&self, meta: &ObjectMeta, now: DateTime<Utc>) -> bool {
+        meta.storage_class.is_some() && self.etag == meta.etag && self.expiry > now
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +101,9 @@ pub struct MultipartUpload {
     pub key: String,
     pub created: DateTime<Utc>,
     pub parts: Vec<PartInfo>,
+    /// Algorithm named by `x-amz-checksum-algorithm` on CreateMultipartUpload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,15 +112,114 @@ pub struct PartInfo {
     pub etag: String,
     pub size: u64,
     pub last_modified: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<Checksum>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Sizes of the parts a multipart object version with `etag` was assembled
+/// from, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartLayout {
+    pub etag: String,
+    pub sizes: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AccessKeyRecord {
     pub access_key_id: String,
     pub secret_access_key: String,
     pub description: String,
     pub created: DateTime<Utc>,
     pub active: bool,
+    /// Operation classes the credential may perform. `None` allows all of
+    /// them; bucket policies still apply either way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<BTreeSet<Permission>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<CredentialRateLimit>,
+    /// The only bucket the credential may access. Bound credentials can be
+    /// managed with that bucket's administration tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+}
+
+/// Throttling of the requests signed by one credential. Unset limits don't
+/// apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredentialRateLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requests_per_sec: Option<u32>,
+    /// Request and response body bytes per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<u64>,
+}
+
+impl AccessKeyRecord {
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions.as_ref().is_none_or(|p| p.contains(&permission))
+    }
+}
+
+/// Coarse class of S3 operations, for per-credential permission masks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// List buckets and objects, read objects, their tags, ACLs and retention.
+    Read,
+    /// Upload objects, including copies and multipart uploads, and set ACLs.
+    Write,
+    /// Delete objects.
+    Delete,
+    /// Set and remove object tags.
+    Tagging,
+    /// Create and delete buckets, change or read their configuration, and
+    /// set object retention and legal holds.
+    BucketAdmin,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 5] = [
+        Permission::Read,
+        Permission::Write,
+        Permission::Delete,
+        Permission::Tagging,
+        Permission::BucketAdmin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Write => "write",
+            Permission::Delete => "delete",
+            Permission::Tagging => "tagging",
+            Permission::BucketAdmin => "bucket_admin",
+        }
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("Unknown permission '{}'", s))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListBucketsRequest {
+    pub prefix: String,
+    pub max_buckets: Option<u32>,
+    pub continuation_token: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListBucketsResponse {
+    pub buckets: Vec<BucketMeta>,
+    pub prefix: String,
+    pub continuation_token: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -59,6 +232,13 @@ pub struct ListObjectsV2Request {
     pub start_after: Option<String>,
 }
 
+/// One entry of a ListObjectsV2 page, in key order.
+#[derive(Debug, Clone)]
+pub enum ListEntry {
+    Object(ObjectMeta),
+    CommonPrefix(String),
+}
+
 #[derive(Debug, Clone)]
 pub struct ListObjectsV2Response {
     pub name: String,
@@ -92,6 +272,29 @@ pub struct LifecycleTagFilter {
     pub value: String,
 }
 
+/// Move matching objects to `storage_class` `days` after they were written,
+/// or from `date` (RFC 3339) on.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LifecycleTransition {
+    #[serde(default)]
+    pub days: Option<u32>,
+    #[serde(default)]
+    pub date: Option<String>,
+    pub storage_class: String,
+}
+
+impl LifecycleTransition {
+    pub fn is_due(&self, last_modified: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match (&self.date, self.days) {
+            (Some(date), _) => DateTime::parse_from_rfc3339(date).is_ok_and(|d| now >= d),
+            (None, Some(days)) => last_modified + chrono::Duration::days(days as i64) < now,
+            (None, None) => false,
+        }
+    }
+}
+
+/// A rule without `Expiration` (no days and no date) only transitions
+/// objects or aborts incomplete multipart uploads.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LifecycleRule {
     pub id: String,
@@ -102,6 +305,40 @@ pub struct LifecycleRule {
     pub expiration_date: Option<String>,
     #[serde(default)]
     pub tags: Vec<LifecycleTagFilter>,
+    #[serde(default)]
+    pub transitions: Vec<LifecycleTransition>,
+    /// Abort multipart uploads of matching keys this many days after they
+    /// were initiated.
+    #[serde(default)]
+    pub abort_incomplete_multipart_days: Option<u32>,
+    /// Delete noncurrent object versions this many days after they stopped
+    /// being current. Buckets are unversioned, so there are none to delete yet.
+    #[serde(default)]
+    pub noncurrent_version_expiration_days: Option<u32>,
+    /// Remove delete markers that are the only remaining version of a key.
+    /// Buckets are unversioned and never hold delete markers, so there is
+    /// nothing to clean up yet.
+    #[serde(default)]
+    pub expired_object_delete_marker: bool,
+}
+
+impl LifecycleRule {
+    pub fn has_expiration(&self) -> bool {
+        self.expiration_days > 0 || self.expiration_date.is_some()
+    }
+
+    /// Storage class an object written at `last_modified` should be in by
+    /// `now`: the due transition that is latest in time, if any.
+    pub fn due_storage_class(&self, last_modified: DateTime<Utc>, now: DateTime<Utc>) -> Option<&str> {
+        self.transitions
+            .iter()
+            .filter(|t| t.is_due(last_modified, now))
+            .max_by_key(|t| match (&t.date, t.days) {
+                (Some(date), _) => DateTime::parse_from_rfc3339(date).map(|d| d.with_timezone(&Utc)).ok(),
+                (None, days) => Some(last_modified + chrono::Duration::days(days.unwrap_or(0) as i64)),
+            })
+            .map(|t| t.storage_class.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -186,3 +423,613 @@ pub struct CorsRule {
 pub struct CorsConfiguration {
     pub rules: Vec<CorsRule>,
 }
+
+// --- Website types ---
+
+/// Static website settings of a bucket. `index_document` is the suffix
+/// appended to requests for a directory (e.g. `index.html`); `error_document`
+/// is the key served for 4xx errors.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebsiteConfiguration {
+    pub index_document: String,
+    #[serde(default)]
+    pub error_document: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routing_rules: Vec<RoutingRule>,
+}
+
+/// Redirects a website request when its condition matches. A rule without a
+/// condition matches every request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    #[serde(default)]
+    pub condition: Option<RoutingRuleCondition>,
+    pub redirect: RoutingRuleRedirect,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingRuleCondition {
+    #[serde(default)]
+    pub key_prefix_equals: Option<String>,
+    /// Only match once the lookup failed with this status (e.g. 404).
+    #[serde(default)]
+    pub http_error_code_returned_equals: Option<u16>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingRuleRedirect {
+    #[serde(default)]
+    pub host_name: Option<String>,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub replace_key_prefix_with: Option<String>,
+    #[serde(default)]
+    pub replace_key_with: Option<String>,
+    #[serde(default)]
+    pub http_redirect_code: Option<u16>,
+}
+
+// --- Configuration history types ---
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigKind {
+    Policy,
+    Lifecycle,
+    Cors,
+    Website,
+}
+
+impl ConfigKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfigKind::Policy => "policy",
+            ConfigKind::Lifecycle => "lifecycle",
+            ConfigKind::Cors => "cors",
+            ConfigKind::Website => "website",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "policy" => Some(ConfigKind::Policy),
+            "lifecycle" => Some(ConfigKind::Lifecycle),
+            "cors" => Some(ConfigKind::Cors),
+            "website" => Some(ConfigKind::Website),
+            _ => None,
+        }
+    }
+}
+
+/// One recorded change to a bucket's policy, lifecycle, CORS or website document.
+/// `previous` and `current` hold the stored JSON form; `None` means absent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigHistoryEntry {
+    pub version: u64,
+    pub bucket: String,
+    pub kind: ConfigKind,
+    pub changed_by: Option<String>,
+    pub changed_at: DateTime<Utc>,
+    pub previous: Option<serde_json::Value>,
+    pub current: Option<serde_json::Value>,
+}
+
+/// An admin API token limited to managing the settings and bound
+/// credentials of one bucket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BucketAdminToken {
+    pub id: String,
+    pub bucket: String,
+    pub description: String,
+    pub created: DateTime<Utc>,
+}
+
+/// One admin API mutation, as kept in the audit trail.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Increasing with time; entries are listed in this order.
+    pub id: u64,
+    pub time: DateTime<Utc>,
+    /// `token` for bearer-token requests, or `cert:<subject>` for requests
+    /// authenticated by a client certificate.
+    pub actor: String,
+    pub remote_ip: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Route template of the endpoint, e.g. `/_admin/buckets/{name}`.
+    pub route: Option<String>,
+    pub status: u16,
+    /// JSON request body with secrets masked; absent when there was none.
+    pub payload: Option<serde_json::Value>,
+}
+
+/// Filters of an audit trail listing. Entries are returned newest first.
+#[derive(Debug, Clone, Default)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    /// Only entries whose path starts with this.
+    pub path_prefix: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only entries with an ID below this, for paging.
+    pub before: Option<u64>,
+    pub limit: usize,
+}
+
+/// Sends keys starting with `prefix` to the named storage backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageRoute {
+    pub prefix: String,
+    pub backend: String,
+}
+
+/// Endpoint notified of access denials on a bucket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityWebhookConfig {
+    pub url: String,
+}
+
+/// Server access logging of a bucket: records are written as objects under
+/// `target_prefix` in `target_bucket`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketLoggingConfiguration {
+    pub target_bucket: String,
+    #[serde(default)]
+    pub target_prefix: String,
+}
+
+/// Where monthly usage reports are written: one CSV object per month under
+/// `target_prefix` in `target_bucket`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageReportConfig {
+    pub target_bucket: String,
+    #[serde(default)]
+    pub target_prefix: String,
+}
+
+/// Usage of a bucket over one calendar month, for chargeback. Requests are
+/// classed like S3 pricing: tier 1 writes and listings, tier 2 other reads;
+/// deletes are not counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BucketUsage {
+    /// Stored bytes integrated over time, sampled by the usage worker.
+    #[serde(default)]
+    pub storage_byte_hours: f64,
+    #[serde(default)]
+    pub tier1_requests: u64,
+    #[serde(default)]
+    pub tier2_requests: u64,
+    /// Response body bytes sent to clients.
+    #[serde(default)]
+    pub egress_bytes: u64,
+}
+
+impl BucketUsage {
+    pub fn add(&mut self, other: &BucketUsage) {
+        self.storage_byte_hours += other.storage_byte_hours;
+        self.tier1_requests += other.tier1_requests;
+        self.tier2_requests += other.tier2_requests;
+        self.egress_bytes += other.egress_bytes;
+    }
+}
+
+/// Number and total size of the objects of a bucket, kept up to date as
+/// objects are written and deleted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketStats {
+    #[serde(default)]
+    pub objects: u64,
+    #[serde(default)]
+    pub bytes: u64,
+}
+
+impl BucketStats {
+    pub fn adjust(&mut self, objects: i64, bytes: i64) {
+        self.objects = self.objects.saturating_add_signed(objects);
+        self.bytes = self.bytes.saturating_add_signed(bytes);
+    }
+}
+
+/// Usage thresholds of a bucket. Going over one raises a usage alert; unset
+/// thresholds are not checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageAlertConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_objects: Option<u64>,
+    /// S3 requests per second, averaged over the alert check interval.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_requests_per_sec: Option<u64>,
+}
+
+impl UsageAlertConfig {
+    /// Threshold set for `metric`, if any.
+    pub fn threshold(&self, metric: UsageMetric) -> Option<u64> {
+        match metric {
+            UsageMetric::Bytes => self.max_bytes,
+            UsageMetric::Objects => self.max_objects,
+            UsageMetric::RequestRate => self.max_requests_per_sec,
+        }
+    }
+}
+
+/// What a usage alert measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UsageMetric {
+    Bytes,
+    Objects,
+    RequestRate,
+}
+
+impl UsageMetric {
+    pub const ALL: [UsageMetric; 3] = [UsageMetric::Bytes, UsageMetric::Objects, UsageMetric::RequestRate];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageMetric::Bytes => "bytes",
+            UsageMetric::Objects => "objects",
+            UsageMetric::RequestRate => "request-rate",
+        }
+    }
+}
+
+/// A bucket usage threshold that is currently exceeded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageAlert {
+    pub bucket: String,
+    pub metric: UsageMetric,
+    pub threshold: u64,
+    /// Value when the alert was last checked.
+    pub value: u64,
+    pub raised: DateTime<Utc>,
+}
+
+/// Asynchronous replication of a bucket's new and updated objects to a bucket
+/// on a remote S3-compatible endpoint, with SigV4-signed requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationConfiguration {
+    /// Base URL of the remote endpoint, e.g. `https://s3.example.com`.
+    pub endpoint: String,
+    pub target_bucket: String,
+    #[serde(default = "default_replication_region")]
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Only keys starting with this prefix are replicated.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Only objects carrying all of these tags are replicated.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Only objects of at least this many bytes are replicated.
+    #[serde(default)]
+    pub min_size: Option<u64>,
+    /// Also delete keys under the prefix from the target when they are
+    /// deleted here.
+    #[serde(default)]
+    pub replicate_deletes: bool,
+}
+
+fn default_replication_region() -> String {
+    "us-east-1".into()
+}
+
+impl ReplicationConfiguration {
+    pub fn applies_to(&self, key: &str) -> bool {
+        self.prefix.as_deref().is_none_or(|p| key.starts_with(p))
+    }
+
+    /// Whether an object of `size` bytes with `tags` passes the filters.
+    pub fn matches(&self, key: &str, size: u64, tags: &HashMap<String, String>) -> bool {
+        self.applies_to(key)
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.tags.iter().all(|(k, v)| tags.get(k) == Some(v))
+    }
+}
+
+/// An object waiting to be copied to its bucket's replication target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationTask {
+    pub bucket: String,
+    pub key: String,
+    pub queued_at: DateTime<Utc>,
+    /// The key was deleted and is deleted from the target instead of copied.
+    #[serde(default)]
+    pub delete: bool,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Failed tasks are not retried before this time.
+    #[serde(default)]
+    pub retry_at: Option<DateTime<Utc>>,
+}
+
+/// Replication state of an object, as reported in `x-amz-replication-status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicationStatus {
+    /// Queued, not copied yet.
+    Pending,
+    /// Copied to the target.
+    Completed,
+    /// The last copy attempt failed; it is retried.
+    Failed,
+}
+
+impl ReplicationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplicationStatus::Pending => "PENDING",
+            ReplicationStatus::Completed => "COMPLETED",
+            ReplicationStatus::Failed => "FAILED",
+        }
+    }
+}
+
+/// Content scan state of an object, as reported in `x-simples3-scan-status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStatus {
+    /// Queued, not scanned yet. Reads are allowed meanwhile.
+    Pending,
+    Clean,
+    /// The scanner found a threat; reads are refused.
+    Infected,
+    /// The scanner could not be reached or gave no verdict.
+    Failed,
+}
+
+impl ScanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanStatus::Pending => "pending",
+            ScanStatus::Clean => "clean",
+            ScanStatus::Infected => "infected",
+            ScanStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Content scan of the object version with `etag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectScan {
+    pub etag: String,
+    pub status: ScanStatus,
+    /// Signature name for infected objects, error for failed scans.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub updated: DateTime<Utc>,
+}
+
+/// Moderation state of an object, set through the admin API for abuse
+/// handling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ModerationState {
+    #[default]
+    Active,
+    /// Awaiting review; anonymous reads are refused.
+    PendingReview,
+    /// Held after review; all reads are refused.
+    Quarantined,
+}
+
+impl ModerationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationState::Active => "active",
+            ModerationState::PendingReview => "pending-review",
+            ModerationState::Quarantined => "quarantined",
+        }
+    }
+}
+
+/// Moderation record of an object that is not `active`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectModeration {
+    pub state: ModerationState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub updated: DateTime<Utc>,
+}
+
+impl ObjectScan {
+    /// Whether reads of `meta` must be refused.
+    pub fn quarantines(&self, meta: &ObjectMeta) -> bool {
+        self.status == ScanStatus::Infected && self.etag == meta.etag
+    }
+
+    /// Whether `meta` is still waiting for its scan.
+    pub fn is_pending(&self, meta: &ObjectMeta) -> bool {
+        self.status == ScanStatus::Pending && self.etag == meta.etag
+    }
+}
+
+/// Size and age of the replication queue.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicationQueueStats {
+    /// Queued objects, including those waiting for a retry.
+    pub pending: usize,
+    /// Queued objects whose last copy attempt failed.
+    pub failed: usize,
+    /// When the longest-waiting object was queued.
+    pub oldest_queued_at: Option<DateTime<Utc>>,
+}
+
+/// Event names accepted in a notification rule.
+pub const NOTIFICATION_EVENTS: &[&str] = &[
+    "s3:ObjectCreated:*",
+    "s3:ObjectCreated:Put",
+    "s3:ObjectCreated:Copy",
+    "s3:ObjectCreated:CompleteMultipartUpload",
+    "s3:ObjectRemoved:*",
+    "s3:ObjectRemoved:Delete",
+    "s3:UsageAlert:*",
+    "s3:UsageAlert:Raised",
+    "s3:UsageAlert:Cleared",
+];
+
+/// Event notifications of a bucket. An empty rule list disables them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationConfiguration {
+    pub rules: Vec<NotificationRule>,
+}
+
+/// Matching events are published as JSON to `endpoint`: POSTed to an
+/// `http(s)://` URL, or sent to a `kafka://brokers/topic` or
+/// `nats://host/subject` target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    pub id: String,
+    pub endpoint: String,
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub suffix: Option<String>,
+}
+
+impl NotificationRule {
+    /// Whether `event` (e.g. `s3:ObjectCreated:Put`) on `key` is delivered by
+    /// this rule. A `*` event covers every event of its category.
+    pub fn matches(&self, event: &str, key: &str) -> bool {
+        let event_matches = self.events.iter().any(|e| match e.strip_suffix('*') {
+            Some(category) => event.starts_with(category),
+            None => e == event,
+        });
+        event_matches
+            && self.prefix.as_deref().is_none_or(|p| key.starts_with(p))
+            && self.suffix.as_deref().is_none_or(|s| key.ends_with(s))
+    }
+}
+
+/// Caching headers added to anonymous GET and HEAD responses of a bucket, so
+/// a CDN in front of it can cache public objects without rewriting each one.
+/// `expires_after_secs` is turned into an `Expires` date relative to the
+/// response time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheHeadersConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_after_secs: Option<u64>,
+}
+
+/// On-demand image renditions of a bucket, requested with
+/// `?x-simples3-resize=WIDTHxHEIGHT` on GetObject.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenditionConfig {
+    /// Largest dimensions a client may request.
+    pub max_width: u32,
+    pub max_height: u32,
+    /// Bytes of cached renditions kept for the bucket; the oldest are
+    /// evicted past it.
+    pub cache_limit_bytes: u64,
+}
+
+/// A cached rendition of the object version with `etag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenditionEntry {
+    pub etag: String,
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+    pub content_type: String,
+    pub created: DateTime<Utc>,
+}
+
+/// Detailed per-request logging of a bucket. A fraction `sample_rate` (0.0 to
+/// 1.0) of its requests is logged, so a busy bucket can be debugged without
+/// logging every request to the server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestLogConfig {
+    pub sample_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RetentionMode {
+    /// Can be shortened or removed with `x-amz-bypass-governance-retention: true`.
+    Governance,
+    /// Can only be extended until it expires.
+    Compliance,
+}
+
+impl RetentionMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "GOVERNANCE" => Some(RetentionMode::Governance),
+            "COMPLIANCE" => Some(RetentionMode::Compliance),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RetentionMode::Governance => "GOVERNANCE",
+            RetentionMode::Compliance => "COMPLIANCE",
+        }
+    }
+}
+
+/// Bucket-level Object Lock settings. Once enabled, Object Lock cannot be
+/// turned off; only the default retention can change.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ObjectLockConfiguration {
+    pub default_retention: Option<DefaultRetention>,
+}
+
+/// Retention applied to new objects that do not set their own. Exactly one
+/// of `days` and `years` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultRetention {
+    pub mode: RetentionMode,
+    pub days: Option<u32>,
+    pub years: Option<u32>,
+}
+
+impl DefaultRetention {
+    pub fn retention_from(&self, now: DateTime<Utc>) -> ObjectRetention {
+        let retain_until = match (self.days, self.years) {
+            (Some(days), _) => now + chrono::Duration::days(days as i64),
+            (None, Some(years)) => now
+                .checked_add_months(chrono::Months::new(years * 12))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+            (None, None) => now,
+        };
+        ObjectRetention { mode: self.mode, retain_until }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectRetention {
+    pub mode: RetentionMode,
+    pub retain_until: DateTime<Utc>,
+}
+
+impl ObjectRetention {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.retain_until > now
+    }
+}
+
+/// An advisory lease on an object key: a claim by `holder`, e.g. a worker
+/// name, that lasts until `expires` unless renewed or released. Nothing else
+/// honours it; it only coordinates clients that check it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectLease {
+    pub bucket: String,
+    pub key: String,
+    pub holder: String,
+    pub acquired: DateTime<Utc>,
+    pub expires: DateTime<Utc>,
+}
+
+impl ObjectLease {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires > now
+    }
+}
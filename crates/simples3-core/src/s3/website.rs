@@ -0,0 +1,158 @@
+use crate::s3::types::{RoutingRule, WebsiteConfiguration};
+
+/// Default status of a routing rule redirect without `HttpRedirectCode`.
+const DEFAULT_REDIRECT_CODE: u16 = 301;
+
+/// Where a website request is redirected to by a routing rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebsiteRedirect {
+    pub status: u16,
+    /// Absolute URL when the rule names a host, otherwise a path on this
+    /// server in the style of the request.
+    pub location: String,
+}
+
+/// Key served for a website request: directory-style keys (empty, or ending
+/// in `/`) get the index document appended.
+pub fn resolve_key(config: &WebsiteConfiguration, key: &str) -> String {
+    if key.is_empty() || key.ends_with('/') {
+        format!("{}{}", key, config.index_document)
+    } else {
+        key.to_string()
+    }
+}
+
+/// First routing rule matching `key`, evaluated in document order. Before the
+/// object lookup `error_code` is `None`, and only rules without an
+/// `HttpErrorCodeReturnedEquals` condition can match; after a failed lookup
+/// it is the returned status, and only rules naming that status match.
+/// Redirects within the server are path-style `/{bucket}/{key}` when `bucket`
+/// is given, and `/{key}` for virtual-host requests.
+pub fn find_redirect(
+    config: &WebsiteConfiguration,
+    bucket: Option<&str>,
+    key: &str,
+    error_code: Option<u16>,
+) -> Option<WebsiteRedirect> {
+    config
+        .routing_rules
+        .iter()
+        .find(|rule| rule_matches(rule, key, error_code))
+        .map(|rule| redirect_to(rule, bucket, key))
+}
+
+fn rule_matches(rule: &RoutingRule, key: &str, error_code: Option<u16>) -> bool {
+    let Some(ref condition) = rule.condition else {
+        return error_code.is_none();
+    };
+    if condition.http_error_code_returned_equals != error_code {
+        return false;
+    }
+    condition
+        .key_prefix_equals
+        .as_deref()
+        .is_none_or(|prefix| key.starts_with(prefix))
+}
+
+fn redirect_to(rule: &RoutingRule, bucket: Option<&str>, key: &str) -> WebsiteRedirect {
+    let redirect = &rule.redirect;
+    let new_key = match (&redirect.replace_key_with, &redirect.replace_key_prefix_with) {
+        (Some(replacement), _) => replacement.clone(),
+        (None, Some(replacement)) => {
+            let prefix = rule
+                .condition
+                .as_ref()
+                .and_then(|c| c.key_prefix_equals.as_deref())
+                .unwrap_or("");
+            format!("{}{}", replacement, &key[prefix.len()..])
+        }
+        (None, None) => key.to_string(),
+    };
+    let location = match redirect.host_name {
+        Some(ref host) => format!(
+            "{}://{}/{}",
+            redirect.protocol.as_deref().unwrap_or("http"),
+            host,
+            new_key
+        ),
+        None => match bucket {
+            Some(bucket) => format!("/{}/{}", bucket, new_key),
+            None => format!("/{}", new_key),
+        },
+    };
+    WebsiteRedirect {
+        status: redirect.http_redirect_code.unwrap_or(DEFAULT_REDIRECT_CODE),
+        location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::types::{RoutingRuleCondition, RoutingRuleRedirect};
+
+    fn config(routing_rules: Vec<RoutingRule>) -> WebsiteConfiguration {
+        WebsiteConfiguration {
+            index_document: "index.html".into(),
+            error_document: None,
+            routing_rules,
+        }
+    }
+
+    #[test]
+    fn test_resolve_key() {
+        let config = config(vec![]);
+        assert_eq!(resolve_key(&config, ""), "index.html");
+        assert_eq!(resolve_key(&config, "docs/"), "docs/index.html");
+        assert_eq!(resolve_key(&config, "docs/page.html"), "docs/page.html");
+    }
+
+    #[test]
+    fn test_prefix_redirect() {
+        let config = config(vec![RoutingRule {
+            condition: Some(RoutingRuleCondition {
+                key_prefix_equals: Some("old/".into()),
+                http_error_code_returned_equals: None,
+            }),
+            redirect: RoutingRuleRedirect {
+                replace_key_prefix_with: Some("new/".into()),
+                ..Default::default()
+            },
+        }]);
+        assert_eq!(
+            find_redirect(&config, Some("site"), "old/a.html", None),
+            Some(WebsiteRedirect { status: 301, location: "/site/new/a.html".into() })
+        );
+        assert_eq!(find_redirect(&config, Some("site"), "other/a.html", None), None);
+        // Rules without an error code condition do not apply after the lookup
+        assert_eq!(find_redirect(&config, Some("site"), "old/a.html", Some(404)), None);
+        assert_eq!(
+            find_redirect(&config, None, "old/a.html", None),
+            Some(WebsiteRedirect { status: 301, location: "/new/a.html".into() })
+        );
+    }
+
+    #[test]
+    fn test_error_code_redirect() {
+        let config = config(vec![RoutingRule {
+            condition: Some(RoutingRuleCondition {
+                key_prefix_equals: None,
+                http_error_code_returned_equals: Some(404),
+            }),
+            redirect: RoutingRuleRedirect {
+                host_name: Some("fallback.example.com".into()),
+                protocol: Some("https".into()),
+                http_redirect_code: Some(302),
+                ..Default::default()
+            },
+        }]);
+        assert_eq!(find_redirect(&config, Some("site"), "missing.html", None), None);
+        assert_eq!(
+            find_redirect(&config, Some("site"), "missing.html", Some(404)),
+            Some(WebsiteRedirect {
+                status: 302,
+                location: "https://fallback.example.com/missing.html".into()
+            })
+        );
+    }
+}
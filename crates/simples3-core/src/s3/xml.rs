@@ -1,12 +1,18 @@
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
 use quick_xml::Writer;
-use quick_xml::events::BytesText;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::io::Cursor;
 
+use crate::s3::checksum::Checksum;
 use crate::s3::types::{
-    BucketMeta, CorsConfiguration, CorsRule, LifecycleConfiguration, LifecycleRule,
-    LifecycleStatus, LifecycleTagFilter, ListObjectsV2Response, MultipartUpload, ObjectMeta,
-    PartInfo,
+    BucketLoggingConfiguration, CorsConfiguration, CorsRule, LifecycleConfiguration, LifecycleRule,
+    LifecycleStatus, LifecycleTagFilter, LifecycleTransition, ListBucketsResponse, ListEntry, ListObjectsV2Response,
+    DefaultRetention, MultipartUpload, NOTIFICATION_EVENTS, NotificationConfiguration, NotificationRule,
+    ObjectLockConfiguration, ObjectMeta, ObjectRetention, PartInfo,
+    RetentionMode, RoutingRule, RoutingRuleCondition, RoutingRuleRedirect, STANDARD_STORAGE_CLASS,
+    WebsiteConfiguration,
 };
 
 const S3_XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
@@ -15,7 +21,9 @@ fn xml_header() -> &'static str {
     "<?xml version=\"1.0\" encoding=\"UTF-8\"?>"
 }
 
-pub fn list_buckets_xml(owner_id: &str, buckets: &[BucketMeta]) -> String {
+/// `CreationDate` is written with millisecond precision and a `Z` suffix, the
+/// form AWS returns and strict clients parse.
+pub fn list_buckets_xml(owner_id: &str, owner_display_name: &str, resp: &ListBucketsResponse) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     writer
         .create_element("ListAllMyBucketsResult")
@@ -26,25 +34,33 @@ pub fn list_buckets_xml(owner_id: &str, buckets: &[BucketMeta]) -> String {
                     w.create_element("ID")
                         .write_text_content(BytesText::new(owner_id))?;
                     w.create_element("DisplayName")
-                        .write_text_content(BytesText::new(owner_id))?;
+                        .write_text_content(BytesText::new(owner_display_name))?;
                     Ok(())
                 })?;
             w.create_element("Buckets")
                 .write_inner_content(|w| {
-                    for b in buckets {
+                    for b in &resp.buckets {
                         w.create_element("Bucket")
                             .write_inner_content(|w| {
                                 w.create_element("Name")
                                     .write_text_content(BytesText::new(&b.name))?;
                                 w.create_element("CreationDate")
                                     .write_text_content(BytesText::new(
-                                        &b.creation_date.to_rfc3339(),
+                                        &b.creation_date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
                                     ))?;
                                 Ok(())
                             })?;
                     }
                     Ok(())
                 })?;
+            if let Some(ref token) = resp.continuation_token {
+                w.create_element("ContinuationToken")
+                    .write_text_content(BytesText::new(token))?;
+            }
+            if !resp.prefix.is_empty() {
+                w.create_element("Prefix")
+                    .write_text_content(BytesText::new(&resp.prefix))?;
+            }
             Ok(())
         })
         .unwrap();
@@ -52,56 +68,148 @@ pub fn list_buckets_xml(owner_id: &str, buckets: &[BucketMeta]) -> String {
     format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
 }
 
-pub fn list_objects_v2_xml(resp: &ListObjectsV2Response) -> String {
+/// Characters `encoding-type=url` leaves as they are in listings. Like S3,
+/// `/` is kept so encoded keys still read as paths.
+const LIST_KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~').remove(b'/');
+
+/// Request options that change how a listing is written.
+#[derive(Debug, Clone, Default)]
+pub struct ListFormat {
+    /// `encoding-type=url`: percent-encode keys, prefixes and delimiters and
+    /// add an `EncodingType` element.
+    pub url_encoded: bool,
+    /// `fetch-owner=true`: the owner written in every `Contents` entry.
+    pub owner: Option<ListOwner>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListOwner {
+    pub id: String,
+    pub display_name: String,
+}
+
+impl ListFormat {
+    fn encode<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        if self.url_encoded {
+            utf8_percent_encode(value, LIST_KEY_ENCODE_SET).into()
+        } else {
+            Cow::Borrowed(value)
+        }
+    }
+}
+
+pub fn list_objects_v2_xml(resp: &ListObjectsV2Response, format: &ListFormat) -> String {
+    let mut xml = list_objects_v2_xml_head(&resp.name, &resp.prefix, &resp.delimiter, resp.max_keys, format);
+    for obj in &resp.contents {
+        xml.push_str(&object_entry_xml(obj, format));
+    }
+    for prefix in &resp.common_prefixes {
+        xml.push_str(&common_prefix_xml(prefix, format));
+    }
+    xml.push_str(&list_objects_v2_xml_tail(
+        resp.key_count,
+        resp.is_truncated,
+        resp.next_continuation_token.as_deref(),
+    ));
+    xml
+}
+
+// A ListObjectsV2 result can be written in three parts, so a listing can be
+// streamed while it is scanned: the head, one fragment per entry, and a tail
+// carrying the totals only known once the scan is over.
+
+pub fn list_objects_v2_xml_head(name: &str, prefix: &str, delimiter: &str, max_keys: u32, format: &ListFormat) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     writer
-        .create_element("ListBucketResult")
-        .with_attribute(("xmlns", S3_XMLNS))
+        .write_event(Event::Start(
+            BytesStart::new("ListBucketResult").with_attributes([("xmlns", S3_XMLNS)]),
+        ))
+        .unwrap();
+    writer
+        .create_element("Name")
+        .write_text_content(BytesText::new(name))
+        .unwrap();
+    writer
+        .create_element("Prefix")
+        .write_text_content(BytesText::new(&format.encode(prefix)))
+        .unwrap();
+    if !delimiter.is_empty() {
+        writer
+            .create_element("Delimiter")
+            .write_text_content(BytesText::new(&format.encode(delimiter)))
+            .unwrap();
+    }
+    writer
+        .create_element("MaxKeys")
+        .write_text_content(BytesText::new(&max_keys.to_string()))
+        .unwrap();
+    if format.url_encoded {
+        writer
+            .create_element("EncodingType")
+            .write_text_content(BytesText::new("url"))
+            .unwrap();
+    }
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+pub fn list_entry_xml(entry: &ListEntry, format: &ListFormat) -> String {
+    match entry {
+        ListEntry::Object(obj) => object_entry_xml(obj, format),
+        ListEntry::CommonPrefix(prefix) => common_prefix_xml(prefix, format),
+    }
+}
+
+fn object_entry_xml(obj: &ObjectMeta, format: &ListFormat) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    write_object_xml(&mut writer, obj, format).unwrap();
+    String::from_utf8(writer.into_inner().into_inner()).unwrap()
+}
+
+fn common_prefix_xml(prefix: &str, format: &ListFormat) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("CommonPrefixes")
         .write_inner_content(|w| {
-            w.create_element("Name")
-                .write_text_content(BytesText::new(&resp.name))?;
             w.create_element("Prefix")
-                .write_text_content(BytesText::new(&resp.prefix))?;
-            w.create_element("MaxKeys")
-                .write_text_content(BytesText::new(&resp.max_keys.to_string()))?;
-            w.create_element("KeyCount")
-                .write_text_content(BytesText::new(&resp.key_count.to_string()))?;
-            w.create_element("IsTruncated")
-                .write_text_content(BytesText::new(&resp.is_truncated.to_string()))?;
-            if !resp.delimiter.is_empty() {
-                w.create_element("Delimiter")
-                    .write_text_content(BytesText::new(&resp.delimiter))?;
-            }
-            if let Some(ref token) = resp.next_continuation_token {
-                w.create_element("NextContinuationToken")
-                    .write_text_content(BytesText::new(token))?;
-            }
-            for obj in &resp.contents {
-                write_object_xml(w, obj)?;
-            }
-            for prefix in &resp.common_prefixes {
-                w.create_element("CommonPrefixes")
-                    .write_inner_content(|w| {
-                        w.create_element("Prefix")
-                            .write_text_content(BytesText::new(prefix))?;
-                        Ok(())
-                    })?;
-            }
+                .write_text_content(BytesText::new(&format.encode(prefix)))?;
             Ok(())
         })
         .unwrap();
-    let bytes = writer.into_inner().into_inner();
-    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+    String::from_utf8(writer.into_inner().into_inner()).unwrap()
+}
+
+pub fn list_objects_v2_xml_tail(key_count: u32, is_truncated: bool, next_continuation_token: Option<&str>) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("KeyCount")
+        .write_text_content(BytesText::new(&key_count.to_string()))
+        .unwrap();
+    writer
+        .create_element("IsTruncated")
+        .write_text_content(BytesText::new(&is_truncated.to_string()))
+        .unwrap();
+    if let Some(token) = next_continuation_token {
+        writer
+            .create_element("NextContinuationToken")
+            .write_text_content(BytesText::new(token))
+            .unwrap();
+    }
+    writer
+        .write_event(Event::End(BytesEnd::new("ListBucketResult")))
+        .unwrap();
+    String::from_utf8(writer.into_inner().into_inner()).unwrap()
 }
 
 fn write_object_xml(
     w: &mut Writer<Cursor<Vec<u8>>>,
     obj: &ObjectMeta,
+    format: &ListFormat,
 ) -> std::io::Result<()> {
     w.create_element("Contents")
         .write_inner_content(|w| {
             w.create_element("Key")
-                .write_text_content(BytesText::new(&obj.key))?;
+                .write_text_content(BytesText::new(&format.encode(&obj.key)))?;
             w.create_element("LastModified")
                 .write_text_content(BytesText::new(&obj.last_modified.to_rfc3339()))?;
             w.create_element("ETag")
@@ -109,7 +217,18 @@ fn write_object_xml(
             w.create_element("Size")
                 .write_text_content(BytesText::new(&obj.size.to_string()))?;
             w.create_element("StorageClass")
-                .write_text_content(BytesText::new("STANDARD"))?;
+                .write_text_content(BytesText::new(
+                    obj.storage_class.as_deref().unwrap_or(STANDARD_STORAGE_CLASS),
+                ))?;
+            if let Some(owner) = &format.owner {
+                w.create_element("Owner").write_inner_content(|w| {
+                    w.create_element("ID")
+                        .write_text_content(BytesText::new(&owner.id))?;
+                    w.create_element("DisplayName")
+                        .write_text_content(BytesText::new(&owner.display_name))?;
+                    Ok(())
+                })?;
+            }
             Ok(())
         })?;
     Ok(())
@@ -139,6 +258,7 @@ pub fn complete_multipart_upload_xml(
     key: &str,
     etag: &str,
     location: &str,
+    checksum: Option<&Checksum>,
 ) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     writer
@@ -153,6 +273,10 @@ pub fn complete_multipart_upload_xml(
                 .write_text_content(BytesText::new(key))?;
             w.create_element("ETag")
                 .write_text_content(BytesText::new(&format!("\"{}\"", etag)))?;
+            if let Some(checksum) = checksum {
+                w.create_element(checksum.algorithm.xml_element())
+                    .write_text_content(BytesText::new(&checksum.value))?;
+            }
             Ok(())
         })
         .unwrap();
@@ -196,6 +320,10 @@ fn write_part_xml(
                 .write_text_content(BytesText::new(&part.size.to_string()))?;
             w.create_element("LastModified")
                 .write_text_content(BytesText::new(&part.last_modified.to_rfc3339()))?;
+            if let Some(checksum) = &part.checksum {
+                w.create_element(checksum.algorithm.xml_element())
+                    .write_text_content(BytesText::new(&checksum.value))?;
+            }
             Ok(())
         })?;
     Ok(())
@@ -284,6 +412,11 @@ pub fn delete_objects_result_xml(
     format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
 }
 
+/// Bucket ACLs share the object ACL shape: AllUsers READ when anonymous reads are allowed.
+pub fn get_bucket_acl_xml(anonymous_read: bool) -> String {
+    get_object_acl_xml(anonymous_read)
+}
+
 pub fn get_object_acl_xml(public: bool) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     writer
@@ -303,11 +436,7 @@ pub fn get_object_acl_xml(public: bool) -> String {
                     // Owner always has FULL_CONTROL
                     write_acl_grant_canonical(w, "simples3", "simples3", "FULL_CONTROL")?;
                     if public {
-                        write_acl_grant_group(
-                            w,
-                            "http://acs.amazonaws.com/groups/global/AllUsers",
-                            "READ",
-                        )?;
+                        write_acl_grant_group(w, ALL_USERS_URI, "READ")?;
                     }
                     Ok(())
                 })?;
@@ -387,19 +516,60 @@ pub fn lifecycle_configuration_xml(config: &LifecycleConfiguration) -> String {
                         };
                         w.create_element("Status")
                             .write_text_content(BytesText::new(status_str))?;
-                        w.create_element("Expiration")
-                            .write_inner_content(|w| {
-                                if let Some(ref date) = rule.expiration_date {
-                                    w.create_element("Date")
-                                        .write_text_content(BytesText::new(date))?;
-                                } else {
-                                    w.create_element("Days")
-                                        .write_text_content(BytesText::new(
-                                            &rule.expiration_days.to_string(),
-                                        ))?;
-                                }
-                                Ok(())
-                            })?;
+                        for transition in &rule.transitions {
+                            w.create_element("Transition")
+                                .write_inner_content(|w| {
+                                    if let Some(ref date) = transition.date {
+                                        w.create_element("Date")
+                                            .write_text_content(BytesText::new(date))?;
+                                    } else if let Some(days) = transition.days {
+                                        w.create_element("Days")
+                                            .write_text_content(BytesText::new(&days.to_string()))?;
+                                    }
+                                    w.create_element("StorageClass")
+                                        .write_text_content(BytesText::new(&transition.storage_class))?;
+                                    Ok(())
+                                })?;
+                        }
+                        if let Some(days) = rule.abort_incomplete_multipart_days {
+                            w.create_element("AbortIncompleteMultipartUpload")
+                                .write_inner_content(|w| {
+                                    w.create_element("DaysAfterInitiation")
+                                        .write_text_content(BytesText::new(&days.to_string()))?;
+                                    Ok(())
+                                })?;
+                        }
+                        if let Some(days) = rule.noncurrent_version_expiration_days {
+                            w.create_element("NoncurrentVersionExpiration")
+                                .write_inner_content(|w| {
+                                    w.create_element("NoncurrentDays")
+                                        .write_text_content(BytesText::new(&days.to_string()))?;
+                                    Ok(())
+                                })?;
+                        }
+                        if rule.expired_object_delete_marker && !rule.has_expiration() {
+                            w.create_element("Expiration")
+                                .write_inner_content(|w| {
+                                    w.create_element("ExpiredObjectDeleteMarker")
+                                        .write_text_content(BytesText::new("true"))?;
+                                    Ok(())
+                                })?;
+                        }
+                        if rule.has_expiration() {
+                            w.create_element("Expiration")
+                                .write_inner_content(|w| {
+                                    if let Some(ref date) = rule.expiration_date {
+                                        w.create_element("Date")
+                                            .write_text_content(BytesText::new(date))?;
+                                    } else {
+                                        w.create_element("Days")
+                                            .write_text_content(BytesText::new(
+                                                &rule.expiration_days.to_string(),
+                                            ))?;
+                                    }
+                                    Ok(())
+                                })?;
+                        }
                         Ok(())
                     })?;
             }
@@ -444,6 +614,14 @@ pub fn parse_lifecycle_configuration_xml(
     let mut in_prefix = false;
     let mut in_status = false;
     let mut in_expiration = false;
+    let mut has_expiration = false;
+    let mut in_transition = false;
+    let mut in_storage_class = false;
+    let mut in_abort = false;
+    let mut in_days_after_initiation = false;
+    let mut in_noncurrent = false;
+    let mut in_noncurrent_days = false;
+    let mut in_delete_marker = false;
     let mut in_days = false;
     let mut in_date = false;
     let mut in_tag = false;
@@ -458,6 +636,13 @@ pub fn parse_lifecycle_configuration_xml(
     let mut current_tags: Vec<LifecycleTagFilter> = Vec::new();
     let mut current_tag_key = String::new();
     let mut current_tag_value = String::new();
+    let mut current_transitions: Vec<LifecycleTransition> = Vec::new();
+    let mut transition_days = String::new();
+    let mut transition_date = String::new();
+    let mut transition_class = String::new();
+    let mut current_abort_days = String::new();
+    let mut current_noncurrent_days = String::new();
+    let mut current_delete_marker = String::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -470,6 +655,11 @@ pub fn parse_lifecycle_configuration_xml(
                     current_days.clear();
                     current_date.clear();
                     current_tags.clear();
+                    current_transitions.clear();
+                    current_abort_days.clear();
+                    current_noncurrent_days.clear();
+                    current_delete_marker.clear();
+                    has_expiration = false;
                 }
                 b"ID" if in_rule => in_id = true,
                 b"Filter" if in_rule => in_filter = true,
@@ -483,15 +673,30 @@ pub fn parse_lifecycle_configuration_xml(
                 b"Key" if in_tag => in_tag_key = true,
                 b"Value" if in_tag => in_tag_value = true,
                 b"Status" if in_rule => in_status = true,
-                b"Expiration" if in_rule => in_expiration = true,
-                b"Days" if in_expiration => in_days = true,
-                b"Date" if in_expiration => in_date = true,
+                b"Expiration" if in_rule => {
+                    in_expiration = true;
+                    has_expiration = true;
+                }
+                b"Transition" if in_rule => {
+                    in_transition = true;
+                    transition_days.clear();
+                    transition_date.clear();
+                    transition_class.clear();
+                }
+                b"StorageClass" if in_transition => in_storage_class = true,
+                b"AbortIncompleteMultipartUpload" if in_rule => in_abort = true,
+                b"DaysAfterInitiation" if in_abort => in_days_after_initiation = true,
+                b"NoncurrentVersionExpiration" if in_rule => in_noncurrent = true,
+                b"NoncurrentDays" if in_noncurrent => in_noncurrent_days = true,
+                b"ExpiredObjectDeleteMarker" if in_expiration => in_delete_marker = true,
+                b"Days" if in_expiration || in_transition => in_days = true,
+                b"Date" if in_expiration || in_transition => in_date = true,
                 _ => {}
             },
             Ok(Event::Text(e)) => {
                 let text = e
                     .unescape()
-                    .map_err(|e| crate::S3Error::InvalidArgument(e.to_string()))?
+                    .map_err(|_| crate::S3Error::MalformedXML)?
                     .into_owned();
                 if in_tag_key {
                     current_tag_key = text;
@@ -503,6 +708,18 @@ pub fn parse_lifecycle_configuration_xml(
                     current_prefix = text;
                 } else if in_status {
                     current_status = text;
+                } else if in_storage_class {
+                    transition_class = text;
+                } else if in_days_after_initiation {
+                    current_abort_days = text;
+                } else if in_noncurrent_days {
+                    current_noncurrent_days = text;
+                } else if in_delete_marker {
+                    current_delete_marker = text;
+                } else if in_days && in_transition {
+                    transition_days = text;
+                } else if in_date && in_transition {
+                    transition_date = text;
                 } else if in_days {
                     current_days = text;
                 } else if in_date {
@@ -528,7 +745,62 @@ pub fn parse_lifecycle_configuration_xml(
                             "Expiration must specify either Days or Date, not both".to_string(),
                         ));
                     }
-                    let (days, date) = if has_date {
+                    let abort_days = if current_abort_days.is_empty() {
+                        None
+                    } else {
+                        match current_abort_days.parse::<u32>() {
+                            Ok(d) if d > 0 => Some(d),
+                            _ => {
+                                return Err(crate::S3Error::InvalidArgument(
+                                    "DaysAfterInitiation must be a positive integer".to_string(),
+                                ));
+                            }
+                        }
+                    };
+                    if abort_days.is_some() && !current_tags.is_empty() {
+                        return Err(crate::S3Error::InvalidArgument(
+                            "AbortIncompleteMultipartUpload cannot be specified with tag filters".to_string(),
+                        ));
+                    }
+                    let noncurrent_days = if current_noncurrent_days.is_empty() {
+                        None
+                    } else {
+                        match current_noncurrent_days.parse::<u32>() {
+                            Ok(d) if d > 0 => Some(d),
+                            _ => {
+                                return Err(crate::S3Error::InvalidArgument(
+                                    "NoncurrentDays must be a positive integer".to_string(),
+                                ));
+                            }
+                        }
+                    };
+                    let delete_marker = match current_delete_marker.as_str() {
+                        "" | "false" => false,
+                        "true" => true,
+                        _ => {
+                            return Err(crate::S3Error::InvalidArgument(
+                                "ExpiredObjectDeleteMarker must be true or false".to_string(),
+                            ));
+                        }
+                    };
+                    if !current_delete_marker.is_empty() && (has_days || has_date) {
+                        return Err(crate::S3Error::InvalidArgument(
+                            "ExpiredObjectDeleteMarker cannot be specified with Days or Date".to_string(),
+                        ));
+                    }
+                    if delete_marker && !current_tags.is_empty() {
+                        return Err(crate::S3Error::InvalidArgument(
+                            "ExpiredObjectDeleteMarker cannot be specified with tag filters".to_string(),
+                        ));
+                    }
+                    let (days, date) = if !current_delete_marker.is_empty()
+                        || (!has_expiration
+                            && (!current_transitions.is_empty()
+                                || abort_days.is_some()
+                                || noncurrent_days.is_some()))
+                    {
+                        (0, None)
+                    } else if has_date {
                         // Validate date parses as ISO 8601
                         chrono::DateTime::parse_from_rfc3339(&current_date).map_err(|_| {
                             crate::S3Error::InvalidArgument(
@@ -556,6 +828,10 @@ pub fn parse_lifecycle_configuration_xml(
                         expiration_days: days,
                         expiration_date: date,
                         tags: current_tags.clone(),
+                        transitions: std::mem::take(&mut current_transitions),
+                        abort_incomplete_multipart_days: abort_days,
+                        noncurrent_version_expiration_days: noncurrent_days,
+                        expired_object_delete_marker: delete_marker,
                     });
                     in_rule = false;
                 }
@@ -574,14 +850,26 @@ pub fn parse_lifecycle_configuration_xml(
                 b"Value" if in_tag => in_tag_value = false,
                 b"Status" => in_status = false,
                 b"Expiration" => in_expiration = false,
+                b"Transition" if in_transition => {
+                    current_transitions.push(parse_lifecycle_transition(
+                        &transition_days,
+                        &transition_date,
+                        &transition_class,
+                    )?);
+                    in_transition = false;
+                }
+                b"StorageClass" => in_storage_class = false,
+                b"AbortIncompleteMultipartUpload" => in_abort = false,
+                b"DaysAfterInitiation" => in_days_after_initiation = false,
+                b"NoncurrentVersionExpiration" => in_noncurrent = false,
+                b"NoncurrentDays" => in_noncurrent_days = false,
+                b"ExpiredObjectDeleteMarker" => in_delete_marker = false,
                 b"Days" => in_days = false,
                 b"Date" => in_date = false,
                 _ => {}
             },
             Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(crate::S3Error::InvalidArgument(e.to_string()));
-            }
+            Err(_) => return Err(crate::S3Error::MalformedXML),
             _ => {}
         }
         buf.clear();
@@ -590,6 +878,44 @@ pub fn parse_lifecycle_configuration_xml(
     Ok(LifecycleConfiguration { rules })
 }
 
+fn parse_lifecycle_transition(
+    days: &str,
+    date: &str,
+    storage_class: &str,
+) -> Result<LifecycleTransition, crate::S3Error> {
+    if storage_class.is_empty() || storage_class == STANDARD_STORAGE_CLASS {
+        return Err(crate::S3Error::InvalidArgument(
+            "Transition must name a storage class other than STANDARD".to_string(),
+        ));
+    }
+    let (days, date) = match (days.is_empty(), date.is_empty()) {
+        (false, true) => {
+            let d: u32 = days.parse().map_err(|_| {
+                crate::S3Error::InvalidArgument("Invalid transition days".to_string())
+            })?;
+            (Some(d), None)
+        }
+        (true, false) => {
+            chrono::DateTime::parse_from_rfc3339(date).map_err(|_| {
+                crate::S3Error::InvalidArgument(
+                    "Invalid transition date format (expected ISO 8601)".to_string(),
+                )
+            })?;
+            (None, Some(date.to_string()))
+        }
+        _ => {
+            return Err(crate::S3Error::InvalidArgument(
+                "Transition must specify either Days or Date".to_string(),
+            ));
+        }
+    };
+    Ok(LifecycleTransition {
+        days,
+        date,
+        storage_class: storage_class.to_string(),
+    })
+}
+
 pub fn cors_configuration_xml(config: &CorsConfiguration) -> String {
     let mut writer = Writer::new(Cursor::new(Vec::new()));
     writer
@@ -682,7 +1008,7 @@ pub fn parse_cors_configuration_xml(
             Ok(Event::Text(e)) => {
                 let text = e
                     .unescape()
-                    .map_err(|e| crate::S3Error::InvalidArgument(e.to_string()))?
+                    .map_err(|_| crate::S3Error::MalformedXML)?
                     .into_owned();
                 if in_id {
                     current_id = Some(text);
@@ -729,9 +1055,7 @@ pub fn parse_cors_configuration_xml(
                 _ => {}
             },
             Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(crate::S3Error::InvalidArgument(e.to_string()));
-            }
+            Err(_) => return Err(crate::S3Error::MalformedXML),
             _ => {}
         }
         buf.clear();
@@ -740,6 +1064,81 @@ pub fn parse_cors_configuration_xml(
     Ok(CorsConfiguration { rules })
 }
 
+const ALL_USERS_URI: &str = "http://acs.amazonaws.com/groups/global/AllUsers";
+
+/// Parse an `AccessControlPolicy` body into the only ACL states SimpleS3
+/// supports: `true` when AllUsers is granted READ, `false` for owner-only.
+/// Grants to other groups or other permissions for AllUsers are rejected.
+pub fn parse_access_control_policy_xml(data: &[u8]) -> Result<bool, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut seen_policy = false;
+    let mut in_grant = false;
+    let mut in_uri = false;
+    let mut in_permission = false;
+    let mut current_uri: Option<String> = None;
+    let mut current_permission = String::new();
+    let mut public = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match e.name().as_ref() {
+                b"AccessControlPolicy" => seen_policy = true,
+                b"Grant" => {
+                    in_grant = true;
+                    current_uri = None;
+                    current_permission.clear();
+                }
+                b"URI" if in_grant => in_uri = true,
+                b"Permission" if in_grant => in_permission = true,
+                _ => {}
+            },
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                if in_uri {
+                    current_uri = Some(text);
+                } else if in_permission {
+                    current_permission = text;
+                }
+            }
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"Grant" => {
+                    if let Some(uri) = current_uri.take() {
+                        if uri != ALL_USERS_URI || current_permission != "READ" {
+                            return Err(crate::S3Error::InvalidArgument(format!(
+                                "Unsupported grant of {} to {}",
+                                current_permission, uri
+                            )));
+                        }
+                        public = true;
+                    }
+                    in_grant = false;
+                }
+                b"URI" => in_uri = false,
+                b"Permission" => in_permission = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !seen_policy {
+        return Err(crate::S3Error::MalformedXML);
+    }
+    Ok(public)
+}
+
 fn write_acl_grant_group(
     w: &mut Writer<Cursor<Vec<u8>>>,
     uri: &str,
@@ -762,84 +1161,928 @@ fn write_acl_grant_group(
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Utc;
+pub fn object_lock_configuration_xml(config: &ObjectLockConfiguration) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("ObjectLockConfiguration")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            w.create_element("ObjectLockEnabled")
+                .write_text_content(BytesText::new("Enabled"))?;
+            if let Some(ref retention) = config.default_retention {
+                w.create_element("Rule")
+                    .write_inner_content(|w| {
+                        w.create_element("DefaultRetention")
+                            .write_inner_content(|w| {
+                                w.create_element("Mode")
+                                    .write_text_content(BytesText::new(retention.mode.as_str()))?;
+                                if let Some(days) = retention.days {
+                                    w.create_element("Days")
+                                        .write_text_content(BytesText::new(&days.to_string()))?;
+                                }
+                                if let Some(years) = retention.years {
+                                    w.create_element("Years")
+                                        .write_text_content(BytesText::new(&years.to_string()))?;
+                                }
+                                Ok(())
+                            })?;
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
 
-    #[test]
-    fn test_list_buckets_xml() {
-        let buckets = vec![BucketMeta {
-            name: "test-bucket".into(),
-            creation_date: Utc::now(),
-            anonymous_read: false,
-            anonymous_list_public: false,
-        }];
-        let xml = list_buckets_xml("owner", &buckets);
-        assert!(xml.contains("xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\""));
-        assert!(xml.contains("<Name>test-bucket</Name>"));
-        assert!(xml.contains("<ListAllMyBucketsResult"));
-    }
+pub fn parse_object_lock_configuration_xml(data: &[u8]) -> Result<ObjectLockConfiguration, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
 
-    #[test]
-    fn test_list_objects_v2_xml() {
-        let resp = ListObjectsV2Response {
-            name: "mybucket".into(),
-            prefix: "".into(),
-            delimiter: "/".into(),
-            max_keys: 1000,
-            is_truncated: false,
-            contents: vec![ObjectMeta {
-                bucket: "mybucket".into(),
-                key: "file.txt".into(),
-                size: 100,
-                etag: "abc123".into(),
-                content_type: "text/plain".into(),
-                last_modified: Utc::now(),
-                public: false,
-            }],
-            common_prefixes: vec!["photos/".into()],
-            next_continuation_token: None,
-            key_count: 1,
-        };
-        let xml = list_objects_v2_xml(&resp);
-        assert!(xml.contains("<ListBucketResult"));
-        assert!(xml.contains("<Key>file.txt</Key>"));
-        assert!(xml.contains("<Prefix>photos/</Prefix>"));
-        assert!(xml.contains("<Delimiter>/</Delimiter>"));
-    }
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
 
-    #[test]
-    fn test_error_xml() {
-        let err = crate::S3Error::NoSuchKey;
-        let xml = err.to_xml();
-        assert!(xml.contains("<Code>NoSuchKey</Code>"));
-        assert!(xml.contains("<Message>"));
-    }
+    let mut current = String::new();
+    let mut enabled = String::new();
+    let mut in_default_retention = false;
+    let mut seen_default_retention = false;
+    let mut mode = String::new();
+    let mut days = String::new();
+    let mut years = String::new();
 
-    #[test]
-    fn test_get_tagging_xml() {
-        let mut tags = HashMap::new();
-        tags.insert("env".into(), "prod".into());
-        let xml = get_tagging_xml(&tags);
-        assert!(xml.contains("<Tagging"));
-        assert!(xml.contains("<TagSet>"));
-        assert!(xml.contains("<Key>env</Key>"));
-        assert!(xml.contains("<Value>prod</Value>"));
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                current = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                if current == "DefaultRetention" {
+                    in_default_retention = true;
+                    seen_default_retention = true;
+                }
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                match current.as_str() {
+                    "ObjectLockEnabled" => enabled = text,
+                    "Mode" if in_default_retention => mode = text,
+                    "Days" if in_default_retention => days = text,
+                    "Years" if in_default_retention => years = text,
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"DefaultRetention" {
+                    in_default_retention = false;
+                }
+                current.clear();
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
     }
 
-    #[test]
-    fn test_copy_object_result_xml() {
-        let xml = copy_object_result_xml("abc123", &Utc::now());
-        assert!(xml.contains("<CopyObjectResult"));
-        assert!(xml.contains("<ETag>"));
-        assert!(xml.contains("abc123"));
-        assert!(xml.contains("<LastModified>"));
+    if enabled != "Enabled" {
+        return Err(crate::S3Error::MalformedXML);
     }
+    if !seen_default_retention {
+        return Ok(ObjectLockConfiguration { default_retention: None });
+    }
+    let mode = RetentionMode::parse(&mode)
+        .ok_or_else(|| crate::S3Error::InvalidArgument("Default retention mode must be GOVERNANCE or COMPLIANCE".into()))?;
+    let parse_period = |value: &str| -> Result<Option<u32>, crate::S3Error> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        match value.parse::<u32>() {
+            Ok(n) if n > 0 => Ok(Some(n)),
+            _ => Err(crate::S3Error::InvalidArgument("Default retention period must be a positive integer".into())),
+        }
+    };
+    let days = parse_period(&days)?;
+    let years = parse_period(&years)?;
+    if days.is_some() == years.is_some() {
+        return Err(crate::S3Error::MalformedXML);
+    }
+    Ok(ObjectLockConfiguration {
+        default_retention: Some(DefaultRetention { mode, days, years }),
+    })
+}
 
-    #[test]
-    fn test_delete_objects_result_xml() {
-        let deleted = vec!["key1".to_string(), "key2".to_string()];
+pub fn retention_xml(retention: &ObjectRetention) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("Retention")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            w.create_element("Mode")
+                .write_text_content(BytesText::new(retention.mode.as_str()))?;
+            w.create_element("RetainUntilDate")
+                .write_text_content(BytesText::new(&format_retain_until(&retention.retain_until)))?;
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+/// Retain-until dates as S3 formats them, e.g. `2030-01-01T00:00:00.000Z`.
+pub fn format_retain_until(date: &chrono::DateTime<chrono::Utc>) -> String {
+    date.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+pub fn parse_retention_xml(data: &[u8]) -> Result<ObjectRetention, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut current = String::new();
+    let mut mode = String::new();
+    let mut retain_until = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => current = String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                match current.as_str() {
+                    "Mode" => mode = text,
+                    "RetainUntilDate" => retain_until = text,
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => current.clear(),
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let mode = RetentionMode::parse(&mode).ok_or(crate::S3Error::MalformedXML)?;
+    let retain_until = parse_retain_until(&retain_until)?;
+    Ok(ObjectRetention { mode, retain_until })
+}
+
+pub fn legal_hold_xml(on: bool) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("LegalHold")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            w.create_element("Status")
+                .write_text_content(BytesText::new(if on { "ON" } else { "OFF" }))?;
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+/// Parse a `<LegalHold><Status>ON|OFF</Status></LegalHold>` body.
+pub fn parse_legal_hold_xml(data: &[u8]) -> Result<bool, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut in_status = false;
+    let mut status = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => in_status = e.name().as_ref() == b"Status",
+            Ok(Event::Text(e)) if in_status => {
+                status = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+            }
+            Ok(Event::End(_)) => in_status = false,
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    match status.as_str() {
+        "ON" => Ok(true),
+        "OFF" => Ok(false),
+        _ => Err(crate::S3Error::MalformedXML),
+    }
+}
+
+/// Number of days from a RestoreObject `RestoreRequest` body. Retrieval
+/// tiers are accepted and ignored: restores complete immediately.
+pub fn parse_restore_request_xml(data: &[u8]) -> Result<u32, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut in_days = false;
+    let mut days = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => in_days = e.name().as_ref() == b"Days",
+            Ok(Event::Text(e)) if in_days => {
+                days = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+            }
+            Ok(Event::End(_)) => in_days = false,
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    match days.parse::<u32>() {
+        Ok(d) if d > 0 => Ok(d),
+        _ => Err(crate::S3Error::InvalidArgument(
+            "RestoreRequest Days must be a positive integer".to_string(),
+        )),
+    }
+}
+
+pub fn website_configuration_xml(config: &WebsiteConfiguration) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("WebsiteConfiguration")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            w.create_element("IndexDocument")
+                .write_inner_content(|w| {
+                    w.create_element("Suffix")
+                        .write_text_content(BytesText::new(&config.index_document))?;
+                    Ok(())
+                })?;
+            if let Some(ref key) = config.error_document {
+                w.create_element("ErrorDocument")
+                    .write_inner_content(|w| {
+                        w.create_element("Key")
+                            .write_text_content(BytesText::new(key))?;
+                        Ok(())
+                    })?;
+            }
+            if !config.routing_rules.is_empty() {
+                w.create_element("RoutingRules")
+                    .write_inner_content(|w| {
+                        for rule in &config.routing_rules {
+                            write_routing_rule(w, rule)?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+fn write_routing_rule<W: std::io::Write>(w: &mut Writer<W>, rule: &RoutingRule) -> std::io::Result<()> {
+    w.create_element("RoutingRule")
+        .write_inner_content(|w| {
+            if let Some(ref condition) = rule.condition {
+                w.create_element("Condition")
+                    .write_inner_content(|w| {
+                        if let Some(ref code) = condition.http_error_code_returned_equals {
+                            w.create_element("HttpErrorCodeReturnedEquals")
+                                .write_text_content(BytesText::new(&code.to_string()))?;
+                        }
+                        if let Some(ref prefix) = condition.key_prefix_equals {
+                            w.create_element("KeyPrefixEquals")
+                                .write_text_content(BytesText::new(prefix))?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            let redirect = &rule.redirect;
+            w.create_element("Redirect")
+                .write_inner_content(|w| {
+                    let fields = [
+                        ("HostName", redirect.host_name.clone()),
+                        ("HttpRedirectCode", redirect.http_redirect_code.map(|c| c.to_string())),
+                        ("Protocol", redirect.protocol.clone()),
+                        ("ReplaceKeyPrefixWith", redirect.replace_key_prefix_with.clone()),
+                        ("ReplaceKeyWith", redirect.replace_key_with.clone()),
+                    ];
+                    for (name, value) in fields {
+                        if let Some(value) = value {
+                            w.create_element(name)
+                                .write_text_content(BytesText::new(&value))?;
+                        }
+                    }
+                    Ok(())
+                })?;
+            Ok(())
+        })?;
+    Ok(())
+}
+
+/// Parse a `<WebsiteConfiguration>` body. `IndexDocument/Suffix` is required
+/// and must be a plain file name; `ErrorDocument/Key` and `RoutingRules` are
+/// optional.
+pub fn parse_website_configuration_xml(data: &[u8]) -> Result<WebsiteConfiguration, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    // Element names from the root down to the current element
+    let mut path: Vec<Vec<u8>> = Vec::new();
+
+    let mut suffix: Option<String> = None;
+    let mut error_key: Option<String> = None;
+    let mut routing_rules = Vec::new();
+    let mut current_condition: Option<RoutingRuleCondition> = None;
+    let mut current_redirect: Option<RoutingRuleRedirect> = None;
+
+    let parse_code = |text: &str| -> Result<u16, crate::S3Error> {
+        text.parse().map_err(|_| crate::S3Error::MalformedXML)
+    };
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                match e.name().as_ref() {
+                    b"RoutingRule" => {
+                        current_condition = None;
+                        current_redirect = None;
+                    }
+                    b"Condition" => current_condition = Some(RoutingRuleCondition::default()),
+                    b"Redirect" => current_redirect = Some(RoutingRuleRedirect::default()),
+                    _ => {}
+                }
+                path.push(e.name().as_ref().to_vec());
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                let parent = path.len().checked_sub(2).map(|i| path[i].as_slice());
+                let name = path.last().map(Vec::as_slice);
+                match (parent, name) {
+                    (Some(b"IndexDocument"), Some(b"Suffix")) => suffix = Some(text),
+                    (Some(b"ErrorDocument"), Some(b"Key")) => error_key = Some(text),
+                    (Some(b"Condition"), Some(field)) => {
+                        let condition = current_condition.as_mut().ok_or(crate::S3Error::MalformedXML)?;
+                        match field {
+                            b"KeyPrefixEquals" => condition.key_prefix_equals = Some(text),
+                            b"HttpErrorCodeReturnedEquals" => {
+                                condition.http_error_code_returned_equals = Some(parse_code(&text)?)
+                            }
+                            _ => {}
+                        }
+                    }
+                    (Some(b"Redirect"), Some(field)) => {
+                        let redirect = current_redirect.as_mut().ok_or(crate::S3Error::MalformedXML)?;
+                        match field {
+                            b"HostName" => redirect.host_name = Some(text),
+                            b"Protocol" => redirect.protocol = Some(text),
+                            b"ReplaceKeyPrefixWith" => redirect.replace_key_prefix_with = Some(text),
+                            b"ReplaceKeyWith" => redirect.replace_key_with = Some(text),
+                            b"HttpRedirectCode" => redirect.http_redirect_code = Some(parse_code(&text)?),
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                if e.name().as_ref() == b"RoutingRule" {
+                    let redirect = current_redirect.take().ok_or_else(|| {
+                        crate::S3Error::InvalidArgument("RoutingRule must have a Redirect".to_string())
+                    })?;
+                    routing_rules.push(RoutingRule {
+                        condition: current_condition.take(),
+                        redirect,
+                    });
+                }
+                path.pop();
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let index_document = suffix.ok_or(crate::S3Error::MalformedXML)?;
+    if index_document.is_empty() || index_document.contains('/') {
+        return Err(crate::S3Error::InvalidArgument(
+            "The IndexDocument Suffix must not be empty and must not include a slash character".to_string(),
+        ));
+    }
+    if error_key.as_deref() == Some("") {
+        return Err(crate::S3Error::InvalidArgument(
+            "The ErrorDocument Key must not be empty".to_string(),
+        ));
+    }
+    if routing_rules.len() > MAX_ROUTING_RULES {
+        return Err(crate::S3Error::InvalidArgument(format!(
+            "At most {} routing rules are allowed",
+            MAX_ROUTING_RULES
+        )));
+    }
+    for rule in &routing_rules {
+        validate_routing_rule(rule)?;
+    }
+
+    Ok(WebsiteConfiguration {
+        index_document,
+        error_document: error_key,
+        routing_rules,
+    })
+}
+
+/// Maximum routing rules in a website configuration, as enforced by S3.
+const MAX_ROUTING_RULES: usize = 50;
+
+fn validate_routing_rule(rule: &RoutingRule) -> Result<(), crate::S3Error> {
+    let invalid = |msg: &str| Err(crate::S3Error::InvalidArgument(msg.to_string()));
+    let redirect = &rule.redirect;
+    if redirect == &RoutingRuleRedirect::default() {
+        return invalid("Redirect must specify at least one of HostName, HttpRedirectCode, Protocol, ReplaceKeyPrefixWith or ReplaceKeyWith");
+    }
+    if redirect.replace_key_prefix_with.is_some() && redirect.replace_key_with.is_some() {
+        return invalid("ReplaceKeyPrefixWith and ReplaceKeyWith cannot both be specified");
+    }
+    if let Some(ref protocol) = redirect.protocol
+        && protocol != "http"
+        && protocol != "https"
+    {
+        return invalid("Protocol must be http or https");
+    }
+    if let Some(code) = redirect.http_redirect_code
+        && !(300..400).contains(&code)
+    {
+        return invalid("HttpRedirectCode must be a 3XX status code");
+    }
+    if let Some(ref condition) = rule.condition {
+        if condition.key_prefix_equals.is_none() && condition.http_error_code_returned_equals.is_none() {
+            return invalid("Condition must specify KeyPrefixEquals or HttpErrorCodeReturnedEquals");
+        }
+        if let Some(code) = condition.http_error_code_returned_equals
+            && !(400..600).contains(&code)
+        {
+            return invalid("HttpErrorCodeReturnedEquals must be a 4XX or 5XX status code");
+        }
+    }
+    Ok(())
+}
+
+/// `BucketLoggingStatus` document; empty when logging is disabled.
+pub fn bucket_logging_xml(config: Option<&BucketLoggingConfiguration>) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("BucketLoggingStatus")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            if let Some(config) = config {
+                w.create_element("LoggingEnabled")
+                    .write_inner_content(|w| {
+                        w.create_element("TargetBucket")
+                            .write_text_content(BytesText::new(&config.target_bucket))?;
+                        w.create_element("TargetPrefix")
+                            .write_text_content(BytesText::new(&config.target_prefix))?;
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+/// Parse a `BucketLoggingStatus` body. Without `LoggingEnabled` it disables
+/// logging and `None` is returned.
+pub fn parse_bucket_logging_xml(data: &[u8]) -> Result<Option<BucketLoggingConfiguration>, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut enabled = false;
+    let mut in_target_bucket = false;
+    let mut in_target_prefix = false;
+    let mut target_bucket: Option<String> = None;
+    let mut target_prefix = String::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match e.name().as_ref() {
+                b"LoggingEnabled" => enabled = true,
+                b"TargetBucket" if enabled => in_target_bucket = true,
+                b"TargetPrefix" if enabled => in_target_prefix = true,
+                _ => {}
+            },
+            Ok(Event::Empty(e)) if e.name().as_ref() == b"LoggingEnabled" => enabled = true,
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                if in_target_bucket {
+                    target_bucket = Some(text);
+                } else if in_target_prefix {
+                    target_prefix = text;
+                }
+            }
+            Ok(Event::End(e)) => match e.name().as_ref() {
+                b"TargetBucket" => in_target_bucket = false,
+                b"TargetPrefix" => in_target_prefix = false,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if !enabled {
+        return Ok(None);
+    }
+    let target_bucket = target_bucket.ok_or(crate::S3Error::MalformedXML)?;
+    Ok(Some(BucketLoggingConfiguration {
+        target_bucket,
+        target_prefix,
+    }))
+}
+
+/// Rules are written back as `QueueConfiguration` elements, whichever
+/// element they were set with.
+pub fn notification_configuration_xml(config: &NotificationConfiguration) -> String {
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    writer
+        .create_element("NotificationConfiguration")
+        .with_attribute(("xmlns", S3_XMLNS))
+        .write_inner_content(|w| {
+            for rule in &config.rules {
+                w.create_element("QueueConfiguration")
+                    .write_inner_content(|w| {
+                        w.create_element("Id")
+                            .write_text_content(BytesText::new(&rule.id))?;
+                        w.create_element("Queue")
+                            .write_text_content(BytesText::new(&rule.endpoint))?;
+                        for event in &rule.events {
+                            w.create_element("Event")
+                                .write_text_content(BytesText::new(event))?;
+                        }
+                        if rule.prefix.is_some() || rule.suffix.is_some() {
+                            w.create_element("Filter").write_inner_content(|w| {
+                                w.create_element("S3Key").write_inner_content(|w| {
+                                    let filters = [("prefix", &rule.prefix), ("suffix", &rule.suffix)];
+                                    for (name, value) in filters {
+                                        if let Some(value) = value {
+                                            w.create_element("FilterRule").write_inner_content(|w| {
+                                                w.create_element("Name")
+                                                    .write_text_content(BytesText::new(name))?;
+                                                w.create_element("Value")
+                                                    .write_text_content(BytesText::new(value))?;
+                                                Ok(())
+                                            })?;
+                                        }
+                                    }
+                                    Ok(())
+                                })?;
+                                Ok(())
+                            })?;
+                        }
+                        Ok(())
+                    })?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    let bytes = writer.into_inner().into_inner();
+    format!("{}{}", xml_header(), String::from_utf8(bytes).unwrap())
+}
+
+/// Parse a `NotificationConfiguration` body. `QueueConfiguration`,
+/// `TopicConfiguration` and `CloudFunctionConfiguration` are all accepted;
+/// their `Queue` / `Topic` / `CloudFunction` is the target URL events are
+/// published to. An empty configuration disables notifications.
+pub fn parse_notification_configuration_xml(data: &[u8]) -> Result<NotificationConfiguration, crate::S3Error> {
+    use quick_xml::Reader;
+    use quick_xml::events::Event;
+
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+
+    // Element names from the root down to the current element
+    let mut path: Vec<Vec<u8>> = Vec::new();
+
+    let mut rules = Vec::new();
+    let mut current: Option<NotificationRule> = None;
+    let mut filter_name: Option<String> = None;
+    let mut filter_value: Option<String> = None;
+
+    let is_rule_element =
+        |name: &[u8]| matches!(name, b"QueueConfiguration" | b"TopicConfiguration" | b"CloudFunctionConfiguration");
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                match e.name().as_ref() {
+                    name if is_rule_element(name) => {
+                        current = Some(NotificationRule {
+                            id: String::new(),
+                            endpoint: String::new(),
+                            events: Vec::new(),
+                            prefix: None,
+                            suffix: None,
+                        });
+                    }
+                    b"FilterRule" => {
+                        filter_name = None;
+                        filter_value = None;
+                    }
+                    _ => {}
+                }
+                path.push(e.name().as_ref().to_vec());
+            }
+            Ok(Event::Text(e)) => {
+                let text = e
+                    .unescape()
+                    .map_err(|_| crate::S3Error::MalformedXML)?
+                    .into_owned();
+                let name = path.last().map(Vec::as_slice);
+                let parent = path.len().checked_sub(2).map(|i| path[i].as_slice());
+                match (parent, name) {
+                    (Some(b"FilterRule"), Some(b"Name")) => filter_name = Some(text),
+                    (Some(b"FilterRule"), Some(b"Value")) => filter_value = Some(text),
+                    (Some(p), Some(field)) if is_rule_element(p) => {
+                        let rule = current.as_mut().ok_or(crate::S3Error::MalformedXML)?;
+                        match field {
+                            b"Id" => rule.id = text,
+                            b"Queue" | b"Topic" | b"CloudFunction" => rule.endpoint = text,
+                            b"Event" => rule.events.push(text),
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::End(e)) => {
+                match e.name().as_ref() {
+                    b"FilterRule" => {
+                        let rule = current.as_mut().ok_or(crate::S3Error::MalformedXML)?;
+                        let value = filter_value.take().ok_or(crate::S3Error::MalformedXML)?;
+                        let slot = match filter_name.take().map(|n| n.to_ascii_lowercase()).as_deref() {
+                            Some("prefix") => &mut rule.prefix,
+                            Some("suffix") => &mut rule.suffix,
+                            _ => {
+                                return Err(crate::S3Error::InvalidArgument(
+                                    "FilterRule Name must be prefix or suffix".into(),
+                                ));
+                            }
+                        };
+                        if slot.replace(value).is_some() {
+                            return Err(crate::S3Error::InvalidArgument(
+                                "Cannot specify more than one prefix or suffix rule in a filter".into(),
+                            ));
+                        }
+                    }
+                    name if is_rule_element(name) => {
+                        let mut rule = current.take().ok_or(crate::S3Error::MalformedXML)?;
+                        if rule.id.is_empty() {
+                            rule.id = format!("notification-{}", rules.len() + 1);
+                        }
+                        validate_notification_rule(&rule)?;
+                        rules.push(rule);
+                    }
+                    _ => {}
+                }
+                path.pop();
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => return Err(crate::S3Error::MalformedXML),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    if rules.len() > MAX_NOTIFICATION_RULES {
+        return Err(crate::S3Error::InvalidArgument(format!(
+            "A notification configuration can have at most {} rules",
+            MAX_NOTIFICATION_RULES
+        )));
+    }
+    for (i, rule) in rules.iter().enumerate() {
+        if rules[..i].iter().any(|r| r.id == rule.id) {
+            return Err(crate::S3Error::InvalidArgument(format!(
+                "Duplicate notification rule Id: {}",
+                rule.id
+            )));
+        }
+    }
+    Ok(NotificationConfiguration { rules })
+}
+
+const MAX_NOTIFICATION_RULES: usize = 100;
+
+/// Whether the server was built with the matching target is checked when the
+/// configuration is stored.
+const NOTIFICATION_TARGET_SCHEMES: &[&str] = &["http://", "https://", "kafka://", "nats://"];
+
+fn validate_notification_rule(rule: &NotificationRule) -> Result<(), crate::S3Error> {
+    if !NOTIFICATION_TARGET_SCHEMES.iter().any(|s| rule.endpoint.starts_with(s)) {
+        return Err(crate::S3Error::InvalidArgument(
+            "Notification destination must be an http://, https://, kafka:// or nats:// URL".into(),
+        ));
+    }
+    if rule.events.is_empty() {
+        return Err(crate::S3Error::InvalidArgument(
+            "A notification rule must have at least one Event".into(),
+        ));
+    }
+    if let Some(event) = rule.events.iter().find(|e| !NOTIFICATION_EVENTS.contains(&e.as_str())) {
+        return Err(crate::S3Error::InvalidArgument(format!(
+            "Unsupported notification event: {}",
+            event
+        )));
+    }
+    Ok(())
+}
+
+pub fn parse_retain_until(value: &str) -> Result<chrono::DateTime<chrono::Utc>, crate::S3Error> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&chrono::Utc))
+        .map_err(|_| crate::S3Error::InvalidArgument("Invalid retain until date (expected ISO 8601)".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::s3::types::BucketMeta;
+    use chrono::Utc;
+
+    #[test]
+    fn test_list_buckets_xml() {
+        let resp = ListBucketsResponse {
+            buckets: vec![BucketMeta {
+                name: "test-bucket".into(),
+                creation_date: "2024-05-01T12:30:45.123456789Z".parse().unwrap(),
+                anonymous_read: false,
+                anonymous_list_public: false,
+                deletion_protected: false,
+                frozen: None,
+            }],
+            prefix: String::new(),
+            continuation_token: None,
+        };
+        let xml = list_buckets_xml("AKID", "backup job", &resp);
+        assert!(xml.contains("<Owner><ID>AKID</ID><DisplayName>backup job</DisplayName></Owner>"));
+        assert!(xml.contains("<CreationDate>2024-05-01T12:30:45.123Z</CreationDate>"));
+        assert!(xml.contains("xmlns=\"http://s3.amazonaws.com/doc/2006-03-01/\""));
+        assert!(xml.contains("<Name>test-bucket</Name>"));
+        assert!(xml.contains("<ListAllMyBucketsResult"));
+        assert!(!xml.contains("<ContinuationToken>"));
+    }
+
+    #[test]
+    fn test_list_objects_v2_xml() {
+        let resp = ListObjectsV2Response {
+            name: "mybucket".into(),
+            prefix: "".into(),
+            delimiter: "/".into(),
+            max_keys: 1000,
+            is_truncated: false,
+            contents: vec![ObjectMeta {
+                bucket: "mybucket".into(),
+                key: "file.txt".into(),
+                size: 100,
+                etag: "abc123".into(),
+                content_type: "text/plain".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }],
+            common_prefixes: vec!["photos/".into()],
+            next_continuation_token: None,
+            key_count: 1,
+        };
+        let xml = list_objects_v2_xml(&resp, &ListFormat::default());
+        assert!(xml.contains("<ListBucketResult"));
+        assert!(xml.contains("<Key>file.txt</Key>"));
+        assert!(xml.contains("<Prefix>photos/</Prefix>"));
+        assert!(xml.contains("<Delimiter>/</Delimiter>"));
+        assert!(!xml.contains("<EncodingType>"));
+    }
+
+    #[test]
+    fn test_list_objects_v2_xml_url_encoded() {
+        let format = ListFormat {
+            url_encoded: true,
+            ..Default::default()
+        };
+        let head = list_objects_v2_xml_head("mybucket", "a b/", "|", 1000, &format);
+        assert!(head.contains("<Prefix>a%20b/</Prefix>"));
+        assert!(head.contains("<Delimiter>%7C</Delimiter>"));
+        assert!(head.contains("<EncodingType>url</EncodingType>"));
+
+        let obj = ObjectMeta {
+            bucket: "mybucket".into(),
+            key: "a b/ctl\u{1}+é.txt".into(),
+            size: 1,
+            etag: "abc".into(),
+            content_type: "text/plain".into(),
+            last_modified: Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        };
+        let entry = list_entry_xml(&ListEntry::Object(obj.clone()), &format);
+        assert!(entry.contains("<Key>a%20b/ctl%01%2B%C3%A9.txt</Key>"));
+        assert!(!entry.contains("<Owner>"));
+        let entry = list_entry_xml(&ListEntry::CommonPrefix("x+y/".into()), &format);
+        assert!(entry.contains("<Prefix>x%2By/</Prefix>"));
+
+        let format = ListFormat {
+            owner: Some(ListOwner {
+                id: "AKID".into(),
+                display_name: "backup job".into(),
+            }),
+            ..Default::default()
+        };
+        let entry = list_entry_xml(&ListEntry::Object(obj), &format);
+        assert!(entry.contains("<StorageClass>STANDARD</StorageClass><Owner><ID>AKID</ID><DisplayName>backup job</DisplayName></Owner></Contents>"));
+    }
+
+    #[test]
+    fn test_error_xml() {
+        let err = crate::S3Error::NoSuchKey;
+        let xml = err.to_xml(&Default::default());
+        assert!(xml.contains("<Code>NoSuchKey</Code>"));
+        assert!(xml.contains("<Message>"));
+        assert!(!xml.contains("<Resource>"));
+
+        let context = crate::error::ErrorContext {
+            resource: Some("/bucket/key".into()),
+            request_id: Some("4442587FB7D0A2F9".into()),
+            host_id: Some("host".into()),
+        };
+        let xml = err.to_xml(&context);
+        assert!(xml.contains(
+            "</Message><Resource>/bucket/key</Resource><RequestId>4442587FB7D0A2F9</RequestId><HostId>host</HostId></Error>"
+        ));
+    }
+
+    #[test]
+    fn test_get_tagging_xml() {
+        let mut tags = HashMap::new();
+        tags.insert("env".into(), "prod".into());
+        let xml = get_tagging_xml(&tags);
+        assert!(xml.contains("<Tagging"));
+        assert!(xml.contains("<TagSet>"));
+        assert!(xml.contains("<Key>env</Key>"));
+        assert!(xml.contains("<Value>prod</Value>"));
+    }
+
+    #[test]
+    fn test_copy_object_result_xml() {
+        let xml = copy_object_result_xml("abc123", &Utc::now());
+        assert!(xml.contains("<CopyObjectResult"));
+        assert!(xml.contains("<ETag>"));
+        assert!(xml.contains("abc123"));
+        assert!(xml.contains("<LastModified>"));
+    }
+
+    #[test]
+    fn test_delete_objects_result_xml() {
+        let deleted = vec!["key1".to_string(), "key2".to_string()];
         let errors: Vec<(String, String, String)> = vec![];
         let xml = delete_objects_result_xml(&deleted, &errors, false);
         assert!(xml.contains("<DeleteResult"));
@@ -863,7 +2106,7 @@ mod tests {
         assert!(xml.contains("<UploadId>upload-123</UploadId>"));
         assert!(xml.contains("<Bucket>mybucket</Bucket>"));
 
-        let xml = complete_multipart_upload_xml("mybucket", "mykey", "etag123", "http://localhost/mybucket/mykey");
+        let xml = complete_multipart_upload_xml("mybucket", "mykey", "etag123", "http://localhost/mybucket/mykey", None);
         assert!(xml.contains("etag123"));
     }
 
@@ -887,6 +2130,10 @@ mod tests {
                     expiration_days: 30,
                     expiration_date: None,
                     tags: vec![],
+                    transitions: vec![],
+                    abort_incomplete_multipart_days: None,
+                    noncurrent_version_expiration_days: None,
+                    expired_object_delete_marker: false,
                 },
                 LifecycleRule {
                     id: "expire-tmp".into(),
@@ -895,6 +2142,10 @@ mod tests {
                     expiration_days: 7,
                     expiration_date: None,
                     tags: vec![],
+                    transitions: vec![],
+                    abort_incomplete_multipart_days: None,
+                    noncurrent_version_expiration_days: None,
+                    expired_object_delete_marker: false,
                 },
             ],
         };
@@ -937,6 +2188,10 @@ mod tests {
                     key: "env".into(),
                     value: "test".into(),
                 }],
+                transitions: vec![],
+                abort_incomplete_multipart_days: None,
+                noncurrent_version_expiration_days: None,
+                expired_object_delete_marker: false,
             }],
         };
         let xml = lifecycle_configuration_xml(&config);
@@ -967,6 +2222,10 @@ mod tests {
                     LifecycleTagFilter { key: "env".into(), value: "staging".into() },
                     LifecycleTagFilter { key: "team".into(), value: "infra".into() },
                 ],
+                transitions: vec![],
+                abort_incomplete_multipart_days: None,
+                noncurrent_version_expiration_days: None,
+                expired_object_delete_marker: false,
             }],
         };
         let xml = lifecycle_configuration_xml(&config);
@@ -993,6 +2252,10 @@ mod tests {
                 expiration_days: 0,
                 expiration_date: Some("2025-12-31T00:00:00+00:00".into()),
                 tags: vec![],
+                transitions: vec![],
+                abort_incomplete_multipart_days: None,
+                noncurrent_version_expiration_days: None,
+                expired_object_delete_marker: false,
             }],
         };
         let xml = lifecycle_configuration_xml(&config);
@@ -1014,6 +2277,106 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_lifecycle_xml_abort_incomplete_multipart_upload() {
+        let xml = r#"<LifecycleConfiguration><Rule><ID>abort</ID><Filter><Prefix>uploads/</Prefix></Filter><Status>Enabled</Status><AbortIncompleteMultipartUpload><DaysAfterInitiation>7</DaysAfterInitiation></AbortIncompleteMultipartUpload></Rule></LifecycleConfiguration>"#;
+        let config = parse_lifecycle_configuration_xml(xml.as_bytes()).unwrap();
+        assert_eq!(config.rules[0].abort_incomplete_multipart_days, Some(7));
+        assert!(!config.rules[0].has_expiration());
+
+        let written = lifecycle_configuration_xml(&config);
+        assert!(written.contains(
+            "<AbortIncompleteMultipartUpload><DaysAfterInitiation>7</DaysAfterInitiation></AbortIncompleteMultipartUpload>"
+        ));
+        assert!(!written.contains("<Expiration>"));
+        let reparsed = parse_lifecycle_configuration_xml(written.as_bytes()).unwrap();
+        assert_eq!(reparsed.rules[0].abort_incomplete_multipart_days, Some(7));
+
+        for invalid in [
+            "<Filter><Prefix></Prefix></Filter><Status>Enabled</Status><AbortIncompleteMultipartUpload><DaysAfterInitiation>0</DaysAfterInitiation></AbortIncompleteMultipartUpload>",
+            "<Filter><Tag><Key>k</Key><Value>v</Value></Tag></Filter><Status>Enabled</Status><AbortIncompleteMultipartUpload><DaysAfterInitiation>1</DaysAfterInitiation></AbortIncompleteMultipartUpload>",
+        ] {
+            let xml = format!("<LifecycleConfiguration><Rule><ID>r</ID>{}</Rule></LifecycleConfiguration>", invalid);
+            assert!(parse_lifecycle_configuration_xml(xml.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_xml_noncurrent_version_expiration() {
+        let xml = r#"<LifecycleConfiguration><Rule><ID>old-versions</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><NoncurrentVersionExpiration><NoncurrentDays>30</NoncurrentDays></NoncurrentVersionExpiration></Rule></LifecycleConfiguration>"#;
+        let config = parse_lifecycle_configuration_xml(xml.as_bytes()).unwrap();
+        assert_eq!(config.rules[0].noncurrent_version_expiration_days, Some(30));
+        assert!(!config.rules[0].has_expiration());
+
+        let written = lifecycle_configuration_xml(&config);
+        assert!(written.contains(
+            "<NoncurrentVersionExpiration><NoncurrentDays>30</NoncurrentDays></NoncurrentVersionExpiration>"
+        ));
+        let reparsed = parse_lifecycle_configuration_xml(written.as_bytes()).unwrap();
+        assert_eq!(reparsed.rules[0].noncurrent_version_expiration_days, Some(30));
+
+        let invalid = r#"<LifecycleConfiguration><Rule><ID>r</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><NoncurrentVersionExpiration><NoncurrentDays>0</NoncurrentDays></NoncurrentVersionExpiration></Rule></LifecycleConfiguration>"#;
+        assert!(parse_lifecycle_configuration_xml(invalid.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_lifecycle_xml_expired_object_delete_marker() {
+        let xml = r#"<LifecycleConfiguration><Rule><ID>markers</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><Expiration><ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker></Expiration></Rule></LifecycleConfiguration>"#;
+        let config = parse_lifecycle_configuration_xml(xml.as_bytes()).unwrap();
+        assert!(config.rules[0].expired_object_delete_marker);
+        assert!(!config.rules[0].has_expiration());
+
+        let written = lifecycle_configuration_xml(&config);
+        assert!(written.contains(
+            "<Expiration><ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker></Expiration>"
+        ));
+        let reparsed = parse_lifecycle_configuration_xml(written.as_bytes()).unwrap();
+        assert!(reparsed.rules[0].expired_object_delete_marker);
+
+        for invalid in [
+            "<Filter><Prefix></Prefix></Filter><Status>Enabled</Status><Expiration><Days>1</Days><ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker></Expiration>",
+            "<Filter><Prefix></Prefix></Filter><Status>Enabled</Status><Expiration><ExpiredObjectDeleteMarker>yes</ExpiredObjectDeleteMarker></Expiration>",
+            "<Filter><Tag><Key>k</Key><Value>v</Value></Tag></Filter><Status>Enabled</Status><Expiration><ExpiredObjectDeleteMarker>true</ExpiredObjectDeleteMarker></Expiration>",
+        ] {
+            let xml = format!("<LifecycleConfiguration><Rule><ID>r</ID>{}</Rule></LifecycleConfiguration>", invalid);
+            assert!(parse_lifecycle_configuration_xml(xml.as_bytes()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_xml_transitions() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><LifecycleConfiguration><Rule><ID>archive</ID><Filter><Prefix>logs/</Prefix></Filter><Status>Enabled</Status><Transition><Days>30</Days><StorageClass>COLD</StorageClass></Transition><Transition><Date>2030-01-01T00:00:00+00:00</Date><StorageClass>GLACIER</StorageClass></Transition><Expiration><Days>365</Days></Expiration></Rule><Rule><ID>move-only</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><Transition><Days>7</Days><StorageClass>COLD</StorageClass></Transition></Rule></LifecycleConfiguration>"#;
+        let config = parse_lifecycle_configuration_xml(xml.as_bytes()).unwrap();
+        let rule = &config.rules[0];
+        assert_eq!(rule.expiration_days, 365);
+        assert_eq!(rule.transitions.len(), 2);
+        assert_eq!(rule.transitions[0].days, Some(30));
+        assert_eq!(rule.transitions[0].storage_class, "COLD");
+        assert_eq!(rule.transitions[1].date.as_deref(), Some("2030-01-01T00:00:00+00:00"));
+        assert!(!config.rules[1].has_expiration());
+
+        let written = lifecycle_configuration_xml(&config);
+        assert!(written.contains("<Transition><Days>30</Days><StorageClass>COLD</StorageClass></Transition>"));
+        assert_eq!(written.matches("<Expiration>").count(), 1);
+        let reparsed = parse_lifecycle_configuration_xml(written.as_bytes()).unwrap();
+        assert_eq!(reparsed.rules[0].transitions, rule.transitions);
+        assert_eq!(reparsed.rules[1].transitions, config.rules[1].transitions);
+
+        // Transitions need a storage class other than STANDARD and exactly one of Days or Date
+        for transition in [
+            "<Transition><Days>30</Days><StorageClass>STANDARD</StorageClass></Transition>",
+            "<Transition><Days>30</Days></Transition>",
+            "<Transition><StorageClass>COLD</StorageClass></Transition>",
+            "<Transition><Days>x</Days><StorageClass>COLD</StorageClass></Transition>",
+        ] {
+            let xml = format!(
+                r#"<LifecycleConfiguration><Rule><ID>r</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status>{}</Rule></LifecycleConfiguration>"#,
+                transition
+            );
+            assert!(parse_lifecycle_configuration_xml(xml.as_bytes()).is_err(), "{}", transition);
+        }
+    }
+
     #[test]
     fn test_cors_xml_roundtrip() {
         use crate::s3::types::{CorsConfiguration, CorsRule};
@@ -1071,6 +2434,148 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_website_xml_roundtrip() {
+        let config = WebsiteConfiguration {
+            index_document: "index.html".into(),
+            error_document: Some("errors/404.html".into()),
+            routing_rules: vec![RoutingRule {
+                condition: Some(RoutingRuleCondition {
+                    key_prefix_equals: Some("docs/".into()),
+                    http_error_code_returned_equals: Some(404),
+                }),
+                redirect: RoutingRuleRedirect {
+                    host_name: Some("docs.example.com".into()),
+                    protocol: Some("https".into()),
+                    replace_key_prefix_with: Some("manual/".into()),
+                    replace_key_with: None,
+                    http_redirect_code: Some(302),
+                },
+            }],
+        };
+        let xml = website_configuration_xml(&config);
+        assert!(xml.contains("<IndexDocument><Suffix>index.html</Suffix></IndexDocument>"));
+        assert!(xml.contains("<ErrorDocument><Key>errors/404.html</Key></ErrorDocument>"));
+        assert!(xml.contains("<KeyPrefixEquals>docs/</KeyPrefixEquals>"));
+        assert!(xml.contains("<ReplaceKeyPrefixWith>manual/</ReplaceKeyPrefixWith>"));
+        assert_eq!(parse_website_configuration_xml(xml.as_bytes()).unwrap(), config);
+
+        let xml = r#"<WebsiteConfiguration><IndexDocument><Suffix>home.html</Suffix></IndexDocument></WebsiteConfiguration>"#;
+        let parsed = parse_website_configuration_xml(xml.as_bytes()).unwrap();
+        assert_eq!(parsed.index_document, "home.html");
+        assert!(parsed.error_document.is_none());
+        assert!(!website_configuration_xml(&parsed).contains("<ErrorDocument>"));
+    }
+
+    #[test]
+    fn test_website_xml_invalid_suffix() {
+        let missing = r#"<WebsiteConfiguration><ErrorDocument><Key>404.html</Key></ErrorDocument></WebsiteConfiguration>"#;
+        assert!(matches!(
+            parse_website_configuration_xml(missing.as_bytes()),
+            Err(crate::S3Error::MalformedXML)
+        ));
+        let slash = r#"<WebsiteConfiguration><IndexDocument><Suffix>docs/index.html</Suffix></IndexDocument></WebsiteConfiguration>"#;
+        assert!(matches!(
+            parse_website_configuration_xml(slash.as_bytes()),
+            Err(crate::S3Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_website_xml_invalid_routing_rule() {
+        let rule = |redirect: &str| {
+            format!(
+                "<WebsiteConfiguration><IndexDocument><Suffix>index.html</Suffix></IndexDocument>\
+                 <RoutingRules><RoutingRule><Redirect>{}</Redirect></RoutingRule></RoutingRules></WebsiteConfiguration>",
+                redirect
+            )
+        };
+        let parsed = parse_website_configuration_xml(rule("<ReplaceKeyWith>moved.html</ReplaceKeyWith>").as_bytes()).unwrap();
+        assert!(parsed.routing_rules[0].condition.is_none());
+        assert_eq!(parsed.routing_rules[0].redirect.replace_key_with.as_deref(), Some("moved.html"));
+
+        for redirect in [
+            "",
+            "<ReplaceKeyWith>a</ReplaceKeyWith><ReplaceKeyPrefixWith>b</ReplaceKeyPrefixWith>",
+            "<Protocol>ftp</Protocol>",
+            "<HttpRedirectCode>200</HttpRedirectCode>",
+        ] {
+            assert!(matches!(
+                parse_website_configuration_xml(rule(redirect).as_bytes()),
+                Err(crate::S3Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bucket_logging_xml_roundtrip() {
+        let config = BucketLoggingConfiguration {
+            target_bucket: "logs".into(),
+            target_prefix: "access/".into(),
+        };
+        let xml = bucket_logging_xml(Some(&config));
+        assert!(xml.contains("<TargetBucket>logs</TargetBucket>"));
+        assert_eq!(parse_bucket_logging_xml(xml.as_bytes()).unwrap(), Some(config));
+
+        let disabled = bucket_logging_xml(None);
+        assert!(!disabled.contains("<LoggingEnabled>"));
+        assert_eq!(parse_bucket_logging_xml(disabled.as_bytes()).unwrap(), None);
+
+        let missing_target = "<BucketLoggingStatus><LoggingEnabled><TargetPrefix>p/</TargetPrefix></LoggingEnabled></BucketLoggingStatus>";
+        assert!(parse_bucket_logging_xml(missing_target.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_notification_configuration_xml_roundtrip() {
+        let body = r#"<NotificationConfiguration>
+            <QueueConfiguration>
+                <Id>images</Id>
+                <Queue>http://localhost:9000/hook</Queue>
+                <Event>s3:ObjectCreated:*</Event>
+                <Event>s3:ObjectRemoved:Delete</Event>
+                <Filter><S3Key>
+                    <FilterRule><Name>prefix</Name><Value>images/</Value></FilterRule>
+                    <FilterRule><Name>Suffix</Name><Value>.jpg</Value></FilterRule>
+                </S3Key></Filter>
+            </QueueConfiguration>
+            <TopicConfiguration>
+                <Topic>https://example.com/events</Topic>
+                <Event>s3:ObjectRemoved:*</Event>
+            </TopicConfiguration>
+        </NotificationConfiguration>"#;
+        let config = parse_notification_configuration_xml(body.as_bytes()).unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].id, "images");
+        assert_eq!(config.rules[0].prefix.as_deref(), Some("images/"));
+        assert_eq!(config.rules[0].suffix.as_deref(), Some(".jpg"));
+        assert_eq!(config.rules[1].id, "notification-2");
+        assert_eq!(config.rules[1].endpoint, "https://example.com/events");
+
+        let xml = notification_configuration_xml(&config);
+        assert!(xml.contains("<Queue>https://example.com/events</Queue>"));
+        assert_eq!(parse_notification_configuration_xml(xml.as_bytes()).unwrap(), config);
+
+        let empty = parse_notification_configuration_xml(b"<NotificationConfiguration/>").unwrap();
+        assert!(empty.rules.is_empty());
+    }
+
+    #[test]
+    fn test_parse_notification_configuration_rejects_invalid() {
+        let rule = |inner: &str| {
+            format!("<NotificationConfiguration><QueueConfiguration>{}</QueueConfiguration></NotificationConfiguration>", inner)
+        };
+        let cases = [
+            rule("<Queue>arn:aws:sqs:us-east-1:1:q</Queue><Event>s3:ObjectCreated:*</Event>"),
+            rule("<Queue>amqp://h/q</Queue><Event>s3:ObjectCreated:*</Event>"),
+            rule("<Queue>http://h/</Queue>"),
+            rule("<Queue>http://h/</Queue><Event>s3:ObjectRestore:Post</Event>"),
+            rule("<Queue>http://h/</Queue><Event>s3:ObjectCreated:*</Event><Filter><S3Key><FilterRule><Name>other</Name><Value>x</Value></FilterRule></S3Key></Filter>"),
+        ];
+        for body in cases {
+            assert!(parse_notification_configuration_xml(body.as_bytes()).is_err(), "{}", body);
+        }
+    }
+
     #[test]
     fn test_get_object_acl_xml_public() {
         let xml = get_object_acl_xml(true);
@@ -1079,4 +2584,55 @@ mod tests {
         assert!(xml.contains("AllUsers"));
         assert!(xml.contains("<Permission>READ</Permission>"));
     }
+
+    #[test]
+    fn test_parse_access_control_policy_roundtrip() {
+        assert!(parse_access_control_policy_xml(get_object_acl_xml(true).as_bytes()).unwrap());
+        assert!(!parse_access_control_policy_xml(get_object_acl_xml(false).as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_parse_access_control_policy_rejects_unsupported_grants() {
+        let xml = r#"<AccessControlPolicy><AccessControlList><Grant>
+            <Grantee xsi:type="Group"><URI>http://acs.amazonaws.com/groups/global/AllUsers</URI></Grantee>
+            <Permission>WRITE</Permission></Grant></AccessControlList></AccessControlPolicy>"#;
+        assert!(parse_access_control_policy_xml(xml.as_bytes()).is_err());
+        assert!(parse_access_control_policy_xml(b"<Tagging/>").is_err());
+    }
+
+    #[test]
+    fn test_object_lock_xml_roundtrip() {
+        let xml = r#"<ObjectLockConfiguration><ObjectLockEnabled>Enabled</ObjectLockEnabled>
+            <Rule><DefaultRetention><Mode>COMPLIANCE</Mode><Days>30</Days></DefaultRetention></Rule>
+            </ObjectLockConfiguration>"#;
+        let config = parse_object_lock_configuration_xml(xml.as_bytes()).unwrap();
+        let retention = config.default_retention.as_ref().unwrap();
+        assert_eq!(retention.mode, RetentionMode::Compliance);
+        assert_eq!(retention.days, Some(30));
+        assert_eq!(parse_object_lock_configuration_xml(object_lock_configuration_xml(&config).as_bytes()).unwrap(), config);
+
+        let both = r#"<ObjectLockConfiguration><ObjectLockEnabled>Enabled</ObjectLockEnabled>
+            <Rule><DefaultRetention><Mode>GOVERNANCE</Mode><Days>1</Days><Years>1</Years></DefaultRetention></Rule>
+            </ObjectLockConfiguration>"#;
+        assert!(parse_object_lock_configuration_xml(both.as_bytes()).is_err());
+
+        let retention = parse_retention_xml(
+            b"<Retention><Mode>GOVERNANCE</Mode><RetainUntilDate>2030-01-01T00:00:00Z</RetainUntilDate></Retention>",
+        )
+        .unwrap();
+        assert_eq!(retention.mode, RetentionMode::Governance);
+        assert!(retention_xml(&retention).contains("<RetainUntilDate>2030-01-01T00:00:00.000Z</RetainUntilDate>"));
+
+        assert!(parse_legal_hold_xml(legal_hold_xml(true).as_bytes()).unwrap());
+        assert!(!parse_legal_hold_xml(b"<LegalHold><Status>OFF</Status></LegalHold>").unwrap());
+        assert!(parse_legal_hold_xml(b"<LegalHold><Status>MAYBE</Status></LegalHold>").is_err());
+    }
+
+    #[test]
+    fn test_parse_restore_request() {
+        let xml = b"<RestoreRequest><Days>3</Days><GlacierJobParameters><Tier>Standard</Tier></GlacierJobParameters></RestoreRequest>";
+        assert_eq!(parse_restore_request_xml(xml).unwrap(), 3);
+        assert!(parse_restore_request_xml(b"<RestoreRequest><Days>0</Days></RestoreRequest>").is_err());
+        assert!(parse_restore_request_xml(b"<RestoreRequest></RestoreRequest>").is_err());
+    }
 }
@@ -1,28 +1,71 @@
+use super::io_pool::IoPool;
 use crate::error::S3Error;
 use md5::{Digest, Md5};
+use sha2::Sha256;
 use std::path::{Path, PathBuf};
 use tokio::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct FileStore {
     data_dir: PathBuf,
+    escape_paths: bool,
+    md5_skip_threshold: Option<u64>,
+    io: IoPool,
 }
 
 impl FileStore {
     pub fn new(data_dir: &Path) -> Self {
         Self {
             data_dir: data_dir.to_path_buf(),
+            escape_paths: false,
+            md5_skip_threshold: None,
+            io: IoPool::default(),
         }
     }
 
+    /// Store each key segment in an NTFS-safe escaped form (see `escape_segment`).
+    pub fn with_path_escaping(mut self, enabled: bool) -> Self {
+        self.escape_paths = enabled;
+        self
+    }
+
+    /// Skip the content MD5 of objects and parts larger than `threshold` bytes.
+    /// Their ETag is derived from size and modification time instead.
+    pub fn with_md5_skip_threshold(mut self, threshold: Option<u64>) -> Self {
+        self.md5_skip_threshold = threshold;
+        self
+    }
+
+    /// Run file IO on `pool` instead of the calling runtime's blocking pool.
+    pub fn with_io_pool(mut self, pool: IoPool) -> Self {
+        self.io = pool;
+        self
+    }
+
+    pub fn io_pool(&self) -> &IoPool {
+        &self.io
+    }
+
+    fn skips_md5(&self, size: u64) -> bool {
+        self.md5_skip_threshold.is_some_and(|t| size > t)
+    }
+
     pub fn bucket_path(&self, bucket: &str) -> PathBuf {
         self.data_dir.join(bucket)
     }
 
     pub fn object_path(&self, bucket: &str, key: &str) -> PathBuf {
-        self.data_dir.join(bucket).join(key)
+        let mut path = self.data_dir.join(bucket);
+        if self.escape_paths {
+            for segment in key.split('/') {
+                path.push(escape_segment(segment));
+            }
+        } else {
+            path.push(key);
+        }
+        path
     }
 
     /// Validate that a resolved path stays within the expected base directory.
@@ -64,79 +107,121 @@ impl FileStore {
     }
 
     pub async fn create_bucket_dir(&self, bucket: &str) -> Result<(), S3Error> {
-        let path = self.safe_bucket_path(bucket)?;
-        fs::create_dir_all(&path)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))
+        self.io.run(async {
+            let path = self.safe_bucket_path(bucket)?;
+            fs::create_dir_all(&path)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))
+        })
+        .await
     }
 
     pub async fn delete_bucket_dir(&self, bucket: &str) -> Result<(), S3Error> {
-        let path = self.safe_bucket_path(bucket)?;
-        if path.exists() {
-            fs::remove_dir_all(&path)
-                .await
-                .map_err(|e| S3Error::InternalError(e.to_string()))?;
-        }
-        Ok(())
+        self.io.run(async {
+            let path = self.safe_bucket_path(bucket)?;
+            if path.exists() {
+                fs::remove_dir_all(&path)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+            Ok(())
+        })
+        .await
     }
 
-    /// Write object data atomically via temp file + rename. Returns (size, md5_hex).
+    /// Write object data atomically via temp file + rename. Returns (size, etag):
+    /// the MD5 hex, or an opaque value above the MD5 skip threshold.
     pub async fn write_object(
         &self,
         bucket: &str,
         key: &str,
         data: &[u8],
     ) -> Result<(u64, String), S3Error> {
-        let target = self.safe_object_path(bucket, key)?;
-        if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent)
-                .await
-                .map_err(|e| S3Error::InternalError(e.to_string()))?;
-        }
+        self.io.run(async {
+            let target = self.safe_object_path(bucket, key)?;
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
 
-        let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
+            let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
 
-        let mut file = fs::File::create(&temp_path)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let mut file = fs::File::create(&temp_path)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        file.write_all(data)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            file.write_all(data)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        file.flush()
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            file.flush()
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        fs::rename(&temp_path, &target)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            fs::rename(&temp_path, &target)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        let size = data.len() as u64;
-        let etag = hex::encode(Md5::digest(data));
-        Ok((size, etag))
+            let size = data.len() as u64;
+            let etag = if self.skips_md5(size) {
+                hex::encode(metadata_digest(&target, size).await?)
+            } else {
+                hex::encode(Md5::digest(data))
+            };
+            Ok((size, etag))
+        })
+        .await
     }
 
-    /// Stream-write object from an async reader. Returns (size, md5_hex).
+    /// Stream-write object from an async reader. Returns (size, etag) like
+    /// `write_object`.
     pub async fn write_object_stream<R: tokio::io::AsyncRead + Unpin>(
         &self,
         bucket: &str,
         key: &str,
         reader: &mut R,
     ) -> Result<(u64, String), S3Error> {
-        let target = self.safe_object_path(bucket, key)?;
-        if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent)
+        self.io.run(async {
+            let target = self.safe_object_path(bucket, key)?;
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+
+            let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
+            let mut file = fs::File::create(&temp_path)
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
-        }
 
-        let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
-        let mut file = fs::File::create(&temp_path)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let (total_size, digest) = self.copy_hashing(reader, &mut file).await?;
+
+            file.flush()
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        let mut hasher = Md5::new();
+            fs::rename(&temp_path, &target)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+
+            let etag = match digest {
+                Some(md5) => hex::encode(md5),
+                None => hex::encode(metadata_digest(&target, total_size).await?),
+            };
+            Ok((total_size, etag))
+        })
+        .await
+    }
+
+    /// Copy `reader` into `file`, hashing the data until it grows past the MD5
+    /// skip threshold. Returns the size and the MD5, unless it was skipped.
+    async fn copy_hashing<R: tokio::io::AsyncRead + Unpin>(
+        &self,
+        reader: &mut R,
+        file: &mut fs::File,
+    ) -> Result<(u64, Option<Vec<u8>>), S3Error> {
+        let mut hasher = Some(Md5::new());
         let mut total_size: u64 = 0;
         let mut buf = vec![0u8; 64 * 1024];
 
@@ -151,27 +236,25 @@ impl FileStore {
             file.write_all(&buf[..n])
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
-            hasher.update(&buf[..n]);
             total_size += n as u64;
+            if self.skips_md5(total_size) {
+                hasher = None;
+            }
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&buf[..n]);
+            }
         }
-
-        file.flush()
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
-
-        fs::rename(&temp_path, &target)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
-
-        let etag = hex::encode(hasher.finalize());
-        Ok((total_size, etag))
+        Ok((total_size, hasher.map(|h| h.finalize().to_vec())))
     }
 
     pub async fn read_object(&self, bucket: &str, key: &str) -> Result<Vec<u8>, S3Error> {
-        let path = self.safe_object_path(bucket, key)?;
-        fs::read(&path)
-            .await
-            .map_err(|_| S3Error::NoSuchKey)
+        self.io.run(async {
+            let path = self.safe_object_path(bucket, key)?;
+            fs::read(&path)
+                .await
+                .map_err(|_| S3Error::NoSuchKey)
+        })
+        .await
     }
 
     pub fn open_object_file(
@@ -194,13 +277,16 @@ impl FileStore {
     }
 
     pub async fn delete_object(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
-        let path = self.safe_object_path(bucket, key)?;
-        if path.exists() {
-            fs::remove_file(&path)
-                .await
-                .map_err(|e| S3Error::InternalError(e.to_string()))?;
-        }
-        Ok(())
+        self.io.run(async {
+            let path = self.safe_object_path(bucket, key)?;
+            if path.exists() {
+                fs::remove_file(&path)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+            Ok(())
+        })
+        .await
     }
 
     // --- Multipart ---
@@ -211,19 +297,26 @@ impl FileStore {
         part_number: u32,
         data: &[u8],
     ) -> Result<(u64, String), S3Error> {
-        let dir = self.multipart_dir(upload_id);
-        fs::create_dir_all(&dir)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.io.run(async {
+            let dir = self.multipart_dir(upload_id);
+            fs::create_dir_all(&dir)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        let path = self.part_path(upload_id, part_number);
-        fs::write(&path, data)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let path = self.part_path(upload_id, part_number);
+            fs::write(&path, data)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
-        let size = data.len() as u64;
-        let etag = hex::encode(Md5::digest(data));
-        Ok((size, etag))
+            let size = data.len() as u64;
+            let etag = if self.skips_md5(size) {
+                hex::encode(metadata_digest(&path, size).await?)
+            } else {
+                hex::encode(Md5::digest(data))
+            };
+            Ok((size, etag))
+        })
+        .await
     }
 
     pub async fn write_part_stream<R: tokio::io::AsyncRead + Unpin>(
@@ -232,41 +325,30 @@ impl FileStore {
         part_number: u32,
         reader: &mut R,
     ) -> Result<(u64, String), S3Error> {
-        let dir = self.multipart_dir(upload_id);
-        fs::create_dir_all(&dir)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
-
-        let path = self.part_path(upload_id, part_number);
-        let mut file = fs::File::create(&path)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
-
-        let mut hasher = Md5::new();
-        let mut total_size: u64 = 0;
-        let mut buf = vec![0u8; 64 * 1024];
-
-        loop {
-            let n = reader
-                .read(&mut buf)
+        self.io.run(async {
+            let dir = self.multipart_dir(upload_id);
+            fs::create_dir_all(&dir)
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
-            if n == 0 {
-                break;
-            }
-            file.write_all(&buf[..n])
+
+            let path = self.part_path(upload_id, part_number);
+            let mut file = fs::File::create(&path)
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
-            hasher.update(&buf[..n]);
-            total_size += n as u64;
-        }
 
-        file.flush()
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let (total_size, digest) = self.copy_hashing(reader, &mut file).await?;
 
-        let etag = hex::encode(hasher.finalize());
-        Ok((total_size, etag))
+            file.flush()
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+
+            let etag = match digest {
+                Some(md5) => hex::encode(md5),
+                None => hex::encode(metadata_digest(&path, total_size).await?),
+            };
+            Ok((total_size, etag))
+        })
+        .await
     }
 
     /// Assemble parts into the final object. Returns (size, multipart_etag).
@@ -277,60 +359,219 @@ impl FileStore {
         upload_id: &str,
         part_numbers: &[u32],
     ) -> Result<(u64, String), S3Error> {
-        let target = self.safe_object_path(bucket, key)?;
-        if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent)
+        let sources: Vec<PathBuf> = part_numbers.iter().map(|&pn| self.part_path(upload_id, pn)).collect();
+        self.assemble(bucket, key, &sources, || S3Error::InvalidPart).await
+    }
+
+    /// Concatenate existing objects, in order, into `key`. Sources are read
+    /// from wherever they are stored, so they may live on another backend;
+    /// one of them may be `key` itself. Returns (size, multipart-style etag).
+    pub async fn concatenate_objects(
+        &self,
+        bucket: &str,
+        key: &str,
+        sources: &[PathBuf],
+    ) -> Result<(u64, String), S3Error> {
+        self.assemble(bucket, key, sources, || S3Error::NoSuchKey).await
+    }
+
+    /// Stream `sources` into a temp file renamed over the object, so readers
+    /// never see a partial result. A missing source fails with `missing()`.
+    async fn assemble(
+        &self,
+        bucket: &str,
+        key: &str,
+        sources: &[PathBuf],
+        missing: impl Fn() -> S3Error,
+    ) -> Result<(u64, String), S3Error> {
+        self.io.run(async {
+            let target = self.safe_object_path(bucket, key)?;
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+
+            let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
+            let result = self.write_concatenated(&temp_path, sources, missing).await;
+            let (total_size, part_md5s) = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    let _ = fs::remove_file(&temp_path).await;
+                    return Err(e);
+                }
+            };
+
+            fs::rename(&temp_path, &target)
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
-        }
 
-        let temp_path = target.with_extension(format!("tmp.{}", Uuid::new_v4()));
-        let mut file = fs::File::create(&temp_path)
+            // Multipart ETag: md5(concat(part_md5s))-N
+            let mut combined = Vec::new();
+            for md5 in &part_md5s {
+                combined.extend_from_slice(md5);
+            }
+            let etag = format!("{}-{}", hex::encode(Md5::digest(&combined)), sources.len());
+
+            Ok((total_size, etag))
+        })
+        .await
+    }
+
+    async fn write_concatenated(
+        &self,
+        path: &Path,
+        sources: &[PathBuf],
+        missing: impl Fn() -> S3Error,
+    ) -> Result<(u64, Vec<Vec<u8>>), S3Error> {
+        let mut file = fs::File::create(path)
             .await
             .map_err(|e| S3Error::InternalError(e.to_string()))?;
 
         let mut total_size: u64 = 0;
         let mut part_md5s: Vec<Vec<u8>> = Vec::new();
 
-        for &pn in part_numbers {
-            let part_path = self.part_path(upload_id, pn);
-            let data = fs::read(&part_path)
-                .await
-                .map_err(|_| S3Error::InvalidPart)?;
-            file.write_all(&data)
-                .await
-                .map_err(|e| S3Error::InternalError(e.to_string()))?;
-            total_size += data.len() as u64;
-            part_md5s.push(Md5::digest(&data).to_vec());
+        for source in sources {
+            let mut reader = fs::File::open(source).await.map_err(|_| missing())?;
+            let (size, digest) = self.copy_hashing(&mut reader, &mut file).await?;
+            let md5 = match digest {
+                Some(md5) => md5,
+                None => metadata_digest(source, size).await?,
+            };
+            total_size += size;
+            part_md5s.push(md5);
         }
 
         file.flush()
             .await
             .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok((total_size, part_md5s))
+    }
 
-        fs::rename(&temp_path, &target)
-            .await
-            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    pub async fn cleanup_multipart(&self, upload_id: &str) -> Result<(), S3Error> {
+        self.io.run(async {
+            let dir = self.multipart_dir(upload_id);
+            if dir.exists() {
+                fs::remove_dir_all(&dir)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+            Ok(())
+        })
+        .await
+    }
 
-        // Multipart ETag: md5(concat(part_md5s))-N
-        let mut combined = Vec::new();
-        for md5 in &part_md5s {
-            combined.extend_from_slice(md5);
-        }
-        let etag = format!("{}-{}", hex::encode(Md5::digest(&combined)), part_numbers.len());
+    // --- Archive segments ---
 
-        Ok((total_size, etag))
+    fn archive_dir(&self, bucket: &str) -> PathBuf {
+        self.data_dir.join(".archive").join(bucket)
     }
 
-    pub async fn cleanup_multipart(&self, upload_id: &str) -> Result<(), S3Error> {
-        let dir = self.multipart_dir(upload_id);
-        if dir.exists() {
-            fs::remove_dir_all(&dir)
+    /// Segment file `segment` of the archive of a frozen bucket.
+    pub fn archive_segment_path(&self, bucket: &str, segment: u32) -> PathBuf {
+        self.archive_dir(bucket).join(format!("segment-{:05}", segment))
+    }
+
+    /// Append the file at `source` to an archive segment, creating it if
+    /// needed. Returns the offset of the data in the segment and its hex
+    /// SHA-256.
+    pub async fn append_to_segment(&self, bucket: &str, segment: u32, source: &Path) -> Result<(u64, String), S3Error> {
+        self.io.run(async {
+            validate_name(bucket)?;
+            fs::create_dir_all(self.archive_dir(bucket))
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let mut input = fs::File::open(source).await.map_err(|_| S3Error::NoSuchKey)?;
+            let mut output = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(self.archive_segment_path(bucket, segment))
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let offset = output
+                .metadata()
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?
+                .len();
+            let sha256 = copy_sha256(&mut input, Some(&mut output)).await?;
+            output
+                .sync_all()
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            Ok((offset, sha256))
+        })
+        .await
+    }
+
+    /// Hex SHA-256 of the file at `path`.
+    pub async fn sha256_file(&self, path: &Path) -> Result<String, S3Error> {
+        self.io.run(async {
+            let mut input = fs::File::open(path).await.map_err(|_| S3Error::NoSuchKey)?;
+            copy_sha256(&mut input, None).await
+        })
+        .await
+    }
+
+    /// Read `len` bytes of the file at `path` from `offset`.
+    pub async fn read_range(&self, path: &Path, offset: u64, len: u64) -> Result<Vec<u8>, S3Error> {
+        self.io.run(async {
+            let mut file = fs::File::open(path).await.map_err(|_| S3Error::NoSuchKey)?;
+            file.seek(std::io::SeekFrom::Start(offset))
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let mut data = Vec::with_capacity(len as usize);
+            file.take(len)
+                .read_to_end(&mut data)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if data.len() as u64 != len {
+                return Err(S3Error::InternalError(format!("{} is truncated", path.display())));
+            }
+            Ok(data)
+        })
+        .await
+    }
+
+    /// Remove the archive segments of a bucket.
+    pub async fn delete_archive(&self, bucket: &str) -> Result<(), S3Error> {
+        self.io.run(async {
+            validate_name(bucket)?;
+            let dir = self.archive_dir(bucket);
+            if dir.exists() {
+                fs::remove_dir_all(&dir)
+                    .await
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Copy `reader` to `writer`, if any, and return the hex SHA-256 of the data.
+async fn copy_sha256<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+    mut writer: Option<&mut fs::File>,
+) -> Result<String, S3Error> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .await
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if n == 0 {
+            break;
+        }
+        if let Some(writer) = writer.as_mut() {
+            writer
+                .write_all(&buf[..n])
                 .await
                 .map_err(|e| S3Error::InternalError(e.to_string()))?;
         }
-        Ok(())
+        hasher.update(&buf[..n]);
     }
+    Ok(hex::encode(hasher.finalize()))
 }
 
 /// Normalize a path by resolving `.` and `..` components without touching the filesystem.
@@ -349,6 +590,65 @@ fn normalize_path(path: &Path) -> PathBuf {
     result
 }
 
+/// Device names Windows reserves regardless of extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn is_windows_unsafe(c: char) -> bool {
+    matches!(c, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*' | '%') || c.is_control()
+}
+
+/// Opaque stand-in for the MD5 of a file above the MD5 skip threshold. It
+/// changes whenever the file is rewritten, even with identical content.
+async fn metadata_digest(path: &Path, size: u64) -> Result<Vec<u8>, S3Error> {
+    let modified = fs::metadata(path)
+        .await
+        .and_then(|m| m.modified())
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    let nanos = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    Ok(Md5::digest(format!("{}:{}", size, nanos)).to_vec())
+}
+
+/// Escape one key segment so it is a valid NTFS file name.
+///
+/// Characters NTFS rejects, `%` itself, a trailing dot or space, and the first
+/// character of a reserved device name are written as `%XX`. The mapping is
+/// injective, so distinct keys never share a file. Empty segments (from `//`
+/// or a trailing `/`) become `%00`, which no valid key can produce otherwise.
+/// `.` and `..` are left for key validation to handle.
+pub fn escape_segment(segment: &str) -> String {
+    if segment.is_empty() {
+        return "%00".to_string();
+    }
+    if segment == "." || segment == ".." {
+        return segment.to_string();
+    }
+    let stem = segment.split('.').next().unwrap_or("");
+    let reserved = WINDOWS_RESERVED_NAMES.iter().any(|r| r.eq_ignore_ascii_case(stem));
+    let last = segment.char_indices().last().map(|(i, _)| i);
+
+    let mut out = String::with_capacity(segment.len());
+    for (i, c) in segment.char_indices() {
+        let escape = is_windows_unsafe(c)
+            || (reserved && i == 0)
+            || (Some(i) == last && (c == '.' || c == ' '));
+        if escape {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
 /// Validate a bucket name: reject path traversal components and null bytes.
 fn validate_name(name: &str) -> Result<(), S3Error> {
     if name.is_empty()
@@ -469,6 +769,82 @@ mod tests {
         assert_eq!(data, b"cross");
     }
 
+    #[tokio::test]
+    async fn test_md5_skip_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path()).with_md5_skip_threshold(Some(4));
+        store.create_bucket_dir("b").await.unwrap();
+
+        let (_, small) = store.write_object("b", "small", b"abcd").await.unwrap();
+        assert_eq!(small, hex::encode(Md5::digest(b"abcd")));
+
+        let data = b"abcdefgh";
+        let (size, etag) = store.write_object("b", "big", data).await.unwrap();
+        assert_eq!(size, 8);
+        assert_eq!(etag.len(), 32);
+        assert_ne!(etag, hex::encode(Md5::digest(data)));
+        let (_, streamed) = store.write_object_stream("b", "big", &mut &data[..]).await.unwrap();
+        assert_ne!(streamed, hex::encode(Md5::digest(data)));
+        assert_eq!(store.read_object("b", "big").await.unwrap(), data);
+
+        let (_, part) = store.write_part_stream("upload", 1, &mut &data[..]).await.unwrap();
+        assert_ne!(part, hex::encode(Md5::digest(data)));
+    }
+
+    #[tokio::test]
+    async fn test_concatenate_objects() {
+        let (store, _dir) = temp_store();
+        store.create_bucket_dir("b").await.unwrap();
+        store.write_object("b", "log", b"one\n").await.unwrap();
+        store.write_object("b", "segment", b"two\n").await.unwrap();
+        let sources = vec![
+            store.open_object_file("b", "log").unwrap(),
+            store.open_object_file("b", "segment").unwrap(),
+        ];
+        // Appending to one of the sources
+        let (size, etag) = store.concatenate_objects("b", "log", &sources).await.unwrap();
+        assert_eq!(size, 8);
+        assert!(etag.ends_with("-2"));
+        assert_eq!(store.read_object("b", "log").await.unwrap(), b"one\ntwo\n");
+
+        let missing = vec![store.open_object_file("b", "nope").unwrap()];
+        assert!(matches!(
+            store.concatenate_objects("b", "out", &missing).await,
+            Err(S3Error::NoSuchKey)
+        ));
+        assert!(store.read_object("b", "out").await.is_err());
+        let entries = std::fs::read_dir(store.bucket_path("b")).unwrap().count();
+        assert_eq!(entries, 2);
+    }
+
+    #[test]
+    fn test_escape_segment() {
+        assert_eq!(escape_segment("plain.txt"), "plain.txt");
+        assert_eq!(escape_segment("a:b*c?.txt"), "a%3Ab%2Ac%3F.txt");
+        assert_eq!(escape_segment("100%"), "100%25");
+        assert_eq!(escape_segment("trailing."), "trailing%2E");
+        assert_eq!(escape_segment("trailing "), "trailing%20");
+        assert_eq!(escape_segment("con"), "%63on");
+        assert_eq!(escape_segment("LPT1.log"), "%4CPT1.log");
+        assert_eq!(escape_segment("console"), "console");
+        assert_eq!(escape_segment(""), "%00");
+    }
+
+    #[tokio::test]
+    async fn test_escaped_paths_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path()).with_path_escaping(true);
+        store.create_bucket_dir("b").await.unwrap();
+        for key in ["dir:1/file?.txt", "dir:1/", "aux/nul.", "a%3A"] {
+            store.write_object("b", key, key.as_bytes()).await.unwrap();
+        }
+        for key in ["dir:1/file?.txt", "dir:1/", "aux/nul.", "a%3A"] {
+            assert_eq!(store.read_object("b", key).await.unwrap(), key.as_bytes());
+        }
+        assert!(dir.path().join("b").join("dir%3A1").join("file%3F.txt").exists());
+        assert!(store.write_object("b", "../escape", b"x").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_path_traversal_rejected() {
         let (store, _dir) = temp_store();
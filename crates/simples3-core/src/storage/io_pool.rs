@@ -0,0 +1,110 @@
+//! Dedicated thread pool for object data IO.
+//!
+//! `tokio::fs` runs every file operation on the blocking pool of the runtime
+//! that polls it. An `IoPool` backed by its own runtime makes the futures and
+//! readers it wraps use that runtime's blocking pool instead, so large uploads
+//! and downloads queue behind each other rather than behind everything else
+//! the server runs on blocking threads.
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::runtime::Handle;
+
+/// Thread name of the dedicated pool's blocking threads.
+pub const IO_THREAD_NAME: &str = "simples3-io";
+
+/// Where file IO runs. The default shares the blocking pool of the calling
+/// runtime.
+#[derive(Clone, Default)]
+pub struct IoPool {
+    handle: Option<Handle>,
+}
+
+impl IoPool {
+    /// A pool running at most `threads` file operations at once. The backing
+    /// runtime lives for the rest of the process.
+    pub fn dedicated(threads: usize) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .max_blocking_threads(threads.max(1))
+            .thread_name(IO_THREAD_NAME)
+            .enable_all()
+            .build()?;
+        // Dropping a runtime from async code panics, so it is never dropped
+        let runtime: &'static tokio::runtime::Runtime = Box::leak(Box::new(runtime));
+        Ok(Self {
+            handle: Some(runtime.handle().clone()),
+        })
+    }
+
+    pub fn is_dedicated(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    /// Run the blocking work `fut` starts on this pool.
+    pub fn run<F: Future>(&self, fut: F) -> OnPool<Pin<Box<F>>> {
+        OnPool {
+            handle: self.handle.clone(),
+            inner: Box::pin(fut),
+        }
+    }
+
+    /// Wrap a reader, such as a `tokio::fs::File`, so its reads run on this
+    /// pool.
+    pub fn reader<R: AsyncRead + Unpin>(&self, reader: R) -> OnPool<R> {
+        OnPool {
+            handle: self.handle.clone(),
+            inner: reader,
+        }
+    }
+}
+
+/// A future or reader polled inside the context of an [`IoPool`].
+pub struct OnPool<T> {
+    handle: Option<Handle>,
+    inner: T,
+}
+
+impl<F: Future> Future for OnPool<Pin<Box<F>>> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let _guard = this.handle.as_ref().map(Handle::enter);
+        this.inner.as_mut().poll(cx)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for OnPool<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let _guard = this.handle.as_ref().map(Handle::enter);
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocking_thread_name() -> Option<String> {
+        std::thread::current().name().map(String::from)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dedicated_pool_runs_blocking_work() {
+        let pool = IoPool::dedicated(2).unwrap();
+        let name = pool
+            .run(async { tokio::task::spawn_blocking(blocking_thread_name).await.unwrap() })
+            .await;
+        assert_eq!(name.as_deref(), Some(IO_THREAD_NAME));
+
+        let shared = IoPool::default()
+            .run(async { tokio::task::spawn_blocking(blocking_thread_name).await.unwrap() })
+            .await;
+        assert_ne!(shared.as_deref(), Some(IO_THREAD_NAME));
+    }
+}
@@ -0,0 +1,115 @@
+use crate::s3::types::{ListObjectsV2Request, ListObjectsV2Response};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ListCacheKey {
+    bucket: String,
+    prefix: String,
+    delimiter: String,
+    max_keys: u32,
+    continuation_token: Option<String>,
+    start_after: Option<String>,
+}
+
+impl ListCacheKey {
+    fn from_request(req: &ListObjectsV2Request) -> Self {
+        Self {
+            bucket: req.bucket.clone(),
+            prefix: req.prefix.clone(),
+            delimiter: req.delimiter.clone(),
+            max_keys: req.max_keys,
+            continuation_token: req.continuation_token.clone(),
+            start_after: req.start_after.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<ListCacheKey, (ListObjectsV2Response, u64)>,
+    /// Bumped on every write to a bucket; results computed under an older
+    /// generation are not inserted.
+    generations: HashMap<String, u64>,
+    tick: u64,
+}
+
+/// Bounded LRU cache of ListObjectsV2 results, invalidated per bucket.
+pub struct ListCache {
+    capacity: usize,
+    inner: Mutex<Inner>,
+}
+
+impl ListCache {
+    /// A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+
+    /// Current write generation of a bucket, to be passed back to `insert`.
+    pub fn generation(&self, bucket: &str) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        inner.generations.get(bucket).copied().unwrap_or(0)
+    }
+
+    pub fn get(&self, req: &ListObjectsV2Request) -> Option<ListObjectsV2Response> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let (resp, last_used) = inner.entries.get_mut(&ListCacheKey::from_request(req))?;
+        *last_used = tick;
+        Some(resp.clone())
+    }
+
+    pub fn insert(&self, req: &ListObjectsV2Request, generation: u64, resp: &ListObjectsV2Response) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        if inner.generations.get(&req.bucket).copied().unwrap_or(0) != generation {
+            return;
+        }
+        let key = ListCacheKey::from_request(req);
+        if inner.entries.len() >= self.capacity && !inner.entries.contains_key(&key) {
+            let oldest = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.entries.insert(key, (resp.clone(), tick));
+    }
+
+    /// Drop every cached listing of a bucket after a write to it.
+    pub fn invalidate_bucket(&self, bucket: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        *inner.generations.entry(bucket.to_string()).or_insert(0) += 1;
+        inner.entries.retain(|k, _| k.bucket != bucket);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
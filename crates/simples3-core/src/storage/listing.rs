@@ -0,0 +1,192 @@
+use crate::error::S3Error;
+use crate::s3::types::{ListEntry, ListObjectsV2Request, ListObjectsV2Response, ObjectMeta};
+use std::ops::Bound;
+use std::sync::Arc;
+
+use super::list_cache::ListCache;
+
+/// Largest page kept for the list cache, S3's default `max-keys`. Larger
+/// pages are not cached, so they are never held in memory.
+const MAX_CACHED_ENTRIES: usize = 1000;
+
+/// One ListObjectsV2 page, produced lazily from a sled range scan so a page
+/// over a huge prefix does not have to be held in memory.
+///
+/// The scan starts at the prefix (or just past the continuation token) and
+/// stops at the first key outside the prefix or after `max_keys` entries.
+/// With the list cache enabled, the entries of pages up to
+/// `MAX_CACHED_ENTRIES` are also collected, and the completed page is offered
+/// to the cache once exhausted.
+pub struct ObjectListing {
+    iter: sled::Iter,
+    req: ListObjectsV2Request,
+    remaining: u32,
+    /// Keys under the last common prefix are rolled into it and skipped.
+    last_common_prefix: Option<String>,
+    last_key: Option<String>,
+    is_truncated: bool,
+    done: bool,
+    cache: Option<(Arc<ListCache>, u64, Vec<ListEntry>)>,
+}
+
+impl ObjectListing {
+    pub(crate) fn new(tree: &sled::Tree, req: &ListObjectsV2Request, cache: &Arc<ListCache>) -> Self {
+        let start = req
+            .continuation_token
+            .as_deref()
+            .or(req.start_after.as_deref())
+            .filter(|s| *s >= req.prefix.as_str());
+        let lower = match start {
+            Some(s) => Bound::Excluded(s.as_bytes().to_vec()),
+            None => Bound::Included(req.prefix.as_bytes().to_vec()),
+        };
+        let iter = tree.range::<Vec<u8>, _>((lower, Bound::Unbounded));
+        let last_common_prefix = start.and_then(|s| common_prefix(req, s));
+        let cache = cache
+            .is_enabled()
+            .then(|| (cache.clone(), cache.generation(&req.bucket), Vec::new()));
+        Self {
+            iter,
+            req: req.clone(),
+            remaining: req.max_keys,
+            last_common_prefix,
+            last_key: None,
+            is_truncated: false,
+            done: false,
+            cache,
+        }
+    }
+
+    pub fn request(&self) -> &ListObjectsV2Request {
+        &self.req
+    }
+
+    /// Whether entries remain past this page. Only final once the listing
+    /// has been exhausted.
+    pub fn is_truncated(&self) -> bool {
+        self.is_truncated
+    }
+
+    pub fn next_continuation_token(&self) -> Option<&str> {
+        if self.is_truncated { self.last_key.as_deref() } else { None }
+    }
+
+    /// Drain the listing into a full response.
+    pub fn into_response(mut self) -> Result<ListObjectsV2Response, S3Error> {
+        let mut contents = Vec::new();
+        let mut common_prefixes = Vec::new();
+        for entry in &mut self {
+            match entry? {
+                ListEntry::Object(meta) => contents.push(meta),
+                ListEntry::CommonPrefix(prefix) => common_prefixes.push(prefix),
+            }
+        }
+        Ok(self.response(contents, common_prefixes))
+    }
+
+    fn response(&self, contents: Vec<ObjectMeta>, common_prefixes: Vec<String>) -> ListObjectsV2Response {
+        ListObjectsV2Response {
+            name: self.req.bucket.clone(),
+            prefix: self.req.prefix.clone(),
+            delimiter: self.req.delimiter.clone(),
+            max_keys: self.req.max_keys,
+            is_truncated: self.is_truncated,
+            key_count: contents.len() as u32,
+            contents,
+            common_prefixes,
+            next_continuation_token: self.next_continuation_token().map(str::to_string),
+        }
+    }
+
+    fn finish(&mut self) {
+        self.done = true;
+        if let Some((cache, generation, entries)) = self.cache.take() {
+            let mut contents = Vec::new();
+            let mut common_prefixes = Vec::new();
+            for entry in entries {
+                match entry {
+                    ListEntry::Object(meta) => contents.push(meta),
+                    ListEntry::CommonPrefix(prefix) => common_prefixes.push(prefix),
+                }
+            }
+            cache.insert(&self.req, generation, &self.response(contents, common_prefixes));
+        }
+    }
+}
+
+impl Iterator for ObjectListing {
+    type Item = Result<ListEntry, S3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let (key_bytes, val) = match self.iter.next() {
+                Some(Ok(kv)) => kv,
+                Some(Err(e)) => {
+                    // A failed page is not cached
+                    self.cache = None;
+                    self.done = true;
+                    return Some(Err(S3Error::InternalError(e.to_string())));
+                }
+                None => break,
+            };
+            let key = String::from_utf8_lossy(&key_bytes).into_owned();
+            if !key.starts_with(&self.req.prefix) {
+                break;
+            }
+            if let Some(cp) = &self.last_common_prefix
+                && key.starts_with(cp.as_str())
+            {
+                continue;
+            }
+
+            if self.remaining == 0 {
+                // Like S3, a `max-keys=0` page is not truncated, as there is
+                // no last key to continue from
+                self.is_truncated = self.req.max_keys > 0;
+                break;
+            }
+            let entry = match common_prefix(&self.req, &key) {
+                Some(cp) => {
+                    self.last_common_prefix = Some(cp.clone());
+                    ListEntry::CommonPrefix(cp)
+                }
+                None => match serde_json::from_slice::<ObjectMeta>(&val) {
+                    Ok(meta) => ListEntry::Object(meta),
+                    Err(e) => {
+                        self.cache = None;
+                        self.done = true;
+                        return Some(Err(S3Error::InternalError(e.to_string())));
+                    }
+                },
+            };
+            self.remaining -= 1;
+            self.last_key = Some(match &entry {
+                ListEntry::Object(meta) => meta.key.clone(),
+                ListEntry::CommonPrefix(cp) => cp.clone(),
+            });
+            if let Some((_, _, entries)) = &mut self.cache {
+                if entries.len() < MAX_CACHED_ENTRIES {
+                    entries.push(entry.clone());
+                } else {
+                    self.cache = None;
+                }
+            }
+            return Some(Ok(entry));
+        }
+        if !self.done {
+            self.finish();
+        }
+        None
+    }
+}
+
+/// The common prefix `key` rolls up into: the request prefix plus everything
+/// up to and including the first delimiter after it.
+fn common_prefix(req: &ListObjectsV2Request, key: &str) -> Option<String> {
+    if req.delimiter.is_empty() {
+        return None;
+    }
+    let relative = key.strip_prefix(req.prefix.as_str())?;
+    let idx = relative.find(&req.delimiter)?;
+    Some(key[..req.prefix.len() + idx + req.delimiter.len()].to_string())
+}
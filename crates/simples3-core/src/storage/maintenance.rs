@@ -0,0 +1,147 @@
+//! Metadata store diagnostics, warm-up and offline compaction.
+//!
+//! sled reclaims the space of overwritten and removed entries lazily, so a
+//! store with a lot of churn can take much more room on disk than its live
+//! entries. The tree report shows where the entries are; compaction rewrites
+//! the live entries into a fresh store that replaces the old one.
+//!
+//! sled also reads pages from disk on first use only, so the first request
+//! to each bucket after startup is slow on slow disks; a warm-up reads the
+//! per-bucket settings ahead of requests to load them into sled's cache.
+
+use crate::error::S3Error;
+use serde::{Deserialize, Serialize};
+use sled::Db;
+use std::path::{Path, PathBuf};
+
+/// Entries held by one tree of the metadata store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeStats {
+    pub name: String,
+    pub keys: u64,
+    /// Key and value bytes of the tree's entries.
+    pub bytes: u64,
+}
+
+/// Size of the metadata store on disk and of each of its trees.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataReport {
+    pub size_on_disk: u64,
+    /// Non-empty trees, largest first. Object metadata is in one
+    /// `objects:<bucket>` tree per bucket.
+    pub trees: Vec<TreeStats>,
+}
+
+/// What a warm-up loaded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmUpReport {
+    pub buckets: u64,
+    /// Bucket records, credentials and bucket settings read.
+    pub entries: u64,
+}
+
+/// Outcome of a compaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionReport {
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+pub(crate) fn report(db: &Db) -> Result<MetadataReport, S3Error> {
+    let mut trees = Vec::new();
+    for name in db.tree_names() {
+        let tree = db.open_tree(&name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut stats = TreeStats {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            keys: 0,
+            bytes: 0,
+        };
+        for item in tree.iter() {
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            stats.keys += 1;
+            stats.bytes += (k.len() + v.len()) as u64;
+        }
+        if stats.keys > 0 {
+            trees.push(stats);
+        }
+    }
+    trees.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    Ok(MetadataReport {
+        size_on_disk: db.size_on_disk().map_err(|e| S3Error::InternalError(e.to_string()))?,
+        trees,
+    })
+}
+
+fn sibling(metadata_dir: &Path, suffix: &str) -> PathBuf {
+    let name = metadata_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "metadata".into());
+    metadata_dir.with_file_name(format!("{}.{}", name, suffix))
+}
+
+/// Rewrite the store at `metadata_dir` with only its live entries. The store
+/// must not be open, by this process or a running server.
+pub fn compact(metadata_dir: &Path) -> Result<CompactionReport, S3Error> {
+    let compacted = sibling(metadata_dir, "compacting");
+    let replaced = sibling(metadata_dir, "precompact");
+    for leftover in [&compacted, &replaced] {
+        if leftover.exists() {
+            return Err(S3Error::InternalError(format!(
+                "{} is left over from an interrupted compaction; remove it first",
+                leftover.display()
+            )));
+        }
+    }
+
+    let size_before;
+    {
+        let db = sled::open(metadata_dir).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        size_before = db.size_on_disk().map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let copy = sled::open(&compacted).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        copy.import(db.export());
+        copy.flush().map_err(|e| S3Error::InternalError(e.to_string()))?;
+    }
+
+    std::fs::rename(metadata_dir, &replaced).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    std::fs::rename(&compacted, metadata_dir).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    std::fs::remove_dir_all(&replaced).map_err(|e| S3Error::InternalError(e.to_string()))?;
+
+    let db = sled::open(metadata_dir).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    Ok(CompactionReport {
+        size_before,
+        size_after: db.size_on_disk().map_err(|e| S3Error::InternalError(e.to_string()))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MetadataStore;
+
+    #[test]
+    fn test_compaction_keeps_live_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_dir = dir.path().join("metadata");
+        {
+            let store = MetadataStore::open(&meta_dir).unwrap();
+            store.create_bucket("kept").unwrap();
+            for i in 0..200 {
+                store.create_bucket(&format!("churn-{}", i)).unwrap();
+                store.delete_bucket(&format!("churn-{}", i)).unwrap();
+            }
+            store.create_credential("AKKEPT", "secret", "").unwrap();
+            let report = store.metadata_report().unwrap();
+            assert!(report.trees.iter().any(|t| t.name == "credentials" && t.keys == 1));
+        }
+
+        let report = compact(&meta_dir).unwrap();
+        assert!(report.size_after > 0);
+        assert!(!sibling(&meta_dir, "precompact").exists());
+
+        let store = MetadataStore::open(&meta_dir).unwrap();
+        assert!(store.get_bucket("kept").is_ok());
+        assert!(store.get_credential("AKKEPT").is_ok());
+        assert!(store.get_bucket("churn-0").is_err());
+    }
+}
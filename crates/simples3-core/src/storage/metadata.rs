@@ -1,27 +1,120 @@
 use crate::error::S3Error;
 use crate::s3::types::{
-    AccessKeyRecord, BucketMeta, BucketPolicy, CorsConfiguration, LifecycleConfiguration,
-    ListObjectsV2Request, ListObjectsV2Response, MultipartUpload, ObjectMeta, PartInfo,
+    AccessKeyRecord, ArchiveEntry, AuditEntry, AuditQuery, BucketAdminToken, BucketFreeze, BucketLoggingConfiguration, BucketMeta, BucketStats, BucketUsage, BucketPolicy, CacheHeadersConfig, ConfigHistoryEntry, ConfigKind, CorsConfiguration, CredentialRateLimit,
+    DeletedBucket,
+    LifecycleConfiguration, ListBucketsRequest, ListBucketsResponse, ListObjectsV2Request,
+    ListObjectsV2Response, ModerationState, MultipartUpload, NotificationConfiguration, ObjectLease, ObjectLockConfiguration, ObjectMeta,
+    ObjectModeration, ObjectRestore, ObjectRetention, ObjectScan, PartInfo, PartLayout, Permission, RenditionConfig, RenditionEntry, ReplicationConfiguration, ReplicationQueueStats, ReplicationStatus, ReplicationTask, RequestLogConfig, RetentionMode, ScanStatus, SecurityWebhookConfig, StorageRoute, UsageAlert, UsageAlertConfig, UsageMetric, UsageReportConfig, WebsiteConfiguration,
 };
 use chrono::Utc;
 use sled::Db;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
+use std::sync::Arc;
+
+use super::list_cache::ListCache;
+use super::listing::ObjectListing;
+use super::maintenance::{MetadataReport, WarmUpReport};
+use super::migrations::MigrationReport;
+use super::snapshot::Snapshot;
 
 const BUCKETS_TREE: &str = "buckets";
+const DELETED_BUCKETS_TREE: &str = "deleted_buckets";
 const CREDENTIALS_TREE: &str = "credentials";
 const MULTIPART_TREE: &str = "multipart";
 const TAGGING_TREE: &str = "tagging";
 const LIFECYCLE_TREE: &str = "lifecycle";
 const POLICIES_TREE: &str = "policies";
 const CORS_TREE: &str = "cors";
+const WEBSITE_TREE: &str = "website";
+const CONFIG_HISTORY_TREE: &str = "config_history";
+const SECURITY_WEBHOOKS_TREE: &str = "security_webhooks";
+const CACHE_HEADERS_TREE: &str = "cache_headers";
+const REQUEST_LOG_TREE: &str = "request_log";
+const LOGGING_TREE: &str = "logging";
+const NOTIFICATION_TREE: &str = "notification";
+const REPLICATION_TREE: &str = "replication";
+const REPLICATION_QUEUE_TREE: &str = "replication_queue";
+/// Objects copied to their bucket's replication target since last written.
+const REPLICATION_COMPLETED_TREE: &str = "replication_completed";
+const DEFAULT_TAGS_TREE: &str = "default_tags";
+pub(crate) const STORAGE_ROUTES_TREE: &str = "storage_routes";
+const OBJECT_LOCK_TREE: &str = "object_lock";
+const RETENTION_TREE: &str = "retention";
+/// Restored copies of transitioned objects, kept after the object is deleted
+/// so the copy is still cleaned up when it expires.
+const RESTORE_TREE: &str = "restore";
+const ARCHIVE_TREE: &str = "archive";
+const SCAN_TREE: &str = "scan";
+/// Moderation records of objects that are not active, keyed by `<bucket>:<key>`.
+const MODERATION_TREE: &str = "moderation";
+/// Part sizes of objects created by CompleteMultipartUpload, keyed by `bucket:key`.
+const PART_LAYOUT_TREE: &str = "part_layout";
+const RENDITION_CONFIG_TREE: &str = "rendition_config";
+/// Cached renditions per object, keyed by `bucket:key`.
+const RENDITIONS_TREE: &str = "renditions";
+/// Monthly usage per bucket, keyed by `YYYY-MM/bucket`.
+const USAGE_TREE: &str = "usage";
+/// Usage report target and the last month reported.
+const USAGE_REPORT_TREE: &str = "usage_report";
+/// Admin API mutations, keyed by zero-padded entry ID.
+const AUDIT_TREE: &str = "audit";
+/// Bucket administration tokens, keyed by the hex SHA-256 of the token.
+const BUCKET_ADMIN_TOKENS_TREE: &str = "bucket_admin_tokens";
+/// Object count and stored bytes per bucket, keyed by bucket name.
+pub(crate) const BUCKET_STATS_TREE: &str = "bucket_stats";
+/// Advisory object leases, keyed by `<bucket>:<key>`.
+const LEASES_TREE: &str = "leases";
+/// Usage alert thresholds per bucket.
+const USAGE_ALERT_CONFIG_TREE: &str = "usage_alert_config";
+/// Exceeded usage thresholds, keyed by `<bucket>:<metric>`.
+const USAGE_ALERTS_TREE: &str = "usage_alerts";
+/// Key rewritten by health probes; left out of snapshots.
+pub(crate) const HEALTH_TREE: &str = "health";
+
+/// Upper bound for the `max-buckets` parameter of ListBuckets.
+pub const MAX_BUCKETS_LIMIT: u32 = 10_000;
+
+fn config_tree_name(kind: ConfigKind) -> &'static str {
+    match kind {
+        ConfigKind::Policy => POLICIES_TREE,
+        ConfigKind::Lifecycle => LIFECYCLE_TREE,
+        ConfigKind::Cors => CORS_TREE,
+        ConfigKind::Website => WEBSITE_TREE,
+    }
+}
+
+/// Bucket names cannot contain `/`, so this keeps each bucket's tasks together.
+fn replication_task_key(bucket: &str, key: &str) -> String {
+    format!("{}/{}", bucket, key)
+}
+
+/// Size of a stored object record; unreadable records count as empty.
+fn stored_size(bytes: &[u8]) -> u64 {
+    serde_json::from_slice::<ObjectMeta>(bytes).map(|m| m.size).unwrap_or(0)
+}
+
+/// Trees read on most requests, loaded by [`MetadataStore::warm_up`].
+const WARM_UP_TREES: &[&str] = &[
+    BUCKETS_TREE,
+    CREDENTIALS_TREE,
+    POLICIES_TREE,
+    CORS_TREE,
+    LIFECYCLE_TREE,
+    WEBSITE_TREE,
+    CACHE_HEADERS_TREE,
+    REQUEST_LOG_TREE,
+    OBJECT_LOCK_TREE,
+    DEFAULT_TAGS_TREE,
+    STORAGE_ROUTES_TREE,
+];
 
 fn objects_tree_name(bucket: &str) -> String {
     format!("objects:{}", bucket)
 }
 
 /// Validate bucket name against S3 naming rules.
-fn validate_bucket_name(name: &str) -> Result<(), S3Error> {
+pub(crate) fn validate_bucket_name(name: &str) -> Result<(), S3Error> {
     if name.len() < 3 || name.len() > 63 {
         return Err(S3Error::InvalidArgument(
             "Bucket name must be between 3 and 63 characters".into(),
@@ -55,27 +148,119 @@ fn validate_bucket_name(name: &str) -> Result<(), S3Error> {
 #[derive(Clone)]
 pub struct MetadataStore {
     db: Db,
+    list_cache: Arc<ListCache>,
 }
 
 impl MetadataStore {
     pub fn open(path: &Path) -> Result<Self, S3Error> {
         let db = sled::open(path).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        Ok(Self { db })
+        Ok(Self {
+            db,
+            list_cache: Arc::new(ListCache::new(0)),
+        })
+    }
+
+    /// Cache up to `capacity` ListObjectsV2 results (0 disables the cache).
+    pub fn with_list_cache(mut self, capacity: usize) -> Self {
+        self.list_cache = Arc::new(ListCache::new(capacity));
+        self
+    }
+
+    pub fn list_cache_len(&self) -> usize {
+        self.list_cache.len()
+    }
+
+    /// Write, read back and flush a probe key, to check the store still
+    /// accepts writes.
+    pub fn probe(&self) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(HEALTH_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let value = Utc::now().timestamp_micros().to_be_bytes();
+        tree.insert("probe", &value).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let read = tree.get("probe").map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if read.as_deref() != Some(&value[..]) {
+            return Err(S3Error::InternalError("Health probe read back another value".into()));
+        }
+        self.db.flush().map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Write every pending change to disk. Returns the number of bytes written.
+    pub fn flush(&self) -> Result<usize, S3Error> {
+        self.db.flush().map_err(|e| S3Error::InternalError(e.to_string()))
+    }
+
+    // --- Schema migrations ---
+
+    pub fn schema_version(&self) -> Result<u32, S3Error> {
+        super::migrations::schema_version(&self.db)
+    }
+
+    /// Upgrade the store to the current schema, backing it up next to
+    /// `metadata_dir` first if any migration has to run.
+    pub fn run_migrations(&self, metadata_dir: &Path) -> Result<MigrationReport, S3Error> {
+        super::migrations::run(&self.db, metadata_dir)
+    }
+
+    /// Key counts and sizes of every tree, to see what the store holds.
+    pub fn metadata_report(&self) -> Result<MetadataReport, S3Error> {
+        super::maintenance::report(&self.db)
+    }
+
+    /// Read the bucket records, credentials and bucket settings, and the
+    /// first object entry of every bucket, so that sled has them in memory
+    /// before the first requests.
+    pub fn warm_up(&self) -> Result<WarmUpReport, S3Error> {
+        let mut report = WarmUpReport::default();
+        for name in WARM_UP_TREES {
+            let tree = self.db.open_tree(name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            for item in tree.iter() {
+                let (key, _) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+                report.entries += 1;
+                if *name == BUCKETS_TREE {
+                    let objects = self
+                        .db
+                        .open_tree(objects_tree_name(&String::from_utf8_lossy(&key)))
+                        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+                    objects.first().map_err(|e| S3Error::InternalError(e.to_string()))?;
+                    report.buckets += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Capture every tree plus a data-file manifest for shipping to a standby.
+    pub fn create_snapshot(&self) -> Result<Snapshot, S3Error> {
+        super::snapshot::create(&self.db)
     }
 
     // --- Bucket operations ---
 
     pub fn create_bucket(&self, name: &str) -> Result<BucketMeta, S3Error> {
+        self.create_bucket_at(name, Utc::now())
+    }
+
+    /// Create a bucket at `now`, which is also when a reserved name's grace
+    /// period is checked against.
+    pub fn create_bucket_at(&self, name: &str, now: chrono::DateTime<Utc>) -> Result<BucketMeta, S3Error> {
         validate_bucket_name(name)?;
+        if let Some(deleted) = self.get_deleted_bucket(name)? {
+            if deleted.purge_after > now {
+                return Err(S3Error::OperationAborted);
+            }
+            self.purge_deleted_bucket(name)?;
+        }
         let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         if tree.contains_key(name).map_err(|e| S3Error::InternalError(e.to_string()))? {
             return Err(S3Error::BucketAlreadyExists);
         }
         let meta = BucketMeta {
             name: name.to_string(),
-            creation_date: Utc::now(),
+            creation_date: now,
             anonymous_read: false,
             anonymous_list_public: false,
+            deletion_protected: false,
+            frozen: None,
         };
         let json = serde_json::to_vec(&meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.insert(name, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
@@ -105,9 +290,84 @@ impl MetadataStore {
         Ok(buckets)
     }
 
+    /// List buckets in name order, filtered by prefix and paginated with a
+    /// continuation token (the last bucket name of the previous page).
+    pub fn list_buckets_page(&self, req: &ListBucketsRequest) -> Result<ListBucketsResponse, S3Error> {
+        let max = match req.max_buckets {
+            Some(n) if n == 0 || n > MAX_BUCKETS_LIMIT => {
+                return Err(S3Error::InvalidArgument(format!(
+                    "max-buckets must be between 1 and {}",
+                    MAX_BUCKETS_LIMIT
+                )));
+            }
+            Some(n) => n as usize,
+            None => usize::MAX,
+        };
+        let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut buckets = Vec::new();
+        let mut truncated = false;
+        // sled iterates keys in byte order, which gives a stable name ordering.
+        for item in tree.scan_prefix(req.prefix.as_bytes()) {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if let Some(ref token) = req.continuation_token
+                && key.as_ref() <= token.as_bytes()
+            {
+                continue;
+            }
+            if buckets.len() == max {
+                truncated = true;
+                break;
+            }
+            let meta: BucketMeta =
+                serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            buckets.push(meta);
+        }
+        let continuation_token = if truncated {
+            buckets.last().map(|b| b.name.clone())
+        } else {
+            None
+        };
+        Ok(ListBucketsResponse {
+            buckets,
+            prefix: req.prefix.clone(),
+            continuation_token,
+        })
+    }
+
     pub fn delete_bucket(&self, name: &str) -> Result<(), S3Error> {
+        let _ = self.remove_bucket_entry(name)?;
+        self.remove_bucket_configs(name)
+    }
+
+    /// Delete a bucket but keep its name reserved (and its policy, lifecycle
+    /// and CORS configuration restorable) for `grace_secs` from `now`. A zero
+    /// grace period deletes the bucket immediately.
+    pub fn delete_bucket_with_grace(&self, name: &str, grace_secs: u64, now: chrono::DateTime<Utc>) -> Result<(), S3Error> {
+        if grace_secs == 0 {
+            return self.delete_bucket(name);
+        }
+        let meta = self.remove_bucket_entry(name)?;
+        let deleted = DeletedBucket {
+            meta,
+            deleted_at: now,
+            purge_after: now + chrono::Duration::seconds(grace_secs as i64),
+        };
+        let tree = self.db.open_tree(DELETED_BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&deleted).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(name, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Remove an empty bucket from the bucket tree and drop its object tree.
+    fn remove_bucket_entry(&self, name: &str) -> Result<BucketMeta, S3Error> {
         // Check bucket exists
-        let _ = self.get_bucket(name)?;
+        let meta = self.get_bucket(name)?;
+        if meta.deletion_protected {
+            return Err(S3Error::BucketDeletionProtected);
+        }
+        if meta.frozen.is_some() {
+            return Err(S3Error::BucketFrozen);
+        }
 
         // Check bucket is empty
         let obj_tree_name = objects_tree_name(name);
@@ -119,18 +379,138 @@ impl MetadataStore {
         let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.remove(name).map_err(|e| S3Error::InternalError(e.to_string()))?;
         self.db.drop_tree(&obj_tree_name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let stats_tree = self.db.open_tree(BUCKET_STATS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        stats_tree.remove(name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.list_cache.invalidate_bucket(name);
+        Ok(meta)
+    }
 
-        // Clean up lifecycle, policy, and CORS entries
+    /// Clean up lifecycle, policy, CORS, website and history entries of a bucket.
+    fn remove_bucket_configs(&self, name: &str) -> Result<(), S3Error> {
         let lifecycle_tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let _ = lifecycle_tree.remove(name);
         let policies_tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let _ = policies_tree.remove(name);
         let cors_tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let _ = cors_tree.remove(name);
+        let website_tree = self.db.open_tree(WEBSITE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = website_tree.remove(name);
+        let history_tree = self.db.open_tree(CONFIG_HISTORY_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, _) in history_tree.scan_prefix(format!("{}:", name)).flatten() {
+            let _ = history_tree.remove(key);
+        }
+        let webhooks_tree = self.db.open_tree(SECURITY_WEBHOOKS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = webhooks_tree.remove(name);
+        let cache_headers_tree = self.db.open_tree(CACHE_HEADERS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = cache_headers_tree.remove(name);
+        let rendition_config_tree = self.db.open_tree(RENDITION_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = rendition_config_tree.remove(name);
+        let tag_tree = self.db.open_tree(TAGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, _) in tag_tree.scan_prefix(format!("{}:", name)).flatten() {
+            let _ = tag_tree.remove(key);
+        }
+        let leases_tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, _) in leases_tree.scan_prefix(format!("{}:", name)).flatten() {
+            let _ = leases_tree.remove(key);
+        }
+        let moderation_tree = self.db.open_tree(MODERATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, _) in moderation_tree.scan_prefix(format!("{}:", name)).flatten() {
+            let _ = moderation_tree.remove(key);
+        }
+        let alert_config_tree = self.db.open_tree(USAGE_ALERT_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = alert_config_tree.remove(name);
+        let alerts_tree = self.db.open_tree(USAGE_ALERTS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, _) in alerts_tree.scan_prefix(format!("{}:", name)).flatten() {
+            let _ = alerts_tree.remove(key);
+        }
+        let request_log_tree = self.db.open_tree(REQUEST_LOG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = request_log_tree.remove(name);
+        let logging_tree = self.db.open_tree(LOGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = logging_tree.remove(name);
+        let notification_tree = self.db.open_tree(NOTIFICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = notification_tree.remove(name);
+        let replication_tree = self.db.open_tree(REPLICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = replication_tree.remove(name);
+        self.drop_replication_tasks(name)?;
+        let default_tags_tree = self.db.open_tree(DEFAULT_TAGS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = default_tags_tree.remove(name);
+        let routes_tree = self.db.open_tree(STORAGE_ROUTES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = routes_tree.remove(name);
+        let object_lock_tree = self.db.open_tree(OBJECT_LOCK_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let _ = object_lock_tree.remove(name);
+        // A bucket created later under the same name must not be reachable
+        // with what was granted for this one
+        let tokens_tree = self.db.open_tree(BUCKET_ADMIN_TOKENS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for (key, val) in tokens_tree.iter().flatten() {
+            if serde_json::from_slice::<BucketAdminToken>(&val).is_ok_and(|t| t.bucket == name) {
+                let _ = tokens_tree.remove(key);
+            }
+        }
+        for credential in self.list_credentials()? {
+            if credential.bucket.as_deref() == Some(name) && credential.active {
+                self.revoke_credential(&credential.access_key_id)?;
+            }
+        }
 
         Ok(())
     }
 
+    pub fn get_deleted_bucket(&self, name: &str) -> Result<Option<DeletedBucket>, S3Error> {
+        let tree = self.db.open_tree(DELETED_BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(name).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn list_deleted_buckets(&self) -> Result<Vec<DeletedBucket>, S3Error> {
+        let tree = self.db.open_tree(DELETED_BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut deleted = Vec::new();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let entry: DeletedBucket =
+                serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            deleted.push(entry);
+        }
+        Ok(deleted)
+    }
+
+    /// Bring back a bucket deleted within its grace period, with its
+    /// original creation date and settings.
+    pub fn restore_deleted_bucket(&self, name: &str) -> Result<BucketMeta, S3Error> {
+        let deleted = self.get_deleted_bucket(name)?.ok_or(S3Error::NoSuchBucket)?;
+        let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&deleted.meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(name, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let deleted_tree = self.db.open_tree(DELETED_BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        deleted_tree.remove(name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(deleted.meta)
+    }
+
+    /// Release a reserved bucket name immediately and drop its configuration.
+    pub fn purge_deleted_bucket(&self, name: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(DELETED_BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if tree.remove(name).map_err(|e| S3Error::InternalError(e.to_string()))?.is_none() {
+            return Err(S3Error::NoSuchBucket);
+        }
+        self.remove_bucket_configs(name)
+    }
+
+    /// Purge every deleted bucket whose grace period ended before `now`.
+    /// Returns the names that were released.
+    pub fn purge_expired_deleted_buckets(&self, now: chrono::DateTime<Utc>) -> Result<Vec<String>, S3Error> {
+        let mut purged = Vec::new();
+        for deleted in self.list_deleted_buckets()? {
+            if deleted.purge_after <= now {
+                self.purge_deleted_bucket(&deleted.meta.name)?;
+                purged.push(deleted.meta.name);
+            }
+        }
+        Ok(purged)
+    }
+
     pub fn set_bucket_anonymous_read(&self, name: &str, anonymous: bool) -> Result<(), S3Error> {
         let mut meta = self.get_bucket(name)?;
         meta.anonymous_read = anonymous;
@@ -149,14 +529,101 @@ impl MetadataStore {
         Ok(())
     }
 
+    pub fn set_bucket_deletion_protected(&self, name: &str, protected: bool) -> Result<(), S3Error> {
+        let mut meta = self.get_bucket(name)?;
+        meta.deletion_protected = protected;
+        let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(name, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn set_bucket_freeze(&self, name: &str, freeze: Option<BucketFreeze>) -> Result<(), S3Error> {
+        let mut meta = self.get_bucket(name)?;
+        meta.frozen = freeze;
+        let tree = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(name, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Archive manifest ---
+
+    pub fn get_archive_entry(&self, bucket: &str, key: &str) -> Result<Option<ArchiveEntry>, S3Error> {
+        let tree = self.db.open_tree(ARCHIVE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_archive_entry(&self, bucket: &str, entry: &ArchiveEntry) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(ARCHIVE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let bytes = serde_json::to_vec(entry).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(format!("{}:{}", bucket, entry.key).as_bytes(), bytes)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// The manifest of a frozen bucket, in key order.
+    pub fn list_archive_entries(&self, bucket: &str) -> Result<Vec<ArchiveEntry>, S3Error> {
+        let tree = self.db.open_tree(ARCHIVE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut entries = Vec::new();
+        for item in tree.scan_prefix(format!("{}:", bucket).as_bytes()) {
+            let (_, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            entries.push(serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?);
+        }
+        Ok(entries)
+    }
+
+    pub fn delete_archive_entries(&self, bucket: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(ARCHIVE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for item in tree.scan_prefix(format!("{}:", bucket).as_bytes()) {
+            let (k, _) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            tree.remove(k).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     // --- Object metadata ---
 
     pub fn put_object_meta(&self, meta: &ObjectMeta) -> Result<(), S3Error> {
         let tree_name = objects_tree_name(&meta.bucket);
         let tree = self.db.open_tree(&tree_name).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let json = serde_json::to_vec(meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.insert(&meta.key, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        Ok(())
+        let previous = tree.insert(&meta.key, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.list_cache.invalidate_bucket(&meta.bucket);
+        match previous.as_deref().map(stored_size) {
+            Some(size) => self.adjust_bucket_stats(&meta.bucket, 0, meta.size as i64 - size as i64),
+            None => self.adjust_bucket_stats(&meta.bucket, 1, meta.size as i64),
+        }
+    }
+
+    /// Replace the metadata of `current.bucket/current.key` with `updated`
+    /// unless the object was rewritten or deleted since `current` was read.
+    /// Returns whether it was replaced.
+    pub fn update_object_meta_if_unchanged(&self, current: &ObjectMeta, updated: &ObjectMeta) -> Result<bool, S3Error> {
+        let tree = self.db.open_tree(objects_tree_name(&current.bucket)).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(bytes) = tree.get(&current.key).map_err(|e| S3Error::InternalError(e.to_string()))? else {
+            return Ok(false);
+        };
+        let stored: ObjectMeta = serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if stored.etag != current.etag
+            || stored.last_modified != current.last_modified
+            || stored.storage_class != current.storage_class
+        {
+            return Ok(false);
+        }
+        let json = serde_json::to_vec(updated).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let swapped = tree
+            .compare_and_swap(&current.key, Some(bytes), Some(json))
+            .map_err(|e| S3Error::InternalError(e.to_string()))?
+            .is_ok();
+        if swapped {
+            self.list_cache.invalidate_bucket(&current.bucket);
+            self.adjust_bucket_stats(&current.bucket, 0, updated.size as i64 - stored.size as i64)?;
+        }
+        Ok(swapped)
     }
 
     pub fn get_object_meta(&self, bucket: &str, key: &str) -> Result<ObjectMeta, S3Error> {
@@ -174,86 +641,82 @@ impl MetadataStore {
     pub fn delete_object_meta(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
         let tree_name = objects_tree_name(bucket);
         let tree = self.db.open_tree(&tree_name).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.remove(key).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let removed = tree.remove(key).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.list_cache.invalidate_bucket(bucket);
+        if let Some(removed) = removed {
+            self.adjust_bucket_stats(bucket, -1, -(stored_size(&removed) as i64))?;
+        }
         // Clean up any tagging for this object
         let tag_tree = self.db.open_tree(TAGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let tag_key = format!("{}:{}", bucket, key);
         tag_tree.remove(tag_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let retention_tree = self.db.open_tree(RETENTION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        retention_tree.remove(tag_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let scan_tree = self.db.open_tree(SCAN_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        scan_tree.remove(tag_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let moderation_tree = self.db.open_tree(MODERATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        moderation_tree.remove(tag_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let layout_tree = self.db.open_tree(PART_LAYOUT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        layout_tree.remove(tag_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let completed_tree = self.db.open_tree(REPLICATION_COMPLETED_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        completed_tree
+            .remove(replication_task_key(bucket, key))
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn list_objects_v2(&self, req: &ListObjectsV2Request) -> Result<ListObjectsV2Response, S3Error> {
-        let tree_name = objects_tree_name(&req.bucket);
-        let tree = self.db.open_tree(&tree_name).map_err(|e| S3Error::InternalError(e.to_string()))?;
-
-        let mut all_objects: Vec<ObjectMeta> = Vec::new();
-        let prefix_bytes = req.prefix.as_bytes();
+    fn adjust_bucket_stats(&self, bucket: &str, objects: i64, bytes: i64) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(BUCKET_STATS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.update_and_fetch(bucket, |current| {
+            let mut stats: BucketStats = current
+                .and_then(|bytes| serde_json::from_slice(bytes).ok())
+                .unwrap_or_default();
+            stats.adjust(objects, bytes);
+            serde_json::to_vec(&stats).ok()
+        })
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
 
-        for item in tree.iter() {
-            let (key_bytes, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
-            let key_str = String::from_utf8_lossy(&key_bytes);
-            if key_str.as_bytes().starts_with(prefix_bytes) {
-                let meta: ObjectMeta = serde_json::from_slice(&val)
-                    .map_err(|e| S3Error::InternalError(e.to_string()))?;
-                all_objects.push(meta);
-            }
+    /// Object count and stored bytes of a bucket, without walking its objects.
+    pub fn bucket_stats(&self, bucket: &str) -> Result<BucketStats, S3Error> {
+        let tree = self.db.open_tree(BUCKET_STATS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(BucketStats::default()),
         }
+    }
 
-        // Sort by key
-        all_objects.sort_by(|a, b| a.key.cmp(&b.key));
-
-        // Apply start_after or continuation_token
-        let start_after = req
-            .continuation_token
-            .as_deref()
-            .or(req.start_after.as_deref());
-        if let Some(start) = start_after {
-            all_objects.retain(|o| o.key.as_str() > start);
+    /// Object count and stored bytes of every bucket with objects, by bucket name.
+    pub fn list_bucket_stats(&self) -> Result<Vec<(String, BucketStats)>, S3Error> {
+        let tree = self.db.open_tree(BUCKET_STATS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut stats = Vec::new();
+        for item in tree.iter() {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let entry = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            stats.push((String::from_utf8_lossy(&key).into_owned(), entry));
         }
+        Ok(stats)
+    }
 
-        // Handle delimiter grouping
-        let mut contents = Vec::new();
-        let mut common_prefixes = std::collections::BTreeSet::new();
-
-        if req.delimiter.is_empty() {
-            contents = all_objects;
-        } else {
-            for obj in &all_objects {
-                let relative = &obj.key[req.prefix.len()..];
-                if let Some(idx) = relative.find(&req.delimiter) {
-                    let cp = format!("{}{}", &req.prefix, &relative[..=idx]);
-                    common_prefixes.insert(cp);
-                } else {
-                    contents.push(obj.clone());
-                }
-            }
+    /// List objects, serving repeated identical listings from the cache.
+    pub fn list_objects_v2(&self, req: &ListObjectsV2Request) -> Result<ListObjectsV2Response, S3Error> {
+        if let Some(resp) = self.list_cache.get(req) {
+            return Ok(resp);
         }
+        self.stream_objects_v2(req)?.into_response()
+    }
 
-        let common_prefixes: Vec<String> = common_prefixes.into_iter().collect();
-        let total_count = contents.len() as u32 + common_prefixes.len() as u32;
-        let is_truncated = total_count > req.max_keys;
-
-        let max = req.max_keys as usize;
-        let truncated_contents: Vec<ObjectMeta> = contents.into_iter().take(max).collect();
-        let next_token = if is_truncated {
-            truncated_contents.last().map(|o| o.key.clone())
-        } else {
-            None
-        };
-
-        let key_count = truncated_contents.len() as u32;
+    /// A cached ListObjectsV2 page, if there is one.
+    pub fn cached_objects_v2(&self, req: &ListObjectsV2Request) -> Option<ListObjectsV2Response> {
+        self.list_cache.get(req)
+    }
 
-        Ok(ListObjectsV2Response {
-            name: req.bucket.clone(),
-            prefix: req.prefix.clone(),
-            delimiter: req.delimiter.clone(),
-            max_keys: req.max_keys,
-            is_truncated,
-            contents: truncated_contents,
-            common_prefixes,
-            next_continuation_token: next_token,
-            key_count,
-        })
+    /// Scan one ListObjectsV2 page lazily, without consulting the cache. The
+    /// page is cached once the listing has been fully read.
+    pub fn stream_objects_v2(&self, req: &ListObjectsV2Request) -> Result<ObjectListing, S3Error> {
+        let tree = self.db.open_tree(objects_tree_name(&req.bucket)).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(ObjectListing::new(&tree, req, &self.list_cache))
     }
 
     // --- Tagging operations ---
@@ -288,104 +751,586 @@ impl MetadataStore {
         Ok(())
     }
 
-    // --- Credential operations ---
-
-    pub fn create_credential(&self, access_key_id: &str, secret_access_key: &str, description: &str) -> Result<AccessKeyRecord, S3Error> {
-        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        if tree.contains_key(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))? {
-            return Err(S3Error::InvalidArgument("Credential already exists".into()));
+    /// Set the tags of a newly uploaded object: the bucket's default tags
+    /// overlaid with `client_tags`, which win on conflict. Replaces any tags
+    /// left from a previous object under the same key.
+    pub fn set_uploaded_object_tagging(
+        &self,
+        bucket: &str,
+        key: &str,
+        client_tags: &HashMap<String, String>,
+    ) -> Result<(), S3Error> {
+        let mut tags = self.get_bucket_default_tags(bucket)?;
+        tags.extend(client_tags.iter().map(|(k, v)| (k.clone(), v.clone())));
+        if tags.is_empty() {
+            let tree = self.db.open_tree(TAGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            tree.remove(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(());
         }
-        let record = AccessKeyRecord {
-            access_key_id: access_key_id.to_string(),
-            secret_access_key: secret_access_key.to_string(),
-            description: description.to_string(),
-            created: Utc::now(),
-            active: true,
-        };
-        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        Ok(record)
+        self.put_object_tagging(bucket, key, &tags)
     }
 
-    pub fn get_credential(&self, access_key_id: &str) -> Result<AccessKeyRecord, S3Error> {
-        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        match val {
-            Some(bytes) => {
-                serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))
+    /// Remove tag entries whose object no longer exists, returning how many
+    /// were removed. An entry rewritten while the pass runs is kept.
+    pub fn remove_orphaned_tags(&self) -> Result<usize, S3Error> {
+        let tree = self.db.open_tree(TAGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let buckets = self.db.open_tree(BUCKETS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        // Object trees of existing buckets; `None` for buckets that are gone
+        let mut object_trees: HashMap<String, Option<sled::Tree>> = HashMap::new();
+        let mut removed = 0;
+        for entry in tree.iter() {
+            let (tag_key, value) = entry.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let orphaned = match std::str::from_utf8(&tag_key).ok().and_then(|k| k.split_once(':')) {
+                Some((bucket, key)) => {
+                    if !object_trees.contains_key(bucket) {
+                        let objects = if buckets.contains_key(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+                            Some(
+                                self.db
+                                    .open_tree(objects_tree_name(bucket))
+                                    .map_err(|e| S3Error::InternalError(e.to_string()))?,
+                            )
+                        } else {
+                            None
+                        };
+                        object_trees.insert(bucket.to_string(), objects);
+                    }
+                    match &object_trees[bucket] {
+                        Some(objects) => !objects.contains_key(key).map_err(|e| S3Error::InternalError(e.to_string()))?,
+                        None => true,
+                    }
+                }
+                None => true,
+            };
+            if orphaned
+                && tree
+                    .compare_and_swap(&tag_key, Some(value), None as Option<&[u8]>)
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?
+                    .is_ok()
+            {
+                removed += 1;
             }
-            None => Err(S3Error::AccessDenied),
         }
+        Ok(removed)
     }
 
-    pub fn list_credentials(&self) -> Result<Vec<AccessKeyRecord>, S3Error> {
-        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let mut creds = Vec::new();
-        for item in tree.iter() {
-            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
-            let record: AccessKeyRecord =
-                serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
-            creds.push(record);
-        }
-        Ok(creds)
+    // --- Bucket default tags ---
+
+    pub fn put_bucket_default_tags(&self, bucket: &str, tags: &HashMap<String, String>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(DEFAULT_TAGS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(tags).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
     }
 
-    pub fn revoke_credential(&self, access_key_id: &str) -> Result<(), S3Error> {
-        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        match val {
-            Some(bytes) => {
-                let mut record: AccessKeyRecord =
-                    serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
-                record.active = false;
-                let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
-                tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
-                Ok(())
-            }
-            None => Err(S3Error::AccessDenied),
+    /// Default tags of a bucket; empty when none are set. Does not check that the bucket exists.
+    pub fn get_bucket_default_tags(&self, bucket: &str) -> Result<HashMap<String, String>, S3Error> {
+        let tree = self.db.open_tree(DEFAULT_TAGS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(HashMap::new()),
         }
     }
 
-    pub fn delete_credential(&self, access_key_id: &str) -> Result<(), S3Error> {
-        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.remove(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    pub fn delete_bucket_default_tags(&self, bucket: &str) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(DEFAULT_TAGS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    // --- Multipart operations ---
+    // --- Object Lock ---
 
-    pub fn create_multipart_upload(&self, upload: &MultipartUpload) -> Result<(), S3Error> {
-        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let json = serde_json::to_vec(upload).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.insert(&upload.upload_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    pub fn put_object_lock_configuration(&self, bucket: &str, config: &ObjectLockConfiguration) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(OBJECT_LOCK_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn get_multipart_upload(&self, upload_id: &str) -> Result<MultipartUpload, S3Error> {
-        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let val = tree.get(upload_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        match val {
-            Some(bytes) => {
-                serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))
-            }
-            None => Err(S3Error::NoSuchUpload),
+    pub fn get_object_lock_configuration(&self, bucket: &str) -> Result<ObjectLockConfiguration, S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(OBJECT_LOCK_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::ObjectLockConfigurationNotFound),
         }
     }
 
-    pub fn add_part_to_upload(&self, upload_id: &str, part: PartInfo) -> Result<(), S3Error> {
-        let mut upload = self.get_multipart_upload(upload_id)?;
-        upload.parts.retain(|p| p.part_number != part.part_number);
-        upload.parts.push(part);
-        upload.parts.sort_by_key(|p| p.part_number);
-        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let json = serde_json::to_vec(&upload).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        tree.insert(upload_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        Ok(())
+    fn object_lock_enabled(&self, bucket: &str) -> Result<Option<ObjectLockConfiguration>, S3Error> {
+        match self.get_object_lock_configuration(bucket) {
+            Ok(config) => Ok(Some(config)),
+            Err(S3Error::ObjectLockConfigurationNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    pub fn count_multipart_uploads(&self) -> Result<usize, S3Error> {
-        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        Ok(tree.len())
+    pub fn get_object_restore(&self, bucket: &str, key: &str) -> Result<Option<ObjectRestore>, S3Error> {
+        let tree = self.db.open_tree(RESTORE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_object_restore(&self, bucket: &str, key: &str, restore: &ObjectRestore) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(RESTORE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let bytes = serde_json::to_vec(restore).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(format!("{}:{}", bucket, key).as_bytes(), bytes)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn delete_object_restore(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(RESTORE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(format!("{}:{}", bucket, key).as_bytes())
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every restore record as `(bucket, key, restore)`, expired or not.
+    pub fn list_object_restores(&self) -> Result<Vec<(String, String, ObjectRestore)>, S3Error> {
+        let tree = self.db.open_tree(RESTORE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut restores = Vec::new();
+        for item in tree.iter() {
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let name = String::from_utf8_lossy(&k);
+            // Bucket names cannot contain ':'
+            let Some((bucket, key)) = name.split_once(':') else {
+                continue;
+            };
+            let restore: ObjectRestore = serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            restores.push((bucket.to_string(), key.to_string(), restore));
+        }
+        Ok(restores)
+    }
+
+    /// Part sizes of a multipart object. The record may describe an older
+    /// version: check its etag.
+    pub fn get_part_layout(&self, bucket: &str, key: &str) -> Result<Option<PartLayout>, S3Error> {
+        let tree = self.db.open_tree(PART_LAYOUT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_part_layout(&self, bucket: &str, key: &str, layout: &PartLayout) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(PART_LAYOUT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let bytes = serde_json::to_vec(layout).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(format!("{}:{}", bucket, key).as_bytes(), bytes)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_object_scan(&self, bucket: &str, key: &str) -> Result<Option<ObjectScan>, S3Error> {
+        let tree = self.db.open_tree(SCAN_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn put_object_scan(&self, bucket: &str, key: &str, scan: &ObjectScan) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(SCAN_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let bytes = serde_json::to_vec(scan).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(format!("{}:{}", bucket, key).as_bytes(), bytes)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Scan records as `(bucket, key, scan)`, of one bucket or of all, in key
+    /// order, optionally limited to one status.
+    pub fn list_object_scans(
+        &self,
+        bucket: Option<&str>,
+        status: Option<ScanStatus>,
+    ) -> Result<Vec<(String, String, ObjectScan)>, S3Error> {
+        let tree = self.db.open_tree(SCAN_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let prefix = bucket.map(|b| format!("{}:", b)).unwrap_or_default();
+        let mut scans = Vec::new();
+        for item in tree.scan_prefix(prefix.as_bytes()) {
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let name = String::from_utf8_lossy(&k);
+            // Bucket names cannot contain ':'
+            let Some((bucket, key)) = name.split_once(':') else {
+                continue;
+            };
+            let scan: ObjectScan = serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if status.is_none_or(|s| s == scan.status) {
+                scans.push((bucket.to_string(), key.to_string(), scan));
+            }
+        }
+        Ok(scans)
+    }
+
+    /// Moderation record of an object; `None` when it is active.
+    pub fn get_object_moderation(&self, bucket: &str, key: &str) -> Result<Option<ObjectModeration>, S3Error> {
+        let tree = self.db.open_tree(MODERATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the moderation state of an existing object. Setting it `active`
+    /// removes the record. The state is kept when the object is overwritten
+    /// and removed with it.
+    pub fn set_object_moderation(&self, bucket: &str, key: &str, moderation: &ObjectModeration) -> Result<(), S3Error> {
+        self.get_object_meta(bucket, key)?;
+        let tree = self.db.open_tree(MODERATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree_key = format!("{}:{}", bucket, key);
+        if moderation.state == ModerationState::Active {
+            tree.remove(tree_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        } else {
+            let bytes = serde_json::to_vec(moderation).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            tree.insert(tree_key.as_bytes(), bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Moderation records of a bucket's objects as `(key, moderation)`, in
+    /// key order, optionally limited to one state.
+    pub fn list_object_moderation(
+        &self,
+        bucket: &str,
+        state: Option<ModerationState>,
+    ) -> Result<Vec<(String, ObjectModeration)>, S3Error> {
+        let tree = self.db.open_tree(MODERATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let prefix = format!("{}:", bucket);
+        let mut records = Vec::new();
+        for item in tree.scan_prefix(prefix.as_bytes()) {
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let key = String::from_utf8_lossy(&k[prefix.len()..]).into_owned();
+            let moderation: ObjectModeration =
+                serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if state.is_none_or(|s| s == moderation.state) {
+                records.push((key, moderation));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Stored retention of an object, whether or not it is still active.
+    pub fn get_object_retention(&self, bucket: &str, key: &str) -> Result<Option<ObjectRetention>, S3Error> {
+        let tree = self.db.open_tree(RETENTION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Set the retention of an existing object. An active COMPLIANCE retention
+    /// can only be extended; an active GOVERNANCE retention can only be
+    /// shortened or switched to another mode with `bypass_governance`.
+    pub fn put_object_retention(
+        &self,
+        bucket: &str,
+        key: &str,
+        retention: &ObjectRetention,
+        bypass_governance: bool,
+    ) -> Result<(), S3Error> {
+        if self.object_lock_enabled(bucket)?.is_none() {
+            return Err(S3Error::InvalidArgument("Bucket is missing Object Lock Configuration".into()));
+        }
+        let _ = self.get_object_meta(bucket, key)?;
+        let now = Utc::now();
+        if retention.retain_until <= now {
+            return Err(S3Error::InvalidArgument("The retain until date must be in the future".into()));
+        }
+        if let Some(current) = self.get_object_retention(bucket, key)?
+            && current.is_active(now)
+        {
+            // Moving GOVERNANCE to COMPLIANCE strengthens the lock; the reverse weakens it
+            let weakened = retention.retain_until < current.retain_until
+                || (current.mode == RetentionMode::Compliance && retention.mode == RetentionMode::Governance);
+            let allowed = match current.mode {
+                RetentionMode::Compliance => !weakened,
+                RetentionMode::Governance => !weakened || bypass_governance,
+            };
+            if !allowed {
+                return Err(S3Error::AccessDenied);
+            }
+        }
+        self.store_object_retention(bucket, key, Some(retention))
+    }
+
+    fn store_object_retention(&self, bucket: &str, key: &str, retention: Option<&ObjectRetention>) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(RETENTION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree_key = format!("{}:{}", bucket, key);
+        match retention {
+            Some(retention) => {
+                let json = serde_json::to_vec(retention).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                tree.insert(tree_key.as_bytes(), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+            None => {
+                tree.remove(tree_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn an object's legal hold on or off. Requires Object Lock on the bucket.
+    pub fn put_object_legal_hold(&self, bucket: &str, key: &str, on: bool) -> Result<(), S3Error> {
+        if self.object_lock_enabled(bucket)?.is_none() {
+            return Err(S3Error::InvalidArgument("Bucket is missing Object Lock Configuration".into()));
+        }
+        let mut meta = self.get_object_meta(bucket, key)?;
+        meta.legal_hold = on;
+        self.put_object_meta(&meta)
+    }
+
+    /// Fail with `AccessDenied` if a legal hold or an active retention protects
+    /// the object from being deleted or overwritten. Missing objects are not locked.
+    pub fn ensure_object_unlocked(&self, bucket: &str, key: &str, bypass_governance: bool) -> Result<(), S3Error> {
+        if let Ok(meta) = self.get_object_meta(bucket, key)
+            && meta.legal_hold
+        {
+            return Err(S3Error::AccessDenied);
+        }
+        match self.get_object_retention(bucket, key)? {
+            Some(retention) if retention.is_active(Utc::now()) => match retention.mode {
+                RetentionMode::Governance if bypass_governance => Ok(()),
+                _ => Err(S3Error::AccessDenied),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Set the retention of a newly uploaded object: `requested` if the client
+    /// sent one, otherwise the bucket's default retention. Replaces any
+    /// retention left from a previous object under the same key.
+    pub fn set_uploaded_object_retention(
+        &self,
+        bucket: &str,
+        key: &str,
+        requested: Option<&ObjectRetention>,
+    ) -> Result<(), S3Error> {
+        let config = self.object_lock_enabled(bucket)?;
+        let retention = match (requested, &config) {
+            (Some(_), None) => {
+                return Err(S3Error::InvalidArgument("Bucket is missing Object Lock Configuration".into()));
+            }
+            (Some(requested), Some(_)) => Some(requested.clone()),
+            (None, Some(config)) => config.default_retention.as_ref().map(|d| d.retention_from(Utc::now())),
+            (None, None) => None,
+        };
+        self.store_object_retention(bucket, key, retention.as_ref())
+    }
+
+    // --- Storage routes ---
+
+    /// Replace a bucket's prefix routes. Only allowed while the bucket holds no
+    /// objects, since existing objects would otherwise be looked up on the wrong backend.
+    pub fn put_storage_routes(&self, bucket: &str, routes: &[StorageRoute]) -> Result<(), S3Error> {
+        self.ensure_bucket_empty(bucket)?;
+        let tree = self.db.open_tree(STORAGE_ROUTES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(routes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Prefix routes of a bucket; empty when none are set. Does not check that the bucket exists.
+    pub fn get_storage_routes(&self, bucket: &str) -> Result<Vec<StorageRoute>, S3Error> {
+        let tree = self.db.open_tree(STORAGE_ROUTES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn delete_storage_routes(&self, bucket: &str) -> Result<(), S3Error> {
+        self.ensure_bucket_empty(bucket)?;
+        let tree = self.db.open_tree(STORAGE_ROUTES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn ensure_bucket_empty(&self, bucket: &str) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let obj_tree = self.db.open_tree(objects_tree_name(bucket)).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if !obj_tree.is_empty() {
+            return Err(S3Error::BucketNotEmpty);
+        }
+        Ok(())
+    }
+
+    // --- Credential operations ---
+
+    pub fn create_credential(&self, access_key_id: &str, secret_access_key: &str, description: &str) -> Result<AccessKeyRecord, S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if tree.contains_key(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            return Err(S3Error::InvalidArgument("Credential already exists".into()));
+        }
+        let record = AccessKeyRecord {
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            description: description.to_string(),
+            created: Utc::now(),
+            active: true,
+            permissions: None,
+            rate_limit: None,
+            bucket: None,
+        };
+        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(record)
+    }
+
+    pub fn get_credential(&self, access_key_id: &str) -> Result<AccessKeyRecord, S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match val {
+            Some(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))
+            }
+            None => Err(S3Error::AccessDenied),
+        }
+    }
+
+    /// The credential, or `None` where [`MetadataStore::get_credential`] would
+    /// deny access.
+    pub fn find_credential(&self, access_key_id: &str) -> Result<Option<AccessKeyRecord>, S3Error> {
+        match self.get_credential(access_key_id) {
+            Ok(record) => Ok(Some(record)),
+            Err(S3Error::AccessDenied) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Store a credential as given, e.g. one imported from another instance.
+    pub fn put_credential(&self, record: &AccessKeyRecord) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(record.access_key_id.as_str(), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn list_credentials(&self) -> Result<Vec<AccessKeyRecord>, S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut creds = Vec::new();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let record: AccessKeyRecord =
+                serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            creds.push(record);
+        }
+        Ok(creds)
+    }
+
+    pub fn revoke_credential(&self, access_key_id: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match val {
+            Some(bytes) => {
+                let mut record: AccessKeyRecord =
+                    serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                record.active = false;
+                let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                Ok(())
+            }
+            None => Err(S3Error::AccessDenied),
+        }
+    }
+
+    /// Restrict the credential to `permissions`, or lift the restriction with `None`.
+    pub fn set_credential_permissions(
+        &self,
+        access_key_id: &str,
+        permissions: Option<BTreeSet<Permission>>,
+    ) -> Result<AccessKeyRecord, S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(bytes) = val else {
+            return Err(S3Error::AccessDenied);
+        };
+        let mut record: AccessKeyRecord =
+            serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        record.permissions = permissions;
+        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(record)
+    }
+
+    /// Throttle the credential to `rate_limit`, or lift the limits with `None`.
+    pub fn set_credential_rate_limit(
+        &self,
+        access_key_id: &str,
+        rate_limit: Option<CredentialRateLimit>,
+    ) -> Result<AccessKeyRecord, S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(bytes) = val else {
+            return Err(S3Error::AccessDenied);
+        };
+        let mut record: AccessKeyRecord =
+            serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        record.rate_limit = rate_limit.filter(|l| *l != CredentialRateLimit::default());
+        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(record)
+    }
+
+    /// Bind a credential to `bucket`, which must exist, or unbind it.
+    pub fn set_credential_bucket(&self, access_key_id: &str, bucket: Option<&str>) -> Result<AccessKeyRecord, S3Error> {
+        if let Some(bucket) = bucket {
+            self.get_bucket(bucket)?;
+        }
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(bytes) = val else {
+            return Err(S3Error::AccessDenied);
+        };
+        let mut record: AccessKeyRecord =
+            serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        record.bucket = bucket.map(str::to_string);
+        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(access_key_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(record)
+    }
+
+    pub fn delete_credential(&self, access_key_id: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(CREDENTIALS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(access_key_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Multipart operations ---
+
+    pub fn create_multipart_upload(&self, upload: &MultipartUpload) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(upload).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(&upload.upload_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_multipart_upload(&self, upload_id: &str) -> Result<MultipartUpload, S3Error> {
+        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree.get(upload_id).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match val {
+            Some(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))
+            }
+            None => Err(S3Error::NoSuchUpload),
+        }
+    }
+
+    pub fn add_part_to_upload(&self, upload_id: &str, part: PartInfo) -> Result<(), S3Error> {
+        let mut upload = self.get_multipart_upload(upload_id)?;
+        upload.parts.retain(|p| p.part_number != part.part_number);
+        upload.parts.push(part);
+        upload.parts.sort_by_key(|p| p.part_number);
+        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&upload).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(upload_id, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn count_multipart_uploads(&self) -> Result<usize, S3Error> {
+        let tree = self.db.open_tree(MULTIPART_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(tree.len())
     }
 
     pub fn list_multipart_uploads(&self) -> Result<Vec<MultipartUpload>, S3Error> {
@@ -406,96 +1351,973 @@ impl MetadataStore {
         Ok(())
     }
 
-    // --- Lifecycle configuration operations ---
+    // --- Lifecycle configuration operations ---
+
+    pub fn put_lifecycle_configuration(&self, bucket: &str, config: &LifecycleConfiguration) -> Result<(), S3Error> {
+        self.put_lifecycle_configuration_by(bucket, config, None)
+    }
+
+    /// Like `put_lifecycle_configuration`, recording `changed_by` in the config history.
+    pub fn put_lifecycle_configuration_by(&self, bucket: &str, config: &LifecycleConfiguration, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.insert(bucket, json.clone()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Lifecycle, changed_by, previous.as_deref(), Some(&json))
+    }
+
+    pub fn get_lifecycle_configuration(&self, bucket: &str) -> Result<LifecycleConfiguration, S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::NoSuchLifecycleConfiguration),
+        }
+    }
+
+    pub fn delete_lifecycle_configuration(&self, bucket: &str) -> Result<(), S3Error> {
+        self.delete_lifecycle_configuration_by(bucket, None)
+    }
+
+    /// Like `delete_lifecycle_configuration`, recording `changed_by` in the config history.
+    pub fn delete_lifecycle_configuration_by(&self, bucket: &str, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Lifecycle, changed_by, previous.as_deref(), None)
+    }
+
+    pub fn list_lifecycle_configurations(&self) -> Result<Vec<(String, LifecycleConfiguration)>, S3Error> {
+        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut results = Vec::new();
+        for item in tree.iter() {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let bucket = String::from_utf8_lossy(&key).into_owned();
+            let config: LifecycleConfiguration = serde_json::from_slice(&val)
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            results.push((bucket, config));
+        }
+        Ok(results)
+    }
+
+    // --- Bucket policy operations ---
+
+    pub fn put_bucket_policy(&self, bucket: &str, policy: &BucketPolicy) -> Result<(), S3Error> {
+        self.put_bucket_policy_by(bucket, policy, None)
+    }
+
+    /// Like `put_bucket_policy`, recording `changed_by` in the config history.
+    pub fn put_bucket_policy_by(&self, bucket: &str, policy: &BucketPolicy, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(policy).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.insert(bucket, json.clone()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Policy, changed_by, previous.as_deref(), Some(&json))
+    }
+
+    pub fn get_bucket_policy(&self, bucket: &str) -> Result<BucketPolicy, S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::NoSuchBucketPolicy),
+        }
+    }
+
+    pub fn delete_bucket_policy(&self, bucket: &str) -> Result<(), S3Error> {
+        self.delete_bucket_policy_by(bucket, None)
+    }
+
+    /// Like `delete_bucket_policy`, recording `changed_by` in the config history.
+    pub fn delete_bucket_policy_by(&self, bucket: &str, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Policy, changed_by, previous.as_deref(), None)
+    }
+
+    // --- CORS configuration operations ---
+
+    pub fn put_cors_configuration(&self, bucket: &str, config: &CorsConfiguration) -> Result<(), S3Error> {
+        self.put_cors_configuration_by(bucket, config, None)
+    }
+
+    /// Like `put_cors_configuration`, recording `changed_by` in the config history.
+    pub fn put_cors_configuration_by(&self, bucket: &str, config: &CorsConfiguration, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.insert(bucket, json.clone()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Cors, changed_by, previous.as_deref(), Some(&json))
+    }
+
+    pub fn get_cors_configuration(&self, bucket: &str) -> Result<CorsConfiguration, S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::NoSuchCORSConfiguration),
+        }
+    }
+
+    pub fn delete_cors_configuration(&self, bucket: &str) -> Result<(), S3Error> {
+        self.delete_cors_configuration_by(bucket, None)
+    }
+
+    /// Like `delete_cors_configuration`, recording `changed_by` in the config history.
+    pub fn delete_cors_configuration_by(&self, bucket: &str, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Cors, changed_by, previous.as_deref(), None)
+    }
+
+    // --- Website configuration operations ---
+
+    pub fn put_website_configuration(&self, bucket: &str, config: &WebsiteConfiguration, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(WEBSITE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.insert(bucket, json.clone()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Website, changed_by, previous.as_deref(), Some(&json))
+    }
+
+    pub fn get_website_configuration(&self, bucket: &str) -> Result<WebsiteConfiguration, S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(WEBSITE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::NoSuchWebsiteConfiguration),
+        }
+    }
+
+    pub fn delete_website_configuration(&self, bucket: &str, changed_by: Option<&str>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(WEBSITE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let previous = tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, ConfigKind::Website, changed_by, previous.as_deref(), None)
+    }
+
+    // --- Security webhook operations ---
+
+    pub fn put_security_webhook(&self, bucket: &str, config: &SecurityWebhookConfig) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(SECURITY_WEBHOOKS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Webhook registered for a bucket, if any. Does not check that the bucket exists.
+    pub fn get_security_webhook(&self, bucket: &str) -> Result<Option<SecurityWebhookConfig>, S3Error> {
+        let tree = self.db.open_tree(SECURITY_WEBHOOKS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete_security_webhook(&self, bucket: &str) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(SECURITY_WEBHOOKS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Server access logging operations ---
+
+    /// Enable access logging of `bucket` into an existing target bucket, or
+    /// disable it with `None`.
+    pub fn put_bucket_logging(&self, bucket: &str, config: Option<&BucketLoggingConfiguration>) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(LOGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(config) = config else {
+            tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(());
+        };
+        match self.get_bucket(&config.target_bucket) {
+            Ok(_) => {}
+            Err(S3Error::NoSuchBucket) => {
+                return Err(S3Error::InvalidArgument("The target bucket for logging does not exist".into()));
+            }
+            Err(e) => return Err(e),
+        }
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Access logging target of a bucket, if enabled. Does not check that the bucket exists.
+    pub fn get_bucket_logging(&self, bucket: &str) -> Result<Option<BucketLoggingConfiguration>, S3Error> {
+        let tree = self.db.open_tree(LOGGING_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    // --- Event notification operations ---
+
+    /// Replace the notification rules of a bucket. No rules disables notifications.
+    pub fn put_bucket_notification(&self, bucket: &str, config: &NotificationConfiguration) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(NOTIFICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if config.rules.is_empty() {
+            tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(());
+        }
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Notification rules of a bucket, empty when none are configured. Does not
+    /// check that the bucket exists.
+    pub fn get_bucket_notification(&self, bucket: &str) -> Result<NotificationConfiguration, S3Error> {
+        let tree = self.db.open_tree(NOTIFICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(NotificationConfiguration::default()),
+        }
+    }
+
+    // --- Replication operations ---
+
+    pub fn put_replication_configuration(&self, bucket: &str, config: &ReplicationConfiguration) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(REPLICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Replication target of a bucket, if any. Does not check that the bucket exists.
+    pub fn get_replication_configuration(&self, bucket: &str) -> Result<Option<ReplicationConfiguration>, S3Error> {
+        let tree = self.db.open_tree(REPLICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Stop replicating a bucket and drop the objects still queued for it.
+    pub fn delete_replication_configuration(&self, bucket: &str) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(REPLICATION_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.drop_replication_tasks(bucket)
+    }
+
+    /// Queue an object for replication. Queuing it again before it was copied
+    /// replaces the earlier entry, so it is only copied once.
+    pub fn queue_replication(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
+        self.queue_replication_task(bucket, key, false)
+    }
+
+    /// Queue the deletion of a key from the replication target. It replaces
+    /// a copy of the key still queued, and is replaced if the key is written
+    /// again before it ran.
+    pub fn queue_replication_delete(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
+        self.queue_replication_task(bucket, key, true)
+    }
+
+    fn queue_replication_task(&self, bucket: &str, key: &str, delete: bool) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(REPLICATION_QUEUE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let task = ReplicationTask {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            queued_at: Utc::now(),
+            delete,
+            attempts: 0,
+            last_error: None,
+            retry_at: None,
+        };
+        let json = serde_json::to_vec(&task).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(replication_task_key(bucket, key), json)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Up to `limit` queued objects that are due for a (re)try at `now`.
+    pub fn list_replication_tasks(&self, now: chrono::DateTime<Utc>, limit: usize) -> Result<Vec<ReplicationTask>, S3Error> {
+        let tree = self.db.open_tree(REPLICATION_QUEUE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut tasks = Vec::new();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let task: ReplicationTask = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if task.retry_at.is_some_and(|t| t > now) {
+                continue;
+            }
+            tasks.push(task);
+            if tasks.len() >= limit {
+                break;
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Remove a task once its object was copied (or deleted from the target),
+    /// unless the object was queued again in the meantime.
+    pub fn complete_replication_task(&self, task: &ReplicationTask) -> Result<(), S3Error> {
+        if self.update_replication_task(task, |_| None)? && !task.delete {
+            let tree = self.db.open_tree(REPLICATION_COMPLETED_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            tree.insert(replication_task_key(&task.bucket, &task.key), Utc::now().to_rfc3339().as_bytes())
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Record a failed copy; the task is retried after `retry_after`.
+    pub fn fail_replication_task(&self, task: &ReplicationTask, error: &str, retry_after: chrono::Duration) -> Result<(), S3Error> {
+        self.update_replication_task(task, |mut stored| {
+            stored.attempts += 1;
+            stored.last_error = Some(error.to_string());
+            stored.retry_at = Some(Utc::now() + retry_after);
+            Some(stored)
+        })?;
+        Ok(())
+    }
+
+    /// Postpone a task that cannot run yet, without counting a failed attempt.
+    pub fn defer_replication_task(&self, task: &ReplicationTask, retry_after: chrono::Duration) -> Result<(), S3Error> {
+        self.update_replication_task(task, |mut stored| {
+            stored.retry_at = Some(Utc::now() + retry_after);
+            Some(stored)
+        })?;
+        Ok(())
+    }
+
+    /// Replace (or with `None`, remove) the queued entry of `task`, only if
+    /// it has not been queued again since `task` was read. Returns whether
+    /// the entry was updated.
+    fn update_replication_task(
+        &self,
+        task: &ReplicationTask,
+        update: impl FnOnce(ReplicationTask) -> Option<ReplicationTask>,
+    ) -> Result<bool, S3Error> {
+        let tree = self.db.open_tree(REPLICATION_QUEUE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let key = replication_task_key(&task.bucket, &task.key);
+        let Some(current) = tree.get(&key).map_err(|e| S3Error::InternalError(e.to_string()))? else {
+            return Ok(false);
+        };
+        let stored: ReplicationTask = serde_json::from_slice(&current).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if stored.queued_at != task.queued_at {
+            return Ok(false);
+        }
+        let new = update(stored)
+            .map(|t| serde_json::to_vec(&t))
+            .transpose()
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        // Losing the race against a new queue_replication keeps the newer entry
+        let swapped = tree
+            .compare_and_swap(&key, Some(current), new)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(swapped.is_ok())
+    }
+
+    /// Replication state of an object: pending or failed while it is queued,
+    /// completed once copied, `None` if it was never queued. Queued entries
+    /// win, so rewriting a copied object reports it pending again.
+    pub fn get_replication_status(&self, bucket: &str, key: &str) -> Result<Option<ReplicationStatus>, S3Error> {
+        let task_key = replication_task_key(bucket, key);
+        let queue = self.db.open_tree(REPLICATION_QUEUE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        if let Some(bytes) = queue.get(&task_key).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            let task: ReplicationTask = serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(Some(if task.attempts > 0 {
+                ReplicationStatus::Failed
+            } else {
+                ReplicationStatus::Pending
+            }));
+        }
+        let completed = self.db.open_tree(REPLICATION_COMPLETED_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let copied = completed.contains_key(&task_key).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(copied.then_some(ReplicationStatus::Completed))
+    }
+
+    /// Depth of the replication queue and the age of its oldest entry.
+    pub fn replication_queue_stats(&self) -> Result<ReplicationQueueStats, S3Error> {
+        let tree = self.db.open_tree(REPLICATION_QUEUE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut stats = ReplicationQueueStats::default();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let task: ReplicationTask = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            stats.pending += 1;
+            if task.attempts > 0 {
+                stats.failed += 1;
+            }
+            if stats.oldest_queued_at.is_none_or(|oldest| task.queued_at < oldest) {
+                stats.oldest_queued_at = Some(task.queued_at);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Forget the queued objects and replication states of a bucket.
+    fn drop_replication_tasks(&self, bucket: &str) -> Result<(), S3Error> {
+        for tree_name in [REPLICATION_QUEUE_TREE, REPLICATION_COMPLETED_TREE] {
+            let tree = self.db.open_tree(tree_name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            for (key, _) in tree.scan_prefix(format!("{}/", bucket)).flatten() {
+                let _ = tree.remove(key);
+            }
+        }
+        Ok(())
+    }
+
+    // --- Usage reporting operations ---
+
+    /// Add `delta` to the usage of `bucket` in `month` (`YYYY-MM`).
+    pub fn add_bucket_usage(&self, month: &str, bucket: &str, delta: &BucketUsage) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(USAGE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.update_and_fetch(format!("{}/{}", month, bucket), |current| {
+            let mut usage: BucketUsage = current
+                .and_then(|bytes| serde_json::from_slice(bytes).ok())
+                .unwrap_or_default();
+            usage.add(delta);
+            serde_json::to_vec(&usage).ok()
+        })
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Usage of every bucket with any in `month`, by bucket name.
+    pub fn list_bucket_usage(&self, month: &str) -> Result<Vec<(String, BucketUsage)>, S3Error> {
+        let tree = self.db.open_tree(USAGE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let prefix = format!("{}/", month);
+        let mut usage = Vec::new();
+        for item in tree.scan_prefix(&prefix) {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let bucket = String::from_utf8_lossy(&key[prefix.len()..]).into_owned();
+            let entry = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            usage.push((bucket, entry));
+        }
+        Ok(usage)
+    }
+
+    /// Write monthly usage reports into an existing bucket, or stop with `None`.
+    pub fn put_usage_report_config(&self, config: Option<&UsageReportConfig>) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(USAGE_REPORT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let Some(config) = config else {
+            tree.remove("config").map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(());
+        };
+        let _ = self.get_bucket(&config.target_bucket)?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert("config", json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn get_usage_report_config(&self) -> Result<Option<UsageReportConfig>, S3Error> {
+        let tree = self.db.open_tree(USAGE_REPORT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get("config").map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// The last month (`YYYY-MM`) a report was written for, if any.
+    pub fn last_usage_report(&self) -> Result<Option<String>, S3Error> {
+        let tree = self.db.open_tree(USAGE_REPORT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let month = tree.get("last_month").map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(month.map(|m| String::from_utf8_lossy(&m).into_owned()))
+    }
+
+    pub fn set_last_usage_report(&self, month: &str) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(USAGE_REPORT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert("last_month", month.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Anonymous caching header operations ---
+
+    pub fn put_cache_headers(&self, bucket: &str, config: &CacheHeadersConfig) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(CACHE_HEADERS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Caching headers configured for a bucket, if any. Does not check that the bucket exists.
+    pub fn get_cache_headers(&self, bucket: &str) -> Result<Option<CacheHeadersConfig>, S3Error> {
+        let tree = self.db.open_tree(CACHE_HEADERS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete_cache_headers(&self, bucket: &str) -> Result<(), S3Error> {
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(CACHE_HEADERS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    // --- Usage alert operations ---
 
-    pub fn put_lifecycle_configuration(&self, bucket: &str, config: &LifecycleConfiguration) -> Result<(), S3Error> {
+    pub fn put_usage_alert_config(&self, bucket: &str, config: &UsageAlertConfig) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(USAGE_ALERT_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn get_lifecycle_configuration(&self, bucket: &str) -> Result<LifecycleConfiguration, S3Error> {
-        let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    /// Usage thresholds of a bucket, if any. Does not check that the bucket exists.
+    pub fn get_usage_alert_config(&self, bucket: &str) -> Result<Option<UsageAlertConfig>, S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERT_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
-            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
-            None => Err(S3Error::NoSuchLifecycleConfiguration),
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
         }
     }
 
-    pub fn delete_lifecycle_configuration(&self, bucket: &str) -> Result<(), S3Error> {
+    /// Remove the thresholds of a bucket. Its alerts stay until the next check.
+    pub fn delete_usage_alert_config(&self, bucket: &str) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(USAGE_ALERT_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn list_lifecycle_configurations(&self) -> Result<Vec<(String, LifecycleConfiguration)>, S3Error> {
-        let tree = self.db.open_tree(LIFECYCLE_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let mut results = Vec::new();
+    /// Thresholds of every bucket that has them, by bucket name.
+    pub fn list_usage_alert_configs(&self) -> Result<Vec<(String, UsageAlertConfig)>, S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERT_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut configs = Vec::new();
         for item in tree.iter() {
-            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
-            let bucket = String::from_utf8_lossy(&key).into_owned();
-            let config: LifecycleConfiguration = serde_json::from_slice(&val)
-                .map_err(|e| S3Error::InternalError(e.to_string()))?;
-            results.push((bucket, config));
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let config = serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            configs.push((String::from_utf8_lossy(&k).into_owned(), config));
         }
-        Ok(results)
+        Ok(configs)
     }
 
-    // --- Bucket policy operations ---
+    pub fn get_usage_alert(&self, bucket: &str, metric: UsageMetric) -> Result<Option<UsageAlert>, S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERTS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let key = format!("{}:{}", bucket, metric.as_str());
+        match tree.get(key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
 
-    pub fn put_bucket_policy(&self, bucket: &str, policy: &BucketPolicy) -> Result<(), S3Error> {
+    /// Record an alert as raised, replacing the one for the same bucket and metric.
+    pub fn put_usage_alert(&self, alert: &UsageAlert) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERTS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let key = format!("{}:{}", alert.bucket, alert.metric.as_str());
+        let json = serde_json::to_vec(alert).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(key.as_bytes(), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Clear an alert. Returns whether it was raised.
+    pub fn remove_usage_alert(&self, bucket: &str, metric: UsageMetric) -> Result<bool, S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERTS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let key = format!("{}:{}", bucket, metric.as_str());
+        let removed = tree.remove(key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(removed.is_some())
+    }
+
+    /// Raised alerts of every bucket, by bucket name.
+    pub fn list_usage_alerts(&self) -> Result<Vec<UsageAlert>, S3Error> {
+        let tree = self.db.open_tree(USAGE_ALERTS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut alerts = Vec::new();
+        for item in tree.iter() {
+            let (_, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            alerts.push(serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?);
+        }
+        Ok(alerts)
+    }
+
+    // --- Rendition operations ---
+
+    pub fn put_rendition_config(&self, bucket: &str, config: &RenditionConfig) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
-        let json = serde_json::to_vec(policy).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(RENDITION_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn get_bucket_policy(&self, bucket: &str) -> Result<BucketPolicy, S3Error> {
-        let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    /// Rendition settings of a bucket, if enabled. Does not check that the bucket exists.
+    pub fn get_rendition_config(&self, bucket: &str) -> Result<Option<RenditionConfig>, S3Error> {
+        let tree = self.db.open_tree(RENDITION_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
-            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
-            None => Err(S3Error::NoSuchBucketPolicy),
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
         }
     }
 
-    pub fn delete_bucket_policy(&self, bucket: &str) -> Result<(), S3Error> {
+    pub fn delete_rendition_config(&self, bucket: &str) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(POLICIES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(RENDITION_CONFIG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    // --- CORS configuration operations ---
+    /// Cached renditions of an object, of any version.
+    pub fn get_renditions(&self, bucket: &str, key: &str) -> Result<Vec<RenditionEntry>, S3Error> {
+        let tree = self.db.open_tree(RENDITIONS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(format!("{}:{}", bucket, key).as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
 
-    pub fn put_cors_configuration(&self, bucket: &str, config: &CorsConfiguration) -> Result<(), S3Error> {
+    /// Replace the cached renditions of an object; an empty list removes the record.
+    pub fn put_renditions(&self, bucket: &str, key: &str, renditions: &[RenditionEntry]) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(RENDITIONS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree_key = format!("{}:{}", bucket, key);
+        if renditions.is_empty() {
+            tree.remove(tree_key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            return Ok(());
+        }
+        let json = serde_json::to_vec(renditions).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(tree_key.as_bytes(), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Every cached rendition of a bucket as `(key, rendition)`.
+    pub fn list_renditions(&self, bucket: &str) -> Result<Vec<(String, RenditionEntry)>, S3Error> {
+        let tree = self.db.open_tree(RENDITIONS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let prefix = format!("{}:", bucket);
+        let mut renditions = Vec::new();
+        for item in tree.scan_prefix(prefix.as_bytes()) {
+            let (k, v) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let key = String::from_utf8_lossy(&k[prefix.len()..]).into_owned();
+            let entries: Vec<RenditionEntry> =
+                serde_json::from_slice(&v).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            renditions.extend(entries.into_iter().map(|entry| (key.clone(), entry)));
+        }
+        Ok(renditions)
+    }
+
+    // --- Request logging operations ---
+
+    pub fn put_request_log(&self, bucket: &str, config: &RequestLogConfig) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(REQUEST_LOG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         let json = serde_json::to_vec(config).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
 
-    pub fn get_cors_configuration(&self, bucket: &str) -> Result<CorsConfiguration, S3Error> {
-        let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    /// Request logging of a bucket, if enabled. Does not check that the bucket exists.
+    pub fn get_request_log(&self, bucket: &str) -> Result<Option<RequestLogConfig>, S3Error> {
+        let tree = self.db.open_tree(REQUEST_LOG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         match tree.get(bucket).map_err(|e| S3Error::InternalError(e.to_string()))? {
-            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
-            None => Err(S3Error::NoSuchCORSConfiguration),
+            Some(bytes) => serde_json::from_slice(&bytes).map(Some).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
         }
     }
 
-    pub fn delete_cors_configuration(&self, bucket: &str) -> Result<(), S3Error> {
+    pub fn delete_request_log(&self, bucket: &str) -> Result<(), S3Error> {
         let _ = self.get_bucket(bucket)?;
-        let tree = self.db.open_tree(CORS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let tree = self.db.open_tree(REQUEST_LOG_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
         tree.remove(bucket).map_err(|e| S3Error::InternalError(e.to_string()))?;
         Ok(())
     }
+
+    // --- Configuration history operations ---
+
+    /// Append a history entry for a policy/lifecycle/CORS/website change. Writes that
+    /// leave the stored document unchanged are not recorded.
+    fn record_config_change(
+        &self,
+        bucket: &str,
+        kind: ConfigKind,
+        changed_by: Option<&str>,
+        previous: Option<&[u8]>,
+        current: Option<&[u8]>,
+    ) -> Result<(), S3Error> {
+        if previous == current {
+            return Ok(());
+        }
+        let parse = |bytes: Option<&[u8]>| -> Result<Option<serde_json::Value>, S3Error> {
+            bytes
+                .map(serde_json::from_slice)
+                .transpose()
+                .map_err(|e| S3Error::InternalError(e.to_string()))
+        };
+        let tree = self.db.open_tree(CONFIG_HISTORY_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let version = self.db.generate_id().map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let entry = ConfigHistoryEntry {
+            version,
+            bucket: bucket.to_string(),
+            kind,
+            changed_by: changed_by.map(|s| s.to_string()),
+            changed_at: Utc::now(),
+            previous: parse(previous)?,
+            current: parse(current)?,
+        };
+        let json = serde_json::to_vec(&entry).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        // Zero-padded version keeps entries for a bucket in chronological order.
+        let key = format!("{}:{:020}", bucket, version);
+        tree.insert(key.as_bytes(), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// List recorded configuration changes for a bucket, oldest first,
+    /// optionally restricted to one document kind.
+    pub fn list_config_history(&self, bucket: &str, kind: Option<ConfigKind>) -> Result<Vec<ConfigHistoryEntry>, S3Error> {
+        let tree = self.db.open_tree(CONFIG_HISTORY_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut entries = Vec::new();
+        for item in tree.scan_prefix(format!("{}:", bucket)) {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let entry: ConfigHistoryEntry =
+                serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if kind.is_none_or(|k| k == entry.kind) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn get_config_history_entry(&self, bucket: &str, version: u64) -> Result<ConfigHistoryEntry, S3Error> {
+        let tree = self.db.open_tree(CONFIG_HISTORY_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let key = format!("{}:{:020}", bucket, version);
+        match tree.get(key.as_bytes()).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Err(S3Error::InvalidArgument(format!("No history entry with version {}", version))),
+        }
+    }
+
+    /// Undo a recorded change by restoring the document that was in place
+    /// before it. The rollback itself is recorded as a new history entry.
+    pub fn rollback_config_change(&self, bucket: &str, version: u64, changed_by: Option<&str>) -> Result<ConfigHistoryEntry, S3Error> {
+        let entry = self.get_config_history_entry(bucket, version)?;
+        let _ = self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(config_tree_name(entry.kind)).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let restored = match &entry.previous {
+            Some(value) => {
+                let json = serde_json::to_vec(value).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                Some(json)
+            }
+            None => None,
+        };
+        let before = match &restored {
+            Some(json) => tree.insert(bucket, json.clone()),
+            None => tree.remove(bucket),
+        }
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        self.record_config_change(bucket, entry.kind, changed_by, before.as_deref(), restored.as_deref())?;
+        Ok(entry)
+    }
+
+    // --- Audit trail operations ---
+
+    /// Append an admin API mutation to the audit trail, assigning its ID.
+    pub fn record_audit_entry(&self, mut entry: AuditEntry) -> Result<AuditEntry, S3Error> {
+        let tree = self.db.open_tree(AUDIT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        entry.id = self.db.generate_id().map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let json = serde_json::to_vec(&entry).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(format!("{:020}", entry.id).as_bytes(), json)
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok(entry)
+    }
+
+    /// Audit entries matching `query`, newest first, at most `query.limit`.
+    pub fn list_audit_entries(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>, S3Error> {
+        let tree = self.db.open_tree(AUDIT_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let range = match query.before {
+            Some(before) => tree.range(..format!("{:020}", before).into_bytes()),
+            None => tree.range::<Vec<u8>, _>(..),
+        };
+        let mut entries = Vec::new();
+        for item in range.rev() {
+            if entries.len() >= query.limit {
+                break;
+            }
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let entry: AuditEntry = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if query.since.is_some_and(|since| entry.time < since) {
+                break;
+            }
+            let matches = query.actor.as_ref().is_none_or(|actor| *actor == entry.actor)
+                && query.path_prefix.as_ref().is_none_or(|prefix| entry.path.starts_with(prefix.as_str()))
+                && query.until.is_none_or(|until| entry.time <= until);
+            if matches {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    // --- Bucket administration tokens ---
+
+    /// Mint an administration token for `bucket`, which must exist. The token
+    /// is returned only here; the store keeps its hash.
+    pub fn create_bucket_admin_token(&self, bucket: &str, description: &str) -> Result<(BucketAdminToken, String), S3Error> {
+        self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(BUCKET_ADMIN_TOKENS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let token = crate::auth::credentials::generate_bucket_admin_token();
+        let record = BucketAdminToken {
+            id: format!("bt{}", &uuid::Uuid::new_v4().simple().to_string()[..14]),
+            bucket: bucket.to_string(),
+            description: description.to_string(),
+            created: Utc::now(),
+        };
+        let json = serde_json::to_vec(&record).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        tree.insert(token_hash(&token), json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        Ok((record, token))
+    }
+
+    /// The bucket administration token `token` is, if any.
+    pub fn find_bucket_admin_token(&self, token: &str) -> Result<Option<BucketAdminToken>, S3Error> {
+        let tree = self.db.open_tree(BUCKET_ADMIN_TOKENS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        match tree.get(token_hash(token)).map_err(|e| S3Error::InternalError(e.to_string()))? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| S3Error::InternalError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Bucket administration tokens, of one bucket or all, oldest first.
+    pub fn list_bucket_admin_tokens(&self, bucket: Option<&str>) -> Result<Vec<BucketAdminToken>, S3Error> {
+        let tree = self.db.open_tree(BUCKET_ADMIN_TOKENS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut tokens = Vec::new();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let token: BucketAdminToken = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if bucket.is_none_or(|b| b == token.bucket) {
+                tokens.push(token);
+            }
+        }
+        tokens.sort_by_key(|t| t.created);
+        Ok(tokens)
+    }
+
+    /// Revoke a bucket administration token by ID. Returns `false` for
+    /// unknown tokens.
+    pub fn delete_bucket_admin_token(&self, id: &str) -> Result<bool, S3Error> {
+        let tree = self.db.open_tree(BUCKET_ADMIN_TOKENS_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for item in tree.iter() {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if serde_json::from_slice::<BucketAdminToken>(&val).is_ok_and(|t| t.id == id) {
+                tree.remove(key).map_err(|e| S3Error::InternalError(e.to_string()))?;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // --- Object leases ---
+
+    /// Acquire the lease on `key` for `holder` until `ttl` from `now`, or
+    /// renew it if `holder` already has it. Fails with `LeaseHeld` while
+    /// another holder's lease is active; expired leases are taken over.
+    pub fn acquire_lease(
+        &self,
+        bucket: &str,
+        key: &str,
+        holder: &str,
+        ttl: chrono::Duration,
+        now: chrono::DateTime<Utc>,
+    ) -> Result<ObjectLease, S3Error> {
+        self.get_bucket(bucket)?;
+        let tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let lease_key = format!("{}:{}", bucket, key);
+        loop {
+            let current = tree.get(&lease_key).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let held = current
+                .as_ref()
+                .and_then(|bytes| serde_json::from_slice::<ObjectLease>(bytes).ok())
+                .filter(|lease| lease.is_active(now));
+            let acquired = match held {
+                Some(lease) if lease.holder != holder => return Err(S3Error::LeaseHeld),
+                Some(lease) => lease.acquired,
+                None => now,
+            };
+            let lease = ObjectLease {
+                bucket: bucket.to_string(),
+                key: key.to_string(),
+                holder: holder.to_string(),
+                acquired,
+                expires: now + ttl,
+            };
+            let json = serde_json::to_vec(&lease).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let swapped = tree
+                .compare_and_swap(&lease_key, current, Some(json))
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if swapped.is_ok() {
+                return Ok(lease);
+            }
+        }
+    }
+
+    /// The active lease on `key`.
+    pub fn get_lease(&self, bucket: &str, key: &str, now: chrono::DateTime<Utc>) -> Result<ObjectLease, S3Error> {
+        let tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let val = tree
+            .get(format!("{}:{}", bucket, key))
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+        val.and_then(|bytes| serde_json::from_slice::<ObjectLease>(&bytes).ok())
+            .filter(|lease| lease.is_active(now))
+            .ok_or(S3Error::NoSuchLease)
+    }
+
+    /// Release `holder`'s lease on `key`. Releasing a lease that expired or
+    /// was never taken succeeds; another holder's active lease is kept.
+    pub fn release_lease(&self, bucket: &str, key: &str, holder: &str, now: chrono::DateTime<Utc>) -> Result<(), S3Error> {
+        let tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let lease_key = format!("{}:{}", bucket, key);
+        loop {
+            let Some(current) = tree.get(&lease_key).map_err(|e| S3Error::InternalError(e.to_string()))? else {
+                return Ok(());
+            };
+            let held_by_other = serde_json::from_slice::<ObjectLease>(&current)
+                .is_ok_and(|lease| lease.is_active(now) && lease.holder != holder);
+            if held_by_other {
+                return Err(S3Error::LeaseHeld);
+            }
+            let swapped = tree
+                .compare_and_swap(&lease_key, Some(current), None as Option<&[u8]>)
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            if swapped.is_ok() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Active leases of a bucket on keys starting with `prefix`, in key order.
+    pub fn list_leases(&self, bucket: &str, prefix: &str, now: chrono::DateTime<Utc>) -> Result<Vec<ObjectLease>, S3Error> {
+        let tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut leases = Vec::new();
+        for item in tree.scan_prefix(format!("{}:{}", bucket, prefix)) {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            match serde_json::from_slice::<ObjectLease>(&val) {
+                Ok(lease) if lease.is_active(now) => leases.push(lease),
+                _ => {}
+            }
+        }
+        Ok(leases)
+    }
+
+    /// Remove leases that expired before `now`, returning how many.
+    pub fn remove_expired_leases(&self, now: chrono::DateTime<Utc>) -> Result<usize, S3Error> {
+        let tree = self.db.open_tree(LEASES_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut removed = 0;
+        for item in tree.iter() {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let expired = serde_json::from_slice::<ObjectLease>(&val).map_or(true, |lease| !lease.is_active(now));
+            // Only if not renewed meanwhile
+            if expired
+                && tree
+                    .compare_and_swap(&key, Some(val), None as Option<&[u8]>)
+                    .map_err(|e| S3Error::InternalError(e.to_string()))?
+                    .is_ok()
+            {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+fn token_hash(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 #[cfg(test)]
@@ -543,6 +2365,9 @@ mod tests {
             content_type: "text/plain".into(),
             last_modified: Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         }).unwrap();
         assert!(matches!(store.delete_bucket("bucket1"), Err(S3Error::BucketNotEmpty)));
     }
@@ -559,6 +2384,9 @@ mod tests {
             content_type: "application/octet-stream".into(),
             last_modified: Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         };
         store.put_object_meta(&meta).unwrap();
         let fetched = store.get_object_meta("test-bkt", "k").unwrap();
@@ -567,6 +2395,85 @@ mod tests {
         assert!(matches!(store.get_object_meta("test-bkt", "k"), Err(S3Error::NoSuchKey)));
     }
 
+    #[test]
+    fn test_object_scans() {
+        let (store, _dir) = temp_store();
+        let scan = |etag: &str, status| ObjectScan {
+            etag: etag.into(),
+            status,
+            detail: None,
+            updated: Utc::now(),
+        };
+        store.put_object_scan("scan-a", "k1", &scan("e1", ScanStatus::Pending)).unwrap();
+        store.put_object_scan("scan-a", "k2", &scan("e2", ScanStatus::Infected)).unwrap();
+        store.put_object_scan("scan-b", "k1", &scan("e3", ScanStatus::Pending)).unwrap();
+
+        assert_eq!(store.get_object_scan("scan-a", "k2").unwrap().unwrap().status, ScanStatus::Infected);
+        assert!(store.get_object_scan("scan-a", "missing").unwrap().is_none());
+        assert_eq!(store.list_object_scans(None, None).unwrap().len(), 3);
+        assert_eq!(store.list_object_scans(Some("scan-a"), None).unwrap().len(), 2);
+        let pending = store.list_object_scans(None, Some(ScanStatus::Pending)).unwrap();
+        let names: Vec<_> = pending.iter().map(|(b, k, _)| format!("{}/{}", b, k)).collect();
+        assert_eq!(names, vec!["scan-a/k1", "scan-b/k1"]);
+
+        // Deleting the object drops its scan record
+        store.delete_object_meta("scan-a", "k2").unwrap();
+        assert!(store.get_object_scan("scan-a", "k2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_part_layout() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("mp-bkt").unwrap();
+        assert!(store.get_part_layout("mp-bkt", "big").unwrap().is_none());
+        let layout = PartLayout {
+            etag: "abc-2".into(),
+            sizes: vec![5 << 20, 17],
+        };
+        store.put_part_layout("mp-bkt", "big", &layout).unwrap();
+        assert_eq!(store.get_part_layout("mp-bkt", "big").unwrap(), Some(layout));
+
+        store.delete_object_meta("mp-bkt", "big").unwrap();
+        assert!(store.get_part_layout("mp-bkt", "big").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_renditions() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("img").unwrap();
+        let config = RenditionConfig {
+            max_width: 800,
+            max_height: 600,
+            cache_limit_bytes: 1 << 20,
+        };
+        store.put_rendition_config("img", &config).unwrap();
+        assert_eq!(store.get_rendition_config("img").unwrap(), Some(config));
+        assert!(matches!(
+            store.put_rendition_config("missing", &RenditionConfig { max_width: 1, max_height: 1, cache_limit_bytes: 1 }),
+            Err(S3Error::NoSuchBucket)
+        ));
+
+        let entry = |width| RenditionEntry {
+            etag: "e".into(),
+            width,
+            height: width,
+            size: 10,
+            content_type: "image/png".into(),
+            created: Utc::now(),
+        };
+        store.put_renditions("img", "a.png", &[entry(100), entry(200)]).unwrap();
+        store.put_renditions("img", "b/c.png", &[entry(100)]).unwrap();
+        assert_eq!(store.get_renditions("img", "a.png").unwrap().len(), 2);
+        let all = store.list_renditions("img").unwrap();
+        let keys: Vec<_> = all.iter().map(|(k, e)| format!("{}@{}", k, e.width)).collect();
+        assert_eq!(keys, vec!["a.png@100", "a.png@200", "b/c.png@100"]);
+
+        store.put_renditions("img", "a.png", &[]).unwrap();
+        assert!(store.get_renditions("img", "a.png").unwrap().is_empty());
+        store.delete_rendition_config("img").unwrap();
+        assert!(store.get_rendition_config("img").unwrap().is_none());
+    }
+
     #[test]
     fn test_list_objects_prefix() {
         let (store, _dir) = temp_store();
@@ -580,6 +2487,9 @@ mod tests {
                 content_type: "".into(),
                 last_modified: Utc::now(),
                 public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
             }).unwrap();
         }
         let resp = store.list_objects_v2(&ListObjectsV2Request {
@@ -593,6 +2503,81 @@ mod tests {
         assert_eq!(resp.contents.len(), 2);
     }
 
+    #[test]
+    fn test_list_objects_cache_invalidated_on_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(dir.path()).unwrap().with_list_cache(8);
+        store.create_bucket("test-bkt").unwrap();
+        let put = |key: &str| {
+            store.put_object_meta(&ObjectMeta {
+                bucket: "test-bkt".into(),
+                key: key.into(),
+                size: 1,
+                etag: "e".into(),
+                content_type: "".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }).unwrap();
+        };
+        put("a.txt");
+        let req = ListObjectsV2Request {
+            bucket: "test-bkt".into(),
+            prefix: String::new(),
+            delimiter: String::new(),
+            max_keys: 1000,
+            continuation_token: None,
+            start_after: None,
+        };
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 1);
+        assert_eq!(store.list_cache_len(), 1);
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 1);
+
+        put("b.txt");
+        assert_eq!(store.list_cache_len(), 0);
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 2);
+
+        store.delete_object_meta("test-bkt", "a.txt").unwrap();
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 1);
+    }
+
+    #[test]
+    fn test_list_objects_cache_skips_large_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = MetadataStore::open(dir.path()).unwrap().with_list_cache(8);
+        store.create_bucket("test-bkt").unwrap();
+        for i in 0..1001 {
+            store.put_object_meta(&ObjectMeta {
+                bucket: "test-bkt".into(),
+                key: format!("{:04}.txt", i),
+                size: 1,
+                etag: "e".into(),
+                content_type: "".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }).unwrap();
+        }
+        let mut req = ListObjectsV2Request {
+            bucket: "test-bkt".into(),
+            prefix: String::new(),
+            delimiter: String::new(),
+            max_keys: 5000,
+            continuation_token: None,
+            start_after: None,
+        };
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 1001);
+        assert_eq!(store.list_cache_len(), 0);
+
+        req.max_keys = 1000;
+        assert_eq!(store.list_objects_v2(&req).unwrap().contents.len(), 1000);
+        assert_eq!(store.list_cache_len(), 1);
+    }
+
     #[test]
     fn test_list_objects_delimiter() {
         let (store, _dir) = temp_store();
@@ -606,6 +2591,9 @@ mod tests {
                 content_type: "".into(),
                 last_modified: Utc::now(),
                 public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
             }).unwrap();
         }
         let resp = store.list_objects_v2(&ListObjectsV2Request {
@@ -633,6 +2621,9 @@ mod tests {
                 content_type: "".into(),
                 last_modified: Utc::now(),
                 public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
             }).unwrap();
         }
         let resp = store.list_objects_v2(&ListObjectsV2Request {
@@ -650,12 +2641,96 @@ mod tests {
         let resp2 = store.list_objects_v2(&ListObjectsV2Request {
             bucket: "test-bkt".into(),
             prefix: String::new(),
-            delimiter: String::new(),
+            delimiter: String::new(),
+            max_keys: 2,
+            continuation_token: resp.next_continuation_token,
+            start_after: None,
+        }).unwrap();
+        assert_eq!(resp2.contents.len(), 2);
+    }
+
+    #[test]
+    fn test_list_objects_max_keys_zero() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+        store.put_object_meta(&ObjectMeta {
+            bucket: "test-bkt".into(),
+            key: "key".into(),
+            size: 1,
+            etag: "e".into(),
+            content_type: "".into(),
+            last_modified: Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        }).unwrap();
+        let resp = store.list_objects_v2(&ListObjectsV2Request {
+            bucket: "test-bkt".into(),
+            prefix: String::new(),
+            delimiter: String::new(),
+            max_keys: 0,
+            continuation_token: None,
+            start_after: None,
+        }).unwrap();
+        assert!(resp.contents.is_empty());
+        assert!(!resp.is_truncated);
+        assert!(resp.next_continuation_token.is_none());
+    }
+
+    #[test]
+    fn test_list_objects_pagination_with_common_prefixes() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+        for key in ["a.txt", "docs/1", "docs/2", "docs/3", "m.txt", "photos/1", "z.txt", "zz/1"] {
+            store.put_object_meta(&ObjectMeta {
+                bucket: "test-bkt".into(),
+                key: key.into(),
+                size: 1,
+                etag: "e".into(),
+                content_type: "".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }).unwrap();
+        }
+        // Common prefixes count toward max-keys, and a page may end on one
+        let mut req = ListObjectsV2Request {
+            bucket: "test-bkt".into(),
+            prefix: String::new(),
+            delimiter: "/".into(),
             max_keys: 2,
-            continuation_token: resp.next_continuation_token,
+            continuation_token: None,
             start_after: None,
+        };
+        let mut keys = Vec::new();
+        let mut prefixes = Vec::new();
+        loop {
+            let resp = store.list_objects_v2(&req).unwrap();
+            assert!(resp.contents.len() + resp.common_prefixes.len() <= 2);
+            keys.extend(resp.contents.into_iter().map(|o| o.key));
+            prefixes.extend(resp.common_prefixes);
+            if !resp.is_truncated {
+                break;
+            }
+            req.continuation_token = resp.next_continuation_token;
+        }
+        assert_eq!(keys, ["a.txt", "m.txt", "z.txt"]);
+        assert_eq!(prefixes, ["docs/", "photos/", "zz/"]);
+
+        // The scan is limited to the prefix, and start-after may precede it
+        let resp = store.list_objects_v2(&ListObjectsV2Request {
+            bucket: "test-bkt".into(),
+            prefix: "docs/".into(),
+            delimiter: String::new(),
+            max_keys: 1000,
+            continuation_token: None,
+            start_after: Some("b".into()),
         }).unwrap();
-        assert_eq!(resp2.contents.len(), 2);
+        let keys: Vec<_> = resp.contents.iter().map(|o| o.key.as_str()).collect();
+        assert_eq!(keys, ["docs/1", "docs/2", "docs/3"]);
     }
 
     #[test]
@@ -670,6 +2745,9 @@ mod tests {
             content_type: "".into(),
             last_modified: Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         }).unwrap();
 
         // No tags initially
@@ -705,6 +2783,9 @@ mod tests {
             content_type: "".into(),
             last_modified: Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         }).unwrap();
 
         let mut tags = HashMap::new();
@@ -723,11 +2804,50 @@ mod tests {
             content_type: "".into(),
             last_modified: Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         }).unwrap();
         let fetched = store.get_object_tagging("test-bkt", "k").unwrap();
         assert!(fetched.is_empty());
     }
 
+    #[test]
+    fn test_uploaded_object_tagging_merges_bucket_defaults() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+        store.put_object_meta(&ObjectMeta {
+            bucket: "test-bkt".into(),
+            key: "k".into(),
+            size: 10,
+            etag: "e".into(),
+            content_type: "".into(),
+            last_modified: Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        }).unwrap();
+
+        let mut defaults = HashMap::new();
+        defaults.insert("team".to_string(), "storage".to_string());
+        defaults.insert("class".to_string(), "standard".to_string());
+        store.put_bucket_default_tags("test-bkt", &defaults).unwrap();
+
+        let mut client = HashMap::new();
+        client.insert("class".to_string(), "archive".to_string());
+        store.set_uploaded_object_tagging("test-bkt", "k", &client).unwrap();
+        let tags = store.get_object_tagging("test-bkt", "k").unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags["team"], "storage");
+        assert_eq!(tags["class"], "archive");
+
+        // Without defaults or client tags, stale tags are cleared
+        store.delete_bucket_default_tags("test-bkt").unwrap();
+        store.set_uploaded_object_tagging("test-bkt", "k", &HashMap::new()).unwrap();
+        assert!(store.get_object_tagging("test-bkt", "k").unwrap().is_empty());
+    }
+
     #[test]
     fn test_credential_crud() {
         let (store, _dir) = temp_store();
@@ -746,6 +2866,58 @@ mod tests {
         assert!(!revoked.active);
     }
 
+    #[test]
+    fn test_credential_permissions() {
+        let (store, _dir) = temp_store();
+        let cred = store.create_credential("AKID", "SECRET", "").unwrap();
+        assert!(cred.permissions.is_none());
+        assert!(cred.allows(Permission::BucketAdmin));
+
+        let read_only = BTreeSet::from([Permission::Read]);
+        store.set_credential_permissions("AKID", Some(read_only)).unwrap();
+        let fetched = store.get_credential("AKID").unwrap();
+        assert!(fetched.allows(Permission::Read));
+        assert!(!fetched.allows(Permission::Write));
+
+        store.set_credential_permissions("AKID", None).unwrap();
+        assert!(store.get_credential("AKID").unwrap().allows(Permission::Write));
+        assert!(matches!(
+            store.set_credential_permissions("MISSING", None),
+            Err(S3Error::AccessDenied)
+        ));
+    }
+
+    #[test]
+    fn test_probe() {
+        let (store, _dir) = temp_store();
+        store.probe().unwrap();
+        store.probe().unwrap();
+        // The probe key stays out of bucket listings
+        assert!(store.list_buckets().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_credential_rate_limit() {
+        let (store, _dir) = temp_store();
+        let cred = store.create_credential("AKID", "SECRET", "").unwrap();
+        assert!(cred.rate_limit.is_none());
+
+        let limit = CredentialRateLimit {
+            requests_per_sec: Some(10),
+            bytes_per_sec: None,
+        };
+        store.set_credential_rate_limit("AKID", Some(limit)).unwrap();
+        assert_eq!(store.get_credential("AKID").unwrap().rate_limit, Some(limit));
+
+        // A limit without any value is no limit
+        store.set_credential_rate_limit("AKID", Some(CredentialRateLimit::default())).unwrap();
+        assert!(store.get_credential("AKID").unwrap().rate_limit.is_none());
+        assert!(matches!(
+            store.set_credential_rate_limit("MISSING", None),
+            Err(S3Error::AccessDenied)
+        ));
+    }
+
     #[test]
     fn test_multipart_lifecycle() {
         let (store, _dir) = temp_store();
@@ -755,6 +2927,7 @@ mod tests {
             key: "k".into(),
             created: Utc::now(),
             parts: vec![],
+            checksum_algorithm: None,
         };
         store.create_multipart_upload(&upload).unwrap();
 
@@ -763,6 +2936,7 @@ mod tests {
             etag: "e1".into(),
             size: 100,
             last_modified: Utc::now(),
+            checksum: None,
         }).unwrap();
 
         let fetched = store.get_multipart_upload("up1").unwrap();
@@ -792,6 +2966,10 @@ mod tests {
                 expiration_days: 30,
                 expiration_date: None,
                 tags: vec![],
+                transitions: vec![],
+                abort_incomplete_multipart_days: None,
+                noncurrent_version_expiration_days: None,
+                expired_object_delete_marker: false,
             }],
         };
         store.put_lifecycle_configuration("test-bkt", &config).unwrap();
@@ -858,6 +3036,10 @@ mod tests {
                 expiration_days: 1,
                 expiration_date: None,
                 tags: vec![],
+                transitions: vec![],
+                abort_incomplete_multipart_days: None,
+                noncurrent_version_expiration_days: None,
+                expired_object_delete_marker: false,
             }],
         };
         store.put_lifecycle_configuration("test-bkt", &config).unwrap();
@@ -923,6 +3105,285 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_website_crud() {
+        use crate::s3::types::WebsiteConfiguration;
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+
+        assert!(matches!(
+            store.get_website_configuration("test-bkt"),
+            Err(S3Error::NoSuchWebsiteConfiguration)
+        ));
+
+        let config = WebsiteConfiguration {
+            index_document: "index.html".into(),
+            error_document: Some("404.html".into()),
+            routing_rules: vec![],
+        };
+        store.put_website_configuration("test-bkt", &config, Some("AKID")).unwrap();
+        assert_eq!(store.get_website_configuration("test-bkt").unwrap(), config);
+
+        store.delete_website_configuration("test-bkt", None).unwrap();
+        assert!(matches!(
+            store.get_website_configuration("test-bkt"),
+            Err(S3Error::NoSuchWebsiteConfiguration)
+        ));
+
+        store.put_website_configuration("test-bkt", &config, None).unwrap();
+        store.delete_bucket("test-bkt").unwrap();
+        store.create_bucket("test-bkt").unwrap();
+        assert!(matches!(
+            store.get_website_configuration("test-bkt"),
+            Err(S3Error::NoSuchWebsiteConfiguration)
+        ));
+    }
+
+    #[test]
+    fn test_bucket_logging() {
+        use crate::s3::types::BucketLoggingConfiguration;
+        let (store, _dir) = temp_store();
+        store.create_bucket("src").unwrap();
+
+        let config = BucketLoggingConfiguration {
+            target_bucket: "logs".into(),
+            target_prefix: "src/".into(),
+        };
+        assert!(matches!(
+            store.put_bucket_logging("src", Some(&config)),
+            Err(S3Error::InvalidArgument(_))
+        ));
+
+        store.create_bucket("logs").unwrap();
+        store.put_bucket_logging("src", Some(&config)).unwrap();
+        assert_eq!(store.get_bucket_logging("src").unwrap(), Some(config));
+
+        store.put_bucket_logging("src", None).unwrap();
+        assert_eq!(store.get_bucket_logging("src").unwrap(), None);
+    }
+
+    #[test]
+    fn test_bucket_notification() {
+        use crate::s3::types::{NotificationConfiguration, NotificationRule};
+        let (store, _dir) = temp_store();
+        assert!(matches!(
+            store.put_bucket_notification("missing", &NotificationConfiguration::default()),
+            Err(S3Error::NoSuchBucket)
+        ));
+        store.create_bucket("src").unwrap();
+        assert!(store.get_bucket_notification("src").unwrap().rules.is_empty());
+
+        let config = NotificationConfiguration {
+            rules: vec![NotificationRule {
+                id: "uploads".into(),
+                endpoint: "http://localhost:9000/hook".into(),
+                events: vec!["s3:ObjectCreated:*".into()],
+                prefix: Some("images/".into()),
+                suffix: None,
+            }],
+        };
+        store.put_bucket_notification("src", &config).unwrap();
+        assert_eq!(store.get_bucket_notification("src").unwrap(), config);
+
+        store.put_bucket_notification("src", &NotificationConfiguration::default()).unwrap();
+        assert!(store.get_bucket_notification("src").unwrap().rules.is_empty());
+    }
+
+    #[test]
+    fn test_replication_queue() {
+        use crate::s3::types::ReplicationConfiguration;
+        let (store, _dir) = temp_store();
+        store.create_bucket("src").unwrap();
+        let config = ReplicationConfiguration {
+            endpoint: "http://remote:9000".into(),
+            target_bucket: "dst".into(),
+            region: "us-east-1".into(),
+            access_key_id: "AKID".into(),
+            secret_access_key: "SECRET".into(),
+            prefix: None,
+            tags: [("class".to_string(), "backup".to_string())].into(),
+            min_size: Some(1024),
+            replicate_deletes: true,
+        };
+        store.put_replication_configuration("src", &config).unwrap();
+        assert_eq!(store.get_replication_configuration("src").unwrap(), Some(config.clone()));
+        let tagged: HashMap<String, String> = [("class".to_string(), "backup".to_string())].into();
+        assert!(config.matches("a.txt", 2048, &tagged));
+        assert!(!config.matches("a.txt", 512, &tagged));
+        assert!(!config.matches("a.txt", 2048, &HashMap::new()));
+
+        store.queue_replication("src", "a.txt").unwrap();
+        store.queue_replication("src", "b.txt").unwrap();
+        let now = Utc::now();
+        let tasks = store.list_replication_tasks(now, 10).unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        // A failed task waits for its retry time
+        store.fail_replication_task(&tasks[0], "connection refused", chrono::Duration::seconds(60)).unwrap();
+        let due = store.list_replication_tasks(now, 10).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].key, "b.txt");
+        let later = store.list_replication_tasks(now + chrono::Duration::seconds(120), 10).unwrap();
+        assert_eq!(later[0].attempts, 1);
+        assert_eq!(later[0].last_error.as_deref(), Some("connection refused"));
+
+        // A deferred task waits too, without counting an attempt
+        store.defer_replication_task(&due[0], chrono::Duration::seconds(60)).unwrap();
+        assert!(store.list_replication_tasks(now, 10).unwrap().is_empty());
+        let deferred = store.list_replication_tasks(now + chrono::Duration::seconds(120), 10).unwrap();
+        assert_eq!(deferred[1].key, "b.txt");
+        assert_eq!(deferred[1].attempts, 0);
+
+        // Queued again while being copied: completing the old task keeps the new one
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        store.queue_replication("src", "b.txt").unwrap();
+        store.complete_replication_task(&due[0]).unwrap();
+        let remaining = store.list_replication_tasks(now, 10).unwrap();
+        assert_eq!(remaining.len(), 1);
+        store.complete_replication_task(&remaining[0]).unwrap();
+        assert!(store.list_replication_tasks(now, 10).unwrap().is_empty());
+
+        store.delete_replication_configuration("src").unwrap();
+        assert_eq!(store.get_replication_configuration("src").unwrap(), None);
+        assert!(store.list_replication_tasks(now + chrono::Duration::seconds(120), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_replication_status() {
+        use crate::s3::types::ReplicationStatus;
+        let (store, _dir) = temp_store();
+        store.create_bucket("src").unwrap();
+        assert_eq!(store.get_replication_status("src", "a.txt").unwrap(), None);
+
+        store.queue_replication("src", "a.txt").unwrap();
+        store.queue_replication("src", "b.txt").unwrap();
+        assert_eq!(store.get_replication_status("src", "a.txt").unwrap(), Some(ReplicationStatus::Pending));
+
+        let tasks = store.list_replication_tasks(Utc::now(), 10).unwrap();
+        store.fail_replication_task(&tasks[0], "timeout", chrono::Duration::seconds(60)).unwrap();
+        assert_eq!(store.get_replication_status("src", "a.txt").unwrap(), Some(ReplicationStatus::Failed));
+        store.complete_replication_task(&tasks[1]).unwrap();
+        assert_eq!(store.get_replication_status("src", "b.txt").unwrap(), Some(ReplicationStatus::Completed));
+
+        let stats = store.replication_queue_stats().unwrap();
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.oldest_queued_at, Some(tasks[0].queued_at));
+
+        // Rewritten after the copy: pending again
+        store.queue_replication("src", "b.txt").unwrap();
+        assert_eq!(store.get_replication_status("src", "b.txt").unwrap(), Some(ReplicationStatus::Pending));
+        let tasks = store.list_replication_tasks(Utc::now(), 10).unwrap();
+        store.complete_replication_task(&tasks[0]).unwrap();
+        store.delete_object_meta("src", "b.txt").unwrap();
+        assert_eq!(store.get_replication_status("src", "b.txt").unwrap(), None);
+
+        // A propagated delete replaces the pending copy and is not reported
+        // as a completed copy
+        store.queue_replication("src", "c.txt").unwrap();
+        store.queue_replication_delete("src", "c.txt").unwrap();
+        let tasks = store.list_replication_tasks(Utc::now(), 10).unwrap();
+        let task = tasks.iter().find(|t| t.key == "c.txt").unwrap();
+        assert!(task.delete);
+        store.complete_replication_task(task).unwrap();
+        assert_eq!(store.get_replication_status("src", "c.txt").unwrap(), None);
+    }
+
+    #[test]
+    fn test_bucket_usage() {
+        use crate::s3::types::{BucketUsage, UsageReportConfig};
+        let (store, _dir) = temp_store();
+        store.create_bucket("src").unwrap();
+        for (key, size) in [("a", 10), ("b", 32)] {
+            store.put_object_meta(&ObjectMeta {
+                bucket: "src".into(),
+                key: key.into(),
+                size,
+                etag: "e".into(),
+                content_type: "".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }).unwrap();
+        }
+        assert_eq!(store.bucket_stats("src").unwrap().bytes, 42);
+
+        let delta = BucketUsage { storage_byte_hours: 1.5, tier1_requests: 2, tier2_requests: 3, egress_bytes: 100 };
+        store.add_bucket_usage("2026-09", "src", &delta).unwrap();
+        store.add_bucket_usage("2026-09", "src", &delta).unwrap();
+        store.add_bucket_usage("2026-10", "src", &delta).unwrap();
+        let usage = store.list_bucket_usage("2026-09").unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].0, "src");
+        assert_eq!(usage[0].1, BucketUsage { storage_byte_hours: 3.0, tier1_requests: 4, tier2_requests: 6, egress_bytes: 200 });
+        assert!(store.list_bucket_usage("2026-08").unwrap().is_empty());
+
+        let missing = UsageReportConfig { target_bucket: "reports".into(), target_prefix: String::new() };
+        assert!(matches!(store.put_usage_report_config(Some(&missing)), Err(S3Error::NoSuchBucket)));
+        let config = UsageReportConfig { target_bucket: "src".into(), target_prefix: "usage/".into() };
+        store.put_usage_report_config(Some(&config)).unwrap();
+        assert_eq!(store.get_usage_report_config().unwrap(), Some(config));
+        store.put_usage_report_config(None).unwrap();
+        assert_eq!(store.get_usage_report_config().unwrap(), None);
+
+        assert_eq!(store.last_usage_report().unwrap(), None);
+        store.set_last_usage_report("2026-09").unwrap();
+        assert_eq!(store.last_usage_report().unwrap().as_deref(), Some("2026-09"));
+    }
+
+    #[test]
+    fn test_remove_orphaned_tags() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+        for key in ["kept", "gone"] {
+            store.put_object_meta(&ObjectMeta {
+                bucket: "test-bkt".into(),
+                key: key.into(),
+                size: 10,
+                etag: "e".into(),
+                content_type: "".into(),
+                last_modified: Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            }).unwrap();
+        }
+        let mut tags = HashMap::new();
+        tags.insert("foo".into(), "bar".into());
+        store.put_object_tagging("test-bkt", "kept", &tags).unwrap();
+        store.put_object_tagging("test-bkt", "gone", &tags).unwrap();
+
+        // Tags left behind by an object and a bucket removed without cleanup
+        let tag_tree = store.db.open_tree(TAGGING_TREE).unwrap();
+        store.db.open_tree(objects_tree_name("test-bkt")).unwrap().remove("gone").unwrap();
+        tag_tree.insert("old-bkt:k", serde_json::to_vec(&tags).unwrap()).unwrap();
+
+        assert_eq!(store.remove_orphaned_tags().unwrap(), 2);
+        assert_eq!(store.get_object_tagging("test-bkt", "kept").unwrap(), tags);
+        assert_eq!(tag_tree.len(), 1);
+        assert!(!store.db.tree_names().iter().any(|n| n.as_ref() == b"objects:old-bkt"));
+        assert_eq!(store.remove_orphaned_tags().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_delete_bucket_cleans_tagging() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+        store.create_bucket("test-bkt2").unwrap();
+        let tag_tree = store.db.open_tree(TAGGING_TREE).unwrap();
+        tag_tree.insert("test-bkt:a", b"{}".to_vec()).unwrap();
+        tag_tree.insert("test-bkt:b/c", b"{}".to_vec()).unwrap();
+        tag_tree.insert("test-bkt2:a", b"{}".to_vec()).unwrap();
+
+        store.delete_bucket("test-bkt").unwrap();
+        assert!(tag_tree.get("test-bkt:a").unwrap().is_none());
+        assert!(tag_tree.get("test-bkt:b/c").unwrap().is_none());
+        assert!(tag_tree.get("test-bkt2:a").unwrap().is_some());
+    }
+
     #[test]
     fn test_delete_bucket_cleans_cors() {
         use crate::s3::types::{CorsConfiguration, CorsRule};
@@ -966,6 +3427,7 @@ mod tests {
                 key: "k".into(),
                 created: Utc::now(),
                 parts: vec![],
+                checksum_algorithm: None,
             }).unwrap();
         }
 
@@ -978,4 +3440,384 @@ mod tests {
         assert_eq!(uploads.len(), 1);
         assert_eq!(uploads[0].upload_id, "up2");
     }
+
+    #[test]
+    fn test_config_history_and_rollback() {
+        use crate::s3::types::{ConfigKind, CorsConfiguration, CorsRule};
+        let (store, _dir) = temp_store();
+        store.create_bucket("test-bkt").unwrap();
+
+        let rule = |origin: &str| CorsConfiguration {
+            rules: vec![CorsRule {
+                id: None,
+                allowed_origins: vec![origin.into()],
+                allowed_methods: vec!["GET".into()],
+                allowed_headers: vec![],
+                expose_headers: vec![],
+                max_age_seconds: None,
+            }],
+        };
+        store.put_cors_configuration_by("test-bkt", &rule("https://a.example"), Some("AKIDONE")).unwrap();
+        // Re-applying the same document is not recorded
+        store.put_cors_configuration_by("test-bkt", &rule("https://a.example"), Some("AKIDONE")).unwrap();
+        store.put_cors_configuration_by("test-bkt", &rule("https://b.example"), Some("AKIDTWO")).unwrap();
+
+        let history = store.list_config_history("test-bkt", None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].kind, ConfigKind::Cors);
+        assert!(history[0].previous.is_none());
+        assert_eq!(history[1].changed_by.as_deref(), Some("AKIDTWO"));
+        assert!(history[1].previous.is_some());
+        assert!(store.list_config_history("test-bkt", Some(ConfigKind::Policy)).unwrap().is_empty());
+
+        // Rolling back the second change restores the first document
+        store.rollback_config_change("test-bkt", history[1].version, None).unwrap();
+        let restored = store.get_cors_configuration("test-bkt").unwrap();
+        assert_eq!(restored.rules[0].allowed_origins, vec!["https://a.example"]);
+
+        // Rolling back the first change removes the configuration entirely
+        store.rollback_config_change("test-bkt", history[0].version, None).unwrap();
+        assert!(matches!(
+            store.get_cors_configuration("test-bkt"),
+            Err(S3Error::NoSuchCORSConfiguration)
+        ));
+        assert_eq!(store.list_config_history("test-bkt", None).unwrap().len(), 4);
+
+        store.delete_bucket("test-bkt").unwrap();
+        assert!(store.list_config_history("test-bkt", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_audit_entries_newest_first() {
+        let (store, _dir) = temp_store();
+        let entry = |actor: &str, path: &str| AuditEntry {
+            id: 0,
+            time: Utc::now(),
+            actor: actor.into(),
+            remote_ip: None,
+            method: "POST".into(),
+            path: path.into(),
+            route: None,
+            status: 200,
+            payload: None,
+        };
+        let first = store.record_audit_entry(entry("token", "/_admin/buckets")).unwrap();
+        let second = store.record_audit_entry(entry("cert:CN=ops", "/_admin/credentials")).unwrap();
+        store.record_audit_entry(entry("token", "/_admin/buckets/a/cors")).unwrap();
+
+        let all = store.list_audit_entries(&AuditQuery { limit: 10, ..Default::default() }).unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all[0].id > all[1].id && all[1].id > all[2].id);
+
+        let query = AuditQuery { actor: Some("token".into()), limit: 10, ..Default::default() };
+        assert_eq!(store.list_audit_entries(&query).unwrap().len(), 2);
+        let query = AuditQuery { path_prefix: Some("/_admin/buckets".into()), limit: 1, ..Default::default() };
+        assert_eq!(store.list_audit_entries(&query).unwrap()[0].path, "/_admin/buckets/a/cors");
+        let query = AuditQuery { before: Some(second.id), limit: 10, ..Default::default() };
+        let older = store.list_audit_entries(&query).unwrap();
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].id, first.id);
+    }
+
+    #[test]
+    fn test_bucket_admin_tokens_dropped_with_bucket() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("team-a").unwrap();
+        store.create_bucket("team-b").unwrap();
+        let (minted, token) = store.create_bucket_admin_token("team-a", "ci").unwrap();
+        store.create_bucket_admin_token("team-b", "").unwrap();
+        assert!(store.create_bucket_admin_token("missing", "").is_err());
+
+        assert_eq!(store.find_bucket_admin_token(&token).unwrap().unwrap().id, minted.id);
+        assert!(store.find_bucket_admin_token("s3bt_wrong").unwrap().is_none());
+        assert_eq!(store.list_bucket_admin_tokens(Some("team-a")).unwrap().len(), 1);
+        assert_eq!(store.list_bucket_admin_tokens(None).unwrap().len(), 2);
+
+        store.create_credential("AKBOUND", "secret", "bound").unwrap();
+        store.set_credential_bucket("AKBOUND", Some("team-a")).unwrap();
+        store.delete_bucket("team-a").unwrap();
+        assert!(store.find_bucket_admin_token(&token).unwrap().is_none());
+        assert!(!store.get_credential("AKBOUND").unwrap().active);
+
+        let (other, _) = store.create_bucket_admin_token("team-b", "").unwrap();
+        assert!(store.delete_bucket_admin_token(&other.id).unwrap());
+        assert!(!store.delete_bucket_admin_token(&other.id).unwrap());
+    }
+
+    #[test]
+    fn test_bucket_stats_follow_writes() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("stats").unwrap();
+        let object = |key: &str, size: u64| ObjectMeta {
+            bucket: "stats".into(),
+            key: key.into(),
+            size,
+            etag: "e".into(),
+            content_type: "".into(),
+            last_modified: Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        };
+        store.put_object_meta(&object("a", 10)).unwrap();
+        store.put_object_meta(&object("b", 5)).unwrap();
+        // Overwrites change the size only
+        store.put_object_meta(&object("a", 3)).unwrap();
+        assert_eq!(store.bucket_stats("stats").unwrap(), BucketStats { objects: 2, bytes: 8 });
+
+        store.delete_object_meta("stats", "b").unwrap();
+        store.delete_object_meta("stats", "missing").unwrap();
+        assert_eq!(store.bucket_stats("stats").unwrap(), BucketStats { objects: 1, bytes: 3 });
+        assert_eq!(store.list_bucket_stats().unwrap().len(), 1);
+
+        store.delete_object_meta("stats", "a").unwrap();
+        store.delete_bucket("stats").unwrap();
+        assert!(store.list_bucket_stats().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_object_moderation() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("ugc").unwrap();
+        let object = |key: &str| ObjectMeta {
+            bucket: "ugc".into(),
+            key: key.into(),
+            size: 1,
+            etag: "e".into(),
+            content_type: "".into(),
+            last_modified: Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        };
+        let hold = |state| ObjectModeration {
+            state,
+            reason: Some("report #12".into()),
+            updated: Utc::now(),
+        };
+        assert!(matches!(
+            store.set_object_moderation("ugc", "a.png", &hold(ModerationState::Quarantined)),
+            Err(S3Error::NoSuchKey)
+        ));
+
+        store.put_object_meta(&object("a.png")).unwrap();
+        store.put_object_meta(&object("b.png")).unwrap();
+        store.set_object_moderation("ugc", "a.png", &hold(ModerationState::Quarantined)).unwrap();
+        store.set_object_moderation("ugc", "b.png", &hold(ModerationState::PendingReview)).unwrap();
+        let quarantined = store.list_object_moderation("ugc", Some(ModerationState::Quarantined)).unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].0, "a.png");
+
+        // Overwrites keep the state; active and deletion clear it
+        store.put_object_meta(&object("a.png")).unwrap();
+        assert!(store.get_object_moderation("ugc", "a.png").unwrap().is_some());
+        store.set_object_moderation("ugc", "a.png", &hold(ModerationState::Active)).unwrap();
+        assert!(store.get_object_moderation("ugc", "a.png").unwrap().is_none());
+        store.delete_object_meta("ugc", "b.png").unwrap();
+        assert!(store.list_object_moderation("ugc", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_object_leases() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("jobs").unwrap();
+        let now = Utc::now();
+        let ttl = chrono::Duration::seconds(30);
+        assert!(matches!(
+            store.acquire_lease("missing", "a", "w1", ttl, now),
+            Err(S3Error::NoSuchBucket)
+        ));
+
+        let lease = store.acquire_lease("jobs", "in/a", "w1", ttl, now).unwrap();
+        assert_eq!(lease.expires, now + ttl);
+        assert!(matches!(
+            store.acquire_lease("jobs", "in/a", "w2", ttl, now),
+            Err(S3Error::LeaseHeld)
+        ));
+        assert!(matches!(store.release_lease("jobs", "in/a", "w2", now), Err(S3Error::LeaseHeld)));
+
+        // Renewal keeps the acquisition time
+        let later = now + chrono::Duration::seconds(20);
+        let renewed = store.acquire_lease("jobs", "in/a", "w1", ttl, later).unwrap();
+        assert_eq!(renewed.acquired, now);
+        assert_eq!(renewed.expires, later + ttl);
+
+        // Once expired, another holder takes over
+        let expired = later + ttl;
+        assert!(matches!(store.get_lease("jobs", "in/a", expired), Err(S3Error::NoSuchLease)));
+        let taken = store.acquire_lease("jobs", "in/a", "w2", ttl, expired).unwrap();
+        assert_eq!(taken.holder, "w2");
+
+        store.acquire_lease("jobs", "in/b", "w1", ttl, expired).unwrap();
+        store.acquire_lease("jobs", "out/c", "w1", ttl, expired).unwrap();
+        let listed: Vec<String> = store.list_leases("jobs", "in/", expired).unwrap().into_iter().map(|l| l.key).collect();
+        assert_eq!(listed, vec!["in/a", "in/b"]);
+
+        store.release_lease("jobs", "in/a", "w2", expired).unwrap();
+        store.release_lease("jobs", "in/a", "w2", expired).unwrap();
+        assert_eq!(store.remove_expired_leases(expired + ttl).unwrap(), 2);
+        assert!(store.list_leases("jobs", "", expired).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_warm_up_reads_bucket_settings() {
+        use crate::s3::types::{CorsConfiguration, CorsRule};
+        let (store, _dir) = temp_store();
+        assert_eq!(store.warm_up().unwrap(), WarmUpReport::default());
+
+        store.create_bucket("warm-a").unwrap();
+        store.create_bucket("warm-b").unwrap();
+        store.create_credential("AKWARM", "secret", "").unwrap();
+        let cors = CorsConfiguration {
+            rules: vec![CorsRule {
+                id: None,
+                allowed_origins: vec!["*".into()],
+                allowed_methods: vec!["GET".into()],
+                allowed_headers: vec![],
+                expose_headers: vec![],
+                max_age_seconds: None,
+            }],
+        };
+        store.put_cors_configuration("warm-a", &cors).unwrap();
+        assert_eq!(store.warm_up().unwrap(), WarmUpReport { buckets: 2, entries: 4 });
+    }
+
+    #[test]
+    fn test_list_buckets_page() {
+        let (store, _dir) = temp_store();
+        for name in ["logs-b", "data-a", "logs-a", "logs-c"] {
+            store.create_bucket(name).unwrap();
+        }
+
+        let all = store.list_buckets_page(&ListBucketsRequest::default()).unwrap();
+        let names: Vec<_> = all.buckets.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["data-a", "logs-a", "logs-b", "logs-c"]);
+        assert!(all.continuation_token.is_none());
+
+        let page1 = store.list_buckets_page(&ListBucketsRequest {
+            prefix: "logs-".into(),
+            max_buckets: Some(2),
+            continuation_token: None,
+        }).unwrap();
+        let names: Vec<_> = page1.buckets.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["logs-a", "logs-b"]);
+        assert_eq!(page1.continuation_token.as_deref(), Some("logs-b"));
+
+        let page2 = store.list_buckets_page(&ListBucketsRequest {
+            prefix: "logs-".into(),
+            max_buckets: Some(2),
+            continuation_token: page1.continuation_token,
+        }).unwrap();
+        let names: Vec<_> = page2.buckets.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["logs-c"]);
+        assert!(page2.continuation_token.is_none());
+
+        assert!(matches!(
+            store.list_buckets_page(&ListBucketsRequest {
+                max_buckets: Some(0),
+                ..Default::default()
+            }),
+            Err(S3Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_deletion_protected_bucket() {
+        let (store, _dir) = temp_store();
+        store.create_bucket("prod").unwrap();
+        store.set_bucket_deletion_protected("prod", true).unwrap();
+
+        assert!(matches!(store.delete_bucket("prod"), Err(S3Error::BucketDeletionProtected)));
+        assert!(matches!(
+            store.delete_bucket_with_grace("prod", 3600, Utc::now()),
+            Err(S3Error::BucketDeletionProtected)
+        ));
+        assert!(store.get_bucket("prod").unwrap().deletion_protected);
+
+        store.set_bucket_deletion_protected("prod", false).unwrap();
+        store.delete_bucket("prod").unwrap();
+        assert!(matches!(store.get_bucket("prod"), Err(S3Error::NoSuchBucket)));
+    }
+
+    #[test]
+    fn test_delete_bucket_with_grace_reserves_name() {
+        use crate::s3::types::{CorsConfiguration, CorsRule};
+        let (store, _dir) = temp_store();
+        store.create_bucket("grace-bkt").unwrap();
+        store.set_bucket_anonymous_read("grace-bkt", true).unwrap();
+        let config = CorsConfiguration {
+            rules: vec![CorsRule {
+                id: None,
+                allowed_origins: vec!["*".into()],
+                allowed_methods: vec!["GET".into()],
+                allowed_headers: vec![],
+                expose_headers: vec![],
+                max_age_seconds: None,
+            }],
+        };
+        store.put_cors_configuration("grace-bkt", &config).unwrap();
+
+        store.delete_bucket_with_grace("grace-bkt", 3600, Utc::now()).unwrap();
+        assert!(matches!(store.get_bucket("grace-bkt"), Err(S3Error::NoSuchBucket)));
+        assert!(matches!(store.create_bucket("grace-bkt"), Err(S3Error::OperationAborted)));
+        assert!(matches!(
+            store.create_bucket_at("grace-bkt", Utc::now() + chrono::Duration::seconds(1800)),
+            Err(S3Error::OperationAborted)
+        ));
+        assert_eq!(store.list_deleted_buckets().unwrap().len(), 1);
+
+        // Nothing has expired yet
+        assert!(store.purge_expired_deleted_buckets(Utc::now()).unwrap().is_empty());
+
+        // Restore brings back settings and configuration
+        let meta = store.restore_deleted_bucket("grace-bkt").unwrap();
+        assert!(meta.anonymous_read);
+        assert!(store.get_cors_configuration("grace-bkt").is_ok());
+        assert!(store.list_deleted_buckets().unwrap().is_empty());
+
+        // Once the grace period is over the name is released and configuration dropped
+        store.delete_bucket_with_grace("grace-bkt", 3600, Utc::now()).unwrap();
+        let purged = store
+            .purge_expired_deleted_buckets(Utc::now() + chrono::Duration::seconds(7200))
+            .unwrap();
+        assert_eq!(purged, vec!["grace-bkt".to_string()]);
+        store.create_bucket("grace-bkt").unwrap();
+        assert!(matches!(
+            store.get_cors_configuration("grace-bkt"),
+            Err(S3Error::NoSuchCORSConfiguration)
+        ));
+    }
+
+    #[test]
+    fn test_usage_alerts() {
+        use crate::s3::types::{UsageAlert, UsageAlertConfig, UsageMetric};
+        let (store, _dir) = temp_store();
+        store.create_bucket("alerted").unwrap();
+        let config = UsageAlertConfig { max_bytes: Some(1024), ..Default::default() };
+        assert!(store.put_usage_alert_config("missing", &config).is_err());
+        store.put_usage_alert_config("alerted", &config).unwrap();
+        assert_eq!(store.get_usage_alert_config("alerted").unwrap(), Some(config.clone()));
+        assert_eq!(store.list_usage_alert_configs().unwrap(), vec![("alerted".to_string(), config)]);
+
+        let alert = UsageAlert {
+            bucket: "alerted".into(),
+            metric: UsageMetric::Bytes,
+            threshold: 1024,
+            value: 2048,
+            raised: Utc::now(),
+        };
+        store.put_usage_alert(&alert).unwrap();
+        assert_eq!(store.get_usage_alert("alerted", UsageMetric::Bytes).unwrap(), Some(alert.clone()));
+        assert!(store.get_usage_alert("alerted", UsageMetric::Objects).unwrap().is_none());
+        assert_eq!(store.list_usage_alerts().unwrap(), vec![alert.clone()]);
+        assert!(store.remove_usage_alert("alerted", UsageMetric::Bytes).unwrap());
+        assert!(!store.remove_usage_alert("alerted", UsageMetric::Bytes).unwrap());
+
+        store.put_usage_alert(&alert).unwrap();
+        store.delete_bucket("alerted").unwrap();
+        assert!(store.get_usage_alert_config("alerted").unwrap().is_none());
+        assert!(store.list_usage_alerts().unwrap().is_empty());
+    }
 }
@@ -0,0 +1,231 @@
+//! Metadata schema versioning and startup migrations.
+//!
+//! The schema version is stored in the `schema` tree. Stores created before
+//! versioning was introduced have no record and are treated as version 0.
+//! Before any migration runs, the whole database is exported to a backup
+//! directory next to the metadata directory.
+
+use crate::error::S3Error;
+use crate::s3::types::{BucketStats, ObjectMeta};
+use chrono::Utc;
+use sled::Db;
+use std::path::{Path, PathBuf};
+
+const SCHEMA_TREE: &str = "schema";
+const SCHEMA_VERSION_KEY: &str = "version";
+
+/// Schema version written by this build.
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+struct Migration {
+    /// Version the store is at after this migration has run.
+    version: u32,
+    description: &'static str,
+    apply: fn(&Db) -> Result<(), S3Error>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "rewrite object metadata with the current field set",
+        apply: backfill_object_meta,
+    },
+    Migration {
+        version: 2,
+        description: "count the objects and bytes of every bucket",
+        apply: count_bucket_stats,
+    },
+];
+
+/// Outcome of a startup migration run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub applied: Vec<u32>,
+    pub backup_path: Option<PathBuf>,
+}
+
+pub(crate) fn schema_version(db: &Db) -> Result<u32, S3Error> {
+    let tree = db.open_tree(SCHEMA_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    match tree.get(SCHEMA_VERSION_KEY).map_err(|e| S3Error::InternalError(e.to_string()))? {
+        Some(bytes) => serde_json::from_slice(&bytes).map_err(|e| S3Error::InternalError(e.to_string())),
+        None => Ok(0),
+    }
+}
+
+fn set_schema_version(db: &Db, version: u32) -> Result<(), S3Error> {
+    let tree = db.open_tree(SCHEMA_TREE).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    let json = serde_json::to_vec(&version).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    tree.insert(SCHEMA_VERSION_KEY, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    db.flush().map_err(|e| S3Error::InternalError(e.to_string()))?;
+    Ok(())
+}
+
+/// A store with no trees besides sled's default one has never been written to.
+fn is_fresh(db: &Db) -> bool {
+    db.tree_names().iter().all(|name| name.as_ref() == b"__sled__default" || name.as_ref() == SCHEMA_TREE.as_bytes())
+        && db.is_empty()
+}
+
+fn backup_path(metadata_dir: &Path, from_version: u32) -> PathBuf {
+    let name = metadata_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "metadata".into());
+    metadata_dir.with_file_name(format!(
+        "{}.backup-v{}-{}",
+        name,
+        from_version,
+        Utc::now().format("%Y%m%dT%H%M%S")
+    ))
+}
+
+fn backup(db: &Db, path: &Path) -> Result<(), S3Error> {
+    let copy = sled::open(path).map_err(|e| S3Error::InternalError(e.to_string()))?;
+    copy.import(db.export());
+    copy.flush().map_err(|e| S3Error::InternalError(e.to_string()))?;
+    Ok(())
+}
+
+/// Bring the store at `metadata_dir` up to `CURRENT_SCHEMA_VERSION`.
+pub(crate) fn run(db: &Db, metadata_dir: &Path) -> Result<MigrationReport, S3Error> {
+    let from_version = schema_version(db)?;
+    if from_version > CURRENT_SCHEMA_VERSION {
+        return Err(S3Error::InternalError(format!(
+            "Metadata schema version {} is newer than supported version {}",
+            from_version, CURRENT_SCHEMA_VERSION
+        )));
+    }
+
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        applied: Vec::new(),
+        backup_path: None,
+    };
+    if from_version == CURRENT_SCHEMA_VERSION {
+        return Ok(report);
+    }
+    if from_version == 0 && is_fresh(db) {
+        set_schema_version(db, CURRENT_SCHEMA_VERSION)?;
+        report.to_version = CURRENT_SCHEMA_VERSION;
+        return Ok(report);
+    }
+
+    let path = backup_path(metadata_dir, from_version);
+    backup(db, &path)?;
+    tracing::info!(path = %path.display(), from_version, "Backed up metadata before migration");
+    report.backup_path = Some(path);
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > from_version) {
+        tracing::info!(version = migration.version, "Applying metadata migration: {}", migration.description);
+        (migration.apply)(db)?;
+        set_schema_version(db, migration.version)?;
+        report.applied.push(migration.version);
+        report.to_version = migration.version;
+    }
+    Ok(report)
+}
+
+// --- Migrations ---
+
+fn backfill_object_meta(db: &Db) -> Result<(), S3Error> {
+    for name in db.tree_names() {
+        if !name.starts_with(b"objects:") {
+            continue;
+        }
+        let tree = db.open_tree(&name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        for item in tree.iter() {
+            let (key, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let meta: ObjectMeta = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let json = serde_json::to_vec(&meta).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            tree.insert(key, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+fn count_bucket_stats(db: &Db) -> Result<(), S3Error> {
+    let stats_tree = db
+        .open_tree(super::metadata::BUCKET_STATS_TREE)
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    stats_tree.clear().map_err(|e| S3Error::InternalError(e.to_string()))?;
+    for name in db.tree_names() {
+        let Some(bucket) = name.strip_prefix(b"objects:") else {
+            continue;
+        };
+        let tree = db.open_tree(&name).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        let mut stats = BucketStats::default();
+        for item in tree.iter() {
+            let (_, val) = item.map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let meta: ObjectMeta = serde_json::from_slice(&val).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            stats.adjust(1, meta.size as i64);
+        }
+        if stats.objects > 0 {
+            let json = serde_json::to_vec(&stats).map_err(|e| S3Error::InternalError(e.to_string()))?;
+            stats_tree.insert(bucket, json).map_err(|e| S3Error::InternalError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MetadataStore;
+
+    #[test]
+    fn test_fresh_store_is_stamped_without_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_dir = dir.path().join("metadata");
+        let store = MetadataStore::open(&meta_dir).unwrap();
+        let report = store.run_migrations(&meta_dir).unwrap();
+        assert_eq!(report.to_version, CURRENT_SCHEMA_VERSION);
+        assert!(report.applied.is_empty());
+        assert!(report.backup_path.is_none());
+        assert_eq!(store.schema_version().unwrap(), CURRENT_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_legacy_store_is_backed_up_and_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_dir = dir.path().join("metadata");
+        {
+            // Write an object record from before the `public` field existed
+            let db = sled::open(&meta_dir).unwrap();
+            db.open_tree("buckets").unwrap().insert("old-bkt", b"{}".to_vec()).unwrap();
+            let legacy = r#"{"bucket":"old-bkt","key":"a.txt","size":1,"etag":"e","content_type":"","last_modified":"2024-01-01T00:00:00Z"}"#;
+            db.open_tree("objects:old-bkt").unwrap().insert("a.txt", legacy.as_bytes()).unwrap();
+            db.flush().unwrap();
+        }
+
+        let store = MetadataStore::open(&meta_dir).unwrap();
+        let report = store.run_migrations(&meta_dir).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.applied, vec![1, 2]);
+        assert_eq!(store.schema_version().unwrap(), CURRENT_SCHEMA_VERSION);
+        let meta = store.get_object_meta("old-bkt", "a.txt").unwrap();
+        assert!(!meta.public);
+        assert_eq!(store.bucket_stats("old-bkt").unwrap(), BucketStats { objects: 1, bytes: 1 });
+
+        let backup = sled::open(report.backup_path.unwrap()).unwrap();
+        assert!(backup.open_tree("objects:old-bkt").unwrap().contains_key("a.txt").unwrap());
+
+        // Running again is a no-op
+        let report = store.run_migrations(&meta_dir).unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.backup_path.is_none());
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let db = sled::open(dir.path()).unwrap();
+            set_schema_version(&db, CURRENT_SCHEMA_VERSION + 1).unwrap();
+        }
+        let store = MetadataStore::open(dir.path()).unwrap();
+        assert!(store.run_migrations(dir.path()).is_err());
+    }
+}
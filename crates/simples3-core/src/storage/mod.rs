@@ -1,5 +1,13 @@
 pub mod filesystem;
+pub mod io_pool;
+pub mod list_cache;
+pub mod listing;
+pub mod maintenance;
 pub mod metadata;
+pub mod migrations;
+pub mod router;
+pub mod snapshot;
 
 pub use filesystem::FileStore;
 pub use metadata::MetadataStore;
+pub use router::StorageRouter;
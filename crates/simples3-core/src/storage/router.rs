@@ -0,0 +1,572 @@
+//! Key-prefix routing across storage backends.
+//!
+//! Every bucket stores its objects on the default `FileStore` unless it has
+//! storage routes, which send keys under a prefix to a named backend. The
+//! longest matching prefix wins. Bucket directories are created on every
+//! backend so any route can be written to.
+//!
+//! Lifecycle transitions move an object to the backend named by its new
+//! storage class; its metadata records the class so it is still found there.
+//! RestoreObject copies a transitioned object back to its routed backend for
+//! a number of days, and reads are served from that copy until it expires.
+//!
+//! Freezing a bucket records a manifest of its objects with their SHA-256
+//! and can pack their data into archive segments on the default store.
+//! Reads of compacted objects are served from their segment until the bucket
+//! is thawed, which writes the data back to where it came from.
+
+use super::io_pool::IoPool;
+use super::{FileStore, MetadataStore};
+use crate::config::Config;
+use crate::error::S3Error;
+use crate::s3::types::{ArchiveEntry, BucketFreeze, ListObjectsV2Request, ObjectMeta, ObjectRestore, StorageRoute};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// Backend name used for the default data directory.
+pub const DEFAULT_BACKEND: &str = "default";
+
+/// Size past which compaction starts a new archive segment.
+pub const ARCHIVE_SEGMENT_SIZE: u64 = 1 << 30;
+
+#[derive(Clone)]
+pub struct StorageRouter {
+    default: FileStore,
+    backends: BTreeMap<String, FileStore>,
+    metadata: MetadataStore,
+}
+
+impl StorageRouter {
+    pub fn new(default: FileStore, metadata: MetadataStore) -> Self {
+        Self {
+            default,
+            backends: BTreeMap::new(),
+            metadata,
+        }
+    }
+
+    /// Default store on `data_dir` plus one store per `storage_backends` entry,
+    /// all sharing one IO pool of `io_threads` threads when it is set.
+    pub fn from_config(config: &Config, metadata: MetadataStore) -> std::io::Result<Self> {
+        let io = match config.io_threads {
+            0 => IoPool::default(),
+            threads => IoPool::dedicated(threads)?,
+        };
+        let store = |dir| {
+            FileStore::new(dir)
+                .with_path_escaping(config.escape_object_paths)
+                .with_md5_skip_threshold(config.md5_skip_threshold)
+                .with_io_pool(io.clone())
+        };
+        let router = config
+            .storage_backends
+            .iter()
+            .fold(Self::new(store(&config.data_dir), metadata), |router, (name, dir)| {
+                router.with_backend(name, store(dir))
+            });
+        Ok(router)
+    }
+
+    pub fn with_backend(mut self, name: &str, store: FileStore) -> Self {
+        self.backends.insert(name.to_string(), store);
+        self
+    }
+
+    /// Names that storage routes may refer to, including `default`.
+    pub fn backend_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_BACKEND.to_string())
+            .chain(self.backends.keys().cloned())
+            .collect()
+    }
+
+    pub fn backend(&self, name: &str) -> Option<&FileStore> {
+        if name == DEFAULT_BACKEND {
+            Some(&self.default)
+        } else {
+            self.backends.get(name)
+        }
+    }
+
+    pub fn default_store(&self) -> &FileStore {
+        &self.default
+    }
+
+    /// The store new data for `bucket/key` is written to, per the bucket's
+    /// storage routes.
+    pub fn for_key(&self, bucket: &str, key: &str) -> Result<&FileStore, S3Error> {
+        let name = self.route_name(bucket, key)?;
+        self.named(&name, bucket)
+    }
+
+    /// The store holding the object described by `meta`: the backend of its
+    /// storage class once transitioned, else the routed one.
+    pub fn for_object(&self, meta: &ObjectMeta) -> Result<&FileStore, S3Error> {
+        match &meta.storage_class {
+            Some(class) => self.named(class, &meta.bucket),
+            None => self.for_key(&meta.bucket, &meta.key),
+        }
+    }
+
+    /// The store to read the object described by `meta` from: its restored
+    /// copy while there is one, else the store holding it.
+    pub fn for_read(&self, meta: &ObjectMeta) -> Result<&FileStore, S3Error> {
+        if self.active_restore(meta)?.is_some() {
+            return self.for_key(&meta.bucket, &meta.key);
+        }
+        self.for_object(meta)
+    }
+
+    /// The restore of `meta` that is currently serving reads, if any.
+    pub fn active_restore(&self, meta: &ObjectMeta) -> Result<Option<ObjectRestore>, S3Error> {
+        let restore = self.metadata.get_object_restore(&meta.bucket, &meta.key)?;
+        Ok(restore.filter(|r| r.serves(meta, Utc::now())))
+    }
+
+    /// Copy a transitioned object back to its routed backend until `days`
+    /// from now. Restoring an object that is already restored only moves the
+    /// expiry. Returns whether it was already restored.
+    pub async fn restore(&self, meta: &ObjectMeta, days: u32) -> Result<bool, S3Error> {
+        let (bucket, key) = (meta.bucket.as_str(), meta.key.as_str());
+        let Some(class) = &meta.storage_class else {
+            return Err(S3Error::InvalidObjectState);
+        };
+        let already_restored = self.active_restore(meta)?.is_some();
+        if !already_restored {
+            let hot = self.route_name(bucket, key)?;
+            if *class != hot {
+                let source = self.named(class, bucket)?.open_object_file(bucket, key)?;
+                self.named(&hot, bucket)?.concatenate_objects(bucket, key, &[source]).await?;
+            }
+        }
+        let restore = ObjectRestore {
+            etag: meta.etag.clone(),
+            expiry: Utc::now() + chrono::Duration::days(days as i64),
+        };
+        self.metadata.put_object_restore(bucket, key, &restore)?;
+        Ok(already_restored)
+    }
+
+    /// Remove restored copies whose expiry has passed. A copy is only deleted
+    /// while the routed backend does not hold the current object, which an
+    /// overwrite since the restore puts there. Returns the number removed.
+    pub async fn expire_restores(&self, now: DateTime<Utc>) -> Result<usize, S3Error> {
+        let mut removed = 0;
+        for (bucket, key, restore) in self.metadata.list_object_restores()? {
+            if restore.expiry > now {
+                continue;
+            }
+            let hot = self.route_name(&bucket, &key)?;
+            let copy_is_live = match self.metadata.get_object_meta(&bucket, &key) {
+                Ok(meta) => meta.storage_class.as_ref().is_none_or(|class| *class == hot),
+                Err(S3Error::NoSuchKey) => false,
+                Err(e) => return Err(e),
+            };
+            if !copy_is_live {
+                self.named(&hot, &bucket)?.delete_object(&bucket, &key).await?;
+                removed += 1;
+            }
+            self.metadata.delete_object_restore(&bucket, &key)?;
+        }
+        Ok(removed)
+    }
+
+    /// The file holding the data of `meta` and the offset of the data in it,
+    /// which is only non-zero for objects compacted into an archive segment.
+    pub fn locate(&self, meta: &ObjectMeta) -> Result<(PathBuf, u64), S3Error> {
+        if let Some(ArchiveEntry { segment: Some(segment), offset, .. }) =
+            self.metadata.get_archive_entry(&meta.bucket, &meta.key)?
+        {
+            return Ok((self.default.archive_segment_path(&meta.bucket, segment), offset));
+        }
+        let path = self.for_read(meta)?.open_object_file(&meta.bucket, &meta.key)?;
+        Ok((path, 0))
+    }
+
+    /// Read the whole object described by `meta`, wherever it is stored.
+    pub async fn read(&self, meta: &ObjectMeta) -> Result<Vec<u8>, S3Error> {
+        let (path, offset) = self.locate(meta)?;
+        self.default.read_range(&path, offset, meta.size).await
+    }
+
+    /// Freeze `bucket` and record the manifest of its objects. With `compact`
+    /// the object data is moved into archive segments of about
+    /// `ARCHIVE_SEGMENT_SIZE` bytes. Returns the manifest.
+    pub async fn freeze(&self, bucket: &str, compact: bool) -> Result<Vec<ArchiveEntry>, S3Error> {
+        self.freeze_with_segment_size(bucket, compact, ARCHIVE_SEGMENT_SIZE).await
+    }
+
+    async fn freeze_with_segment_size(
+        &self,
+        bucket: &str,
+        compact: bool,
+        segment_size: u64,
+    ) -> Result<Vec<ArchiveEntry>, S3Error> {
+        if self.metadata.get_bucket(bucket)?.frozen.is_some() {
+            return Err(S3Error::BucketFrozen);
+        }
+        // Writes are rejected from here on, so the listing below is final
+        let mut freeze = BucketFreeze {
+            frozen_at: Utc::now(),
+            compacted: false,
+        };
+        self.metadata.set_bucket_freeze(bucket, Some(freeze.clone()))?;
+
+        let objects = self
+            .metadata
+            .list_objects_v2(&ListObjectsV2Request {
+                bucket: bucket.to_string(),
+                prefix: String::new(),
+                delimiter: String::new(),
+                max_keys: u32::MAX,
+                continuation_token: None,
+                start_after: None,
+            })?
+            .contents;
+
+        let (mut segment, mut segment_len) = (0u32, 0u64);
+        let mut manifest = Vec::with_capacity(objects.len());
+        for meta in &objects {
+            let source = self.for_object(meta)?.open_object_file(bucket, &meta.key)?;
+            let mut entry = ArchiveEntry {
+                key: meta.key.clone(),
+                size: meta.size,
+                etag: meta.etag.clone(),
+                sha256: String::new(),
+                segment: None,
+                offset: 0,
+            };
+            if compact {
+                if segment_len > 0 && segment_len + meta.size > segment_size {
+                    segment += 1;
+                }
+                let (offset, sha256) = self.default.append_to_segment(bucket, segment, &source).await?;
+                segment_len = offset + meta.size;
+                entry.sha256 = sha256;
+                entry.segment = Some(segment);
+                entry.offset = offset;
+            } else {
+                entry.sha256 = self.default.sha256_file(&source).await?;
+            }
+            self.metadata.put_archive_entry(bucket, &entry)?;
+            manifest.push(entry);
+        }
+
+        if compact {
+            for meta in &objects {
+                self.for_object(meta)?.delete_object(bucket, &meta.key).await?;
+            }
+            freeze.compacted = true;
+            self.metadata.set_bucket_freeze(bucket, Some(freeze))?;
+        }
+        Ok(manifest)
+    }
+
+    /// Thaw a frozen bucket: write compacted objects back to the stores they
+    /// came from, drop the archive and manifest, and accept writes again.
+    /// Returns the number of objects written back.
+    pub async fn thaw(&self, bucket: &str) -> Result<usize, S3Error> {
+        if self.metadata.get_bucket(bucket)?.frozen.is_none() {
+            return Err(S3Error::InvalidArgument("Bucket is not frozen".into()));
+        }
+        let mut restored = 0;
+        for entry in self.metadata.list_archive_entries(bucket)? {
+            let Some(segment) = entry.segment else {
+                continue;
+            };
+            let meta = self.metadata.get_object_meta(bucket, &entry.key)?;
+            let path = self.default.archive_segment_path(bucket, segment);
+            let mut file = tokio::fs::File::open(&path)
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            file.seek(std::io::SeekFrom::Start(entry.offset))
+                .await
+                .map_err(|e| S3Error::InternalError(e.to_string()))?;
+            let mut reader = file.take(entry.size);
+            self.for_object(&meta)?.write_object_stream(bucket, &entry.key, &mut reader).await?;
+            restored += 1;
+        }
+        self.default.delete_archive(bucket).await?;
+        self.metadata.delete_archive_entries(bucket)?;
+        self.metadata.set_bucket_freeze(bucket, None)?;
+        Ok(restored)
+    }
+
+    /// The store holding the current object at `bucket/key`, which may have
+    /// been transitioned. Falls back to the routed store for unknown keys.
+    pub fn for_existing(&self, bucket: &str, key: &str) -> Result<&FileStore, S3Error> {
+        match self.metadata.get_object_meta(bucket, key) {
+            Ok(meta) => self.for_object(&meta),
+            Err(S3Error::NoSuchKey) => self.for_key(bucket, key),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the data of a transitioned object about to be replaced by one
+    /// written to the routed store. Call before recording the new metadata.
+    pub async fn delete_transitioned_copy(&self, bucket: &str, key: &str) -> Result<(), S3Error> {
+        let class = match self.metadata.get_object_meta(bucket, key) {
+            Ok(ObjectMeta { storage_class: Some(class), .. }) => class,
+            Ok(_) | Err(S3Error::NoSuchKey) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if class == self.route_name(bucket, key)? {
+            return Ok(());
+        }
+        self.named(&class, bucket)?.delete_object(bucket, key).await
+    }
+
+    /// Move an object to the backend of `storage_class` and record the class.
+    /// Returns false, leaving the object where it was, if it was rewritten or
+    /// deleted in the meantime.
+    pub async fn transition(&self, meta: &ObjectMeta, storage_class: &str) -> Result<bool, S3Error> {
+        let (bucket, key) = (meta.bucket.as_str(), meta.key.as_str());
+        match self.metadata.get_object_meta(bucket, key) {
+            Ok(stored)
+                if stored.etag == meta.etag
+                    && stored.last_modified == meta.last_modified
+                    && stored.storage_class == meta.storage_class => {}
+            Ok(_) | Err(S3Error::NoSuchKey) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+        let current = match &meta.storage_class {
+            Some(class) => class.clone(),
+            None => self.route_name(bucket, key)?,
+        };
+        let target = self.named(storage_class, bucket)?;
+        let moved = current != storage_class;
+        if moved {
+            let source = self.named(&current, bucket)?.open_object_file(bucket, key)?;
+            target.concatenate_objects(bucket, key, &[source]).await?;
+        }
+        let updated = ObjectMeta {
+            storage_class: Some(storage_class.to_string()),
+            ..meta.clone()
+        };
+        if !self.metadata.update_object_meta_if_unchanged(meta, &updated)? {
+            if moved {
+                target.delete_object(bucket, key).await?;
+            }
+            return Ok(false);
+        }
+        if moved {
+            self.named(&current, bucket)?.delete_object(bucket, key).await?;
+        }
+        Ok(true)
+    }
+
+    /// Backend name the bucket's storage routes give `key`.
+    fn route_name(&self, bucket: &str, key: &str) -> Result<String, S3Error> {
+        let routes = self.metadata.get_storage_routes(bucket)?;
+        Ok(resolve(&routes, key).unwrap_or(DEFAULT_BACKEND).to_string())
+    }
+
+    fn named(&self, name: &str, bucket: &str) -> Result<&FileStore, S3Error> {
+        self.backend(name).ok_or_else(|| {
+            S3Error::InternalError(format!("Unknown storage backend '{}' for bucket {}", name, bucket))
+        })
+    }
+
+    fn all(&self) -> impl Iterator<Item = &FileStore> {
+        std::iter::once(&self.default).chain(self.backends.values())
+    }
+
+    pub async fn create_bucket_dir(&self, bucket: &str) -> Result<(), S3Error> {
+        for store in self.all() {
+            store.create_bucket_dir(bucket).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn delete_bucket_dir(&self, bucket: &str) -> Result<(), S3Error> {
+        for store in self.all() {
+            store.delete_bucket_dir(bucket).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Backend name of the longest route prefix matching `key`, if any.
+pub fn resolve<'a>(routes: &'a [StorageRoute], key: &str) -> Option<&'a str> {
+    routes
+        .iter()
+        .filter(|r| key.starts_with(&r.prefix))
+        .max_by_key(|r| r.prefix.len())
+        .map(|r| r.backend.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(prefix: &str, backend: &str) -> StorageRoute {
+        StorageRoute {
+            prefix: prefix.into(),
+            backend: backend.into(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_longest_prefix() {
+        let routes = vec![route("raw/", "archive"), route("raw/hot/", "nvme")];
+        assert_eq!(resolve(&routes, "raw/a.bin"), Some("archive"));
+        assert_eq!(resolve(&routes, "raw/hot/a.bin"), Some("nvme"));
+        assert_eq!(resolve(&routes, "thumbs/a.png"), None);
+    }
+
+    #[tokio::test]
+    async fn test_router_writes_to_routed_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = MetadataStore::open(&dir.path().join("meta")).unwrap();
+        metadata.create_bucket("bkt").unwrap();
+        metadata.put_storage_routes("bkt", &[route("thumbs/", "fast")]).unwrap();
+
+        let router = StorageRouter::new(FileStore::new(&dir.path().join("data")), metadata)
+            .with_backend("fast", FileStore::new(&dir.path().join("fast")));
+        router.create_bucket_dir("bkt").await.unwrap();
+
+        router.for_key("bkt", "thumbs/a.png").unwrap().write_object("bkt", "thumbs/a.png", b"x").await.unwrap();
+        router.for_key("bkt", "raw/a.bin").unwrap().write_object("bkt", "raw/a.bin", b"y").await.unwrap();
+        assert!(dir.path().join("fast/bkt/thumbs/a.png").exists());
+        assert!(dir.path().join("data/bkt/raw/a.bin").exists());
+        assert!(!dir.path().join("data/bkt/thumbs/a.png").exists());
+    }
+
+    #[tokio::test]
+    async fn test_transition_moves_object() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = MetadataStore::open(&dir.path().join("meta")).unwrap();
+        metadata.create_bucket("bkt").unwrap();
+        let router = StorageRouter::new(FileStore::new(&dir.path().join("data")), metadata.clone())
+            .with_backend("COLD", FileStore::new(&dir.path().join("cold")));
+        router.create_bucket_dir("bkt").await.unwrap();
+
+        let (size, etag) = router.for_key("bkt", "a.log").unwrap().write_object("bkt", "a.log", b"old").await.unwrap();
+        let meta = ObjectMeta {
+            bucket: "bkt".into(),
+            key: "a.log".into(),
+            size,
+            etag,
+            content_type: "text/plain".into(),
+            last_modified: chrono::Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        };
+        metadata.put_object_meta(&meta).unwrap();
+
+        assert!(router.transition(&meta, "COLD").await.unwrap());
+        assert!(!dir.path().join("data/bkt/a.log").exists());
+        let moved = metadata.get_object_meta("bkt", "a.log").unwrap();
+        assert_eq!(moved.storage_class.as_deref(), Some("COLD"));
+        assert_eq!(moved.etag, meta.etag);
+        assert_eq!(router.for_existing("bkt", "a.log").unwrap().read_object("bkt", "a.log").await.unwrap(), b"old");
+
+        // A stale snapshot of the object is not moved again
+        assert!(!router.transition(&meta, "COLD").await.unwrap());
+
+        // Overwriting writes to the routed store and drops the cold copy
+        router.for_key("bkt", "a.log").unwrap().write_object("bkt", "a.log", b"new").await.unwrap();
+        router.delete_transitioned_copy("bkt", "a.log").await.unwrap();
+        assert!(!dir.path().join("cold/bkt/a.log").exists());
+        assert!(dir.path().join("data/bkt/a.log").exists());
+    }
+
+    #[tokio::test]
+    async fn test_restore_and_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = MetadataStore::open(&dir.path().join("meta")).unwrap();
+        metadata.create_bucket("bkt").unwrap();
+        let router = StorageRouter::new(FileStore::new(&dir.path().join("data")), metadata.clone())
+            .with_backend("COLD", FileStore::new(&dir.path().join("cold")));
+        router.create_bucket_dir("bkt").await.unwrap();
+
+        let (size, etag) = router.for_key("bkt", "a.log").unwrap().write_object("bkt", "a.log", b"old").await.unwrap();
+        let meta = ObjectMeta {
+            bucket: "bkt".into(),
+            key: "a.log".into(),
+            size,
+            etag,
+            content_type: "text/plain".into(),
+            last_modified: chrono::Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        };
+        metadata.put_object_meta(&meta).unwrap();
+
+        // Only transitioned objects can be restored
+        assert!(matches!(router.restore(&meta, 1).await, Err(S3Error::InvalidObjectState)));
+
+        router.transition(&meta, "COLD").await.unwrap();
+        let cold = metadata.get_object_meta("bkt", "a.log").unwrap();
+        assert!(!router.restore(&cold, 1).await.unwrap());
+        assert!(dir.path().join("data/bkt/a.log").exists());
+        assert!(router.active_restore(&cold).unwrap().is_some());
+        assert_eq!(router.for_read(&cold).unwrap().read_object("bkt", "a.log").await.unwrap(), b"old");
+        // Restoring again only extends the expiry
+        assert!(router.restore(&cold, 2).await.unwrap());
+
+        // Not yet expired
+        assert_eq!(router.expire_restores(Utc::now()).await.unwrap(), 0);
+        assert!(dir.path().join("data/bkt/a.log").exists());
+
+        let later = Utc::now() + chrono::Duration::days(3);
+        assert_eq!(router.expire_restores(later).await.unwrap(), 1);
+        assert!(!dir.path().join("data/bkt/a.log").exists());
+        assert!(dir.path().join("cold/bkt/a.log").exists());
+        assert!(metadata.get_object_restore("bkt", "a.log").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_freeze_compact_and_thaw() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata = MetadataStore::open(&dir.path().join("meta")).unwrap();
+        metadata.create_bucket("bkt").unwrap();
+        let router = StorageRouter::new(FileStore::new(&dir.path().join("data")), metadata.clone());
+        router.create_bucket_dir("bkt").await.unwrap();
+
+        let mut objects = Vec::new();
+        for (key, data) in [("a.txt", &b"alpha"[..]), ("b.txt", b"bravo!"), ("c.txt", b"charlie")] {
+            let (size, etag) = router.default_store().write_object("bkt", key, data).await.unwrap();
+            let meta = ObjectMeta {
+                bucket: "bkt".into(),
+                key: key.into(),
+                size,
+                etag,
+                content_type: "text/plain".into(),
+                last_modified: chrono::Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            };
+            metadata.put_object_meta(&meta).unwrap();
+            objects.push(meta);
+        }
+
+        // Segments roll over once they would exceed 12 bytes
+        let manifest = router.freeze_with_segment_size("bkt", true, 12).await.unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest[0].segment, Some(0));
+        assert_eq!((manifest[1].segment, manifest[1].offset), (Some(0), 5));
+        assert_eq!((manifest[2].segment, manifest[2].offset), (Some(1), 0));
+        assert_eq!(manifest[0].sha256, hex::encode(<sha2::Sha256 as sha2::Digest>::digest(b"alpha")));
+        assert!(!dir.path().join("data/bkt/a.txt").exists());
+        assert!(metadata.get_bucket("bkt").unwrap().frozen.unwrap().compacted);
+        assert_eq!(router.read(&objects[1]).await.unwrap(), b"bravo!");
+        assert!(matches!(router.freeze("bkt", false).await, Err(S3Error::BucketFrozen)));
+        assert!(matches!(metadata.delete_bucket("bkt"), Err(S3Error::BucketFrozen)));
+
+        assert_eq!(router.thaw("bkt").await.unwrap(), 3);
+        assert!(metadata.get_bucket("bkt").unwrap().frozen.is_none());
+        assert!(metadata.list_archive_entries("bkt").unwrap().is_empty());
+        assert!(!dir.path().join("data/.archive/bkt").exists());
+        assert_eq!(router.read(&objects[2]).await.unwrap(), b"charlie");
+        assert_eq!(router.default_store().read_object("bkt", "a.txt").await.unwrap(), b"alpha");
+        assert!(matches!(router.thaw("bkt").await, Err(S3Error::InvalidArgument(_))));
+    }
+}
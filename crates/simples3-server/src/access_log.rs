@@ -0,0 +1,145 @@
+use crate::AppState;
+use chrono::Utc;
+use simples3_core::S3Error;
+use simples3_core::s3::types::ObjectMeta;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Records buffered per source bucket between flushes. Records past this are
+/// dropped until the next flush.
+const MAX_PENDING_RECORDS: usize = 10_000;
+
+/// Server access log records, formatted as S3 log lines and grouped by the
+/// bucket they were recorded for, until the flush loop writes them out.
+#[derive(Default)]
+pub struct AccessLogBuffer {
+    pending: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl AccessLogBuffer {
+    pub fn record(&self, bucket: &str, line: String) {
+        let mut pending = self.pending.lock().unwrap();
+        let lines = pending.entry(bucket.to_string()).or_default();
+        if lines.len() < MAX_PENDING_RECORDS {
+            lines.push(line);
+        }
+    }
+
+    pub fn drain(&self) -> Vec<(String, Vec<String>)> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.drain().collect()
+    }
+}
+
+/// One request, as written to the access log.
+pub struct AccessLogRecord<'a> {
+    pub bucket: &'a str,
+    pub time: chrono::DateTime<Utc>,
+    pub remote_ip: Option<&'a str>,
+    pub requester: Option<&'a str>,
+    pub request_id: &'a str,
+    pub operation: &'a str,
+    pub key: Option<&'a str>,
+    pub request_uri: &'a str,
+    pub status: u16,
+    pub error_code: Option<&'a str>,
+    pub bytes_sent: Option<u64>,
+    pub object_size: Option<u64>,
+    pub total_time_ms: u128,
+    pub referer: Option<&'a str>,
+    pub user_agent: Option<&'a str>,
+    pub signature_version: Option<&'a str>,
+    pub auth_type: Option<&'a str>,
+    pub host: Option<&'a str>,
+}
+
+impl AccessLogRecord<'_> {
+    /// Format in the S3 server access log layout. Fields SimpleS3 does not
+    /// track (bucket owner, version id, host id, TLS details) are `-`.
+    pub fn to_line(&self) -> String {
+        let dash = |v: Option<&str>| v.filter(|s| !s.is_empty()).unwrap_or("-").to_string();
+        let quoted = |v: Option<&str>| format!("\"{}\"", v.unwrap_or("-").replace('"', "\\\""));
+        [
+            "-".to_string(),
+            self.bucket.to_string(),
+            self.time.format("[%d/%b/%Y:%H:%M:%S %z]").to_string(),
+            dash(self.remote_ip),
+            dash(self.requester),
+            self.request_id.to_string(),
+            self.operation.to_string(),
+            dash(self.key),
+            quoted(Some(self.request_uri)),
+            self.status.to_string(),
+            dash(self.error_code),
+            self.bytes_sent.map_or("-".to_string(), |b| b.to_string()),
+            self.object_size.map_or("-".to_string(), |b| b.to_string()),
+            self.total_time_ms.to_string(),
+            self.total_time_ms.to_string(),
+            quoted(self.referer),
+            quoted(self.user_agent),
+            "-".to_string(),
+            "-".to_string(),
+            dash(self.signature_version),
+            "-".to_string(),
+            dash(self.auth_type),
+            dash(self.host),
+            "-".to_string(),
+        ]
+        .join(" ")
+    }
+}
+
+/// Write buffered records every `interval_secs`, one object per source
+/// bucket and flush. Records of buckets whose logging was disabled in the
+/// meantime are discarded.
+pub async fn flush_loop(state: Arc<AppState>, interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        flush(&state).await;
+    }
+}
+
+pub async fn flush(state: &AppState) {
+    for (bucket, lines) in state.access_logs.drain() {
+        let config = match state.metadata.get_bucket_logging(&bucket) {
+            Ok(Some(c)) => c,
+            _ => continue,
+        };
+        let key = format!(
+            "{}{}-{}",
+            config.target_prefix,
+            Utc::now().format("%Y-%m-%d-%H-%M-%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..16].to_uppercase()
+        );
+        let mut body = lines.join("\n");
+        body.push('\n');
+        match write_log_object(state, &config.target_bucket, &key, body.as_bytes()).await {
+            Ok(()) => {
+                tracing::debug!(bucket = %bucket, target = %config.target_bucket, key = %key, records = lines.len(), "Wrote access log");
+            }
+            Err(e) => {
+                tracing::warn!(bucket = %bucket, target = %config.target_bucket, error = %e, "Access log write failed");
+            }
+        }
+    }
+}
+
+async fn write_log_object(state: &AppState, bucket: &str, key: &str, data: &[u8]) -> Result<(), S3Error> {
+    let _ = state.metadata.get_bucket(bucket)?;
+    let (size, etag) = state.storage.for_key(bucket, key)?.write_object(bucket, key, data).await?;
+    state.metadata.put_object_meta(&ObjectMeta {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        size,
+        etag,
+        content_type: "text/plain".into(),
+        last_modified: Utc::now(),
+        public: false,
+        checksum: None,
+        legal_hold: false,
+        storage_class: None,
+    })
+}
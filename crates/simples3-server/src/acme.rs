@@ -0,0 +1,473 @@
+//! Automatic TLS certificates from an ACME CA such as Let's Encrypt.
+//!
+//! With `SIMPLES3_ACME_ENABLED`, the listeners serve HTTPS with a certificate
+//! for `SIMPLES3_HOSTNAME` ordered from `SIMPLES3_ACME_DIRECTORY` (RFC 8555).
+//! Domain control is proven with the TLS-ALPN-01 challenge, answered by the
+//! S3 listener itself, so it must be reachable by the CA on port 443. HTTP-01
+//! is not supported. The account key and the certificate are kept in
+//! `<data_dir>/.acme` and the certificate is renewed after 60 days.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use chrono::{DateTime, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{ECDSA_P256_SHA256_FIXED_SIGNING, EcdsaKeyPair, KeyPair};
+use rustls::ServerConfig;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use simples3_core::Config;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// ALPN protocol of TLS-ALPN-01 validation connections (RFC 8737).
+const ACME_TLS_ALPN: &[u8] = b"acme-tls/1";
+/// Certificates are renewed once this old (Let's Encrypt issues them for 90 days).
+const RENEW_AFTER_DAYS: i64 = 60;
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 3600);
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+/// Polls of a pending authorization or order before giving up.
+const POLL_ATTEMPTS: u32 = 30;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Serves the ACME certificate, or the challenge certificate to validation
+/// connections.
+#[derive(Debug, Default)]
+pub struct CertResolver {
+    certificate: RwLock<Option<Arc<CertifiedKey>>>,
+    challenge: RwLock<Option<Arc<CertifiedKey>>>,
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let validation = hello.alpn().is_some_and(|mut protocols| protocols.any(|p| p == ACME_TLS_ALPN));
+        if validation {
+            self.challenge.read().unwrap().clone()
+        } else {
+            self.certificate.read().unwrap().clone()
+        }
+    }
+}
+
+/// Issuance record stored next to the certificate.
+#[derive(Serialize, Deserialize)]
+struct CertificateInfo {
+    domain: String,
+    issued: DateTime<Utc>,
+}
+
+/// TLS settings serving the ACME certificate, and the task renewing it.
+pub struct Acme {
+    pub server_config: Arc<ServerConfig>,
+    pub handle: tokio::task::JoinHandle<()>,
+}
+
+/// Set up ACME certificates when enabled: load the stored certificate and
+/// spawn the task obtaining and renewing it. Until a certificate is issued,
+/// TLS handshakes other than the CA's validation fail.
+pub fn start(config: &Config) -> Result<Option<Acme>, String> {
+    if !config.acme_enabled {
+        return Ok(None);
+    }
+    let manager = Manager {
+        directory: config.acme_directory.clone(),
+        email: config.acme_email.clone(),
+        domain: config.hostname.clone(),
+        dir: config.data_dir.join(".acme"),
+        http: reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| e.to_string())?,
+        resolver: Arc::new(CertResolver::default()),
+    };
+    std::fs::create_dir_all(&manager.dir).map_err(|e| format!("Failed to create {}: {}", manager.dir.display(), e))?;
+    if let Some(certificate) = manager.load_certificate()? {
+        *manager.resolver.certificate.write().unwrap() = Some(certificate);
+    }
+
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_cert_resolver(manager.resolver.clone());
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec(), ACME_TLS_ALPN.to_vec()];
+    Ok(Some(Acme {
+        server_config: Arc::new(server_config),
+        handle: tokio::spawn(renewal_loop(manager)),
+    }))
+}
+
+struct Manager {
+    directory: String,
+    email: Option<String>,
+    domain: String,
+    dir: PathBuf,
+    http: reqwest::Client,
+    resolver: Arc<CertResolver>,
+}
+
+async fn renewal_loop(manager: Manager) {
+    loop {
+        let wait = match manager.renew_if_due().await {
+            Ok(()) => CHECK_INTERVAL,
+            Err(e) => {
+                tracing::warn!(domain = %manager.domain, error = %e, "ACME certificate order failed");
+                RETRY_INTERVAL
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+impl Manager {
+    /// The stored certificate, if it was issued for the configured domain.
+    fn load_certificate(&self) -> Result<Option<Arc<CertifiedKey>>, String> {
+        let Some(info) = self.certificate_info() else {
+            return Ok(None);
+        };
+        if info.domain != self.domain {
+            return Ok(None);
+        }
+        let certs = CertificateDer::pem_file_iter(self.dir.join("cert.pem"))
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to read the ACME certificate: {}", e))?;
+        let key = PrivateKeyDer::from_pem_file(self.dir.join("key.pem"))
+            .map_err(|e| format!("Failed to read the ACME certificate key: {}", e))?;
+        certified_key(certs, key).map(Some)
+    }
+
+    fn certificate_info(&self) -> Option<CertificateInfo> {
+        let bytes = std::fs::read(self.dir.join("certificate.json")).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    async fn renew_if_due(&self) -> Result<(), String> {
+        let current = self.resolver.certificate.read().unwrap().is_some();
+        if current
+            && let Some(info) = self.certificate_info()
+            && info.domain == self.domain
+            && Utc::now() - info.issued < chrono::Duration::days(RENEW_AFTER_DAYS)
+        {
+            return Ok(());
+        }
+
+        tracing::info!(domain = %self.domain, directory = %self.directory, "Ordering ACME certificate");
+        let mut account = Account::register(self).await?;
+        let result = account.order(&self.domain, &self.resolver).await;
+        *self.resolver.challenge.write().unwrap() = None;
+        let (chain, key) = result?;
+
+        let certs = CertificateDer::pem_slice_iter(chain.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Invalid certificate chain: {}", e))?;
+        let certificate = certified_key(certs, PrivateKeyDer::Pkcs8(key.serialize_der().into()))?;
+        let info = CertificateInfo {
+            domain: self.domain.clone(),
+            issued: Utc::now(),
+        };
+        write_file(&self.dir.join("cert.pem"), chain.as_bytes())?;
+        write_file(&self.dir.join("key.pem"), key.serialize_pem().as_bytes())?;
+        write_file(
+            &self.dir.join("certificate.json"),
+            &serde_json::to_vec(&info).map_err(|e| e.to_string())?,
+        )?;
+        *self.resolver.certificate.write().unwrap() = Some(certificate);
+        tracing::info!(domain = %self.domain, "Installed ACME certificate");
+        Ok(())
+    }
+}
+
+fn certified_key(certs: Vec<CertificateDer<'static>>, key: PrivateKeyDer<'static>) -> Result<Arc<CertifiedKey>, String> {
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key).map_err(|e| e.to_string())?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+fn write_file(path: &std::path::Path, data: &[u8]) -> Result<(), String> {
+    std::fs::write(path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    status: String,
+    #[serde(default)]
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+    status: String,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    token: String,
+    error: Option<Problem>,
+}
+
+#[derive(Deserialize)]
+struct Problem {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    detail: String,
+}
+
+/// An ACME account, signing its requests with JWS.
+struct Account<'a> {
+    http: &'a reqwest::Client,
+    directory: Directory,
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    nonce: Option<String>,
+    /// Account URL, once registered.
+    kid: Option<String>,
+}
+
+impl<'a> Account<'a> {
+    /// Load or create the account key and register it, which also returns
+    /// the existing account for a known key.
+    async fn register(manager: &'a Manager) -> Result<Self, String> {
+        let directory: Directory = manager
+            .http
+            .get(&manager.directory)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| format!("Failed to fetch the ACME directory: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid ACME directory: {}", e))?;
+
+        let rng = SystemRandom::new();
+        let key_path = manager.dir.join("account.key");
+        let pkcs8 = match std::fs::read(&key_path) {
+            Ok(pkcs8) => pkcs8,
+            Err(_) => {
+                let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+                    .map_err(|e| e.to_string())?;
+                write_file(&key_path, pkcs8.as_ref())?;
+                pkcs8.as_ref().to_vec()
+            }
+        };
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &rng)
+            .map_err(|e| format!("Invalid ACME account key: {}", e))?;
+
+        let mut account = Account {
+            http: &manager.http,
+            directory,
+            key,
+            rng,
+            nonce: None,
+            kid: None,
+        };
+        let mut payload = serde_json::json!({ "termsOfServiceAgreed": true });
+        if let Some(email) = &manager.email {
+            payload["contact"] = serde_json::json!([format!("mailto:{}", email)]);
+        }
+        let url = account.directory.new_account.clone();
+        let resp = account.post(&url, Some(&payload)).await?;
+        account.kid = Some(location(&resp)?);
+        Ok(account)
+    }
+
+    /// Order a certificate for `domain`, answering its challenge through
+    /// `resolver`. Returns the PEM chain and the certificate key.
+    async fn order(&mut self, domain: &str, resolver: &CertResolver) -> Result<(String, rcgen::KeyPair), String> {
+        let payload = serde_json::json!({ "identifiers": [{ "type": "dns", "value": domain }] });
+        let url = self.directory.new_order.clone();
+        let resp = self.post(&url, Some(&payload)).await?;
+        let order_url = location(&resp)?;
+        let order: Order = resp.json().await.map_err(|e| e.to_string())?;
+
+        for authz_url in &order.authorizations {
+            let authz: Authorization = self.fetch(authz_url).await?;
+            if authz.status == "valid" {
+                continue;
+            }
+            let challenge = authz
+                .challenges
+                .iter()
+                .find(|c| c.kind == "tls-alpn-01")
+                .ok_or("The CA offered no tls-alpn-01 challenge")?;
+            let key_authorization = format!("{}.{}", challenge.token, self.thumbprint());
+            *resolver.challenge.write().unwrap() = Some(challenge_certificate(domain, &key_authorization)?);
+            self.post(&challenge.url, Some(&serde_json::json!({}))).await?;
+            self.poll_authorization(authz_url).await?;
+        }
+
+        let key = rcgen::KeyPair::generate().map_err(|e| e.to_string())?;
+        let csr = rcgen::CertificateParams::new(vec![domain.to_string()])
+            .and_then(|params| params.serialize_request(&key))
+            .map_err(|e| e.to_string())?;
+        let payload = serde_json::json!({ "csr": URL_SAFE_NO_PAD.encode(csr.der()) });
+        self.post(&order.finalize, Some(&payload)).await?;
+
+        for _ in 0..POLL_ATTEMPTS {
+            let order: Order = self.fetch(&order_url).await?;
+            match (order.status.as_str(), order.certificate) {
+                ("valid", Some(certificate_url)) => {
+                    let resp = self.post(&certificate_url, None).await?;
+                    let chain = resp.text().await.map_err(|e| e.to_string())?;
+                    return Ok((chain, key));
+                }
+                ("invalid", _) => return Err("The CA rejected the order".into()),
+                _ => tokio::time::sleep(POLL_INTERVAL).await,
+            }
+        }
+        Err("Timed out waiting for the certificate".into())
+    }
+
+    async fn poll_authorization(&mut self, url: &str) -> Result<(), String> {
+        for _ in 0..POLL_ATTEMPTS {
+            let authz: Authorization = self.fetch(url).await?;
+            match authz.status.as_str() {
+                "valid" => return Ok(()),
+                "pending" => tokio::time::sleep(POLL_INTERVAL).await,
+                status => {
+                    let detail = authz
+                        .challenges
+                        .iter()
+                        .find_map(|c| c.error.as_ref())
+                        .map(|p| format!(": {}", p.detail))
+                        .unwrap_or_default();
+                    return Err(format!("Authorization is {}{}", status, detail));
+                }
+            }
+        }
+        Err("Timed out waiting for the domain validation".into())
+    }
+
+    /// POST-as-GET a resource.
+    async fn fetch<T: serde::de::DeserializeOwned>(&mut self, url: &str) -> Result<T, String> {
+        self.post(url, None).await?.json().await.map_err(|e| e.to_string())
+    }
+
+    /// Send a signed request, retrying once with a fresh nonce when the CA
+    /// rejects the previous one.
+    async fn post(&mut self, url: &str, payload: Option<&serde_json::Value>) -> Result<reqwest::Response, String> {
+        let mut retried = false;
+        loop {
+            let body = self.sign(url, payload).await?;
+            let resp = self
+                .http
+                .post(url)
+                .header("content-type", "application/jose+json")
+                .body(body)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            self.nonce = resp
+                .headers()
+                .get("replay-nonce")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            if resp.status().is_success() {
+                return Ok(resp);
+            }
+            let status = resp.status();
+            let problem: Problem = resp.json().await.unwrap_or(Problem {
+                kind: String::new(),
+                detail: String::new(),
+            });
+            if problem.kind.ends_with(":badNonce") && !retried {
+                retried = true;
+                continue;
+            }
+            return Err(format!("{} answered {} {}: {}", url, status, problem.kind, problem.detail));
+        }
+    }
+
+    async fn sign(&mut self, url: &str, payload: Option<&serde_json::Value>) -> Result<String, String> {
+        let nonce = match self.nonce.take() {
+            Some(nonce) => nonce,
+            None => self.new_nonce().await?,
+        };
+        let mut protected = serde_json::json!({ "alg": "ES256", "nonce": nonce, "url": url });
+        match &self.kid {
+            Some(kid) => protected["kid"] = serde_json::json!(kid),
+            None => protected["jwk"] = self.jwk(),
+        }
+        let protected = URL_SAFE_NO_PAD.encode(protected.to_string());
+        let payload = payload.map(|p| URL_SAFE_NO_PAD.encode(p.to_string())).unwrap_or_default();
+        let signature = self
+            .key
+            .sign(&self.rng, format!("{}.{}", protected, payload).as_bytes())
+            .map_err(|e| e.to_string())?;
+        Ok(serde_json::json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": URL_SAFE_NO_PAD.encode(signature.as_ref()),
+        })
+        .to_string())
+    }
+
+    async fn new_nonce(&self) -> Result<String, String> {
+        let resp = self
+            .http
+            .head(&self.directory.new_nonce)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        resp.headers()
+            .get("replay-nonce")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| "The CA returned no nonce".into())
+    }
+
+    /// Public key coordinates; the key is an uncompressed P-256 point.
+    fn coordinates(&self) -> (String, String) {
+        let point = self.key.public_key().as_ref();
+        (URL_SAFE_NO_PAD.encode(&point[1..33]), URL_SAFE_NO_PAD.encode(&point[33..65]))
+    }
+
+    fn jwk(&self) -> serde_json::Value {
+        let (x, y) = self.coordinates();
+        serde_json::json!({ "crv": "P-256", "kty": "EC", "x": x, "y": y })
+    }
+
+    /// JWK thumbprint (RFC 7638): members in lexicographic order, no whitespace.
+    fn thumbprint(&self) -> String {
+        let (x, y) = self.coordinates();
+        let jwk = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+        URL_SAFE_NO_PAD.encode(Sha256::digest(jwk.as_bytes()))
+    }
+}
+
+fn location(resp: &reqwest::Response) -> Result<String, String> {
+    resp.headers()
+        .get("location")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} returned no Location", resp.url()))
+}
+
+/// Self-signed certificate carrying the key authorization digest in the
+/// `acmeIdentifier` extension (RFC 8737).
+fn challenge_certificate(domain: &str, key_authorization: &str) -> Result<Arc<CertifiedKey>, String> {
+    let mut params = rcgen::CertificateParams::new(vec![domain.to_string()]).map_err(|e| e.to_string())?;
+    params.custom_extensions = vec![rcgen::CustomExtension::new_acme_identifier(&Sha256::digest(
+        key_authorization.as_bytes(),
+    ))];
+    let key = rcgen::KeyPair::generate().map_err(|e| e.to_string())?;
+    let cert = params.self_signed(&key).map_err(|e| e.to_string())?;
+    certified_key(vec![cert.der().clone()], PrivateKeyDer::Pkcs8(key.serialize_der().into()))
+}
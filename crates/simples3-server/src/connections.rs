@@ -0,0 +1,149 @@
+//! Requests in flight on the S3 listener, for the admin API.
+//!
+//! The connections middleware registers every S3 request under its request
+//! ID with the client address, operation and bucket, and counts the body
+//! bytes received and sent until the response has been sent. An operator can
+//! cancel a request: one still being handled is answered with `503` and its
+//! connection closed, while a response still streaming is cut off, which
+//! closes the connection as well.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// A request in flight, as listed by `GET /_admin/connections`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveRequestInfo {
+    /// The request's `x-amz-request-id`, used to cancel it.
+    pub id: String,
+    pub remote_ip: Option<String>,
+    pub method: String,
+    pub operation: &'static str,
+    pub bucket: Option<String>,
+    pub key: Option<String>,
+    pub started: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+    /// Cancelled, waiting for the handler or response body to stop.
+    pub cancelled: bool,
+}
+
+/// What the middleware knows about a request when it starts.
+pub struct RequestDetails {
+    pub id: String,
+    pub remote_ip: Option<String>,
+    pub method: String,
+    pub operation: &'static str,
+    pub bucket: Option<String>,
+    pub key: Option<String>,
+}
+
+pub struct ActiveRequest {
+    details: RequestDetails,
+    started: DateTime<Utc>,
+    since: Instant,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    cancel: CancellationToken,
+}
+
+impl ActiveRequest {
+    pub fn received(&self, bytes: u64) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub fn sent(&self, bytes: u64) {
+        self.bytes_sent.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Completes once the request has been cancelled.
+    pub fn cancelled(&self) -> tokio_util::sync::WaitForCancellationFuture<'_> {
+        self.cancel.cancelled()
+    }
+
+    fn info(&self) -> ActiveRequestInfo {
+        let d = &self.details;
+        ActiveRequestInfo {
+            id: d.id.clone(),
+            remote_ip: d.remote_ip.clone(),
+            method: d.method.clone(),
+            operation: d.operation,
+            bucket: d.bucket.clone(),
+            key: d.key.clone(),
+            started: self.started,
+            duration_ms: self.since.elapsed().as_millis() as u64,
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            cancelled: self.cancel.is_cancelled(),
+        }
+    }
+}
+
+/// Requests in flight on the S3 listener, by request ID.
+#[derive(Clone, Default)]
+pub struct ActiveRequests {
+    requests: Arc<Mutex<HashMap<String, Arc<ActiveRequest>>>>,
+}
+
+impl ActiveRequests {
+    /// Register a request until the returned guard drops.
+    pub fn register(&self, details: RequestDetails) -> ActiveGuard {
+        let request = Arc::new(ActiveRequest {
+            details,
+            started: Utc::now(),
+            since: Instant::now(),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            cancel: CancellationToken::new(),
+        });
+        self.requests
+            .lock()
+            .unwrap()
+            .insert(request.details.id.clone(), request.clone());
+        ActiveGuard {
+            requests: self.clone(),
+            request,
+        }
+    }
+
+    /// Every request in flight, longest-running first.
+    pub fn list(&self) -> Vec<ActiveRequestInfo> {
+        let mut requests: Vec<_> = self.requests.lock().unwrap().values().map(|r| r.info()).collect();
+        requests.sort_by_key(|r| std::cmp::Reverse(r.duration_ms));
+        requests
+    }
+
+    /// Cancel a request in flight. Returns `false` for unknown requests.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.requests.lock().unwrap().get(id) {
+            Some(request) => {
+                request.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a request listed as in flight.
+pub struct ActiveGuard {
+    requests: ActiveRequests,
+    request: Arc<ActiveRequest>,
+}
+
+impl ActiveGuard {
+    pub fn request(&self) -> &Arc<ActiveRequest> {
+        &self.request
+    }
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.requests.requests.lock().unwrap().remove(&self.request.details.id);
+    }
+}
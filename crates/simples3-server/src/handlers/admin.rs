@@ -1,10 +1,22 @@
 use crate::AppState;
 use axum::body::Body;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, Request, State};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::{DateTime, Utc};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
+use simples3_core::auth::credential_export::{self, CredentialExport};
+use simples3_core::auth::presigned_post::{presign_post, PresignedPostParams};
+use simples3_core::auth::sigv4;
+use simples3_core::auth::simulate::{self, SimulateRequest};
+use simples3_core::clock::Clock;
+use simples3_core::s3::types::{
+    AccessKeyRecord, ArchiveEntry, AuditQuery, BucketAdminToken, BucketUsage, CacheHeadersConfig, ConfigKind, CredentialRateLimit, ModerationState, ObjectMeta, ObjectModeration, ObjectScan, Permission, RenditionConfig, ReplicationConfiguration, RequestLogConfig,
+    ScanStatus, SecurityWebhookConfig,
+    StorageRoute, UsageAlertConfig, UsageReportConfig,
+};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 
 #[derive(Serialize)]
@@ -13,6 +25,16 @@ struct BucketInfo {
     creation_date: String,
     anonymous_read: bool,
     anonymous_list_public: bool,
+    deletion_protected: bool,
+    frozen: bool,
+}
+
+#[derive(Serialize)]
+struct DeletedBucketInfo {
+    name: String,
+    creation_date: String,
+    deleted_at: String,
+    purge_after: String,
 }
 
 #[derive(Serialize)]
@@ -22,11 +44,87 @@ struct CredentialInfo {
     description: String,
     created: String,
     active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    permissions: Option<BTreeSet<Permission>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_limit: Option<CredentialRateLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket: Option<String>,
+}
+
+impl CredentialInfo {
+    fn new(record: AccessKeyRecord, secret_access_key: String) -> Self {
+        CredentialInfo {
+            access_key_id: record.access_key_id,
+            secret_access_key,
+            description: record.description,
+            created: record.created.to_rfc3339(),
+            active: record.active,
+            permissions: record.permissions,
+            rate_limit: record.rate_limit,
+            bucket: record.bucket,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportCredentialsRequest {
+    /// Encrypt the export with this passphrase.
+    pub passphrase: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportCredentialsRequest {
+    pub export: CredentialExport,
+    pub passphrase: Option<String>,
+    /// Overwrite existing credentials that differ from the export.
+    #[serde(default)]
+    pub replace: bool,
 }
 
 #[derive(Deserialize)]
 pub struct CreateCredentialRequest {
     pub description: Option<String>,
+    /// Operations the key may perform; omitted means unrestricted.
+    pub permissions: Option<BTreeSet<Permission>>,
+    /// Bind the key to this bucket; omitted means any bucket.
+    pub bucket: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBucketCredentialRequest {
+    pub description: Option<String>,
+    pub permissions: Option<BTreeSet<Permission>>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBucketTokenRequest {
+    pub bucket: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+pub struct BucketTokenListQuery {
+    pub bucket: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MintedBucketToken {
+    #[serde(flatten)]
+    record: BucketAdminToken,
+    /// Shown only once.
+    token: String,
+}
+
+#[derive(Deserialize)]
+pub struct CredentialPermissionsRequest {
+    pub permissions: Option<BTreeSet<Permission>>,
+}
+
+#[derive(Deserialize)]
+pub struct CredentialRateLimitRequest {
+    pub rate_limit: Option<CredentialRateLimit>,
 }
 
 #[derive(Deserialize)]
@@ -34,15 +132,196 @@ pub struct SetAnonymousRequest {
     pub enabled: bool,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct DefaultTagsRequest {
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StorageRoutesRequest {
+    pub routes: Vec<StorageRoute>,
+}
+
+#[derive(Deserialize)]
+pub struct PresignedPostRequest {
+    pub key: String,
+    pub access_key_id: String,
+    pub expires_in_secs: Option<u64>,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub conditions: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+pub struct ConcatenateRequest {
+    pub key: String,
+    pub sources: Vec<String>,
+    pub content_type: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ConcatenateInfo {
+    key: String,
+    size: u64,
+    etag: String,
+}
+
+#[derive(Serialize)]
+struct PresignedPostInfo {
+    url: String,
+    fields: BTreeMap<String, String>,
+    expiration: String,
+}
+
+#[derive(Deserialize)]
+pub struct CanonicalRequestDebugRequest {
+    pub method: String,
+    pub path: String,
+    /// Raw query string as sent by the client, without the leading `?`.
+    #[serde(default)]
+    pub query: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Credential to sign with; defaults to the one named in the Authorization header.
+    pub access_key_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CanonicalRequestDebugInfo {
+    access_key_id: String,
+    region: String,
+    signed_headers: Vec<String>,
+    canonical_request: String,
+    string_to_sign: String,
+    signature: String,
+    provided_signature: Option<String>,
+    matches: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LogLevelRequest {
+    pub filter: String,
+}
+
+#[derive(Deserialize)]
+pub struct FreezeClockRequest {
+    pub time: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct ClockInfo {
+    now: DateTime<Utc>,
+    frozen: bool,
+}
+
+#[derive(Deserialize)]
+pub struct LifecycleRunQuery {
+    pub bucket: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Deserialize)]
+pub struct UsageQuery {
+    /// `YYYY-MM`; defaults to the current month.
+    pub month: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BucketUsageEntry {
+    bucket: String,
+    #[serde(flatten)]
+    usage: BucketUsage,
+}
+
+#[derive(Serialize)]
+struct UsageResponse {
+    month: String,
+    buckets: Vec<BucketUsageEntry>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfigHistoryQuery {
+    pub kind: Option<String>,
+}
+
+/// Entries returned by `GET /_admin/audit` without `limit`, and the most it
+/// returns at once.
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+const MAX_AUDIT_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+pub struct AuditListQuery {
+    pub actor: Option<String>,
+    /// Only entries whose path starts with this.
+    pub path: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only entries older than this ID, to page through the trail.
+    pub before: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct ScanListQuery {
+    pub status: Option<ScanStatus>,
+}
+
+/// Longest lease a holder may take at once; longer jobs renew it.
+const MAX_LEASE_TTL_SECS: u64 = 24 * 3600;
+
+#[derive(Deserialize)]
+pub struct LeaseRequest {
+    pub holder: String,
+    pub ttl_secs: u64,
+}
+
+#[derive(Deserialize)]
+pub struct LeaseListQuery {
+    #[serde(default)]
+    pub prefix: String,
+}
+
+#[derive(Deserialize)]
+pub struct LeaseReleaseQuery {
+    pub holder: String,
+}
+
+#[derive(Deserialize)]
+pub struct ModerationRequest {
+    pub state: ModerationState,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ModerationListQuery {
+    pub state: Option<ModerationState>,
+}
+
+#[derive(Serialize)]
+struct ModerationInfo {
+    key: String,
+    #[serde(flatten)]
+    moderation: ObjectModeration,
+}
+
+#[derive(Serialize)]
+struct ScanInfo {
+    key: String,
+    #[serde(flatten)]
+    scan: ObjectScan,
+}
+
 // --- Bucket admin endpoints ---
 
 pub async fn admin_create_bucket(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
 ) -> Response<Body> {
-    match state.metadata.create_bucket(&name) {
+    match state.metadata.create_bucket_at(&name, state.clock.now()) {
         Ok(_) => {
-            if let Err(e) = state.filestore.create_bucket_dir(&name).await {
+            if let Err(e) = state.storage.create_bucket_dir(&name).await {
                 return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
             }
             StatusCode::CREATED.into_response()
@@ -61,6 +340,8 @@ pub async fn admin_list_buckets(State(state): State<Arc<AppState>>) -> Response<
                     creation_date: b.creation_date.to_rfc3339(),
                     anonymous_read: b.anonymous_read,
                     anonymous_list_public: b.anonymous_list_public,
+                    deletion_protected: b.deletion_protected,
+                    frozen: b.frozen.is_some(),
                 })
                 .collect();
             Json(infos).into_response()
@@ -73,9 +354,12 @@ pub async fn admin_delete_bucket(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
 ) -> Response<Body> {
-    match state.metadata.delete_bucket(&name) {
+    match state
+        .metadata
+        .delete_bucket_with_grace(&name, state.config.bucket_delete_grace_secs, state.clock.now())
+    {
         Ok(()) => {
-            if let Err(e) = state.filestore.delete_bucket_dir(&name).await {
+            if let Err(e) = state.storage.delete_bucket_dir(&name).await {
                 return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
             }
             StatusCode::NO_CONTENT.into_response()
@@ -95,47 +379,103 @@ pub async fn admin_set_anonymous(
     }
 }
 
-// --- Credential admin endpoints ---
+pub async fn admin_list_deleted_buckets(State(state): State<Arc<AppState>>) -> Response<Body> {
+    match state.metadata.list_deleted_buckets() {
+        Ok(deleted) => {
+            let infos: Vec<DeletedBucketInfo> = deleted
+                .into_iter()
+                .map(|d| DeletedBucketInfo {
+                    name: d.meta.name,
+                    creation_date: d.meta.creation_date.to_rfc3339(),
+                    deleted_at: d.deleted_at.to_rfc3339(),
+                    purge_after: d.purge_after.to_rfc3339(),
+                })
+                .collect();
+            Json(infos).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
 
-pub async fn admin_create_credential(
+pub async fn admin_restore_deleted_bucket(
     State(state): State<Arc<AppState>>,
-    Json(body): Json<CreateCredentialRequest>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.restore_deleted_bucket(&name) {
+        Ok(_) => {
+            if let Err(e) = state.storage.create_bucket_dir(&name).await {
+                return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+            }
+            StatusCode::OK.into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_purge_deleted_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.purge_deleted_bucket(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Credential admin endpoints ---
+
+/// Create a credential with a fresh key, and return it with its secret.
+fn create_credential(
+    state: &AppState,
+    description: Option<String>,
+    permissions: Option<BTreeSet<Permission>>,
+    bucket: Option<&str>,
 ) -> Response<Body> {
+    if let Some(bucket) = bucket
+        && let Err(e) = state.metadata.get_bucket(bucket)
+    {
+        return e.into_response();
+    }
     let access_key_id = simples3_core::auth::credentials::generate_access_key_id();
     let secret_access_key = simples3_core::auth::credentials::generate_secret_access_key();
-    let description = body.description.unwrap_or_default();
+    let description = description.unwrap_or_default();
 
-    match state
+    let created = state
         .metadata
         .create_credential(&access_key_id, &secret_access_key, &description)
-    {
+        .and_then(|record| match permissions {
+            Some(permissions) => state
+                .metadata
+                .set_credential_permissions(&record.access_key_id, Some(permissions)),
+            None => Ok(record),
+        })
+        .and_then(|record| match bucket {
+            Some(bucket) => state.metadata.set_credential_bucket(&record.access_key_id, Some(bucket)),
+            None => Ok(record),
+        });
+    match created {
         Ok(record) => {
-            let info = CredentialInfo {
-                access_key_id: record.access_key_id,
-                secret_access_key: record.secret_access_key,
-                description: record.description,
-                created: record.created.to_rfc3339(),
-                active: record.active,
-            };
-            (StatusCode::CREATED, Json(info)).into_response()
+            let secret = record.secret_access_key.clone();
+            (StatusCode::CREATED, Json(CredentialInfo::new(record, secret))).into_response()
         }
         Err(e) => e.into_response(),
     }
 }
 
+pub async fn admin_create_credential(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateCredentialRequest>,
+) -> Response<Body> {
+    create_credential(&state, body.description, body.permissions, body.bucket.as_deref())
+}
+
 pub async fn admin_list_credentials(State(state): State<Arc<AppState>>) -> Response<Body> {
     match state.metadata.list_credentials() {
         Ok(creds) => {
             let infos: Vec<CredentialInfo> = creds
                 .into_iter()
-                .map(|c| CredentialInfo {
-                    access_key_id: c.access_key_id,
-                    // Don't expose secrets in list
-                    secret_access_key: "********".into(),
-                    description: c.description,
-                    created: c.created.to_rfc3339(),
-                    active: c.active,
-                })
+                // Don't expose secrets in list
+                .map(|c| CredentialInfo::new(c, "********".into()))
                 .collect();
             Json(infos).into_response()
         }
@@ -143,6 +483,56 @@ pub async fn admin_list_credentials(State(state): State<Arc<AppState>>) -> Respo
     }
 }
 
+/// Export every credential with its secret, for importing into another
+/// instance.
+pub async fn admin_export_credentials(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<ExportCredentialsRequest>,
+) -> Response<Body> {
+    let credentials = match state.metadata.list_credentials() {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    // Key derivation is deliberately slow
+    let export = tokio::task::spawn_blocking(move || {
+        credential_export::export(credentials, body.passphrase.as_deref())
+    })
+    .await;
+    match export {
+        Ok(Ok(export)) => {
+            tracing::info!(encrypted = export.encrypted.is_some(), "Credentials exported via admin API");
+            Json(export).into_response()
+        }
+        Ok(Err(e)) => e.into_response(),
+        Err(e) => simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+    }
+}
+
+/// Import the credentials of an export, keeping their access key IDs.
+pub async fn admin_import_credentials(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<ImportCredentialsRequest>,
+) -> Response<Body> {
+    let credentials = tokio::task::spawn_blocking(move || body.export.open(body.passphrase.as_deref())).await;
+    let credentials = match credentials {
+        Ok(Ok(c)) => c,
+        Ok(Err(e)) => return e.into_response(),
+        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+    };
+    match credential_export::import(&state.metadata, &credentials, body.replace) {
+        Ok(report) => {
+            tracing::info!(
+                created = report.created.len(),
+                updated = report.updated.len(),
+                conflicts = report.conflicts.len(),
+                "Credentials imported via admin API"
+            );
+            Json(report).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
 pub async fn admin_revoke_credential(
     State(state): State<Arc<AppState>>,
     Path(access_key_id): Path<String>,
@@ -153,6 +543,34 @@ pub async fn admin_revoke_credential(
     }
 }
 
+pub async fn admin_set_credential_permissions(
+    State(state): State<Arc<AppState>>,
+    Path(access_key_id): Path<String>,
+    Json(body): Json<CredentialPermissionsRequest>,
+) -> Response<Body> {
+    match state
+        .metadata
+        .set_credential_permissions(&access_key_id, body.permissions)
+    {
+        Ok(record) => Json(CredentialInfo::new(record, "********".into())).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_set_credential_rate_limit(
+    State(state): State<Arc<AppState>>,
+    Path(access_key_id): Path<String>,
+    Json(body): Json<CredentialRateLimitRequest>,
+) -> Response<Body> {
+    match state
+        .metadata
+        .set_credential_rate_limit(&access_key_id, body.rate_limit)
+    {
+        Ok(record) => Json(CredentialInfo::new(record, "********".into())).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
 pub async fn admin_set_anonymous_list_public(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
@@ -163,3 +581,1356 @@ pub async fn admin_set_anonymous_list_public(
         Err(e) => e.into_response(),
     }
 }
+
+pub async fn admin_set_deletion_protection(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<SetAnonymousRequest>,
+) -> Response<Body> {
+    match state.metadata.set_bucket_deletion_protected(&name, body.enabled) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Bucket freeze ---
+
+#[derive(Deserialize)]
+pub struct FreezeRequest {
+    #[serde(default)]
+    pub compact: bool,
+}
+
+#[derive(Serialize)]
+struct FreezeInfo {
+    bucket: String,
+    frozen_at: String,
+    compacted: bool,
+    objects: Vec<ArchiveEntry>,
+}
+
+#[derive(Serialize)]
+struct ThawInfo {
+    bucket: String,
+    restored: usize,
+}
+
+fn freeze_info(state: &AppState, name: &str) -> Result<FreezeInfo, simples3_core::S3Error> {
+    let Some(freeze) = state.metadata.get_bucket(name)?.frozen else {
+        return Err(simples3_core::S3Error::InvalidArgument("Bucket is not frozen".into()));
+    };
+    Ok(FreezeInfo {
+        bucket: name.to_string(),
+        frozen_at: freeze.frozen_at.to_rfc3339(),
+        compacted: freeze.compacted,
+        objects: state.metadata.list_archive_entries(name)?,
+    })
+}
+
+/// Freeze a bucket: reject writes, record a manifest of its objects and,
+/// with `compact`, pack their data into archive segments.
+pub async fn admin_freeze_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<FreezeRequest>,
+) -> Response<Body> {
+    if let Err(e) = state.storage.freeze(&name, body.compact).await {
+        return e.into_response();
+    }
+    tracing::info!(bucket = %name, compact = body.compact, "Bucket frozen");
+    match freeze_info(&state, &name) {
+        Ok(info) => Json(info).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_get_bucket_freeze(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match freeze_info(&state, &name) {
+        Ok(info) => Json(info).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_thaw_bucket(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.storage.thaw(&name).await {
+        Ok(restored) => {
+            tracing::info!(bucket = %name, restored = restored, "Bucket thawed");
+            Json(ThawInfo { bucket: name, restored }).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Content scan records of a bucket's objects, optionally of one status.
+pub async fn admin_list_bucket_scans(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<ScanListQuery>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.list_object_scans(Some(&name), query.status) {
+        Ok(scans) => {
+            let scans: Vec<ScanInfo> = scans.into_iter().map(|(_, key, scan)| ScanInfo { key, scan }).collect();
+            Json(scans).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Object moderation admin endpoints ---
+
+/// Objects of a bucket that are not active, optionally of one state.
+pub async fn admin_list_moderation(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<ModerationListQuery>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.list_object_moderation(&name, query.state) {
+        Ok(records) => {
+            let records: Vec<ModerationInfo> = records
+                .into_iter()
+                .map(|(key, moderation)| ModerationInfo { key, moderation })
+                .collect();
+            Json(records).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_get_moderation(
+    State(state): State<Arc<AppState>>,
+    Path((name, key)): Path<(String, String)>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_object_meta(&name, &key) {
+        return e.into_response();
+    }
+    match state.metadata.get_object_moderation(&name, &key) {
+        Ok(Some(moderation)) => Json(moderation).into_response(),
+        Ok(None) => Json(serde_json::json!({ "state": ModerationState::Active })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_moderation(
+    State(state): State<Arc<AppState>>,
+    Path((name, key)): Path<(String, String)>,
+    Json(body): Json<ModerationRequest>,
+) -> Response<Body> {
+    let moderation = ObjectModeration {
+        state: body.state,
+        reason: body.reason,
+        updated: state.clock.now(),
+    };
+    let was_quarantined = matches!(
+        state.metadata.get_object_moderation(&name, &key),
+        Ok(Some(previous)) if previous.state == ModerationState::Quarantined
+    );
+    match state.metadata.set_object_moderation(&name, &key, &moderation) {
+        Ok(()) => {
+            tracing::warn!(
+                bucket = %name,
+                key = %key,
+                state = moderation.state.as_str(),
+                "Object moderation state changed via admin API"
+            );
+            // Replication skipped the object while it was quarantined
+            if was_quarantined && moderation.state != ModerationState::Quarantined {
+                crate::replication::object_written(&state, &name, &key);
+            }
+            Json(moderation).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Object lease admin endpoints ---
+
+/// Active leases of a bucket, optionally on keys with a prefix.
+pub async fn admin_list_leases(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<LeaseListQuery>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.list_leases(&name, &query.prefix, state.clock.now()) {
+        Ok(leases) => Json(leases).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_get_lease(
+    State(state): State<Arc<AppState>>,
+    Path((name, key)): Path<(String, String)>,
+) -> Response<Body> {
+    match state.metadata.get_lease(&name, &key, state.clock.now()) {
+        Ok(lease) => Json(lease).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Acquire or renew the lease on a key; `409 LeaseHeld` while another
+/// holder has it.
+pub async fn admin_acquire_lease(
+    State(state): State<Arc<AppState>>,
+    Path((name, key)): Path<(String, String)>,
+    Json(body): Json<LeaseRequest>,
+) -> Response<Body> {
+    if body.holder.is_empty() || body.holder.len() > 256 {
+        return simples3_core::S3Error::InvalidArgument("holder must be 1 to 256 characters".into()).into_response();
+    }
+    if body.ttl_secs == 0 || body.ttl_secs > MAX_LEASE_TTL_SECS {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "ttl_secs must be between 1 and {}",
+            MAX_LEASE_TTL_SECS
+        ))
+        .into_response();
+    }
+    let ttl = chrono::Duration::seconds(body.ttl_secs as i64);
+    match state.metadata.acquire_lease(&name, &key, &body.holder, ttl, state.clock.now()) {
+        Ok(lease) => Json(lease).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_release_lease(
+    State(state): State<Arc<AppState>>,
+    Path((name, key)): Path<(String, String)>,
+    Query(query): Query<LeaseReleaseQuery>,
+) -> Response<Body> {
+    match state.metadata.release_lease(&name, &key, &query.holder, state.clock.now()) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Configuration history admin endpoints ---
+
+pub async fn admin_list_config_history(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<ConfigHistoryQuery>,
+) -> Response<Body> {
+    let kind = match query.kind.as_deref() {
+        Some(k) => match ConfigKind::parse(k) {
+            Some(kind) => Some(kind),
+            None => {
+                return simples3_core::S3Error::InvalidArgument(format!(
+                    "Unknown configuration kind '{}'",
+                    k
+                ))
+                .into_response();
+            }
+        },
+        None => None,
+    };
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.list_config_history(&name, kind) {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_rollback_config_change(
+    State(state): State<Arc<AppState>>,
+    Path((name, version)): Path<(String, u64)>,
+) -> Response<Body> {
+    match state.metadata.rollback_config_change(&name, version, Some("admin")) {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Security webhook admin endpoints ---
+
+pub async fn admin_get_security_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_security_webhook(&name) {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_security_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<SecurityWebhookConfig>,
+) -> Response<Body> {
+    if !body.url.starts_with("http://") && !body.url.starts_with("https://") {
+        return simples3_core::S3Error::InvalidArgument("Webhook url must be http:// or https://".into())
+            .into_response();
+    }
+    match state.metadata.put_security_webhook(&name, &body) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_security_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_security_webhook(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Anonymous caching header admin endpoints ---
+
+/// `Expires` should not be more than one year ahead (RFC 9111).
+const MAX_EXPIRES_AFTER_SECS: u64 = 365 * 24 * 3600;
+
+pub async fn admin_get_cache_headers(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_cache_headers(&name) {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_cache_headers(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<CacheHeadersConfig>,
+) -> Response<Body> {
+    if body.cache_control.is_none() && body.expires_after_secs.is_none() {
+        return simples3_core::S3Error::InvalidArgument(
+            "At least one of cache_control or expires_after_secs is required".into(),
+        )
+        .into_response();
+    }
+    if body.expires_after_secs.is_some_and(|secs| secs > MAX_EXPIRES_AFTER_SECS) {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "expires_after_secs must be at most {}",
+            MAX_EXPIRES_AFTER_SECS
+        ))
+        .into_response();
+    }
+    if let Some(ref value) = body.cache_control
+        && (value.is_empty() || http::HeaderValue::from_str(value).is_err())
+    {
+        return simples3_core::S3Error::InvalidArgument("cache_control is not a valid header value".into())
+            .into_response();
+    }
+    match state.metadata.put_cache_headers(&name, &body) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_cache_headers(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_cache_headers(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Usage alert admin endpoints ---
+
+pub async fn admin_get_usage_alert_config(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_usage_alert_config(&name) {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_usage_alert_config(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<UsageAlertConfig>,
+) -> Response<Body> {
+    let thresholds = [body.max_bytes, body.max_objects, body.max_requests_per_sec];
+    if thresholds.iter().all(Option::is_none) {
+        return simples3_core::S3Error::InvalidArgument(
+            "At least one of max_bytes, max_objects or max_requests_per_sec is required".into(),
+        )
+        .into_response();
+    }
+    if thresholds.contains(&Some(0)) {
+        return simples3_core::S3Error::InvalidArgument("Thresholds must be greater than 0".into()).into_response();
+    }
+    match state.metadata.put_usage_alert_config(&name, &body) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_usage_alert_config(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_usage_alert_config(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UsageAlertListQuery {
+    bucket: Option<String>,
+}
+
+/// Raised usage alerts, optionally of one bucket.
+pub async fn admin_list_usage_alerts(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageAlertListQuery>,
+) -> Response<Body> {
+    match state.metadata.list_usage_alerts() {
+        Ok(mut alerts) => {
+            if let Some(bucket) = query.bucket {
+                alerts.retain(|alert| alert.bucket == bucket);
+            }
+            Json(alerts).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Image rendition admin endpoints ---
+
+pub async fn admin_get_renditions(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_rendition_config(&name) {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_renditions(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<RenditionConfig>,
+) -> Response<Body> {
+    if body.max_width == 0 || body.max_height == 0 {
+        return simples3_core::S3Error::InvalidArgument("max_width and max_height must be positive".into())
+            .into_response();
+    }
+    match state.metadata.put_rendition_config(&name, &body) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_renditions(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_rendition_config(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Request logging admin endpoints ---
+
+pub async fn admin_get_request_log(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_request_log(&name) {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_request_log(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<RequestLogConfig>,
+) -> Response<Body> {
+    if !(body.sample_rate > 0.0 && body.sample_rate <= 1.0) {
+        return simples3_core::S3Error::InvalidArgument("sample_rate must be greater than 0 and at most 1".into())
+            .into_response();
+    }
+    match state.metadata.put_request_log(&name, &body) {
+        Ok(()) => {
+            tracing::info!(bucket = %name, sample_rate = body.sample_rate, "Request logging enabled via admin API");
+            StatusCode::OK.into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_request_log(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_request_log(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Replication admin endpoints ---
+
+/// Shown instead of the target secret key when reading a configuration back.
+const MASKED_SECRET: &str = "********";
+
+pub async fn admin_get_replication(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_replication_configuration(&name) {
+        Ok(Some(mut config)) => {
+            config.secret_access_key = MASKED_SECRET.into();
+            Json(config).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_replication(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<ReplicationConfiguration>,
+) -> Response<Body> {
+    let endpoint_valid = reqwest::Url::parse(&body.endpoint)
+        .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.host_str().is_some());
+    if !endpoint_valid {
+        return simples3_core::S3Error::InvalidArgument("endpoint must be an http(s) URL".into()).into_response();
+    }
+    if body.target_bucket.is_empty() || body.target_bucket.contains('/') {
+        return simples3_core::S3Error::InvalidArgument("target_bucket must be a bucket name".into()).into_response();
+    }
+    if body.access_key_id.is_empty() || body.secret_access_key.is_empty() || body.region.is_empty() {
+        return simples3_core::S3Error::InvalidArgument(
+            "access_key_id, secret_access_key and region must not be empty".into(),
+        )
+        .into_response();
+    }
+    if body.tags.keys().any(|k| k.is_empty()) {
+        return simples3_core::S3Error::InvalidArgument("tag keys must not be empty".into()).into_response();
+    }
+    match state.metadata.put_replication_configuration(&name, &body) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_replication(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_replication_configuration(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Default tags admin endpoints ---
+
+/// Maximum tags per object, as enforced by S3.
+const MAX_OBJECT_TAGS: usize = 10;
+
+pub async fn admin_get_default_tags(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_bucket_default_tags(&name) {
+        Ok(tags) => Json(DefaultTagsRequest { tags }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_default_tags(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<DefaultTagsRequest>,
+) -> Response<Body> {
+    if body.tags.len() > MAX_OBJECT_TAGS {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "At most {} default tags are allowed",
+            MAX_OBJECT_TAGS
+        ))
+        .into_response();
+    }
+    if body.tags.keys().any(|k| k.is_empty()) {
+        return simples3_core::S3Error::InvalidArgument("Tag keys must not be empty".into()).into_response();
+    }
+    match state.metadata.put_bucket_default_tags(&name, &body.tags) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_default_tags(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_bucket_default_tags(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Storage route admin endpoints ---
+
+pub async fn admin_get_storage_routes(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.get_storage_routes(&name) {
+        Ok(routes) => Json(StorageRoutesRequest { routes }).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_storage_routes(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<StorageRoutesRequest>,
+) -> Response<Body> {
+    if let Some(route) = body.routes.iter().find(|r| state.storage.backend(&r.backend).is_none()) {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "Unknown storage backend '{}'; configured backends: {}",
+            route.backend,
+            state.storage.backend_names().join(", ")
+        ))
+        .into_response();
+    }
+    match state.metadata.put_storage_routes(&name, &body.routes) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_storage_routes(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_storage_routes(&name) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Presigned POST admin endpoint ---
+
+/// Longest validity accepted for a presigned POST policy, matching presigned URLs.
+const MAX_PRESIGNED_POST_EXPIRY_SECS: u64 = 7 * 24 * 3600;
+
+pub async fn admin_presigned_post(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<PresignedPostRequest>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    let expires_in_secs = body.expires_in_secs.unwrap_or(3600);
+    if expires_in_secs == 0 || expires_in_secs > MAX_PRESIGNED_POST_EXPIRY_SECS {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "expires_in_secs must be between 1 and {}",
+            MAX_PRESIGNED_POST_EXPIRY_SECS
+        ))
+        .into_response();
+    }
+    let credential = match state.metadata.get_credential(&body.access_key_id) {
+        Ok(c) if c.active => c,
+        Ok(_) => {
+            return simples3_core::S3Error::InvalidArgument("Credential is revoked".into()).into_response();
+        }
+        Err(e) => return e.into_response(),
+    };
+
+    let post = presign_post(
+        &PresignedPostParams {
+            bucket: &name,
+            key: &body.key,
+            region: &state.config.region,
+            access_key_id: &credential.access_key_id,
+            secret_access_key: &credential.secret_access_key,
+            expires_in_secs,
+            fields: &body.fields,
+            conditions: &body.conditions,
+        },
+        chrono::Utc::now(),
+    );
+    Json(PresignedPostInfo {
+        url: format!("{}://{}/{}", state.config.url_scheme(), state.config.hostname, name),
+        fields: post.fields,
+        expiration: post.expiration.to_rfc3339(),
+    })
+    .into_response()
+}
+
+// --- Object concatenation admin endpoint ---
+
+/// Most source objects in one concatenation, matching the multipart part limit.
+const MAX_CONCATENATE_SOURCES: usize = 10_000;
+
+/// Concatenate existing objects of the bucket into a new object without the
+/// data leaving the server. The result gets a multipart-style ETag, and the
+/// sources are left in place.
+pub async fn admin_concatenate_objects(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<ConcatenateRequest>,
+) -> Response<Body> {
+    match state.metadata.get_bucket(&name) {
+        Ok(meta) if meta.frozen.is_some() => return simples3_core::S3Error::BucketFrozen.into_response(),
+        Ok(_) => {}
+        Err(e) => return e.into_response(),
+    }
+    if body.key.is_empty() {
+        return simples3_core::S3Error::InvalidArgument("key must not be empty".into()).into_response();
+    }
+    if body.sources.is_empty() || body.sources.len() > MAX_CONCATENATE_SOURCES {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "sources must list between 1 and {} objects",
+            MAX_CONCATENATE_SOURCES
+        ))
+        .into_response();
+    }
+    if let Err(e) = state.metadata.ensure_object_unlocked(&name, &body.key, false) {
+        return e.into_response();
+    }
+
+    let mut paths = Vec::with_capacity(body.sources.len());
+    let mut first_content_type = None;
+    for source in &body.sources {
+        let meta = match state.metadata.get_object_meta(&name, source) {
+            Ok(m) => m,
+            Err(e) => return e.into_response(),
+        };
+        if let Err(e) = super::object::ensure_not_quarantined(&state, &meta) {
+            return e.into_response();
+        }
+        match state.storage.for_object(&meta).and_then(|s| s.open_object_file(&name, source)) {
+            Ok(path) => paths.push(path),
+            Err(e) => return e.into_response(),
+        }
+        first_content_type.get_or_insert(meta.content_type);
+    }
+
+    let store = match state.storage.for_key(&name, &body.key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+    let (size, etag) = match store.concatenate_objects(&name, &body.key, &paths).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+
+    let meta = ObjectMeta {
+        bucket: name.clone(),
+        key: body.key.clone(),
+        size,
+        etag: etag.clone(),
+        content_type: body.content_type.or(first_content_type).unwrap_or_default(),
+        last_modified: Utc::now(),
+        public: false,
+        checksum: None,
+        legal_hold: false,
+        storage_class: None,
+    };
+    if let Err(e) = state.storage.delete_transitioned_copy(&name, &body.key).await {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.put_object_meta(&meta) {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.set_uploaded_object_tagging(&name, &body.key, &HashMap::new()) {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.set_uploaded_object_retention(&name, &body.key, None) {
+        return e.into_response();
+    }
+    let object = crate::notifications::EventObject { key: &body.key, size: Some(size), etag: Some(&etag) };
+    let source = crate::notifications::EventSource::default();
+    crate::notifications::notify(&state, &name, "ObjectCreated:Copy", object, &source);
+    crate::replication::object_written(&state, &name, &body.key);
+
+    Json(ConcatenateInfo { key: body.key, size, etag }).into_response()
+}
+
+// --- Signing debug endpoints ---
+
+/// Recompute the SigV4 canonical request and string-to-sign for a captured
+/// request. Signed headers, date and region come from its Authorization header
+/// when present; otherwise every supplied header is signed and the scope uses
+/// `x-amz-date` and the server region.
+pub async fn admin_debug_canonical_request(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CanonicalRequestDebugRequest>,
+) -> Response<Body> {
+    let headers: BTreeMap<String, String> = body
+        .headers
+        .iter()
+        .map(|(k, v)| (k.to_lowercase(), v.clone()))
+        .collect();
+    let auth = match headers.get("authorization") {
+        Some(h) => match sigv4::parse_auth_header(h) {
+            Ok(a) => Some(a),
+            Err(_) => {
+                return simples3_core::S3Error::InvalidArgument(
+                    "authorization header is not a SigV4 header".into(),
+                )
+                .into_response();
+            }
+        },
+        None => None,
+    };
+    let amz_date = headers.get("x-amz-date").cloned().unwrap_or_default();
+    let (signed_headers, date, region) = match &auth {
+        Some(a) => (a.signed_headers.clone(), a.date.clone(), a.region.clone()),
+        None => (
+            headers.keys().filter(|k| *k != "authorization").cloned().collect(),
+            amz_date.get(..8).unwrap_or("").to_string(),
+            state.config.region.clone(),
+        ),
+    };
+    let Some(access_key_id) = body
+        .access_key_id
+        .clone()
+        .or_else(|| auth.as_ref().map(|a| a.access_key_id.clone()))
+    else {
+        return simples3_core::S3Error::InvalidArgument(
+            "access_key_id is required without an authorization header".into(),
+        )
+        .into_response();
+    };
+    let credential = match state.metadata.get_credential(&access_key_id) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let payload_hash = headers
+        .get("x-amz-content-sha256")
+        .map(|s| s.as_str())
+        .unwrap_or("UNSIGNED-PAYLOAD");
+    let canonical_request = sigv4::canonical_request(
+        &body.method.to_uppercase(),
+        &body.path,
+        &sigv4::canonical_query_string(body.query.trim_start_matches('?')),
+        &headers,
+        &signed_headers,
+        payload_hash,
+    );
+    let string_to_sign = sigv4::string_to_sign(&amz_date, &date, &region, &canonical_request);
+    let key = sigv4::signing_key(&credential.secret_access_key, &date, &region);
+    let signature = hex::encode(sigv4::hmac_sha256(&key, string_to_sign.as_bytes()));
+    let provided_signature = auth.map(|a| a.signature);
+    let matches = provided_signature.as_ref().map(|p| *p == signature);
+
+    Json(CanonicalRequestDebugInfo {
+        access_key_id,
+        region,
+        signed_headers,
+        canonical_request,
+        string_to_sign,
+        signature,
+        provided_signature,
+        matches,
+    })
+    .into_response()
+}
+
+// --- Policy simulation ---
+
+/// Evaluate a hypothetical request against the bucket policy the same way the
+/// S3 auth middleware does, without sending the request. Bucket and object
+/// ACLs are not considered.
+pub async fn admin_simulate(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<SimulateRequest>,
+) -> Response<Body> {
+    match simulate::simulate(&state.metadata, state.config.anonymous_global, state.clock.now(), &body) {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Audit trail ---
+
+/// Recorded admin API mutations, newest first.
+pub async fn admin_list_audit(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditListQuery>,
+) -> Response<Body> {
+    let query = AuditQuery {
+        actor: query.actor,
+        path_prefix: query.path,
+        since: query.since,
+        until: query.until,
+        before: query.before,
+        limit: query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT).min(MAX_AUDIT_LIMIT),
+    };
+    match state.metadata.list_audit_entries(&query) {
+        Ok(entries) => Json(entries).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Metadata store diagnostics ---
+
+/// Key counts and sizes of the metadata store's trees. Walks every entry, so
+/// it runs off the async workers.
+pub async fn admin_metadata_report(State(state): State<Arc<AppState>>) -> Response<Body> {
+    let metadata = state.metadata.clone();
+    match tokio::task::spawn_blocking(move || metadata.metadata_report()).await {
+        Ok(Ok(report)) => Json(report).into_response(),
+        Ok(Err(e)) => e.into_response(),
+        Err(e) => simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+    }
+}
+
+// --- Lifecycle admin endpoint ---
+
+/// Run the lifecycle scanner now, on every bucket or only `bucket`. With
+/// `dry_run` nothing is deleted, moved or aborted.
+pub async fn admin_run_lifecycle(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LifecycleRunQuery>,
+) -> Response<Body> {
+    match crate::lifecycle::run_scan(&state, query.bucket.as_deref(), query.dry_run).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Sync job admin endpoints ---
+
+/// Start syncing one bucket or prefix into another in the background.
+pub async fn admin_start_sync_job(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<crate::sync::SyncRequest>,
+) -> Response<Body> {
+    match crate::sync::start(&state, body) {
+        Ok(job) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Usage report admin endpoints ---
+
+/// Month of a usage query, checked to be `YYYY-MM`.
+fn usage_month(query: UsageQuery) -> Result<String, simples3_core::S3Error> {
+    match query.month {
+        Some(month) if crate::usage::valid_month(&month) => Ok(month),
+        Some(_) => Err(simples3_core::S3Error::InvalidArgument("month must be YYYY-MM".into())),
+        None => Ok(crate::usage::month_of(Utc::now())),
+    }
+}
+
+/// Per-bucket usage of a month, including the requests counted since the
+/// last flush.
+pub async fn admin_get_usage(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageQuery>,
+) -> Response<Body> {
+    let month = match usage_month(query) {
+        Ok(m) => m,
+        Err(e) => return e.into_response(),
+    };
+    let usage = crate::usage::flush(&state, Utc::now()).and_then(|()| state.metadata.list_bucket_usage(&month));
+    match usage {
+        Ok(usage) => {
+            let buckets = usage.into_iter().map(|(bucket, usage)| BucketUsageEntry { bucket, usage }).collect();
+            Json(UsageResponse { month, buckets }).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Write the usage report of a month now, e.g. to refresh the current
+/// month's figures or rewrite an earlier report.
+pub async fn admin_write_usage_report(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageQuery>,
+) -> Response<Body> {
+    let month = match usage_month(query) {
+        Ok(m) => m,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = crate::usage::flush(&state, Utc::now()) {
+        return e.into_response();
+    }
+    match crate::usage::write_report(&state, &month).await {
+        Ok(key) => Json(serde_json::json!({ "month": month, "key": key })).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_get_usage_report_config(State(state): State<Arc<AppState>>) -> Response<Body> {
+    match state.metadata.get_usage_report_config() {
+        Ok(Some(config)) => Json(config).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_put_usage_report_config(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<UsageReportConfig>,
+) -> Response<Body> {
+    match state.metadata.put_usage_report_config(Some(&body)) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_delete_usage_report_config(State(state): State<Arc<AppState>>) -> Response<Body> {
+    match state.metadata.put_usage_report_config(None) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Event notification queue and per-target delivery counts.
+pub async fn admin_notification_status(State(state): State<Arc<AppState>>) -> Response<Body> {
+    Json(state.notifications.status()).into_response()
+}
+
+pub async fn admin_list_sync_jobs(State(state): State<Arc<AppState>>) -> Response<Body> {
+    Json(state.sync_jobs.list()).into_response()
+}
+
+pub async fn admin_get_sync_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response<Body> {
+    match state.sync_jobs.get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Stop a running sync job after the object in progress.
+pub async fn admin_cancel_sync_job(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response<Body> {
+    if state.sync_jobs.cancel(&id) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+// --- Bucket administration endpoints ---
+//
+// Policy, CORS and lifecycle use the S3 API's formats. These routes, and the
+// other per-bucket settings, are what bucket administration tokens may use.
+
+pub async fn admin_get_bucket_policy(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response<Body> {
+    super::policy::get_bucket_policy(state, &name).await
+}
+
+pub async fn admin_put_bucket_policy(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    request: Request<Body>,
+) -> Response<Body> {
+    super::policy::put_bucket_policy(state, &name, Some("admin"), request).await
+}
+
+pub async fn admin_delete_bucket_policy(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response<Body> {
+    super::policy::delete_bucket_policy(state, &name, Some("admin")).await
+}
+
+pub async fn admin_get_bucket_cors(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response<Body> {
+    super::cors::get_bucket_cors(state, &name).await
+}
+
+pub async fn admin_put_bucket_cors(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    request: Request<Body>,
+) -> Response<Body> {
+    super::cors::put_bucket_cors(state, &name, Some("admin"), request).await
+}
+
+pub async fn admin_delete_bucket_cors(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response<Body> {
+    super::cors::delete_bucket_cors(state, &name, Some("admin")).await
+}
+
+pub async fn admin_get_bucket_lifecycle(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Response<Body> {
+    super::lifecycle::get_lifecycle_configuration(state, &name).await
+}
+
+pub async fn admin_put_bucket_lifecycle(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    request: Request<Body>,
+) -> Response<Body> {
+    super::lifecycle::put_lifecycle_configuration(state, &name, Some("admin"), request).await
+}
+
+pub async fn admin_delete_bucket_lifecycle(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    super::lifecycle::delete_lifecycle_configuration(state, &name, Some("admin")).await
+}
+
+/// Credentials bound to the bucket, secrets masked.
+pub async fn admin_list_bucket_credentials(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(&name) {
+        return e.into_response();
+    }
+    match state.metadata.list_credentials() {
+        Ok(creds) => {
+            let infos: Vec<CredentialInfo> = creds
+                .into_iter()
+                .filter(|c| c.bucket.as_deref() == Some(name.as_str()))
+                .map(|c| CredentialInfo::new(c, "********".into()))
+                .collect();
+            Json(infos).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Create a credential bound to the bucket.
+pub async fn admin_create_bucket_credential(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(body): Json<CreateBucketCredentialRequest>,
+) -> Response<Body> {
+    create_credential(&state, body.description, body.permissions, Some(&name))
+}
+
+/// Revoke a credential bound to the bucket; other credentials are not found.
+pub async fn admin_revoke_bucket_credential(
+    State(state): State<Arc<AppState>>,
+    Path((name, access_key_id)): Path<(String, String)>,
+) -> Response<Body> {
+    match state.metadata.get_credential(&access_key_id) {
+        Ok(record) if record.bucket.as_deref() == Some(name.as_str()) => {
+            match state.metadata.revoke_credential(&access_key_id) {
+                Ok(()) => StatusCode::NO_CONTENT.into_response(),
+                Err(e) => e.into_response(),
+            }
+        }
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Mint a token that can only manage one bucket. The token is returned once.
+pub async fn admin_create_bucket_token(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<CreateBucketTokenRequest>,
+) -> Response<Body> {
+    match state.metadata.create_bucket_admin_token(&body.bucket, &body.description) {
+        Ok((record, token)) => {
+            tracing::info!(id = %record.id, bucket = %record.bucket, "Bucket administration token created");
+            (StatusCode::CREATED, Json(MintedBucketToken { record, token })).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_list_bucket_tokens(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<BucketTokenListQuery>,
+) -> Response<Body> {
+    match state.metadata.list_bucket_admin_tokens(query.bucket.as_deref()) {
+        Ok(tokens) => Json(tokens).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn admin_revoke_bucket_token(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response<Body> {
+    match state.metadata.delete_bucket_admin_token(&id) {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+// --- Active connection admin endpoints ---
+
+#[derive(Serialize)]
+struct ConnectionsResponse {
+    /// Client connections open on the S3 listener, idle ones included.
+    open_connections: usize,
+    requests: Vec<crate::connections::ActiveRequestInfo>,
+}
+
+/// Requests in flight on the S3 listener, longest-running first.
+pub async fn admin_list_connections(State(state): State<Arc<AppState>>) -> Response<Body> {
+    Json(ConnectionsResponse {
+        open_connections: state.load.connections(),
+        requests: state.connections.list(),
+    })
+    .into_response()
+}
+
+/// Cancel a request in flight by its request ID and close its connection.
+pub async fn admin_cancel_connection(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response<Body> {
+    if state.connections.cancel(&id) {
+        tracing::warn!(request_id = %id, "Request cancellation requested via admin API");
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
+
+// --- Server info admin endpoint ---
+
+#[derive(Serialize)]
+struct ServerInfo {
+    version: &'static str,
+    uptime_secs: u64,
+    metadata_health: crate::metadata_health::MetadataHealthStatus,
+    config: simples3_core::Config,
+}
+
+/// Server version, uptime and the effective configuration, secrets masked.
+pub async fn admin_server_info(State(state): State<Arc<AppState>>) -> Response<Body> {
+    Json(ServerInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        uptime_secs: state.start_time.elapsed().as_secs(),
+        metadata_health: state.metadata_health.status(),
+        config: state.runtime_config.tunables().applied_to(&state.config),
+    })
+    .into_response()
+}
+
+// --- Clock control admin endpoints ---
+
+fn clock_info(state: &AppState) -> Response<Body> {
+    Json(ClockInfo {
+        now: state.clock.now(),
+        frozen: state.clock.frozen().is_some(),
+    })
+    .into_response()
+}
+
+/// Clock changes are `404 Not Found` unless clock control is enabled.
+fn ensure_clock_control(state: &AppState) -> Result<(), StatusCode> {
+    if state.config.clock_control {
+        Ok(())
+    } else {
+        tracing::debug!("Clock change refused: clock control is disabled; set SIMPLES3_CLOCK_CONTROL=true");
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+pub async fn admin_get_clock(State(state): State<Arc<AppState>>) -> Response<Body> {
+    clock_info(&state)
+}
+
+/// Freeze the server clock at `time`, for testing expiration logic.
+pub async fn admin_freeze_clock(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<FreezeClockRequest>,
+) -> Response<Body> {
+    if let Err(status) = ensure_clock_control(&state) {
+        return status.into_response();
+    }
+    state.clock.freeze(body.time);
+    tracing::warn!(time = %body.time, "Server clock frozen via admin API");
+    clock_info(&state)
+}
+
+pub async fn admin_resume_clock(State(state): State<Arc<AppState>>) -> Response<Body> {
+    if let Err(status) = ensure_clock_control(&state) {
+        return status.into_response();
+    }
+    state.clock.resume();
+    tracing::info!("Server clock resumed via admin API");
+    clock_info(&state)
+}
+
+// --- Log level admin endpoints ---
+
+pub async fn admin_get_log_level(State(state): State<Arc<AppState>>) -> Response<Body> {
+    Json(LogLevelRequest {
+        filter: state.log_level.current(),
+    })
+    .into_response()
+}
+
+pub async fn admin_set_log_level(
+    State(state): State<Arc<AppState>>,
+    Json(body): Json<LogLevelRequest>,
+) -> Response<Body> {
+    match state.log_level.set(&body.filter) {
+        Ok(()) => {
+            tracing::info!(filter = %body.filter, "Log level changed via admin API");
+            Json(body).into_response()
+        }
+        Err(e) => simples3_core::S3Error::InvalidArgument(format!("Invalid log filter: {}", e)).into_response(),
+    }
+}
+
+// --- Configuration reload admin endpoint ---
+
+pub async fn admin_reload_config(State(state): State<Arc<AppState>>) -> Response<Body> {
+    let reloaded = crate::reload::reload(&state);
+    if state.config.warm_up {
+        crate::warm_up::run(state.metadata.clone()).await;
+    }
+    match reloaded {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => simples3_core::S3Error::InvalidArgument(format!("Configuration reload failed: {}", e)).into_response(),
+    }
+}
@@ -0,0 +1,74 @@
+use axum::body::Body;
+use axum::extract::Request;
+use http_body_util::{BodyExt, LengthLimitError};
+use simples3_core::S3Error;
+
+use crate::load::LoadTracker;
+
+/// A request body read into memory. Its size counts towards the server's
+/// buffered bytes until it is dropped.
+pub struct BufferedBody {
+    data: Vec<u8>,
+    load: LoadTracker,
+}
+
+impl BufferedBody {
+    fn extend(&mut self, chunk: &[u8]) {
+        self.data.extend_from_slice(chunk);
+        self.load.buffered(chunk.len() as u64);
+    }
+}
+
+impl std::ops::Deref for BufferedBody {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Drop for BufferedBody {
+    fn drop(&mut self) {
+        self.load.released(self.data.len() as u64);
+    }
+}
+
+/// Buffer a request body of at most `limit` bytes.
+///
+/// Bodies over the limit are rejected with `EntityTooLarge`, up front when the
+/// client sends a `Content-Length` and otherwise as soon as the limit is hit.
+pub async fn read_body(request: Request<Body>, limit: usize) -> Result<BufferedBody, S3Error> {
+    let declared = request
+        .headers()
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|len| len > limit as u64) {
+        return Err(S3Error::EntityTooLarge);
+    }
+
+    let mut buffered = BufferedBody {
+        data: Vec::new(),
+        load: request.extensions().get::<LoadTracker>().cloned().unwrap_or_default(),
+    };
+    let mut body = request.into_body();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|e| {
+            let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&e);
+            while let Some(err) = source {
+                if err.is::<LengthLimitError>() {
+                    return S3Error::EntityTooLarge;
+                }
+                source = err.source();
+            }
+            S3Error::InternalError(e.to_string())
+        })?;
+        if let Ok(chunk) = frame.into_data() {
+            if buffered.len() + chunk.len() > limit {
+                return Err(S3Error::EntityTooLarge);
+            }
+            buffered.extend(&chunk);
+        }
+    }
+    Ok(buffered)
+}
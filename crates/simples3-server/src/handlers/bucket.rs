@@ -1,14 +1,51 @@
 use crate::AppState;
 use axum::body::Body;
 use axum::response::{IntoResponse, Response};
-use http::StatusCode;
+use http::{HeaderMap, StatusCode};
+use simples3_core::clock::Clock;
+use simples3_core::s3::types::{ListBucketsRequest, ObjectLockConfiguration};
 use simples3_core::s3::xml;
+use std::collections::HashMap;
 use std::sync::Arc;
 
-pub async fn create_bucket(state: Arc<AppState>, bucket: &str) -> Response<Body> {
-    match state.metadata.create_bucket(bucket) {
+/// Parse the `x-amz-acl` canned ACL header. Only `private` and `public-read`
+/// are supported; `Some(true)` means public-read.
+pub fn canned_acl(headers: &HeaderMap) -> Result<Option<bool>, simples3_core::S3Error> {
+    match headers.get("x-amz-acl").and_then(|v| v.to_str().ok()) {
+        Some("public-read") => Ok(Some(true)),
+        Some("private") => Ok(Some(false)),
+        None => Ok(None),
+        Some(other) => Err(simples3_core::S3Error::InvalidArgument(format!(
+            "Unsupported x-amz-acl value: {}",
+            other
+        ))),
+    }
+}
+
+pub async fn create_bucket(state: Arc<AppState>, bucket: &str, headers: &HeaderMap) -> Response<Body> {
+    let public_read = match canned_acl(headers) {
+        Ok(acl) => acl.unwrap_or(false),
+        Err(e) => return e.into_response(),
+    };
+    let object_lock = headers
+        .get("x-amz-bucket-object-lock-enabled")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+    match state.metadata.create_bucket_at(bucket, state.clock.now()) {
         Ok(_) => {
-            if let Err(e) = state.filestore.create_bucket_dir(bucket).await {
+            if object_lock
+                && let Err(e) = state
+                    .metadata
+                    .put_object_lock_configuration(bucket, &ObjectLockConfiguration::default())
+            {
+                return e.into_response();
+            }
+            if public_read
+                && let Err(e) = state.metadata.set_bucket_anonymous_read(bucket, true)
+            {
+                return e.into_response();
+            }
+            if let Err(e) = state.storage.create_bucket_dir(bucket).await {
                 return e.into_response();
             }
             (
@@ -22,10 +59,33 @@ pub async fn create_bucket(state: Arc<AppState>, bucket: &str) -> Response<Body>
     }
 }
 
-pub async fn list_buckets(state: Arc<AppState>) -> Response<Body> {
-    match state.metadata.list_buckets() {
-        Ok(buckets) => {
-            let body = xml::list_buckets_xml("simples3", &buckets);
+pub async fn list_buckets(
+    state: Arc<AppState>,
+    principal: Option<&str>,
+    query: &HashMap<String, String>,
+) -> Response<Body> {
+    let max_buckets = match query.get("max-buckets") {
+        Some(v) => match v.parse() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                return simples3_core::S3Error::InvalidArgument(
+                    "max-buckets must be an integer".into(),
+                )
+                .into_response();
+            }
+        },
+        None => None,
+    };
+    let req = ListBucketsRequest {
+        prefix: query.get("prefix").cloned().unwrap_or_default(),
+        max_buckets,
+        continuation_token: query.get("continuation-token").cloned(),
+    };
+
+    match state.metadata.list_buckets_page(&req) {
+        Ok(resp) => {
+            let (owner_id, display_name) = caller_owner(&state, principal);
+            let body = xml::list_buckets_xml(&owner_id, &display_name, &resp);
             (
                 StatusCode::OK,
                 [("content-type", "application/xml")],
@@ -37,10 +97,27 @@ pub async fn list_buckets(state: Arc<AppState>) -> Response<Body> {
     }
 }
 
+/// Owner reported by ListBuckets and `fetch-owner` listings: the calling
+/// credential, named by its description when it has one. Anonymous callers
+/// see `anonymous`. Buckets and objects have no owner of their own.
+pub fn caller_owner(state: &AppState, principal: Option<&str>) -> (String, String) {
+    let Some(access_key_id) = principal else {
+        return ("anonymous".to_string(), "anonymous".to_string());
+    };
+    let display_name = match state.metadata.get_credential(access_key_id) {
+        Ok(record) if !record.description.is_empty() => record.description,
+        _ => access_key_id.to_string(),
+    };
+    (access_key_id.to_string(), display_name)
+}
+
 pub async fn delete_bucket(state: Arc<AppState>, bucket: &str) -> Response<Body> {
-    match state.metadata.delete_bucket(bucket) {
+    match state
+        .metadata
+        .delete_bucket_with_grace(bucket, state.config.bucket_delete_grace_secs, state.clock.now())
+    {
         Ok(()) => {
-            if let Err(e) = state.filestore.delete_bucket_dir(bucket).await {
+            if let Err(e) = state.storage.delete_bucket_dir(bucket).await {
                 return e.into_response();
             }
             StatusCode::NO_CONTENT.into_response()
@@ -55,3 +132,26 @@ pub async fn head_bucket(state: Arc<AppState>, bucket: &str) -> Response<Body> {
         Err(e) => e.into_response(),
     }
 }
+
+pub async fn put_bucket_acl(state: Arc<AppState>, bucket: &str, headers: &HeaderMap) -> Response<Body> {
+    let public_read = match canned_acl(headers) {
+        Ok(acl) => acl.unwrap_or(false),
+        Err(e) => return e.into_response(),
+    };
+    match state.metadata.set_bucket_anonymous_read(bucket, public_read) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_bucket_acl(state: Arc<AppState>, bucket: &str) -> Response<Body> {
+    match state.metadata.get_bucket(bucket) {
+        Ok(meta) => (
+            StatusCode::OK,
+            [("content-type", "application/xml")],
+            xml::get_bucket_acl_xml(meta.anonymous_read),
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
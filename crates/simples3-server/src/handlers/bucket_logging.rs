@@ -0,0 +1,46 @@
+use crate::AppState;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use http::StatusCode;
+use simples3_core::s3::xml;
+use std::sync::Arc;
+
+pub async fn put_bucket_logging(
+    state: Arc<AppState>,
+    bucket: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let config = match xml::parse_bucket_logging_xml(&body_bytes) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.metadata.put_bucket_logging(bucket, config.as_ref()) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_bucket_logging(state: Arc<AppState>, bucket: &str) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(bucket) {
+        return e.into_response();
+    }
+    match state.metadata.get_bucket_logging(bucket) {
+        Ok(config) => {
+            let body = xml::bucket_logging_xml(config.as_ref());
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
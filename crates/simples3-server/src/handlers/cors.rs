@@ -9,11 +9,12 @@ use std::sync::Arc;
 pub async fn put_bucket_cors(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
     request: Request<Body>,
 ) -> Response<Body> {
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_xml_body_size).await {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
         Ok(b) => b,
-        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+        Err(e) => return e.into_response(),
     };
 
     let config = match xml::parse_cors_configuration_xml(&body_bytes) {
@@ -21,7 +22,7 @@ pub async fn put_bucket_cors(
         Err(e) => return e.into_response(),
     };
 
-    match state.metadata.put_cors_configuration(bucket, &config) {
+    match state.metadata.put_cors_configuration_by(bucket, &config, principal) {
         Ok(()) => StatusCode::OK.into_response(),
         Err(e) => e.into_response(),
     }
@@ -48,8 +49,9 @@ pub async fn get_bucket_cors(
 pub async fn delete_bucket_cors(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
 ) -> Response<Body> {
-    match state.metadata.delete_cors_configuration(bucket) {
+    match state.metadata.delete_cors_configuration_by(bucket, principal) {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
@@ -2,6 +2,7 @@ use crate::AppState;
 use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use axum::Json;
 use std::sync::Arc;
 
 pub async fn health() -> impl IntoResponse {
@@ -9,51 +10,41 @@ pub async fn health() -> impl IntoResponse {
 }
 
 pub async fn ready(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    // Check sled is accessible
-    if let Err(e) = state.metadata.list_buckets() {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            format!("metadata store unavailable: {}", e),
-        );
+    let report = crate::readiness::check(&state);
+    match report.first_error() {
+        Some(error) => (StatusCode::SERVICE_UNAVAILABLE, error.to_string()),
+        None => (StatusCode::OK, "ready".to_string()),
     }
+}
 
-    // Check filesystem is accessible by writing and removing a probe file
-    let probe_path = state.config.data_dir.join(".ready-probe");
-    if let Err(e) = std::fs::write(&probe_path, b"probe") {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            format!("data directory not writable: {}", e),
-        );
-    }
-    let _ = std::fs::remove_file(&probe_path);
+/// Liveness: the process is up and answering.
+pub async fn healthz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "status": "alive",
+        "uptime_secs": state.start_time.elapsed().as_secs(),
+    }))
+}
 
-    (StatusCode::OK, "ready".to_string())
+/// Readiness with the outcome of every dependency check.
+pub async fn readyz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let report = crate::readiness::check(&state);
+    let status = if report.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(report))
 }
 
 pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    // Collect storage gauges on-demand
     if let Ok(buckets) = state.metadata.list_buckets() {
         metrics::gauge!("simples3_bucket_count").set(buckets.len() as f64);
+    }
 
-        let mut total_objects: u64 = 0;
-        let mut total_bytes: u64 = 0;
-        for bucket in &buckets {
-            if let Ok(resp) = state.metadata.list_objects_v2(
-                &simples3_core::s3::types::ListObjectsV2Request {
-                    bucket: bucket.name.clone(),
-                    prefix: String::new(),
-                    delimiter: String::new(),
-                    max_keys: u32::MAX,
-                    continuation_token: None,
-                    start_after: None,
-                },
-            ) {
-                total_objects += resp.contents.len() as u64;
-                total_bytes += resp.contents.iter().map(|o| o.size).sum::<u64>();
-            }
-        }
+    // Object counts and sizes are kept up to date by object writes, so
+    // scrapes don't walk the buckets
+    if let Ok(stats) = state.metadata.list_bucket_stats() {
+        let total_objects: u64 = stats.iter().map(|(_, s)| s.objects).sum();
+        let total_bytes: u64 = stats.iter().map(|(_, s)| s.bytes).sum();
         metrics::gauge!("simples3_total_object_count").set(total_objects as f64);
         metrics::gauge!("simples3_total_storage_bytes").set(total_bytes as f64);
+        crate::metrics::set_bucket_storage(&stats);
     }
 
     if let Ok(creds) = state.metadata.list_credentials() {
@@ -77,6 +68,26 @@ pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoRes
         metrics::gauge!(crate::metrics::LIFECYCLE_RULES_TOTAL).set(total_rules as f64);
     }
 
+    if let Ok(stats) = state.metadata.replication_queue_stats() {
+        metrics::gauge!(crate::metrics::REPLICATION_QUEUE_DEPTH).set(stats.pending as f64);
+        metrics::gauge!(crate::metrics::REPLICATION_FAILED_OBJECTS).set(stats.failed as f64);
+        let lag = stats
+            .oldest_queued_at
+            .map(|t| chrono::Utc::now().signed_duration_since(t).num_seconds().max(0) as f64)
+            .unwrap_or(0.0);
+        metrics::gauge!(crate::metrics::REPLICATION_LAG_SECONDS).set(lag);
+    }
+
+    let notifications = state.notifications.status();
+    metrics::gauge!(crate::metrics::NOTIFICATION_QUEUE_DEPTH).set(notifications.queue_depth as f64);
+    for target in notifications.targets {
+        let age = target
+            .oldest_pending
+            .map(|t| chrono::Utc::now().signed_duration_since(t).num_seconds().max(0) as f64)
+            .unwrap_or(0.0);
+        metrics::gauge!(crate::metrics::NOTIFICATION_OLDEST_PENDING_SECONDS, "target" => target.endpoint).set(age);
+    }
+
     let uptime = state.start_time.elapsed().as_secs_f64();
     metrics::gauge!("simples3_uptime_seconds").set(uptime);
 
@@ -4,16 +4,18 @@ use axum::extract::Request;
 use axum::response::{IntoResponse, Response};
 use http::StatusCode;
 use simples3_core::s3::xml;
+use simples3_core::storage::router::DEFAULT_BACKEND;
 use std::sync::Arc;
 
 pub async fn put_lifecycle_configuration(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
     request: Request<Body>,
 ) -> Response<Body> {
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_xml_body_size).await {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
         Ok(b) => b,
-        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+        Err(e) => return e.into_response(),
     };
 
     let config = match xml::parse_lifecycle_configuration_xml(&body_bytes) {
@@ -21,7 +23,28 @@ pub async fn put_lifecycle_configuration(
         Err(e) => return e.into_response(),
     };
 
-    match state.metadata.put_lifecycle_configuration(bucket, &config) {
+    // A storage class is the name of a storage backend other than the default one
+    let unknown_class = config
+        .rules
+        .iter()
+        .flat_map(|r| &r.transitions)
+        .find(|t| t.storage_class == DEFAULT_BACKEND || state.storage.backend(&t.storage_class).is_none());
+    if let Some(transition) = unknown_class {
+        return simples3_core::S3Error::InvalidArgument(format!(
+            "Unknown storage class '{}'; configured backends: {}",
+            transition.storage_class,
+            state
+                .storage
+                .backend_names()
+                .into_iter()
+                .filter(|n| n != DEFAULT_BACKEND)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .into_response();
+    }
+
+    match state.metadata.put_lifecycle_configuration_by(bucket, &config, principal) {
         Ok(()) => StatusCode::OK.into_response(),
         Err(e) => e.into_response(),
     }
@@ -48,8 +71,9 @@ pub async fn get_lifecycle_configuration(
 pub async fn delete_lifecycle_configuration(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
 ) -> Response<Body> {
-    match state.metadata.delete_lifecycle_configuration(bucket) {
+    match state.metadata.delete_lifecycle_configuration_by(bucket, principal) {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
@@ -1,8 +1,13 @@
 pub mod admin;
+pub mod body;
 pub mod bucket;
+pub mod bucket_logging;
 pub mod cors;
 pub mod health;
 pub mod lifecycle;
 pub mod multipart;
+pub mod notification;
 pub mod object;
+pub mod object_lock;
 pub mod policy;
+pub mod website;
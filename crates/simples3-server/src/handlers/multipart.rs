@@ -1,11 +1,16 @@
 use crate::AppState;
+use crate::notifications::{self, EventObject, EventSource};
+use crate::replication;
 use axum::body::Body;
 use axum::extract::Request;
 use axum::response::{IntoResponse, Response};
 use chrono::Utc;
 use http::StatusCode;
-use simples3_core::s3::types::{CompletedPart, MultipartUpload, ObjectMeta, PartInfo};
+use simples3_core::clock::Clock;
+use simples3_core::s3::checksum::{self, ChecksumAlgorithm, RequestedChecksum};
+use simples3_core::s3::types::{CompletedPart, MultipartUpload, ObjectMeta, PartInfo, PartLayout};
 use simples3_core::s3::xml;
+use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -13,18 +18,31 @@ pub async fn create_multipart_upload(
     state: Arc<AppState>,
     bucket: &str,
     key: &str,
+    headers: &http::HeaderMap,
 ) -> Response<Body> {
     if let Err(e) = state.metadata.get_bucket(bucket) {
         return e.into_response();
     }
 
+    let checksum_algorithm = match headers.get("x-amz-checksum-algorithm") {
+        Some(v) => match v.to_str().ok().and_then(ChecksumAlgorithm::parse) {
+            Some(a) => Some(a),
+            None => {
+                return simples3_core::S3Error::InvalidArgument("Unsupported checksum algorithm".into())
+                    .into_response();
+            }
+        },
+        None => None,
+    };
+
     let upload_id = Uuid::new_v4().to_string();
     let upload = MultipartUpload {
         upload_id: upload_id.clone(),
         bucket: bucket.to_string(),
         key: key.to_string(),
-        created: Utc::now(),
+        created: state.clock.now(),
         parts: vec![],
+        checksum_algorithm,
     };
 
     if let Err(e) = state.metadata.create_multipart_upload(&upload) {
@@ -32,12 +50,13 @@ pub async fn create_multipart_upload(
     }
 
     let body = xml::initiate_multipart_upload_xml(bucket, key, &upload_id);
-    (
-        StatusCode::OK,
-        [("content-type", "application/xml")],
-        body,
-    )
-        .into_response()
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/xml");
+    if let Some(algorithm) = checksum_algorithm {
+        builder = builder.header("x-amz-checksum-algorithm", algorithm.name());
+    }
+    builder.body(Body::from(body)).unwrap()
 }
 
 pub async fn upload_part(
@@ -49,20 +68,31 @@ pub async fn upload_part(
     request: Request<Body>,
 ) -> Response<Body> {
     // Verify upload exists
-    let _ = match state.metadata.get_multipart_upload(upload_id) {
+    let upload = match state.metadata.get_multipart_upload(upload_id) {
         Ok(u) => u,
         Err(e) => return e.into_response(),
     };
 
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_object_size).await {
+    let requested_checksum = match RequestedChecksum::from_headers(request.headers(), upload.checksum_algorithm) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    let body_bytes = match super::body::read_body(request, state.config.max_object_size).await {
         Ok(b) => b,
-        Err(e) => {
-            return simples3_core::S3Error::InternalError(e.to_string()).into_response();
-        }
+        Err(e) => return e.into_response(),
+    };
+
+    let checksum = match requested_checksum.map(|c| c.verify(&body_bytes)).transpose() {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
     };
 
-    let (size, etag) = match state
-        .filestore
+    let store = match state.storage.for_key(&upload.bucket, &upload.key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+    let (size, etag) = match store
         .write_part(upload_id, part_number, &body_bytes)
         .await
     {
@@ -75,13 +105,20 @@ pub async fn upload_part(
         etag: etag.clone(),
         size,
         last_modified: Utc::now(),
+        checksum: checksum.clone(),
     };
 
     if let Err(e) = state.metadata.add_part_to_upload(upload_id, part_info) {
         return e.into_response();
     }
 
-    (StatusCode::OK, [("etag", format!("\"{}\"", etag).as_str())], "").into_response()
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("etag", format!("\"{}\"", etag));
+    if let Some(checksum) = checksum {
+        builder = builder.header(checksum.algorithm.header(), checksum.value);
+    }
+    builder.body(Body::empty()).unwrap()
 }
 
 pub async fn complete_multipart_upload(
@@ -91,17 +128,22 @@ pub async fn complete_multipart_upload(
     upload_id: &str,
     request: Request<Body>,
 ) -> Response<Body> {
-    let _upload = match state.metadata.get_multipart_upload(upload_id) {
+    let upload = match state.metadata.get_multipart_upload(upload_id) {
         Ok(u) => u,
         Err(e) => return e.into_response(),
     };
 
+    // Refuse to overwrite an object under retention
+    let bypass = super::object_lock::bypass_governance(request.headers());
+    if let Err(e) = state.metadata.ensure_object_unlocked(bucket, key, bypass) {
+        return e.into_response();
+    }
+
     // Parse the XML body to get part list
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_xml_body_size).await {
+    let source = EventSource::from_request(&request);
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
         Ok(b) => b,
-        Err(e) => {
-            return simples3_core::S3Error::InternalError(e.to_string()).into_response();
-        }
+        Err(e) => return e.into_response(),
     };
 
     let parts = match parse_complete_multipart_xml(&body_bytes) {
@@ -116,10 +158,29 @@ pub async fn complete_multipart_upload(
         }
     }
 
+    if let Err(e) = validate_part_etags(&upload, &parts) {
+        return e.into_response();
+    }
+
     let part_numbers: Vec<u32> = parts.iter().map(|p| p.part_number).collect();
+    let part_checksums: Vec<_> = parts
+        .iter()
+        .map(|p| {
+            upload
+                .parts
+                .iter()
+                .find(|stored| stored.part_number == p.part_number)
+                .and_then(|stored| stored.checksum.as_ref())
+        })
+        .collect();
+    let checksum = checksum::composite(&part_checksums);
 
-    let (size, etag) = match state
-        .filestore
+    // Parts were written to the backend that routes this key
+    let store = match state.storage.for_key(bucket, key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+    let (size, etag) = match store
         .assemble_parts(bucket, key, upload_id, &part_numbers)
         .await
     {
@@ -137,18 +198,45 @@ pub async fn complete_multipart_upload(
         content_type,
         last_modified: Utc::now(),
         public: false,
+        checksum: checksum.clone(),
+        legal_hold: false,
+        storage_class: None,
     };
 
+    if let Err(e) = state.storage.delete_transitioned_copy(bucket, key).await {
+        return e.into_response();
+    }
     if let Err(e) = state.metadata.put_object_meta(&meta) {
         return e.into_response();
     }
+    if let Err(e) = state.metadata.set_uploaded_object_tagging(bucket, key, &HashMap::new()) {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.set_uploaded_object_retention(bucket, key, None) {
+        return e.into_response();
+    }
+    let layout = PartLayout {
+        etag: etag.clone(),
+        sizes: parts
+            .iter()
+            .filter_map(|p| upload.parts.iter().find(|stored| stored.part_number == p.part_number))
+            .map(|stored| stored.size)
+            .collect(),
+    };
+    if let Err(e) = state.metadata.put_part_layout(bucket, key, &layout) {
+        return e.into_response();
+    }
 
     // Cleanup
-    let _ = state.filestore.cleanup_multipart(upload_id).await;
+    let _ = store.cleanup_multipart(upload_id).await;
     let _ = state.metadata.delete_multipart_upload(upload_id);
 
-    let location = format!("http://{}/{}/{}", state.config.hostname, bucket, key);
-    let body = xml::complete_multipart_upload_xml(bucket, key, &etag, &location);
+    let object = EventObject { key, size: Some(size), etag: Some(&etag) };
+    notifications::notify(&state, bucket, "ObjectCreated:CompleteMultipartUpload", object, &source);
+    replication::object_written(&state, bucket, key);
+
+    let location = format!("{}://{}/{}/{}", state.config.url_scheme(), state.config.hostname, bucket, key);
+    let body = xml::complete_multipart_upload_xml(bucket, key, &etag, &location, checksum.as_ref());
     (
         StatusCode::OK,
         [("content-type", "application/xml")],
@@ -161,11 +249,14 @@ pub async fn abort_multipart_upload(
     state: Arc<AppState>,
     upload_id: &str,
 ) -> Response<Body> {
-    if let Err(e) = state.metadata.get_multipart_upload(upload_id) {
-        return e.into_response();
-    }
+    let upload = match state.metadata.get_multipart_upload(upload_id) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
 
-    let _ = state.filestore.cleanup_multipart(upload_id).await;
+    if let Ok(store) = state.storage.for_key(&upload.bucket, &upload.key) {
+        let _ = store.cleanup_multipart(upload_id).await;
+    }
     let _ = state.metadata.delete_multipart_upload(upload_id);
 
     StatusCode::NO_CONTENT.into_response()
@@ -186,6 +277,24 @@ pub async fn list_parts(state: Arc<AppState>, upload_id: &str) -> Response<Body>
         .into_response()
 }
 
+/// Every listed part must have been uploaded, with the ETag the client sent.
+fn validate_part_etags(
+    upload: &MultipartUpload,
+    parts: &[CompletedPart],
+) -> Result<(), simples3_core::S3Error> {
+    for part in parts {
+        let stored = upload
+            .parts
+            .iter()
+            .find(|p| p.part_number == part.part_number)
+            .ok_or(simples3_core::S3Error::InvalidPart)?;
+        if !stored.etag.eq_ignore_ascii_case(&part.etag) {
+            return Err(simples3_core::S3Error::InvalidPart);
+        }
+    }
+    Ok(())
+}
+
 fn parse_complete_multipart_xml(data: &[u8]) -> Result<Vec<CompletedPart>, simples3_core::S3Error> {
     use quick_xml::Reader;
     use quick_xml::events::Event;
@@ -214,18 +323,16 @@ fn parse_complete_multipart_xml(data: &[u8]) -> Result<Vec<CompletedPart>, simpl
                     _ => {}
                 }
             }
-            Ok(Event::Text(ref e)) => {
-                if in_part {
-                    let text = e.unescape().unwrap_or_default().to_string();
-                    match current_element.as_str() {
-                        "PartNumber" => {
-                            current_part_number = text.parse().ok();
-                        }
-                        "ETag" => {
-                            current_etag = Some(text.trim_matches('"').to_string());
-                        }
-                        _ => {}
+            Ok(Event::Text(ref e)) if in_part => {
+                let text = e.unescape().unwrap_or_default().to_string();
+                match current_element.as_str() {
+                    "PartNumber" => {
+                        current_part_number = text.parse().ok();
                     }
+                    "ETag" => {
+                        current_etag = Some(text.trim_matches('"').to_string());
+                    }
+                    _ => {}
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -242,12 +349,7 @@ fn parse_complete_multipart_xml(data: &[u8]) -> Result<Vec<CompletedPart>, simpl
                 current_element.clear();
             }
             Ok(Event::Eof) => break,
-            Err(e) => {
-                return Err(simples3_core::S3Error::InvalidArgument(format!(
-                    "Invalid XML: {}",
-                    e
-                )));
-            }
+            Err(_) => return Err(simples3_core::S3Error::MalformedXML),
             _ => {}
         }
         buf.clear();
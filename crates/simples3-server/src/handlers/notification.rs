@@ -0,0 +1,53 @@
+use crate::AppState;
+use crate::notifications::NotificationTarget;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use http::StatusCode;
+use simples3_core::S3Error;
+use simples3_core::s3::xml;
+use std::sync::Arc;
+
+pub async fn put_bucket_notification(
+    state: Arc<AppState>,
+    bucket: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let config = match xml::parse_notification_configuration_xml(&body_bytes) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+    for rule in &config.rules {
+        if let Err(e) = NotificationTarget::parse(&rule.endpoint) {
+            return S3Error::InvalidArgument(e).into_response();
+        }
+    }
+
+    match state.metadata.put_bucket_notification(bucket, &config) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_bucket_notification(state: Arc<AppState>, bucket: &str) -> Response<Body> {
+    if let Err(e) = state.metadata.get_bucket(bucket) {
+        return e.into_response();
+    }
+    match state.metadata.get_bucket_notification(bucket) {
+        Ok(config) => {
+            let body = xml::notification_configuration_xml(&config);
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
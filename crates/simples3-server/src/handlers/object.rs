@@ -1,4 +1,6 @@
 use crate::AppState;
+use crate::notifications::{self, EventObject, EventSource};
+use crate::replication;
 use axum::body::Body;
 use axum::extract::Request;
 use axum::response::{IntoResponse, Response};
@@ -6,10 +8,14 @@ use chrono::Utc;
 use http::StatusCode;
 use quick_xml::Reader;
 use quick_xml::events::Event;
-use simples3_core::s3::types::{ListObjectsV2Request, ObjectMeta};
+use simples3_core::s3::checksum::{Checksum, RequestedChecksum};
+use simples3_core::s3::conditional::{self, ByteRange, Precondition};
+use simples3_core::s3::types::{ListEntry, ListObjectsV2Request, ModerationState, ObjectMeta};
+use simples3_core::storage::listing::ObjectListing;
 use simples3_core::s3::xml;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tokio_util::io::ReaderStream;
 
 pub async fn put_object(
@@ -31,27 +37,55 @@ pub async fn put_object(
         .to_string();
 
     // Parse x-amz-acl header
-    let public = match request.headers().get("x-amz-acl").and_then(|v| v.to_str().ok()) {
-        Some("public-read") => true,
-        Some("private") | None => false,
-        Some(other) => {
-            return simples3_core::S3Error::InvalidArgument(format!(
-                "Unsupported x-amz-acl value: {}",
-                other
-            ))
-            .into_response();
-        }
+    let public = match super::bucket::canned_acl(request.headers()) {
+        Ok(acl) => acl.unwrap_or(false),
+        Err(e) => return e.into_response(),
     };
 
+    let tags = request
+        .headers()
+        .get("x-amz-tagging")
+        .and_then(|v| v.to_str().ok())
+        .map(parse_tagging_header)
+        .unwrap_or_default();
+
+    let requested_checksum = match RequestedChecksum::from_headers(request.headers(), None) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    // Refuse to overwrite an object under retention
+    let retention = match super::object_lock::requested_retention(&state, bucket, request.headers()) {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    let legal_hold = match super::object_lock::requested_legal_hold(&state, bucket, request.headers()) {
+        Ok(on) => on,
+        Err(e) => return e.into_response(),
+    };
+    let bypass = super::object_lock::bypass_governance(request.headers());
+    if let Err(e) = state.metadata.ensure_object_unlocked(bucket, key, bypass) {
+        return e.into_response();
+    }
+
     // Stream body to disk
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_object_size).await {
+    let source = EventSource::from_request(&request);
+    let body_bytes = match super::body::read_body(request, state.config.max_object_size).await {
         Ok(b) => b,
-        Err(e) => {
-            return simples3_core::S3Error::InternalError(e.to_string()).into_response();
-        }
+        Err(e) => return e.into_response(),
+    };
+
+    // Verify before writing so a corrupt upload never replaces the object
+    let checksum = match requested_checksum.map(|c| c.verify(&body_bytes)).transpose() {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
     };
 
-    let (size, etag) = match state.filestore.write_object(bucket, key, &body_bytes).await {
+    let store = match state.storage.for_key(bucket, key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+    let (size, etag) = match store.write_object(bucket, key, &body_bytes).await {
         Ok(r) => r,
         Err(e) => return e.into_response(),
     };
@@ -64,78 +98,422 @@ pub async fn put_object(
         content_type,
         last_modified: Utc::now(),
         public,
+        checksum: checksum.clone(),
+        legal_hold,
+        storage_class: None,
     };
 
+    if let Err(e) = state.storage.delete_transitioned_copy(bucket, key).await {
+        return e.into_response();
+    }
     if let Err(e) = state.metadata.put_object_meta(&meta) {
         return e.into_response();
     }
+    if let Err(e) = state.metadata.set_uploaded_object_tagging(bucket, key, &tags) {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.set_uploaded_object_retention(bucket, key, retention.as_ref()) {
+        return e.into_response();
+    }
+    let object = EventObject { key, size: Some(size), etag: Some(&etag) };
+    notifications::notify(&state, bucket, "ObjectCreated:Put", object, &source);
+    replication::object_written(&state, bucket, key);
 
-    (StatusCode::OK, [("etag", format!("\"{}\"", etag).as_str())], "").into_response()
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("etag", format!("\"{}\"", etag));
+    if let Some(checksum) = checksum {
+        builder = builder.header(checksum.algorithm.header(), checksum.value);
+    }
+    builder.body(Body::empty()).unwrap()
 }
 
-pub async fn get_object(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
+pub async fn get_object(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    principal: Option<&str>,
+    headers: &http::HeaderMap,
+) -> Response<Body> {
     let meta = match state.metadata.get_object_meta(bucket, key) {
         Ok(m) => m,
         Err(e) => return e.into_response(),
     };
+    if let Err(e) = ensure_not_quarantined(&state, &meta).and_then(|()| ensure_reviewed(&state, bucket, key, principal)) {
+        return e.into_response();
+    }
+    let range = match conditional::evaluate_preconditions(headers, &meta.etag, meta.last_modified) {
+        Precondition::Proceed => conditional::requested_range(headers, meta.size),
+        Precondition::NotModified => return not_modified(&meta),
+        Precondition::Failed => Err(simples3_core::S3Error::PreconditionFailed),
+    };
+    let range = match range {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
 
-    let file_path = match state.filestore.open_object_file(bucket, key) {
-        Ok(p) => p,
+    let (file_path, offset) = match state.storage.locate(&meta) {
+        Ok(l) => l,
         Err(e) => return e.into_response(),
     };
-    let file = match tokio::fs::File::open(&file_path).await {
+    let io = state.storage.default_store().io_pool();
+    let start = offset + range.map_or(0, |r| r.start);
+    let opened = io
+        .run(async move {
+            let mut file = tokio::fs::File::open(&file_path).await?;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            Ok::<_, std::io::Error>(file)
+        })
+        .await;
+    let file = match opened {
         Ok(f) => f,
         Err(_) => return simples3_core::S3Error::NoSuchKey.into_response(),
     };
 
-    let stream = ReaderStream::new(file);
+    let length = range.map_or(meta.size, |r| r.length());
+    let stream = ReaderStream::new(io.reader(file.take(length)));
     let body = Body::from_stream(stream);
 
-    let mut builder = Response::builder()
-        .status(StatusCode::OK)
+    let mut builder = object_response(&meta, range)
         .header("content-type", &meta.content_type)
-        .header("content-length", meta.size.to_string())
         .header("etag", format!("\"{}\"", meta.etag))
         .header("last-modified", meta.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
 
-    if let Ok(tags) = state.metadata.get_object_tagging(bucket, key) {
-        if !tags.is_empty() {
-            builder = builder.header("x-amz-tagging-count", tags.len().to_string());
-        }
+    if let Ok(tags) = state.metadata.get_object_tagging(bucket, key)
+        && !tags.is_empty()
+    {
+        builder = builder.header("x-amz-tagging-count", tags.len().to_string());
+    }
+    if let Some(checksum) = requested_checksum(headers, &meta) {
+        builder = builder.header(checksum.algorithm.header(), &checksum.value);
+    }
+    builder = super::object_lock::retention_headers(&state, &meta, builder);
+    if let Ok(Some(status)) = state.metadata.get_replication_status(bucket, key) {
+        builder = builder.header("x-amz-replication-status", status.as_str());
+    }
+    if let Ok(Some(scan)) = state.metadata.get_object_scan(bucket, key)
+        && scan.etag == meta.etag
+    {
+        builder = builder.header("x-simples3-scan-status", scan.status.as_str());
+    }
+    if let Ok(Some(moderation)) = state.metadata.get_object_moderation(bucket, key) {
+        builder = builder.header("x-simples3-moderation", moderation.state.as_str());
+    }
+    if let Some(ref class) = meta.storage_class {
+        builder = builder.header("x-amz-storage-class", class);
+    }
+    if let Ok(Some(restore)) = state.storage.active_restore(&meta) {
+        builder = builder.header(
+            "x-amz-restore",
+            format!(
+                "ongoing-request=\"false\", expiry-date=\"{}\"",
+                restore.expiry.format("%a, %d %b %Y %H:%M:%S GMT")
+            ),
+        );
+    }
+    if principal.is_none() {
+        builder = anonymous_cache_headers(&state, bucket, builder);
     }
 
     builder.body(body).unwrap()
 }
 
-pub async fn head_object(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
+/// GetObject with `x-simples3-resize`: serve a scaled rendition of an image.
+pub async fn get_object_rendition(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    spec: &str,
+    principal: Option<&str>,
+) -> Response<Body> {
+    if let Err(e) = ensure_reviewed(&state, bucket, key, principal) {
+        return e.into_response();
+    }
+    let rendered = match crate::renditions::render(&state, bucket, key, spec).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    let rendition = rendered.rendition;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", &rendition.content_type)
+        .header("content-length", rendition.data.len())
+        .header(
+            "etag",
+            format!("\"{}-{}x{}\"", rendered.meta.etag, rendered.width, rendered.height),
+        )
+        .header(
+            "last-modified",
+            rendered.meta.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        )
+        .header("x-simples3-rendition", format!("{}x{}", rendered.width, rendered.height))
+        .header("x-simples3-rendition-cache", if rendered.cached { "hit" } else { "miss" })
+        .body(Body::from(rendition.data))
+        .unwrap()
+}
+
+/// Query parameter of GetObject returning the part manifest instead of the
+/// object data.
+pub const PARTS_PARAM: &str = "x-simples3-parts";
+
+#[derive(serde::Serialize)]
+struct PartManifest<'a> {
+    bucket: &'a str,
+    key: &'a str,
+    etag: &'a str,
+    size: u64,
+    parts: Vec<PartRange>,
+}
+
+#[derive(serde::Serialize)]
+struct PartRange {
+    part_number: u32,
+    offset: u64,
+    size: u64,
+    /// `Range` header value fetching exactly this part.
+    range: String,
+}
+
+/// GetObject with `x-simples3-parts`: the byte ranges of the parts the object
+/// was uploaded in, for parallel ranged downloads along part boundaries.
+/// Objects not created by CompleteMultipartUpload are a single part.
+pub async fn get_object_parts(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
     let meta = match state.metadata.get_object_meta(bucket, key) {
         Ok(m) => m,
         Err(e) => return e.into_response(),
     };
+    let sizes = match state.metadata.get_part_layout(bucket, key) {
+        Ok(Some(layout)) if layout.etag == meta.etag && layout.sizes.iter().sum::<u64>() == meta.size => layout.sizes,
+        Ok(_) => vec![meta.size],
+        Err(e) => return e.into_response(),
+    };
+    let mut offset = 0;
+    let mut parts = Vec::with_capacity(sizes.len());
+    for (i, size) in sizes.into_iter().enumerate() {
+        parts.push(PartRange {
+            part_number: i as u32 + 1,
+            offset,
+            size,
+            range: format!("bytes={}-{}", offset, (offset + size).saturating_sub(1)),
+        });
+        offset += size;
+    }
+    let manifest = PartManifest {
+        bucket,
+        key,
+        etag: &meta.etag,
+        size: meta.size,
+        parts,
+    };
+    axum::Json(manifest).into_response()
+}
 
-    let mut builder = Response::builder()
-        .status(StatusCode::OK)
+pub async fn head_object(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    principal: Option<&str>,
+    headers: &http::HeaderMap,
+) -> Response<Body> {
+    let meta = match state.metadata.get_object_meta(bucket, key) {
+        Ok(m) => m,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(e) = ensure_reviewed(&state, bucket, key, principal) {
+        return e.into_response();
+    }
+    let range = match conditional::evaluate_preconditions(headers, &meta.etag, meta.last_modified) {
+        Precondition::Proceed => conditional::requested_range(headers, meta.size),
+        Precondition::NotModified => return not_modified(&meta),
+        Precondition::Failed => Err(simples3_core::S3Error::PreconditionFailed),
+    };
+    let range = match range {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut builder = object_response(&meta, range)
         .header("content-type", &meta.content_type)
-        .header("content-length", meta.size.to_string())
         .header("etag", format!("\"{}\"", meta.etag))
         .header("last-modified", meta.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
 
-    if let Ok(tags) = state.metadata.get_object_tagging(bucket, key) {
-        if !tags.is_empty() {
-            builder = builder.header("x-amz-tagging-count", tags.len().to_string());
-        }
+    if let Ok(tags) = state.metadata.get_object_tagging(bucket, key)
+        && !tags.is_empty()
+    {
+        builder = builder.header("x-amz-tagging-count", tags.len().to_string());
+    }
+    if let Some(checksum) = requested_checksum(headers, &meta) {
+        builder = builder.header(checksum.algorithm.header(), &checksum.value);
+    }
+    builder = super::object_lock::retention_headers(&state, &meta, builder);
+    if let Ok(Some(status)) = state.metadata.get_replication_status(bucket, key) {
+        builder = builder.header("x-amz-replication-status", status.as_str());
+    }
+    if let Ok(Some(scan)) = state.metadata.get_object_scan(bucket, key)
+        && scan.etag == meta.etag
+    {
+        builder = builder.header("x-simples3-scan-status", scan.status.as_str());
+    }
+    if let Ok(Some(moderation)) = state.metadata.get_object_moderation(bucket, key) {
+        builder = builder.header("x-simples3-moderation", moderation.state.as_str());
+    }
+    if let Some(ref class) = meta.storage_class {
+        builder = builder.header("x-amz-storage-class", class);
+    }
+    if let Ok(Some(restore)) = state.storage.active_restore(&meta) {
+        builder = builder.header(
+            "x-amz-restore",
+            format!(
+                "ongoing-request=\"false\", expiry-date=\"{}\"",
+                restore.expiry.format("%a, %d %b %Y %H:%M:%S GMT")
+            ),
+        );
+    }
+    if principal.is_none() {
+        builder = anonymous_cache_headers(&state, bucket, builder);
     }
 
     builder.body(Body::empty()).unwrap()
 }
 
-pub async fn delete_object(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
+/// Copy a transitioned object back to its routed backend for the number of
+/// days in the `RestoreRequest`: `202 Accepted` for a new restore, `200 OK`
+/// when an existing one was extended.
+pub async fn restore_object(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let meta = match state.metadata.get_object_meta(bucket, key) {
+        Ok(m) => m,
+        Err(e) => return e.into_response(),
+    };
+    let body = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+    let days = match xml::parse_restore_request_xml(&body) {
+        Ok(d) => d,
+        Err(e) => return e.into_response(),
+    };
+    match state.storage.restore(&meta, days).await {
+        Ok(true) => StatusCode::OK.into_response(),
+        Ok(false) => {
+            tracing::info!(bucket = %bucket, key = %key, days = days, "Restored object");
+            StatusCode::ACCEPTED.into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Refuse reads of an object that a content scan found infected or that is
+/// quarantined for moderation.
+pub(crate) fn ensure_not_quarantined(state: &AppState, meta: &ObjectMeta) -> Result<(), simples3_core::S3Error> {
+    if let Ok(Some(scan)) = state.metadata.get_object_scan(&meta.bucket, &meta.key)
+        && scan.quarantines(meta)
+    {
+        return Err(simples3_core::S3Error::ObjectQuarantined);
+    }
+    if let Ok(Some(moderation)) = state.metadata.get_object_moderation(&meta.bucket, &meta.key)
+        && moderation.state == ModerationState::Quarantined
+    {
+        return Err(simples3_core::S3Error::ObjectUnderModeration);
+    }
+    Ok(())
+}
+
+/// Refuse anonymous reads of an object pending moderation review.
+fn ensure_reviewed(state: &AppState, bucket: &str, key: &str, principal: Option<&str>) -> Result<(), simples3_core::S3Error> {
+    if principal.is_none()
+        && let Ok(Some(moderation)) = state.metadata.get_object_moderation(bucket, key)
+        && moderation.state == ModerationState::PendingReview
+    {
+        return Err(simples3_core::S3Error::ObjectPendingReview);
+    }
+    Ok(())
+}
+
+/// `304 Not Modified` for a GET or HEAD whose `If-None-Match` or
+/// `If-Modified-Since` condition matched.
+fn not_modified(meta: &ObjectMeta) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header("etag", format!("\"{}\"", meta.etag))
+        .header("last-modified", meta.last_modified.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// Status and length headers for the whole object, or `206` with
+/// `Content-Range` when a range was requested.
+fn object_response(meta: &ObjectMeta, range: Option<ByteRange>) -> http::response::Builder {
+    let builder = Response::builder().header("accept-ranges", "bytes");
+    match range {
+        Some(range) => builder
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header("content-length", range.length().to_string())
+            .header("content-range", range.content_range(meta.size)),
+        None => builder
+            .status(StatusCode::OK)
+            .header("content-length", meta.size.to_string()),
+    }
+}
+
+/// Add the bucket's configured `Cache-Control` / `Expires` defaults to an
+/// anonymous GET or HEAD response.
+fn anonymous_cache_headers(
+    state: &AppState,
+    bucket: &str,
+    mut builder: http::response::Builder,
+) -> http::response::Builder {
+    let Ok(Some(config)) = state.metadata.get_cache_headers(bucket) else {
+        return builder;
+    };
+    if let Some(cache_control) = config.cache_control {
+        builder = builder.header("cache-control", cache_control);
+    }
+    if let Some(secs) = config.expires_after_secs {
+        let expires = Utc::now() + chrono::Duration::seconds(secs as i64);
+        builder = builder.header("expires", expires.format("%a, %d %b %Y %H:%M:%S GMT").to_string());
+    }
+    builder
+}
+
+/// The stored checksum, when the client asked for it with
+/// `x-amz-checksum-mode: ENABLED`.
+fn requested_checksum<'a>(headers: &http::HeaderMap, meta: &'a ObjectMeta) -> Option<&'a Checksum> {
+    let enabled = headers
+        .get("x-amz-checksum-mode")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("ENABLED"));
+    if enabled { meta.checksum.as_ref() } else { None }
+}
+
+pub async fn delete_object(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    headers: &http::HeaderMap,
+    source: &EventSource,
+) -> Response<Body> {
+    let bypass = super::object_lock::bypass_governance(headers);
+    if let Err(e) = state.metadata.ensure_object_unlocked(bucket, key, bypass) {
+        return e.into_response();
+    }
+    let store = match state.storage.for_existing(bucket, key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
     if let Err(e) = state.metadata.delete_object_meta(bucket, key) {
         return e.into_response();
     }
-    if let Err(e) = state.filestore.delete_object(bucket, key).await {
+    if let Err(e) = store.delete_object(bucket, key).await {
         return e.into_response();
     }
+    let object = EventObject { key, size: None, etag: None };
+    notifications::notify(&state, bucket, "ObjectRemoved:Delete", object, source);
+    replication::object_deleted(&state, bucket, key);
     StatusCode::NO_CONTENT.into_response()
 }
 
@@ -143,6 +521,7 @@ pub async fn list_objects_v2(
     state: Arc<AppState>,
     bucket: &str,
     query: &HashMap<String, String>,
+    principal: Option<&str>,
     public_only: bool,
 ) -> Response<Body> {
     // Verify bucket exists
@@ -158,6 +537,19 @@ pub async fn list_objects_v2(
         .unwrap_or(1000);
     let continuation_token = query.get("continuation-token").cloned();
     let start_after = query.get("start-after").cloned();
+    let url_encoded = match query.get("encoding-type").map(String::as_str) {
+        None => false,
+        Some("url") => true,
+        Some(_) => {
+            return simples3_core::S3Error::InvalidArgument("Invalid Encoding Method specified in Request".into())
+                .into_response();
+        }
+    };
+    let owner = (query.get("fetch-owner").map(String::as_str) == Some("true")).then(|| {
+        let (id, display_name) = super::bucket::caller_owner(&state, principal);
+        xml::ListOwner { id, display_name }
+    });
+    let format = xml::ListFormat { url_encoded, owner };
 
     let req = ListObjectsV2Request {
         bucket: bucket.to_string(),
@@ -168,26 +560,130 @@ pub async fn list_objects_v2(
         start_after,
     };
 
-    match state.metadata.list_objects_v2(&req) {
-        Ok(mut resp) => {
-            if public_only {
-                resp.contents.retain(|obj| obj.public);
-                resp.key_count = resp.contents.len() as u32;
+    if let Some(mut resp) = state.metadata.cached_objects_v2(&req) {
+        if public_only {
+            resp.contents.retain(|obj| obj.public);
+            resp.key_count = resp.contents.len() as u32;
+        }
+        let body = xml::list_objects_v2_xml(&resp, &format);
+        return (
+            StatusCode::OK,
+            [("content-type", "application/xml")],
+            body,
+        )
+            .into_response();
+    }
+
+    // Stream the page as it is scanned instead of building it in memory
+    let listing = match state.metadata.stream_objects_v2(&req) {
+        Ok(l) => l,
+        Err(e) => return e.into_response(),
+    };
+    let chunks = ListingXml {
+        listing,
+        format,
+        public_only,
+        key_count: 0,
+        started: false,
+        finished: false,
+    };
+    // The sled scan blocks, so it runs off the async workers and hands the
+    // chunks over as they are produced
+    let (tx, rx) = tokio::sync::mpsc::channel(LIST_CHUNKS_BUFFERED);
+    tokio::task::spawn_blocking(move || {
+        for chunk in chunks {
+            // Stop scanning once the client has gone away
+            if tx.blocking_send(chunk).is_err() {
+                break;
             }
-            let body = xml::list_objects_v2_xml(&resp);
-            (
-                StatusCode::OK,
-                [("content-type", "application/xml")],
-                body,
-            )
-                .into_response()
         }
-        Err(e) => e.into_response(),
+    });
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/xml")
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// Flush streamed listings in chunks of about this many bytes.
+const LIST_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Chunks of a streamed listing scanned ahead of the client.
+const LIST_CHUNKS_BUFFERED: usize = 4;
+
+/// ListObjectsV2 XML, produced chunk by chunk while the listing is scanned.
+struct ListingXml {
+    listing: ObjectListing,
+    format: xml::ListFormat,
+    public_only: bool,
+    key_count: u32,
+    started: bool,
+    finished: bool,
+}
+
+impl Iterator for ListingXml {
+    type Item = Result<String, simples3_core::S3Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut chunk = String::new();
+        if !self.started {
+            self.started = true;
+            let req = self.listing.request();
+            chunk = xml::list_objects_v2_xml_head(&req.bucket, &req.prefix, &req.delimiter, req.max_keys, &self.format);
+        }
+        while chunk.len() < LIST_CHUNK_SIZE {
+            match self.listing.next() {
+                Some(Ok(entry)) => {
+                    if let ListEntry::Object(meta) = &entry {
+                        if self.public_only && !meta.public {
+                            continue;
+                        }
+                        self.key_count += 1;
+                    }
+                    chunk.push_str(&xml::list_entry_xml(&entry, &self.format));
+                }
+                Some(Err(e)) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+                None => {
+                    self.finished = true;
+                    chunk.push_str(&xml::list_objects_v2_xml_tail(
+                        self.key_count,
+                        self.listing.is_truncated(),
+                        self.listing.next_continuation_token(),
+                    ));
+                    break;
+                }
+            }
+        }
+        Some(Ok(chunk))
     }
 }
 
 // --- Tagging handlers ---
 
+/// Parse the URL-encoded `x-amz-tagging` header (`k1=v1&k2=v2`).
+fn parse_tagging_header(value: &str) -> HashMap<String, String> {
+    let decode = |s: &str| {
+        percent_encoding::percent_decode_str(&s.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+    value
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let mut kv = p.splitn(2, '=');
+            (decode(kv.next().unwrap_or("")), decode(kv.next().unwrap_or("")))
+        })
+        .collect()
+}
+
 fn parse_tagging_xml(data: &[u8]) -> Result<HashMap<String, String>, simples3_core::S3Error> {
     let mut reader = Reader::from_reader(data);
     reader.config_mut().trim_text(true);
@@ -206,7 +702,7 @@ fn parse_tagging_xml(data: &[u8]) -> Result<HashMap<String, String>, simples3_co
                 _ => {}
             },
             Ok(Event::Text(e)) => {
-                let text = e.unescape().map_err(|e| simples3_core::S3Error::InvalidArgument(e.to_string()))?.into_owned();
+                let text = e.unescape().map_err(|_| simples3_core::S3Error::MalformedXML)?.into_owned();
                 if in_key {
                     current_key = text;
                 } else if in_value {
@@ -226,7 +722,7 @@ fn parse_tagging_xml(data: &[u8]) -> Result<HashMap<String, String>, simples3_co
                 _ => {}
             },
             Ok(Event::Eof) => break,
-            Err(e) => return Err(simples3_core::S3Error::InvalidArgument(e.to_string())),
+            Err(_) => return Err(simples3_core::S3Error::MalformedXML),
             _ => {}
         }
         buf.clear();
@@ -240,9 +736,9 @@ pub async fn put_object_tagging(
     key: &str,
     request: Request<Body>,
 ) -> Response<Body> {
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_xml_body_size).await {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
         Ok(b) => b,
-        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+        Err(e) => return e.into_response(),
     };
 
     let tags = match parse_tagging_xml(&body_bytes) {
@@ -296,17 +792,9 @@ pub async fn copy_object(
     request: Request<Body>,
 ) -> Response<Body> {
     // Parse x-amz-acl header (if absent, inherit from source)
-    let acl_override = match request.headers().get("x-amz-acl").and_then(|v| v.to_str().ok()) {
-        Some("public-read") => Some(true),
-        Some("private") => Some(false),
-        None => None,
-        Some(other) => {
-            return simples3_core::S3Error::InvalidArgument(format!(
-                "Unsupported x-amz-acl value: {}",
-                other
-            ))
-            .into_response();
-        }
+    let acl_override = match super::bucket::canned_acl(request.headers()) {
+        Ok(acl) => acl,
+        Err(e) => return e.into_response(),
     };
 
     let copy_source = match request.headers().get("x-amz-copy-source") {
@@ -340,19 +828,39 @@ pub async fn copy_object(
         return e.into_response();
     }
 
+    let retention = match super::object_lock::requested_retention(&state, dest_bucket, request.headers()) {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    let legal_hold = match super::object_lock::requested_legal_hold(&state, dest_bucket, request.headers()) {
+        Ok(on) => on,
+        Err(e) => return e.into_response(),
+    };
+    let bypass = super::object_lock::bypass_governance(request.headers());
+    if let Err(e) = state.metadata.ensure_object_unlocked(dest_bucket, dest_key, bypass) {
+        return e.into_response();
+    }
+
     // Get source metadata
     let src_meta = match state.metadata.get_object_meta(src_bucket, src_key) {
         Ok(m) => m,
         Err(e) => return e.into_response(),
     };
+    if let Err(e) = ensure_not_quarantined(&state, &src_meta) {
+        return e.into_response();
+    }
 
     // Read source data and write to destination
-    let data = match state.filestore.read_object(src_bucket, src_key).await {
+    let dest_store = match state.storage.for_key(dest_bucket, dest_key) {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+    let data = match state.storage.read(&src_meta).await {
         Ok(d) => d,
         Err(e) => return e.into_response(),
     };
 
-    let (size, etag) = match state.filestore.write_object(dest_bucket, dest_key, &data).await {
+    let (size, etag) = match dest_store.write_object(dest_bucket, dest_key, &data).await {
         Ok(r) => r,
         Err(e) => return e.into_response(),
     };
@@ -366,18 +874,33 @@ pub async fn copy_object(
         content_type: src_meta.content_type,
         last_modified: now,
         public: acl_override.unwrap_or(src_meta.public),
+        // The copy is a single-part object, so a composite checksum is recomputed whole
+        checksum: src_meta.checksum.map(|c| Checksum {
+            algorithm: c.algorithm,
+            value: c.algorithm.compute(&data),
+        }),
+        legal_hold,
+        storage_class: None,
     };
 
+    if let Err(e) = state.storage.delete_transitioned_copy(dest_bucket, dest_key).await {
+        return e.into_response();
+    }
     if let Err(e) = state.metadata.put_object_meta(&dest_meta) {
         return e.into_response();
     }
 
-    // Copy tags from source to destination
-    if let Ok(tags) = state.metadata.get_object_tagging(src_bucket, src_key) {
-        if !tags.is_empty() {
-            let _ = state.metadata.put_object_tagging(dest_bucket, dest_key, &tags);
-        }
+    // Copy tags from source to destination, over the destination bucket's defaults
+    let tags = state.metadata.get_object_tagging(src_bucket, src_key).unwrap_or_default();
+    if let Err(e) = state.metadata.set_uploaded_object_tagging(dest_bucket, dest_key, &tags) {
+        return e.into_response();
+    }
+    if let Err(e) = state.metadata.set_uploaded_object_retention(dest_bucket, dest_key, retention.as_ref()) {
+        return e.into_response();
     }
+    let object = EventObject { key: dest_key, size: Some(size), etag: Some(&etag) };
+    notifications::notify(&state, dest_bucket, "ObjectCreated:Copy", object, &EventSource::from_request(&request));
+    replication::object_written(&state, dest_bucket, dest_key);
 
     let body = xml::copy_object_result_xml(&etag, &now);
     (
@@ -407,7 +930,7 @@ fn parse_delete_objects_xml(data: &[u8]) -> Result<(Vec<String>, bool), simples3
                 _ => {}
             },
             Ok(Event::Text(e)) => {
-                let text = e.unescape().map_err(|e| simples3_core::S3Error::InvalidArgument(e.to_string()))?.into_owned();
+                let text = e.unescape().map_err(|_| simples3_core::S3Error::MalformedXML)?.into_owned();
                 if in_key {
                     keys.push(text);
                 } else if in_quiet {
@@ -420,7 +943,7 @@ fn parse_delete_objects_xml(data: &[u8]) -> Result<(Vec<String>, bool), simples3
                 _ => {}
             },
             Ok(Event::Eof) => break,
-            Err(e) => return Err(simples3_core::S3Error::InvalidArgument(e.to_string())),
+            Err(_) => return Err(simples3_core::S3Error::MalformedXML),
             _ => {}
         }
         buf.clear();
@@ -428,6 +951,10 @@ fn parse_delete_objects_xml(data: &[u8]) -> Result<(Vec<String>, bool), simples3
     Ok((keys, quiet))
 }
 
+/// Header making DeleteObjects report what it would delete, and which keys
+/// would fail, without deleting anything.
+pub const DRY_RUN_HEADER: &str = "x-simples3-dry-run";
+
 pub async fn delete_objects(
     state: Arc<AppState>,
     bucket: &str,
@@ -438,9 +965,15 @@ pub async fn delete_objects(
         return e.into_response();
     }
 
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_xml_body_size).await {
+    let bypass = super::object_lock::bypass_governance(request.headers());
+    let dry_run = request
+        .headers()
+        .get(DRY_RUN_HEADER)
+        .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"true"));
+    let source = EventSource::from_request(&request);
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
         Ok(b) => b,
-        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+        Err(e) => return e.into_response(),
     };
 
     let (keys, quiet) = match parse_delete_objects_xml(&body_bytes) {
@@ -452,6 +985,21 @@ pub async fn delete_objects(
     let mut errors: Vec<(String, String, String)> = Vec::new();
 
     for key in keys {
+        if let Err(e) = state.metadata.ensure_object_unlocked(bucket, &key, bypass) {
+            errors.push((key.clone(), e.code().to_string(), e.to_string()));
+            continue;
+        }
+        let store = match state.storage.for_existing(bucket, &key) {
+            Ok(s) => s,
+            Err(e) => {
+                errors.push((key.clone(), e.code().to_string(), e.to_string()));
+                continue;
+            }
+        };
+        if dry_run {
+            deleted.push(key);
+            continue;
+        }
         // Delete meta (which also cleans up tags)
         match state.metadata.delete_object_meta(bucket, &key) {
             Ok(()) => {}
@@ -464,20 +1012,29 @@ pub async fn delete_objects(
             }
         }
         // Delete file
-        if let Err(e) = state.filestore.delete_object(bucket, &key).await {
+        if let Err(e) = store.delete_object(bucket, &key).await {
             errors.push((key.clone(), e.code().to_string(), e.to_string()));
             continue;
         }
+        let object = EventObject { key: &key, size: None, etag: None };
+        notifications::notify(&state, bucket, "ObjectRemoved:Delete", object, &source);
+        replication::object_deleted(&state, bucket, &key);
         deleted.push(key);
     }
 
     let body = xml::delete_objects_result_xml(&deleted, &errors, quiet);
-    (
+    let mut response = (
         StatusCode::OK,
         [("content-type", "application/xml")],
         body,
     )
-        .into_response()
+        .into_response();
+    if dry_run {
+        response
+            .headers_mut()
+            .insert(DRY_RUN_HEADER, http::HeaderValue::from_static("true"));
+    }
+    response
 }
 
 // --- ACL handlers ---
@@ -488,17 +1045,24 @@ pub async fn put_object_acl(
     key: &str,
     request: Request<Body>,
 ) -> Response<Body> {
-    let acl = match request.headers().get("x-amz-acl").and_then(|v| v.to_str().ok()) {
-        Some("public-read") => true,
-        Some("private") => false,
-        None => false,
-        Some(other) => {
-            return simples3_core::S3Error::InvalidArgument(format!(
-                "Unsupported x-amz-acl value: {}",
-                other
-            ))
-            .into_response();
+    // A canned ACL header takes precedence over an AccessControlPolicy body
+    let acl = match super::bucket::canned_acl(request.headers()) {
+        Ok(Some(acl)) => acl,
+        Ok(None) => {
+            let body = match super::body::read_body(request, state.config.max_xml_body_size).await {
+                Ok(b) => b,
+                Err(e) => return e.into_response(),
+            };
+            if body.is_empty() {
+                false
+            } else {
+                match xml::parse_access_control_policy_xml(&body) {
+                    Ok(public) => public,
+                    Err(e) => return e.into_response(),
+                }
+            }
         }
+        Err(e) => return e.into_response(),
     };
 
     let mut meta = match state.metadata.get_object_meta(bucket, key) {
@@ -0,0 +1,214 @@
+use crate::AppState;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use http::{HeaderMap, StatusCode};
+use simples3_core::S3Error;
+use simples3_core::s3::types::{ObjectMeta, ObjectRetention, RetentionMode};
+use simples3_core::s3::xml;
+use std::sync::Arc;
+
+pub async fn put_object_lock_configuration(
+    state: Arc<AppState>,
+    bucket: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let config = match xml::parse_object_lock_configuration_xml(&body_bytes) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.metadata.put_object_lock_configuration(bucket, &config) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_object_lock_configuration(state: Arc<AppState>, bucket: &str) -> Response<Body> {
+    match state.metadata.get_object_lock_configuration(bucket) {
+        Ok(config) => {
+            let body = xml::object_lock_configuration_xml(&config);
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn put_object_retention(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let bypass = bypass_governance(request.headers());
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let retention = match xml::parse_retention_xml(&body_bytes) {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.metadata.put_object_retention(bucket, key, &retention, bypass) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_object_retention(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
+    if let Err(e) = state.metadata.get_object_meta(bucket, key) {
+        return e.into_response();
+    }
+    match state.metadata.get_object_retention(bucket, key) {
+        Ok(Some(retention)) => {
+            let body = xml::retention_xml(&retention);
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Ok(None) => S3Error::NoSuchObjectLockConfiguration.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn put_object_legal_hold(
+    state: Arc<AppState>,
+    bucket: &str,
+    key: &str,
+    request: Request<Body>,
+) -> Response<Body> {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let on = match xml::parse_legal_hold_xml(&body_bytes) {
+        Ok(on) => on,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.metadata.put_object_legal_hold(bucket, key, on) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_object_legal_hold(state: Arc<AppState>, bucket: &str, key: &str) -> Response<Body> {
+    match state.metadata.get_object_meta(bucket, key) {
+        Ok(meta) => {
+            let body = xml::legal_hold_xml(meta.legal_hold);
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// `x-amz-bypass-governance-retention: true` lets a delete or overwrite go
+/// through a GOVERNANCE retention. COMPLIANCE retention is never bypassed.
+pub fn bypass_governance(headers: &HeaderMap) -> bool {
+    headers
+        .get("x-amz-bypass-governance-retention")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// Retention requested on upload with `x-amz-object-lock-mode` and
+/// `x-amz-object-lock-retain-until-date`. Both headers must be sent together,
+/// and only to buckets with Object Lock enabled.
+pub fn requested_retention(
+    state: &AppState,
+    bucket: &str,
+    headers: &HeaderMap,
+) -> Result<Option<ObjectRetention>, S3Error> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let retention = match (
+        header("x-amz-object-lock-mode"),
+        header("x-amz-object-lock-retain-until-date"),
+    ) {
+        (None, None) => return Ok(None),
+        (Some(mode), Some(date)) => {
+            let mode = RetentionMode::parse(mode)
+                .ok_or_else(|| S3Error::InvalidArgument("Unknown x-amz-object-lock-mode".into()))?;
+            let retain_until = xml::parse_retain_until(date)?;
+            if retain_until <= chrono::Utc::now() {
+                return Err(S3Error::InvalidArgument("The retain until date must be in the future".into()));
+            }
+            ObjectRetention { mode, retain_until }
+        }
+        _ => {
+            return Err(S3Error::InvalidArgument(
+                "x-amz-object-lock-mode and x-amz-object-lock-retain-until-date must both be supplied".into(),
+            ));
+        }
+    };
+    match state.metadata.get_object_lock_configuration(bucket) {
+        Ok(_) => Ok(Some(retention)),
+        Err(S3Error::ObjectLockConfigurationNotFound) => Err(S3Error::InvalidArgument(
+            "Bucket is missing Object Lock Configuration".into(),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+/// Legal hold requested on upload with `x-amz-object-lock-legal-hold: ON`.
+/// Turning it on requires Object Lock on the bucket.
+pub fn requested_legal_hold(state: &AppState, bucket: &str, headers: &HeaderMap) -> Result<bool, S3Error> {
+    let on = match headers.get("x-amz-object-lock-legal-hold").map(|v| v.to_str()) {
+        None => return Ok(false),
+        Some(Ok("ON")) => true,
+        Some(Ok("OFF")) => false,
+        Some(_) => return Err(S3Error::InvalidArgument("x-amz-object-lock-legal-hold must be ON or OFF".into())),
+    };
+    if on && let Err(e) = state.metadata.get_object_lock_configuration(bucket) {
+        return Err(match e {
+            S3Error::ObjectLockConfigurationNotFound => {
+                S3Error::InvalidArgument("Bucket is missing Object Lock Configuration".into())
+            }
+            e => e,
+        });
+    }
+    Ok(on)
+}
+
+/// Add `x-amz-object-lock-mode` / `x-amz-object-lock-retain-until-date` to a
+/// GET or HEAD response when the object has a retention, and
+/// `x-amz-object-lock-legal-hold` when it is on hold.
+pub fn retention_headers(
+    state: &AppState,
+    meta: &ObjectMeta,
+    mut builder: http::response::Builder,
+) -> http::response::Builder {
+    let (bucket, key) = (meta.bucket.as_str(), meta.key.as_str());
+    if meta.legal_hold {
+        builder = builder.header("x-amz-object-lock-legal-hold", "ON");
+    }
+    if let Ok(Some(retention)) = state.metadata.get_object_retention(bucket, key) {
+        builder = builder
+            .header("x-amz-object-lock-mode", retention.mode.as_str())
+            .header(
+                "x-amz-object-lock-retain-until-date",
+                xml::format_retain_until(&retention.retain_until),
+            );
+    }
+    builder
+}
@@ -3,38 +3,25 @@ use axum::body::Body;
 use axum::extract::Request;
 use axum::response::{IntoResponse, Response};
 use http::StatusCode;
-use simples3_core::s3::types::BucketPolicy;
 use std::sync::Arc;
 
 pub async fn put_bucket_policy(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
     request: Request<Body>,
 ) -> Response<Body> {
-    let body_bytes = match axum::body::to_bytes(request.into_body(), state.config.max_policy_body_size).await {
+    let body_bytes = match super::body::read_body(request, state.config.max_policy_body_size).await {
         Ok(b) => b,
-        Err(e) => return simples3_core::S3Error::InternalError(e.to_string()).into_response(),
+        Err(e) => return e.into_response(),
     };
 
-    let policy: BucketPolicy = match serde_json::from_slice(&body_bytes) {
+    let policy = match simples3_core::s3::policy::parse_bucket_policy(&body_bytes) {
         Ok(p) => p,
-        Err(e) => {
-            return simples3_core::S3Error::InvalidArgument(format!(
-                "Invalid policy JSON: {}",
-                e
-            ))
-            .into_response();
-        }
+        Err(e) => return e.into_response(),
     };
 
-    if policy.statements.is_empty() {
-        return simples3_core::S3Error::InvalidArgument(
-            "Policy must contain at least one statement".to_string(),
-        )
-        .into_response();
-    }
-
-    match state.metadata.put_bucket_policy(bucket, &policy) {
+    match state.metadata.put_bucket_policy_by(bucket, &policy, principal) {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
@@ -61,8 +48,9 @@ pub async fn get_bucket_policy(
 pub async fn delete_bucket_policy(
     state: Arc<AppState>,
     bucket: &str,
+    principal: Option<&str>,
 ) -> Response<Body> {
-    match state.metadata.delete_bucket_policy(bucket) {
+    match state.metadata.delete_bucket_policy_by(bucket, principal) {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => e.into_response(),
     }
@@ -0,0 +1,133 @@
+use crate::AppState;
+use crate::middleware::auth::WebsiteRequest;
+use crate::middleware::host_rewrite::virtual_host_bucket;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use http::{HeaderMap, StatusCode};
+use simples3_core::S3Error;
+use simples3_core::s3::website::{self, WebsiteRedirect};
+use simples3_core::s3::xml;
+use std::sync::Arc;
+
+pub async fn put_bucket_website(
+    state: Arc<AppState>,
+    bucket: &str,
+    principal: Option<&str>,
+    request: Request<Body>,
+) -> Response<Body> {
+    let body_bytes = match super::body::read_body(request, state.config.max_xml_body_size).await {
+        Ok(b) => b,
+        Err(e) => return e.into_response(),
+    };
+
+    let config = match xml::parse_website_configuration_xml(&body_bytes) {
+        Ok(c) => c,
+        Err(e) => return e.into_response(),
+    };
+
+    match state.metadata.put_website_configuration(bucket, &config, principal) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn get_bucket_website(
+    state: Arc<AppState>,
+    bucket: &str,
+) -> Response<Body> {
+    match state.metadata.get_website_configuration(bucket) {
+        Ok(config) => {
+            let body = xml::website_configuration_xml(&config);
+            (
+                StatusCode::OK,
+                [("content-type", "application/xml")],
+                body,
+            )
+                .into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+pub async fn delete_bucket_website(
+    state: Arc<AppState>,
+    bucket: &str,
+    principal: Option<&str>,
+) -> Response<Body> {
+    match state.metadata.delete_website_configuration(bucket, principal) {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Serve an anonymous website request: routing rule redirects first, then the
+/// object (with the index document for directory-style keys), then on a
+/// missing key the 404 routing rules and the error document, if the
+/// requester may read it.
+pub async fn serve_website(state: Arc<AppState>, request: WebsiteRequest, headers: &HeaderMap) -> Response<Body> {
+    let WebsiteRequest {
+        bucket,
+        key,
+        config,
+        error_document_allowed,
+    } = request;
+    // Redirects within the server keep the style of the request
+    let virtual_host = headers
+        .get(http::header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|host| virtual_host_bucket(&state.config.hostname, host))
+        == Some(bucket.as_str());
+    let path_bucket = (!virtual_host).then_some(bucket.as_str());
+    if let Some(redirect) = website::find_redirect(&config, path_bucket, &key, None) {
+        return redirect_response(redirect);
+    }
+
+    let resolved = website::resolve_key(&config, &key);
+    match state.metadata.get_object_meta(&bucket, &resolved) {
+        Ok(_) => return super::object::get_object(state, &bucket, &resolved, None, headers).await,
+        Err(S3Error::NoSuchKey) => {}
+        Err(e) => return e.into_response(),
+    }
+
+    // `docs` is a directory when `docs/index.html` exists
+    if resolved == key
+        && state
+            .metadata
+            .get_object_meta(&bucket, &format!("{}/{}", key, config.index_document))
+            .is_ok()
+    {
+        let location = match path_bucket {
+            Some(bucket) => format!("/{}/{}/", bucket, key),
+            None => format!("/{}/", key),
+        };
+        return redirect_response(WebsiteRedirect {
+            status: StatusCode::FOUND.as_u16(),
+            location,
+        });
+    }
+
+    let not_found = StatusCode::NOT_FOUND.as_u16();
+    if let Some(redirect) = website::find_redirect(&config, path_bucket, &key, Some(not_found)) {
+        return redirect_response(redirect);
+    }
+    if let Some(error_key) = config.error_document
+        && error_document_allowed
+        && state.metadata.get_object_meta(&bucket, &error_key).is_ok()
+    {
+        let mut response = super::object::get_object(state, &bucket, &error_key, None, headers).await;
+        if response.status().is_success() {
+            *response.status_mut() = StatusCode::NOT_FOUND;
+        }
+        return response;
+    }
+    S3Error::NoSuchKey.into_response()
+}
+
+fn redirect_response(redirect: WebsiteRedirect) -> Response<Body> {
+    let status = StatusCode::from_u16(redirect.status).unwrap_or(StatusCode::MOVED_PERMANENTLY);
+    match http::HeaderValue::from_str(&redirect.location) {
+        Ok(location) => (status, [(http::header::LOCATION, location)]).into_response(),
+        Err(_) => S3Error::InternalError("Invalid redirect location".into()).into_response(),
+    }
+}
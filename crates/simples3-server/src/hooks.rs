@@ -0,0 +1,87 @@
+//! Hooks for embedders of the server library.
+//!
+//! Programs that build their own `AppState` can register a [`Hook`] to run
+//! custom code (virus scanning, thumbnail generation, quota billing, ...) on
+//! object and authorization events without changing the handlers. Hooks are
+//! called inline on the request path after the operation has been decided,
+//! so slow work should be handed off to a task.
+
+use crate::notifications::EventSource;
+use simples3_core::S3Error;
+use simples3_core::s3::request::S3Operation;
+use std::sync::{Arc, RwLock};
+
+pub trait Hook: Send + Sync {
+    /// An object was written by PutObject, CopyObject or
+    /// CompleteMultipartUpload.
+    fn on_object_created(&self, _event: &ObjectEvent<'_>) {}
+
+    /// An object was removed by DeleteObject or DeleteObjects.
+    fn on_object_deleted(&self, _event: &ObjectEvent<'_>) {}
+
+    /// An S3 request was allowed or denied by the auth middleware.
+    fn on_auth_decision(&self, _decision: &AuthDecision<'_>) {}
+}
+
+/// An object event, as also published to bucket notifications.
+pub struct ObjectEvent<'a> {
+    pub bucket: &'a str,
+    pub key: &'a str,
+    /// Notification event name, e.g. `ObjectCreated:Put`.
+    pub event: &'a str,
+    /// Size and ETag of created objects; removals carry neither.
+    pub size: Option<u64>,
+    pub etag: Option<&'a str>,
+    pub source: &'a EventSource,
+}
+
+pub struct AuthDecision<'a> {
+    /// The requested operation, `None` for requests that do not parse as one.
+    pub operation: Option<&'a S3Operation>,
+    /// Access key that signed an allowed request; `None` for anonymous access
+    /// and denials.
+    pub principal: Option<&'a str>,
+    /// Why the request was denied.
+    pub error: Option<&'a S3Error>,
+}
+
+impl AuthDecision<'_> {
+    pub fn allowed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Hooks registered on the server, called in registration order.
+#[derive(Default)]
+pub struct Hooks {
+    hooks: RwLock<Vec<Arc<dyn Hook>>>,
+}
+
+impl Hooks {
+    pub fn register(&self, hook: impl Hook + 'static) {
+        self.hooks.write().unwrap().push(Arc::new(hook));
+    }
+
+    /// The registered hooks, copied so none runs under the lock.
+    fn snapshot(&self) -> Vec<Arc<dyn Hook>> {
+        self.hooks.read().unwrap().clone()
+    }
+
+    pub(crate) fn object_created(&self, event: &ObjectEvent<'_>) {
+        for hook in self.snapshot() {
+            hook.on_object_created(event);
+        }
+    }
+
+    pub(crate) fn object_deleted(&self, event: &ObjectEvent<'_>) {
+        for hook in self.snapshot() {
+            hook.on_object_deleted(event);
+        }
+    }
+
+    pub(crate) fn auth_decision(&self, decision: &AuthDecision<'_>) {
+        for hook in self.snapshot() {
+            hook.on_auth_decision(decision);
+        }
+    }
+}
@@ -1,12 +1,54 @@
+pub mod access_log;
+pub mod acme;
+pub mod connections;
 pub mod handlers;
+pub mod hooks;
+pub mod lifecycle;
+pub mod load;
+pub mod logging;
+pub mod metadata_health;
 pub mod metrics;
 pub mod middleware;
+pub mod notifications;
+pub mod rate_limit;
+pub mod readiness;
+pub mod reload;
+pub mod renditions;
+pub mod replication;
 pub mod router;
+pub mod scanning;
+pub mod security_webhook;
+pub mod server_access_log;
+pub mod snapshot_shipper;
+pub mod sync;
+pub mod systemd;
+pub mod tls;
+pub mod usage;
+pub mod usage_alerts;
+pub mod warm_up;
 
 pub struct AppState {
     pub config: simples3_core::Config,
     pub metadata: simples3_core::storage::MetadataStore,
-    pub filestore: simples3_core::storage::FileStore,
+    pub storage: simples3_core::storage::StorageRouter,
     pub start_time: std::time::Instant,
     pub metrics_handle: metrics_exporter_prometheus::PrometheusHandle,
+    pub log_level: logging::LogLevelHandle,
+    pub security_webhooks: security_webhook::DenialAggregator,
+    pub access_logs: access_log::AccessLogBuffer,
+    pub server_access_log: server_access_log::ServerAccessLog,
+    pub notifications: notifications::NotificationDispatcher,
+    pub load: load::LoadTracker,
+    pub connections: connections::ActiveRequests,
+    pub hooks: hooks::Hooks,
+    pub renditions: renditions::Renditions,
+    pub sync_jobs: sync::SyncJobs,
+    /// Time used by expiration logic; see [`simples3_core::clock`].
+    pub clock: simples3_core::clock::ServerClock,
+    pub rate_limiter: rate_limit::RateLimiter,
+    pub metadata_health: metadata_health::MetadataHealth,
+    pub usage: usage::UsageTracker,
+    pub readiness: readiness::Readiness,
+    /// Tunable settings, which may differ from `config` after a reload.
+    pub runtime_config: reload::RuntimeConfig,
 }
@@ -0,0 +1,277 @@
+use crate::AppState;
+use serde::Serialize;
+use simples3_core::S3Error;
+use simples3_core::clock::Clock;
+use simples3_core::s3::types::{LifecycleConfiguration, LifecycleRule, LifecycleStatus, ListObjectsV2Request, ObjectMeta};
+use std::sync::Arc;
+
+/// What one lifecycle scan did, or would do in a dry run.
+#[derive(Debug, Default, Serialize)]
+pub struct ScanReport {
+    pub dry_run: bool,
+    pub expired: Vec<ScanEntry>,
+    pub transitioned: Vec<ScanEntry>,
+    pub aborted_uploads: Vec<ScanEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScanEntry {
+    pub bucket: String,
+    pub key: String,
+    pub rule_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_id: Option<String>,
+}
+
+impl ScanEntry {
+    fn new(bucket: &str, key: &str, rule: &LifecycleRule) -> Self {
+        Self {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            rule_id: rule.id.clone(),
+            storage_class: None,
+            upload_id: None,
+        }
+    }
+}
+
+/// Run a lifecycle scan every `lifecycle_scan_interval_secs`, starting one
+/// interval after startup. A reload that changes the interval restarts the
+/// wait with the new one; an interval of 0 disables scans until then.
+pub async fn expiration_loop(state: Arc<AppState>) {
+    let mut logged_interval = None;
+    loop {
+        let changed = state.runtime_config.changed();
+        let interval_secs = state.runtime_config.tunables().lifecycle_scan_interval_secs;
+        if logged_interval != Some(interval_secs) {
+            if interval_secs == 0 {
+                tracing::info!("Lifecycle expiration scanner is disabled (interval = 0)");
+            } else {
+                tracing::info!(
+                    interval_secs = interval_secs,
+                    "Starting lifecycle expiration scanner"
+                );
+            }
+            logged_interval = Some(interval_secs);
+        }
+        if interval_secs == 0 {
+            changed.await;
+            continue;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_secs(interval_secs)) => {}
+            _ = changed => continue,
+        }
+        if let Err(e) = run_scan(&state, None, false).await {
+            tracing::warn!(error = %e, "Failed to list lifecycle configurations");
+        }
+        match state.storage.expire_restores(state.clock.now()).await {
+            Ok(0) => {}
+            Ok(removed) => tracing::info!(removed = removed, "Removed expired restored copies"),
+            Err(e) => tracing::warn!(error = %e, "Failed to expire restored copies"),
+        }
+    }
+}
+
+/// Apply the enabled lifecycle rules of every bucket, or only of `bucket`.
+/// A dry run reports what the scan would do without changing anything.
+pub async fn run_scan(state: &AppState, bucket: Option<&str>, dry_run: bool) -> Result<ScanReport, S3Error> {
+    let configs: Vec<(String, LifecycleConfiguration)> = match bucket {
+        Some(name) => {
+            state.metadata.get_bucket(name)?;
+            match state.metadata.get_lifecycle_configuration(name) {
+                Ok(config) => vec![(name.to_string(), config)],
+                Err(S3Error::NoSuchLifecycleConfiguration) => Vec::new(),
+                Err(e) => return Err(e),
+            }
+        }
+        None => state.metadata.list_lifecycle_configurations()?,
+    };
+
+    let now = state.clock.now();
+    let mut report = ScanReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    for (bucket, config) in configs {
+        // Frozen buckets are left as they are until thawed
+        if state.metadata.get_bucket(&bucket).is_ok_and(|b| b.frozen.is_some()) {
+            continue;
+        }
+        for rule in &config.rules {
+            if rule.status != LifecycleStatus::Enabled {
+                continue;
+            }
+            if let Some(days) = rule.abort_incomplete_multipart_days {
+                abort_incomplete_uploads(state, &bucket, rule, days, now, &mut report).await;
+            }
+            // Objects are unversioned, so NoncurrentVersionExpiration has
+            // no noncurrent versions to delete and ExpiredObjectDeleteMarker
+            // no delete markers to remove
+            if !rule.has_expiration() && rule.transitions.is_empty() {
+                continue;
+            }
+
+            let list_req = ListObjectsV2Request {
+                bucket: bucket.clone(),
+                prefix: rule.prefix.clone(),
+                delimiter: String::new(),
+                max_keys: u32::MAX,
+                continuation_token: None,
+                start_after: None,
+            };
+
+            let objects = match state.metadata.list_objects_v2(&list_req) {
+                Ok(resp) => resp.contents,
+                Err(e) => {
+                    tracing::warn!(bucket = %bucket, error = %e, "Failed to list objects for lifecycle");
+                    continue;
+                }
+            };
+
+            for obj in objects {
+                apply_rule(state, &bucket, rule, &obj, now, &mut report).await;
+            }
+        }
+    }
+    Ok(report)
+}
+
+async fn apply_rule(
+    state: &AppState,
+    bucket: &str,
+    rule: &LifecycleRule,
+    obj: &ObjectMeta,
+    now: chrono::DateTime<chrono::Utc>,
+    report: &mut ScanReport,
+) {
+    // Tag matching: if rule has tags, all must match
+    if !rule.tags.is_empty() {
+        let obj_tags = state
+            .metadata
+            .get_object_tagging(bucket, &obj.key)
+            .unwrap_or_default();
+        let all_match = rule.tags.iter().all(|rt| {
+            obj_tags.get(&rt.key) == Some(&rt.value)
+        });
+        if !all_match {
+            return;
+        }
+    }
+
+    // Determine if object should be expired
+    let should_expire = if !rule.has_expiration() {
+        false
+    } else if let Some(ref date_str) = rule.expiration_date {
+        // Date-based expiration: expire if now >= date
+        if let Ok(exp_date) = chrono::DateTime::parse_from_rfc3339(date_str) {
+            now >= exp_date
+        } else {
+            false
+        }
+    } else {
+        // Days-based expiration
+        let expiration = chrono::Duration::days(rule.expiration_days as i64);
+        obj.last_modified + expiration < now
+    };
+
+    // Lifecycle never removes an object under retention
+    let locked = state.metadata.ensure_object_unlocked(bucket, &obj.key, false).is_err();
+
+    if should_expire && !locked {
+        report.expired.push(ScanEntry::new(bucket, &obj.key, rule));
+        if report.dry_run {
+            return;
+        }
+        tracing::info!(
+            bucket = %bucket,
+            key = %obj.key,
+            rule_id = %rule.id,
+            "Deleting expired object (lifecycle)"
+        );
+        let _ = state.metadata.delete_object_meta(bucket, &obj.key);
+        if let Ok(store) = state.storage.for_object(obj) {
+            let _ = store.delete_object(bucket, &obj.key).await;
+        }
+        metrics::counter!(crate::metrics::LIFECYCLE_EXPIRED_TOTAL).increment(1);
+        return;
+    }
+
+    if let Some(class) = rule.due_storage_class(obj.last_modified, now)
+        && obj.storage_class.as_deref() != Some(class)
+    {
+        let entry = ScanEntry {
+            storage_class: Some(class.to_string()),
+            ..ScanEntry::new(bucket, &obj.key, rule)
+        };
+        if report.dry_run {
+            report.transitioned.push(entry);
+            return;
+        }
+        match state.storage.transition(obj, class).await {
+            Ok(true) => {
+                tracing::info!(
+                    bucket = %bucket,
+                    key = %obj.key,
+                    rule_id = %rule.id,
+                    storage_class = %class,
+                    "Transitioned object (lifecycle)"
+                );
+                metrics::counter!(crate::metrics::LIFECYCLE_TRANSITIONED_TOTAL).increment(1);
+                report.transitioned.push(entry);
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(bucket = %bucket, key = %obj.key, error = %e, "Failed to transition object");
+            }
+        }
+    }
+}
+
+/// Abort the bucket's multipart uploads under the rule's prefix that were
+/// initiated more than `days` days ago.
+async fn abort_incomplete_uploads(
+    state: &AppState,
+    bucket: &str,
+    rule: &LifecycleRule,
+    days: u32,
+    now: chrono::DateTime<chrono::Utc>,
+    report: &mut ScanReport,
+) {
+    let uploads = match state.metadata.list_multipart_uploads() {
+        Ok(u) => u,
+        Err(e) => {
+            tracing::warn!(bucket = %bucket, error = %e, "Failed to list multipart uploads for lifecycle");
+            return;
+        }
+    };
+    let max_age = chrono::Duration::days(days as i64);
+    for upload in uploads {
+        if upload.bucket != bucket || !upload.key.starts_with(&rule.prefix) || upload.created + max_age >= now {
+            continue;
+        }
+        report.aborted_uploads.push(ScanEntry {
+            upload_id: Some(upload.upload_id.clone()),
+            ..ScanEntry::new(bucket, &upload.key, rule)
+        });
+        if report.dry_run {
+            continue;
+        }
+        tracing::info!(
+            upload_id = %upload.upload_id,
+            bucket = %bucket,
+            key = %upload.key,
+            rule_id = %rule.id,
+            "Aborting incomplete multipart upload (lifecycle)"
+        );
+        if let Ok(store) = state.storage.for_key(&upload.bucket, &upload.key) {
+            let _ = store.cleanup_multipart(&upload.upload_id).await;
+        }
+        let _ = state.metadata.delete_multipart_upload(&upload.upload_id);
+        metrics::counter!(crate::metrics::LIFECYCLE_ABORTED_UPLOADS_TOTAL).increment(1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Server load tracking for overload visibility and backpressure.
+//!
+//! A `LoadTracker` counts open client connections, requests in flight,
+//! object transfers in flight and request body bytes buffered in memory,
+//! mirroring each count in a gauge. Connections are counted by serving
+//! through [`track`]; requests and transfers by the backpressure middleware,
+//! which also turns new requests away with `503 SlowDown` while the buffered
+//! bytes are over the configured high-watermark, or all transfer slots are
+//! taken.
+
+use axum::serve::{Listener, ListenerExt, TapIo};
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Clone, Default)]
+pub struct LoadTracker {
+    inner: Arc<Counters>,
+}
+
+#[derive(Default)]
+struct Counters {
+    connections: AtomicUsize,
+    in_flight: AtomicUsize,
+    transfers: AtomicUsize,
+    buffered_bytes: AtomicU64,
+}
+
+impl LoadTracker {
+    pub fn connections(&self) -> usize {
+        self.inner.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::Relaxed)
+    }
+
+    pub fn transfers(&self) -> usize {
+        self.inner.transfers.load(Ordering::Relaxed)
+    }
+
+    pub fn buffered_bytes(&self) -> u64 {
+        self.inner.buffered_bytes.load(Ordering::Relaxed)
+    }
+
+    fn connection_opened(&self) -> ConnectionGuard {
+        self.inner.connections.fetch_add(1, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::CONNECTIONS_OPEN).increment(1.0);
+        ConnectionGuard { load: self.clone() }
+    }
+
+    /// Count a request of `operation` as in flight until the guard drops.
+    pub fn request_started(&self, operation: &'static str) -> InFlightGuard {
+        self.inner.in_flight.fetch_add(1, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::REQUESTS_IN_FLIGHT, "operation" => operation).increment(1.0);
+        InFlightGuard {
+            load: self.clone(),
+            operation,
+        }
+    }
+
+    /// Take one of `limit` transfer slots until the guard drops, or `None`
+    /// when all are taken.
+    pub fn transfer_started(&self, limit: usize) -> Option<TransferGuard> {
+        self.inner
+            .transfers
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < limit).then_some(n + 1))
+            .ok()?;
+        metrics::gauge!(crate::metrics::TRANSFERS_IN_FLIGHT).increment(1.0);
+        Some(TransferGuard { load: self.clone() })
+    }
+
+    /// Count `bytes` more of request body as buffered in memory.
+    pub fn buffered(&self, bytes: u64) {
+        self.inner.buffered_bytes.fetch_add(bytes, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::BUFFERED_BODY_BYTES).increment(bytes as f64);
+    }
+
+    /// Release `bytes` of buffered request body.
+    pub fn released(&self, bytes: u64) {
+        self.inner.buffered_bytes.fetch_sub(bytes, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::BUFFERED_BODY_BYTES).decrement(bytes as f64);
+    }
+}
+
+struct ConnectionGuard {
+    load: LoadTracker,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.load.inner.connections.fetch_sub(1, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::CONNECTIONS_OPEN).decrement(1.0);
+    }
+}
+
+pub struct InFlightGuard {
+    load: LoadTracker,
+    operation: &'static str,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.load.inner.in_flight.fetch_sub(1, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::REQUESTS_IN_FLIGHT, "operation" => self.operation).decrement(1.0);
+    }
+}
+
+pub struct TransferGuard {
+    load: LoadTracker,
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        self.load.inner.transfers.fetch_sub(1, Ordering::Relaxed);
+        metrics::gauge!(crate::metrics::TRANSFERS_IN_FLIGHT).decrement(1.0);
+    }
+}
+
+/// A TCP listener whose connections are counted by a `LoadTracker`.
+pub struct TrackedListener {
+    inner: TcpListener,
+    load: LoadTracker,
+}
+
+/// Listener returned by [`track`]. The no-op tap gives it the `SocketAddr`
+/// connect info that `into_make_service_with_connect_info` needs.
+pub type TrackedTcpListener = TapIo<TrackedListener, fn(&mut TrackedStream)>;
+
+/// Serve `listener` with its open connections counted by `load`.
+pub fn track(listener: TcpListener, load: LoadTracker) -> TrackedTcpListener {
+    TrackedListener { inner: listener, load }.tap_io(|_| {})
+}
+
+impl Listener for TrackedListener {
+    type Io = TrackedStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let (stream, addr) = Listener::accept(&mut self.inner).await;
+        let stream = TrackedStream {
+            stream,
+            _guard: self.load.connection_opened(),
+        };
+        (stream, addr)
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Listener::local_addr(&self.inner)
+    }
+}
+
+/// A client connection, counted as open until dropped.
+pub struct TrackedStream {
+    stream: TcpStream,
+    _guard: ConnectionGuard,
+}
+
+impl AsyncRead for TrackedStream {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for TrackedStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
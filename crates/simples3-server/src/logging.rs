@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Handle to the global tracing filter, used to change log directives at runtime.
+#[derive(Clone)]
+pub struct LogLevelHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+    current: Arc<Mutex<String>>,
+}
+
+impl LogLevelHandle {
+    /// The directives currently in effect (e.g. `info,simples3_server=debug`).
+    pub fn current(&self) -> String {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Replace the active filter. Invalid directives leave the filter unchanged.
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.handle.reload(filter).map_err(|e| e.to_string())?;
+        *self.current.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+}
+
+static HANDLE: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// Install the global tracing subscriber. `RUST_LOG` takes precedence over
+/// `default_directives`.
+pub fn init_logging(default_directives: &str) -> LogLevelHandle {
+    HANDLE
+        .get_or_init(|| {
+            let directives = std::env::var("RUST_LOG")
+                .ok()
+                .filter(|v| EnvFilter::try_new(v).is_ok())
+                .unwrap_or_else(|| default_directives.to_string());
+            let filter = EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new("info"));
+            let (filter_layer, handle) = reload::Layer::new(filter);
+            let _ = tracing_subscriber::registry()
+                .with(filter_layer)
+                .with(tracing_subscriber::fmt::layer())
+                .try_init();
+            LogLevelHandle {
+                handle,
+                current: Arc::new(Mutex::new(directives)),
+            }
+        })
+        .clone()
+}
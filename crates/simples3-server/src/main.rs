@@ -1,14 +1,23 @@
 use clap::Parser;
+use simples3_core::clock::Clock;
 use simples3_core::Config;
+use simples3_core::config::{ConfigBuilder, ConfigError, ConfigOverrides, DirPolicy};
 use simples3_server::{AppState, router};
 use std::path::Path;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing_subscriber::EnvFilter;
 
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 #[command(name = "simples3-server", about = "Simple S3-compatible object storage server")]
 struct Cli {
+    /// Server config TOML file, overridden by environment variables and flags
+    #[arg(long, env = "SIMPLES3_CONFIG")]
+    config: Option<String>,
+
+    /// Fail instead of creating missing data, metadata and storage backend directories
+    #[arg(long)]
+    no_create_dirs: bool,
+
     /// Address to bind to (overrides SIMPLES3_BIND)
     #[arg(long)]
     bind: Option<String>,
@@ -36,107 +45,288 @@ struct Cli {
     /// Path to init config TOML file (overrides SIMPLES3_INIT_CONFIG)
     #[arg(long, env = "SIMPLES3_INIT_CONFIG")]
     init_config: Option<String>,
+
+    /// Check an init config file for errors and exit without starting the server
+    #[arg(long, value_name = "PATH")]
+    validate_init_config: Option<String>,
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
-    let mut config = Config::from_env();
 
-    if let Some(bind) = cli.bind {
-        config.bind = bind;
-    }
-    if let Some(data_dir) = cli.data_dir {
-        config.data_dir = data_dir.into();
-    }
-    if let Some(metadata_dir) = cli.metadata_dir {
-        config.metadata_dir = metadata_dir.into();
-    }
-    if let Some(hostname) = cli.hostname {
-        config.hostname = hostname;
-    }
-    if let Some(region) = cli.region {
-        config.region = region;
-    }
-    if let Some(admin_bind) = cli.admin_bind {
-        config.admin_bind = admin_bind;
+    if let Some(ref path) = cli.validate_init_config {
+        match simples3_core::init::load(Path::new(path)) {
+            Ok(init_cfg) => {
+                println!(
+                    "{}: OK ({} buckets, {} credentials)",
+                    path,
+                    init_cfg.buckets.len(),
+                    init_cfg.credentials.len()
+                );
+                return;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
     }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new(&config.log_level)),
-        )
-        .init();
-
-    std::fs::create_dir_all(&config.data_dir).expect("Failed to create data directory");
-    std::fs::create_dir_all(&config.metadata_dir).expect("Failed to create metadata directory");
+    let config = load_config(&cli).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
 
-    let metadata =
-        simples3_core::storage::MetadataStore::open(&config.metadata_dir).expect("Failed to open metadata store");
-    let filestore = simples3_core::storage::FileStore::new(&config.data_dir);
+    let log_level = simples3_server::logging::init_logging(&config.log_level);
+
+    let metadata = simples3_core::storage::MetadataStore::open(&config.metadata_dir)
+        .expect("Failed to open metadata store")
+        .with_list_cache(config.list_cache_entries);
+    let report = metadata
+        .run_migrations(&config.metadata_dir)
+        .expect("Failed to migrate metadata store");
+    if !report.applied.is_empty() {
+        tracing::info!(
+            from = report.from_version,
+            to = report.to_version,
+            "Metadata schema migrated"
+        );
+    }
+    let storage = simples3_core::storage::StorageRouter::from_config(&config, metadata.clone())
+        .expect("Failed to start IO pool");
 
     if let Some(ref init_path) = cli.init_config {
-        let init_cfg = simples3_core::init::load(Path::new(init_path))
-            .expect("Failed to load init config");
-        simples3_core::init::apply(&init_cfg, &metadata)
-            .expect("Failed to apply init config");
+        let applied = simples3_core::init::load(Path::new(init_path))
+            .and_then(|init_cfg| simples3_core::init::apply(&init_cfg, &metadata));
+        if let Err(e) = applied {
+            tracing::error!("Init config failed: {}", e);
+            std::process::exit(1);
+        }
         tracing::info!(path = %init_path, "Init config applied successfully");
     }
 
+    if config.warm_up {
+        simples3_server::warm_up::run(metadata.clone()).await;
+    }
+
     let metrics_handle = simples3_server::metrics::init_metrics();
+    let server_access_log = simples3_server::server_access_log::ServerAccessLog::from_config(&config)
+        .expect("Failed to open access log");
 
     let state = Arc::new(AppState {
         config: config.clone(),
         metadata,
-        filestore,
+        storage,
         start_time: std::time::Instant::now(),
         metrics_handle,
+        log_level,
+        security_webhooks: Default::default(),
+        access_logs: Default::default(),
+        server_access_log,
+        notifications: Default::default(),
+        load: Default::default(),
+        connections: Default::default(),
+        hooks: Default::default(),
+        renditions: Default::default(),
+        sync_jobs: Default::default(),
+        clock: Default::default(),
+        rate_limiter: Default::default(),
+        metadata_health: Default::default(),
+        usage: Default::default(),
+        readiness: Default::default(),
+        runtime_config: simples3_server::reload::RuntimeConfig::new(&config),
     });
 
+    // Reloads rebuild the configuration the same way, picking up changes to
+    // the config file and environment
+    state.runtime_config.set_source(move || load_config(&cli));
+
     let s3_app = router::build_s3_router(state.clone());
-    let s3_listener = tokio::net::TcpListener::bind(&config.bind)
-        .await
-        .expect("Failed to bind S3 listener");
-    tracing::info!("simples3 S3 API listening on {}", config.bind);
+    let activated = simples3_server::systemd::ActivatedListeners::from_env()
+        .expect("Invalid sockets passed by systemd");
+    let s3_listener = match activated.s3 {
+        Some(listener) => tokio::net::TcpListener::from_std(listener).expect("Failed to use S3 socket from systemd"),
+        None => tokio::net::TcpListener::bind(&config.bind)
+            .await
+            .expect("Failed to bind S3 listener"),
+    };
+    let s3_addr = s3_listener.local_addr().expect("Failed to read S3 listener address");
+    let s3_listener = simples3_server::load::track(s3_listener, state.load.clone());
+    let acme = simples3_server::acme::start(&config).expect("Invalid ACME configuration");
+    let tls = match &acme {
+        Some(acme) => Some(acme.server_config.clone()),
+        None => simples3_server::tls::server_config(&config).expect("Invalid TLS configuration"),
+    };
+    let acme_handle = acme.map(|acme| acme.handle);
+    let s3_listener = simples3_server::tls::listen(s3_listener, tls.clone());
+    tracing::info!("simples3 S3 API listening on {} ({})", s3_addr, config.url_scheme());
+
+    let cleanup_handle = state.readiness.spawn("multipart_cleanup", multipart_cleanup_loop(state.clone()));
+    let lifecycle_handle = state
+        .readiness
+        .spawn("lifecycle", simples3_server::lifecycle::expiration_loop(state.clone()));
+    let purge_handle = state.readiness.spawn("deleted_bucket_purge", deleted_bucket_purge_loop(state.clone()));
+    let tagging_gc_handle = state.readiness.spawn("tagging_gc", tagging_gc_loop(state.clone()));
+    let webhook_handle = state.readiness.spawn(
+        "security_webhooks",
+        simples3_server::security_webhook::delivery_loop(state.clone(), config.security_webhook_interval_secs),
+    );
+    let access_log_handle = state.readiness.spawn(
+        "access_log",
+        simples3_server::access_log::flush_loop(state.clone(), config.access_log_interval_secs),
+    );
+    let notification_handle = state
+        .readiness
+        .spawn("notifications", simples3_server::notifications::delivery_loop(state.clone()));
+    let health_handle = state
+        .readiness
+        .spawn("metadata_probe", simples3_server::metadata_health::probe_loop(state.clone()));
+    let replication_handle = state.readiness.spawn(
+        "replication",
+        simples3_server::replication::replication_loop(state.clone(), config.replication_interval_secs),
+    );
+    let reload_handle = state
+        .readiness
+        .spawn("config_reload", simples3_server::reload::sighup_loop(state.clone()));
+    let usage_handle = state.readiness.spawn(
+        "usage",
+        simples3_server::usage::usage_loop(state.clone(), config.usage_interval_secs),
+    );
+    let usage_alert_handle = state.readiness.spawn(
+        "usage_alerts",
+        simples3_server::usage_alerts::alert_loop(state.clone(), config.usage_alert_interval_secs),
+    );
+    let snapshot_target = simples3_server::snapshot_shipper::SnapshotTarget::from_config(&config)
+        .expect("Invalid snapshot shipping configuration");
+    let snapshot_handle = snapshot_target.map(|target| {
+        tracing::info!(target = ?config.snapshot_target, "Shipping metadata snapshots");
+        state.readiness.spawn(
+            "snapshot_shipping",
+            simples3_server::snapshot_shipper::shipping_loop(state.clone(), target, config.snapshot_interval_secs),
+        )
+    });
 
-    let cleanup_handle = tokio::spawn(multipart_cleanup_loop(state.clone()));
-    let lifecycle_handle = tokio::spawn(lifecycle_expiration_loop(state.clone()));
+    let scan_target = simples3_server::scanning::ScanTarget::from_config(&config)
+        .expect("Invalid content scanning configuration");
+    let scan_handle = scan_target.map(|target| {
+        tracing::info!(endpoint = ?config.scan_endpoint, "Scanning new objects");
+        simples3_server::scanning::start(&state, target)
+    });
 
+    if let Some(transformer) = simples3_server::renditions::CommandTransformer::from_config(&config)
+        .expect("Invalid rendition configuration")
+    {
+        tracing::info!(command = ?config.rendition_command, "Serving image renditions");
+        simples3_server::renditions::enable(&state, transformer);
+    }
+
+    let readiness = state.readiness.clone();
+    let draining = Arc::new(tokio::sync::Notify::new());
+    let s3_server = axum::serve(s3_listener, s3_app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal(readiness, draining.clone()))
+        .into_future();
+    let shutdown_state = state.clone();
     if config.admin_enabled {
         let admin_app = router::build_admin_router(state);
-        let admin_listener = tokio::net::TcpListener::bind(&config.admin_bind)
-            .await
-            .expect("Failed to bind admin listener");
-        tracing::info!("simples3 admin API listening on {}", config.admin_bind);
-
-        let s3_handle = tokio::spawn(async move {
-            axum::serve(s3_listener, s3_app.into_make_service_with_connect_info::<SocketAddr>())
-                .with_graceful_shutdown(shutdown_signal())
+        let admin_listener = match activated.admin {
+            Some(listener) => {
+                tokio::net::TcpListener::from_std(listener).expect("Failed to use admin socket from systemd")
+            }
+            None => tokio::net::TcpListener::bind(&config.admin_bind)
                 .await
-                .expect("S3 server error");
-        });
+                .expect("Failed to bind admin listener"),
+        };
+        let admin_addr = admin_listener.local_addr().expect("Failed to read admin listener address");
+        let admin_tls = simples3_server::tls::admin_server_config(&config, tls)
+            .expect("Invalid admin client CA configuration");
+        let admin_listener = simples3_server::tls::listen_admin(admin_listener, admin_tls);
+        tracing::info!("simples3 admin API listening on {} ({})", admin_addr, config.url_scheme());
+        simples3_server::systemd::notify_ready();
 
         let admin_handle = tokio::spawn(async move {
-            axum::serve(admin_listener, admin_app.into_make_service_with_connect_info::<SocketAddr>())
+            axum::serve(admin_listener, admin_app.into_make_service_with_connect_info::<simples3_server::tls::TlsConnectInfo>())
                 .await
                 .expect("Admin server error");
         });
 
-        // Wait for S3 server to finish (shutdown signal), then drop admin and cleanup
-        let _ = s3_handle.await;
+        // The admin API stays up while S3 requests drain, then goes with the
+        // background tasks
+        serve_until_drained(s3_server, draining, &shutdown_state).await;
         admin_handle.abort();
         cleanup_handle.abort();
         lifecycle_handle.abort();
+        purge_handle.abort();
+        tagging_gc_handle.abort();
+        webhook_handle.abort();
+        access_log_handle.abort();
+        notification_handle.abort();
+        health_handle.abort();
+        replication_handle.abort();
+        usage_handle.abort();
+        usage_alert_handle.abort();
+        reload_handle.abort();
+        if let Some(handle) = snapshot_handle {
+            handle.abort();
+        }
+        if let Some(handle) = scan_handle {
+            handle.abort();
+        }
+        if let Some(handle) = acme_handle {
+            handle.abort();
+        }
     } else {
         tracing::info!("Admin API is disabled");
-        axum::serve(s3_listener, s3_app.into_make_service_with_connect_info::<SocketAddr>())
-            .with_graceful_shutdown(shutdown_signal())
-            .await
-            .expect("S3 server error");
+        simples3_server::systemd::notify_ready();
+        serve_until_drained(s3_server, draining, &shutdown_state).await;
         cleanup_handle.abort();
         lifecycle_handle.abort();
+        purge_handle.abort();
+        tagging_gc_handle.abort();
+        webhook_handle.abort();
+        access_log_handle.abort();
+        notification_handle.abort();
+        health_handle.abort();
+        replication_handle.abort();
+        usage_handle.abort();
+        usage_alert_handle.abort();
+        reload_handle.abort();
+        if let Some(handle) = snapshot_handle {
+            handle.abort();
+        }
+        if let Some(handle) = scan_handle {
+            handle.abort();
+        }
+        if let Some(handle) = acme_handle {
+            handle.abort();
+        }
     }
+    flush_on_exit(&shutdown_state).await;
+}
+
+/// Build the configuration from the config file, environment and flags.
+fn load_config(cli: &Cli) -> Result<Config, ConfigError> {
+    let mut builder = ConfigBuilder::new();
+    if let Some(ref path) = cli.config {
+        builder = builder.file(Path::new(path))?;
+    }
+    let dir_policy = if cli.no_create_dirs {
+        DirPolicy::MustExist
+    } else {
+        DirPolicy::Create
+    };
+    builder
+        .env()
+        .overrides(ConfigOverrides {
+            bind: cli.bind.clone(),
+            data_dir: cli.data_dir.clone().map(Into::into),
+            metadata_dir: cli.metadata_dir.clone().map(Into::into),
+            hostname: cli.hostname.clone(),
+            region: cli.region.clone(),
+            admin_bind: cli.admin_bind.clone(),
+        })
+        .dir_policy(dir_policy)
+        .build()
 }
 
 async fn multipart_cleanup_loop(state: Arc<AppState>) {
@@ -167,7 +357,7 @@ async fn multipart_cleanup_loop(state: Arc<AppState>) {
             }
         };
 
-        let now = chrono::Utc::now();
+        let now = state.clock.now();
         let ttl_duration = chrono::Duration::seconds(ttl as i64);
 
         for upload in uploads {
@@ -179,7 +369,9 @@ async fn multipart_cleanup_loop(state: Arc<AppState>) {
                     age_secs = now.signed_duration_since(upload.created).num_seconds(),
                     "Cleaning up expired multipart upload"
                 );
-                let _ = state.filestore.cleanup_multipart(&upload.upload_id).await;
+                if let Ok(store) = state.storage.for_key(&upload.bucket, &upload.key) {
+                    let _ = store.cleanup_multipart(&upload.upload_id).await;
+                }
                 let _ = state.metadata.delete_multipart_upload(&upload.upload_id);
                 metrics::counter!(simples3_server::metrics::MULTIPART_EXPIRED_TOTAL).increment(1);
             }
@@ -187,107 +379,147 @@ async fn multipart_cleanup_loop(state: Arc<AppState>) {
     }
 }
 
-async fn lifecycle_expiration_loop(state: Arc<AppState>) {
-    let interval_secs = state.config.lifecycle_scan_interval_secs;
-    if interval_secs == 0 {
-        tracing::info!("Lifecycle expiration scanner is disabled (interval = 0)");
+async fn deleted_bucket_purge_loop(state: Arc<AppState>) {
+    let grace = state.config.bucket_delete_grace_secs;
+    if grace == 0 {
         return;
     }
-
+    // Check at least hourly, more often for short grace periods
+    let interval_secs = grace.min(3600);
     tracing::info!(
+        grace_secs = grace,
         interval_secs = interval_secs,
-        "Starting lifecycle expiration scanner"
+        "Starting deleted bucket purge task"
     );
 
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
-    // Skip first tick so we don't scan immediately on startup
     interval.tick().await;
 
     loop {
         interval.tick().await;
 
-        let configs = match state.metadata.list_lifecycle_configurations() {
-            Ok(c) => c,
+        match state.metadata.purge_expired_deleted_buckets(state.clock.now()) {
+            Ok(purged) => {
+                for name in purged {
+                    tracing::info!(bucket = %name, "Released reserved bucket name after grace period");
+                }
+            }
             Err(e) => {
-                tracing::warn!(error = %e, "Failed to list lifecycle configurations");
-                continue;
+                tracing::warn!(error = %e, "Failed to purge deleted buckets");
             }
-        };
+        }
+    }
+}
 
-        let now = chrono::Utc::now();
+async fn tagging_gc_loop(state: Arc<AppState>) {
+    let interval_secs = state.config.tagging_gc_interval_secs;
+    if interval_secs == 0 {
+        tracing::info!("Tagging garbage collection is disabled (interval = 0)");
+        return;
+    }
+    tracing::info!(interval_secs = interval_secs, "Starting tagging garbage collection task");
 
-        for (bucket, config) in configs {
-            for rule in &config.rules {
-                if rule.status != simples3_core::s3::types::LifecycleStatus::Enabled {
-                    continue;
-                }
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+    interval.tick().await;
 
-                let list_req = simples3_core::s3::types::ListObjectsV2Request {
-                    bucket: bucket.clone(),
-                    prefix: rule.prefix.clone(),
-                    delimiter: String::new(),
-                    max_keys: u32::MAX,
-                    continuation_token: None,
-                    start_after: None,
-                };
-
-                let objects = match state.metadata.list_objects_v2(&list_req) {
-                    Ok(resp) => resp.contents,
-                    Err(e) => {
-                        tracing::warn!(bucket = %bucket, error = %e, "Failed to list objects for lifecycle");
-                        continue;
-                    }
-                };
-
-                for obj in objects {
-                    // Tag matching: if rule has tags, all must match
-                    if !rule.tags.is_empty() {
-                        let obj_tags = state
-                            .metadata
-                            .get_object_tagging(&bucket, &obj.key)
-                            .unwrap_or_default();
-                        let all_match = rule.tags.iter().all(|rt| {
-                            obj_tags.get(&rt.key).map_or(false, |v| v == &rt.value)
-                        });
-                        if !all_match {
-                            continue;
-                        }
-                    }
-
-                    // Determine if object should be expired
-                    let should_expire = if let Some(ref date_str) = rule.expiration_date {
-                        // Date-based expiration: expire if now >= date
-                        if let Ok(exp_date) = chrono::DateTime::parse_from_rfc3339(date_str) {
-                            now >= exp_date
-                        } else {
-                            false
-                        }
-                    } else {
-                        // Days-based expiration
-                        let expiration = chrono::Duration::days(rule.expiration_days as i64);
-                        obj.last_modified + expiration < now
-                    };
-
-                    if should_expire {
-                        tracing::info!(
-                            bucket = %bucket,
-                            key = %obj.key,
-                            rule_id = %rule.id,
-                            "Deleting expired object (lifecycle)"
-                        );
-                        let _ = state.metadata.delete_object_meta(&bucket, &obj.key);
-                        let _ = state.filestore.delete_object(&bucket, &obj.key).await;
-                        metrics::counter!(simples3_server::metrics::LIFECYCLE_EXPIRED_TOTAL).increment(1);
-                    }
-                }
+    loop {
+        interval.tick().await;
+
+        match state.metadata.remove_orphaned_tags() {
+            Ok(0) => {}
+            Ok(removed) => {
+                tracing::info!(removed = removed, "Removed tags of objects that no longer exist");
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to garbage-collect object tags");
             }
         }
+        match state.metadata.remove_expired_leases(state.clock.now()) {
+            Ok(0) => {}
+            Ok(removed) => tracing::info!(removed = removed, "Removed expired object leases"),
+            Err(e) => tracing::warn!(error = %e, "Failed to remove expired object leases"),
+        }
+    }
+}
+
+/// Wait for Ctrl+C or `SIGTERM`. The handlers are installed right away, so
+/// a signal arriving before the server is first polled is not missed.
+fn shutdown_signal(
+    readiness: simples3_server::readiness::Readiness,
+    draining: Arc<tokio::sync::Notify>,
+) -> impl Future<Output = ()> {
+    #[cfg(unix)]
+    let (mut interrupt, mut terminate) = {
+        use tokio::signal::unix::{SignalKind, signal};
+        (
+            signal(SignalKind::interrupt()).expect("Failed to install CTRL+C handler"),
+            signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler"),
+        )
+    };
+    async move {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => {}
+        }
+        #[cfg(not(unix))]
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install CTRL+C handler");
+        tracing::info!("Shutdown signal received");
+        simples3_server::systemd::notify_stopping();
+        // /readyz fails from now on, while in-flight requests finish
+        readiness.start_draining();
+        draining.notify_one();
+    }
+}
+
+/// Run the S3 server until it has shut down. Once `draining` is notified it
+/// no longer accepts connections; requests in flight, uploads and multipart
+/// completions included, get `SIMPLES3_SHUTDOWN_TIMEOUT` seconds to finish
+/// before they are cut off.
+async fn serve_until_drained<F>(server: F, draining: Arc<tokio::sync::Notify>, state: &AppState)
+where
+    F: Future<Output = std::io::Result<()>> + Send + 'static,
+{
+    let mut server = tokio::spawn(server);
+    tokio::select! {
+        result = &mut server => {
+            result.expect("S3 server panicked").expect("S3 server error");
+            return;
+        }
+        () = draining.notified() => {}
+    }
+    let timeout = std::time::Duration::from_secs(state.config.shutdown_timeout_secs);
+    tracing::info!(
+        requests = state.connections.list().len(),
+        timeout_secs = timeout.as_secs(),
+        "Waiting for requests in flight to finish"
+    );
+    match tokio::time::timeout(timeout, &mut server).await {
+        Ok(result) => {
+            result.expect("S3 server panicked").expect("S3 server error");
+            tracing::info!("All requests finished");
+        }
+        Err(_) => {
+            server.abort();
+            tracing::warn!(
+                requests = state.connections.list().len(),
+                "Shutdown timeout reached, dropping requests still in flight"
+            );
+        }
     }
 }
 
-async fn shutdown_signal() {
-    tokio::signal::ctrl_c()
-        .await
-        .expect("Failed to install CTRL+C handler");
-    tracing::info!("Shutdown signal received");
+/// Write out what is only buffered in memory: usage counters, access log
+/// records and the metadata store's pending changes.
+async fn flush_on_exit(state: &AppState) {
+    if let Err(e) = simples3_server::usage::flush(state, chrono::Utc::now()) {
+        tracing::warn!(error = %e, "Failed to record bucket usage");
+    }
+    simples3_server::access_log::flush(state).await;
+    match state.metadata.flush() {
+        Ok(bytes) => tracing::info!(bytes = bytes, "Metadata store flushed"),
+        Err(e) => tracing::error!(error = %e, "Failed to flush metadata store"),
+    }
 }
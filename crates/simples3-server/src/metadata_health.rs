@@ -0,0 +1,100 @@
+//! Degraded mode while the metadata store is failing.
+//!
+//! A background loop probes the store every
+//! `SIMPLES3_METADATA_PROBE_INTERVAL` seconds, and right away when a request
+//! fails with an internal error. After `SIMPLES3_METADATA_FAILURE_THRESHOLD`
+//! consecutive failed probes the server is degraded: S3 writes are turned
+//! away with `503 ServiceUnavailable` and a `Retry-After` instead of failing
+//! halfway, reads are still attempted (repeated listings are answered from
+//! the list cache), and the readiness probe fails so load balancers move
+//! traffic elsewhere. The first successful probe ends the degraded mode.
+
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use simples3_core::S3Error;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetadataHealthStatus {
+    /// When the server became degraded, if it is.
+    pub degraded_since: Option<DateTime<Utc>>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct MetadataHealth {
+    status: Mutex<MetadataHealthStatus>,
+    wakeup: Notify,
+}
+
+impl MetadataHealth {
+    pub fn status(&self) -> MetadataHealthStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.status.lock().unwrap().degraded_since.is_some()
+    }
+
+    /// Record the outcome of a probe; `threshold` consecutive failures
+    /// degrade the server.
+    pub fn record_probe(&self, result: Result<(), S3Error>, threshold: u32) {
+        let mut status = self.status.lock().unwrap();
+        match result {
+            Ok(()) => {
+                if let Some(since) = status.degraded_since.take() {
+                    tracing::warn!(since = %since, "Metadata store recovered, leaving degraded mode");
+                }
+                status.consecutive_failures = 0;
+                status.last_error = None;
+            }
+            Err(e) => {
+                let detail = match e {
+                    S3Error::InternalError(detail) => detail,
+                    e => e.to_string(),
+                };
+                status.consecutive_failures += 1;
+                if status.degraded_since.is_none() && status.consecutive_failures >= threshold.max(1) {
+                    tracing::error!(error = %detail, "Metadata store failing, entering degraded mode");
+                    status.degraded_since = Some(Utc::now());
+                } else {
+                    tracing::warn!(error = %detail, failures = status.consecutive_failures, "Metadata store probe failed");
+                }
+                status.last_error = Some(detail);
+            }
+        }
+        metrics::gauge!(crate::metrics::METADATA_DEGRADED).set(status.degraded_since.is_some() as u8 as f64);
+    }
+
+    /// Probe the store now instead of at the next interval.
+    pub fn probe_soon(&self) {
+        self.wakeup.notify_one();
+    }
+}
+
+/// Probe the metadata store until the server stops.
+pub async fn probe_loop(state: Arc<AppState>) {
+    let interval_secs = state.config.metadata_probe_interval_secs;
+    if interval_secs == 0 {
+        tracing::info!("Metadata store health probes are disabled (interval = 0)");
+        return;
+    }
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = state.metadata_health.wakeup.notified() => {}
+        }
+        let metadata = state.metadata.clone();
+        let result = tokio::task::spawn_blocking(move || metadata.probe())
+            .await
+            .unwrap_or_else(|e| Err(S3Error::InternalError(e.to_string())));
+        state
+            .metadata_health
+            .record_probe(result, state.config.metadata_failure_threshold);
+    }
+}
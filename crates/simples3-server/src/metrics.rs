@@ -1,6 +1,8 @@
-use std::sync::OnceLock;
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
 
-use metrics_exporter_prometheus::PrometheusHandle;
+use metrics_exporter_prometheus::{Matcher, PrometheusHandle};
+use simples3_core::s3::types::BucketStats;
 
 pub const REQUEST_COUNTER: &str = "s3_requests_total";
 pub const REQUEST_DURATION: &str = "s3_request_duration_seconds";
@@ -10,16 +12,68 @@ pub const MULTIPART_ACTIVE_UPLOADS: &str = "simples3_active_multipart_uploads";
 pub const MULTIPART_TOTAL_PARTS: &str = "simples3_multipart_total_parts";
 pub const MULTIPART_OLDEST_AGE_SECONDS: &str = "simples3_multipart_oldest_age_seconds";
 pub const LIFECYCLE_EXPIRED_TOTAL: &str = "simples3_lifecycle_expired_total";
+pub const LIFECYCLE_TRANSITIONED_TOTAL: &str = "simples3_lifecycle_transitioned_total";
+pub const LIFECYCLE_ABORTED_UPLOADS_TOTAL: &str = "simples3_lifecycle_aborted_uploads_total";
 pub const LIFECYCLE_RULES_TOTAL: &str = "simples3_lifecycle_rules_total";
+pub const REPLICATION_COMPLETED_TOTAL: &str = "simples3_replication_completed_total";
+pub const REPLICATION_FAILURES_TOTAL: &str = "simples3_replication_failures_total";
+pub const REPLICATION_QUEUE_DEPTH: &str = "simples3_replication_queue_depth";
+pub const REPLICATION_FAILED_OBJECTS: &str = "simples3_replication_failed_objects";
+pub const REPLICATION_LAG_SECONDS: &str = "simples3_replication_lag_seconds";
+pub const NOTIFICATIONS_TOTAL: &str = "simples3_notifications_total";
+pub const NOTIFICATION_QUEUE_DEPTH: &str = "simples3_notification_queue_depth";
+pub const NOTIFICATION_OLDEST_PENDING_SECONDS: &str = "simples3_notification_oldest_pending_seconds";
+pub const CONNECTIONS_OPEN: &str = "simples3_connections_open";
+pub const REQUESTS_IN_FLIGHT: &str = "simples3_requests_in_flight";
+pub const BUFFERED_BODY_BYTES: &str = "simples3_buffered_body_bytes";
+pub const SLOWDOWN_TOTAL: &str = "simples3_slowdown_responses_total";
+pub const TRANSFERS_IN_FLIGHT: &str = "simples3_transfers_in_flight";
+pub const SCAN_RESULTS_TOTAL: &str = "simples3_scan_results_total";
+pub const RENDITIONS_TOTAL: &str = "simples3_renditions_total";
+pub const SYNC_OBJECTS_TOTAL: &str = "simples3_sync_objects_total";
+pub const RATE_LIMITED_TOTAL: &str = "simples3_rate_limited_total";
+pub const REQUEST_TIMEOUTS_TOTAL: &str = "simples3_request_timeouts_total";
+pub const ACCESS_LOG_DROPPED_TOTAL: &str = "simples3_access_log_dropped_total";
+pub const METADATA_DEGRADED: &str = "simples3_metadata_degraded";
+pub const BUCKET_OBJECTS: &str = "simples3_bucket_objects";
+pub const BUCKET_BYTES: &str = "simples3_bucket_bytes";
+pub const USAGE_ALERTS_TOTAL: &str = "simples3_usage_alerts_total";
+
+/// Upper bounds, in seconds, of the request duration histogram buckets:
+/// from quick metadata requests to multi-minute transfers.
+pub const REQUEST_DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
 
 static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
 
+/// Buckets given storage gauges by the last scrape.
+static REPORTED_BUCKETS: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
 pub fn init_metrics() -> PrometheusHandle {
     HANDLE
         .get_or_init(|| {
             metrics_exporter_prometheus::PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.into()), REQUEST_DURATION_BUCKETS)
+                .expect("Invalid request duration buckets")
                 .install_recorder()
                 .expect("Failed to install Prometheus recorder")
         })
         .clone()
 }
+
+/// Set the per-bucket storage gauges. Gauges cannot be removed, so those of
+/// buckets deleted since the last call are set to zero.
+pub fn set_bucket_storage(stats: &[(String, BucketStats)]) {
+    let mut reported = REPORTED_BUCKETS.lock().unwrap();
+    let current: HashSet<String> = stats.iter().map(|(bucket, _)| bucket.clone()).collect();
+    for gone in reported.iter().flatten().filter(|b| !current.contains(*b)) {
+        metrics::gauge!(BUCKET_OBJECTS, "bucket" => gone.clone()).set(0.0);
+        metrics::gauge!(BUCKET_BYTES, "bucket" => gone.clone()).set(0.0);
+    }
+    for (bucket, stats) in stats {
+        metrics::gauge!(BUCKET_OBJECTS, "bucket" => bucket.clone()).set(stats.objects as f64);
+        metrics::gauge!(BUCKET_BYTES, "bucket" => bucket.clone()).set(stats.bytes as f64);
+    }
+    *reported = Some(current);
+}
@@ -0,0 +1,101 @@
+use crate::AppState;
+use crate::access_log::AccessLogRecord;
+use crate::middleware::headers::{content_length, header};
+use crate::middleware::request_id::RequestId;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use simples3_core::error::S3ErrorInfo;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Subresources named in the operation field instead of OBJECT / BUCKET.
+const LOGGED_SUBRESOURCES: &[&str] = &[
+    "acl", "cors", "delete", "legal-hold", "lifecycle", "logging", "object-lock", "policy", "retention",
+    "tagging", "uploadId", "uploads", "website",
+];
+
+/// `REST.GET.OBJECT`-style operation name.
+fn log_operation(method: &str, key: Option<&str>, query: &str) -> String {
+    let subresource = query
+        .split('&')
+        .map(|pair| pair.split('=').next().unwrap_or(""))
+        .find(|name| LOGGED_SUBRESOURCES.contains(name));
+    let resource = match (subresource, key) {
+        (Some("uploadId" | "uploads"), _) => "UPLOAD".to_string(),
+        (Some(name), _) => name.replace('-', "_").to_uppercase(),
+        (None, Some(_)) => "OBJECT".to_string(),
+        (None, None) => "BUCKET".to_string(),
+    };
+    format!("REST.{}.{}", method, resource)
+}
+
+/// Record requests on buckets with server access logging enabled. Must run
+/// after host rewriting so the path is path-style.
+pub async fn access_log_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let (bucket, key) = match path.trim_start_matches('/').split_once('/') {
+        Some((bucket, key)) => (bucket.to_string(), Some(key.to_string()).filter(|k| !k.is_empty())),
+        None => (path.trim_start_matches('/').to_string(), None),
+    };
+    if bucket.is_empty() || !matches!(state.metadata.get_bucket_logging(&bucket), Ok(Some(_))) {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let request_id = RequestId::of(&request).id;
+    let time = chrono::Utc::now();
+    let method = request.method().to_string();
+    let query = request.uri().query().unwrap_or("").to_string();
+    let request_uri = format!("{} {} {:?}", method, request.uri(), request.version());
+    let headers = request.headers().clone();
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip().to_string());
+    let requester = super::denial_webhook::claimed_access_key(&request);
+    let (signature_version, auth_type) = match header(&headers, "authorization") {
+        Some(auth) if auth.starts_with("AWS ") => (Some("SigV2"), Some("AuthHeader")),
+        Some(_) => (Some("SigV4"), Some("AuthHeader")),
+        None if query.contains("X-Amz-Signature=") => (Some("SigV4"), Some("QueryString")),
+        None if query.contains("Signature=") => (Some("SigV2"), Some("QueryString")),
+        None => (None, None),
+    };
+
+    let response = next.run(request).await;
+
+    let bytes_sent = content_length(response.headers());
+    let object_size = match method.as_str() {
+        "PUT" | "POST" => content_length(&headers),
+        _ => bytes_sent,
+    };
+    let error_code = response.extensions().get::<S3ErrorInfo>().map(|info| info.code.clone());
+    let record = AccessLogRecord {
+        bucket: &bucket,
+        time,
+        remote_ip: remote_ip.as_deref(),
+        requester: requester.as_deref(),
+        request_id: &request_id,
+        operation: &log_operation(&method, key.as_deref(), &query),
+        key: key.as_deref(),
+        request_uri: &request_uri,
+        status: response.status().as_u16(),
+        error_code: error_code.as_deref(),
+        bytes_sent,
+        object_size,
+        total_time_ms: started.elapsed().as_millis(),
+        referer: header(&headers, "referer"),
+        user_agent: header(&headers, "user-agent"),
+        signature_version,
+        auth_type,
+        host: header(&headers, "host"),
+    };
+    state.access_logs.record(&bucket, record.to_line());
+    response
+}
@@ -1,46 +1,140 @@
 use crate::AppState;
+use crate::tls::TlsConnectInfo;
 use axum::{
     body::Body,
-    extract::{Request, State},
+    extract::{ConnectInfo, MatchedPath, OriginalUri, Request, State},
     http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Response},
 };
 use std::sync::Arc;
 
+/// Who an authenticated admin request came from, as recorded in the audit
+/// trail: `token`, `cert:<subject>` for a client certificate, or
+/// `bucket-token:<id>` for a bucket administration token.
+#[derive(Debug, Clone)]
+pub struct AdminActor(pub String);
+
+/// Prefix of bucket administration tokens, so other bearer tokens are not
+/// looked up.
+const BUCKET_TOKEN_PREFIX: &str = "s3bt_";
+
+/// Admin routes a bucket administration token may use, for its own bucket:
+/// the bucket's settings, bound credentials and object leases, but not
+/// deleting it, freezing it, or where its objects are stored.
+const BUCKET_ADMIN_ROUTES: &[&str] = &[
+    "/_admin/buckets/{name}/policy",
+    "/_admin/buckets/{name}/cors",
+    "/_admin/buckets/{name}/lifecycle",
+    "/_admin/buckets/{name}/credentials",
+    "/_admin/buckets/{name}/credentials/{access_key_id}",
+    "/_admin/buckets/{name}/anonymous",
+    "/_admin/buckets/{name}/anonymous-list-public",
+    "/_admin/buckets/{name}/anonymous-list",
+    "/_admin/buckets/{name}/history",
+    "/_admin/buckets/{name}/history/{version}/rollback",
+    "/_admin/buckets/{name}/security-webhook",
+    "/_admin/buckets/{name}/cache-headers",
+    "/_admin/buckets/{name}/usage-alerts",
+    "/_admin/buckets/{name}/renditions",
+    "/_admin/buckets/{name}/request-log",
+    "/_admin/buckets/{name}/replication",
+    "/_admin/buckets/{name}/default-tags",
+    "/_admin/buckets/{name}/scans",
+    "/_admin/buckets/{name}/leases",
+    "/_admin/buckets/{name}/leases/{*key}",
+];
+
 pub async fn admin_auth_middleware(
     State(state): State<Arc<AppState>>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Response {
-    let expected_token = match &state.config.admin_token {
-        Some(token) => token,
-        None => {
-            tracing::warn!("Admin request rejected: SIMPLES3_ADMIN_TOKEN is not configured");
-            return (
-                StatusCode::UNAUTHORIZED,
-                axum::Json(serde_json::json!({ "error": "Admin token not configured" })),
-            )
-                .into_response();
-        }
-    };
+    // With mutual TLS, the certificate verified during the handshake is enough
+    let client_subject = request
+        .extensions()
+        .get::<ConnectInfo<TlsConnectInfo>>()
+        .and_then(|info| info.0.client_subject.clone());
+    if let Some(subject) = client_subject {
+        tracing::info!(
+            client = %subject,
+            method = %request.method(),
+            path = %request.uri().path(),
+            "Admin request authenticated by client certificate"
+        );
+        request.extensions_mut().insert(AdminActor(format!("cert:{}", subject)));
+        return next.run(request).await;
+    }
 
-    let provided = request
+    let bearer = request
         .headers()
         .get("authorization")
         .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.strip_prefix("Bearer "));
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::to_string);
+
+    let authorized = match (&state.config.admin_token, &bearer) {
+        (Some(expected), Some(token)) => constant_time_eq(token.as_bytes(), expected.as_bytes()),
+        _ => false,
+    };
+    if authorized {
+        request.extensions_mut().insert(AdminActor("token".into()));
+        return next.run(request).await;
+    }
 
-    match provided {
-        Some(token) if constant_time_eq(token.as_bytes(), expected_token.as_bytes()) => {
-            next.run(request).await
+    if let Some(token) = bearer.as_deref().filter(|t| t.starts_with(BUCKET_TOKEN_PREFIX)) {
+        match state.metadata.find_bucket_admin_token(token) {
+            Ok(Some(delegated)) => {
+                if !bucket_route_allowed(&request, &delegated.bucket) {
+                    return (
+                        StatusCode::FORBIDDEN,
+                        axum::Json(serde_json::json!({
+                            "error": format!("Token may only manage bucket '{}'", delegated.bucket)
+                        })),
+                    )
+                        .into_response();
+                }
+                request.extensions_mut().insert(AdminActor(format!("bucket-token:{}", delegated.id)));
+                return next.run(request).await;
+            }
+            Ok(None) => {}
+            Err(e) => return e.into_response(),
         }
-        _ => (
+    }
+
+    if state.config.admin_token.is_none() {
+        tracing::warn!("Admin request rejected: SIMPLES3_ADMIN_TOKEN is not configured");
+        return (
             StatusCode::UNAUTHORIZED,
-            axum::Json(serde_json::json!({ "error": "Unauthorized" })),
+            axum::Json(serde_json::json!({ "error": "Admin token not configured" })),
         )
-            .into_response(),
+            .into_response();
+    }
+    (
+        StatusCode::UNAUTHORIZED,
+        axum::Json(serde_json::json!({ "error": "Unauthorized" })),
+    )
+        .into_response()
+}
+
+/// Whether a bucket administration token for `bucket` may make `request`:
+/// one of [`BUCKET_ADMIN_ROUTES`] naming that bucket.
+fn bucket_route_allowed(request: &Request<Body>, bucket: &str) -> bool {
+    let Some(route) = request.extensions().get::<MatchedPath>().map(|p| p.as_str()) else {
+        return false;
+    };
+    if !BUCKET_ADMIN_ROUTES.contains(&route) {
+        return false;
     }
+    // The matched route includes the /_admin prefix the nested URI lacks
+    let path = match request.extensions().get::<OriginalUri>() {
+        Some(uri) => uri.path().to_string(),
+        None => request.uri().path().to_string(),
+    };
+    route
+        .split('/')
+        .zip(path.split('/'))
+        .any(|(template, segment)| template == "{name}" && segment == bucket)
 }
 
 fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
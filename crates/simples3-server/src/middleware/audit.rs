@@ -0,0 +1,103 @@
+use crate::AppState;
+use crate::middleware::admin_auth::AdminActor;
+use crate::tls::TlsConnectInfo;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, MatchedPath, OriginalUri, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http::{Method, StatusCode};
+use simples3_core::s3::types::AuditEntry;
+use std::sync::Arc;
+
+/// Largest admin request body buffered for the audit trail, the same as the
+/// default JSON body limit of the handlers.
+const MAX_AUDITED_BODY: usize = 2 * 1024 * 1024;
+
+/// POST endpoints that change nothing and are left out of the audit trail.
+const READ_ONLY_ROUTES: &[&str] = &["/_admin/simulate", "/_admin/debug/canonical-request"];
+
+/// Object leases are acquired, renewed and released by workers as they go,
+/// which would drown the audit trail; they are not configuration changes.
+const UNAUDITED_ROUTES: &[&str] = &["/_admin/buckets/{name}/leases/{*key}"];
+
+/// Payload fields whose values are replaced by `********` before recording.
+const SECRET_FIELDS: &[&str] = &["passphrase", "secret_access_key", "export"];
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (name, field) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&name.as_str()) && !field.is_null() {
+                    *field = serde_json::Value::String("********".into());
+                } else {
+                    redact(field);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Record every admin API mutation (any method but GET and HEAD) with its
+/// actor, JSON payload and response status. Must run after
+/// [`super::admin_auth::admin_auth_middleware`], which names the actor;
+/// rejected requests never reach it.
+pub async fn audit_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let route = request.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string());
+    if matches!(*request.method(), Method::GET | Method::HEAD)
+        || route
+            .as_deref()
+            .is_some_and(|r| READ_ONLY_ROUTES.contains(&r) || UNAUDITED_ROUTES.contains(&r))
+    {
+        return next.run(request).await;
+    }
+
+    let actor = request
+        .extensions()
+        .get::<AdminActor>()
+        .map(|a| a.0.clone())
+        .unwrap_or_else(|| "unknown".into());
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<TlsConnectInfo>>()
+        .map(|ci| ci.0.remote_addr.ip().to_string());
+    let method = request.method().to_string();
+    // The admin router is nested under /_admin, which the nested URI lacks
+    let path = match request.extensions().get::<OriginalUri>() {
+        Some(uri) => uri.path().to_string(),
+        None => request.uri().path().to_string(),
+    };
+
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_AUDITED_BODY).await {
+        Ok(b) => b,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+    let payload = serde_json::from_slice::<serde_json::Value>(&bytes).ok().map(|mut value| {
+        redact(&mut value);
+        value
+    });
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+
+    let entry = AuditEntry {
+        id: 0,
+        time: chrono::Utc::now(),
+        actor,
+        remote_ip,
+        method,
+        path,
+        route,
+        status: response.status().as_u16(),
+        payload,
+    };
+    if let Err(e) = state.metadata.record_audit_entry(entry) {
+        tracing::warn!(error = %e, "Failed to record admin audit entry");
+    }
+    response
+}
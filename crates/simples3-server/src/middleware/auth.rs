@@ -1,4 +1,5 @@
 use crate::AppState;
+use crate::hooks::AuthDecision;
 use axum::{
     body::Body,
     extract::{ConnectInfo, Request, State},
@@ -6,8 +7,10 @@ use axum::{
     response::Response,
 };
 use axum::response::IntoResponse;
-use chrono::{NaiveDateTime, Utc};
-use simples3_core::auth::sigv4;
+use chrono::NaiveDateTime;
+use simples3_core::auth::{sigv2, sigv4};
+use simples3_core::clock::Clock;
+use simples3_core::s3::types::{AccessKeyRecord, WebsiteConfiguration};
 use simples3_core::s3::policy::RequestContext;
 use simples3_core::s3::request::{parse_s3_operation, S3Operation};
 use std::collections::{BTreeMap, HashMap};
@@ -17,16 +20,30 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct AnonymousPublicListOnly;
 
+/// Access key id of the credential that signed the request.
+#[derive(Clone)]
+pub struct AuthenticatedPrincipal(pub String);
+
+/// Anonymous GET without query parameters on a bucket with a website
+/// configuration. Served with the index and error documents instead of as a
+/// plain S3 request.
+#[derive(Clone)]
+pub struct WebsiteRequest {
+    pub bucket: String,
+    pub key: String,
+    pub config: WebsiteConfiguration,
+    /// Whether the requester may read the error document, which is served in
+    /// place of the requested key.
+    pub error_document_allowed: bool,
+}
+
 pub async fn auth_middleware(
     State(state): State<Arc<AppState>>,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Response {
-    let method = request.method().clone();
-    let uri = request.uri().clone();
-    let path = uri.path().to_string();
-
-    let query: HashMap<String, String> = uri
+    let query: HashMap<String, String> = request
+        .uri()
         .query()
         .map(|q| {
             q.split('&')
@@ -38,8 +55,36 @@ pub async fn auth_middleware(
                 .collect()
         })
         .unwrap_or_default();
+    let mut operation = parse_s3_operation(request.method(), request.uri().path(), &query);
+
+    let result = authorize(&state, &mut request, &query, &mut operation);
+    state.hooks.auth_decision(&AuthDecision {
+        operation: operation.as_ref(),
+        principal: result.as_ref().ok().and_then(|p| p.as_deref()),
+        error: result.as_ref().err(),
+    });
+    match result {
+        Ok(Some(access_key_id)) => {
+            request.extensions_mut().insert(AuthenticatedPrincipal(access_key_id.clone()));
+            super::rate_limit::run_for_credential(&state, &access_key_id, request, next).await
+        }
+        Ok(None) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
 
-    let operation = parse_s3_operation(&method, &path, &query);
+/// Decide whether a request may proceed. Returns the access key that signed
+/// it, or `None` for allowed anonymous requests. Website requests rewrite
+/// `operation` to the GetObject they resolve to.
+fn authorize(
+    state: &AppState,
+    request: &mut Request<Body>,
+    query: &HashMap<String, String>,
+    operation: &mut Option<S3Operation>,
+) -> Result<Option<String>, simples3_core::S3Error> {
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let path = uri.path().to_string();
 
     // Check for presigned URL (query-string auth)
     if query.contains_key("X-Amz-Algorithm") {
@@ -54,52 +99,65 @@ pub async fn auth_middleware(
             }
         }
 
-        match verify_presigned_url(&state, &method_str, &path_str, &raw_query, &headers_map) {
-            Ok(()) => return next.run(request).await,
-            Err(e) => return e.into_response(),
-        }
+        let access_key_id = verify_presigned_url(state, &method_str, &path_str, &raw_query, &headers_map)?;
+        check_permission(state, &access_key_id, operation.as_ref(), request.headers())?;
+        return Ok(Some(access_key_id));
     }
 
     // If no Authorization header is present, check anonymous access
     if !request.headers().contains_key("authorization") {
+        // Website requests are authorized as a GetObject of the document they
+        // resolve to. Redirects by key prefix reveal nothing and need no access.
+        if let Some(mut website) = website_request(state, &method, query, operation.as_ref()) {
+            let redirect = simples3_core::s3::website::find_redirect(&website.config, None, &website.key, None);
+            if let Some(ref error_key) = website.config.error_document {
+                let ctx = build_request_context(state, request, query);
+                website.error_document_allowed = anonymous_get_allowed(state, &website.bucket, error_key, &ctx);
+            }
+            *operation = Some(S3Operation::GetObject {
+                bucket: website.bucket.clone(),
+                key: simples3_core::s3::website::resolve_key(&website.config, &website.key),
+            });
+            request.extensions_mut().insert(website);
+            if redirect.is_some() {
+                return Ok(None);
+            }
+        }
+
         // Global anonymous mode bypasses auth entirely
         if state.config.anonymous_global {
-            return next.run(request).await;
+            return Ok(None);
         }
 
         // Per-bucket anonymous read: only allow read-only operations
-        if let Some(ref op) = operation {
-            if op.is_read_only() {
-                if let Some(bucket_name) = op.bucket() {
-                    if let Ok(bucket_meta) = state.metadata.get_bucket(bucket_name) {
-                        if bucket_meta.anonymous_read {
-                            return next.run(request).await;
-                        }
-                    }
-                }
-            }
+        if let Some(ref op) = *operation
+            && op.is_read_only()
+            && let Some(bucket_name) = op.bucket()
+            && let Ok(bucket_meta) = state.metadata.get_bucket(bucket_name)
+            && bucket_meta.anonymous_read
+        {
+            return Ok(None);
         }
 
         // Per-object public access on private buckets
-        if let Some(ref op) = operation {
+        if let Some(ref op) = *operation {
             match op {
                 S3Operation::GetObject { bucket, key }
                 | S3Operation::HeadObject { bucket, key }
                 | S3Operation::GetObjectTagging { bucket, key }
                 | S3Operation::GetObjectAcl { bucket, key } => {
-                    if let Ok(meta) = state.metadata.get_object_meta(bucket, key) {
-                        if meta.public {
-                            return next.run(request).await;
-                        }
+                    if let Ok(meta) = state.metadata.get_object_meta(bucket, key)
+                        && meta.public
+                    {
+                        return Ok(None);
                     }
                 }
                 S3Operation::ListObjectsV2 { bucket } => {
-                    if let Ok(bucket_meta) = state.metadata.get_bucket(bucket) {
-                        if bucket_meta.anonymous_list_public {
-                            let mut request = request;
-                            request.extensions_mut().insert(AnonymousPublicListOnly);
-                            return next.run(request).await;
-                        }
+                    if let Ok(bucket_meta) = state.metadata.get_bucket(bucket)
+                        && bucket_meta.anonymous_list_public
+                    {
+                        request.extensions_mut().insert(AnonymousPublicListOnly);
+                        return Ok(None);
                     }
                 }
                 _ => {}
@@ -107,31 +165,30 @@ pub async fn auth_middleware(
         }
 
         // Evaluate bucket policy for anonymous requests
-        if let Some(ref op) = operation {
-            if let Some(bucket_name) = op.bucket() {
-                if let Ok(policy) = state.metadata.get_bucket_policy(bucket_name) {
-                    let s3_action = simples3_core::s3::policy::operation_to_s3_action(op.name());
-                    let key = extract_key(op);
-                    let ctx = build_request_context(&request, &query);
-                    let decision = simples3_core::s3::policy::evaluate_policy(
-                        &policy,
-                        s3_action,
-                        bucket_name,
-                        key.as_deref(),
-                        None,
-                        Some(&ctx),
-                    );
-                    match decision {
-                        simples3_core::s3::policy::PolicyDecision::ExplicitAllow => {
-                            return next.run(request).await;
-                        }
-                        simples3_core::s3::policy::PolicyDecision::ExplicitDeny => {
-                            return simples3_core::S3Error::AccessDenied.into_response();
-                        }
-                        simples3_core::s3::policy::PolicyDecision::ImplicitDeny => {
-                            // Fall through to existing behavior (AccessDenied below)
-                        }
-                    }
+        if let Some(ref op) = *operation
+            && let Some(bucket_name) = op.bucket()
+            && let Ok(policy) = state.metadata.get_bucket_policy(bucket_name)
+        {
+            let s3_action = simples3_core::s3::policy::operation_to_s3_action(op.name());
+            let key = extract_key(op);
+            let ctx = build_request_context(state, request, query);
+            let decision = simples3_core::s3::policy::evaluate_policy(
+                &policy,
+                s3_action,
+                bucket_name,
+                key.as_deref(),
+                None,
+                Some(&ctx),
+            );
+            match decision {
+                simples3_core::s3::policy::PolicyDecision::ExplicitAllow => {
+                    return Ok(None);
+                }
+                simples3_core::s3::policy::PolicyDecision::ExplicitDeny => {
+                    return Err(simples3_core::S3Error::AccessDenied);
+                }
+                simples3_core::s3::policy::PolicyDecision::ImplicitDeny => {
+                    // Fall through to existing behavior (AccessDenied below)
                 }
             }
         }
@@ -143,45 +200,191 @@ pub async fn auth_middleware(
             Ok(s) => s.to_string(),
             Err(_) => {
                 tracing::debug!("Auth failed: authorization header is not valid UTF-8");
-                return simples3_core::S3Error::AccessDenied.into_response();
+                return Err(simples3_core::S3Error::AccessDenied);
             }
         },
         None => {
             tracing::debug!(method = %method, path = %path, "Auth failed: no authorization header");
-            return simples3_core::S3Error::AccessDenied.into_response();
+            return Err(simples3_core::S3Error::AccessDenied);
         }
     };
 
-    // Parse SigV4
-    let auth = match sigv4::parse_auth_header(&auth_header) {
-        Ok(a) => a,
-        Err(e) => {
-            tracing::debug!(auth_header = %auth_header, "Auth failed: could not parse SigV4 auth header");
-            return e.into_response();
+    // Legacy SigV2 clients send "AWS AKID:signature"
+    let verified = if auth_header.starts_with("AWS ") {
+        verify_sigv2(state, request, &auth_header)
+    } else {
+        verify_sigv4(state, request, &auth_header)
+    };
+    let access_key_id = verified?;
+    check_permission(state, &access_key_id, operation.as_ref(), request.headers())?;
+
+    // Evaluate bucket policy for authenticated requests (explicit deny overrides)
+    if let Some(ref op) = *operation
+        && let Some(bucket_name) = op.bucket()
+        && let Ok(policy) = state.metadata.get_bucket_policy(bucket_name)
+    {
+        let s3_action = simples3_core::s3::policy::operation_to_s3_action(op.name());
+        let key = extract_key(op);
+        let ctx = build_request_context(state, request, query);
+        let decision = simples3_core::s3::policy::evaluate_policy(
+            &policy,
+            s3_action,
+            bucket_name,
+            key.as_deref(),
+            Some(&access_key_id),
+            Some(&ctx),
+        );
+        if decision == simples3_core::s3::policy::PolicyDecision::ExplicitDeny {
+            return Err(simples3_core::S3Error::AccessDenied);
         }
+    }
+
+    Ok(Some(access_key_id))
+}
+
+fn website_request(
+    state: &AppState,
+    method: &http::Method,
+    query: &HashMap<String, String>,
+    operation: Option<&S3Operation>,
+) -> Option<WebsiteRequest> {
+    if method != http::Method::GET || !query.is_empty() {
+        return None;
+    }
+    let (bucket, key) = match operation? {
+        S3Operation::ListObjectsV2 { bucket } => (bucket, ""),
+        S3Operation::GetObject { bucket, key } => (bucket, key.as_str()),
+        _ => return None,
     };
+    let config = state.metadata.get_website_configuration(bucket).ok()?;
+    Some(WebsiteRequest {
+        bucket: bucket.clone(),
+        key: key.to_string(),
+        config,
+        error_document_allowed: false,
+    })
+}
 
-    // Look up credential
-    let credential = match state.metadata.get_credential(&auth.access_key_id) {
+/// Whether an anonymous request may get the object at `key`, by the rules
+/// `authorize` applies to an anonymous GetObject.
+fn anonymous_get_allowed(state: &AppState, bucket: &str, key: &str, ctx: &RequestContext) -> bool {
+    if state.config.anonymous_global
+        || state.metadata.get_bucket(bucket).is_ok_and(|b| b.anonymous_read)
+        || state.metadata.get_object_meta(bucket, key).is_ok_and(|meta| meta.public)
+    {
+        return true;
+    }
+    state.metadata.get_bucket_policy(bucket).is_ok_and(|policy| {
+        simples3_core::s3::policy::evaluate_policy(
+            &policy,
+            simples3_core::s3::policy::operation_to_s3_action("GetObject"),
+            bucket,
+            Some(key),
+            None,
+            Some(ctx),
+        ) == simples3_core::s3::policy::PolicyDecision::ExplicitAllow
+    })
+}
+
+/// Look up an access key and make sure it has not been revoked.
+fn active_credential(
+    state: &AppState,
+    access_key_id: &str,
+) -> Result<AccessKeyRecord, simples3_core::S3Error> {
+    let credential = match state.metadata.get_credential(access_key_id) {
         Ok(c) => c,
         Err(e) => {
-            tracing::debug!(access_key_id = %auth.access_key_id, "Auth failed: credential not found");
-            return e.into_response();
+            tracing::debug!(access_key_id = %access_key_id, "Auth failed: credential not found");
+            return Err(e);
         }
     };
-
     if !credential.active {
-        tracing::debug!(access_key_id = %auth.access_key_id, "Auth failed: credential is revoked");
-        return simples3_core::S3Error::AccessDenied.into_response();
+        tracing::debug!(access_key_id = %access_key_id, "Auth failed: credential is revoked");
+        return Err(simples3_core::S3Error::AccessDenied);
     }
+    Ok(credential)
+}
+
+/// Enforce the credential's permission mask and bucket binding, if it has
+/// them. A bound credential cannot list buckets, nor copy from another
+/// bucket.
+fn check_permission(
+    state: &AppState,
+    access_key_id: &str,
+    operation: Option<&S3Operation>,
+    headers: &http::HeaderMap,
+) -> Result<(), simples3_core::S3Error> {
+    let Some(op) = operation else {
+        return Ok(());
+    };
+    let credential = state.metadata.get_credential(access_key_id)?;
+    let permission = op.permission();
+    if !credential.allows(permission) {
+        tracing::debug!(
+            access_key_id = %access_key_id,
+            operation = op.name(),
+            permission = permission.as_str(),
+            "Auth failed: operation not permitted for credential"
+        );
+        return Err(simples3_core::S3Error::AccessDenied);
+    }
+    let copy_source_bucket = headers
+        .get("x-amz-copy-source")
+        .and_then(|v| v.to_str().ok())
+        .map(|source| {
+            let source = percent_encoding::percent_decode_str(source.trim_start_matches('/')).decode_utf8_lossy();
+            source.split('/').next().unwrap_or_default().to_string()
+        });
+    if let Some(bound) = &credential.bucket
+        && (op.bucket() != Some(bound.as_str()) || copy_source_bucket.is_some_and(|b| b != *bound))
+    {
+        tracing::debug!(
+            access_key_id = %access_key_id,
+            operation = op.name(),
+            bucket = %bound,
+            "Auth failed: credential is bound to another bucket"
+        );
+        return Err(simples3_core::S3Error::AccessDenied);
+    }
+    Ok(())
+}
+
+/// Reject credential scopes for regions this server does not serve.
+fn check_region(state: &AppState, region: &str) -> Result<(), simples3_core::S3Error> {
+    if state.config.accepts_region(region) {
+        return Ok(());
+    }
+    tracing::debug!(region = %region, "Auth failed: region not accepted");
+    Err(simples3_core::S3Error::AuthorizationHeaderMalformed {
+        received: region.to_string(),
+        expected: state.config.region.clone(),
+    })
+}
+
+/// Verify a SigV4 Authorization header. Returns the access key id on success.
+fn verify_sigv4(
+    state: &AppState,
+    request: &Request<Body>,
+    auth_header: &str,
+) -> Result<String, simples3_core::S3Error> {
+    let auth = match sigv4::parse_auth_header(auth_header) {
+        Ok(a) => a,
+        Err(e) => {
+            tracing::debug!(auth_header = %auth_header, "Auth failed: could not parse SigV4 auth header");
+            return Err(e);
+        }
+    };
+
+    check_region(state, &auth.region)?;
+    let credential = active_credential(state, &auth.access_key_id)?;
 
     // Build headers map for verification
     let mut headers_map = BTreeMap::new();
     for name in &auth.signed_headers {
-        if let Some(val) = request.headers().get(name.as_str()) {
-            if let Ok(v) = val.to_str() {
-                headers_map.insert(name.clone(), v.to_string());
-            }
+        if let Some(val) = request.headers().get(name.as_str())
+            && let Ok(v) = val.to_str()
+        {
+            headers_map.insert(name.clone(), v.to_string());
         }
     }
 
@@ -196,72 +399,105 @@ pub async fn auth_middleware(
     // Build canonical query string from raw URI query (sorted)
     // We use the raw query string to preserve the exact encoding the client used,
     // since AWS SigV4 requires unreserved chars (A-Z, a-z, 0-9, -, _, ., ~) to NOT be encoded.
-    let mut raw_pairs: Vec<(&str, &str)> = uri
+    let uri = request.uri();
+    let canonical_query = sigv4::canonical_query_string(uri.query().unwrap_or(""));
+
+    match sigv4::verify_signature(
+        request.method().as_str(),
+        uri.path(),
+        &canonical_query,
+        &headers_map,
+        &auth,
+        &credential.secret_access_key,
+        &payload_hash,
+    ) {
+        Ok(()) => Ok(auth.access_key_id),
+        Err(e) => {
+            tracing::debug!(
+                method = %request.method(),
+                path = %uri.path(),
+                access_key_id = %auth.access_key_id,
+                signed_headers = ?auth.signed_headers,
+                payload_hash = %payload_hash,
+                canonical_query = %canonical_query,
+                "Auth failed: signature mismatch"
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Verify a legacy SigV2 Authorization header. Returns the access key id on success.
+fn verify_sigv2(
+    state: &AppState,
+    request: &Request<Body>,
+    auth_header: &str,
+) -> Result<String, simples3_core::S3Error> {
+    let auth = match sigv2::parse_auth_header(auth_header) {
+        Ok(a) => a,
+        Err(e) => {
+            tracing::debug!(auth_header = %auth_header, "Auth failed: could not parse SigV2 auth header");
+            return Err(e);
+        }
+    };
+
+    let credential = active_credential(state, &auth.access_key_id)?;
+
+    let mut headers_map = BTreeMap::new();
+    for (name, value) in request.headers().iter() {
+        if let Ok(v) = value.to_str() {
+            headers_map
+                .entry(name.as_str().to_lowercase())
+                .and_modify(|existing: &mut String| {
+                    existing.push(',');
+                    existing.push_str(v);
+                })
+                .or_insert_with(|| v.to_string());
+        }
+    }
+
+    let uri = request.uri();
+    let query_pairs: Vec<(String, String)> = uri
         .query()
         .unwrap_or("")
         .split('&')
         .filter(|p| !p.is_empty())
         .map(|p| {
             let mut kv = p.splitn(2, '=');
-            let k = kv.next().unwrap_or("");
-            let v = kv.next().unwrap_or("");
-            (k, v)
+            let decode = |s: &str| {
+                percent_encoding::percent_decode_str(s)
+                    .decode_utf8_lossy()
+                    .into_owned()
+            };
+            (decode(kv.next().unwrap_or("")), decode(kv.next().unwrap_or("")))
         })
         .collect();
-    raw_pairs.sort_by(|a, b| a.0.cmp(&b.0));
-    let canonical_query: String = raw_pairs
-        .iter()
-        .map(|(k, v)| format!("{}={}", k, v))
-        .collect::<Vec<_>>()
-        .join("&");
 
-    // Verify signature
-    match sigv4::verify_signature(
-        method.as_str(),
+    match sigv2::verify_signature(
+        request.method().as_str(),
         uri.path(),
-        &canonical_query,
+        &query_pairs,
         &headers_map,
         &auth,
         &credential.secret_access_key,
-        &payload_hash,
     ) {
-        Ok(()) => {
-            // Evaluate bucket policy for authenticated requests (explicit deny overrides)
-            if let Some(ref op) = operation {
-                if let Some(bucket_name) = op.bucket() {
-                    if let Ok(policy) = state.metadata.get_bucket_policy(bucket_name) {
-                        let s3_action = simples3_core::s3::policy::operation_to_s3_action(op.name());
-                        let key = extract_key(op);
-                        let ctx = build_request_context(&request, &query);
-                        let decision = simples3_core::s3::policy::evaluate_policy(
-                            &policy,
-                            s3_action,
-                            bucket_name,
-                            key.as_deref(),
-                            Some(&auth.access_key_id),
-                            Some(&ctx),
-                        );
-                        if decision == simples3_core::s3::policy::PolicyDecision::ExplicitDeny {
-                            return simples3_core::S3Error::AccessDenied.into_response();
-                        }
-                    }
-                }
-            }
-            next.run(request).await
-        }
+        Ok(()) => {}
         Err(e) => {
             tracing::debug!(
-                method = %method,
-                path = %path,
+                method = %request.method(),
+                path = %uri.path(),
                 access_key_id = %auth.access_key_id,
-                signed_headers = ?auth.signed_headers,
-                payload_hash = %payload_hash,
-                canonical_query = %canonical_query,
-                "Auth failed: signature mismatch"
+                "Auth failed: SigV2 signature mismatch"
             );
-            e.into_response()
+            return Err(e);
         }
     }
+
+    if let Err(e) = sigv2::check_request_time(&headers_map, state.clock.now()) {
+        tracing::debug!(access_key_id = %auth.access_key_id, "Auth failed: SigV2 request date missing or skewed");
+        return Err(e);
+    }
+    Ok(auth.access_key_id)
 }
 
 fn verify_presigned_url(
@@ -270,7 +506,7 @@ fn verify_presigned_url(
     path: &str,
     raw_query: &str,
     headers: &BTreeMap<String, String>,
-) -> Result<(), simples3_core::S3Error> {
+) -> Result<String, simples3_core::S3Error> {
     // Parse query params from raw query (preserving encoding)
     let query_pairs: Vec<(String, String)> = raw_query
         .split('&')
@@ -317,6 +553,7 @@ fn verify_presigned_url(
     let access_key_id = cred_parts[0];
     let date = cred_parts[1];
     let region = cred_parts[2];
+    check_region(state, region)?;
 
     // Look up credential
     let cred_record = state.metadata.get_credential(access_key_id)?;
@@ -333,7 +570,7 @@ fn verify_presigned_url(
     let request_time = NaiveDateTime::parse_from_str(&amz_date_decoded, "%Y%m%dT%H%M%SZ")
         .map_err(|_| simples3_core::S3Error::AccessDenied)?;
     let request_time = request_time.and_utc();
-    let now = Utc::now();
+    let now = state.clock.now();
     let elapsed = (now - request_time).num_seconds();
     if elapsed > expires || elapsed < 0 {
         return Err(simples3_core::S3Error::AccessDenied);
@@ -369,10 +606,11 @@ fn verify_presigned_url(
         region,
         &cred_record.secret_access_key,
         &signature,
-    )
+    )?;
+    Ok(access_key_id.to_string())
 }
 
-fn build_request_context(request: &Request<Body>, query: &HashMap<String, String>) -> RequestContext {
+fn build_request_context(state: &AppState, request: &Request<Body>, query: &HashMap<String, String>) -> RequestContext {
     let source_ip = request
         .extensions()
         .get::<ConnectInfo<SocketAddr>>()
@@ -383,12 +621,12 @@ fn build_request_context(request: &Request<Body>, query: &HashMap<String, String
         .and_then(|v| v.to_str().ok())
         .map(|v| v == "https")
         .unwrap_or_else(|| {
-            request.uri().scheme_str().map_or(false, |s| s == "https")
+            request.uri().scheme_str() == Some("https")
         });
     let s3_prefix = query.get("prefix").cloned();
     RequestContext {
         source_ip,
-        current_time: Utc::now(),
+        current_time: state.clock.now(),
         secure_transport,
         s3_prefix,
     }
@@ -405,6 +643,11 @@ fn extract_key(op: &S3Operation) -> Option<String> {
         | S3Operation::DeleteObjectTagging { key, .. }
         | S3Operation::PutObjectAcl { key, .. }
         | S3Operation::GetObjectAcl { key, .. }
+        | S3Operation::PutObjectRetention { key, .. }
+        | S3Operation::GetObjectRetention { key, .. }
+        | S3Operation::PutObjectLegalHold { key, .. }
+        | S3Operation::GetObjectLegalHold { key, .. }
+        | S3Operation::RestoreObject { key, .. }
         | S3Operation::CreateMultipartUpload { key, .. }
         | S3Operation::UploadPart { key, .. }
         | S3Operation::CompleteMultipartUpload { key, .. }
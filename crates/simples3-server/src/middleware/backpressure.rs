@@ -0,0 +1,70 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use simples3_core::S3Error;
+use simples3_core::s3::request::parse_s3_operation;
+
+use crate::AppState;
+use crate::load::TransferGuard;
+use crate::router::url_query_pairs;
+
+/// Count the request as in flight, and turn it away with `503 SlowDown`
+/// while more request body bytes are buffered than the high-watermark, or
+/// when it transfers object data and `max_concurrent_transfers` are already
+/// in flight. A transfer holds its slot until the response body is sent.
+/// The server's `LoadTracker` is added to the request extensions so body
+/// reads can account for what they buffer.
+pub async fn backpressure_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request<Body>,
+    next: Next,
+) -> Response {
+    let watermark = state.config.buffered_bytes_high_watermark;
+    if watermark > 0 && state.load.buffered_bytes() > watermark {
+        metrics::counter!(crate::metrics::SLOWDOWN_TOTAL, "reason" => "buffered_bytes").increment(1);
+        return S3Error::SlowDown.into_response();
+    }
+
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(url_query_pairs)
+        .unwrap_or_default();
+    let operation = parse_s3_operation(request.method(), request.uri().path(), &query);
+    let limit = state.config.max_concurrent_transfers;
+    let transfer = match &operation {
+        Some(op) if limit > 0 && op.is_transfer() => match state.load.transfer_started(limit) {
+            Some(guard) => Some(guard),
+            None => {
+                metrics::counter!(crate::metrics::SLOWDOWN_TOTAL, "reason" => "transfers").increment(1);
+                return S3Error::SlowDown.into_response();
+            }
+        },
+        _ => None,
+    };
+    let name = operation.map(|op| op.name()).unwrap_or("Unknown");
+
+    let _in_flight = state.load.request_started(name);
+    request.extensions_mut().insert(state.load.clone());
+    let response = next.run(request).await;
+    match transfer {
+        Some(guard) => hold_until_sent(response, guard),
+        None => response,
+    }
+}
+
+/// Keep `guard` until the response body has been sent, or dropped with the
+/// connection.
+fn hold_until_sent(response: Response, guard: TransferGuard) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = body.into_data_stream().map(move |chunk| {
+        let _ = &guard;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
@@ -0,0 +1,96 @@
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use futures_util::StreamExt;
+use http::HeaderValue;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use simples3_core::S3Error;
+use simples3_core::s3::request::parse_s3_operation;
+
+use crate::AppState;
+use crate::connections::{ActiveGuard, RequestDetails};
+use crate::middleware::request_id::RequestId;
+use crate::router::url_query_pairs;
+
+/// List the request as in flight until its response has been sent, counting
+/// the body bytes in both directions, and stop it when it is cancelled
+/// through the admin API. Must run after host rewriting so the path is
+/// path-style.
+pub async fn connections_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(url_query_pairs)
+        .unwrap_or_default();
+    let operation = parse_s3_operation(request.method(), request.uri().path(), &query);
+    let path = request.uri().path().trim_start_matches('/');
+    let (bucket, key) = match path.split_once('/') {
+        Some((bucket, key)) => (Some(bucket.to_string()), Some(key.to_string()).filter(|k| !k.is_empty())),
+        None => (Some(path.to_string()).filter(|b| !b.is_empty()), None),
+    };
+    let guard = state.connections.register(RequestDetails {
+        id: RequestId::of(&request).id,
+        remote_ip: request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ci| ci.0.ip().to_string()),
+        method: request.method().to_string(),
+        operation: operation.map(|op| op.name()).unwrap_or("Unknown"),
+        bucket,
+        key,
+    });
+
+    let active = guard.request().clone();
+    let (parts, body) = request.into_parts();
+    let received = active.clone();
+    let body = body.into_data_stream().inspect(move |chunk| {
+        if let Ok(chunk) = chunk {
+            received.received(chunk.len() as u64);
+        }
+    });
+    let request = Request::from_parts(parts, Body::from_stream(body));
+
+    tokio::select! {
+        response = next.run(request) => send_until_cancelled(response, guard),
+        _ = active.cancelled() => {
+            tracing::warn!("Request cancelled via admin API");
+            let mut response = S3Error::ServiceUnavailable.into_response();
+            response
+                .headers_mut()
+                .insert(http::header::CONNECTION, HeaderValue::from_static("close"));
+            response
+        }
+    }
+}
+
+/// Count the response body as it is sent, and fail it, which drops the
+/// connection, if the request is cancelled before it ends.
+fn send_until_cancelled(response: Response, guard: ActiveGuard) -> Response {
+    let (parts, body) = response.into_parts();
+    let stream = futures_util::stream::unfold(Some((body.into_data_stream(), guard)), |state| async move {
+        let (mut body, guard) = state?;
+        let active = guard.request().clone();
+        tokio::select! {
+            chunk = body.next() => {
+                let chunk = chunk?;
+                if let Ok(bytes) = &chunk {
+                    active.sent(bytes.len() as u64);
+                }
+                Some((chunk, Some((body, guard))))
+            }
+            _ = active.cancelled() => {
+                tracing::warn!("Response cancelled via admin API");
+                Some((Err(axum::Error::new("request cancelled")), None))
+            }
+        }
+    });
+    Response::from_parts(parts, Body::from_stream(stream))
+}
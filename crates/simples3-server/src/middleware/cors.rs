@@ -100,10 +100,10 @@ pub async fn cors_middleware(
                                 headers.insert("access-control-expose-headers", v);
                             }
                         }
-                        if let Some(max_age) = rule.max_age_seconds {
-                            if let Ok(v) = HeaderValue::from_str(&max_age.to_string()) {
-                                headers.insert("access-control-max-age", v);
-                            }
+                        if let Some(max_age) = rule.max_age_seconds
+                            && let Ok(v) = HeaderValue::from_str(&max_age.to_string())
+                        {
+                            headers.insert("access-control-max-age", v);
                         }
                         return response;
                     }
@@ -161,7 +161,7 @@ fn build_global_preflight_response(
     let mut response = StatusCode::OK.into_response();
     let headers = response.headers_mut();
 
-    match &state.config.cors_origins {
+    match &state.runtime_config.tunables().cors_origins {
         Some(origins) => {
             if origins.iter().any(|o| origin_matches(o, origin)) {
                 if let Ok(v) = HeaderValue::from_str(origin) {
@@ -192,7 +192,7 @@ fn build_global_preflight_response(
 
 fn apply_global_cors_headers(state: &AppState, response: &mut Response, origin: &str) {
     let headers = response.headers_mut();
-    match &state.config.cors_origins {
+    match &state.runtime_config.tunables().cors_origins {
         Some(origins) => {
             if origins.iter().any(|o| origin_matches(o, origin)) {
                 if let Ok(v) = HeaderValue::from_str(origin) {
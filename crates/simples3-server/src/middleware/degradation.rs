@@ -0,0 +1,46 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use simples3_core::S3Error;
+use simples3_core::error::S3ErrorInfo;
+use simples3_core::s3::request::parse_s3_operation;
+
+use crate::AppState;
+use crate::router::url_query_pairs;
+
+/// Fail writes fast with `503 ServiceUnavailable` while the metadata store
+/// is degraded, and have the store probed when a request fails with an
+/// internal error.
+pub async fn degradation_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    if state.metadata_health.is_degraded() {
+        let query: HashMap<String, String> = request
+            .uri()
+            .query()
+            .map(url_query_pairs)
+            .unwrap_or_default();
+        let read_only = parse_s3_operation(request.method(), request.uri().path(), &query)
+            .is_some_and(|op| op.is_read_only());
+        if !read_only {
+            let mut response = S3Error::ServiceUnavailable.into_response();
+            let retry_after = state.config.metadata_probe_interval_secs.max(1);
+            response
+                .headers_mut()
+                .insert(http::header::RETRY_AFTER, http::HeaderValue::from(retry_after));
+            return response;
+        }
+    }
+
+    let response = next.run(request).await;
+    if response
+        .extensions()
+        .get::<S3ErrorInfo>()
+        .is_some_and(|info| info.code == "InternalError")
+    {
+        state.metadata_health.probe_soon();
+    }
+    response
+}
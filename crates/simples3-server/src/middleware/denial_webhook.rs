@@ -0,0 +1,74 @@
+use crate::AppState;
+use crate::security_webhook::{DenialEvent, REPORTED_CODES};
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use simples3_core::error::S3ErrorInfo;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Access key id claimed by a request, signed or presigned, SigV4 or SigV2.
+pub(crate) fn claimed_access_key(request: &Request<Body>) -> Option<String> {
+    if let Some(auth) = request.headers().get("authorization").and_then(|v| v.to_str().ok()) {
+        if let Some(rest) = auth.strip_prefix("AWS ") {
+            return rest.split(':').next().map(|s| s.to_string());
+        }
+        let credential = auth.split("Credential=").nth(1)?;
+        return credential.split('/').next().map(|s| s.to_string());
+    }
+    request.uri().query().and_then(|q| {
+        q.split('&').find_map(|pair| {
+            let (k, v) = pair.split_once('=')?;
+            match k {
+                "X-Amz-Credential" => {
+                    let v = percent_encoding::percent_decode_str(v).decode_utf8_lossy();
+                    v.split('/').next().map(|s| s.to_string())
+                }
+                "AWSAccessKeyId" => Some(v.to_string()),
+                _ => None,
+            }
+        })
+    })
+}
+
+/// Report AccessDenied / SignatureDoesNotMatch responses on buckets that have
+/// a security webhook. Must run after host rewriting so the path is path-style.
+pub async fn denial_webhook_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let access_key_id = claimed_access_key(&request);
+    let source_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip().to_string());
+
+    let response = next.run(request).await;
+
+    let Some(info) = response.extensions().get::<S3ErrorInfo>() else {
+        return response;
+    };
+    if !REPORTED_CODES.contains(&info.code.as_str()) {
+        return response;
+    }
+    let bucket = path.trim_start_matches('/').split('/').next().unwrap_or("");
+    if bucket.is_empty() || !matches!(state.metadata.get_security_webhook(bucket), Ok(Some(_))) {
+        return response;
+    }
+    state.security_webhooks.record(
+        bucket,
+        DenialEvent {
+            time: chrono::Utc::now(),
+            method,
+            path: path.clone(),
+            code: info.code.clone(),
+            access_key_id,
+            source_ip,
+        },
+    );
+    response
+}
@@ -0,0 +1,67 @@
+use axum::body::Body;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use simples3_core::error::S3ErrorInfo;
+
+use super::request_id::RequestId;
+
+/// Header that opts a request into JSON error bodies.
+pub const ERROR_FORMAT_HEADER: &str = "x-simples3-error-format";
+
+#[derive(Serialize)]
+struct JsonError {
+    code: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+/// Whether the client asked for JSON errors, either explicitly through
+/// `x-simples3-error-format: json` or with `Accept: application/json`.
+fn wants_json(request: &Request<Body>) -> bool {
+    let header = |name: &str| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_ascii_lowercase())
+    };
+    if let Some(format) = header(ERROR_FORMAT_HEADER) {
+        return format == "json";
+    }
+    header("accept").is_some_and(|accept| {
+        accept
+            .split(',')
+            .any(|part| part.split(';').next().unwrap_or("").trim() == "application/json")
+    })
+}
+
+/// Re-renders S3 error responses as JSON for diagnostic clients. Requests
+/// that don't opt in keep the standard XML body SDKs expect.
+pub async fn error_format_middleware(request: Request<Body>, next: Next) -> Response {
+    let json = wants_json(&request);
+    let request_id = request.extensions().get::<RequestId>().map(|ids| ids.id.clone());
+    let response = next.run(request).await;
+    if !json {
+        return response;
+    }
+    let Some(info) = response.extensions().get::<S3ErrorInfo>().cloned() else {
+        return response;
+    };
+
+    let (mut parts, _) = response.into_parts();
+    let body = serde_json::to_string(&JsonError {
+        code: info.code,
+        message: info.message,
+        request_id,
+    })
+    .unwrap();
+    parts.headers.remove(http::header::CONTENT_LENGTH);
+    parts.headers.insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, Body::from(body)).into_response()
+}
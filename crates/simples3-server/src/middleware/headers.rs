@@ -0,0 +1,11 @@
+use http::HeaderMap;
+
+/// Value of a header, if present and valid UTF-8.
+pub fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+/// `Content-Length` of a request or response, if present and valid.
+pub fn content_length(headers: &HeaderMap) -> Option<u64> {
+    header(headers, "content-length").and_then(|v| v.parse().ok())
+}
@@ -7,6 +7,16 @@ use axum::{
 };
 use std::sync::Arc;
 
+/// Bucket named by a virtual-host style `Host` header (`bucket.hostname`,
+/// with or without port), if it is one.
+pub fn virtual_host_bucket<'a>(hostname: &str, host: &'a str) -> Option<&'a str> {
+    let host_no_port = host.split(':').next().unwrap_or(host);
+    host_no_port
+        .strip_suffix(hostname)
+        .and_then(|b| b.strip_suffix('.'))
+        .filter(|b| !b.is_empty())
+}
+
 /// Rewrites virtual-host style requests to path-style.
 /// e.g. `Host: mybucket.s3.localhost` + `GET /mykey` → `GET /mybucket/mykey`
 pub async fn host_rewrite_middleware(
@@ -16,21 +26,15 @@ pub async fn host_rewrite_middleware(
 ) -> Response {
     let hostname = &state.config.hostname;
 
-    if let Some(host) = request.headers().get("host").and_then(|v| v.to_str().ok()) {
-        // Strip port if present
-        let host_no_port = host.split(':').next().unwrap_or(host);
-
-        // Check if host is `bucket.hostname`
-        if let Some(bucket) = host_no_port.strip_suffix(&format!(".{}", hostname)) {
-            if !bucket.is_empty() {
-                let old_path = request.uri().path().to_string();
-                let query = request.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
-                let new_path = format!("/{}{}{}", bucket, old_path, query);
+    if let Some(host) = request.headers().get("host").and_then(|v| v.to_str().ok())
+        && let Some(bucket) = virtual_host_bucket(hostname, host)
+    {
+        let old_path = request.uri().path().to_string();
+        let query = request.uri().query().map(|q| format!("?{}", q)).unwrap_or_default();
+        let new_path = format!("/{}{}{}", bucket, old_path, query);
 
-                let new_uri: http::Uri = new_path.parse().unwrap_or_else(|_| request.uri().clone());
-                *request.uri_mut() = new_uri;
-            }
-        }
+        let new_uri: http::Uri = new_path.parse().unwrap_or_else(|_| request.uri().clone());
+        *request.uri_mut() = new_uri;
     }
 
     next.run(request).await
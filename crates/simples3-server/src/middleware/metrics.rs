@@ -1,37 +1,74 @@
 use axum::body::Body;
-use axum::extract::Request;
+use axum::extract::{Request, State};
 use axum::middleware::Next;
 use axum::response::Response;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use simples3_core::s3::request::parse_s3_operation;
 
+use crate::AppState;
 use crate::router::url_query_pairs;
 
-pub async fn metrics_middleware(request: Request<Body>, next: Next) -> Response {
+/// `2xx`, `3xx`, `4xx` or `5xx`.
+fn status_class(status: u16) -> &'static str {
+    match status {
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        500..=599 => "5xx",
+        _ => "other",
+    }
+}
+
+/// Count every S3 request and record its duration by operation, bucket and
+/// status class. Runs before host rewriting, so virtual-host requests are
+/// resolved to their bucket here.
+pub async fn metrics_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
     let method = request.method().clone();
     let uri = request.uri().clone();
     let path = uri.path().to_string();
 
     let query: HashMap<String, String> = uri
         .query()
-        .map(|q| url_query_pairs(q))
+        .map(url_query_pairs)
         .unwrap_or_default();
 
     let operation_name = parse_s3_operation(&method, &path, &query)
         .map(|op| op.name())
         .unwrap_or("Unknown");
+    let bucket = request
+        .headers()
+        .get("host")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|host| super::host_rewrite::virtual_host_bucket(&state.config.hostname, host))
+        .or_else(|| path.trim_start_matches('/').split('/').next())
+        .unwrap_or_default()
+        .to_string();
 
     let start = Instant::now();
     let response = next.run(request).await;
     let duration = start.elapsed().as_secs_f64();
 
+    let status = response.status().as_u16();
+    // Failed requests can name any bucket; only label those that exist, so
+    // clients cannot grow the number of series
+    let bucket = if status < 400 || state.metadata.get_bucket(&bucket).is_ok() {
+        bucket
+    } else {
+        String::new()
+    };
+
     metrics::counter!(crate::metrics::REQUEST_COUNTER, "operation" => operation_name).increment(1);
-    metrics::histogram!(crate::metrics::REQUEST_DURATION, "operation" => operation_name)
-        .record(duration);
+    metrics::histogram!(
+        crate::metrics::REQUEST_DURATION,
+        "operation" => operation_name,
+        "bucket" => bucket,
+        "status_class" => status_class(status),
+    )
+    .record(duration);
 
-    let status = response.status().as_u16();
     if status >= 400 {
         metrics::counter!(crate::metrics::ERROR_COUNTER, "status" => status.to_string())
             .increment(1);
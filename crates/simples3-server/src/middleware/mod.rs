@@ -1,5 +1,19 @@
+pub mod access_log;
 pub mod admin_auth;
+pub mod audit;
 pub mod auth;
+pub mod backpressure;
+pub mod connections;
 pub mod cors;
+pub mod degradation;
+pub mod denial_webhook;
+pub mod error_format;
+pub mod headers;
 pub mod host_rewrite;
 pub mod metrics;
+pub mod rate_limit;
+pub mod request_id;
+pub mod request_log;
+pub mod server_access_log;
+pub mod timeout;
+pub mod usage;
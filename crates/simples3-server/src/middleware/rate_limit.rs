@@ -0,0 +1,64 @@
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use simples3_core::S3Error;
+
+use crate::AppState;
+use crate::middleware::headers::content_length;
+use crate::rate_limit::RateLimited;
+
+/// `503 SlowDown` with a `Retry-After` in whole seconds.
+fn slow_down(limited: RateLimited) -> Response {
+    metrics::counter!(crate::metrics::RATE_LIMITED_TOTAL, "scope" => limited.scope.as_str()).increment(1);
+    let mut response = S3Error::SlowDown.into_response();
+    let seconds = limited.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    response.headers_mut().insert(http::header::RETRY_AFTER, http::HeaderValue::from(seconds));
+    response
+}
+
+/// Turn the request away with `503 SlowDown` when it is over the global or
+/// per-client rate limit.
+pub async fn rate_limit_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip());
+    let acquired = state
+        .rate_limiter
+        .acquire(&state.runtime_config.tunables(), client, Instant::now());
+    if let Err(limited) = acquired {
+        tracing::debug!(client = ?client, scope = limited.scope.as_str(), "Request rate limited");
+        return slow_down(limited);
+    }
+    next.run(request).await
+}
+
+/// Run a request signed by `access_key_id` within the credential's rate
+/// limit, if it has one. Called by the auth middleware once the signature is
+/// verified; the bytes of the request and response bodies count against the
+/// credential's byte rate.
+pub async fn run_for_credential(state: &AppState, access_key_id: &str, request: Request<Body>, next: Next) -> Response {
+    let limit = match state.metadata.get_credential(access_key_id) {
+        Ok(credential) => credential.rate_limit,
+        Err(_) => None,
+    };
+    let Some(limit) = limit else {
+        return next.run(request).await;
+    };
+    let request_bytes = content_length(request.headers()).unwrap_or(0);
+    if let Err(limited) = state
+        .rate_limiter
+        .acquire_credential(access_key_id, &limit, request_bytes, Instant::now())
+    {
+        tracing::debug!(access_key_id = %access_key_id, "Request rate limited for credential");
+        return slow_down(limited);
+    }
+    let response = next.run(request).await;
+    state.rate_limiter.credential_sent(access_key_id, content_length(response.headers()).unwrap_or(0));
+    response
+}
@@ -0,0 +1,68 @@
+use axum::body::Body;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use http::HeaderValue;
+use simples3_core::error::{ErrorContext, S3ErrorInfo};
+use tracing::Instrument;
+
+/// Identifiers of one request, returned in `x-amz-request-id` and
+/// `x-amz-id-2` and reused by access logs and event notifications.
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    pub id: String,
+    pub host_id: String,
+}
+
+impl RequestId {
+    pub fn generate() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string()[..16].to_uppercase(),
+            host_id: uuid::Uuid::new_v4().simple().to_string(),
+        }
+    }
+
+    /// The ID assigned to `request`, or a fresh one outside the middleware.
+    pub fn of(request: &Request<Body>) -> Self {
+        request.extensions().get::<RequestId>().cloned().unwrap_or_else(Self::generate)
+    }
+}
+
+/// Assign every request an ID, log everything it does within a span carrying
+/// that ID, and return it on the response, errors included. XML error bodies
+/// are re-rendered with the `Resource`, `RequestId` and `HostId` elements.
+pub async fn request_id_middleware(mut request: Request<Body>, next: Next) -> Response {
+    let ids = RequestId::generate();
+    request.extensions_mut().insert(ids.clone());
+    let resource = request.uri().path().to_string();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %ids.id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    let mut response = next.run(request).instrument(span).await;
+    if let Some(info) = response.extensions().get::<S3ErrorInfo>().cloned()
+        && response
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .is_some_and(|v| v.as_bytes() == b"application/xml")
+    {
+        let context = ErrorContext {
+            resource: Some(resource),
+            request_id: Some(ids.id.clone()),
+            host_id: Some(ids.host_id.clone()),
+        };
+        let (mut parts, _) = response.into_parts();
+        parts.headers.remove(http::header::CONTENT_LENGTH);
+        response = Response::from_parts(parts, Body::from(info.to_xml(&context)));
+    }
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&ids.id) {
+        headers.insert("x-amz-request-id", value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&ids.host_id) {
+        headers.insert("x-amz-id-2", value);
+    }
+    response
+}
@@ -0,0 +1,68 @@
+use crate::AppState;
+use crate::middleware::headers::header;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use simples3_core::error::S3ErrorInfo;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Log a sample of the requests on buckets with request logging enabled, one
+/// INFO event per request. Must run after host rewriting so the path is
+/// path-style.
+pub async fn request_log_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let bucket = path.trim_start_matches('/').split('/').next().unwrap_or("").to_string();
+    if bucket.is_empty() {
+        return next.run(request).await;
+    }
+    let sample_rate = match state.metadata.get_request_log(&bucket) {
+        Ok(Some(config)) => config.sample_rate,
+        _ => return next.run(request).await,
+    };
+    if fastrand::f64() >= sample_rate {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    let user_agent = header(request.headers(), "user-agent").unwrap_or("-").to_string();
+    let content_length = header(request.headers(), "content-length").unwrap_or("-").to_string();
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip().to_string())
+        .unwrap_or_else(|| "-".into());
+    let access_key = super::denial_webhook::claimed_access_key(&request).unwrap_or_else(|| "-".into());
+
+    let response = next.run(request).await;
+
+    let error_code = response
+        .extensions()
+        .get::<S3ErrorInfo>()
+        .map(|info| info.code.clone())
+        .unwrap_or_else(|| "-".into());
+    let response_length = header(response.headers(), "content-length").unwrap_or("-");
+    tracing::info!(
+        bucket = %bucket,
+        method = %method,
+        uri = %uri,
+        status = response.status().as_u16(),
+        error_code = %error_code,
+        duration_ms = started.elapsed().as_millis() as u64,
+        remote_ip = %remote_ip,
+        access_key = %access_key,
+        user_agent = %user_agent,
+        request_bytes = %content_length,
+        response_bytes = %response_length,
+        "Request"
+    );
+    response
+}
@@ -0,0 +1,83 @@
+use crate::AppState;
+use crate::server_access_log::ServerAccessLogEntry;
+use crate::middleware::headers::{content_length, header};
+use crate::middleware::request_id::RequestId;
+use crate::router::url_query_pairs;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use simples3_core::error::S3ErrorInfo;
+use simples3_core::s3::request::parse_s3_operation;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Write every S3 request to the server access log, when enabled. Runs
+/// outside the other middleware so throttled and rejected requests are
+/// logged too; virtual-host requests are resolved to their bucket here.
+pub async fn server_access_log_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    if !state.server_access_log.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let time = chrono::Utc::now();
+    let request_id = RequestId::of(&request).id;
+    let method = request.method().clone();
+    let uri = request.uri().to_string();
+    let protocol = format!("{:?}", request.version());
+    let remote_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0.ip().to_string());
+    let requester = super::denial_webhook::claimed_access_key(&request);
+    let user_agent = header(request.headers(), "user-agent").map(str::to_string);
+    let bytes_received = content_length(request.headers()).unwrap_or(0);
+
+    let virtual_bucket = header(request.headers(), "host")
+        .and_then(|host| super::host_rewrite::virtual_host_bucket(&state.config.hostname, host))
+        .map(str::to_string);
+    let path = match &virtual_bucket {
+        Some(bucket) => format!("/{}{}", bucket, request.uri().path()),
+        None => request.uri().path().to_string(),
+    };
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(url_query_pairs)
+        .unwrap_or_default();
+    let operation = parse_s3_operation(&method, &path, &query);
+    let (bucket, key) = match path.trim_start_matches('/').split_once('/') {
+        Some((bucket, key)) => (Some(bucket.to_string()), Some(key.to_string()).filter(|k| !k.is_empty())),
+        None => (Some(path.trim_start_matches('/').to_string()).filter(|b| !b.is_empty()), None),
+    };
+
+    let response = next.run(request).await;
+
+    let error_code = response.extensions().get::<S3ErrorInfo>().map(|info| info.code.clone());
+    state.server_access_log.log(&ServerAccessLogEntry {
+        time,
+        remote_ip: remote_ip.as_deref(),
+        requester: requester.as_deref(),
+        request_id: &request_id,
+        method: method.as_str(),
+        uri: &uri,
+        protocol: &protocol,
+        bucket: bucket.as_deref(),
+        key: key.as_deref(),
+        operation: operation.as_ref().map(|op| op.name()).unwrap_or("Unknown"),
+        status: response.status().as_u16(),
+        error_code: error_code.as_deref(),
+        bytes_sent: content_length(response.headers()).unwrap_or(0),
+        bytes_received,
+        duration_ms: started.elapsed().as_millis(),
+        user_agent: user_agent.as_deref(),
+    });
+    response
+}
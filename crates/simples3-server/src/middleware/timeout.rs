@@ -0,0 +1,46 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use simples3_core::S3Error;
+use simples3_core::s3::request::parse_s3_operation;
+
+use crate::AppState;
+use crate::router::url_query_pairs;
+
+/// Fail the request with `400 RequestTimeout` when its handler does not
+/// produce a response within the read or write budget of its operation, e.g.
+/// because the client stopped sending the body. The handler is dropped, which
+/// releases what it held. A response body that is still streaming is not
+/// covered.
+pub async fn timeout_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(url_query_pairs)
+        .unwrap_or_default();
+    let operation = parse_s3_operation(request.method(), request.uri().path(), &query);
+    let read_only = operation.as_ref().is_some_and(|op| op.is_read_only());
+    let secs = if read_only {
+        state.config.read_timeout_secs
+    } else {
+        state.config.write_timeout_secs
+    };
+    if secs == 0 {
+        return next.run(request).await;
+    }
+
+    match tokio::time::timeout(Duration::from_secs(secs), next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            let name = operation.map(|op| op.name()).unwrap_or("Unknown");
+            tracing::warn!(operation = name, timeout_secs = secs, "Request timed out");
+            metrics::counter!(crate::metrics::REQUEST_TIMEOUTS_TOTAL, "operation" => name).increment(1);
+            S3Error::RequestTimeout.into_response()
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use simples3_core::s3::request::parse_s3_operation;
+
+use crate::AppState;
+use crate::router::url_query_pairs;
+use crate::usage::RequestClass;
+
+/// Count the request and its response bytes towards its bucket's usage and
+/// request rate.
+/// Must run after host rewriting so the path is path-style.
+pub async fn usage_middleware(State(state): State<Arc<AppState>>, request: Request<Body>, next: Next) -> Response {
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(url_query_pairs)
+        .unwrap_or_default();
+    let operation = parse_s3_operation(request.method(), request.uri().path(), &query);
+    let counted = operation.and_then(|op| Some((op.bucket()?.to_string(), RequestClass::of(&op))));
+
+    let response = next.run(request).await;
+    if let Some((bucket, class)) = counted {
+        state.usage.record_request(&bucket);
+        if let Some(class) = class {
+            let egress = response
+                .headers()
+                .get(http::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+            state.usage.record(&bucket, class, egress);
+        }
+    }
+    response
+}
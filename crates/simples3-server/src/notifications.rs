@@ -0,0 +1,567 @@
+use crate::AppState;
+use crate::hooks::ObjectEvent;
+use crate::middleware::auth::AuthenticatedPrincipal;
+use crate::middleware::request_id::RequestId;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request};
+use chrono::{DateTime, Utc};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use serde::Serialize;
+use simples3_core::s3::types::{NotificationRule, UsageAlert};
+use std::collections::{BTreeMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Events waiting for delivery. Once full, new events are dropped.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Object keys are URL-encoded in event records, keeping `/` as is.
+const KEY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~').remove(b'/');
+
+/// Who triggered an event, taken from the request before its body is consumed.
+#[derive(Debug, Clone, Default)]
+pub struct EventSource {
+    pub principal_id: Option<String>,
+    pub source_ip: Option<String>,
+    pub request_id: String,
+    pub host_id: String,
+    /// `x-amz-meta-*` headers sent with the request, without the prefix.
+    pub user_metadata: BTreeMap<String, String>,
+}
+
+impl EventSource {
+    pub fn from_request(request: &Request<Body>) -> Self {
+        let ids = RequestId::of(request);
+        let user_metadata = request
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.as_str().strip_prefix("x-amz-meta-")?;
+                Some((name.to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        Self {
+            principal_id: request
+                .extensions()
+                .get::<AuthenticatedPrincipal>()
+                .map(|p| p.0.clone()),
+            source_ip: request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ci| ci.0.ip().to_string()),
+            request_id: ids.id,
+            host_id: ids.host_id,
+            user_metadata,
+        }
+    }
+}
+
+/// The object an event is about. Removals carry no size or ETag.
+pub struct EventObject<'a> {
+    pub key: &'a str,
+    pub size: Option<u64>,
+    pub etag: Option<&'a str>,
+}
+
+/// Body POSTed to a notification endpoint, in the S3 event message format.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventMessage {
+    #[serde(rename = "Records")]
+    pub records: Vec<EventRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventRecord {
+    pub event_version: &'static str,
+    pub event_source: &'static str,
+    pub aws_region: String,
+    pub event_time: DateTime<Utc>,
+    pub event_name: String,
+    pub user_identity: Identity,
+    pub request_parameters: RequestParameters,
+    pub response_elements: ResponseElements,
+    pub s3: S3Entity,
+    /// The alert of a `UsageAlert:*` event.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage_alert: Option<UsageAlert>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub principal_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestParameters {
+    #[serde(rename = "sourceIPAddress")]
+    pub source_ip_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseElements {
+    #[serde(rename = "x-amz-request-id")]
+    pub request_id: String,
+    #[serde(rename = "x-amz-id-2")]
+    pub host_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Entity {
+    pub s3_schema_version: &'static str,
+    pub configuration_id: String,
+    pub bucket: BucketEntity,
+    /// Absent for bucket events such as usage alerts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object: Option<ObjectEntity>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketEntity {
+    pub name: String,
+    pub arn: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectEntity {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e_tag: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub user_metadata: BTreeMap<String, String>,
+    pub sequencer: String,
+}
+
+/// Where the events of a notification rule are published, parsed from its
+/// endpoint. Kafka and NATS targets are only available with the `kafka` and
+/// `nats` cargo features.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotificationTarget {
+    /// `http://` or `https://` URL the event message is POSTed to.
+    Webhook(String),
+    /// `kafka://broker1:9092,broker2:9092/topic`
+    #[cfg(feature = "kafka")]
+    Kafka { brokers: String, topic: String },
+    /// `nats://host:4222/subject`
+    #[cfg(feature = "nats")]
+    Nats { server: String, subject: String },
+}
+
+impl NotificationTarget {
+    pub fn parse(endpoint: &str) -> Result<Self, String> {
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            return Ok(NotificationTarget::Webhook(endpoint.to_string()));
+        }
+        let (scheme, rest) = endpoint
+            .split_once("://")
+            .ok_or_else(|| format!("Invalid notification target: {}", endpoint))?;
+        match scheme {
+            "kafka" => kafka_target(rest),
+            "nats" => nats_target(rest),
+            _ => Err(format!("Unsupported notification target scheme: {}", scheme)),
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+fn kafka_target(rest: &str) -> Result<NotificationTarget, String> {
+    let (brokers, topic) = split_target("kafka", rest)?;
+    Ok(NotificationTarget::Kafka { brokers, topic })
+}
+
+#[cfg(not(feature = "kafka"))]
+fn kafka_target(_rest: &str) -> Result<NotificationTarget, String> {
+    Err(not_enabled("kafka"))
+}
+
+#[cfg(feature = "nats")]
+fn nats_target(rest: &str) -> Result<NotificationTarget, String> {
+    let (host, subject) = split_target("nats", rest)?;
+    Ok(NotificationTarget::Nats {
+        server: format!("nats://{}", host),
+        subject,
+    })
+}
+
+#[cfg(not(feature = "nats"))]
+fn nats_target(_rest: &str) -> Result<NotificationTarget, String> {
+    Err(not_enabled("nats"))
+}
+
+/// Split `host[:port][,host...]/name` into its hosts and the topic or subject.
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn split_target(scheme: &str, rest: &str) -> Result<(String, String), String> {
+    match rest.split_once('/') {
+        Some((hosts, name)) if !hosts.is_empty() && !name.is_empty() => Ok((hosts.to_string(), name.to_string())),
+        _ => Err(format!("A {0} notification target must look like {0}://host:port/name", scheme)),
+    }
+}
+
+#[cfg(any(not(feature = "kafka"), not(feature = "nats")))]
+fn not_enabled(scheme: &str) -> String {
+    format!(
+        "{0} notification targets are not enabled in this build (cargo feature `{0}`)",
+        scheme
+    )
+}
+
+struct Delivery {
+    /// The endpoint without credentials, as reported in metrics and status.
+    endpoint: String,
+    target: NotificationTarget,
+    message: EventMessage,
+}
+
+/// Connections to notification targets, opened on first use and reused.
+struct Publishers {
+    http: reqwest::Client,
+    #[cfg(feature = "kafka")]
+    kafka: std::collections::HashMap<String, rdkafka::producer::FutureProducer>,
+    #[cfg(feature = "nats")]
+    nats: std::collections::HashMap<String, async_nats::Client>,
+}
+
+impl Publishers {
+    fn new() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build notification HTTP client"),
+            #[cfg(feature = "kafka")]
+            kafka: Default::default(),
+            #[cfg(feature = "nats")]
+            nats: Default::default(),
+        }
+    }
+
+    async fn publish(&mut self, target: &NotificationTarget, message: &EventMessage) -> Result<(), String> {
+        let body = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        match target {
+            NotificationTarget::Webhook(url) => {
+                let resp = self
+                    .http
+                    .post(url)
+                    .header("content-type", "application/json")
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if resp.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("endpoint returned {}", resp.status()))
+                }
+            }
+            #[cfg(feature = "kafka")]
+            NotificationTarget::Kafka { brokers, topic } => {
+                use rdkafka::producer::{FutureProducer, FutureRecord};
+                use std::collections::hash_map::Entry;
+                let producer = match self.kafka.entry(brokers.clone()) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => e.insert(
+                        rdkafka::ClientConfig::new()
+                            .set("bootstrap.servers", brokers)
+                            .set("message.timeout.ms", "10000")
+                            .create::<FutureProducer>()
+                            .map_err(|e| e.to_string())?,
+                    ),
+                };
+                // Keyed by bucket so a bucket's events stay ordered within a partition
+                let bucket = &message.records[0].s3.bucket.name;
+                let record = FutureRecord::to(topic).key(bucket.as_str()).payload(&body);
+                producer
+                    .send(record, Duration::from_secs(10))
+                    .await
+                    .map(|_| ())
+                    .map_err(|(e, _)| e.to_string())
+            }
+            #[cfg(feature = "nats")]
+            NotificationTarget::Nats { server, subject } => {
+                use std::collections::hash_map::Entry;
+                let client = match self.nats.entry(server.clone()) {
+                    Entry::Occupied(e) => e.into_mut(),
+                    Entry::Vacant(e) => e.insert(async_nats::connect(server.as_str()).await.map_err(|e| e.to_string())?),
+                };
+                client
+                    .publish(subject.clone(), body.into())
+                    .await
+                    .map_err(|e| e.to_string())?;
+                client.flush().await.map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// `endpoint` without user info or query string, which may hold secrets.
+fn display_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.split(['?', '#']).next().unwrap_or_default();
+    match endpoint.split_once("://") {
+        Some((scheme, rest)) => {
+            let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            format!("{}://{}{}", scheme, host, path)
+        }
+        None => endpoint.to_string(),
+    }
+}
+
+/// Delivery counts of one notification target since the server started.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TargetStatus {
+    pub endpoint: String,
+    pub delivered: u64,
+    pub failed: u64,
+    /// Events dropped because the queue was full.
+    pub dropped: u64,
+    /// Events queued or being delivered.
+    pub pending: usize,
+    pub oldest_pending: Option<DateTime<Utc>>,
+    pub last_delivered: Option<DateTime<Utc>>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationStatus {
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    pub targets: Vec<TargetStatus>,
+}
+
+#[derive(Default)]
+struct TargetState {
+    status: TargetStatus,
+    /// Queue times of the target's pending events, oldest first.
+    pending: VecDeque<DateTime<Utc>>,
+}
+
+/// Queue between request handlers and the delivery loop, so a slow endpoint
+/// never holds up the request that triggered the event.
+pub struct NotificationDispatcher {
+    sender: mpsc::Sender<Delivery>,
+    receiver: Mutex<Option<mpsc::Receiver<Delivery>>>,
+    targets: Mutex<BTreeMap<String, TargetState>>,
+}
+
+impl Default for NotificationDispatcher {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        Self {
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            targets: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl NotificationDispatcher {
+    /// Events waiting for delivery, and the counts of every target that has
+    /// been sent an event.
+    pub fn status(&self) -> NotificationStatus {
+        let targets = self.targets.lock().unwrap();
+        NotificationStatus {
+            queue_depth: targets.values().map(|t| t.pending.len()).sum(),
+            queue_capacity: QUEUE_CAPACITY,
+            targets: targets
+                .values()
+                .map(|t| TargetStatus {
+                    pending: t.pending.len(),
+                    oldest_pending: t.pending.front().copied(),
+                    ..t.status.clone()
+                })
+                .collect(),
+        }
+    }
+
+    fn enqueue(&self, delivery: Delivery, now: DateTime<Utc>) -> bool {
+        let mut targets = self.targets.lock().unwrap();
+        let target = targets.entry(delivery.endpoint.clone()).or_insert_with(|| TargetState {
+            status: TargetStatus {
+                endpoint: delivery.endpoint.clone(),
+                ..Default::default()
+            },
+            pending: VecDeque::new(),
+        });
+        let endpoint = delivery.endpoint.clone();
+        if self.sender.try_send(delivery).is_ok() {
+            target.pending.push_back(now);
+            return true;
+        }
+        target.status.dropped += 1;
+        metrics::counter!(crate::metrics::NOTIFICATIONS_TOTAL, "target" => endpoint, "result" => "dropped").increment(1);
+        false
+    }
+
+    fn delivered(&self, endpoint: &str, result: &Result<(), String>) {
+        let mut targets = self.targets.lock().unwrap();
+        let Some(target) = targets.get_mut(endpoint) else {
+            return;
+        };
+        target.pending.pop_front();
+        let now = Utc::now();
+        match result {
+            Ok(()) => {
+                target.status.delivered += 1;
+                target.status.last_delivered = Some(now);
+            }
+            Err(e) => {
+                target.status.failed += 1;
+                target.status.last_failure = Some(now);
+                target.status.last_error = Some(e.clone());
+            }
+        }
+        let result = if result.is_ok() { "delivered" } else { "failed" };
+        metrics::counter!(crate::metrics::NOTIFICATIONS_TOTAL, "target" => endpoint.to_string(), "result" => result)
+            .increment(1);
+    }
+}
+
+/// Queue `event` (e.g. `ObjectCreated:Put`) for every notification rule of the
+/// bucket that matches it, after passing it to the registered hooks. Lookup
+/// failures and a full queue are logged and otherwise ignored: the object
+/// operation has already succeeded.
+pub fn notify(state: &AppState, bucket: &str, event: &str, object: EventObject<'_>, source: &EventSource) {
+    let hook_event = ObjectEvent {
+        bucket,
+        key: object.key,
+        event,
+        size: object.size,
+        etag: object.etag,
+        source,
+    };
+    if event.starts_with("ObjectRemoved:") {
+        state.hooks.object_deleted(&hook_event);
+    } else {
+        state.hooks.object_created(&hook_event);
+    }
+
+    let now = Utc::now();
+    publish(state, bucket, event, object.key, |rule| EventRecord {
+        event_version: "2.1",
+        event_source: "aws:s3",
+        aws_region: state.config.region.clone(),
+        event_time: now,
+        event_name: event.to_string(),
+        user_identity: Identity {
+            principal_id: source.principal_id.clone().unwrap_or_else(|| "anonymous".into()),
+        },
+        request_parameters: RequestParameters {
+            source_ip_address: source.source_ip.clone(),
+        },
+        response_elements: ResponseElements {
+            request_id: source.request_id.clone(),
+            host_id: source.host_id.clone(),
+        },
+        s3: S3Entity {
+            s3_schema_version: "1.0",
+            configuration_id: rule.id.clone(),
+            bucket: BucketEntity {
+                name: bucket.to_string(),
+                arn: format!("arn:aws:s3:::{}", bucket),
+            },
+            object: Some(ObjectEntity {
+                key: percent_encoding::utf8_percent_encode(object.key, KEY_ENCODE_SET).to_string(),
+                size: object.size,
+                e_tag: object.etag.map(str::to_string),
+                user_metadata: source.user_metadata.clone(),
+                sequencer: format!("{:016X}", now.timestamp_nanos_opt().unwrap_or_default()),
+            }),
+        },
+        usage_alert: None,
+    });
+}
+
+/// Queue `event` (`UsageAlert:Raised` or `UsageAlert:Cleared`) for the
+/// notification rules of the alert's bucket that match it. Rules with a key
+/// prefix or suffix filter only receive object events.
+pub fn notify_usage_alert(state: &AppState, event: &str, alert: &UsageAlert) {
+    let bucket = &alert.bucket;
+    let now = Utc::now();
+    publish(state, bucket, event, "", |rule| EventRecord {
+        event_version: "2.1",
+        event_source: "aws:s3",
+        aws_region: state.config.region.clone(),
+        event_time: now,
+        event_name: event.to_string(),
+        user_identity: Identity {
+            principal_id: "simples3".into(),
+        },
+        request_parameters: RequestParameters { source_ip_address: None },
+        response_elements: ResponseElements {
+            request_id: String::new(),
+            host_id: String::new(),
+        },
+        s3: S3Entity {
+            s3_schema_version: "1.0",
+            configuration_id: rule.id.clone(),
+            bucket: BucketEntity {
+                name: bucket.to_string(),
+                arn: format!("arn:aws:s3:::{}", bucket),
+            },
+            object: None,
+        },
+        usage_alert: Some(alert.clone()),
+    });
+}
+
+/// Queue the record built by `record` for every notification rule of the
+/// bucket that matches `event` on `key`.
+fn publish(state: &AppState, bucket: &str, event: &str, key: &str, record: impl Fn(&NotificationRule) -> EventRecord) {
+    let config = match state.metadata.get_bucket_notification(bucket) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(bucket = %bucket, error = %e, "Failed to read notification configuration");
+            return;
+        }
+    };
+    let full_event = format!("s3:{}", event);
+    let now = Utc::now();
+    for rule in config.rules.iter().filter(|r| r.matches(&full_event, key)) {
+        let target = match NotificationTarget::parse(&rule.endpoint) {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!(bucket = %bucket, endpoint = %rule.endpoint, error = %e, "Skipping notification rule");
+                continue;
+            }
+        };
+        let delivery = Delivery {
+            endpoint: display_endpoint(&rule.endpoint),
+            target,
+            message: EventMessage { records: vec![record(rule)] },
+        };
+        if !state.notifications.enqueue(delivery, now) {
+            tracing::warn!(bucket = %bucket, event = %event, "Notification queue full, dropping event");
+        }
+    }
+}
+
+/// Deliver queued events in order. Failed deliveries are logged and dropped.
+pub async fn delivery_loop(state: Arc<AppState>) {
+    let Some(mut receiver) = state.notifications.receiver.lock().unwrap().take() else {
+        tracing::warn!("Notification delivery loop already running");
+        return;
+    };
+    let mut publishers = Publishers::new();
+    while let Some(delivery) = receiver.recv().await {
+        let bucket = &delivery.message.records[0].s3.bucket.name;
+        let result = publishers.publish(&delivery.target, &delivery.message).await;
+        state.notifications.delivered(&delivery.endpoint, &result);
+        match result {
+            Ok(()) => {
+                tracing::debug!(bucket = %bucket, endpoint = %delivery.endpoint, "Delivered event notification");
+            }
+            Err(e) => {
+                tracing::warn!(bucket = %bucket, endpoint = %delivery.endpoint, error = %e, "Notification delivery failed");
+            }
+        }
+    }
+}
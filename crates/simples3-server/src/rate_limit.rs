@@ -0,0 +1,232 @@
+//! Token-bucket rate limiting of S3 requests.
+//!
+//! Each limit is a bucket holding up to `burst` tokens, refilled at `rps`
+//! tokens per second; a request takes one token from the global bucket and
+//! one from the bucket of its client IP. When either is empty the request is
+//! turned away with `503 SlowDown` and a `Retry-After` of the time until a
+//! token is available. Limits are read from the tunable settings on every
+//! request, so only the bucket levels are kept here. At most
+//! `MAX_TRACKED_CLIENTS` client buckets are kept; a new client evicts the one
+//! that has gone longest without a request.
+//!
+//! Credentials with a rate limit get buckets of their own, checked once the
+//! request's signature is verified: one for requests and one for body bytes.
+//! The byte bucket holds a second's worth of bytes and may go into debt, so a
+//! large transfer is let through and delays the credential's next requests.
+
+use simples3_core::s3::types::CredentialRateLimit;
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::reload::Tunables;
+
+/// Client buckets tracked before the least recently used one is dropped.
+pub const MAX_TRACKED_CLIENTS: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitScope {
+    Global,
+    Client,
+    Credential,
+}
+
+impl LimitScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LimitScope::Global => "global",
+            LimitScope::Client => "client",
+            LimitScope::Credential => "credential",
+        }
+    }
+}
+
+/// A request over a limit, and how long until it would be accepted.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub scope: LimitScope,
+    pub retry_after: Duration,
+}
+
+#[derive(Clone, Copy)]
+struct Limit {
+    rps: f64,
+    burst: f64,
+}
+
+impl Limit {
+    fn new(rps: u64, burst: u64) -> Option<Self> {
+        (rps > 0).then_some(Limit {
+            rps: rps as f64,
+            burst: if burst == 0 { rps } else { burst } as f64,
+        })
+    }
+}
+
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: Limit, now: Instant) -> Self {
+        TokenBucket {
+            tokens: limit.burst,
+            updated: now,
+        }
+    }
+
+    /// Refill for the time elapsed, then the wait until a token is available.
+    fn refill(&mut self, limit: Limit, now: Instant) -> Option<Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.rps).min(limit.burst);
+        self.updated = now;
+        (self.tokens < 1.0).then(|| Duration::from_secs_f64((1.0 - self.tokens) / limit.rps))
+    }
+}
+
+#[derive(Default)]
+struct CredentialBuckets {
+    requests: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+}
+
+/// Client buckets, with their IPs ordered by last request for eviction.
+#[derive(Default)]
+struct ClientBuckets {
+    buckets: HashMap<IpAddr, TokenBucket>,
+    by_last_use: BTreeSet<(Instant, IpAddr)>,
+}
+
+impl ClientBuckets {
+    /// The bucket of `ip`, refilled up to `now`, making room for it first
+    /// when it is new and the limit of tracked clients is reached.
+    fn refill(&mut self, ip: IpAddr, limit: Limit, now: Instant) -> (&mut TokenBucket, Option<Duration>) {
+        match self.buckets.get(&ip) {
+            Some(bucket) => {
+                self.by_last_use.remove(&(bucket.updated, ip));
+            }
+            None => {
+                if self.buckets.len() >= MAX_TRACKED_CLIENTS
+                    && let Some((_, oldest)) = self.by_last_use.pop_first()
+                {
+                    self.buckets.remove(&oldest);
+                }
+            }
+        }
+        let bucket = self.buckets.entry(ip).or_insert_with(|| TokenBucket::full(limit, now));
+        let retry_after = bucket.refill(limit, now);
+        self.by_last_use.insert((bucket.updated, ip));
+        (bucket, retry_after)
+    }
+}
+
+#[derive(Default)]
+pub struct RateLimiter {
+    global: Mutex<Option<TokenBucket>>,
+    clients: Mutex<ClientBuckets>,
+    credentials: Mutex<HashMap<String, CredentialBuckets>>,
+}
+
+impl RateLimiter {
+    /// Take a token for a request from `client`, or report the limit it is
+    /// over. No token is taken from any bucket when one of them is empty.
+    pub fn acquire(&self, limits: &Tunables, client: Option<IpAddr>, now: Instant) -> Result<(), RateLimited> {
+        let global_limit = Limit::new(limits.rate_limit_rps.into(), limits.rate_limit_burst.into());
+        let client_limit = Limit::new(limits.rate_limit_per_ip_rps.into(), limits.rate_limit_per_ip_burst.into());
+
+        let mut clients = self.clients.lock().unwrap();
+        let mut client_bucket = match (client_limit, client) {
+            (Some(limit), Some(ip)) => {
+                let (bucket, retry_after) = clients.refill(ip, limit, now);
+                if let Some(retry_after) = retry_after {
+                    return Err(RateLimited {
+                        scope: LimitScope::Client,
+                        retry_after,
+                    });
+                }
+                Some(bucket)
+            }
+            _ => None,
+        };
+
+        let mut global = self.global.lock().unwrap();
+        if let Some(limit) = global_limit {
+            let bucket = global.get_or_insert_with(|| TokenBucket::full(limit, now));
+            if let Some(retry_after) = bucket.refill(limit, now) {
+                return Err(RateLimited {
+                    scope: LimitScope::Global,
+                    retry_after,
+                });
+            }
+            bucket.tokens -= 1.0;
+        }
+        if let Some(bucket) = client_bucket.as_mut() {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    /// Number of client IPs with a bucket.
+    pub fn tracked_clients(&self) -> usize {
+        self.clients.lock().unwrap().buckets.len()
+    }
+
+    /// Take a token for a request signed by `access_key_id` that uploads
+    /// `request_bytes`, or report that the credential is over its `limit`.
+    pub fn acquire_credential(
+        &self,
+        access_key_id: &str,
+        limit: &CredentialRateLimit,
+        request_bytes: u64,
+        now: Instant,
+    ) -> Result<(), RateLimited> {
+        let request_limit = limit.requests_per_sec.and_then(|rps| Limit::new(rps.into(), 0));
+        let byte_limit = limit.bytes_per_sec.and_then(|bps| Limit::new(bps, 0));
+        let over = |retry_after| RateLimited {
+            scope: LimitScope::Credential,
+            retry_after,
+        };
+
+        let mut credentials = self.credentials.lock().unwrap();
+        let buckets = credentials.entry(access_key_id.to_string()).or_default();
+        // Buckets of lifted limits are dropped, so they start full if set again
+        if request_limit.is_none() {
+            buckets.requests = None;
+        }
+        if byte_limit.is_none() {
+            buckets.bytes = None;
+        }
+        if let Some(limit) = request_limit {
+            let bucket = buckets.requests.get_or_insert_with(|| TokenBucket::full(limit, now));
+            if let Some(retry_after) = bucket.refill(limit, now) {
+                return Err(over(retry_after));
+            }
+        }
+        if let Some(limit) = byte_limit {
+            let bucket = buckets.bytes.get_or_insert_with(|| TokenBucket::full(limit, now));
+            if let Some(retry_after) = bucket.refill(limit, now) {
+                return Err(over(retry_after));
+            }
+            bucket.tokens -= request_bytes as f64;
+        }
+        if let Some(bucket) = buckets.requests.as_mut() {
+            bucket.tokens -= 1.0;
+        }
+        Ok(())
+    }
+
+    /// Count `bytes` sent to a credential with a byte rate limit.
+    pub fn credential_sent(&self, access_key_id: &str, bytes: u64) {
+        if let Some(bucket) = self
+            .credentials
+            .lock()
+            .unwrap()
+            .get_mut(access_key_id)
+            .and_then(|b| b.bytes.as_mut())
+        {
+            bucket.tokens -= bytes as f64;
+        }
+    }
+}
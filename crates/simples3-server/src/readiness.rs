@@ -0,0 +1,162 @@
+//! Liveness and readiness reporting.
+//!
+//! `/healthz` only says the process answers. `/readyz` checks what serving
+//! requests depends on: the metadata store opens and reads (and is not
+//! degraded), the data directory is writable, and none of the background
+//! loops has died. Background loops are spawned through [`Readiness::spawn`]
+//! so a panic shows up here; a loop that returns, because its feature is
+//! disabled, is reported as `stopped` and does not fail readiness. Once a
+//! shutdown signal is received the server is draining and no longer ready,
+//! so load balancers stop routing to it while in-flight requests finish.
+
+use crate::AppState;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::task::AbortHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Running,
+    /// Returned on its own, e.g. because its feature is disabled.
+    Stopped,
+    Panicked,
+}
+
+/// Shutdown state and status of the background loops.
+#[derive(Clone, Default)]
+pub struct Readiness {
+    draining: Arc<AtomicBool>,
+    tasks: Arc<Mutex<BTreeMap<&'static str, TaskStatus>>>,
+}
+
+impl Readiness {
+    /// Spawn a background loop whose status is reported by `/readyz`.
+    pub fn spawn<F>(&self, name: &'static str, task: F) -> AbortHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.lock().unwrap().insert(name, TaskStatus::Running);
+        let handle = tokio::spawn(task);
+        let abort = handle.abort_handle();
+        let tasks = self.tasks.clone();
+        tokio::spawn(async move {
+            let status = match handle.await {
+                Ok(()) => TaskStatus::Stopped,
+                Err(e) if e.is_panic() => {
+                    tracing::error!(task = name, "Background task panicked");
+                    TaskStatus::Panicked
+                }
+                // Aborted at shutdown
+                Err(_) => return,
+            };
+            tasks.lock().unwrap().insert(name, status);
+        });
+        abort
+    }
+
+    /// Stop reporting ready; called when a shutdown signal is received.
+    pub fn start_draining(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    pub fn tasks(&self) -> BTreeMap<&'static str, TaskStatus> {
+        self.tasks.lock().unwrap().clone()
+    }
+}
+
+/// One dependency checked by `/readyz`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<(), String>) -> Check {
+        let (ok, error) = match result {
+            Ok(()) => (true, None),
+            Err(e) => (false, Some(e)),
+        };
+        Check { name, ok, error }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub draining: bool,
+    pub checks: Vec<Check>,
+    pub tasks: BTreeMap<&'static str, TaskStatus>,
+}
+
+impl ReadinessReport {
+    /// Error of the first failed check, if any.
+    pub fn first_error(&self) -> Option<&str> {
+        self.checks.iter().find(|c| !c.ok).and_then(|c| c.error.as_deref())
+    }
+}
+
+/// Check every dependency of request serving.
+pub fn check(state: &AppState) -> ReadinessReport {
+    let draining = state.readiness.is_draining();
+    let tasks = state.readiness.tasks();
+    let mut checks = vec![Check::new(
+        "shutdown",
+        if draining { Err("shutting down".into()) } else { Ok(()) },
+    )];
+
+    let health = state.metadata_health.status();
+    let metadata = match health.degraded_since {
+        Some(since) => Err(format!(
+            "metadata store degraded since {}: {}",
+            since.to_rfc3339(),
+            health.last_error.unwrap_or_default()
+        )),
+        None => state
+            .metadata
+            .list_buckets()
+            .map(|_| ())
+            .map_err(|e| format!("metadata store unavailable: {}", e)),
+    };
+    checks.push(Check::new("metadata", metadata));
+
+    // Write and remove a probe file
+    let probe_path = state.config.data_dir.join(".ready-probe");
+    let data_dir = std::fs::write(&probe_path, b"probe")
+        .map(|()| {
+            let _ = std::fs::remove_file(&probe_path);
+        })
+        .map_err(|e| format!("data directory not writable: {}", e));
+    checks.push(Check::new("data_dir", data_dir));
+
+    let panicked: Vec<&str> = tasks
+        .iter()
+        .filter(|(_, status)| **status == TaskStatus::Panicked)
+        .map(|(name, _)| *name)
+        .collect();
+    checks.push(Check::new(
+        "background_tasks",
+        if panicked.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("background tasks stopped: {}", panicked.join(", ")))
+        },
+    ));
+
+    ReadinessReport {
+        ready: checks.iter().all(|c| c.ok),
+        draining,
+        checks,
+        tasks,
+    }
+}
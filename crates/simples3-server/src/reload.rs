@@ -0,0 +1,190 @@
+//! Reloading tunable settings while the server runs.
+//!
+//! On `SIGHUP` or `POST /_admin/config/reload` the configuration is rebuilt
+//! from the sources it was started with: the config file, which may have
+//! changed, the environment and the command-line flags. The settings in
+//! [`Tunables`] take effect right away, without dropping connections; other
+//! changed settings are reported as needing a restart and are ignored. With
+//! `warm_up` set, the metadata store is warmed up again after each reload.
+
+use crate::AppState;
+use serde::Serialize;
+use simples3_core::Config;
+use simples3_core::config::ConfigError;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use tokio::sync::Notify;
+use tokio::sync::futures::Notified;
+
+/// Settings that can change while the server runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Tunables {
+    pub log_level: String,
+    pub lifecycle_scan_interval_secs: u64,
+    pub rate_limit_rps: u32,
+    pub rate_limit_burst: u32,
+    pub rate_limit_per_ip_rps: u32,
+    pub rate_limit_per_ip_burst: u32,
+    pub cors_origins: Option<Vec<String>>,
+}
+
+impl From<&Config> for Tunables {
+    fn from(config: &Config) -> Self {
+        Self {
+            log_level: config.log_level.clone(),
+            lifecycle_scan_interval_secs: config.lifecycle_scan_interval_secs,
+            rate_limit_rps: config.rate_limit_rps,
+            rate_limit_burst: config.rate_limit_burst,
+            rate_limit_per_ip_rps: config.rate_limit_per_ip_rps,
+            rate_limit_per_ip_burst: config.rate_limit_per_ip_burst,
+            cors_origins: config.cors_origins.clone(),
+        }
+    }
+}
+
+impl Tunables {
+    /// Names of the settings that differ from `other`.
+    fn changes(&self, other: &Tunables) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! compare {
+            ($($field:ident),* $(,)?) => {
+                $(if self.$field != other.$field {
+                    changed.push(stringify!($field));
+                })*
+            };
+        }
+        compare!(
+            log_level,
+            lifecycle_scan_interval_secs,
+            rate_limit_rps,
+            rate_limit_burst,
+            rate_limit_per_ip_rps,
+            rate_limit_per_ip_burst,
+            cors_origins,
+        );
+        changed
+    }
+
+    /// `config` with these settings in place of its own.
+    pub fn applied_to(&self, config: &Config) -> Config {
+        let mut config = config.clone();
+        config.log_level = self.log_level.clone();
+        config.lifecycle_scan_interval_secs = self.lifecycle_scan_interval_secs;
+        config.rate_limit_rps = self.rate_limit_rps;
+        config.rate_limit_burst = self.rate_limit_burst;
+        config.rate_limit_per_ip_rps = self.rate_limit_per_ip_rps;
+        config.rate_limit_per_ip_burst = self.rate_limit_per_ip_burst;
+        config.cors_origins = self.cors_origins.clone();
+        config
+    }
+}
+
+type ConfigSource = Box<dyn Fn() -> Result<Config, ConfigError> + Send + Sync>;
+
+/// The tunable settings in effect, and how to rebuild the configuration.
+pub struct RuntimeConfig {
+    tunables: RwLock<Tunables>,
+    source: Mutex<Option<ConfigSource>>,
+    changed: Notify,
+}
+
+impl RuntimeConfig {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            tunables: RwLock::new(Tunables::from(config)),
+            source: Mutex::new(None),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Rebuild the configuration with `source` on reloads. Without one,
+    /// reloads fail.
+    pub fn set_source(&self, source: impl Fn() -> Result<Config, ConfigError> + Send + Sync + 'static) {
+        *self.source.lock().unwrap() = Some(Box::new(source));
+    }
+
+    pub fn tunables(&self) -> RwLockReadGuard<'_, Tunables> {
+        self.tunables.read().unwrap()
+    }
+
+    /// Completes at the next reload that changes a setting. Create it before
+    /// reading the settings so that no reload is missed.
+    pub fn changed(&self) -> Notified<'_> {
+        self.changed.notified()
+    }
+}
+
+/// What a reload changed.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReloadReport {
+    /// Settings now in effect with new values.
+    pub changed: Vec<&'static str>,
+    /// Changed settings that only take effect after a restart.
+    pub restart_required: Vec<String>,
+}
+
+/// Rebuild the configuration from its sources and apply the tunable settings.
+/// Nothing changes if the configuration is invalid.
+pub fn reload(state: &AppState) -> Result<ReloadReport, String> {
+    let config = {
+        let source = state.runtime_config.source.lock().unwrap();
+        let source = source.as_ref().ok_or("The configuration sources are not known")?;
+        source().map_err(|e| e.to_string())?
+    };
+    let new = Tunables::from(&config);
+    let mut tunables = state.runtime_config.tunables.write().unwrap();
+    let changed = tunables.changes(&new);
+    if changed.contains(&"log_level") {
+        state.log_level.set(&new.log_level)?;
+    }
+
+    let running = serde_json::to_value(new.applied_to(&state.config)).map_err(|e| e.to_string())?;
+    let reloaded = serde_json::to_value(&config).map_err(|e| e.to_string())?;
+    let restart_required = match (running, reloaded) {
+        (serde_json::Value::Object(running), serde_json::Value::Object(reloaded)) => reloaded
+            .into_iter()
+            .filter(|(key, value)| running.get(key) != Some(value))
+            .map(|(key, _)| key)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    *tunables = new;
+    drop(tunables);
+    if !changed.is_empty() {
+        state.runtime_config.changed.notify_waiters();
+    }
+    tracing::info!(changed = ?changed, "Configuration reloaded");
+    if !restart_required.is_empty() {
+        tracing::warn!(settings = ?restart_required, "Changed settings need a restart to take effect");
+    }
+    Ok(ReloadReport {
+        changed,
+        restart_required,
+    })
+}
+
+/// Reload on every `SIGHUP`. Does nothing on platforms without signals.
+pub async fn sighup_loop(state: Arc<AppState>) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to install SIGHUP handler");
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            tracing::info!("SIGHUP received, reloading configuration");
+            if let Err(e) = reload(&state) {
+                tracing::error!(error = %e, "Configuration reload failed, keeping current settings");
+            }
+            if state.config.warm_up {
+                crate::warm_up::run(state.metadata.clone()).await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = state;
+}
@@ -0,0 +1,270 @@
+//! On-demand image renditions (`GET /bucket/key?x-simples3-resize=200x200`).
+//!
+//! Buckets opt in through the admin API with the largest dimensions clients
+//! may ask for and a cache size. Renditions are produced by a [`Transformer`]:
+//! the built-in [`CommandTransformer`] pipes the object through an external
+//! command such as ImageMagick, and embedders can register their own. Results
+//! are cached under `<data_dir>/.renditions` until the object is overwritten
+//! or deleted, or the bucket's cache limit evicts them, oldest first.
+
+use crate::AppState;
+use crate::hooks::{Hook, ObjectEvent};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use simples3_core::s3::types::{ObjectMeta, RenditionConfig, RenditionEntry};
+use simples3_core::storage::MetadataStore;
+use simples3_core::{Config, S3Error};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+
+/// Query parameter of GetObject selecting a rendition.
+pub const RESIZE_PARAM: &str = "x-simples3-resize";
+
+/// Image data produced by a transformer.
+pub struct Rendition {
+    pub data: Vec<u8>,
+    pub content_type: String,
+}
+
+pub trait Transformer: Send + Sync {
+    /// Scale `source`, an image of `content_type`, to fit within `width` x
+    /// `height`. Runs on a blocking thread.
+    fn resize(&self, source: &[u8], content_type: &str, width: u32, height: u32) -> Result<Rendition, String>;
+}
+
+/// Runs a command with the image on stdin and reads the rendition from
+/// stdout, in the source format. `{width}` and `{height}` in the arguments
+/// are replaced by the requested dimensions.
+pub struct CommandTransformer {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandTransformer {
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let Some(command) = &config.rendition_command else {
+            return Ok(None);
+        };
+        let mut parts = command.split_whitespace().map(str::to_string);
+        let program = parts.next().ok_or("SIMPLES3_RENDITION_COMMAND is empty")?;
+        Ok(Some(CommandTransformer {
+            program,
+            args: parts.collect(),
+        }))
+    }
+}
+
+impl Transformer for CommandTransformer {
+    fn resize(&self, source: &[u8], content_type: &str, width: u32, height: u32) -> Result<Rendition, String> {
+        let args = self.args.iter().map(|arg| {
+            arg.replace("{width}", &width.to_string())
+                .replace("{height}", &height.to_string())
+        });
+        let mut child = Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", self.program, e))?;
+        let mut stdin = child.stdin.take().ok_or("No stdin")?;
+        // Feed stdin from another thread so a command writing before it has
+        // read everything cannot deadlock
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || {
+                let _ = stdin.write_all(source);
+            });
+            child.wait_with_output()
+        })
+        .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(Rendition {
+            data: output.stdout,
+            content_type: content_type.to_string(),
+        })
+    }
+}
+
+/// The transformer in use, if renditions are enabled on this server.
+#[derive(Default)]
+pub struct Renditions {
+    transformer: RwLock<Option<Arc<dyn Transformer>>>,
+}
+
+impl Renditions {
+    fn transformer(&self) -> Option<Arc<dyn Transformer>> {
+        self.transformer.read().unwrap().clone()
+    }
+}
+
+/// Serve renditions with `transformer`, and register the hook dropping
+/// cached renditions of overwritten and deleted objects.
+pub fn enable(state: &AppState, transformer: impl Transformer + 'static) {
+    *state.renditions.transformer.write().unwrap() = Some(Arc::new(transformer));
+    state.hooks.register(CacheInvalidation {
+        metadata: state.metadata.clone(),
+        root: cache_root(&state.config),
+    });
+}
+
+struct CacheInvalidation {
+    metadata: MetadataStore,
+    root: PathBuf,
+}
+
+impl CacheInvalidation {
+    fn invalidate(&self, bucket: &str, key: &str) {
+        let Ok(entries) = self.metadata.get_renditions(bucket, key) else {
+            return;
+        };
+        if entries.is_empty() {
+            return;
+        }
+        for entry in &entries {
+            let _ = std::fs::remove_file(cache_path(&self.root, bucket, key, entry.width, entry.height));
+        }
+        if let Err(e) = self.metadata.put_renditions(bucket, key, &[]) {
+            tracing::warn!(bucket = %bucket, key = %key, error = %e, "Failed to drop cached renditions");
+        }
+    }
+}
+
+impl Hook for CacheInvalidation {
+    fn on_object_created(&self, event: &ObjectEvent<'_>) {
+        self.invalidate(event.bucket, event.key);
+    }
+
+    fn on_object_deleted(&self, event: &ObjectEvent<'_>) {
+        self.invalidate(event.bucket, event.key);
+    }
+}
+
+fn cache_root(config: &Config) -> PathBuf {
+    config.data_dir.join(".renditions")
+}
+
+/// Cache file of a rendition. Keys are hashed so any key maps to one flat name.
+fn cache_path(root: &Path, bucket: &str, key: &str, width: u32, height: u32) -> PathBuf {
+    let name = hex::encode(Sha256::digest(key.as_bytes()));
+    root.join(bucket).join(format!("{}-{}x{}", name, width, height))
+}
+
+/// Parse a `WIDTHxHEIGHT` rendition size within the bucket's limits.
+fn parse_size(spec: &str, config: &RenditionConfig) -> Result<(u32, u32), S3Error> {
+    let invalid = || S3Error::InvalidArgument(format!("Invalid {} value '{}'", RESIZE_PARAM, spec));
+    let (width, height) = spec.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    if width > config.max_width || height > config.max_height {
+        return Err(S3Error::InvalidArgument(format!(
+            "Renditions of this bucket are limited to {}x{}",
+            config.max_width, config.max_height
+        )));
+    }
+    Ok((width, height))
+}
+
+/// A rendition of an object, with whether it came from the cache.
+pub struct Rendered {
+    pub meta: ObjectMeta,
+    pub width: u32,
+    pub height: u32,
+    pub rendition: Rendition,
+    pub cached: bool,
+}
+
+/// Get the `spec` rendition of an object from the cache, or generate and
+/// cache it.
+pub async fn render(state: &AppState, bucket: &str, key: &str, spec: &str) -> Result<Rendered, S3Error> {
+    let config = state
+        .metadata
+        .get_rendition_config(bucket)?
+        .ok_or_else(|| S3Error::InvalidArgument("Renditions are not enabled for this bucket".into()))?;
+    let transformer = state.renditions.transformer().ok_or(S3Error::NotImplemented)?;
+    let (width, height) = parse_size(spec, &config)?;
+    let meta = state.metadata.get_object_meta(bucket, key)?;
+    crate::handlers::object::ensure_not_quarantined(state, &meta)?;
+    if !meta.content_type.starts_with("image/") {
+        return Err(S3Error::InvalidArgument("Renditions are only generated for images".into()));
+    }
+
+    let root = cache_root(&state.config);
+    let path = cache_path(&root, bucket, key, width, height);
+    let mut entries = state.metadata.get_renditions(bucket, key)?;
+    if let Some(entry) = entries
+        .iter()
+        .find(|e| e.etag == meta.etag && e.width == width && e.height == height)
+        && let Ok(data) = tokio::fs::read(&path).await
+    {
+        metrics::counter!(crate::metrics::RENDITIONS_TOTAL, "result" => "hit").increment(1);
+        let rendition = Rendition {
+            data,
+            content_type: entry.content_type.clone(),
+        };
+        return Ok(Rendered { meta, width, height, rendition, cached: true });
+    }
+
+    let source = state.storage.read(&meta).await?;
+    let content_type = meta.content_type.clone();
+    let rendition = tokio::task::spawn_blocking(move || transformer.resize(&source, &content_type, width, height))
+        .await
+        .map_err(|e| S3Error::InternalError(e.to_string()))?
+        .map_err(|e| S3Error::InternalError(format!("Rendition failed: {}", e)))?;
+    metrics::counter!(crate::metrics::RENDITIONS_TOTAL, "result" => "generated").increment(1);
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    }
+    tokio::fs::write(&path, &rendition.data)
+        .await
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    // Renditions of older versions are dropped with the hook; keep the rest
+    entries.retain(|e| e.etag == meta.etag && (e.width, e.height) != (width, height));
+    entries.push(RenditionEntry {
+        etag: meta.etag.clone(),
+        width,
+        height,
+        size: rendition.data.len() as u64,
+        content_type: rendition.content_type.clone(),
+        created: Utc::now(),
+    });
+    state.metadata.put_renditions(bucket, key, &entries)?;
+    evict(state, &root, bucket, config.cache_limit_bytes)?;
+
+    Ok(Rendered { meta, width, height, rendition, cached: false })
+}
+
+/// Drop the oldest cached renditions of `bucket` until they fit in `limit`.
+fn evict(state: &AppState, root: &Path, bucket: &str, limit: u64) -> Result<(), S3Error> {
+    let mut cached = state.metadata.list_renditions(bucket)?;
+    let mut total: u64 = cached.iter().map(|(_, e)| e.size).sum();
+    if total <= limit {
+        return Ok(());
+    }
+    cached.sort_by_key(|(_, e)| e.created);
+    for (key, entry) in cached {
+        if total <= limit {
+            break;
+        }
+        let _ = std::fs::remove_file(cache_path(root, bucket, &key, entry.width, entry.height));
+        let mut remaining = state.metadata.get_renditions(bucket, &key)?;
+        remaining.retain(|e| (e.width, e.height) != (entry.width, entry.height));
+        state.metadata.put_renditions(bucket, &key, &remaining)?;
+        total -= entry.size;
+    }
+    Ok(())
+}
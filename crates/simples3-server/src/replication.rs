@@ -0,0 +1,218 @@
+use crate::AppState;
+use simples3_core::S3Error;
+use simples3_core::auth::sigv4;
+use simples3_core::s3::types::{ReplicationConfiguration, ReplicationTask};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Tasks copied per pass of the replication loop.
+const BATCH_SIZE: usize = 1000;
+/// Upper bound of the retry backoff after a failed copy.
+const MAX_BACKOFF_SECS: i64 = 300;
+/// Delay before an object waiting for its content scan is looked at again.
+const SCAN_WAIT_SECS: i64 = 10;
+
+/// How a replication task that did not fail ended.
+enum Outcome {
+    Done,
+    /// The object's content scan has not finished; the task is retried later.
+    AwaitingScan,
+}
+
+/// Queue a newly written object for replication when its bucket replicates
+/// it. Failures are logged: the write itself has already succeeded.
+pub fn object_written(state: &AppState, bucket: &str, key: &str) {
+    let Some(config) = replication_configuration(state, bucket) else {
+        return;
+    };
+    match passes_filters(state, &config, bucket, key) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!(bucket = %bucket, key = %key, error = %e, "Failed to evaluate replication filters");
+            return;
+        }
+    }
+    if let Err(e) = state.metadata.queue_replication(bucket, key) {
+        tracing::warn!(bucket = %bucket, key = %key, error = %e, "Failed to queue object for replication");
+    }
+}
+
+/// Queue the deletion of a key from the target when its bucket propagates
+/// deletes. Only the prefix filter applies: the tags and size of the deleted
+/// object are gone.
+pub fn object_deleted(state: &AppState, bucket: &str, key: &str) {
+    let Some(config) = replication_configuration(state, bucket) else {
+        return;
+    };
+    if !config.replicate_deletes || !config.applies_to(key) {
+        return;
+    }
+    if let Err(e) = state.metadata.queue_replication_delete(bucket, key) {
+        tracing::warn!(bucket = %bucket, key = %key, error = %e, "Failed to queue delete for replication");
+    }
+}
+
+fn replication_configuration(state: &AppState, bucket: &str) -> Option<ReplicationConfiguration> {
+    match state.metadata.get_replication_configuration(bucket) {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::warn!(bucket = %bucket, error = %e, "Failed to read replication configuration");
+            None
+        }
+    }
+}
+
+/// Whether the stored object passes the prefix, size and tag filters. The
+/// object's metadata is only read when a size or tag filter is set.
+fn passes_filters(state: &AppState, config: &ReplicationConfiguration, bucket: &str, key: &str) -> Result<bool, S3Error> {
+    if !config.applies_to(key) {
+        return Ok(false);
+    }
+    if config.min_size.is_none() && config.tags.is_empty() {
+        return Ok(true);
+    }
+    let size = state.metadata.get_object_meta(bucket, key)?.size;
+    let tags = if config.tags.is_empty() {
+        HashMap::new()
+    } else {
+        state.metadata.get_object_tagging(bucket, key)?
+    };
+    Ok(config.matches(key, size, &tags))
+}
+
+/// Copy queued objects to their bucket's replication target every
+/// `interval_secs`. Failed copies are retried with exponential backoff.
+pub async fn replication_loop(state: Arc<AppState>, interval_secs: u64) {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(300))
+        .build()
+        .expect("Failed to build replication HTTP client");
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        let tasks = match state.metadata.list_replication_tasks(chrono::Utc::now(), BATCH_SIZE) {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to list replication tasks");
+                continue;
+            }
+        };
+        for task in tasks {
+            let result = replicate(&state, &client, &task).await;
+            let update = match result {
+                Ok(Outcome::AwaitingScan) => {
+                    tracing::debug!(bucket = %task.bucket, key = %task.key, "Replication waits for content scan");
+                    state
+                        .metadata
+                        .defer_replication_task(&task, chrono::Duration::seconds(SCAN_WAIT_SECS))
+                }
+                Ok(Outcome::Done) => {
+                    tracing::debug!(bucket = %task.bucket, key = %task.key, delete = task.delete, "Replicated object");
+                    metrics::counter!(crate::metrics::REPLICATION_COMPLETED_TOTAL).increment(1);
+                    state.metadata.complete_replication_task(&task)
+                }
+                Err(error) => {
+                    let backoff = 2i64.saturating_pow(task.attempts).min(MAX_BACKOFF_SECS);
+                    metrics::counter!(crate::metrics::REPLICATION_FAILURES_TOTAL).increment(1);
+                    tracing::warn!(
+                        bucket = %task.bucket,
+                        key = %task.key,
+                        attempts = task.attempts + 1,
+                        error = %error,
+                        "Replication failed"
+                    );
+                    state
+                        .metadata
+                        .fail_replication_task(&task, &error, chrono::Duration::seconds(backoff))
+                }
+            };
+            if let Err(e) = update {
+                tracing::warn!(bucket = %task.bucket, key = %task.key, error = %e, "Failed to update replication task");
+            }
+        }
+    }
+}
+
+/// Copy one object to the target, or delete it there for a delete task.
+/// Objects deleted since they were queued, objects no longer passing the
+/// filters, quarantined objects and buckets no longer replicated complete
+/// without a copy.
+async fn replicate(state: &AppState, client: &reqwest::Client, task: &ReplicationTask) -> Result<Outcome, String> {
+    let Some(config) = state
+        .metadata
+        .get_replication_configuration(&task.bucket)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(Outcome::Done);
+    };
+    let signer = sigv4::Signer {
+        access_key: &config.access_key_id,
+        secret_key: &config.secret_access_key,
+        region: &config.region,
+    };
+    let (url, host, path) = target_location(&config, &task.key)?;
+    if task.delete {
+        if !config.replicate_deletes {
+            return Ok(Outcome::Done);
+        }
+        let headers = signer.sign("DELETE", &host, &path, &[], chrono::Utc::now());
+        let request = headers
+            .into_iter()
+            .fold(client.delete(url), |request, (name, value)| request.header(name, value));
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+        // Already gone from the target is as good as deleted
+        if !resp.status().is_success() && resp.status() != reqwest::StatusCode::NOT_FOUND {
+            return Err(format!("DELETE returned {}", resp.status()));
+        }
+        return Ok(Outcome::Done);
+    }
+
+    let meta = match state.metadata.get_object_meta(&task.bucket, &task.key) {
+        Ok(m) => m,
+        Err(S3Error::NoSuchKey) => return Ok(Outcome::Done),
+        Err(e) => return Err(e.to_string()),
+    };
+    // The object may have been retagged since it was queued
+    if !passes_filters(state, &config, &task.bucket, &task.key).map_err(|e| e.to_string())? {
+        return Ok(Outcome::Done);
+    }
+    // Only content that could be read from this server is shipped
+    if let Some(scan) = state.metadata.get_object_scan(&task.bucket, &task.key).map_err(|e| e.to_string())?
+        && scan.is_pending(&meta)
+    {
+        return Ok(Outcome::AwaitingScan);
+    }
+    if let Err(e) = crate::handlers::object::ensure_not_quarantined(state, &meta) {
+        tracing::debug!(bucket = %task.bucket, key = %task.key, error = %e, "Not replicating quarantined object");
+        return Ok(Outcome::Done);
+    }
+    let data = state.storage.read(&meta).await.map_err(|e| e.to_string())?;
+
+    let headers = signer.sign("PUT", &host, &path, &data, chrono::Utc::now());
+    let request = headers
+        .into_iter()
+        .fold(client.put(url), |request, (name, value)| request.header(name, value))
+        .header("content-type", meta.content_type);
+    let resp = request.body(data).send().await.map_err(|e| e.to_string())?;
+    if !resp.status().is_success() {
+        return Err(format!("PUT returned {}", resp.status()));
+    }
+    Ok(Outcome::Done)
+}
+
+/// URL, signed host and path of the target copy of `key`. Keys are stored as
+/// they appeared in the request path, so they are sent unchanged.
+fn target_location(config: &ReplicationConfiguration, key: &str) -> Result<(reqwest::Url, String, String), String> {
+    let endpoint = reqwest::Url::parse(&config.endpoint).map_err(|e| e.to_string())?;
+    let host = match (endpoint.host_str(), endpoint.port()) {
+        (Some(h), Some(p)) => format!("{}:{}", h, p),
+        (Some(h), None) => h.to_string(),
+        (None, _) => return Err("Replication endpoint has no host".into()),
+    };
+    let base = endpoint.path().trim_end_matches('/');
+    let path = format!("{}/{}/{}", base, config.target_bucket, key);
+    let url = endpoint.join(&path).map_err(|e| e.to_string())?;
+    Ok((url, host, path))
+}
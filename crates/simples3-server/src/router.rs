@@ -9,7 +9,7 @@ use axum::{
     extract::{Request, State},
     middleware as axum_mw,
     response::Response,
-    routing::{delete, get, put},
+    routing::{delete, get, post, put},
 };
 use simples3_core::s3::request::{parse_s3_operation, S3Operation};
 use std::collections::HashMap;
@@ -41,10 +41,28 @@ async fn s3_dispatcher(
 
     tracing::debug!(?operation, "Dispatching S3 operation");
 
+    // Frozen buckets only serve reads until thawed
+    if !operation.is_read_only()
+        && let Some(bucket) = operation.bucket()
+        && state.metadata.get_bucket(bucket).is_ok_and(|b| b.frozen.is_some())
+    {
+        return simples3_core::S3Error::BucketFrozen.into_response();
+    }
+
+    let principal = request
+        .extensions()
+        .get::<crate::middleware::auth::AuthenticatedPrincipal>()
+        .map(|p| p.0.clone());
+    let principal = principal.as_deref();
+
+    if let Some(website) = request.extensions().get::<crate::middleware::auth::WebsiteRequest>().cloned() {
+        return handlers::website::serve_website(state, website, request.headers()).await;
+    }
+
     match operation {
-        S3Operation::ListBuckets => handlers::bucket::list_buckets(state).await,
+        S3Operation::ListBuckets => handlers::bucket::list_buckets(state, principal, &query).await,
         S3Operation::CreateBucket { bucket } => {
-            handlers::bucket::create_bucket(state, &bucket).await
+            handlers::bucket::create_bucket(state, &bucket, request.headers()).await
         }
         S3Operation::DeleteBucket { bucket } => {
             handlers::bucket::delete_bucket(state, &bucket).await
@@ -57,7 +75,7 @@ async fn s3_dispatcher(
                 .extensions()
                 .get::<crate::middleware::auth::AnonymousPublicListOnly>()
                 .is_some();
-            handlers::object::list_objects_v2(state, &bucket, &query, public_only).await
+            handlers::object::list_objects_v2(state, &bucket, &query, principal, public_only).await
         }
         S3Operation::PutObject { bucket, key } => {
             if request.headers().contains_key("x-amz-copy-source") {
@@ -67,16 +85,23 @@ async fn s3_dispatcher(
             }
         }
         S3Operation::GetObject { bucket, key } => {
-            handlers::object::get_object(state, &bucket, &key).await
+            if let Some(spec) = query.get(crate::renditions::RESIZE_PARAM) {
+                return handlers::object::get_object_rendition(state, &bucket, &key, spec, principal).await;
+            }
+            if query.contains_key(handlers::object::PARTS_PARAM) {
+                return handlers::object::get_object_parts(state, &bucket, &key).await;
+            }
+            handlers::object::get_object(state, &bucket, &key, principal, request.headers()).await
         }
         S3Operation::HeadObject { bucket, key } => {
-            handlers::object::head_object(state, &bucket, &key).await
+            handlers::object::head_object(state, &bucket, &key, principal, request.headers()).await
         }
         S3Operation::DeleteObject { bucket, key } => {
-            handlers::object::delete_object(state, &bucket, &key).await
+            let source = crate::notifications::EventSource::from_request(&request);
+            handlers::object::delete_object(state, &bucket, &key, request.headers(), &source).await
         }
         S3Operation::CreateMultipartUpload { bucket, key } => {
-            handlers::multipart::create_multipart_upload(state, &bucket, &key).await
+            handlers::multipart::create_multipart_upload(state, &bucket, &key, request.headers()).await
         }
         S3Operation::UploadPart {
             bucket,
@@ -123,32 +148,80 @@ async fn s3_dispatcher(
         S3Operation::GetObjectAcl { bucket, key } => {
             handlers::object::get_object_acl(state, &bucket, &key).await
         }
+        S3Operation::PutBucketAcl { bucket } => {
+            handlers::bucket::put_bucket_acl(state, &bucket, request.headers()).await
+        }
+        S3Operation::GetBucketAcl { bucket } => {
+            handlers::bucket::get_bucket_acl(state, &bucket).await
+        }
+        S3Operation::PutObjectLockConfiguration { bucket } => {
+            handlers::object_lock::put_object_lock_configuration(state, &bucket, request).await
+        }
+        S3Operation::GetObjectLockConfiguration { bucket } => {
+            handlers::object_lock::get_object_lock_configuration(state, &bucket).await
+        }
+        S3Operation::PutObjectRetention { bucket, key } => {
+            handlers::object_lock::put_object_retention(state, &bucket, &key, request).await
+        }
+        S3Operation::GetObjectRetention { bucket, key } => {
+            handlers::object_lock::get_object_retention(state, &bucket, &key).await
+        }
+        S3Operation::PutObjectLegalHold { bucket, key } => {
+            handlers::object_lock::put_object_legal_hold(state, &bucket, &key, request).await
+        }
+        S3Operation::GetObjectLegalHold { bucket, key } => {
+            handlers::object_lock::get_object_legal_hold(state, &bucket, &key).await
+        }
+        S3Operation::RestoreObject { bucket, key } => {
+            handlers::object::restore_object(state, &bucket, &key, request).await
+        }
         S3Operation::PutBucketLifecycleConfiguration { bucket } => {
-            handlers::lifecycle::put_lifecycle_configuration(state, &bucket, request).await
+            handlers::lifecycle::put_lifecycle_configuration(state, &bucket, principal, request).await
         }
         S3Operation::GetBucketLifecycleConfiguration { bucket } => {
             handlers::lifecycle::get_lifecycle_configuration(state, &bucket).await
         }
         S3Operation::DeleteBucketLifecycleConfiguration { bucket } => {
-            handlers::lifecycle::delete_lifecycle_configuration(state, &bucket).await
+            handlers::lifecycle::delete_lifecycle_configuration(state, &bucket, principal).await
         }
         S3Operation::PutBucketPolicy { bucket } => {
-            handlers::policy::put_bucket_policy(state, &bucket, request).await
+            handlers::policy::put_bucket_policy(state, &bucket, principal, request).await
         }
         S3Operation::GetBucketPolicy { bucket } => {
             handlers::policy::get_bucket_policy(state, &bucket).await
         }
         S3Operation::DeleteBucketPolicy { bucket } => {
-            handlers::policy::delete_bucket_policy(state, &bucket).await
+            handlers::policy::delete_bucket_policy(state, &bucket, principal).await
         }
         S3Operation::PutBucketCors { bucket } => {
-            handlers::cors::put_bucket_cors(state, &bucket, request).await
+            handlers::cors::put_bucket_cors(state, &bucket, principal, request).await
         }
         S3Operation::GetBucketCors { bucket } => {
             handlers::cors::get_bucket_cors(state, &bucket).await
         }
         S3Operation::DeleteBucketCors { bucket } => {
-            handlers::cors::delete_bucket_cors(state, &bucket).await
+            handlers::cors::delete_bucket_cors(state, &bucket, principal).await
+        }
+        S3Operation::PutBucketLogging { bucket } => {
+            handlers::bucket_logging::put_bucket_logging(state, &bucket, request).await
+        }
+        S3Operation::GetBucketLogging { bucket } => {
+            handlers::bucket_logging::get_bucket_logging(state, &bucket).await
+        }
+        S3Operation::PutBucketNotification { bucket } => {
+            handlers::notification::put_bucket_notification(state, &bucket, request).await
+        }
+        S3Operation::GetBucketNotification { bucket } => {
+            handlers::notification::get_bucket_notification(state, &bucket).await
+        }
+        S3Operation::PutBucketWebsite { bucket } => {
+            handlers::website::put_bucket_website(state, &bucket, principal, request).await
+        }
+        S3Operation::GetBucketWebsite { bucket } => {
+            handlers::website::get_bucket_website(state, &bucket).await
+        }
+        S3Operation::DeleteBucketWebsite { bucket } => {
+            handlers::website::delete_bucket_website(state, &bucket, principal).await
         }
     }
 }
@@ -184,17 +257,65 @@ pub fn build_s3_router(state: Arc<AppState>) -> Router {
             state.clone(),
             auth_middleware,
         ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::denial_webhook::denial_webhook_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::request_log::request_log_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::access_log::access_log_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::usage::usage_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::degradation::degradation_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::connections::connections_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::backpressure::backpressure_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::timeout::timeout_middleware,
+        ))
+        // Before timeouts and backpressure, so virtual-host requests are classified by their path-style form
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             host_rewrite_middleware,
         ))
-        .layer(axum_mw::from_fn(
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::rate_limit::rate_limit_middleware,
+        ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
             crate::middleware::metrics::metrics_middleware,
         ))
+        .layer(axum_mw::from_fn(
+            crate::middleware::error_format::error_format_middleware,
+        ))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             crate::middleware::cors::cors_middleware,
         ))
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::server_access_log::server_access_log_middleware,
+        ))
+        .layer(axum_mw::from_fn(
+            crate::middleware::request_id::request_id_middleware,
+        ))
         .with_state(state)
 }
 
@@ -206,6 +327,33 @@ pub fn build_admin_router(state: Arc<AppState>) -> Router {
             put(handlers::admin::admin_create_bucket)
                 .delete(handlers::admin::admin_delete_bucket),
         )
+        .route(
+            "/buckets/{name}/policy",
+            get(handlers::admin::admin_get_bucket_policy)
+                .put(handlers::admin::admin_put_bucket_policy)
+                .delete(handlers::admin::admin_delete_bucket_policy),
+        )
+        .route(
+            "/buckets/{name}/cors",
+            get(handlers::admin::admin_get_bucket_cors)
+                .put(handlers::admin::admin_put_bucket_cors)
+                .delete(handlers::admin::admin_delete_bucket_cors),
+        )
+        .route(
+            "/buckets/{name}/lifecycle",
+            get(handlers::admin::admin_get_bucket_lifecycle)
+                .put(handlers::admin::admin_put_bucket_lifecycle)
+                .delete(handlers::admin::admin_delete_bucket_lifecycle),
+        )
+        .route(
+            "/buckets/{name}/credentials",
+            get(handlers::admin::admin_list_bucket_credentials)
+                .post(handlers::admin::admin_create_bucket_credential),
+        )
+        .route(
+            "/buckets/{name}/credentials/{access_key_id}",
+            delete(handlers::admin::admin_revoke_bucket_credential),
+        )
         .route(
             "/buckets/{name}/anonymous",
             put(handlers::admin::admin_set_anonymous),
@@ -214,15 +362,206 @@ pub fn build_admin_router(state: Arc<AppState>) -> Router {
             "/buckets/{name}/anonymous-list-public",
             put(handlers::admin::admin_set_anonymous_list_public),
         )
+        .route(
+            "/buckets/{name}/anonymous-list",
+            put(handlers::admin::admin_set_anonymous_list_public),
+        )
+        .route(
+            "/buckets/{name}/deletion-protection",
+            put(handlers::admin::admin_set_deletion_protection),
+        )
+        .route(
+            "/buckets/{name}/freeze",
+            get(handlers::admin::admin_get_bucket_freeze)
+                .post(handlers::admin::admin_freeze_bucket),
+        )
+        .route(
+            "/buckets/{name}/thaw",
+            post(handlers::admin::admin_thaw_bucket),
+        )
+        .route(
+            "/buckets/{name}/scans",
+            get(handlers::admin::admin_list_bucket_scans),
+        )
+        .route(
+            "/buckets/{name}/moderation",
+            get(handlers::admin::admin_list_moderation),
+        )
+        .route(
+            "/buckets/{name}/moderation/{*key}",
+            get(handlers::admin::admin_get_moderation)
+                .put(handlers::admin::admin_put_moderation),
+        )
+        .route(
+            "/buckets/{name}/leases",
+            get(handlers::admin::admin_list_leases),
+        )
+        .route(
+            "/buckets/{name}/leases/{*key}",
+            get(handlers::admin::admin_get_lease)
+                .put(handlers::admin::admin_acquire_lease)
+                .delete(handlers::admin::admin_release_lease),
+        )
+        .route(
+            "/buckets/{name}/history",
+            get(handlers::admin::admin_list_config_history),
+        )
+        .route(
+            "/buckets/{name}/history/{version}/rollback",
+            post(handlers::admin::admin_rollback_config_change),
+        )
+        .route(
+            "/buckets/{name}/security-webhook",
+            get(handlers::admin::admin_get_security_webhook)
+                .put(handlers::admin::admin_put_security_webhook)
+                .delete(handlers::admin::admin_delete_security_webhook),
+        )
+        .route(
+            "/buckets/{name}/cache-headers",
+            get(handlers::admin::admin_get_cache_headers)
+                .put(handlers::admin::admin_put_cache_headers)
+                .delete(handlers::admin::admin_delete_cache_headers),
+        )
+        .route(
+            "/buckets/{name}/usage-alerts",
+            get(handlers::admin::admin_get_usage_alert_config)
+                .put(handlers::admin::admin_put_usage_alert_config)
+                .delete(handlers::admin::admin_delete_usage_alert_config),
+        )
+        .route(
+            "/buckets/{name}/renditions",
+            get(handlers::admin::admin_get_renditions)
+                .put(handlers::admin::admin_put_renditions)
+                .delete(handlers::admin::admin_delete_renditions),
+        )
+        .route(
+            "/buckets/{name}/request-log",
+            get(handlers::admin::admin_get_request_log)
+                .put(handlers::admin::admin_put_request_log)
+                .delete(handlers::admin::admin_delete_request_log),
+        )
+        .route(
+            "/buckets/{name}/replication",
+            get(handlers::admin::admin_get_replication)
+                .put(handlers::admin::admin_put_replication)
+                .delete(handlers::admin::admin_delete_replication),
+        )
+        .route(
+            "/buckets/{name}/default-tags",
+            get(handlers::admin::admin_get_default_tags)
+                .put(handlers::admin::admin_put_default_tags)
+                .delete(handlers::admin::admin_delete_default_tags),
+        )
+        .route(
+            "/buckets/{name}/storage-routes",
+            get(handlers::admin::admin_get_storage_routes)
+                .put(handlers::admin::admin_put_storage_routes)
+                .delete(handlers::admin::admin_delete_storage_routes),
+        )
+        .route(
+            "/buckets/{name}/concatenate",
+            post(handlers::admin::admin_concatenate_objects),
+        )
+        .route(
+            "/buckets/{name}/presigned-post",
+            post(handlers::admin::admin_presigned_post),
+        )
+        .route(
+            "/deleted-buckets",
+            get(handlers::admin::admin_list_deleted_buckets),
+        )
+        .route(
+            "/deleted-buckets/{name}",
+            delete(handlers::admin::admin_purge_deleted_bucket),
+        )
+        .route(
+            "/deleted-buckets/{name}/restore",
+            post(handlers::admin::admin_restore_deleted_bucket),
+        )
+        .route(
+            "/debug/canonical-request",
+            post(handlers::admin::admin_debug_canonical_request),
+        )
+        .route("/simulate", post(handlers::admin::admin_simulate))
+        .route("/audit", get(handlers::admin::admin_list_audit))
+        .route("/info", get(handlers::admin::admin_server_info))
+        .route("/metadata", get(handlers::admin::admin_metadata_report))
+        .route(
+            "/clock",
+            get(handlers::admin::admin_get_clock)
+                .put(handlers::admin::admin_freeze_clock)
+                .delete(handlers::admin::admin_resume_clock),
+        )
+        .route("/lifecycle/run", post(handlers::admin::admin_run_lifecycle))
+        .route("/notifications", get(handlers::admin::admin_notification_status))
+        .route("/usage", get(handlers::admin::admin_get_usage))
+        .route("/alerts", get(handlers::admin::admin_list_usage_alerts))
+        .route("/usage/reports", post(handlers::admin::admin_write_usage_report))
+        .route(
+            "/usage/report-config",
+            get(handlers::admin::admin_get_usage_report_config)
+                .put(handlers::admin::admin_put_usage_report_config)
+                .delete(handlers::admin::admin_delete_usage_report_config),
+        )
+        .route(
+            "/sync-jobs",
+            get(handlers::admin::admin_list_sync_jobs)
+                .post(handlers::admin::admin_start_sync_job),
+        )
+        .route(
+            "/sync-jobs/{id}",
+            get(handlers::admin::admin_get_sync_job)
+                .delete(handlers::admin::admin_cancel_sync_job),
+        )
+        .route("/connections", get(handlers::admin::admin_list_connections))
+        .route(
+            "/connections/{id}",
+            delete(handlers::admin::admin_cancel_connection),
+        )
+        .route(
+            "/log-level",
+            get(handlers::admin::admin_get_log_level)
+                .put(handlers::admin::admin_set_log_level),
+        )
+        .route("/config/reload", post(handlers::admin::admin_reload_config))
         .route(
             "/credentials",
             get(handlers::admin::admin_list_credentials)
                 .post(handlers::admin::admin_create_credential),
         )
+        .route(
+            "/credentials/export",
+            post(handlers::admin::admin_export_credentials),
+        )
+        .route(
+            "/credentials/import",
+            post(handlers::admin::admin_import_credentials),
+        )
         .route(
             "/credentials/{access_key_id}",
             delete(handlers::admin::admin_revoke_credential),
         )
+        .route(
+            "/credentials/{access_key_id}/permissions",
+            put(handlers::admin::admin_set_credential_permissions),
+        )
+        .route(
+            "/credentials/{access_key_id}/rate-limit",
+            put(handlers::admin::admin_set_credential_rate_limit),
+        )
+        .route(
+            "/bucket-tokens",
+            get(handlers::admin::admin_list_bucket_tokens)
+                .post(handlers::admin::admin_create_bucket_token),
+        )
+        .route(
+            "/bucket-tokens/{id}",
+            delete(handlers::admin::admin_revoke_bucket_token),
+        )
+        .layer(axum_mw::from_fn_with_state(
+            state.clone(),
+            crate::middleware::audit::audit_middleware,
+        ))
         .layer(axum_mw::from_fn_with_state(
             state.clone(),
             admin_auth_middleware,
@@ -232,8 +571,14 @@ pub fn build_admin_router(state: Arc<AppState>) -> Router {
     let observability = Router::new()
         .route("/health", get(handlers::health::health))
         .route("/ready", get(handlers::health::ready))
+        .route("/healthz", get(handlers::health::healthz))
+        .route("/readyz", get(handlers::health::readyz))
         .route("/metrics", get(handlers::health::metrics_handler))
         .with_state(state);
 
-    observability.merge(Router::new().nest("/_admin", admin_routes))
+    observability
+        .merge(Router::new().nest("/_admin", admin_routes))
+        .layer(axum_mw::from_fn(
+            crate::middleware::request_id::request_id_middleware,
+        ))
 }
@@ -0,0 +1,242 @@
+//! Content scanning of new objects.
+//!
+//! When `SIMPLES3_SCAN_ENDPOINT` is set, a [`Scanner`] hook marks every
+//! created object as pending and queues it. A background worker streams the
+//! object to clamd (`clamd://host:port`, `INSTREAM` command) or POSTs it to an
+//! `http(s)://` webhook, which answers `2xx` for clean content and `403` with
+//! the threat name as body for infected content. Infected objects are
+//! quarantined until they are overwritten or deleted: GetObject, CopyObject,
+//! image renditions, bucket sync and admin concatenation refuse them, and
+//! replication skips them. Objects still pending are replicated once their
+//! scan finishes.
+
+use crate::AppState;
+use crate::hooks::{Hook, ObjectEvent};
+use chrono::Utc;
+use simples3_core::storage::MetadataStore;
+use simples3_core::{Config, S3Error};
+use simples3_core::s3::types::{ObjectScan, ScanStatus};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Objects waiting for a scan. Once full, objects stay pending until the
+/// next restart queues them again.
+const QUEUE_CAPACITY: usize = 10_000;
+/// Attempts per object before it is marked as failed.
+const MAX_ATTEMPTS: u32 = 3;
+/// Size of the chunks streamed to clamd.
+const CLAMD_CHUNK_SIZE: usize = 64 * 1024;
+
+pub enum ScanTarget {
+    /// clamd TCP socket address.
+    Clamd(String),
+    Webhook { client: reqwest::Client, url: reqwest::Url },
+}
+
+/// Result of a completed scan.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Clean,
+    /// Threat name reported by the scanner.
+    Infected(String),
+}
+
+impl ScanTarget {
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let Some(endpoint) = &config.scan_endpoint else {
+            return Ok(None);
+        };
+        if let Some(addr) = endpoint.strip_prefix("clamd://") {
+            let addr = addr.trim_end_matches('/');
+            if addr.is_empty() {
+                return Err("Scan endpoint clamd:// URL has no address".into());
+            }
+            return Ok(Some(ScanTarget::Clamd(addr.to_string())));
+        }
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            let url = reqwest::Url::parse(endpoint).map_err(|e| format!("Invalid scan endpoint: {}", e))?;
+            let client = reqwest::Client::builder()
+                .timeout(Duration::from_secs(300))
+                .build()
+                .map_err(|e| e.to_string())?;
+            return Ok(Some(ScanTarget::Webhook { client, url }));
+        }
+        Err(format!("Unsupported scan endpoint '{}': expected clamd:// or http(s)://", endpoint))
+    }
+
+    async fn scan(
+        &self,
+        bucket: &str,
+        key: &str,
+        size: u64,
+        data: impl AsyncRead + Send + Unpin + 'static,
+    ) -> Result<Verdict, String> {
+        match self {
+            ScanTarget::Clamd(addr) => scan_clamd(addr, data).await,
+            ScanTarget::Webhook { client, url } => {
+                let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(data));
+                let resp = client
+                    .post(url.clone())
+                    .header("content-length", size)
+                    .header("x-simples3-bucket", bucket)
+                    .header("x-simples3-key", key)
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let status = resp.status();
+                if status.is_success() {
+                    return Ok(Verdict::Clean);
+                }
+                let text = resp.text().await.unwrap_or_default();
+                if status == reqwest::StatusCode::FORBIDDEN {
+                    return Ok(Verdict::Infected(text.trim().to_string()));
+                }
+                Err(format!("scanner answered {}: {}", status, text.trim()))
+            }
+        }
+    }
+}
+
+/// Stream `data` to clamd with `zINSTREAM` and parse its reply, e.g.
+/// `stream: OK` or `stream: Eicar-Signature FOUND`.
+async fn scan_clamd(addr: &str, mut data: impl AsyncRead + Unpin) -> Result<Verdict, String> {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.map_err(|e| e.to_string())?;
+    stream.write_all(b"zINSTREAM\0").await.map_err(|e| e.to_string())?;
+    let mut buf = vec![0u8; CLAMD_CHUNK_SIZE];
+    loop {
+        let n = data.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        stream.write_all(&(n as u32).to_be_bytes()).await.map_err(|e| e.to_string())?;
+        stream.write_all(&buf[..n]).await.map_err(|e| e.to_string())?;
+    }
+    stream.write_all(&0u32.to_be_bytes()).await.map_err(|e| e.to_string())?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await.map_err(|e| e.to_string())?;
+    let reply = String::from_utf8_lossy(&reply);
+    let reply = reply.trim_end_matches('\0').trim();
+    let result = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+    if result == "OK" {
+        Ok(Verdict::Clean)
+    } else if let Some(signature) = result.strip_suffix(" FOUND") {
+        Ok(Verdict::Infected(signature.to_string()))
+    } else {
+        Err(format!("clamd answered '{}'", reply))
+    }
+}
+
+/// Hook marking created objects as pending and queueing them for the scan
+/// worker.
+pub struct Scanner {
+    metadata: MetadataStore,
+    sender: mpsc::Sender<(String, String)>,
+}
+
+impl Hook for Scanner {
+    fn on_object_created(&self, event: &ObjectEvent<'_>) {
+        let scan = ObjectScan {
+            etag: event.etag.unwrap_or_default().to_string(),
+            status: ScanStatus::Pending,
+            detail: None,
+            updated: Utc::now(),
+        };
+        if let Err(e) = self.metadata.put_object_scan(event.bucket, event.key, &scan) {
+            tracing::warn!(bucket = %event.bucket, key = %event.key, error = %e, "Failed to record pending scan");
+            return;
+        }
+        if self.sender.try_send((event.bucket.to_string(), event.key.to_string())).is_err() {
+            tracing::warn!(bucket = %event.bucket, key = %event.key, "Scan queue is full; object stays pending");
+        }
+    }
+}
+
+/// Register the scanner hook and spawn the worker scanning queued objects
+/// with `target`. Objects left pending by a previous run are queued first.
+pub fn start(state: &Arc<AppState>, target: ScanTarget) -> tokio::task::JoinHandle<()> {
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    state.hooks.register(Scanner {
+        metadata: state.metadata.clone(),
+        sender,
+    });
+    tokio::spawn(scan_loop(state.clone(), target, receiver))
+}
+
+async fn scan_loop(state: Arc<AppState>, target: ScanTarget, mut receiver: mpsc::Receiver<(String, String)>) {
+    match state.metadata.list_object_scans(None, Some(ScanStatus::Pending)) {
+        Ok(pending) => {
+            for (bucket, key, _) in pending {
+                scan_object(&state, &target, &bucket, &key).await;
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Failed to list pending scans"),
+    }
+    while let Some((bucket, key)) = receiver.recv().await {
+        scan_object(&state, &target, &bucket, &key).await;
+    }
+}
+
+/// Scan the current version of an object and record the verdict, unless the
+/// object was deleted or overwritten meanwhile (the new version is queued).
+async fn scan_object(state: &AppState, target: &ScanTarget, bucket: &str, key: &str) {
+    let Ok(meta) = state.metadata.get_object_meta(bucket, key) else {
+        return;
+    };
+    let mut attempt = 0;
+    let (status, detail) = loop {
+        attempt += 1;
+        let result = match open_object(state, &meta).await {
+            Ok(data) => target.scan(bucket, key, meta.size, data).await,
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(Verdict::Clean) => break (ScanStatus::Clean, None),
+            Ok(Verdict::Infected(signature)) => break (ScanStatus::Infected, Some(signature)),
+            Err(e) if attempt >= MAX_ATTEMPTS => break (ScanStatus::Failed, Some(e)),
+            Err(e) => {
+                tracing::debug!(bucket = %bucket, key = %key, error = %e, attempt, "Scan attempt failed");
+                tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+            }
+        }
+    };
+
+    match state.metadata.get_object_meta(bucket, key) {
+        Ok(current) if current.etag == meta.etag => {}
+        _ => return,
+    }
+    metrics::counter!(crate::metrics::SCAN_RESULTS_TOTAL, "result" => status.as_str()).increment(1);
+    match status {
+        ScanStatus::Infected => {
+            tracing::warn!(bucket = %bucket, key = %key, signature = ?detail, "Quarantined infected object")
+        }
+        ScanStatus::Failed => tracing::warn!(bucket = %bucket, key = %key, error = ?detail, "Object scan failed"),
+        _ => {}
+    }
+    let scan = ObjectScan {
+        etag: meta.etag,
+        status,
+        detail,
+        updated: Utc::now(),
+    };
+    if let Err(e) = state.metadata.put_object_scan(bucket, key, &scan) {
+        tracing::warn!(bucket = %bucket, key = %key, error = %e, "Failed to record scan result");
+    }
+}
+
+async fn open_object(
+    state: &AppState,
+    meta: &simples3_core::s3::types::ObjectMeta,
+) -> Result<tokio::io::Take<tokio::fs::File>, S3Error> {
+    let (path, offset) = state.storage.locate(meta)?;
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    file.seek(std::io::SeekFrom::Start(offset))
+        .await
+        .map_err(|e| S3Error::InternalError(e.to_string()))?;
+    Ok(file.take(meta.size))
+}
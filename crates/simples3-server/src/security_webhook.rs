@@ -0,0 +1,123 @@
+use crate::AppState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Error codes reported to bucket security webhooks.
+pub const REPORTED_CODES: &[&str] = &["AccessDenied", "SignatureDoesNotMatch"];
+
+/// Sample requests kept per bucket and delivery window.
+const MAX_SAMPLES: usize = 10;
+/// Distinct access keys / source IPs counted per window before folding into "other".
+const MAX_DISTINCT: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DenialEvent {
+    pub time: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub code: String,
+    pub access_key_id: Option<String>,
+    pub source_ip: Option<String>,
+}
+
+/// Denials on one bucket aggregated over a delivery window.
+#[derive(Debug, Clone, Serialize)]
+pub struct DenialSummary {
+    pub bucket: String,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+    pub total: u64,
+    pub by_code: BTreeMap<String, u64>,
+    pub by_access_key: BTreeMap<String, u64>,
+    pub by_source_ip: BTreeMap<String, u64>,
+    pub samples: Vec<DenialEvent>,
+}
+
+fn count(map: &mut BTreeMap<String, u64>, key: &str) {
+    if map.len() >= MAX_DISTINCT && !map.contains_key(key) {
+        *map.entry("other".into()).or_insert(0) += 1;
+    } else {
+        *map.entry(key.to_string()).or_insert(0) += 1;
+    }
+}
+
+impl DenialSummary {
+    fn new(bucket: &str, now: DateTime<Utc>) -> Self {
+        Self {
+            bucket: bucket.to_string(),
+            window_start: now,
+            window_end: now,
+            total: 0,
+            by_code: BTreeMap::new(),
+            by_access_key: BTreeMap::new(),
+            by_source_ip: BTreeMap::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, event: DenialEvent) {
+        self.total += 1;
+        self.window_end = event.time;
+        count(&mut self.by_code, &event.code);
+        count(&mut self.by_access_key, event.access_key_id.as_deref().unwrap_or("anonymous"));
+        count(&mut self.by_source_ip, event.source_ip.as_deref().unwrap_or("unknown"));
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(event);
+        }
+    }
+}
+
+/// Collects denial events per bucket until the delivery loop drains them.
+#[derive(Default)]
+pub struct DenialAggregator {
+    pending: Mutex<HashMap<String, DenialSummary>>,
+}
+
+impl DenialAggregator {
+    pub fn record(&self, bucket: &str, event: DenialEvent) {
+        let mut pending = self.pending.lock().unwrap();
+        pending
+            .entry(bucket.to_string())
+            .or_insert_with(|| DenialSummary::new(bucket, event.time))
+            .add(event);
+    }
+
+    pub fn drain(&self) -> Vec<DenialSummary> {
+        let mut pending = self.pending.lock().unwrap();
+        pending.drain().map(|(_, summary)| summary).collect()
+    }
+}
+
+/// Deliver aggregated denials every `interval_secs`, at most one request per
+/// bucket per interval. Failed deliveries are logged and dropped.
+pub async fn delivery_loop(state: Arc<AppState>, interval_secs: u64) {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build webhook HTTP client");
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        for summary in state.security_webhooks.drain() {
+            let webhook = match state.metadata.get_security_webhook(&summary.bucket) {
+                Ok(Some(w)) => w,
+                _ => continue,
+            };
+            match client.post(&webhook.url).json(&summary).send().await {
+                Ok(resp) if resp.status().is_success() => {
+                    tracing::debug!(bucket = %summary.bucket, total = summary.total, "Delivered security webhook");
+                }
+                Ok(resp) => {
+                    tracing::warn!(bucket = %summary.bucket, status = %resp.status(), "Security webhook rejected delivery");
+                }
+                Err(e) => {
+                    tracing::warn!(bucket = %summary.bucket, error = %e, "Security webhook delivery failed");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Server-wide access log: one line per S3 request, in Common Log Format or
+//! JSON, written to stdout or to a file rotated by size.
+//!
+//! Unlike bucket access logging ([`crate::access_log`]), which writes S3
+//! access log objects into buckets, this log is meant for the operator's log
+//! pipeline. Lines are handed to a writer thread through a bounded queue so
+//! requests never wait on disk; lines arriving while it is full are dropped
+//! and counted.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use simples3_core::Config;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+/// Lines queued for the writer thread before new ones are dropped.
+const QUEUE_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerAccessLogFormat {
+    /// Common Log Format, followed by the quoted operation and the latency
+    /// in milliseconds.
+    Clf,
+    Json,
+}
+
+/// One request, as written to the access log.
+#[derive(Serialize)]
+pub struct ServerAccessLogEntry<'a> {
+    pub time: DateTime<Utc>,
+    pub remote_ip: Option<&'a str>,
+    pub requester: Option<&'a str>,
+    pub request_id: &'a str,
+    pub method: &'a str,
+    pub uri: &'a str,
+    pub protocol: &'a str,
+    pub bucket: Option<&'a str>,
+    pub key: Option<&'a str>,
+    pub operation: &'a str,
+    pub status: u16,
+    pub error_code: Option<&'a str>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub duration_ms: u128,
+    pub user_agent: Option<&'a str>,
+}
+
+impl ServerAccessLogEntry<'_> {
+    pub fn to_line(&self, format: ServerAccessLogFormat) -> String {
+        match format {
+            ServerAccessLogFormat::Json => serde_json::to_string(self).unwrap_or_default(),
+            ServerAccessLogFormat::Clf => format!(
+                "{} - {} {} \"{} {} {}\" {} {} \"{}\" {}",
+                self.remote_ip.unwrap_or("-"),
+                self.requester.filter(|r| !r.is_empty()).unwrap_or("-"),
+                self.time.format("[%d/%b/%Y:%H:%M:%S %z]"),
+                self.method,
+                self.uri,
+                self.protocol,
+                self.status,
+                // CLF writes `-` for an empty body
+                if self.bytes_sent == 0 { "-".to_string() } else { self.bytes_sent.to_string() },
+                self.operation,
+                self.duration_ms
+            ),
+        }
+    }
+}
+
+/// The access log of the server; logs nothing unless enabled in the config.
+#[derive(Default)]
+pub struct ServerAccessLog {
+    sink: Option<(ServerAccessLogFormat, SyncSender<String>)>,
+}
+
+impl ServerAccessLog {
+    /// Open the access log configured in `config`, if any, and start its
+    /// writer thread.
+    pub fn from_config(config: &Config) -> io::Result<Self> {
+        let Some(target) = &config.access_log else {
+            return Ok(Self::default());
+        };
+        let format = match config.access_log_format.as_str() {
+            "json" => ServerAccessLogFormat::Json,
+            _ => ServerAccessLogFormat::Clf,
+        };
+        let output = if target == "-" {
+            Output::Stdout
+        } else {
+            Output::File(RotatingFile::open(
+                PathBuf::from(target),
+                config.access_log_max_size,
+                config.access_log_max_files,
+            )?)
+        };
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        std::thread::Builder::new()
+            .name("access-log".into())
+            .spawn(move || write_lines(receiver, output))?;
+        Ok(Self {
+            sink: Some((format, sender)),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    pub fn log(&self, entry: &ServerAccessLogEntry<'_>) {
+        let Some((format, sender)) = &self.sink else {
+            return;
+        };
+        match sender.try_send(entry.to_line(*format)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                metrics::counter!(crate::metrics::ACCESS_LOG_DROPPED_TOTAL).increment(1);
+            }
+            Err(TrySendError::Disconnected(_)) => {}
+        }
+    }
+}
+
+enum Output {
+    Stdout,
+    File(RotatingFile),
+}
+
+fn write_lines(receiver: Receiver<String>, mut output: Output) {
+    for line in receiver {
+        let result = match &mut output {
+            Output::Stdout => writeln!(io::stdout().lock(), "{}", line),
+            Output::File(file) => file.write_line(&line),
+        };
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "Failed to write access log");
+        }
+    }
+}
+
+/// A log file renamed to `<path>.1` once it would grow past `max_size`,
+/// shifting older rotations up to `<path>.<max_files>`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.max_size > 0 && self.size > 0 && self.size + len > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(format!("{}\n", line).as_bytes())?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(rotated(&self.path, self.max_files));
+            for n in (1..self.max_files).rev() {
+                let from = rotated(&self.path, n);
+                if from.exists() {
+                    std::fs::rename(&from, rotated(&self.path, n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(&self.path, 1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
@@ -0,0 +1,229 @@
+use crate::AppState;
+use simples3_core::Config;
+use simples3_core::auth::sigv4;
+use simples3_core::storage::MetadataStore;
+use simples3_core::storage::snapshot::{LATEST_MANIFEST, SnapshotManifest};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Destination for shipped metadata snapshots.
+pub enum SnapshotTarget {
+    /// Local or mounted directory, e.g. one kept in sync with rsync.
+    Directory(PathBuf),
+    /// S3-compatible bucket, written with SigV4-signed PUTs.
+    S3 {
+        client: reqwest::Client,
+        endpoint: reqwest::Url,
+        bucket: String,
+        prefix: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+/// Outcome of one shipment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShipReport {
+    pub manifest: String,
+    pub files_uploaded: usize,
+    pub bytes_uploaded: u64,
+}
+
+impl SnapshotTarget {
+    /// Target configured by `SIMPLES3_SNAPSHOT_TARGET`, if any.
+    pub fn from_config(config: &Config) -> Result<Option<Self>, String> {
+        let Some(target) = &config.snapshot_target else {
+            return Ok(None);
+        };
+        let Some(location) = target.strip_prefix("s3://") else {
+            return Ok(Some(SnapshotTarget::Directory(PathBuf::from(target))));
+        };
+
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            return Err("Snapshot target s3:// URL has no bucket".into());
+        }
+        let endpoint = config
+            .snapshot_s3_endpoint
+            .as_deref()
+            .ok_or("SIMPLES3_SNAPSHOT_S3_ENDPOINT is required for an s3:// snapshot target")?;
+        let endpoint = reqwest::Url::parse(endpoint).map_err(|e| format!("Invalid snapshot S3 endpoint: {}", e))?;
+        let (Some(access_key), Some(secret_key)) =
+            (&config.snapshot_s3_access_key, &config.snapshot_s3_secret_key)
+        else {
+            return Err("SIMPLES3_SNAPSHOT_S3_ACCESS_KEY and SIMPLES3_SNAPSHOT_S3_SECRET_KEY are required for an s3:// snapshot target".into());
+        };
+        let prefix = match prefix.trim_matches('/') {
+            "" => String::new(),
+            p => format!("{}/", p),
+        };
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Some(SnapshotTarget::S3 {
+            client,
+            endpoint,
+            bucket: bucket.to_string(),
+            prefix,
+            region: config.snapshot_s3_region.clone(),
+            access_key: access_key.clone(),
+            secret_key: secret_key.clone(),
+        }))
+    }
+
+    async fn put(&self, name: &str, bytes: Vec<u8>) -> Result<(), String> {
+        match self {
+            SnapshotTarget::Directory(dir) => {
+                let path = dir.join(name);
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent).await.map_err(|e| e.to_string())?;
+                }
+                // Write then rename so a reader never sees a partial file
+                let temp = path.with_extension("tmp");
+                tokio::fs::write(&temp, bytes).await.map_err(|e| e.to_string())?;
+                tokio::fs::rename(&temp, &path).await.map_err(|e| e.to_string())
+            }
+            SnapshotTarget::S3 { client, .. } => {
+                let request = self.signed_request(client.put(self.object_url(name)?), "PUT", name, &bytes)?;
+                let resp = request.body(bytes).send().await.map_err(|e| e.to_string())?;
+                if !resp.status().is_success() {
+                    return Err(format!("PUT {} returned {}", name, resp.status()));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            SnapshotTarget::Directory(dir) => match tokio::fs::read(dir.join(name)).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(e) => Err(e.to_string()),
+            },
+            SnapshotTarget::S3 { client, .. } => {
+                let request = self.signed_request(client.get(self.object_url(name)?), "GET", name, b"")?;
+                let resp = request.send().await.map_err(|e| e.to_string())?;
+                if resp.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                if !resp.status().is_success() {
+                    return Err(format!("GET {} returned {}", name, resp.status()));
+                }
+                resp.bytes().await.map(|b| Some(b.to_vec())).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    fn object_path(&self, name: &str) -> String {
+        match self {
+            SnapshotTarget::Directory(_) => name.to_string(),
+            SnapshotTarget::S3 { bucket, prefix, .. } => format!("/{}/{}{}", bucket, prefix, name),
+        }
+    }
+
+    fn object_url(&self, name: &str) -> Result<reqwest::Url, String> {
+        match self {
+            SnapshotTarget::S3 { endpoint, .. } => endpoint
+                .join(&self.object_path(name))
+                .map_err(|e| e.to_string()),
+            SnapshotTarget::Directory(_) => Err("Directory targets have no URL".into()),
+        }
+    }
+
+    fn signed_request(
+        &self,
+        request: reqwest::RequestBuilder,
+        method: &str,
+        name: &str,
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder, String> {
+        let SnapshotTarget::S3 { endpoint, region, access_key, secret_key, .. } = self else {
+            return Ok(request);
+        };
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(h), Some(p)) => format!("{}:{}", h, p),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return Err("Snapshot S3 endpoint has no host".into()),
+        };
+        let signer = sigv4::Signer {
+            access_key,
+            secret_key,
+            region,
+        };
+        let headers = signer.sign(method, &host, &self.object_path(name), body, chrono::Utc::now());
+        Ok(headers
+            .into_iter()
+            .fold(request, |request, (name, value)| request.header(name, value)))
+    }
+}
+
+/// Files already present on the target according to its latest manifest.
+pub async fn shipped_files(target: &SnapshotTarget) -> Result<HashSet<String>, String> {
+    let Some(bytes) = target.get(LATEST_MANIFEST).await? else {
+        return Ok(HashSet::new());
+    };
+    let manifest: SnapshotManifest = serde_json::from_slice(&bytes).map_err(|e| e.to_string())?;
+    Ok(manifest.files().cloned().collect())
+}
+
+/// Take a snapshot and upload the files not yet in `shipped`, then the
+/// manifest. `latest.json` is written last, so it only ever names complete snapshots.
+pub async fn ship_once(
+    metadata: &MetadataStore,
+    target: &SnapshotTarget,
+    shipped: &mut HashSet<String>,
+) -> Result<ShipReport, String> {
+    let snapshot = metadata.create_snapshot().map_err(|e| e.to_string())?;
+    let mut report = ShipReport {
+        manifest: snapshot.manifest.file_name(),
+        files_uploaded: 0,
+        bytes_uploaded: 0,
+    };
+    for (name, bytes) in snapshot.files {
+        if shipped.contains(&name) {
+            continue;
+        }
+        report.bytes_uploaded += bytes.len() as u64;
+        target.put(&name, bytes).await?;
+        shipped.insert(name);
+        report.files_uploaded += 1;
+    }
+    let manifest = serde_json::to_vec_pretty(&snapshot.manifest).map_err(|e| e.to_string())?;
+    target.put(&report.manifest, manifest.clone()).await?;
+    target.put(LATEST_MANIFEST, manifest).await?;
+    Ok(report)
+}
+
+/// Ship a snapshot every `interval_secs`. Failures are logged and retried at
+/// the next interval.
+pub async fn shipping_loop(state: Arc<AppState>, target: SnapshotTarget, interval_secs: u64) {
+    if interval_secs == 0 {
+        tracing::info!("Snapshot shipping is disabled (interval = 0)");
+        return;
+    }
+    let mut shipped = match shipped_files(&target).await {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::warn!(error = %e, "Could not read previous snapshot manifest; shipping all files");
+            HashSet::new()
+        }
+    };
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    loop {
+        interval.tick().await;
+        match ship_once(&state.metadata, &target, &mut shipped).await {
+            Ok(report) => tracing::info!(
+                manifest = %report.manifest,
+                files = report.files_uploaded,
+                bytes = report.bytes_uploaded,
+                "Shipped metadata snapshot"
+            ),
+            Err(e) => tracing::warn!(error = %e, "Metadata snapshot shipping failed"),
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Server-side sync jobs between two local buckets, or bucket prefixes.
+//!
+//! A job started through the admin API copies every source object whose
+//! target is missing or differs in ETag or size, optionally copies tags and
+//! deletes target objects that no longer exist in the source. Jobs run in the
+//! background, one object at a time, and report their progress until they
+//! finish; they are kept in memory and do not survive a restart.
+
+use crate::AppState;
+use crate::notifications::{self, EventObject, EventSource};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use simples3_core::S3Error;
+use simples3_core::s3::types::{ListObjectsV2Request, ObjectMeta};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Failures kept in a job's report; later ones are only counted.
+const MAX_REPORTED_FAILURES: usize = 100;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncRequest {
+    pub source_bucket: String,
+    #[serde(default)]
+    pub source_prefix: String,
+    pub target_bucket: String,
+    #[serde(default)]
+    pub target_prefix: String,
+    /// Delete target objects under the target prefix missing from the source.
+    #[serde(default)]
+    pub delete: bool,
+    /// Copy the tags of source objects, including unchanged ones, instead of
+    /// applying the target bucket's default tags.
+    #[serde(default)]
+    pub copy_tags: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    Running,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncProgress {
+    /// Source objects compared so far.
+    pub scanned: u64,
+    pub copied: u64,
+    pub bytes_copied: u64,
+    /// Objects whose target already matched.
+    pub skipped: u64,
+    pub tags_updated: u64,
+    pub deleted: u64,
+    pub failed: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncFailure {
+    pub key: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncJob {
+    pub id: String,
+    pub source_bucket: String,
+    pub source_prefix: String,
+    pub target_bucket: String,
+    pub target_prefix: String,
+    pub delete: bool,
+    pub copy_tags: bool,
+    pub status: SyncStatus,
+    pub progress: SyncProgress,
+    pub failures: Vec<SyncFailure>,
+    pub started: DateTime<Utc>,
+    pub finished: Option<DateTime<Utc>>,
+    /// Why a failed job stopped.
+    pub error: Option<String>,
+}
+
+/// Sync jobs started since the server did, by ID.
+#[derive(Default)]
+pub struct SyncJobs {
+    jobs: Mutex<BTreeMap<String, Entry>>,
+}
+
+struct Entry {
+    job: SyncJob,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SyncJobs {
+    pub fn list(&self) -> Vec<SyncJob> {
+        let mut jobs: Vec<SyncJob> = self.jobs.lock().unwrap().values().map(|e| e.job.clone()).collect();
+        jobs.sort_by_key(|j| j.started);
+        jobs
+    }
+
+    pub fn get(&self, id: &str) -> Option<SyncJob> {
+        self.jobs.lock().unwrap().get(id).map(|e| e.job.clone())
+    }
+
+    /// Ask a running job to stop after the object in progress. Returns
+    /// `false` for unknown jobs.
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.jobs.lock().unwrap().get(id) {
+            Some(entry) => {
+                entry.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update(&self, id: &str, f: impl FnOnce(&mut SyncJob)) {
+        if let Some(entry) = self.jobs.lock().unwrap().get_mut(id) {
+            f(&mut entry.job);
+        }
+    }
+}
+
+/// Validate `request` and run it as a background job. Returns the job as
+/// first reported.
+pub fn start(state: &Arc<AppState>, request: SyncRequest) -> Result<SyncJob, S3Error> {
+    state.metadata.get_bucket(&request.source_bucket)?;
+    if state.metadata.get_bucket(&request.target_bucket)?.frozen.is_some() {
+        return Err(S3Error::BucketFrozen);
+    }
+    if request.source_bucket == request.target_bucket
+        && (request.source_prefix.starts_with(&request.target_prefix)
+            || request.target_prefix.starts_with(&request.source_prefix))
+    {
+        return Err(S3Error::InvalidArgument(
+            "Source and target prefixes of the same bucket must not overlap".into(),
+        ));
+    }
+
+    let job = SyncJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        source_bucket: request.source_bucket,
+        source_prefix: request.source_prefix,
+        target_bucket: request.target_bucket,
+        target_prefix: request.target_prefix,
+        delete: request.delete,
+        copy_tags: request.copy_tags,
+        status: SyncStatus::Running,
+        progress: SyncProgress::default(),
+        failures: Vec::new(),
+        started: Utc::now(),
+        finished: None,
+        error: None,
+    };
+    let cancelled = Arc::new(AtomicBool::new(false));
+    state.sync_jobs.jobs.lock().unwrap().insert(
+        job.id.clone(),
+        Entry {
+            job: job.clone(),
+            cancelled: cancelled.clone(),
+        },
+    );
+    tracing::info!(
+        job = %job.id,
+        source = %job.source_bucket,
+        target = %job.target_bucket,
+        "Starting sync job"
+    );
+
+    let state = state.clone();
+    let spec = job.clone();
+    tokio::spawn(async move {
+        let result = run(&state, &spec, &cancelled).await;
+        let status = match &result {
+            Ok(()) if cancelled.load(Ordering::Relaxed) => SyncStatus::Cancelled,
+            Ok(()) => SyncStatus::Completed,
+            Err(_) => SyncStatus::Failed,
+        };
+        state.sync_jobs.update(&spec.id, |job| {
+            job.status = status;
+            job.finished = Some(Utc::now());
+            job.error = result.err().map(|e| e.to_string());
+            tracing::info!(job = %job.id, status = ?job.status, progress = ?job.progress, "Sync job finished");
+        });
+    });
+    Ok(job)
+}
+
+fn list(state: &AppState, bucket: &str, prefix: &str) -> Result<Vec<ObjectMeta>, S3Error> {
+    let request = ListObjectsV2Request {
+        bucket: bucket.to_string(),
+        prefix: prefix.to_string(),
+        delimiter: String::new(),
+        max_keys: u32::MAX,
+        continuation_token: None,
+        start_after: None,
+    };
+    Ok(state.metadata.list_objects_v2(&request)?.contents)
+}
+
+/// Progress of one object, applied to the job once it is handled.
+enum Outcome {
+    Copied(u64),
+    Skipped { tags_updated: bool },
+    Deleted,
+}
+
+async fn run(state: &AppState, job: &SyncJob, cancelled: &AtomicBool) -> Result<(), S3Error> {
+    let target_key = |key: &str| format!("{}{}", job.target_prefix, &key[job.source_prefix.len()..]);
+    let sources = list(state, &job.source_bucket, &job.source_prefix)?;
+    let mut synced = HashSet::with_capacity(sources.len());
+
+    for source in &sources {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let key = target_key(&source.key);
+        let result = sync_object(state, job, source, &key).await;
+        record(state, job, &key, result, true);
+        synced.insert(key);
+    }
+
+    if !job.delete {
+        return Ok(());
+    }
+    for target in list(state, &job.target_bucket, &job.target_prefix)? {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        if synced.contains(&target.key) {
+            continue;
+        }
+        let result = delete_object(state, job, &target.key).await.map(|()| Outcome::Deleted);
+        record(state, job, &target.key, result, false);
+    }
+    Ok(())
+}
+
+fn record(state: &AppState, job: &SyncJob, key: &str, result: Result<Outcome, S3Error>, scanned: bool) {
+    let action = match &result {
+        Ok(Outcome::Copied(_)) => "copied",
+        Ok(Outcome::Skipped { .. }) => "skipped",
+        Ok(Outcome::Deleted) => "deleted",
+        Err(_) => "failed",
+    };
+    metrics::counter!(crate::metrics::SYNC_OBJECTS_TOTAL, "action" => action).increment(1);
+    state.sync_jobs.update(&job.id, |job| {
+        let progress = &mut job.progress;
+        if scanned {
+            progress.scanned += 1;
+        }
+        match result {
+            Ok(Outcome::Copied(size)) => {
+                progress.copied += 1;
+                progress.bytes_copied += size;
+            }
+            Ok(Outcome::Skipped { tags_updated }) => {
+                progress.skipped += 1;
+                progress.tags_updated += tags_updated as u64;
+            }
+            Ok(Outcome::Deleted) => progress.deleted += 1,
+            Err(e) => {
+                tracing::warn!(job = %job.id, key = %key, error = %e, "Failed to sync object");
+                progress.failed += 1;
+                if job.failures.len() < MAX_REPORTED_FAILURES {
+                    job.failures.push(SyncFailure {
+                        key: key.to_string(),
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+    });
+}
+
+/// Copy `source` to `key` of the target bucket unless the target already has
+/// the same ETag and size. Copies keep the source ETag, so multipart objects
+/// compare equal on the next run.
+async fn sync_object(state: &AppState, job: &SyncJob, source: &ObjectMeta, key: &str) -> Result<Outcome, S3Error> {
+    let bucket = job.target_bucket.as_str();
+    let tags = if job.copy_tags {
+        Some(state.metadata.get_object_tagging(&source.bucket, &source.key)?)
+    } else {
+        None
+    };
+
+    if let Ok(existing) = state.metadata.get_object_meta(bucket, key)
+        && existing.etag == source.etag
+        && existing.size == source.size
+    {
+        let mut tags_updated = false;
+        if let Some(tags) = tags
+            && state.metadata.get_object_tagging(bucket, key)? != tags
+        {
+            state.metadata.put_object_tagging(bucket, key, &tags)?;
+            tags_updated = true;
+        }
+        return Ok(Outcome::Skipped { tags_updated });
+    }
+
+    state.metadata.ensure_object_unlocked(bucket, key, false)?;
+    crate::handlers::object::ensure_not_quarantined(state, source)?;
+    let data = state.storage.read(source).await?;
+    let (size, _) = state.storage.for_key(bucket, key)?.write_object(bucket, key, &data).await?;
+    let meta = ObjectMeta {
+        bucket: bucket.to_string(),
+        key: key.to_string(),
+        size,
+        etag: source.etag.clone(),
+        content_type: source.content_type.clone(),
+        last_modified: Utc::now(),
+        public: source.public,
+        checksum: source.checksum.clone(),
+        legal_hold: false,
+        storage_class: None,
+    };
+    state.storage.delete_transitioned_copy(bucket, key).await?;
+    state.metadata.put_object_meta(&meta)?;
+    if let Some(layout) = state.metadata.get_part_layout(&source.bucket, &source.key)? {
+        state.metadata.put_part_layout(bucket, key, &layout)?;
+    }
+    state
+        .metadata
+        .set_uploaded_object_tagging(bucket, key, &tags.unwrap_or_default())?;
+    state.metadata.set_uploaded_object_retention(bucket, key, None)?;
+
+    let object = EventObject { key, size: Some(size), etag: Some(&meta.etag) };
+    notifications::notify(state, bucket, "ObjectCreated:Copy", object, &EventSource::default());
+    crate::replication::object_written(state, bucket, key);
+    Ok(Outcome::Copied(size))
+}
+
+async fn delete_object(state: &AppState, job: &SyncJob, key: &str) -> Result<(), S3Error> {
+    let bucket = job.target_bucket.as_str();
+    state.metadata.ensure_object_unlocked(bucket, key, false)?;
+    let store = state.storage.for_existing(bucket, key)?;
+    state.metadata.delete_object_meta(bucket, key)?;
+    store.delete_object(bucket, key).await?;
+    let object = EventObject { key, size: None, etag: None };
+    notifications::notify(state, bucket, "ObjectRemoved:Delete", object, &EventSource::default());
+    Ok(())
+}
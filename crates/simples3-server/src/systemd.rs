@@ -0,0 +1,131 @@
+//! Integration with systemd service management.
+//!
+//! Under `Type=notify` the server reports `READY=1` once its listeners are
+//! accepting and `STOPPING=1` when shutdown starts. With socket activation
+//! (`ListenStream=` in a `.socket` unit) the listeners are inherited from
+//! systemd instead of bound, so connections arriving while the service
+//! restarts wait in the socket's accept queue rather than being refused.
+//! Outside systemd all of this does nothing.
+
+use std::io;
+
+/// First file descriptor passed by systemd (`SD_LISTEN_FDS_START`).
+#[cfg(unix)]
+const LISTEN_FDS_START: i32 = 3;
+
+/// Listeners inherited through socket activation.
+///
+/// Sockets named `s3` or `admin` (`FileDescriptorName=`) go to that API;
+/// unnamed ones are used in order, S3 first, then admin.
+#[derive(Default)]
+pub struct ActivatedListeners {
+    pub s3: Option<std::net::TcpListener>,
+    pub admin: Option<std::net::TcpListener>,
+}
+
+impl ActivatedListeners {
+    /// Take the listeners systemd passed to this process, if any.
+    pub fn from_env() -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            let Some(count) = listen_fds()? else {
+                return Ok(Self::default());
+            };
+            let names = std::env::var("LISTEN_FDNAMES").unwrap_or_default();
+            let names: Vec<&str> = names.split(':').collect();
+            let mut listeners = Self::default();
+            let mut unnamed = Vec::new();
+            for i in 0..count {
+                let fd = LISTEN_FDS_START + i;
+                let listener = inherit(fd)?;
+                match names.get(i as usize).copied() {
+                    Some("s3") if listeners.s3.is_none() => listeners.s3 = Some(listener),
+                    Some("admin") if listeners.admin.is_none() => listeners.admin = Some(listener),
+                    _ => unnamed.push(listener),
+                }
+            }
+            for listener in unnamed {
+                if listeners.s3.is_none() {
+                    listeners.s3 = Some(listener);
+                } else if listeners.admin.is_none() {
+                    listeners.admin = Some(listener);
+                } else {
+                    tracing::warn!(addr = ?listener.local_addr().ok(), "Ignoring extra socket passed by systemd");
+                }
+            }
+            Ok(listeners)
+        }
+        #[cfg(not(unix))]
+        Ok(Self::default())
+    }
+}
+
+/// Number of sockets passed to this process, or `None` when the variables
+/// are unset or meant for another process.
+#[cfg(unix)]
+fn listen_fds() -> io::Result<Option<i32>> {
+    let (Ok(pid), Ok(fds)) = (std::env::var("LISTEN_PID"), std::env::var("LISTEN_FDS")) else {
+        return Ok(None);
+    };
+    if pid.parse::<u32>().ok() != Some(std::process::id()) {
+        return Ok(None);
+    }
+    let count: i32 = fds
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("invalid LISTEN_FDS: {fds}")))?;
+    Ok((count > 0).then_some(count))
+}
+
+#[cfg(unix)]
+fn inherit(fd: i32) -> io::Result<std::net::TcpListener> {
+    use std::os::fd::FromRawFd;
+    // SAFETY: systemd passes these descriptors to this process for it to own,
+    // and nothing else in the process refers to them.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    // Fails if the descriptor is not a TCP socket
+    listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Tell systemd the server is accepting requests.
+pub fn notify_ready() {
+    notify("READY=1");
+}
+
+/// Tell systemd the server is shutting down.
+pub fn notify_stopping() {
+    notify("STOPPING=1");
+}
+
+/// Send a state to `NOTIFY_SOCKET`. Failures are only logged: the server
+/// runs the same whether or not systemd hears about it.
+fn notify(message: &str) {
+    let Ok(path) = std::env::var("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(e) = send(&path, message) {
+        tracing::warn!(error = %e, socket = %path, "Failed to notify systemd");
+    }
+}
+
+#[cfg(unix)]
+fn send(path: &str, message: &str) -> io::Result<()> {
+    use std::os::unix::net::UnixDatagram;
+    let socket = UnixDatagram::unbound()?;
+    // A leading '@' names a socket in the abstract namespace
+    #[cfg(target_os = "linux")]
+    if let Some(name) = path.strip_prefix('@') {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        socket.send_to_addr(message.as_bytes(), &addr)?;
+        return Ok(());
+    }
+    socket.send_to(message.as_bytes(), path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send(_path: &str, _message: &str) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! Native HTTPS for the S3 and admin listeners.
+//!
+//! With `SIMPLES3_TLS_CERT` and `SIMPLES3_TLS_KEY` set, [`listen`] wraps a
+//! listener so that every accepted connection completes a rustls handshake
+//! before it is served. Handshakes run concurrently, so a slow or silent
+//! client does not hold up the connections accepted after it. With
+//! `SIMPLES3_ADMIN_CLIENT_CA` the admin listener also requires a client
+//! certificate, whose subject is passed to requests in [`TlsConnectInfo`].
+
+use axum::extract::connect_info::Connected;
+use axum::serve::{IncomingStream, Listener, ListenerExt, TapIo};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use simples3_core::Config;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+
+/// Connections that have not completed their handshake by then are closed.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Load the certificate chain and private key named in the configuration.
+pub fn server_config(config: &Config) -> Result<Option<Arc<ServerConfig>>, String> {
+    let (Some(cert_path), Some(key_path)) = (&config.tls_cert, &config.tls_key) else {
+        return Ok(None);
+    };
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read TLS certificate {}: {}", cert_path.display(), e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificate found in {}", cert_path.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("Failed to read TLS private key {}: {}", key_path.display(), e))?;
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid TLS certificate or key: {}", e))?;
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Some(Arc::new(server_config)))
+}
+
+/// The server config of the admin listener: `tls` requiring a client
+/// certificate signed by `admin_client_ca` when that is set.
+pub fn admin_server_config(
+    config: &Config,
+    tls: Option<Arc<ServerConfig>>,
+) -> Result<Option<Arc<ServerConfig>>, String> {
+    let Some(ca_path) = &config.admin_client_ca else {
+        return Ok(tls);
+    };
+    let tls = tls.ok_or("admin_client_ca requires tls_cert or acme_enabled")?;
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(ca_path)
+        .map_err(|e| format!("Failed to read admin client CA {}: {}", ca_path.display(), e))?
+    {
+        let cert = cert.map_err(|e| format!("Failed to read admin client CA {}: {}", ca_path.display(), e))?;
+        roots
+            .add(cert)
+            .map_err(|e| format!("Invalid admin client CA certificate: {}", e))?;
+    }
+    if roots.is_empty() {
+        return Err(format!("No certificate found in {}", ca_path.display()));
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| format!("Invalid admin client CA: {}", e))?;
+    let mut server_config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_client_cert_verifier(verifier)
+        .with_cert_resolver(tls.cert_resolver.clone());
+    server_config.alpn_protocols = tls.alpn_protocols.clone();
+    Ok(Some(Arc::new(server_config)))
+}
+
+/// A listener serving TLS connections when given a server config, plain ones
+/// otherwise.
+pub struct TlsListener<L: Listener> {
+    inner: L,
+    acceptor: Option<TlsAcceptor>,
+    /// Handshakes in progress, yielding the connections that complete.
+    handshakes: JoinSet<Option<Accepted<L>>>,
+}
+
+type Accepted<L> = (MaybeTlsStream<<L as Listener>::Io>, <L as Listener>::Addr);
+
+/// Listener returned by [`listen`]. The no-op tap gives it the connect info
+/// that `into_make_service_with_connect_info` needs.
+pub type ServerListener<L> = TapIo<TlsListener<L>, fn(&mut MaybeTlsStream<<L as Listener>::Io>)>;
+
+/// Serve `listener` over TLS with `tls`, or in plain HTTP without it.
+pub fn listen<L>(listener: L, tls: Option<Arc<ServerConfig>>) -> ServerListener<L>
+where
+    L: Listener,
+    L::Addr: 'static,
+{
+    TlsListener {
+        inner: listener,
+        acceptor: tls.map(TlsAcceptor::from),
+        handshakes: JoinSet::new(),
+    }
+    .tap_io(|_| {})
+}
+
+/// Like [`listen`], for the admin listener, which is served with
+/// [`TlsConnectInfo`] so that requests see the client certificate.
+pub fn listen_admin(listener: TcpListener, tls: Option<Arc<ServerConfig>>) -> TlsListener<TcpListener> {
+    TlsListener {
+        inner: listener,
+        acceptor: tls.map(TlsAcceptor::from),
+        handshakes: JoinSet::new(),
+    }
+}
+
+impl<L> Listener for TlsListener<L>
+where
+    L: Listener,
+    L::Addr: 'static,
+{
+    type Io = MaybeTlsStream<L::Io>;
+    type Addr = L::Addr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        let Some(acceptor) = &self.acceptor else {
+            let (io, addr) = self.inner.accept().await;
+            return (MaybeTlsStream::Plain(io), addr);
+        };
+        loop {
+            tokio::select! {
+                (io, addr) = self.inner.accept() => {
+                    let acceptor = acceptor.clone();
+                    self.handshakes.spawn(async move {
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(io)).await {
+                            Ok(Ok(stream)) => Some((MaybeTlsStream::Tls(Box::new(stream)), addr)),
+                            Ok(Err(e)) => {
+                                tracing::debug!(error = %e, "TLS handshake failed");
+                                None
+                            }
+                            Err(_) => {
+                                tracing::debug!("TLS handshake timed out");
+                                None
+                            }
+                        }
+                    });
+                }
+                Some(done) = self.handshakes.join_next() => {
+                    if let Ok(Some(connection)) = done {
+                        return connection;
+                    }
+                }
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
+
+/// Connect info of a [`listen_admin`] listener, for
+/// `into_make_service_with_connect_info::<TlsConnectInfo>()`.
+#[derive(Clone, Debug)]
+pub struct TlsConnectInfo {
+    pub remote_addr: SocketAddr,
+    /// Subject of the client certificate, verified during the handshake.
+    pub client_subject: Option<String>,
+}
+
+impl Connected<IncomingStream<'_, TlsListener<TcpListener>>> for TlsConnectInfo {
+    fn connect_info(stream: IncomingStream<'_, TlsListener<TcpListener>>) -> Self {
+        let client_subject = match stream.io() {
+            MaybeTlsStream::Tls(tls) => tls
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .and_then(|cert| x509_parser::parse_x509_certificate(cert).ok())
+                .map(|(_, cert)| cert.subject().to_string()),
+            MaybeTlsStream::Plain(_) => None,
+        };
+        TlsConnectInfo {
+            remote_addr: *stream.remote_addr(),
+            client_subject,
+        }
+    }
+}
+
+/// A client connection, encrypted or not.
+pub enum MaybeTlsStream<S> {
+    Plain(S),
+    Tls(Box<TlsStream<S>>),
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncRead for MaybeTlsStream<S> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            MaybeTlsStream::Tls(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> AsyncWrite for MaybeTlsStream<S> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            MaybeTlsStream::Tls(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            MaybeTlsStream::Tls(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            MaybeTlsStream::Tls(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            MaybeTlsStream::Plain(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+            MaybeTlsStream::Tls(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            MaybeTlsStream::Plain(s) => s.is_write_vectored(),
+            MaybeTlsStream::Tls(s) => s.is_write_vectored(),
+        }
+    }
+}
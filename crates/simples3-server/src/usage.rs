@@ -0,0 +1,207 @@
+//! Per-bucket usage accounting for chargeback reports.
+//!
+//! The usage middleware counts requests and egress bytes per bucket in a
+//! [`UsageTracker`]; the usage loop adds them to the month's totals in the
+//! metadata store, integrates each bucket's stored bytes over time, and once
+//! a month is over writes it as a CSV report into the configured bucket.
+
+use crate::AppState;
+use chrono::{DateTime, Datelike, Utc};
+use simples3_core::S3Error;
+use simples3_core::s3::request::S3Operation;
+use simples3_core::s3::types::{BucketUsage, ObjectMeta};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Buckets counted between flushes. Requests for further buckets are not
+/// counted until the next flush.
+const MAX_PENDING_BUCKETS: usize = 10_000;
+
+/// S3 pricing class of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestClass {
+    /// Writes and listings.
+    Tier1,
+    /// Other reads.
+    Tier2,
+}
+
+impl RequestClass {
+    /// Class of `operation`, or `None` for deletes, which are not billed.
+    pub fn of(operation: &S3Operation) -> Option<RequestClass> {
+        let name = operation.name();
+        if (name.starts_with("Delete") && name != "DeleteObjects") || name == "AbortMultipartUpload" {
+            return None;
+        }
+        let listing = matches!(
+            operation,
+            S3Operation::ListBuckets | S3Operation::ListObjectsV2 { .. } | S3Operation::ListParts { .. }
+        );
+        if listing || !operation.is_read_only() {
+            Some(RequestClass::Tier1)
+        } else {
+            Some(RequestClass::Tier2)
+        }
+    }
+}
+
+/// Usage counted since the last flush, and when stored bytes were last sampled.
+/// Requests are also counted, deletes included, for the request rate checked
+/// by usage alerts.
+#[derive(Default)]
+pub struct UsageTracker {
+    pending: Mutex<HashMap<String, BucketUsage>>,
+    last_sample: Mutex<Option<DateTime<Utc>>>,
+    requests: Mutex<HashMap<String, u64>>,
+}
+
+impl UsageTracker {
+    pub fn record(&self, bucket: &str, class: RequestClass, egress_bytes: u64) {
+        let mut pending = self.pending.lock().unwrap();
+        if !pending.contains_key(bucket) && pending.len() >= MAX_PENDING_BUCKETS {
+            return;
+        }
+        let usage = pending.entry(bucket.to_string()).or_default();
+        match class {
+            RequestClass::Tier1 => usage.tier1_requests += 1,
+            RequestClass::Tier2 => usage.tier2_requests += 1,
+        }
+        usage.egress_bytes += egress_bytes;
+    }
+
+    fn drain(&self) -> Vec<(String, BucketUsage)> {
+        self.pending.lock().unwrap().drain().collect()
+    }
+
+    /// Count a request to `bucket` towards its request rate.
+    pub fn record_request(&self, bucket: &str) {
+        let mut requests = self.requests.lock().unwrap();
+        if !requests.contains_key(bucket) && requests.len() >= MAX_PENDING_BUCKETS {
+            return;
+        }
+        *requests.entry(bucket.to_string()).or_default() += 1;
+    }
+
+    /// Requests per bucket since the last call.
+    pub fn take_requests(&self) -> HashMap<String, u64> {
+        std::mem::take(&mut *self.requests.lock().unwrap())
+    }
+}
+
+/// `YYYY-MM` of `time`.
+pub fn month_of(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m").to_string()
+}
+
+/// `YYYY-MM` of the month before `time`.
+fn previous_month(time: DateTime<Utc>) -> String {
+    let (year, month) = match time.month() {
+        1 => (time.year() - 1, 12),
+        m => (time.year(), m - 1),
+    };
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Whether `month` is a `YYYY-MM` month.
+pub fn valid_month(month: &str) -> bool {
+    month.len() == 7 && chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_ok()
+}
+
+/// Flush usage every `interval_secs`, and write the report of the previous
+/// month once it is over.
+pub async fn usage_loop(state: Arc<AppState>, interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+        if let Err(e) = flush(&state, now) {
+            tracing::warn!(error = %e, "Failed to record bucket usage");
+        }
+        if let Err(e) = report_previous_month(&state, now).await {
+            tracing::warn!(error = %e, "Failed to write usage report");
+        }
+    }
+}
+
+/// Add the counted requests, and the stored bytes of every bucket over the
+/// time since the last sample, to the usage of the month of `now`.
+pub fn flush(state: &AppState, now: DateTime<Utc>) -> Result<(), S3Error> {
+    let month = month_of(now);
+    for (bucket, usage) in state.usage.drain() {
+        if state.metadata.get_bucket(&bucket).is_ok() {
+            state.metadata.add_bucket_usage(&month, &bucket, &usage)?;
+        }
+    }
+
+    let previous = state.usage.last_sample.lock().unwrap().replace(now);
+    let Some(previous) = previous else {
+        return Ok(());
+    };
+    let hours = (now - previous).num_milliseconds().max(0) as f64 / 3_600_000.0;
+    for bucket in state.metadata.list_buckets()? {
+        let size = state.metadata.bucket_stats(&bucket.name)?.bytes;
+        if size == 0 {
+            continue;
+        }
+        let usage = BucketUsage {
+            storage_byte_hours: size as f64 * hours,
+            ..Default::default()
+        };
+        state.metadata.add_bucket_usage(&month, &bucket.name, &usage)?;
+    }
+    Ok(())
+}
+
+/// Write the report of the month before `now` if reports are configured and
+/// it was not written yet.
+async fn report_previous_month(state: &AppState, now: DateTime<Utc>) -> Result<(), S3Error> {
+    let month = previous_month(now);
+    if state.metadata.get_usage_report_config()?.is_none()
+        || state.metadata.last_usage_report()?.is_some_and(|last| last >= month)
+    {
+        return Ok(());
+    }
+    let key = write_report(state, &month).await?;
+    tracing::info!(month = %month, key = %key, "Wrote usage report");
+    state.metadata.set_last_usage_report(&month)
+}
+
+/// Usage of `month` as CSV, one line per bucket.
+pub fn report_csv(month: &str, usage: &[(String, BucketUsage)]) -> String {
+    let mut csv = String::from("month,bucket,storage_byte_hours,tier1_requests,tier2_requests,egress_bytes\n");
+    for (bucket, usage) in usage {
+        csv.push_str(&format!(
+            "{},{},{:.0},{},{},{}\n",
+            month, bucket, usage.storage_byte_hours, usage.tier1_requests, usage.tier2_requests, usage.egress_bytes
+        ));
+    }
+    csv
+}
+
+/// Write the report of `month` as `<target_prefix><month>.csv` into the
+/// report bucket, replacing an earlier one. Returns the key written.
+pub async fn write_report(state: &AppState, month: &str) -> Result<String, S3Error> {
+    let Some(config) = state.metadata.get_usage_report_config()? else {
+        return Err(S3Error::InvalidArgument("No usage report bucket is configured".into()));
+    };
+    let usage = state.metadata.list_bucket_usage(month)?;
+    let data = report_csv(month, &usage);
+    let bucket = &config.target_bucket;
+    let key = format!("{}{}.csv", config.target_prefix, month);
+    let _ = state.metadata.get_bucket(bucket)?;
+    let (size, etag) = state.storage.for_key(bucket, &key)?.write_object(bucket, &key, data.as_bytes()).await?;
+    state.metadata.put_object_meta(&ObjectMeta {
+        bucket: bucket.to_string(),
+        key: key.clone(),
+        size,
+        etag,
+        content_type: "text/csv".into(),
+        last_modified: Utc::now(),
+        public: false,
+        checksum: None,
+        legal_hold: false,
+        storage_class: None,
+    })?;
+    Ok(key)
+}
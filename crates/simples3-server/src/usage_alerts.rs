@@ -0,0 +1,102 @@
+//! Alerts on runaway bucket growth.
+//!
+//! Buckets can be given thresholds on stored bytes, object count and request
+//! rate. Every `SIMPLES3_USAGE_ALERT_INTERVAL` seconds the alert loop compares
+//! them with the bucket's stats and the requests counted since the previous
+//! check. Going over a threshold raises an alert, kept in the metadata store
+//! and listed by `GET /_admin/alerts`, and sends a `UsageAlert:Raised` event
+//! to the bucket's notification rules; once usage is back under the threshold
+//! (or the threshold is removed) the alert is cleared with a
+//! `UsageAlert:Cleared` event.
+
+use crate::AppState;
+use chrono::Utc;
+use simples3_core::S3Error;
+use simples3_core::s3::types::{UsageAlert, UsageAlertConfig, UsageMetric};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Check the alert thresholds every `interval_secs`.
+pub async fn alert_loop(state: Arc<AppState>, interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs.max(1)));
+    interval.tick().await;
+    let mut last_check = tokio::time::Instant::now();
+    // Requests made before the first tick are not spread over a full interval
+    state.usage.take_requests();
+    loop {
+        interval.tick().await;
+        let now = tokio::time::Instant::now();
+        if let Err(e) = check(&state, now - last_check) {
+            tracing::warn!(error = %e, "Failed to check bucket usage alerts");
+        }
+        last_check = now;
+    }
+}
+
+/// Raise and clear the alerts of every bucket with thresholds or raised
+/// alerts, taking the requests counted over the last `elapsed`.
+pub fn check(state: &AppState, elapsed: Duration) -> Result<(), S3Error> {
+    let requests = state.usage.take_requests();
+    let configs: HashMap<String, UsageAlertConfig> = state.metadata.list_usage_alert_configs()?.into_iter().collect();
+    let raised: HashMap<(String, UsageMetric), UsageAlert> = state
+        .metadata
+        .list_usage_alerts()?
+        .into_iter()
+        .map(|alert| ((alert.bucket.clone(), alert.metric), alert))
+        .collect();
+    let buckets: BTreeSet<&String> = configs.keys().chain(raised.keys().map(|(bucket, _)| bucket)).collect();
+    let secs = elapsed.as_secs_f64().max(1.0);
+
+    for bucket in buckets {
+        let config = configs.get(bucket).cloned().unwrap_or_default();
+        let stats = state.metadata.bucket_stats(bucket)?;
+        for metric in UsageMetric::ALL {
+            let value = match metric {
+                UsageMetric::Bytes => stats.bytes,
+                UsageMetric::Objects => stats.objects,
+                UsageMetric::RequestRate => {
+                    (requests.get(bucket.as_str()).copied().unwrap_or(0) as f64 / secs).round() as u64
+                }
+            };
+            let previous = raised.get(&(bucket.clone(), metric));
+            match config.threshold(metric) {
+                Some(threshold) if value > threshold => {
+                    let alert = UsageAlert {
+                        bucket: bucket.clone(),
+                        metric,
+                        threshold,
+                        value,
+                        raised: previous.map_or_else(Utc::now, |p| p.raised),
+                    };
+                    state.metadata.put_usage_alert(&alert)?;
+                    if previous.is_none() {
+                        tracing::warn!(
+                            bucket = %bucket,
+                            metric = metric.as_str(),
+                            threshold = threshold,
+                            value = value,
+                            "Bucket usage alert raised"
+                        );
+                        metrics::counter!(crate::metrics::USAGE_ALERTS_TOTAL, "metric" => metric.as_str())
+                            .increment(1);
+                        crate::notifications::notify_usage_alert(state, "UsageAlert:Raised", &alert);
+                    }
+                }
+                _ => {
+                    if let Some(previous) = previous
+                        && state.metadata.remove_usage_alert(bucket, metric)?
+                    {
+                        tracing::info!(bucket = %bucket, metric = metric.as_str(), value = value, "Bucket usage alert cleared");
+                        let cleared = UsageAlert {
+                            value,
+                            ..previous.clone()
+                        };
+                        crate::notifications::notify_usage_alert(state, "UsageAlert:Cleared", &cleared);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,24 @@
+//! Loading bucket metadata into memory ahead of requests.
+//!
+//! With `SIMPLES3_WARM_UP` set, the bucket records, credentials and bucket
+//! settings are read before the listeners start and again after every
+//! configuration reload, so the first request to each bucket does not pay
+//! for reading them from a slow disk.
+
+use simples3_core::storage::MetadataStore;
+
+/// Warm up the metadata store, logging what was loaded. Failures are only
+/// logged: requests read the store themselves anyway.
+pub async fn run(metadata: MetadataStore) {
+    let started = std::time::Instant::now();
+    match tokio::task::spawn_blocking(move || metadata.warm_up()).await {
+        Ok(Ok(report)) => tracing::info!(
+            buckets = report.buckets,
+            entries = report.entries,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Metadata warmed up"
+        ),
+        Ok(Err(e)) => tracing::warn!(error = %e, "Metadata warm-up failed"),
+        Err(e) => tracing::warn!(error = %e, "Metadata warm-up task failed"),
+    }
+}
@@ -0,0 +1,205 @@
+mod common;
+
+use common::TestServer;
+
+#[tokio::test]
+async fn test_bucket_logging_configuration() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/logged", base)).send().await.unwrap();
+
+    let resp = client.get(format!("{}/logged?logging", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!resp.text().await.unwrap().contains("<LoggingEnabled>"));
+
+    let enable = "<BucketLoggingStatus><LoggingEnabled><TargetBucket>log-target</TargetBucket>\
+                  <TargetPrefix>logged/</TargetPrefix></LoggingEnabled></BucketLoggingStatus>";
+
+    // The target bucket must exist
+    let resp = client.put(format!("{}/logged?logging", base)).body(enable).send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+
+    client.put(format!("{}/log-target", base)).send().await.unwrap();
+    let resp = client.put(format!("{}/logged?logging", base)).body(enable).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/logged?logging", base)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<TargetBucket>log-target</TargetBucket>"));
+    assert!(body.contains("<TargetPrefix>logged/</TargetPrefix>"));
+
+    // An empty status disables logging
+    let resp = client
+        .put(format!("{}/logged?logging", base))
+        .body("<BucketLoggingStatus/>")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(server.metadata.get_bucket_logging("logged").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_access_log_records_written_to_target() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.access_log_interval_secs = 1;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/site", base)).send().await.unwrap();
+    client.put(format!("{}/site-logs", base)).send().await.unwrap();
+    client
+        .put(format!("{}/site?logging", base))
+        .body("<BucketLoggingStatus><LoggingEnabled><TargetBucket>site-logs</TargetBucket>\
+               <TargetPrefix>access/</TargetPrefix></LoggingEnabled></BucketLoggingStatus>")
+        .send()
+        .await
+        .unwrap();
+
+    client.put(format!("{}/site/page.html", base)).body("hello").send().await.unwrap();
+    client
+        .get(format!("{}/site/page.html", base))
+        .header("user-agent", "log-test")
+        .send()
+        .await
+        .unwrap();
+    client.get(format!("{}/site/missing.html", base)).send().await.unwrap();
+
+    let mut log_key = None;
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        let resp = client
+            .get(format!("{}/site-logs?list-type=2&prefix=access/", base))
+            .send()
+            .await
+            .unwrap();
+        let body = resp.text().await.unwrap();
+        if let Some(start) = body.find("<Key>") {
+            let end = body.find("</Key>").unwrap();
+            log_key = Some(body[start + 5..end].to_string());
+            break;
+        }
+    }
+    let log_key = log_key.expect("no access log object written");
+
+    let logs = client
+        .get(format!("{}/site-logs/{}", base, log_key))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let lines: Vec<&str> = logs.lines().collect();
+    assert!(lines.iter().any(|l| l.contains("REST.PUT.OBJECT page.html") && l.contains(" 200 ")));
+    assert!(lines.iter().any(|l| {
+        l.contains("REST.GET.OBJECT page.html")
+            && l.contains("\"GET /site/page.html HTTP/1.1\" 200 - 5 5")
+            && l.contains("\"log-test\"")
+    }));
+    assert!(lines.iter().any(|l| l.contains("REST.GET.OBJECT missing.html") && l.contains(" 404 NoSuchKey ")));
+    // Requests on the target bucket itself are not logged
+    assert!(!logs.contains("site-logs"));
+}
+
+/// Lines of the access log file once it has at least `count`.
+async fn wait_for_lines(path: &std::path::Path, count: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for _ in 0..50 {
+        lines = std::fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        if lines.len() >= count {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    lines
+}
+
+#[tokio::test]
+async fn test_server_access_log_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let log = path.to_str().unwrap().to_string();
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.access_log = Some(log);
+        c.access_log_format = "json".into();
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/journal", base)).send().await.unwrap();
+    client.put(format!("{}/journal/a.txt", base)).body("hello").send().await.unwrap();
+    client.get(format!("{}/journal/missing.txt", base)).send().await.unwrap();
+    // Virtual-host requests are logged with their bucket
+    client
+        .get(format!("http://{}/a.txt", server.addr))
+        .header("host", format!("journal.{}", server.state.config.hostname))
+        .send()
+        .await
+        .unwrap();
+
+    let lines = wait_for_lines(&path, 4).await;
+    assert_eq!(lines.len(), 4, "{:?}", lines);
+    let entries: Vec<serde_json::Value> = lines.iter().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(entries[1]["operation"], "PutObject");
+    assert_eq!(entries[1]["bucket"], "journal");
+    assert_eq!(entries[1]["key"], "a.txt");
+    assert_eq!(entries[1]["status"], 200);
+    assert_eq!(entries[1]["bytes_received"], 5);
+    assert_eq!(entries[1]["remote_ip"], "127.0.0.1");
+    assert!(entries[1]["duration_ms"].is_u64());
+    assert!(entries[1]["request_id"].as_str().is_some_and(|id| !id.is_empty()));
+    assert_eq!(entries[2]["status"], 404);
+    assert_eq!(entries[2]["error_code"], "NoSuchKey");
+    assert_eq!(entries[3]["operation"], "GetObject");
+    assert_eq!(entries[3]["bucket"], "journal");
+    assert_eq!(entries[3]["bytes_sent"], 5);
+}
+
+#[tokio::test]
+async fn test_server_access_log_clf_rotation() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    let log = path.to_str().unwrap().to_string();
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.access_log = Some(log);
+        c.access_log_max_size = 300;
+        c.access_log_max_files = 2;
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    client.put(format!("{}/rotated", server.base_url)).send().await.unwrap();
+    let lines = wait_for_lines(&path, 1).await;
+    let line = &lines[0];
+    assert!(line.starts_with("127.0.0.1 - - ["), "{}", line);
+    assert!(line.contains("\"PUT /rotated HTTP/1.1\" 200 - \"CreateBucket\" "), "{}", line);
+
+    // Enough requests for several rotations; only two rotated files are kept
+    for i in 0..20 {
+        client.head(format!("{}/rotated/{}", server.base_url, i)).send().await.unwrap();
+    }
+    let rotated = |n: usize| dir.path().join(format!("access.log.{}", n));
+    for _ in 0..50 {
+        if rotated(2).exists() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert!(rotated(1).exists());
+    assert!(rotated(2).exists());
+    assert!(!rotated(3).exists());
+    assert!(std::fs::metadata(rotated(1)).unwrap().len() <= 300);
+}
@@ -0,0 +1,269 @@
+mod common;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use common::TestServer;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const TOKEN: &str = "challenge-token";
+/// DER encoding of the `id-pe-acmeIdentifier` extension OID.
+const ACME_IDENTIFIER_OID: &[u8] = &[0x06, 0x08, 0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x01, 0x1F];
+
+/// A minimal ACME CA: it validates the TLS-ALPN-01 challenge against the
+/// server under test and signs the CSR with its own root.
+struct FakeCa {
+    base: String,
+    target: tokio::sync::watch::Receiver<Option<SocketAddr>>,
+    root: rcgen::CertifiedIssuer<'static, rcgen::KeyPair>,
+    nonce: u64,
+    bad_nonce_sent: bool,
+    thumbprint: Option<String>,
+    authz_status: &'static str,
+    certificate: Option<String>,
+}
+
+type Ca = Arc<Mutex<FakeCa>>;
+
+fn payload(body: &[u8]) -> (serde_json::Value, serde_json::Value) {
+    let jws: serde_json::Value = serde_json::from_slice(body).unwrap();
+    let decode = |field: &str| {
+        let bytes = URL_SAFE_NO_PAD.decode(jws[field].as_str().unwrap()).unwrap();
+        serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null)
+    };
+    (decode("protected"), decode("payload"))
+}
+
+async fn handle(State(ca): State<Ca>, method: http::Method, uri: http::Uri, body: Bytes) -> Response {
+    let path = uri.path().to_string();
+    if path == "/directory" {
+        let base = ca.lock().unwrap().base.clone();
+        return axum::Json(serde_json::json!({
+            "newNonce": format!("{}/nonce", base),
+            "newAccount": format!("{}/account", base),
+            "newOrder": format!("{}/order", base),
+        }))
+        .into_response();
+    }
+    let nonce = {
+        let mut ca = ca.lock().unwrap();
+        ca.nonce += 1;
+        format!("nonce-{}", ca.nonce)
+    };
+    if method == http::Method::HEAD {
+        return ([("replay-nonce", nonce)], "").into_response();
+    }
+    let (protected, request) = payload(&body);
+    assert!(protected["nonce"].as_str().is_some_and(|n| n.starts_with("nonce-")));
+    let base = ca.lock().unwrap().base.clone();
+    let order = |ca: &FakeCa| {
+        let mut order = serde_json::json!({
+            "status": if ca.certificate.is_some() { "valid" } else { "pending" },
+            "authorizations": [format!("{}/authz/1", base)],
+            "finalize": format!("{}/finalize/1", base),
+        });
+        if ca.certificate.is_some() {
+            order["certificate"] = serde_json::json!(format!("{}/cert/1", base));
+        }
+        order
+    };
+
+    let (status, location, body): (u16, Option<String>, String) = match path.as_str() {
+        "/account" => {
+            let mut ca = ca.lock().unwrap();
+            if !ca.bad_nonce_sent {
+                ca.bad_nonce_sent = true;
+                let problem = serde_json::json!({ "type": "urn:ietf:params:acme:error:badNonce", "detail": "stale" });
+                return (http::StatusCode::BAD_REQUEST, [("replay-nonce", nonce)], problem.to_string()).into_response();
+            }
+            let jwk = &protected["jwk"];
+            let canonical = format!(
+                r#"{{"crv":"{}","kty":"{}","x":"{}","y":"{}"}}"#,
+                jwk["crv"].as_str().unwrap(),
+                jwk["kty"].as_str().unwrap(),
+                jwk["x"].as_str().unwrap(),
+                jwk["y"].as_str().unwrap()
+            );
+            ca.thumbprint = Some(URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes())));
+            (201, Some(format!("{}/acct/1", base)), r#"{"status":"valid"}"#.into())
+        }
+        "/order" => {
+            assert_eq!(request["identifiers"][0]["value"], "localhost");
+            let ca = ca.lock().unwrap();
+            (201, Some(format!("{}/order/1", base)), order(&ca).to_string())
+        }
+        "/order/1" | "/finalize/1" => {
+            let mut ca = ca.lock().unwrap();
+            if path == "/finalize/1" {
+                let csr = URL_SAFE_NO_PAD.decode(request["csr"].as_str().unwrap()).unwrap();
+                let params = rcgen::CertificateSigningRequestParams::from_der(&csr.into()).unwrap();
+                let cert = params.signed_by(&ca.root).unwrap();
+                ca.certificate = Some(format!("{}{}", cert.pem(), ca.root.pem()));
+            }
+            (200, None, order(&ca).to_string())
+        }
+        "/authz/1" => {
+            let ca = ca.lock().unwrap();
+            let authz = serde_json::json!({
+                "status": ca.authz_status,
+                "identifier": { "type": "dns", "value": "localhost" },
+                "challenges": [
+                    { "type": "http-01", "url": format!("{}/chall/0", base), "token": TOKEN },
+                    { "type": "tls-alpn-01", "url": format!("{}/chall/1", base), "token": TOKEN },
+                ],
+            });
+            (200, None, authz.to_string())
+        }
+        "/chall/1" => {
+            let (mut target, thumbprint) = {
+                let ca = ca.lock().unwrap();
+                (ca.target.clone(), ca.thumbprint.clone().unwrap())
+            };
+            let addr = target.wait_for(Option::is_some).await.unwrap().unwrap();
+            let expected = Sha256::digest(format!("{}.{}", TOKEN, thumbprint).as_bytes());
+            let valid = validate_tls_alpn(addr, &expected).await;
+            ca.lock().unwrap().authz_status = if valid { "valid" } else { "invalid" };
+            (200, None, r#"{"status":"processing"}"#.into())
+        }
+        "/cert/1" => {
+            let ca = ca.lock().unwrap();
+            (200, None, ca.certificate.clone().unwrap())
+        }
+        _ => (404, None, String::new()),
+    };
+    let mut resp = (http::StatusCode::from_u16(status).unwrap(), body).into_response();
+    resp.headers_mut().insert("replay-nonce", nonce.parse().unwrap());
+    if let Some(location) = location {
+        resp.headers_mut().insert("location", location.parse().unwrap());
+    }
+    resp
+}
+
+#[derive(Debug)]
+struct AcceptAny;
+
+impl ServerCertVerifier for AcceptAny {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Connect with the `acme-tls/1` protocol, as a CA does, and check the
+/// certificate carries the expected key authorization digest.
+async fn validate_tls_alpn(addr: SocketAddr, expected: &[u8]) -> bool {
+    let mut config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAny))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"acme-tls/1".to_vec()];
+    let connector = tokio_rustls::TlsConnector::from(Arc::new(config));
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let Ok(tls) = connector
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+    else {
+        return false;
+    };
+    let cert = tls.get_ref().1.peer_certificates().unwrap()[0].to_vec();
+    let contains = |needle: &[u8]| cert.windows(needle.len()).any(|w| w == needle);
+    contains(ACME_IDENTIFIER_OID) && contains(expected)
+}
+
+#[tokio::test]
+async fn test_acme_certificate() {
+    let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let root = rcgen::CertifiedIssuer::self_signed(params, rcgen::KeyPair::generate().unwrap()).unwrap();
+    let root_pem = root.pem();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let (target_tx, target) = tokio::sync::watch::channel(None);
+    let ca: Ca = Arc::new(Mutex::new(FakeCa {
+        base: base.clone(),
+        target,
+        root,
+        nonce: 0,
+        bad_nonce_sent: false,
+        thumbprint: None,
+        authz_status: "pending",
+        certificate: None,
+    }));
+    let app = axum::Router::new().fallback(handle).with_state(ca.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.hostname = "localhost".into();
+        c.acme_enabled = true;
+        c.acme_directory = format!("{}/directory", base);
+        c.acme_email = Some("ops@example.com".into());
+    })
+    .await;
+    assert!(server.base_url.starts_with("https://localhost:"));
+    target_tx.send(Some(server.addr)).unwrap();
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(root_pem.as_bytes()).unwrap())
+        .build()
+        .unwrap();
+    let mut status = None;
+    for _ in 0..100 {
+        if let Ok(resp) = client.put(format!("{}/acme-bucket", server.base_url)).send().await {
+            status = Some(resp.status());
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, Some(reqwest::StatusCode::OK));
+    assert_eq!(ca.lock().unwrap().authz_status, "valid");
+
+    // The account key and certificate are kept for restarts
+    let dir = server.state.config.data_dir.join(".acme");
+    for file in ["account.key", "cert.pem", "key.pem", "certificate.json"] {
+        assert!(dir.join(file).exists(), "{}", file);
+    }
+}
@@ -100,6 +100,156 @@ async fn test_admin_set_anonymous() {
     assert_eq!(buckets[0]["anonymous_read"], true);
 }
 
+#[tokio::test]
+async fn test_admin_set_anonymous_list() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    server.metadata.create_bucket("anon-list").unwrap();
+
+    let resp = client
+        .put(format!("{}/_admin/buckets/anon-list/anonymous-list", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "enabled": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(server.metadata.get_bucket("anon-list").unwrap().anonymous_list_public);
+
+    // Anonymous listing is now allowed
+    let resp = client.get(format!("{}/anon-list?list-type=2", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_admin_deletion_protection() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+    })
+    .await;
+    let client = admin_client();
+    server.metadata.create_bucket("protected").unwrap();
+
+    let set_protection = |enabled: bool| {
+        client
+            .put(format!("{}/_admin/buckets/protected/deletion-protection", server.admin_base_url))
+            .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .json(&serde_json::json!({ "enabled": enabled }))
+            .send()
+    };
+    let resp = set_protection(true).await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/_admin/buckets", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let buckets: Vec<serde_json::Value> = resp.json().await.unwrap();
+    assert_eq!(buckets[0]["deletion_protected"], true);
+
+    // Neither the S3 API nor the admin API can delete it
+    let resp = client.delete(format!("{}/protected", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    assert!(resp.text().await.unwrap().contains("BucketDeletionProtected"));
+    let resp = client
+        .delete(format!("{}/_admin/buckets/protected", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+    assert!(server.metadata.get_bucket("protected").is_ok());
+
+    let resp = set_protection(false).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.delete(format!("{}/protected", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+}
+
+#[tokio::test]
+async fn test_admin_freeze_and_thaw_bucket() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+    })
+    .await;
+    let client = admin_client();
+    client.put(format!("{}/cold", server.base_url)).send().await.unwrap();
+    for (key, body) in [("a.txt", "alpha"), ("dir/b.txt", "bravo")] {
+        let resp = client.put(format!("{}/cold/{}", server.base_url, key)).body(body).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    let resp = client
+        .post(format!("{}/_admin/buckets/cold/freeze", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "compact": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["compacted"], true);
+    assert_eq!(body["objects"].as_array().unwrap().len(), 2);
+    assert_eq!(body["objects"][1]["key"], "dir/b.txt");
+    assert_eq!(body["objects"][1]["offset"], 5);
+    assert_eq!(
+        body["objects"][0]["sha256"],
+        "8ed3f6ad685b959ead7022518e1af76cd816f8e8ec7ccdda1ed4018e8f2223f8"
+    );
+
+    // Writes are rejected, reads are served from the archive
+    let resp = client.put(format!("{}/cold/c.txt", server.base_url)).body("x").send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    assert!(resp.text().await.unwrap().contains("BucketFrozen"));
+    let resp = client.delete(format!("{}/cold/a.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    let resp = client.get(format!("{}/cold/dir/b.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "bravo");
+    let resp = client
+        .get(format!("{}/cold/dir/b.txt", server.base_url))
+        .header("Range", "bytes=1-2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.text().await.unwrap(), "ra");
+
+    let resp = client
+        .get(format!("{}/_admin/buckets", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let buckets: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(buckets[0]["frozen"], true);
+
+    let resp = client
+        .post(format!("{}/_admin/buckets/cold/thaw", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["restored"], 2);
+
+    let resp = client
+        .get(format!("{}/_admin/buckets/cold/freeze", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = client.get(format!("{}/cold/a.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "alpha");
+    let resp = client.put(format!("{}/cold/c.txt", server.base_url)).body("x").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 #[tokio::test]
 async fn test_admin_create_and_list_credentials() {
     let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
@@ -169,6 +319,62 @@ async fn test_admin_revoke_credential() {
     assert_eq!(revoked["active"], false);
 }
 
+#[tokio::test]
+async fn test_admin_export_import_credentials() {
+    let source = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let target = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    let created = source.metadata.create_credential("AKIDEXPORT", "EXPORTSECRET", "shared").unwrap();
+
+    let resp = client
+        .post(format!("{}/_admin/credentials/export", source.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "passphrase": "s3cret phrase" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let export: Value = resp.json().await.unwrap();
+    assert!(export["credentials"].is_null());
+    assert!(!export.to_string().contains("EXPORTSECRET"));
+
+    // A wrong passphrase is rejected
+    let resp = client
+        .post(format!("{}/_admin/credentials/import", target.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "export": export, "passphrase": "wrong" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // The fixture credential exists on both sides with another creation
+    // time, so it conflicts until the import replaces it
+    let resp = client
+        .post(format!("{}/_admin/credentials/import", target.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "export": export, "passphrase": "s3cret phrase" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let report: Value = resp.json().await.unwrap();
+    assert_eq!(report["created"], serde_json::json!(["AKIDEXPORT"]));
+    assert_eq!(report["conflicts"], serde_json::json!(["TESTAKID"]));
+    assert_eq!(target.metadata.get_credential("AKIDEXPORT").unwrap(), created);
+
+    let resp = client
+        .post(format!("{}/_admin/credentials/import", target.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "export": export, "passphrase": "s3cret phrase", "replace": true }))
+        .send()
+        .await
+        .unwrap();
+    let report: Value = resp.json().await.unwrap();
+    assert_eq!(report["updated"], serde_json::json!(["TESTAKID"]));
+    assert_eq!(report["unchanged"], serde_json::json!(["AKIDEXPORT"]));
+}
+
 #[tokio::test]
 async fn test_admin_api_not_on_s3_port() {
     // Admin routes should NOT be served on the S3 port
@@ -244,3 +450,1245 @@ async fn test_admin_no_token_when_unconfigured() {
         .unwrap();
     assert_eq!(resp.status(), 401);
 }
+
+#[tokio::test]
+async fn test_admin_config_history_and_rollback() {
+    use simples3_core::s3::types::{BucketPolicy, OneOrMany, PolicyEffect, PolicyPrincipal, PolicyStatement};
+
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+
+    client
+        .put(format!("{}/_admin/buckets/hist-bucket", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+
+    let policy = BucketPolicy {
+        version: "2012-10-17".into(),
+        statements: vec![PolicyStatement {
+            sid: Some("AllowGet".into()),
+            effect: PolicyEffect::Allow,
+            principal: PolicyPrincipal::Wildcard("*".into()),
+            action: OneOrMany::One("s3:GetObject".into()),
+            resource: OneOrMany::One("arn:aws:s3:::hist-bucket/*".into()),
+            condition: None,
+        }],
+    };
+    server
+        .metadata
+        .put_bucket_policy_by("hist-bucket", &policy, Some("AKIDEXAMPLE"))
+        .unwrap();
+
+    let resp = client
+        .get(format!("{}/_admin/buckets/hist-bucket/history?kind=policy", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["kind"], "policy");
+    assert_eq!(entries[0]["changed_by"], "AKIDEXAMPLE");
+    assert!(entries[0]["previous"].is_null());
+    let version = entries[0]["version"].as_u64().unwrap();
+
+    // Roll back the policy creation, which removes the policy
+    let resp = client
+        .post(format!(
+            "{}/_admin/buckets/hist-bucket/history/{}/rollback",
+            server.admin_base_url, version
+        ))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(server.metadata.get_bucket_policy("hist-bucket").is_err());
+
+    let resp = client
+        .get(format!("{}/_admin/buckets/hist-bucket/history", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["changed_by"], "admin");
+    assert!(entries[1]["current"].is_null());
+
+    // Unknown kind is rejected
+    let resp = client
+        .get(format!("{}/_admin/buckets/hist-bucket/history?kind=acl", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_admin_restore_bucket_within_grace_period() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+        c.bucket_delete_grace_secs = 3600;
+    })
+    .await;
+    let client = admin_client();
+
+    let resp = client
+        .put(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .delete(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+
+    // The name stays reserved during the grace period
+    let resp = client
+        .put(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+    assert!(resp.text().await.unwrap().contains("OperationAborted"));
+
+    let resp = client
+        .get(format!("{}/_admin/deleted-buckets", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let deleted: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(deleted[0]["name"], "grace-bucket");
+
+    let resp = client
+        .post(format!("{}/_admin/deleted-buckets/grace-bucket/restore", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .head(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // The grace period follows the server clock
+    let resp = client
+        .delete(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    server.state.clock.freeze(chrono::Utc::now() + chrono::Duration::hours(2));
+    let resp = client
+        .put(format!("{}/grace-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_admin_set_log_level() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    let url = format!("{}/_admin/log-level", server.admin_base_url);
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "filter": "off,simples3_server::handlers::admin=error" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["filter"], "off,simples3_server::handlers::admin=error");
+
+    // Invalid directives are rejected and leave the filter unchanged
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "filter": "simples3_server=notalevel" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "filter": "off" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_admin_reload_config() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    let client = admin_client();
+    let url = format!("{}/_admin/config/reload", server.admin_base_url);
+
+    // Nothing to reload from until the configuration sources are set
+    let resp = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let mut reloaded = server.state.config.clone();
+    reloaded.rate_limit_rps = 1;
+    reloaded.rate_limit_burst = 1;
+    reloaded.region = "eu-west-1".into();
+    server.state.runtime_config.set_source(move || Ok(reloaded.clone()));
+
+    let resp = client
+        .post(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["changed"], serde_json::json!(["rate_limit_rps", "rate_limit_burst"]));
+    assert_eq!(body["restart_required"], serde_json::json!(["region"]));
+
+    // The new limits apply to the next requests; the region does not change
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let resp = client.get(format!("{}/no-such-bucket", server.base_url)).send().await.unwrap();
+        statuses.push(resp.status().as_u16());
+    }
+    assert_eq!(statuses, vec![404, 503]);
+
+    let resp = client
+        .get(format!("{}/_admin/info", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["config"]["rate_limit_rps"], 1);
+    assert_eq!(body["config"]["region"], server.state.config.region.as_str());
+}
+
+#[tokio::test]
+async fn test_admin_cache_headers() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    let client = admin_client();
+    client.put(format!("{}/cdn-bucket", server.base_url)).send().await.unwrap();
+    client.put(format!("{}/cdn-bucket/logo.png", server.base_url)).body("png").send().await.unwrap();
+    let url = format!("{}/_admin/buckets/cdn-bucket/cache-headers", server.admin_base_url);
+
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "cache_control": "public, max-age=86400", "expires_after_secs": 86400 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/cdn-bucket/logo.png", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "public, max-age=86400");
+    assert!(resp.headers().get("expires").unwrap().to_str().unwrap().ends_with(" GMT"));
+    let resp = client.head(format!("{}/cdn-bucket/logo.png", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.headers().get("cache-control").unwrap(), "public, max-age=86400");
+
+    let resp = client
+        .delete(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(format!("{}/cdn-bucket/logo.png", server.base_url)).send().await.unwrap();
+    assert!(!resp.headers().contains_key("cache-control"));
+
+    // Empty or invalid settings are rejected
+    for body in [
+        serde_json::json!({}),
+        serde_json::json!({ "cache_control": "bad\nvalue" }),
+        serde_json::json!({ "expires_after_secs": 10 * 365 * 24 * 3600 }),
+    ] {
+        let resp = client
+            .put(&url)
+            .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .json(&body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+}
+
+#[tokio::test]
+async fn test_admin_request_log() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    let client = admin_client();
+    client.put(format!("{}/noisy-bucket", server.base_url)).send().await.unwrap();
+    let url = format!("{}/_admin/buckets/noisy-bucket/request-log", server.admin_base_url);
+    let auth = format!("Bearer {}", ADMIN_TOKEN);
+
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "sample_rate": 0.01 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["sample_rate"], 0.01);
+
+    // Sampled requests are still served normally
+    let resp = client
+        .put(&url)
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "sample_rate": 1.0 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.put(format!("{}/noisy-bucket/a.txt", server.base_url)).body("a").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/noisy-bucket/a.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "a");
+
+    for rate in [0.0, -0.5, 1.5] {
+        let resp = client
+            .put(&url)
+            .header("Authorization", &auth)
+            .json(&serde_json::json!({ "sample_rate": rate }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400);
+    }
+    let resp = client
+        .put(format!("{}/_admin/buckets/missing/request-log", server.admin_base_url))
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "sample_rate": 0.5 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client.delete(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_admin_default_tags() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    server.metadata.create_bucket("tagged-bucket").unwrap();
+    let url = format!("{}/_admin/buckets/tagged-bucket/default-tags", server.admin_base_url);
+
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "tags": { "team": "storage" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["tags"]["team"], "storage");
+
+    let resp = client
+        .delete(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(server.metadata.get_bucket_default_tags("tagged-bucket").unwrap().is_empty());
+
+    // More than 10 tags is rejected
+    let tags: serde_json::Map<String, Value> =
+        (0..11).map(|i| (format!("k{}", i), Value::from("v"))).collect();
+    let resp = client
+        .put(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "tags": tags }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_admin_presigned_post() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    server.metadata.create_bucket("upload-bucket").unwrap();
+
+    let resp = client
+        .post(format!("{}/_admin/buckets/upload-bucket/presigned-post", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({
+            "key": "uploads/${filename}",
+            "access_key_id": "TESTAKID",
+            "expires_in_secs": 600,
+            "conditions": [["content-length-range", 0, 1048576]]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["url"], "http://s3.localhost/upload-bucket");
+    assert_eq!(body["fields"]["key"], "uploads/${filename}");
+    assert_eq!(body["fields"]["x-amz-algorithm"], "AWS4-HMAC-SHA256");
+    assert!(body["fields"]["x-amz-credential"].as_str().unwrap().starts_with("TESTAKID/"));
+    assert_eq!(body["fields"]["x-amz-signature"].as_str().unwrap().len(), 64);
+
+    // Unknown credential
+    let resp = client
+        .post(format!("{}/_admin/buckets/upload-bucket/presigned-post", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "key": "a.txt", "access_key_id": "NOPE" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+}
+
+#[tokio::test]
+async fn test_admin_concatenate_objects() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+    })
+    .await;
+    let client = admin_client();
+    let base = &server.base_url;
+    server.metadata.create_bucket("logs").unwrap();
+
+    for (key, body) in [("app.log", "one\n"), ("segment-1", "two\n"), ("segment-2", "three\n")] {
+        client
+            .put(format!("{}/logs/{}", base, key))
+            .header("content-type", "text/plain")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let concatenate = |body: serde_json::Value| {
+        client
+            .post(format!("{}/_admin/buckets/logs/concatenate", server.admin_base_url))
+            .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .json(&body)
+            .send()
+    };
+
+    // Append segments to an existing object
+    let resp = concatenate(serde_json::json!({
+        "key": "app.log",
+        "sources": ["app.log", "segment-1", "segment-2"],
+    }))
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["size"], 14);
+    assert!(body["etag"].as_str().unwrap().ends_with("-3"));
+
+    let resp = client.get(format!("{}/logs/app.log", base)).send().await.unwrap();
+    assert_eq!(resp.headers().get("content-type").unwrap(), "text/plain");
+    assert_eq!(resp.text().await.unwrap(), "one\ntwo\nthree\n");
+    // Sources are kept
+    let resp = client.get(format!("{}/logs/segment-1", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = concatenate(serde_json::json!({
+        "key": "merged.log",
+        "sources": ["segment-1", "missing"],
+    }))
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = client.get(format!("{}/logs/merged.log", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = concatenate(serde_json::json!({ "key": "merged.log", "sources": [] }))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_admin_debug_canonical_request() {
+    use simples3_core::auth::sigv4;
+    use std::collections::BTreeMap;
+
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+
+    let mut headers = BTreeMap::new();
+    headers.insert("host".to_string(), "s3.localhost".to_string());
+    headers.insert("x-amz-content-sha256".to_string(), "UNSIGNED-PAYLOAD".to_string());
+    headers.insert("x-amz-date".to_string(), "20240101T000000Z".to_string());
+    let signed: Vec<String> = headers.keys().cloned().collect();
+    let canon = sigv4::canonical_request(
+        "GET",
+        "/bkt",
+        "list-type=2&prefix=a%2Fb",
+        &headers,
+        &signed,
+        "UNSIGNED-PAYLOAD",
+    );
+    let sts = sigv4::string_to_sign("20240101T000000Z", "20240101", "us-east-1", &canon);
+    let key = sigv4::signing_key("TESTSECRET", "20240101", "us-east-1");
+    let signature = hex::encode(sigv4::hmac_sha256(&key, sts.as_bytes()));
+
+    let mut captured: BTreeMap<String, String> = headers.clone();
+    captured.insert(
+        "Authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential=TESTAKID/20240101/us-east-1/s3/aws4_request, SignedHeaders={}, Signature={}",
+            signed.join(";"),
+            signature
+        ),
+    );
+    let resp = client
+        .post(format!("{}/_admin/debug/canonical-request", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({
+            "method": "get",
+            "path": "/bkt",
+            "query": "prefix=a%2Fb&list-type=2",
+            "headers": captured,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["canonical_request"], canon);
+    assert_eq!(body["string_to_sign"], sts);
+    assert_eq!(body["access_key_id"], "TESTAKID");
+    assert_eq!(body["matches"], true);
+
+    // A tampered signature is reported as a mismatch with the expected value
+    captured.insert(
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential=TESTAKID/20240101/us-east-1/s3/aws4_request, SignedHeaders={}, Signature=deadbeef",
+            signed.join(";")
+        ),
+    );
+    captured.remove("Authorization");
+    let resp = client
+        .post(format!("{}/_admin/debug/canonical-request", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({
+            "method": "GET",
+            "path": "/bkt",
+            "query": "list-type=2&prefix=a%2Fb",
+            "headers": captured,
+        }))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["matches"], false);
+    assert_eq!(body["signature"], signature);
+
+    // Without an Authorization header a credential must be chosen
+    let resp = client
+        .post(format!("{}/_admin/debug/canonical-request", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "method": "GET", "path": "/bkt", "headers": headers }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_admin_simulate_policy() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+
+    server.metadata.create_bucket("sim-bucket").unwrap();
+    let policy: simples3_core::s3::types::BucketPolicy = serde_json::from_value(serde_json::json!({
+        "Version": "2012-10-17",
+        "Statement": [
+            {
+                "Sid": "PublicReadFromOffice",
+                "Effect": "Allow",
+                "Principal": "*",
+                "Action": "s3:GetObject",
+                "Resource": "arn:aws:s3:::sim-bucket/*",
+                "Condition": {"IpAddress": {"aws:SourceIp": "10.0.0.0/8"}}
+            },
+            {
+                "Sid": "NoDeletes",
+                "Effect": "Deny",
+                "Principal": {"AWS": ["TESTAKID"]},
+                "Action": "s3:DeleteObject",
+                "Resource": "arn:aws:s3:::sim-bucket/*"
+            }
+        ]
+    }))
+    .unwrap();
+    server.metadata.put_bucket_policy("sim-bucket", &policy).unwrap();
+
+    let simulate = |body: Value| {
+        let client = client.clone();
+        let url = format!("{}/_admin/simulate", server.admin_base_url);
+        async move {
+            let resp = client
+                .post(url)
+                .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), 200);
+            resp.json::<Value>().await.unwrap()
+        }
+    };
+
+    // Anonymous read from inside the allowed range matches the Allow statement
+    let body = simulate(serde_json::json!({
+        "action": "GetObject",
+        "bucket": "sim-bucket",
+        "key": "report.pdf",
+        "source_ip": "10.1.2.3",
+    }))
+    .await;
+    assert_eq!(body["allowed"], true);
+    assert_eq!(body["action"], "s3:GetObject");
+    assert_eq!(body["policy_decision"], "ExplicitAllow");
+    assert_eq!(body["matched_statement"]["sid"], "PublicReadFromOffice");
+    assert_eq!(body["matched_statement"]["index"], 0);
+
+    // Outside the range nothing matches
+    let body = simulate(serde_json::json!({
+        "action": "s3:GetObject",
+        "bucket": "sim-bucket",
+        "key": "report.pdf",
+        "source_ip": "192.168.1.1",
+    }))
+    .await;
+    assert_eq!(body["allowed"], false);
+    assert_eq!(body["policy_decision"], "ImplicitDeny");
+    assert!(body["matched_statement"].is_null());
+
+    // The credential is denied deletes, but allowed everything else
+    let body = simulate(serde_json::json!({
+        "principal": "TESTAKID",
+        "action": "s3:DeleteObject",
+        "bucket": "sim-bucket",
+        "key": "report.pdf",
+    }))
+    .await;
+    assert_eq!(body["allowed"], false);
+    assert_eq!(body["matched_statement"]["sid"], "NoDeletes");
+    assert_eq!(body["matched_statement"]["effect"], "Deny");
+
+    let body = simulate(serde_json::json!({
+        "principal": "TESTAKID",
+        "action": "s3:PutObject",
+        "bucket": "sim-bucket",
+        "key": "report.pdf",
+    }))
+    .await;
+    assert_eq!(body["allowed"], true);
+
+    let body = simulate(serde_json::json!({
+        "principal": "NOSUCHKEY",
+        "action": "s3:PutObject",
+        "bucket": "sim-bucket",
+    }))
+    .await;
+    assert_eq!(body["allowed"], false);
+
+    // Unknown bucket
+    let resp = client
+        .post(format!("{}/_admin/simulate", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({"action": "s3:GetObject", "bucket": "missing"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_admin_server_info() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    let url = format!("{}/_admin/info", server.admin_base_url);
+
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+
+    let resp = client
+        .get(&url)
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["uptime_secs"].is_u64());
+    assert_eq!(body["config"]["region"], "us-east-1");
+    assert_eq!(body["config"]["hostname"], "s3.localhost");
+    // The token itself is never returned
+    assert_eq!(body["config"]["admin_token"], "********");
+}
+
+#[tokio::test]
+async fn test_admin_metadata_report() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    server.metadata.create_bucket("report-a").unwrap();
+    server.metadata.create_bucket("report-b").unwrap();
+
+    let resp = client
+        .get(format!("{}/_admin/metadata", server.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["size_on_disk"].as_u64().unwrap() > 0);
+    let buckets = body["trees"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["name"] == "buckets")
+        .unwrap();
+    assert_eq!(buckets["keys"], 2);
+    assert!(buckets["bytes"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn test_admin_usage_report() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+    })
+    .await;
+    let client = admin_client();
+    let auth = format!("Bearer {}", ADMIN_TOKEN);
+    let base = &server.base_url;
+    server.metadata.create_bucket("billed").unwrap();
+    server.metadata.create_bucket("reports").unwrap();
+
+    client.put(format!("{}/billed/a.txt", base)).body("hello world").send().await.unwrap();
+    client.put(format!("{}/billed/b.txt", base)).body("b").send().await.unwrap();
+    client.get(format!("{}/billed?list-type=2", base)).send().await.unwrap();
+    let resp = client.get(format!("{}/billed/a.txt", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "hello world");
+    // Deletes are not billed
+    client.delete(format!("{}/billed/b.txt", base)).send().await.unwrap();
+
+    let resp = client
+        .get(format!("{}/_admin/usage", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    let month = body["month"].as_str().unwrap().to_string();
+    assert_eq!(month, chrono::Utc::now().format("%Y-%m").to_string());
+    let billed = body["buckets"].as_array().unwrap().iter().find(|b| b["bucket"] == "billed").unwrap().clone();
+    assert_eq!(billed["tier1_requests"], 3);
+    assert_eq!(billed["tier2_requests"], 1);
+    assert!(billed["egress_bytes"].as_u64().unwrap() >= 11);
+    assert!(billed["storage_byte_hours"].as_f64().unwrap() >= 0.0);
+
+    // Reports need a configured bucket
+    let reports_url = format!("{}/_admin/usage/reports?month={}", server.admin_base_url, month);
+    let resp = client.post(&reports_url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let config_url = format!("{}/_admin/usage/report-config", server.admin_base_url);
+    let resp = client
+        .put(&config_url)
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "target_bucket": "missing" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = client
+        .put(&config_url)
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "target_bucket": "reports", "target_prefix": "usage/" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(&config_url).header("Authorization", &auth).send().await.unwrap();
+    let config: Value = resp.json().await.unwrap();
+    assert_eq!(config["target_prefix"], "usage/");
+
+    let resp = client.post(&reports_url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let written: Value = resp.json().await.unwrap();
+    let key = format!("usage/{}.csv", month);
+    assert_eq!(written["key"], key);
+
+    let resp = client.get(format!("{}/reports/{}", base, key)).send().await.unwrap();
+    assert_eq!(resp.headers()["content-type"], "text/csv");
+    let csv = resp.text().await.unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "month,bucket,storage_byte_hours,tier1_requests,tier2_requests,egress_bytes"
+    );
+    let row = lines.find(|l| l.contains(",billed,")).unwrap();
+    let fields: Vec<&str> = row.split(',').collect();
+    assert_eq!(fields[0], month);
+    assert_eq!(fields[3], "3");
+    assert_eq!(fields[4], "1");
+
+    let resp = client
+        .get(format!("{}/_admin/usage?month=2026-13", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let resp = client.delete(&config_url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(&config_url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_admin_audit_trail() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    let client = admin_client();
+    let auth = format!("Bearer {}", ADMIN_TOKEN);
+
+    client
+        .put(format!("{}/_admin/buckets/audited", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{}/_admin/credentials", server.admin_base_url))
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "description": "ci" }))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{}/_admin/credentials/export", server.admin_base_url))
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "passphrase": "hunter2" }))
+        .send()
+        .await
+        .unwrap();
+    // Reads, read-only POSTs and unauthenticated requests are not recorded
+    client
+        .get(format!("{}/_admin/buckets", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{}/_admin/simulate", server.admin_base_url))
+        .header("Authorization", &auth)
+        .json(&serde_json::json!({ "action": "GetObject", "bucket": "audited" }))
+        .send()
+        .await
+        .unwrap();
+    client
+        .delete(format!("{}/_admin/buckets/audited", server.admin_base_url))
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client
+        .get(format!("{}/_admin/audit", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(entries.len(), 3, "{:?}", entries);
+    assert_eq!(entries[0]["path"], "/_admin/credentials/export");
+    assert_eq!(entries[0]["payload"]["passphrase"], "********");
+    assert_eq!(entries[1]["method"], "POST");
+    assert_eq!(entries[1]["route"], "/_admin/credentials");
+    assert_eq!(entries[1]["payload"]["description"], "ci");
+    assert_eq!(entries[1]["status"], 201);
+    assert_eq!(entries[2]["actor"], "token");
+    assert_eq!(entries[2]["route"], "/_admin/buckets/{name}");
+    assert_eq!(entries[2]["remote_ip"], "127.0.0.1");
+    assert!(entries[2]["payload"].is_null());
+
+    let resp = client
+        .get(format!("{}/_admin/audit?path=/_admin/buckets&limit=5", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["path"], "/_admin/buckets/audited");
+}
+
+#[tokio::test]
+async fn test_admin_list_and_cancel_connections() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    server.metadata.create_bucket("stuck").unwrap();
+    let client = admin_client();
+    let auth = format!("Bearer {}", ADMIN_TOKEN);
+
+    // An upload that sends part of its body, then stalls
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(1);
+    sender.send(Ok(b"half".to_vec())).await.unwrap();
+    let body = futures_util::stream::unfold(receiver, |mut r| async move { r.recv().await.map(|c| (c, r)) });
+    let upload = tokio::spawn(
+        reqwest::Client::new()
+            .put(format!("{}/stuck/big.bin", server.base_url))
+            .header("content-length", "10")
+            .body(reqwest::Body::wrap_stream(body))
+            .send(),
+    );
+
+    let mut request = Value::Null;
+    for _ in 0..50 {
+        let resp = client
+            .get(format!("{}/_admin/connections", server.admin_base_url))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let listed: Value = resp.json().await.unwrap();
+        if let Some(r) = listed["requests"].as_array().and_then(|r| r.first())
+            && r["bytes_received"] == 4
+        {
+            assert_eq!(listed["open_connections"], 1);
+            request = r.clone();
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(request["operation"], "PutObject");
+    assert_eq!(request["bucket"], "stuck");
+    assert_eq!(request["key"], "big.bin");
+    assert_eq!(request["remote_ip"], "127.0.0.1");
+
+    let resp = client
+        .delete(format!("{}/_admin/connections/{}", server.admin_base_url, request["id"].as_str().unwrap()))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = upload.await.unwrap().unwrap();
+    assert_eq!(resp.status(), 503);
+    drop(sender);
+
+    let resp = client
+        .get(format!("{}/_admin/connections", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    let listed: Value = resp.json().await.unwrap();
+    assert_eq!(listed["requests"].as_array().unwrap().len(), 0);
+
+    let resp = client
+        .delete(format!("{}/_admin/connections/unknown", server.admin_base_url))
+        .header("Authorization", &auth)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_bucket_admin_tokens() {
+    let server = TestServer::start_with_admin_token(ADMIN_TOKEN).await;
+    server.metadata.create_bucket("team-a").unwrap();
+    server.metadata.create_bucket("team-b").unwrap();
+    let client = admin_client();
+    let admin = format!("Bearer {}", ADMIN_TOKEN);
+
+    let resp = client
+        .post(format!("{}/_admin/bucket-tokens", server.admin_base_url))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "bucket": "team-a", "description": "team a" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let minted: Value = resp.json().await.unwrap();
+    let token = format!("Bearer {}", minted["token"].as_str().unwrap());
+    let id = minted["id"].as_str().unwrap().to_string();
+    let url = |path: &str| format!("{}/_admin{}", server.admin_base_url, path);
+
+    // The bucket's settings and bound credentials
+    let policy = serde_json::json!({
+        "Version": "2012-10-17",
+        "Statement": [{ "Effect": "Allow", "Principal": "*", "Action": "s3:GetObject", "Resource": "arn:aws:s3:::team-a/*" }]
+    });
+    let resp = client.put(url("/buckets/team-a/policy")).header("Authorization", &token).json(&policy).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(url("/buckets/team-a/policy")).header("Authorization", &token).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let cors = "<CORSConfiguration><CORSRule><AllowedOrigin>*</AllowedOrigin><AllowedMethod>GET</AllowedMethod></CORSRule></CORSConfiguration>";
+    let resp = client.put(url("/buckets/team-a/cors")).header("Authorization", &token).body(cors).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .post(url("/buckets/team-a/credentials"))
+        .header("Authorization", &token)
+        .json(&serde_json::json!({ "description": "deploy" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 201);
+    let credential: Value = resp.json().await.unwrap();
+    assert_eq!(credential["bucket"], "team-a");
+    let resp = client.get(url("/buckets/team-a/credentials")).header("Authorization", &token).send().await.unwrap();
+    let listed: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["secret_access_key"], "********");
+    let resp = client
+        .delete(url(&format!("/buckets/team-a/credentials/{}", credential["access_key_id"].as_str().unwrap())))
+        .header("Authorization", &token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.delete(url("/buckets/team-a/credentials/TESTAKID")).header("Authorization", &token).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = client
+        .put(url("/buckets/team-a/leases/report.csv"))
+        .header("Authorization", &token)
+        .json(&serde_json::json!({ "holder": "job", "ttl_secs": 30 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Nothing else
+    for (method, path) in [
+        (reqwest::Method::PUT, "/buckets/team-b/policy"),
+        (reqwest::Method::DELETE, "/buckets/team-a"),
+        (reqwest::Method::POST, "/buckets/team-a/freeze"),
+        (reqwest::Method::GET, "/buckets"),
+        (reqwest::Method::GET, "/credentials"),
+        (reqwest::Method::GET, "/bucket-tokens"),
+    ] {
+        let resp = client.request(method, url(path)).header("Authorization", &token).json(&policy).send().await.unwrap();
+        assert_eq!(resp.status(), 403, "{}", path);
+    }
+
+    let resp = client.get(url("/audit")).header("Authorization", &admin).send().await.unwrap();
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(entries[0]["actor"], format!("bucket-token:{}", id));
+
+    let resp = client.get(url("/bucket-tokens?bucket=team-a")).header("Authorization", &admin).send().await.unwrap();
+    let tokens: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].get("token").is_none());
+    let resp = client.delete(url(&format!("/bucket-tokens/{}", id))).header("Authorization", &admin).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(url("/buckets/team-a/policy")).header("Authorization", &token).send().await.unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+#[tokio::test]
+async fn test_admin_object_leases() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.clock_control = true;
+    })
+    .await;
+    server.metadata.create_bucket("jobs").unwrap();
+    let client = admin_client();
+    let admin = format!("Bearer {}", ADMIN_TOKEN);
+    let url = |path: &str| format!("{}/_admin{}", server.admin_base_url, path);
+    let lease = |holder: &str, ttl_secs: u64| serde_json::json!({ "holder": holder, "ttl_secs": ttl_secs });
+
+    let resp = client.put(url("/buckets/jobs/leases/in/a.csv")).header("Authorization", &admin).json(&lease("w1", 60)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["key"], "in/a.csv");
+    assert_eq!(body["holder"], "w1");
+
+    let resp = client.put(url("/buckets/jobs/leases/in/a.csv")).header("Authorization", &admin).json(&lease("w2", 60)).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    let resp = client.put(url("/buckets/jobs/leases/in/b.csv")).header("Authorization", &admin).json(&lease("w2", 0)).send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = client.put(url("/buckets/nope/leases/a")).header("Authorization", &admin).json(&lease("w2", 60)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client.get(url("/buckets/jobs/leases?prefix=in/")).header("Authorization", &admin).send().await.unwrap();
+    let leases: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(leases.len(), 1);
+
+    // Expired leases are free to take
+    let resp = client
+        .put(url("/clock"))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "time": (chrono::Utc::now() + chrono::Duration::minutes(2)).to_rfc3339() }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(url("/buckets/jobs/leases/in/a.csv")).header("Authorization", &admin).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = client.put(url("/buckets/jobs/leases/in/a.csv")).header("Authorization", &admin).json(&lease("w2", 60)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.delete(url("/buckets/jobs/leases/in/a.csv?holder=w1")).header("Authorization", &admin).send().await.unwrap();
+    assert_eq!(resp.status(), 409);
+    let resp = client.delete(url("/buckets/jobs/leases/in/a.csv?holder=w2")).header("Authorization", &admin).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+
+    // Lease traffic stays out of the audit trail
+    let resp = client.get(url("/audit?path=/_admin/buckets/jobs/leases")).header("Authorization", &admin).send().await.unwrap();
+    let entries: Vec<Value> = resp.json().await.unwrap();
+    assert!(entries.is_empty());
+}
+
+#[tokio::test]
+async fn test_admin_object_moderation() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    let client = admin_client();
+    let admin = format!("Bearer {}", ADMIN_TOKEN);
+    let url = |path: &str| format!("{}/_admin{}", server.admin_base_url, path);
+    client.put(format!("{}/ugc", server.base_url)).send().await.unwrap();
+    client.put(format!("{}/ugc/up/cat.png", server.base_url)).body("meow").send().await.unwrap();
+
+    let resp = client.get(url("/buckets/ugc/moderation/up/cat.png")).header("Authorization", &admin).send().await.unwrap();
+    let body: Value = resp.json().await.unwrap();
+    assert_eq!(body["state"], "active");
+    let resp = client
+        .put(url("/buckets/ugc/moderation/up/missing.png"))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "state": "quarantined" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    // Pending review hides the object from anonymous readers
+    let resp = client
+        .put(url("/buckets/ugc/moderation/up/cat.png"))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "state": "pending-review", "reason": "reported" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/ugc/up/cat.png", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("<Code>ObjectPendingReview</Code>"));
+
+    // Quarantine refuses every read with its own code
+    let resp = client
+        .put(url("/buckets/ugc/moderation/up/cat.png"))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "state": "quarantined", "reason": "abuse" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/ugc/up/cat.png", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("<Code>ObjectUnderModeration</Code>"));
+    let resp = client.get(url("/buckets/ugc/moderation?state=quarantined")).header("Authorization", &admin).send().await.unwrap();
+    let listed: Vec<Value> = resp.json().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["key"], "up/cat.png");
+    assert_eq!(listed[0]["reason"], "abuse");
+
+    let resp = client
+        .put(url("/buckets/ugc/moderation/up/cat.png"))
+        .header("Authorization", &admin)
+        .json(&serde_json::json!({ "state": "active" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/ugc/up/cat.png", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().get("x-simples3-moderation").is_none());
+}
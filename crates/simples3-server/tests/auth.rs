@@ -35,6 +35,9 @@ async fn test_anonymous_read_on_enabled_bucket() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
@@ -88,6 +91,9 @@ async fn test_anonymous_get_public_object_on_private_bucket() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: true,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
@@ -102,6 +108,9 @@ async fn test_anonymous_get_public_object_on_private_bucket() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
@@ -151,6 +160,9 @@ async fn test_anonymous_list_public_objects_only() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: true,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
     server
@@ -163,6 +175,9 @@ async fn test_anonymous_list_public_objects_only() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
@@ -195,3 +210,352 @@ async fn test_anonymous_list_denied_without_flag() {
         .unwrap();
     assert_eq!(resp.status(), 403);
 }
+
+#[tokio::test]
+async fn test_sigv2_signed_requests() {
+    use simples3_core::auth::sigv2;
+    use std::collections::BTreeMap;
+
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server
+        .metadata
+        .create_credential("AKIDSIGV2TEST", "sigv2-secret", "legacy client")
+        .unwrap();
+
+    let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S +0000").to_string();
+    let sign = |method: &str, path: &str, secret: &str| {
+        let mut headers = BTreeMap::new();
+        headers.insert("date".to_string(), date.clone());
+        let sts = sigv2::string_to_sign(method, path, &[], &headers);
+        format!("AWS AKIDSIGV2TEST:{}", sigv2::sign(secret, &sts))
+    };
+
+    let resp = client
+        .put(format!("{}/legacy-bucket", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("PUT", "/legacy-bucket", "sigv2-secret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("GET", "/", "sigv2-secret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Name>legacy-bucket</Name>"));
+    // Owner is the calling credential
+    assert!(body.contains("<ID>AKIDSIGV2TEST</ID><DisplayName>legacy client</DisplayName>"));
+
+    // Wrong secret is rejected
+    let resp = client
+        .get(format!("{}/", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("GET", "/", "not-the-secret"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // A request signed too far from the server's time is rejected
+    let stale = (chrono::Utc::now() - chrono::Duration::hours(1))
+        .format("%a, %d %b %Y %H:%M:%S +0000")
+        .to_string();
+    let mut headers = BTreeMap::new();
+    headers.insert("date".to_string(), stale.clone());
+    let sts = sigv2::string_to_sign("GET", "/", &[], &headers);
+    let resp = client
+        .get(format!("{}/", server.base_url))
+        .header("date", &stale)
+        .header("authorization", format!("AWS AKIDSIGV2TEST:{}", sigv2::sign("sigv2-secret", &sts)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("<Code>RequestTimeTooSkewed</Code>"));
+}
+
+#[tokio::test]
+async fn test_credential_permission_mask() {
+    use simples3_core::auth::sigv2;
+    use simples3_core::s3::types::Permission;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server
+        .metadata
+        .create_credential("AKIDREADONLY", "readonly-secret", "reader")
+        .unwrap();
+    server
+        .metadata
+        .set_credential_permissions("AKIDREADONLY", Some(BTreeSet::from([Permission::Read])))
+        .unwrap();
+    server.metadata.create_bucket("masked-bucket").unwrap();
+
+    let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S +0000").to_string();
+    let sign = |method: &str, path: &str| {
+        let mut headers = BTreeMap::new();
+        headers.insert("date".to_string(), date.clone());
+        let sts = sigv2::string_to_sign(method, path, &[], &headers);
+        format!("AWS AKIDREADONLY:{}", sigv2::sign("readonly-secret", &sts))
+    };
+
+    // Listing is a read operation
+    let resp = client
+        .get(format!("{}/masked-bucket", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("GET", "/masked-bucket"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Uploads need the write permission
+    let resp = client
+        .put(format!("{}/masked-bucket/file.txt", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("PUT", "/masked-bucket/file.txt"))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Lifting the mask restores full access
+    server
+        .metadata
+        .set_credential_permissions("AKIDREADONLY", None)
+        .unwrap();
+    let resp = client
+        .put(format!("{}/masked-bucket/file.txt", server.base_url))
+        .header("date", &date)
+        .header("authorization", sign("PUT", "/masked-bucket/file.txt"))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_credential_bound_to_bucket() {
+    use simples3_core::auth::sigv2;
+    use std::collections::BTreeMap;
+
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server.metadata.create_bucket("team-bucket").unwrap();
+    server.metadata.create_bucket("other-bucket").unwrap();
+    server.metadata.create_credential("AKIDBOUND", "bound-secret", "team").unwrap();
+    server.metadata.set_credential_bucket("AKIDBOUND", Some("team-bucket")).unwrap();
+
+    let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S +0000").to_string();
+    let send = |method: reqwest::Method, path: &str, copy_source: Option<&str>| {
+        let mut headers = BTreeMap::new();
+        headers.insert("date".to_string(), date.clone());
+        if let Some(source) = copy_source {
+            headers.insert("x-amz-copy-source".to_string(), source.to_string());
+        }
+        let sts = sigv2::string_to_sign(method.as_str(), path, &[], &headers);
+        let mut request = client
+            .request(method, format!("{}{}", server.base_url, path))
+            .header("date", &date)
+            .header("authorization", format!("AWS AKIDBOUND:{}", sigv2::sign("bound-secret", &sts)));
+        if let Some(source) = copy_source {
+            request = request.header("x-amz-copy-source", source);
+        }
+        request.send()
+    };
+
+    let resp = send(reqwest::Method::PUT, "/team-bucket/a.txt", None).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = send(reqwest::Method::GET, "/team-bucket", None).await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Other buckets, listing buckets and copying in from elsewhere are denied
+    let resp = send(reqwest::Method::PUT, "/other-bucket/a.txt", None).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = send(reqwest::Method::GET, "/", None).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = send(reqwest::Method::PUT, "/team-bucket/b.txt", Some("/other-bucket/a.txt")).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = send(reqwest::Method::PUT, "/team-bucket/b.txt", Some("/team-bucket/a.txt")).await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_credential_rate_limit() {
+    use simples3_core::auth::sigv2;
+    use std::collections::BTreeMap;
+
+    let server = TestServer::start_with_config(|c| c.admin_token = Some("limit-admin".into())).await;
+    let client = reqwest::Client::new();
+    server.metadata.create_credential("AKIDNOISY", "noisy-secret", "noisy").unwrap();
+    server.metadata.create_bucket("limited-bucket").unwrap();
+
+    let resp = client
+        .put(format!("{}/_admin/credentials/AKIDNOISY/rate-limit", server.admin_base_url))
+        .bearer_auth("limit-admin")
+        .json(&serde_json::json!({ "rate_limit": { "requests_per_sec": 1 } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let info: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(info["rate_limit"]["requests_per_sec"], 1);
+
+    let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S +0000").to_string();
+    let request = |method: &str, path: &str, access_key: &str, secret: &str| {
+        let mut headers = BTreeMap::new();
+        headers.insert("date".to_string(), date.clone());
+        let sts = sigv2::string_to_sign(method, path, &[], &headers);
+        client
+            .request(method.parse().unwrap(), format!("{}{}", server.base_url, path))
+            .header("date", &date)
+            .header("authorization", format!("AWS {}:{}", access_key, sigv2::sign(secret, &sts)))
+    };
+
+    let resp = request("GET", "/limited-bucket", "AKIDNOISY", "noisy-secret").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = request("GET", "/limited-bucket", "AKIDNOISY", "noisy-secret").send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers()["retry-after"], "1");
+
+    // Other credentials are not throttled
+    for _ in 0..3 {
+        let resp = request("GET", "/limited-bucket", "TESTAKID", "TESTSECRET").send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    // A transfer over the byte rate delays the next request
+    server
+        .metadata
+        .set_credential_rate_limit(
+            "AKIDNOISY",
+            Some(simples3_core::s3::types::CredentialRateLimit {
+                requests_per_sec: None,
+                bytes_per_sec: Some(4),
+            }),
+        )
+        .unwrap();
+    let resp = request("PUT", "/limited-bucket/big.txt", "AKIDNOISY", "noisy-secret")
+        .body("0123456789abcdef")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = request("GET", "/limited-bucket", "AKIDNOISY", "noisy-secret").send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers()["retry-after"], "4");
+
+    let resp = client
+        .put(format!("{}/_admin/credentials/AKIDNOISY/rate-limit", server.admin_base_url))
+        .bearer_auth("limit-admin")
+        .json(&serde_json::json!({ "rate_limit": null }))
+        .send()
+        .await
+        .unwrap();
+    let info: serde_json::Value = resp.json().await.unwrap();
+    assert!(info.get("rate_limit").is_none());
+    let resp = request("GET", "/limited-bucket", "AKIDNOISY", "noisy-secret").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_access_denials_reported_to_security_webhook() {
+    use std::sync::{Arc, Mutex};
+
+    // Webhook receiver
+    let received: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    let sink = received.clone();
+    let receiver = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+            let sink = sink.clone();
+            async move {
+                sink.lock().unwrap().push(body);
+                "ok"
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let hook_addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some("tok".into());
+        c.security_webhook_interval_secs = 1;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    server.metadata.create_bucket("guarded").unwrap();
+    server.metadata.create_bucket("unguarded").unwrap();
+
+    let resp = client
+        .put(format!("{}/_admin/buckets/guarded/security-webhook", server.admin_base_url))
+        .header("Authorization", "Bearer tok")
+        .json(&serde_json::json!({ "url": format!("http://{}/hook", hook_addr) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    for _ in 0..3 {
+        let resp = client.get(format!("{}/guarded", server.base_url)).send().await.unwrap();
+        assert_eq!(resp.status(), 403);
+    }
+    let resp = client
+        .get(format!("{}/guarded/key", server.base_url))
+        .header("Authorization", "AWS PROBEKEY:c2lnbmF0dXJl")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    client.get(format!("{}/unguarded", server.base_url)).send().await.unwrap();
+
+    // Requests may straddle a delivery tick, so add up all deliveries
+    let total = |summaries: &[serde_json::Value], field: &str, key: &str| -> u64 {
+        summaries.iter().map(|s| s[field][key].as_u64().unwrap_or(0)).sum()
+    };
+    let mut summaries = Vec::new();
+    for _ in 0..50 {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        summaries = received.lock().unwrap().clone();
+        if summaries.iter().map(|s| s["total"].as_u64().unwrap()).sum::<u64>() >= 4 {
+            break;
+        }
+    }
+    assert!(summaries.iter().all(|s| s["bucket"] == "guarded"));
+    assert_eq!(summaries.iter().map(|s| s["total"].as_u64().unwrap()).sum::<u64>(), 4);
+    assert_eq!(total(&summaries, "by_access_key", "anonymous"), 3);
+    assert_eq!(total(&summaries, "by_access_key", "PROBEKEY"), 1);
+    assert_eq!(total(&summaries, "by_source_ip", "127.0.0.1"), 4);
+}
+
+#[tokio::test]
+async fn test_sigv4_wrong_region_rejected_before_signature_check() {
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(&server.base_url)
+        .header(
+            "Authorization",
+            "AWS4-HMAC-SHA256 Credential=TESTAKID/20260101/ap-south-1/s3/aws4_request, SignedHeaders=host, Signature=00",
+        )
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Code>AuthorizationHeaderMalformed</Code>"));
+    assert!(body.contains("ap-south-1"));
+    assert!(body.contains("<Region>us-east-1</Region>"));
+}
@@ -20,6 +20,12 @@ async fn test_create_and_list_buckets() {
     assert_eq!(resp.status(), 200);
     let body = resp.text().await.unwrap();
     assert!(body.contains("<Name>test-bucket</Name>"));
+    assert!(body.contains("<ID>anonymous</ID>"));
+
+    // CreationDate has millisecond precision, e.g. 2024-05-01T12:30:45.123Z
+    let date = body.split("<CreationDate>").nth(1).unwrap().split("</CreationDate>").next().unwrap();
+    assert_eq!(date.len(), "2024-05-01T12:30:45.123Z".len());
+    assert!(date.ends_with('Z'));
 }
 
 #[tokio::test]
@@ -101,3 +107,101 @@ async fn test_delete_nonempty_bucket_returns_409() {
         .unwrap();
     assert_eq!(resp.status(), 409);
 }
+
+#[tokio::test]
+async fn test_list_buckets_prefix_and_pagination() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+
+    for name in ["team-b", "other", "team-a"] {
+        let resp = client
+            .put(format!("{}/{}", server.base_url, name))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    let resp = client
+        .get(format!("{}/?prefix=team-&max-buckets=1", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Name>team-a</Name>"));
+    assert!(!body.contains("<Name>team-b</Name>"));
+    assert!(!body.contains("<Name>other</Name>"));
+    assert!(body.contains("<ContinuationToken>team-a</ContinuationToken>"));
+    assert!(body.contains("<Prefix>team-</Prefix>"));
+
+    let resp = client
+        .get(format!(
+            "{}/?prefix=team-&max-buckets=1&continuation-token=team-a",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Name>team-b</Name>"));
+    assert!(!body.contains("<ContinuationToken>"));
+
+    let resp = client
+        .get(format!("{}/?max-buckets=0", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_bucket_acl_and_canned_acl_on_create() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .put(format!("{}/acl-bucket", server.base_url))
+        .header("x-amz-acl", "public-read")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(server.metadata.get_bucket("acl-bucket").unwrap().anonymous_read);
+
+    let resp = client
+        .get(format!("{}/acl-bucket?acl", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<AccessControlPolicy"));
+    assert!(body.contains("AllUsers"));
+
+    let resp = client
+        .put(format!("{}/acl-bucket?acl", server.base_url))
+        .header("x-amz-acl", "private")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!server.metadata.get_bucket("acl-bucket").unwrap().anonymous_read);
+
+    let resp = client
+        .get(format!("{}/acl-bucket?acl", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert!(!resp.text().await.unwrap().contains("AllUsers"));
+
+    // Unsupported canned ACLs are rejected before the bucket is created
+    let resp = client
+        .put(format!("{}/acl-bucket-2", server.base_url))
+        .header("x-amz-acl", "authenticated-read")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(server.metadata.get_bucket("acl-bucket-2").is_err());
+}
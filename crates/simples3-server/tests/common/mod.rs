@@ -1,5 +1,7 @@
+#![allow(dead_code)]
+
 use simples3_core::Config;
-use simples3_core::storage::{FileStore, MetadataStore};
+use simples3_core::storage::{MetadataStore, StorageRouter};
 use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
@@ -10,36 +12,43 @@ pub struct TestServer {
     pub admin_addr: SocketAddr,
     pub admin_base_url: String,
     pub metadata: MetadataStore,
+    pub state: Arc<simples3_server::AppState>,
     _data_dir: tempfile::TempDir,
     _metadata_dir: tempfile::TempDir,
 }
 
 impl TestServer {
     pub async fn start() -> Self {
-        Self::start_inner(false, None, None).await
+        Self::start_inner(false, None, None, |_| {}).await
     }
 
     pub async fn start_anonymous() -> Self {
-        Self::start_inner(true, None, None).await
+        Self::start_inner(true, None, None, |_| {}).await
     }
 
     pub async fn start_with_admin_token(token: &str) -> Self {
-        Self::start_inner(false, Some(token.to_string()), None).await
+        Self::start_inner(false, Some(token.to_string()), None, |_| {}).await
     }
 
     pub async fn start_with_init_config(init_config_path: &Path) -> Self {
-        Self::start_inner(false, Some("init-admin-token".into()), Some(init_config_path.to_path_buf())).await
+        Self::start_inner(false, Some("init-admin-token".into()), Some(init_config_path.to_path_buf()), |_| {}).await
+    }
+
+    /// Start a server with the default test configuration adjusted by `configure`.
+    pub async fn start_with_config(configure: impl FnOnce(&mut Config)) -> Self {
+        Self::start_inner(false, None, None, configure).await
     }
 
     async fn start_inner(
         anonymous_global: bool,
         admin_token: Option<String>,
         init_config_path: Option<std::path::PathBuf>,
+        configure: impl FnOnce(&mut Config),
     ) -> Self {
         let data_dir = tempfile::tempdir().unwrap();
         let metadata_dir = tempfile::tempdir().unwrap();
 
-        let config = Config {
+        let mut config = Config {
             bind: "127.0.0.1:0".into(),
             data_dir: data_dir.path().to_path_buf(),
             metadata_dir: metadata_dir.path().to_path_buf(),
@@ -53,14 +62,61 @@ impl TestServer {
             multipart_ttl_secs: 86400,
             multipart_cleanup_interval_secs: 3600,
             lifecycle_scan_interval_secs: 0,
+            tagging_gc_interval_secs: 0,
             cors_origins: None,
             max_object_size: 5 * 1024 * 1024 * 1024,
+            io_threads: 0,
             max_xml_body_size: 256 * 1024,
             max_policy_body_size: 20 * 1024,
+            buffered_bytes_high_watermark: 0,
+            max_concurrent_transfers: 0,
+            rate_limit_rps: 0,
+            rate_limit_burst: 0,
+            rate_limit_per_ip_rps: 0,
+            rate_limit_per_ip_burst: 0,
+            read_timeout_secs: 0,
+            write_timeout_secs: 0,
+            shutdown_timeout_secs: 30,
+            bucket_delete_grace_secs: 0,
+            list_cache_entries: 256,
+            warm_up: false,
+            escape_object_paths: false,
+            md5_skip_threshold: None,
+            security_webhook_interval_secs: 60,
+            access_log_interval_secs: 300,
+            access_log: None,
+            access_log_format: "clf".into(),
+            access_log_max_size: 100 * 1024 * 1024,
+            access_log_max_files: 5,
+            replication_interval_secs: 5,
+            usage_interval_secs: 300,
+            usage_alert_interval_secs: 60,
+            accepted_regions: None,
+            storage_backends: Vec::new(),
+            scan_endpoint: None,
+            rendition_command: None,
+            tls_cert: None,
+            tls_key: None,
+            acme_enabled: false,
+            acme_directory: String::new(),
+            acme_email: None,
+            admin_client_ca: None,
+            clock_control: false,
+            metadata_probe_interval_secs: 5,
+            metadata_failure_threshold: 3,
+            snapshot_target: None,
+            snapshot_interval_secs: 3600,
+            snapshot_s3_endpoint: None,
+            snapshot_s3_region: "us-east-1".into(),
+            snapshot_s3_access_key: None,
+            snapshot_s3_secret_key: None,
         };
+        configure(&mut config);
 
-        let metadata = MetadataStore::open(&config.metadata_dir).unwrap();
-        let filestore = FileStore::new(&config.data_dir);
+        let metadata = MetadataStore::open(&config.metadata_dir)
+            .unwrap()
+            .with_list_cache(config.list_cache_entries);
+        let storage = StorageRouter::from_config(&config, metadata.clone()).unwrap();
 
         if let Some(ref path) = init_config_path {
             let init_cfg = simples3_core::init::load(path).expect("Failed to load init config");
@@ -71,22 +127,84 @@ impl TestServer {
         let _ = metadata.create_credential("TESTAKID", "TESTSECRET", "test");
 
         let metrics_handle = simples3_server::metrics::init_metrics();
+        let log_level = simples3_server::logging::init_logging("off");
 
+        let server_access_log = simples3_server::server_access_log::ServerAccessLog::from_config(&config).unwrap();
+        let runtime_config = simples3_server::reload::RuntimeConfig::new(&config);
         let state = Arc::new(simples3_server::AppState {
             config,
             metadata: metadata.clone(),
-            filestore,
+            storage,
             start_time: std::time::Instant::now(),
             metrics_handle,
+            log_level,
+            security_webhooks: Default::default(),
+            access_logs: Default::default(),
+            server_access_log,
+            notifications: Default::default(),
+            load: Default::default(),
+            connections: Default::default(),
+            hooks: Default::default(),
+            renditions: Default::default(),
+            sync_jobs: Default::default(),
+            clock: Default::default(),
+            rate_limiter: Default::default(),
+            metadata_health: Default::default(),
+            usage: Default::default(),
+            readiness: Default::default(),
+            runtime_config,
         });
 
+        let readiness = &state.readiness;
+        readiness.spawn(
+            "security_webhooks",
+            simples3_server::security_webhook::delivery_loop(state.clone(), state.config.security_webhook_interval_secs),
+        );
+        readiness.spawn(
+            "access_log",
+            simples3_server::access_log::flush_loop(state.clone(), state.config.access_log_interval_secs),
+        );
+        readiness.spawn("notifications", simples3_server::notifications::delivery_loop(state.clone()));
+        readiness.spawn("metadata_probe", simples3_server::metadata_health::probe_loop(state.clone()));
+        readiness.spawn(
+            "replication",
+            simples3_server::replication::replication_loop(state.clone(), state.config.replication_interval_secs),
+        );
+        readiness.spawn(
+            "usage",
+            simples3_server::usage::usage_loop(state.clone(), state.config.usage_interval_secs),
+        );
+        readiness.spawn(
+            "usage_alerts",
+            simples3_server::usage_alerts::alert_loop(state.clone(), state.config.usage_alert_interval_secs),
+        );
+
+        if let Some(target) = simples3_server::scanning::ScanTarget::from_config(&state.config).unwrap() {
+            simples3_server::scanning::start(&state, target);
+        }
+        if let Some(transformer) =
+            simples3_server::renditions::CommandTransformer::from_config(&state.config).unwrap()
+        {
+            simples3_server::renditions::enable(&state, transformer);
+        }
+
         let s3_app = simples3_server::router::build_s3_router(state.clone());
         let s3_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = s3_listener.local_addr().unwrap();
+        let s3_listener = simples3_server::load::track(s3_listener, state.load.clone());
+        let tls = match simples3_server::acme::start(&state.config).unwrap() {
+            Some(acme) => Some(acme.server_config),
+            None => simples3_server::tls::server_config(&state.config).unwrap(),
+        };
+        // Test certificates are issued for localhost
+        let (scheme, host) = if tls.is_some() { ("https", "localhost") } else { ("http", "127.0.0.1") };
+        let s3_listener = simples3_server::tls::listen(s3_listener, tls.clone());
 
-        let admin_app = simples3_server::router::build_admin_router(state);
+        let admin_app = simples3_server::router::build_admin_router(state.clone());
         let admin_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
         let admin_addr = admin_listener.local_addr().unwrap();
+        let admin_tls = simples3_server::tls::admin_server_config(&state.config, tls).unwrap();
+        let admin_listener = simples3_server::tls::listen_admin(admin_listener, admin_tls);
 
         tokio::spawn(async move {
             axum::serve(s3_listener, s3_app.into_make_service_with_connect_info::<SocketAddr>())
@@ -95,17 +213,18 @@ impl TestServer {
         });
 
         tokio::spawn(async move {
-            axum::serve(admin_listener, admin_app.into_make_service_with_connect_info::<SocketAddr>())
+            axum::serve(admin_listener, admin_app.into_make_service_with_connect_info::<simples3_server::tls::TlsConnectInfo>())
                 .await
                 .unwrap();
         });
 
         Self {
-            base_url: format!("http://{}", addr),
+            base_url: format!("{}://{}:{}", scheme, host, addr.port()),
             addr,
-            admin_base_url: format!("http://{}", admin_addr),
+            admin_base_url: format!("{}://{}:{}", scheme, host, admin_addr.port()),
             admin_addr,
             metadata,
+            state,
             _data_dir: data_dir,
             _metadata_dir: metadata_dir,
         }
@@ -30,6 +30,48 @@ async fn test_ready_endpoint() {
     assert_eq!(resp.text().await.unwrap(), "ready");
 }
 
+#[tokio::test]
+async fn test_healthz_and_readyz() {
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+
+    let resp = client.get(format!("{}/healthz", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["status"], "alive");
+
+    let resp = client.get(format!("{}/readyz", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["ready"], true);
+    assert_eq!(body["draining"], false);
+    assert!(body["checks"].as_array().unwrap().iter().all(|c| c["ok"] == true));
+    assert_eq!(body["tasks"]["notifications"], "running");
+
+    // A background loop that dies fails readiness
+    server.state.readiness.spawn("broken", async { panic!("loop failed") });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let resp = client.get(format!("{}/readyz", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["tasks"]["broken"], "panicked");
+    let failed: Vec<_> = body["checks"].as_array().unwrap().iter().filter(|c| c["ok"] == false).collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["name"], "background_tasks");
+
+    // Draining for shutdown
+    server.state.readiness.start_draining();
+    let resp = client.get(format!("{}/readyz", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["draining"], true);
+    let resp = client.get(format!("{}/ready", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.text().await.unwrap(), "shutting down");
+    let resp = client.get(format!("{}/healthz", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
 #[tokio::test]
 async fn test_metrics_endpoint() {
     let server = TestServer::start().await;
@@ -115,3 +157,418 @@ async fn test_metrics_request_counters() {
     assert!(body.contains("s3_requests_total"));
     assert!(body.contains("s3_request_duration_seconds"));
 }
+
+#[tokio::test]
+async fn test_request_duration_by_bucket_and_status_class() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/latency-bucket", server.base_url)).send().await.unwrap();
+    client
+        .put(format!("{}/latency-bucket/k", server.base_url))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    let resp = client
+        .get(format!("{}/latency-missing-bucket/k", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let body = client
+        .get(format!("{}/metrics", server.admin_base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    let series = |labels: &[&str]| {
+        body.lines().any(|line| {
+            line.starts_with("s3_request_duration_seconds_bucket{") && labels.iter().all(|l| line.contains(l))
+        })
+    };
+    assert!(series(&["operation=\"PutObject\"", "bucket=\"latency-bucket\"", "status_class=\"2xx\"", "le=\"0.005\""]));
+    assert!(series(&["operation=\"GetObject\"", "bucket=\"\"", "status_class=\"4xx\""]));
+    assert!(!body.contains("latency-missing-bucket"));
+}
+
+#[tokio::test]
+async fn test_bucket_storage_gauges() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/gauge-bucket", server.base_url)).send().await.unwrap();
+    for (key, body) in [("a", "12345"), ("b", "123"), ("a", "1234567")] {
+        client
+            .put(format!("{}/gauge-bucket/{}", server.base_url, key))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+    }
+    client.delete(format!("{}/gauge-bucket/b", server.base_url)).send().await.unwrap();
+
+    let body = client
+        .get(format!("{}/metrics", server.admin_base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("simples3_bucket_objects{bucket=\"gauge-bucket\"} 1"));
+    assert!(body.contains("simples3_bucket_bytes{bucket=\"gauge-bucket\"} 7"));
+}
+
+#[tokio::test]
+async fn test_slowdown_over_buffered_bytes_high_watermark() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.buffered_bytes_high_watermark = 8;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/busy", server.base_url)).send().await.unwrap();
+
+    // An upload that stalls halfway keeps its first bytes buffered
+    let mut stalled = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    stalled
+        .write_all(b"PUT /busy/slow.bin HTTP/1.1\r\nHost: localhost\r\nContent-Length: 32\r\n\r\n0123456789abcdef")
+        .await
+        .unwrap();
+
+    let mut rejected = None;
+    for _ in 0..100 {
+        let resp = client.get(format!("{}/busy?list-type=2", server.base_url)).send().await.unwrap();
+        if resp.status() == 503 {
+            rejected = Some(resp.text().await.unwrap());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(rejected.expect("no SlowDown while over the watermark").contains("<Code>SlowDown</Code>"));
+
+    let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("simples3_buffered_body_bytes"));
+    assert!(body.contains("simples3_requests_in_flight{operation=\"PutObject\"}"));
+    assert!(body.contains("simples3_connections_open"));
+    assert!(body.contains("simples3_slowdown_responses_total"));
+
+    // Once the upload completes its buffer is released
+    stalled.write_all(b"0123456789abcdef").await.unwrap();
+    let mut response = [0u8; 12];
+    stalled.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"HTTP/1.1 200");
+    let resp = client.get(format!("{}/busy/slow.bin", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text().await.unwrap().len(), 32);
+}
+
+#[tokio::test]
+async fn test_rate_limit_per_client() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.rate_limit_per_ip_rps = 1;
+        c.rate_limit_per_ip_burst = 3;
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    // The burst is accepted, then requests are turned away until tokens refill
+    for _ in 0..3 {
+        let resp = client.get(format!("{}/no-such-bucket", server.base_url)).send().await.unwrap();
+        assert_eq!(resp.status(), 404);
+    }
+    let resp = client.get(format!("{}/no-such-bucket", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers()["retry-after"], "1");
+    assert!(resp.text().await.unwrap().contains("<Code>SlowDown</Code>"));
+
+    // Admin endpoints are not limited
+    let resp = client.get(format!("{}/health", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let resp = client.get(format!("{}/no-such-bucket", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("simples3_rate_limited_total{scope=\"client\"}"));
+}
+
+#[tokio::test]
+async fn test_rate_limit_client_tracking_is_bounded() {
+    use simples3_server::rate_limit::MAX_TRACKED_CLIENTS;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Instant;
+
+    let server = TestServer::start_with_config(|c| {
+        c.rate_limit_per_ip_rps = 1;
+        c.rate_limit_per_ip_burst = 1;
+    })
+    .await;
+    let limiter = &server.state.rate_limiter;
+    let tunables = server.state.runtime_config.tunables().clone();
+    let start = Instant::now();
+    let at = |n: u32| start + std::time::Duration::from_micros(n.into());
+    let ip = |n: u32| Some(IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + n)));
+
+    // Every client has drained its bucket, and the first one stays active
+    for n in 0..MAX_TRACKED_CLIENTS as u32 {
+        limiter.acquire(&tunables, ip(n), at(n)).unwrap();
+        assert!(limiter.acquire(&tunables, ip(0), at(n)).is_err());
+    }
+    assert_eq!(limiter.tracked_clients(), MAX_TRACKED_CLIENTS);
+
+    // A new client makes room by dropping the least recently used one
+    let later = at(MAX_TRACKED_CLIENTS as u32);
+    limiter.acquire(&tunables, ip(MAX_TRACKED_CLIENTS as u32), later).unwrap();
+    assert_eq!(limiter.tracked_clients(), MAX_TRACKED_CLIENTS);
+    assert!(limiter.acquire(&tunables, ip(0), later).is_err());
+    limiter.acquire(&tunables, ip(1), later).unwrap();
+}
+
+#[tokio::test]
+async fn test_slowdown_over_concurrent_transfers() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.max_concurrent_transfers = 1;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/transfers", server.base_url)).send().await.unwrap();
+
+    // A stalled upload holds the only transfer slot
+    let mut stalled = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    stalled
+        .write_all(b"PUT /transfers/slow.bin HTTP/1.1\r\nHost: localhost\r\nContent-Length: 8\r\n\r\n0123")
+        .await
+        .unwrap();
+    let mut rejected = None;
+    for _ in 0..100 {
+        let resp = client.put(format!("{}/transfers/other.bin", server.base_url)).body("x").send().await.unwrap();
+        if resp.status() == 503 {
+            rejected = Some(resp.text().await.unwrap());
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(rejected.expect("no SlowDown with all transfer slots taken").contains("<Code>SlowDown</Code>"));
+
+    // Requests not moving object data are not limited
+    let resp = client.get(format!("{}/transfers?list-type=2", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("simples3_transfers_in_flight 1"));
+    assert!(body.contains("simples3_slowdown_responses_total{reason=\"transfers\"}"));
+
+    // The slot is free again once the upload completes
+    stalled.write_all(b"4567").await.unwrap();
+    let mut response = [0u8; 12];
+    stalled.read_exact(&mut response).await.unwrap();
+    assert_eq!(&response, b"HTTP/1.1 200");
+    let resp = client.put(format!("{}/transfers/other.bin", server.base_url)).body("x").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/transfers/other.bin", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "x");
+    for _ in 0..50 {
+        if server.state.load.transfers() == 0 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(server.state.load.transfers(), 0);
+}
+
+#[tokio::test]
+async fn test_slowdown_over_concurrent_transfers_virtual_host() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.max_concurrent_transfers = 1;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let host = format!("vh-transfers.s3.localhost:{}", server.addr.port());
+    client.put(format!("{}/vh-transfers", server.base_url)).send().await.unwrap();
+
+    // A stalled virtual-host upload holds the only transfer slot
+    let mut stalled = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    stalled
+        .write_all(format!("PUT /slow.bin HTTP/1.1\r\nHost: {}\r\nContent-Length: 8\r\n\r\n0123", host).as_bytes())
+        .await
+        .unwrap();
+    let mut rejected = false;
+    for _ in 0..100 {
+        let resp = client
+            .put(format!("http://{}/other.bin", server.addr))
+            .header("host", &host)
+            .body("x")
+            .send()
+            .await
+            .unwrap();
+        if resp.status() == 503 {
+            rejected = true;
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(rejected, "no SlowDown with all transfer slots taken");
+    assert_eq!(server.state.load.transfers(), 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit_global() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.rate_limit_rps = 1;
+        c.rate_limit_burst = 2;
+        c.rate_limit_per_ip_rps = 100;
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let resp = client.get(format!("{}/no-such-bucket", server.base_url)).send().await.unwrap();
+        statuses.push(resp.status().as_u16());
+    }
+    assert_eq!(statuses, vec![404, 404, 503]);
+
+    // JSON errors keep the Retry-After header
+    let resp = client
+        .get(format!("{}/no-such-bucket", server.base_url))
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 503);
+    assert!(resp.headers().contains_key("retry-after"));
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["code"], "SlowDown");
+}
+
+#[tokio::test]
+async fn test_degraded_metadata_store() {
+    use simples3_core::S3Error;
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("health-admin".into());
+        // Probes are driven by the test
+        c.metadata_probe_interval_secs = 0;
+        c.metadata_failure_threshold = 2;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let health = &server.state.metadata_health;
+    client.put(format!("{}/degraded", server.base_url)).send().await.unwrap();
+    client.put(format!("{}/degraded/a.txt", server.base_url)).body("a").send().await.unwrap();
+
+    // A single failure is not enough
+    health.record_probe(Err(S3Error::InternalError("IO error: disk full".into())), 2);
+    let resp = client.get(format!("{}/ready", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    health.record_probe(Err(S3Error::InternalError("IO error: disk full".into())), 2);
+    let resp = client.get(format!("{}/ready", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert!(resp.text().await.unwrap().contains("disk full"));
+
+    // Writes fail fast, reads are still served
+    let resp = client.put(format!("{}/degraded/b.txt", server.base_url)).body("b").send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    assert_eq!(resp.headers()["retry-after"], "1");
+    assert!(resp.text().await.unwrap().contains("<Code>ServiceUnavailable</Code>"));
+    let resp = client.delete(format!("{}/degraded/a.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 503);
+    let resp = client.get(format!("{}/degraded/a.txt", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/degraded?list-type=2", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let info: serde_json::Value = client
+        .get(format!("{}/_admin/info", server.admin_base_url))
+        .bearer_auth("health-admin")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(info["metadata_health"]["degraded_since"].is_string());
+    assert_eq!(info["metadata_health"]["consecutive_failures"], 2);
+    let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+    assert!(resp.text().await.unwrap().contains("simples3_metadata_degraded 1"));
+
+    // One successful probe ends the degraded mode
+    health.record_probe(Ok(()), 2);
+    let resp = client.get(format!("{}/ready", server.admin_base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.put(format!("{}/degraded/b.txt", server.base_url)).body("b").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_request_timeout() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("timeout-admin".into());
+        c.write_timeout_secs = 1;
+        c.read_timeout_secs = 60;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/timeouts", server.base_url)).send().await.unwrap();
+
+    // An upload whose client stops sending the body is cut off
+    let mut stream = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    let request = format!(
+        "PUT /timeouts/stalled.txt HTTP/1.1\r\nHost: {}\r\nContent-Length: 100\r\n\r\npartial",
+        server.addr
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = vec![0u8; 4096];
+    let n = tokio::time::timeout(std::time::Duration::from_secs(10), stream.read(&mut response))
+        .await
+        .expect("No response after the write timeout")
+        .unwrap();
+    let response = String::from_utf8_lossy(&response[..n]);
+    assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    assert!(response.contains("<Code>RequestTimeout</Code>"));
+    assert!(server.metadata.get_object_meta("timeouts", "stalled.txt").is_err());
+
+    // Virtual-host uploads get the write budget too
+    let mut stream = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    let request = format!(
+        "PUT /stalled.txt HTTP/1.1\r\nHost: timeouts.s3.localhost:{}\r\nContent-Length: 100\r\n\r\npartial",
+        server.addr.port()
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = vec![0u8; 4096];
+    let n = tokio::time::timeout(std::time::Duration::from_secs(10), stream.read(&mut response))
+        .await
+        .expect("No response after the write timeout")
+        .unwrap();
+    assert!(String::from_utf8_lossy(&response[..n]).starts_with("HTTP/1.1 400"));
+
+    // Reads have their own budget
+    let resp = client.get(format!("{}/timeouts", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("simples3_request_timeouts_total{operation=\"PutObject\"} 2"));
+}
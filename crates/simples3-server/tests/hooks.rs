@@ -0,0 +1,98 @@
+mod common;
+
+use common::TestServer;
+use simples3_server::hooks::{AuthDecision, Hook, ObjectEvent};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Hook for Recorder {
+    fn on_object_created(&self, event: &ObjectEvent<'_>) {
+        self.events.lock().unwrap().push(format!(
+            "created {} {}/{} {}",
+            event.event,
+            event.bucket,
+            event.key,
+            event.size.unwrap_or_default()
+        ));
+    }
+
+    fn on_object_deleted(&self, event: &ObjectEvent<'_>) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("deleted {} {}/{}", event.event, event.bucket, event.key));
+    }
+
+    fn on_auth_decision(&self, decision: &AuthDecision<'_>) {
+        let operation = decision.operation.map(|op| op.name()).unwrap_or("-");
+        let outcome = match decision.error {
+            None => "allowed".to_string(),
+            Some(e) => e.code().to_string(),
+        };
+        self.events.lock().unwrap().push(format!("auth {} {}", operation, outcome));
+    }
+}
+
+#[tokio::test]
+async fn test_hooks_object_events() {
+    let server = TestServer::start_anonymous().await;
+    let recorder = Recorder::default();
+    server.state.hooks.register(recorder.clone());
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/hooks-bucket", base)).send().await.unwrap();
+    client
+        .put(format!("{}/hooks-bucket/a.txt", base))
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    client
+        .put(format!("{}/hooks-bucket/b.txt", base))
+        .header("x-amz-copy-source", "/hooks-bucket/a.txt")
+        .send()
+        .await
+        .unwrap();
+    client.delete(format!("{}/hooks-bucket/a.txt", base)).send().await.unwrap();
+
+    let events: Vec<String> = recorder
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|e| !e.starts_with("auth "))
+        .cloned()
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            "created ObjectCreated:Put hooks-bucket/a.txt 5",
+            "created ObjectCreated:Copy hooks-bucket/b.txt 5",
+            "deleted ObjectRemoved:Delete hooks-bucket/a.txt",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_hooks_auth_decision() {
+    let server = TestServer::start().await;
+    let recorder = Recorder::default();
+    server.state.hooks.register(recorder.clone());
+    server.metadata.create_bucket("hooks-auth").unwrap();
+    let client = reqwest::Client::new();
+
+    // Unsigned request on a private bucket
+    let resp = client
+        .get(format!("{}/hooks-auth/key", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    assert_eq!(*recorder.events.lock().unwrap(), vec!["auth GetObject AccessDenied"]);
+}
@@ -76,3 +76,356 @@ async fn test_lifecycle_nonexistent_bucket() {
         .unwrap();
     assert_eq!(resp.status(), 404);
 }
+
+#[tokio::test]
+async fn test_lifecycle_body_limits_and_malformed_xml() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.max_xml_body_size = 512;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client
+        .put(format!("{}/limit-bucket", server.base_url))
+        .send()
+        .await
+        .unwrap();
+
+    let oversized = format!(
+        "<LifecycleConfiguration>{}</LifecycleConfiguration>",
+        "<Rule><Status>Enabled</Status></Rule>".repeat(64)
+    );
+    let resp = client
+        .put(format!("{}/limit-bucket?lifecycle", server.base_url))
+        .body(oversized)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains("<Code>EntityTooLarge</Code>"));
+
+    let resp = client
+        .put(format!("{}/limit-bucket?lifecycle", server.base_url))
+        .body("<LifecycleConfiguration><Rule></Status></LifecycleConfiguration>")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains("<Code>MalformedXML</Code>"));
+}
+
+#[tokio::test]
+async fn test_lifecycle_transitions() {
+    let cold_dir = tempfile::tempdir().unwrap();
+    let cold_path = cold_dir.path().to_path_buf();
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.storage_backends = vec![("COLD".into(), cold_path)];
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/tiered", server.base_url)).send().await.unwrap();
+
+    let rule = |class: &str| {
+        format!(
+            "<LifecycleConfiguration><Rule><ID>archive</ID><Filter><Prefix>logs/</Prefix></Filter><Status>Enabled</Status><Transition><Days>30</Days><StorageClass>{}</StorageClass></Transition></Rule></LifecycleConfiguration>",
+            class
+        )
+    };
+    for class in ["GLACIER", "default"] {
+        let resp = client
+            .put(format!("{}/tiered?lifecycle", server.base_url))
+            .body(rule(class))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400, "{}", class);
+    }
+    let resp = client
+        .put(format!("{}/tiered?lifecycle", server.base_url))
+        .body(rule("COLD"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = client
+        .get(format!("{}/tiered?lifecycle", server.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("<Transition><Days>30</Days><StorageClass>COLD</StorageClass></Transition>"));
+    assert!(!body.contains("<Expiration>"));
+
+    // Stand in for the scanner: move the object's data to COLD and record the class
+    let url = format!("{}/tiered/logs/a.log", server.base_url);
+    client.put(&url).body("hot").send().await.unwrap();
+    std::fs::create_dir_all(cold_dir.path().join("tiered/logs")).unwrap();
+    std::fs::write(cold_dir.path().join("tiered/logs/a.log"), "ice").unwrap();
+    let mut meta = server.metadata.get_object_meta("tiered", "logs/a.log").unwrap();
+    meta.storage_class = Some("COLD".into());
+    server.metadata.put_object_meta(&meta).unwrap();
+
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.headers()["x-amz-storage-class"], "COLD");
+    assert_eq!(resp.text().await.unwrap(), "ice");
+    let resp = client.head(&url).send().await.unwrap();
+    assert_eq!(resp.headers()["x-amz-storage-class"], "COLD");
+    let listing = client
+        .get(format!("{}/tiered?list-type=2", server.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(listing.contains("<StorageClass>COLD</StorageClass>"));
+
+    // Overwriting stores a STANDARD object and drops the cold copy
+    client.put(&url).body("new").send().await.unwrap();
+    assert!(!cold_dir.path().join("tiered/logs/a.log").exists());
+    let resp = client.get(&url).send().await.unwrap();
+    assert!(resp.headers().get("x-amz-storage-class").is_none());
+    assert_eq!(resp.text().await.unwrap(), "new");
+
+    // Deleting a transitioned object removes its cold data
+    std::fs::write(cold_dir.path().join("tiered/logs/a.log"), "ice").unwrap();
+    let mut meta = server.metadata.get_object_meta("tiered", "logs/a.log").unwrap();
+    meta.storage_class = Some("COLD".into());
+    server.metadata.put_object_meta(&meta).unwrap();
+    let resp = client.delete(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(!cold_dir.path().join("tiered/logs/a.log").exists());
+}
+
+#[tokio::test]
+async fn test_restore_object() {
+    let hot_dir = tempfile::tempdir().unwrap();
+    let cold_dir = tempfile::tempdir().unwrap();
+    let (hot, cold_path) = (hot_dir.path().to_path_buf(), cold_dir.path().to_path_buf());
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.data_dir = hot;
+        c.storage_backends = vec![("COLD".into(), cold_path)];
+    })
+    .await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/archive", server.base_url)).send().await.unwrap();
+    let url = format!("{}/archive/old.log", server.base_url);
+    let restore = |days: u32| {
+        client
+            .post(format!("{}?restore", url))
+            .body(format!("<RestoreRequest><Days>{}</Days></RestoreRequest>", days))
+            .send()
+    };
+
+    // A STANDARD object cannot be restored
+    client.put(&url).body("ice").send().await.unwrap();
+    let resp = restore(1).await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("<Code>InvalidObjectState</Code>"));
+
+    // Stand in for the scanner: move the object's data to COLD
+    let hot_path = hot_dir.path().join("archive/old.log");
+    std::fs::create_dir_all(cold_dir.path().join("archive")).unwrap();
+    std::fs::rename(&hot_path, cold_dir.path().join("archive/old.log")).unwrap();
+    let mut meta = server.metadata.get_object_meta("archive", "old.log").unwrap();
+    meta.storage_class = Some("COLD".into());
+    server.metadata.put_object_meta(&meta).unwrap();
+    let resp = client.head(&url).send().await.unwrap();
+    assert!(resp.headers().get("x-amz-restore").is_none());
+
+    let resp = restore(0).await.unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = restore(2).await.unwrap();
+    assert_eq!(resp.status(), 202);
+    assert_eq!(std::fs::read_to_string(&hot_path).unwrap(), "ice");
+
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.headers()["x-amz-storage-class"], "COLD");
+    let restore_header = resp.headers()["x-amz-restore"].to_str().unwrap().to_string();
+    assert!(restore_header.starts_with("ongoing-request=\"false\", expiry-date=\""));
+    assert_eq!(resp.text().await.unwrap(), "ice");
+
+    // Restoring again extends the existing copy
+    let resp = restore(5).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.head(&url).send().await.unwrap();
+    assert_ne!(resp.headers()["x-amz-restore"].to_str().unwrap(), restore_header);
+
+    let resp = client
+        .post(format!("{}/archive/missing.log?restore", server.base_url))
+        .body("<RestoreRequest><Days>1</Days></RestoreRequest>")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_lifecycle_abort_incomplete_multipart_upload() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    client.put(format!("{}/uploads-bucket", server.base_url)).send().await.unwrap();
+
+    let xml = "<LifecycleConfiguration><Rule><ID>abort</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><AbortIncompleteMultipartUpload><DaysAfterInitiation>3</DaysAfterInitiation></AbortIncompleteMultipartUpload></Rule></LifecycleConfiguration>";
+    let resp = client
+        .put(format!("{}/uploads-bucket?lifecycle", server.base_url))
+        .body(xml)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = client
+        .get(format!("{}/uploads-bucket?lifecycle", server.base_url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(body.contains("<DaysAfterInitiation>3</DaysAfterInitiation>"));
+
+    let resp = client
+        .put(format!("{}/uploads-bucket?lifecycle", server.base_url))
+        .body(xml.replace(">3<", ">0<"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_admin_lifecycle_run() {
+    const ADMIN_TOKEN: &str = "test-admin-token";
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let run = |query: &'static str| {
+        client
+            .post(format!("{}/_admin/lifecycle/run{}", server.admin_base_url, query))
+            .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+            .send()
+    };
+
+    for bucket in ["expiring-bucket", "other-bucket"] {
+        client.put(format!("{}/{}", server.base_url, bucket)).send().await.unwrap();
+        client.put(format!("{}/{}/tmp/a.txt", server.base_url, bucket)).body("a").send().await.unwrap();
+        client.put(format!("{}/{}/keep.txt", server.base_url, bucket)).body("k").send().await.unwrap();
+        let xml = "<LifecycleConfiguration><Rule><ID>expire-tmp</ID><Filter><Prefix>tmp/</Prefix></Filter><Status>Enabled</Status><Expiration><Date>2020-01-01T00:00:00+00:00</Date></Expiration></Rule></LifecycleConfiguration>";
+        let resp = client
+            .put(format!("{}/{}?lifecycle", server.base_url, bucket))
+            .body(xml)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    // A dry run lists the keys without deleting them
+    let resp = run("?bucket=expiring-bucket&dry_run=true").await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["dry_run"], true);
+    let expired = body["expired"].as_array().unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0]["bucket"], "expiring-bucket");
+    assert_eq!(expired[0]["key"], "tmp/a.txt");
+    assert_eq!(expired[0]["rule_id"], "expire-tmp");
+    assert!(server.metadata.get_object_meta("expiring-bucket", "tmp/a.txt").is_ok());
+
+    // A scoped run only touches that bucket
+    let resp = run("?bucket=expiring-bucket").await.unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["expired"].as_array().unwrap().len(), 1);
+    assert!(server.metadata.get_object_meta("expiring-bucket", "tmp/a.txt").is_err());
+    assert!(server.metadata.get_object_meta("expiring-bucket", "keep.txt").is_ok());
+    assert!(server.metadata.get_object_meta("other-bucket", "tmp/a.txt").is_ok());
+
+    let resp = run("").await.unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["expired"][0]["bucket"], "other-bucket");
+    assert!(server.metadata.get_object_meta("other-bucket", "tmp/a.txt").is_err());
+
+    let resp = run("?bucket=missing-bucket").await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_lifecycle_with_frozen_clock() {
+    const ADMIN_TOKEN: &str = "clock-admin";
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.clock_control = true;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let run = || {
+        client
+            .post(format!("{}/_admin/lifecycle/run?bucket=clock-bucket", server.admin_base_url))
+            .bearer_auth(ADMIN_TOKEN)
+            .send()
+    };
+
+    client.put(format!("{}/clock-bucket", server.base_url)).send().await.unwrap();
+    client.put(format!("{}/clock-bucket/a.txt", server.base_url)).body("a").send().await.unwrap();
+    let xml = "<LifecycleConfiguration><Rule><ID>expire-1d</ID><Filter><Prefix></Prefix></Filter><Status>Enabled</Status><Expiration><Days>1</Days></Expiration></Rule></LifecycleConfiguration>";
+    client
+        .put(format!("{}/clock-bucket?lifecycle", server.base_url))
+        .body(xml)
+        .send()
+        .await
+        .unwrap();
+
+    let body: serde_json::Value = run().await.unwrap().json().await.unwrap();
+    assert!(body["expired"].as_array().unwrap().is_empty());
+
+    // Two days later, without waiting for them
+    let later = chrono::Utc::now() + chrono::Duration::days(2);
+    let resp = client
+        .put(format!("{}/_admin/clock", server.admin_base_url))
+        .bearer_auth(ADMIN_TOKEN)
+        .json(&serde_json::json!({ "time": later }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let clock: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(clock["frozen"], true);
+
+    let body: serde_json::Value = run().await.unwrap().json().await.unwrap();
+    assert_eq!(body["expired"][0]["key"], "a.txt");
+    assert!(server.metadata.get_object_meta("clock-bucket", "a.txt").is_err());
+
+    let resp = client
+        .delete(format!("{}/_admin/clock", server.admin_base_url))
+        .bearer_auth(ADMIN_TOKEN)
+        .send()
+        .await
+        .unwrap();
+    let clock: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(clock["frozen"], false);
+}
+
+#[tokio::test]
+async fn test_clock_control_disabled() {
+    let server = TestServer::start_with_config(|c| {
+        c.admin_token = Some("clock-admin".into());
+    })
+    .await;
+    let resp = reqwest::Client::new()
+        .put(format!("{}/_admin/clock", server.admin_base_url))
+        .bearer_auth("clock-admin")
+        .json(&serde_json::json!({ "time": "2030-01-01T00:00:00Z" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    assert!(server.state.clock.frozen().is_none());
+}
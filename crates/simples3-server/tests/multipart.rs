@@ -11,7 +11,6 @@ use common::TestServer;
 async fn test_multipart_core_lifecycle() {
     use chrono::Utc;
     use simples3_core::s3::types::{MultipartUpload, PartInfo};
-    use simples3_core::storage::FileStore;
 
     let server = TestServer::start_anonymous().await;
 
@@ -22,6 +21,7 @@ async fn test_multipart_core_lifecycle() {
         key: "large-file.bin".into(),
         created: Utc::now(),
         parts: vec![],
+        checksum_algorithm: None,
     };
 
     server.metadata.create_bucket("mp-bucket").unwrap();
@@ -40,6 +40,7 @@ async fn test_multipart_core_lifecycle() {
                 etag: "etag1".into(),
                 size: 100,
                 last_modified: Utc::now(),
+                checksum: None,
             },
         )
         .unwrap();
@@ -53,6 +54,7 @@ async fn test_multipart_core_lifecycle() {
                 etag: "etag2".into(),
                 size: 200,
                 last_modified: Utc::now(),
+                checksum: None,
             },
         )
         .unwrap();
@@ -70,3 +72,227 @@ async fn test_multipart_core_lifecycle() {
         .unwrap();
     assert!(server.metadata.get_multipart_upload(upload_id).is_err());
 }
+
+fn extract_upload_id(xml: &str) -> String {
+    let start = xml.find("<UploadId>").unwrap() + "<UploadId>".len();
+    let end = xml.find("</UploadId>").unwrap();
+    xml[start..end].to_string()
+}
+
+fn complete_body(parts: &[(u32, &str)]) -> String {
+    let mut body = String::from("<CompleteMultipartUpload>");
+    for (number, etag) in parts {
+        body.push_str(&format!(
+            "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+            number, etag
+        ));
+    }
+    body.push_str("</CompleteMultipartUpload>");
+    body
+}
+
+#[tokio::test]
+async fn test_complete_multipart_validates_part_etags() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    client
+        .put(format!("{}/mp-etag", server.base_url))
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client
+        .post(format!("{}/mp-etag/file.bin?uploads", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let upload_id = extract_upload_id(&resp.text().await.unwrap());
+
+    let resp = client
+        .put(format!(
+            "{}/mp-etag/file.bin?partNumber=1&uploadId={}",
+            server.base_url, upload_id
+        ))
+        .body("part one")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = resp.headers()["etag"].to_str().unwrap().to_string();
+
+    let complete_url = format!("{}/mp-etag/file.bin?uploadId={}", server.base_url, upload_id);
+
+    // Wrong ETag
+    let resp = client
+        .post(&complete_url)
+        .body(complete_body(&[(1, "\"0123456789abcdef0123456789abcdef\"")]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains("<Code>InvalidPart</Code>"));
+
+    // Part that was never uploaded
+    let resp = client
+        .post(&complete_url)
+        .body(complete_body(&[(1, &etag), (2, &etag)]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Matching ETag completes the upload
+    let resp = client
+        .post(&complete_url)
+        .body(complete_body(&[(1, &etag)]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/mp-etag/file.bin", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "part one");
+}
+
+#[tokio::test]
+async fn test_multipart_checksums() {
+    use simples3_core::s3::checksum::ChecksumAlgorithm;
+
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    client
+        .put(format!("{}/mp-checksum", server.base_url))
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client
+        .post(format!("{}/mp-checksum/file.bin?uploads", server.base_url))
+        .header("x-amz-checksum-algorithm", "SHA1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-amz-checksum-algorithm"], "SHA1");
+    let upload_id = extract_upload_id(&resp.text().await.unwrap());
+    let part_url = |n: u32| {
+        format!(
+            "{}/mp-checksum/file.bin?partNumber={}&uploadId={}",
+            server.base_url, n, upload_id
+        )
+    };
+
+    // A part with a different algorithm than the upload is rejected
+    let resp = client
+        .put(part_url(1))
+        .header("x-amz-checksum-crc32", ChecksumAlgorithm::Crc32.compute(b"part one"))
+        .body("part one")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let mut etags = Vec::new();
+    for (n, data) in [(1, "part one"), (2, "part two")] {
+        let resp = client.put(part_url(n)).body(data).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers()["x-amz-checksum-sha1"],
+            ChecksumAlgorithm::Sha1.compute(data.as_bytes()).as_str()
+        );
+        etags.push(resp.headers()["etag"].to_str().unwrap().to_string());
+    }
+
+    let resp = client
+        .post(format!("{}/mp-checksum/file.bin?uploadId={}", server.base_url, upload_id))
+        .body(complete_body(&[(1, &etags[0]), (2, &etags[1])]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    let checksum = server.metadata.get_object_meta("mp-checksum", "file.bin").unwrap().checksum.unwrap();
+    assert_eq!(checksum.algorithm, ChecksumAlgorithm::Sha1);
+    assert!(checksum.value.ends_with("-2"));
+    assert!(body.contains(&format!("<ChecksumSHA1>{}</ChecksumSHA1>", checksum.value)));
+}
+
+#[tokio::test]
+async fn test_multipart_part_manifest() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+    client.put(format!("{}/mp-manifest", base)).send().await.unwrap();
+
+    let resp = client
+        .post(format!("{}/mp-manifest/file.bin?uploads", base))
+        .send()
+        .await
+        .unwrap();
+    let upload_id = extract_upload_id(&resp.text().await.unwrap());
+    let mut etags = Vec::new();
+    for (number, body) in [(1, "aaaaa"), (2, "bbb"), (4, "cccccccc")] {
+        let resp = client
+            .put(format!("{}/mp-manifest/file.bin?partNumber={}&uploadId={}", base, number, upload_id))
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        etags.push((number, resp.headers()["etag"].to_str().unwrap().to_string()));
+    }
+    // Part 2 is left out of the object
+    let parts: Vec<(u32, &str)> = [&etags[0], &etags[2]].iter().map(|(n, e)| (*n, e.as_str())).collect();
+    let resp = client
+        .post(format!("{}/mp-manifest/file.bin?uploadId={}", base, upload_id))
+        .body(complete_body(&parts))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .get(format!("{}/mp-manifest/file.bin?x-simples3-parts", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let manifest: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(manifest["size"], 13);
+    let parts = manifest["parts"].as_array().unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["part_number"], 1);
+    assert_eq!(parts[0]["range"], "bytes=0-4");
+    assert_eq!(parts[1]["part_number"], 2);
+    assert_eq!(parts[1]["offset"], 5);
+    assert_eq!(parts[1]["size"], 8);
+
+    // Ranges from the manifest return exactly one part
+    let resp = client
+        .get(format!("{}/mp-manifest/file.bin", base))
+        .header("range", parts[1]["range"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "cccccccc");
+
+    // Overwritten by a single PutObject, the object is one part
+    client
+        .put(format!("{}/mp-manifest/file.bin", base))
+        .body("plain")
+        .send()
+        .await
+        .unwrap();
+    let resp = client
+        .get(format!("{}/mp-manifest/file.bin?x-simples3-parts", base))
+        .send()
+        .await
+        .unwrap();
+    let manifest: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(manifest["parts"].as_array().unwrap().len(), 1);
+    assert_eq!(manifest["parts"][0]["range"], "bytes=0-4");
+}
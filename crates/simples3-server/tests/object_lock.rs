@@ -0,0 +1,268 @@
+mod common;
+
+use common::TestServer;
+
+const GOVERNANCE_DEFAULT: &str = r#"<ObjectLockConfiguration>
+    <ObjectLockEnabled>Enabled</ObjectLockEnabled>
+    <Rule><DefaultRetention><Mode>GOVERNANCE</Mode><Days>1</Days></DefaultRetention></Rule>
+</ObjectLockConfiguration>"#;
+
+#[tokio::test]
+async fn test_object_lock_configuration() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/lock-config", base)).send().await.unwrap();
+
+    // Not configured yet
+    let resp = client.get(format!("{}/lock-config?object-lock", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert!(resp.text().await.unwrap().contains("ObjectLockConfigurationNotFoundError"));
+
+    let resp = client
+        .put(format!("{}/lock-config?object-lock", base))
+        .body(GOVERNANCE_DEFAULT)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/lock-config?object-lock", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<ObjectLockEnabled>Enabled</ObjectLockEnabled>"));
+    assert!(body.contains("<Mode>GOVERNANCE</Mode>"));
+    assert!(body.contains("<Days>1</Days>"));
+
+    // Enabled at creation time
+    let resp = client
+        .put(format!("{}/lock-at-create", base))
+        .header("x-amz-bucket-object-lock-enabled", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/lock-at-create?object-lock", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_default_retention_blocks_delete_and_overwrite() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/lock-default", base)).send().await.unwrap();
+    client
+        .put(format!("{}/lock-default?object-lock", base))
+        .body(GOVERNANCE_DEFAULT)
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client.put(format!("{}/lock-default/doc.txt", base)).body("v1").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.head(format!("{}/lock-default/doc.txt", base)).send().await.unwrap();
+    assert_eq!(resp.headers().get("x-amz-object-lock-mode").unwrap(), "GOVERNANCE");
+    assert!(resp.headers().contains_key("x-amz-object-lock-retain-until-date"));
+
+    let resp = client.get(format!("{}/lock-default/doc.txt?retention", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text().await.unwrap().contains("<Mode>GOVERNANCE</Mode>"));
+
+    // Delete and overwrite are refused while the retention is active
+    let resp = client.delete(format!("{}/lock-default/doc.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client.put(format!("{}/lock-default/doc.txt", base)).body("v2").send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let delete_xml = "<Delete><Object><Key>doc.txt</Key></Object></Delete>";
+    let resp = client
+        .post(format!("{}/lock-default?delete", base))
+        .body(delete_xml)
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Code>AccessDenied</Code>"));
+
+    let resp = client.get(format!("{}/lock-default/doc.txt", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "v1");
+
+    // GOVERNANCE can be bypassed explicitly
+    let resp = client
+        .delete(format!("{}/lock-default/doc.txt", base))
+        .header("x-amz-bypass-governance-retention", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(format!("{}/lock-default/doc.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_compliance_retention() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client
+        .put(format!("{}/lock-compliance", base))
+        .header("x-amz-bucket-object-lock-enabled", "true")
+        .send()
+        .await
+        .unwrap();
+
+    let until = chrono::Utc::now() + chrono::Duration::days(2);
+    let resp = client
+        .put(format!("{}/lock-compliance/record.txt", base))
+        .header("x-amz-object-lock-mode", "COMPLIANCE")
+        .header("x-amz-object-lock-retain-until-date", until.to_rfc3339())
+        .body("record")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // COMPLIANCE ignores the governance bypass
+    let resp = client
+        .delete(format!("{}/lock-compliance/record.txt", base))
+        .header("x-amz-bypass-governance-retention", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Shortening is refused, extending is allowed
+    let retention = |date: chrono::DateTime<chrono::Utc>| {
+        format!(
+            "<Retention><Mode>COMPLIANCE</Mode><RetainUntilDate>{}</RetainUntilDate></Retention>",
+            date.format("%Y-%m-%dT%H:%M:%SZ")
+        )
+    };
+    let resp = client
+        .put(format!("{}/lock-compliance/record.txt?retention", base))
+        .body(retention(until - chrono::Duration::days(1)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client
+        .put(format!("{}/lock-compliance/record.txt?retention", base))
+        .body(retention(until + chrono::Duration::days(1)))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_retention_requires_object_lock() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/no-lock", base)).send().await.unwrap();
+
+    let until = chrono::Utc::now() + chrono::Duration::days(1);
+    let resp = client
+        .put(format!("{}/no-lock/a.txt", base))
+        .header("x-amz-object-lock-mode", "GOVERNANCE")
+        .header("x-amz-object-lock-retain-until-date", until.to_rfc3339())
+        .body("a")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    client.put(format!("{}/no-lock/a.txt", base)).body("a").send().await.unwrap();
+    let resp = client
+        .put(format!("{}/no-lock/a.txt?retention", base))
+        .body("<Retention><Mode>GOVERNANCE</Mode><RetainUntilDate>2099-01-01T00:00:00Z</RetainUntilDate></Retention>")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Objects without retention can still be deleted
+    let resp = client.delete(format!("{}/no-lock/a.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+}
+
+#[tokio::test]
+async fn test_legal_hold() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client
+        .put(format!("{}/hold-bucket", base))
+        .header("x-amz-bucket-object-lock-enabled", "true")
+        .send()
+        .await
+        .unwrap();
+    client.put(format!("{}/hold-bucket/evidence.txt", base)).body("v1").send().await.unwrap();
+
+    let resp = client.get(format!("{}/hold-bucket/evidence.txt?legal-hold", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text().await.unwrap().contains("<Status>OFF</Status>"));
+
+    let hold = |status: &str| format!("<LegalHold><Status>{}</Status></LegalHold>", status);
+    let resp = client
+        .put(format!("{}/hold-bucket/evidence.txt?legal-hold", base))
+        .body(hold("ON"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.head(format!("{}/hold-bucket/evidence.txt", base)).send().await.unwrap();
+    assert_eq!(resp.headers().get("x-amz-object-lock-legal-hold").unwrap(), "ON");
+
+    // No retention date is involved, and the governance bypass does not apply
+    let resp = client
+        .delete(format!("{}/hold-bucket/evidence.txt", base))
+        .header("x-amz-bypass-governance-retention", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client.put(format!("{}/hold-bucket/evidence.txt", base)).body("v2").send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    let resp = client
+        .put(format!("{}/hold-bucket/evidence.txt?legal-hold", base))
+        .body(hold("OFF"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.delete(format!("{}/hold-bucket/evidence.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+
+    // Set on upload
+    let resp = client
+        .put(format!("{}/hold-bucket/held-on-upload.txt", base))
+        .header("x-amz-object-lock-legal-hold", "ON")
+        .body("x")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.delete(format!("{}/hold-bucket/held-on-upload.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Requires Object Lock on the bucket
+    client.put(format!("{}/no-hold-bucket", base)).send().await.unwrap();
+    client.put(format!("{}/no-hold-bucket/a.txt", base)).body("a").send().await.unwrap();
+    let resp = client
+        .put(format!("{}/no-hold-bucket/a.txt?legal-hold", base))
+        .body(hold("ON"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
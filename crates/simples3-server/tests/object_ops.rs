@@ -61,6 +61,69 @@ async fn test_head_object() {
     assert!(resp.headers().get("last-modified").is_some());
 }
 
+#[tokio::test]
+async fn test_head_and_get_object_range_and_preconditions() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "range-obj").await;
+    let url = format!("{}/range-obj/digits.txt", server.base_url);
+
+    let resp = client.put(&url).body("0123456789").send().await.unwrap();
+    let etag = resp.headers().get("etag").unwrap().to_str().unwrap().to_string();
+
+    // HEAD probes the range without a body
+    let resp = client.head(&url).header("range", "bytes=2-5").send().await.unwrap();
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers()["content-length"], "4");
+    assert_eq!(resp.headers()["content-range"], "bytes 2-5/10");
+    assert_eq!(resp.headers()["accept-ranges"], "bytes");
+
+    // GET serves the same bytes
+    let resp = client.get(&url).header("range", "bytes=2-5").send().await.unwrap();
+    assert_eq!(resp.status(), 206);
+    assert_eq!(resp.headers()["content-range"], "bytes 2-5/10");
+    assert_eq!(resp.text().await.unwrap(), "2345");
+    let resp = client.get(&url).header("range", "bytes=-3").send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "789");
+
+    let resp = client.head(&url).header("range", "bytes=10-").send().await.unwrap();
+    assert_eq!(resp.status(), 416);
+    assert_eq!(resp.headers()["content-range"], "bytes */10");
+
+    // Preconditions
+    let resp = client.head(&url).header("if-match", &etag).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.head(&url).header("if-match", "\"nope\"").send().await.unwrap();
+    assert_eq!(resp.status(), 412);
+    let resp = client.head(&url).header("if-none-match", &etag).send().await.unwrap();
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers()["etag"].to_str().unwrap(), etag);
+    let resp = client
+        .get(&url)
+        .header("if-modified-since", "Fri, 01 Jan 2100 00:00:00 GMT")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 304);
+    let resp = client
+        .head(&url)
+        .header("if-unmodified-since", "Thu, 01 Jan 2015 00:00:00 GMT")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 412);
+
+    // A failed precondition is checked before the range
+    let resp = client
+        .head(&url)
+        .header("if-match", "\"nope\"")
+        .header("range", "bytes=0-1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 412);
+}
+
 #[tokio::test]
 async fn test_delete_object() {
     let server = TestServer::start_anonymous().await;
@@ -105,6 +168,89 @@ async fn test_get_nonexistent_returns_404() {
     assert!(body.contains("<Code>NoSuchKey</Code>"));
 }
 
+#[tokio::test]
+async fn test_json_error_format_opt_in() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "json-err").await;
+
+    // Default stays XML
+    let resp = client
+        .get(format!("{}/json-err/missing.txt", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.headers()["content-type"], "application/xml");
+
+    for (name, value) in [("accept", "application/json"), ("x-simples3-error-format", "json")] {
+        let resp = client
+            .get(format!("{}/json-err/missing.txt", server.base_url))
+            .header(name, value)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 404);
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["code"], "NoSuchKey");
+        assert_eq!(body["message"], "The specified key does not exist");
+    }
+}
+
+#[tokio::test]
+async fn test_request_id_headers() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "req-ids").await;
+
+    let ok = client
+        .put(format!("{}/req-ids/a.txt", server.base_url))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(ok.status(), 200);
+    let ok_id = ok.headers()["x-amz-request-id"].to_str().unwrap().to_string();
+    assert_eq!(ok_id.len(), 16);
+    assert!(ok.headers().contains_key("x-amz-id-2"));
+
+    // Errors carry them too, and JSON error bodies repeat the request ID
+    let err = client
+        .get(format!("{}/req-ids/missing.txt", server.base_url))
+        .header("accept", "application/json")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(err.status(), 404);
+    let err_id = err.headers()["x-amz-request-id"].to_str().unwrap().to_string();
+    assert!(err.headers().contains_key("x-amz-id-2"));
+    assert_ne!(err_id, ok_id);
+    let body: serde_json::Value = err.json().await.unwrap();
+    assert_eq!(body["request_id"], err_id.as_str());
+}
+
+#[tokio::test]
+async fn test_error_xml_context() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "err-ctx").await;
+
+    let resp = client
+        .get(format!("{}/err-ctx/missing.txt", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    let request_id = resp.headers()["x-amz-request-id"].to_str().unwrap().to_string();
+    let host_id = resp.headers()["x-amz-id-2"].to_str().unwrap().to_string();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Code>NoSuchKey</Code>"));
+    assert!(body.contains("<Resource>/err-ctx/missing.txt</Resource>"));
+    assert!(body.contains(&format!("<RequestId>{}</RequestId>", request_id)));
+    assert!(body.contains(&format!("<HostId>{}</HostId>", host_id)));
+}
+
 #[tokio::test]
 async fn test_list_objects_v2() {
     let server = TestServer::start_anonymous().await;
@@ -135,6 +281,150 @@ async fn test_list_objects_v2() {
     assert!(!body.contains("<Key>docs/c.pdf</Key>"));
 }
 
+#[tokio::test]
+async fn test_list_objects_v2_encoding_type_url() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "enc-list").await;
+
+    server
+        .metadata
+        .put_object_meta(&simples3_core::s3::types::ObjectMeta {
+            bucket: "enc-list".into(),
+            key: "dir a/bell\u{7}+plus.txt".into(),
+            size: 1,
+            etag: "e".into(),
+            content_type: "text/plain".into(),
+            last_modified: chrono::Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        })
+        .unwrap();
+
+    // Both the streamed and the cached listing encode keys
+    for _ in 0..2 {
+        let resp = client
+            .get(format!("{}/enc-list?list-type=2&encoding-type=url", server.base_url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let body = resp.text().await.unwrap();
+        assert!(body.contains("<EncodingType>url</EncodingType>"));
+        assert!(body.contains("<Key>dir%20a/bell%07%2Bplus.txt</Key>"));
+    }
+
+    let resp = client
+        .get(format!("{}/enc-list?list-type=2&delimiter=/&encoding-type=url", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Prefix>dir%20a/</Prefix>"));
+
+    let resp = client
+        .get(format!("{}/enc-list?list-type=2&encoding-type=base64", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_list_objects_v2_fetch_owner() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "owner-list").await;
+    client
+        .put(format!("{}/owner-list/a.txt", server.base_url))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+
+    let resp = client
+        .get(format!("{}/owner-list?list-type=2", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert!(!resp.text().await.unwrap().contains("<Owner>"));
+
+    let resp = client
+        .get(format!("{}/owner-list?list-type=2&fetch-owner=true", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Owner><ID>anonymous</ID><DisplayName>anonymous</DisplayName></Owner></Contents>"));
+}
+
+#[tokio::test]
+async fn test_list_objects_v2_streamed_pages() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "big-list").await;
+
+    // Enough keys that a page spans several streamed chunks
+    for i in 0..1500 {
+        server
+            .metadata
+            .put_object_meta(&simples3_core::s3::types::ObjectMeta {
+                bucket: "big-list".into(),
+                key: format!("logs/{:05}.log", i),
+                size: 1,
+                etag: "e".into(),
+                content_type: "text/plain".into(),
+                last_modified: chrono::Utc::now(),
+                public: false,
+                checksum: None,
+                legal_hold: false,
+                storage_class: None,
+            })
+            .unwrap();
+    }
+
+    let resp = client
+        .get(format!("{}/big-list?list-type=2&prefix=logs/", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.starts_with("<?xml"));
+    assert!(body.ends_with("</ListBucketResult>"));
+    assert_eq!(body.matches("<Key>").count(), 1000);
+    assert!(body.contains("<KeyCount>1000</KeyCount>"));
+    assert!(body.contains("<IsTruncated>true</IsTruncated>"));
+    assert!(body.contains("<NextContinuationToken>logs/00999.log</NextContinuationToken>"));
+
+    let resp = client
+        .get(format!(
+            "{}/big-list?list-type=2&prefix=logs/&continuation-token=logs/00999.log",
+            server.base_url
+        ))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert_eq!(body.matches("<Key>").count(), 500);
+    assert!(body.contains("<Key>logs/01000.log</Key>"));
+    assert!(body.contains("<IsTruncated>false</IsTruncated>"));
+    assert!(!body.contains("<NextContinuationToken>"));
+
+    // An empty page has nowhere to continue from
+    let resp = client
+        .get(format!("{}/big-list?list-type=2&prefix=logs/&max-keys=0", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<KeyCount>0</KeyCount>"));
+    assert!(body.contains("<IsTruncated>false</IsTruncated>"));
+    assert!(!body.contains("<NextContinuationToken>"));
+}
+
 #[tokio::test]
 async fn test_put_object_preserves_content_type() {
     let server = TestServer::start_anonymous().await;
@@ -293,6 +583,48 @@ async fn test_get_object_returns_tagging_count() {
     );
 }
 
+#[tokio::test]
+async fn test_bucket_default_tags_merged_on_upload() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "deftags-bucket").await;
+
+    let mut defaults = std::collections::HashMap::new();
+    defaults.insert("team".to_string(), "storage".to_string());
+    defaults.insert("env".to_string(), "prod".to_string());
+    server.metadata.put_bucket_default_tags("deftags-bucket", &defaults).unwrap();
+
+    // Client tags win on conflict
+    client
+        .put(format!("{}/deftags-bucket/a.txt", server.base_url))
+        .header("x-amz-tagging", "env=dev&owner=jo%20smith")
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    let tags = server.metadata.get_object_tagging("deftags-bucket", "a.txt").unwrap();
+    assert_eq!(tags.len(), 3);
+    assert_eq!(tags["team"], "storage");
+    assert_eq!(tags["env"], "dev");
+    assert_eq!(tags["owner"], "jo smith");
+
+    // Untagged uploads still get the defaults
+    client
+        .put(format!("{}/deftags-bucket/b.txt", server.base_url))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+    let resp = client
+        .get(format!("{}/deftags-bucket/b.txt?tagging", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Key>team</Key>"));
+    assert!(body.contains("<Value>prod</Value>"));
+}
+
 // --- CopyObject tests ---
 
 #[tokio::test]
@@ -441,6 +773,49 @@ async fn test_delete_objects_nonexistent_keys() {
     assert!(body.contains("<Deleted>"));
 }
 
+#[tokio::test]
+async fn test_delete_objects_dry_run() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "batch-dry").await;
+
+    for key in ["a.txt", "held.txt"] {
+        client
+            .put(format!("{}/batch-dry/{}", server.base_url, key))
+            .body("data")
+            .send()
+            .await
+            .unwrap();
+    }
+    let mut meta = server.metadata.get_object_meta("batch-dry", "held.txt").unwrap();
+    meta.legal_hold = true;
+    server.metadata.put_object_meta(&meta).unwrap();
+
+    let delete_xml = r#"<Delete><Object><Key>a.txt</Key></Object><Object><Key>held.txt</Key></Object></Delete>"#;
+    let resp = client
+        .post(format!("{}/batch-dry?delete", server.base_url))
+        .header("x-simples3-dry-run", "true")
+        .body(delete_xml)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-simples3-dry-run"], "true");
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Deleted><Key>a.txt</Key></Deleted>"), "{}", body);
+    assert!(body.contains("<Error><Key>held.txt</Key>"), "{}", body);
+
+    // Nothing was deleted
+    for key in ["a.txt", "held.txt"] {
+        let resp = client
+            .get(format!("{}/batch-dry/{}", server.base_url, key))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+}
+
 // --- ACL tests ---
 
 #[tokio::test]
@@ -552,6 +927,65 @@ async fn test_put_object_acl_toggle() {
     assert!(!body.contains("AllUsers"));
 }
 
+#[tokio::test]
+async fn test_put_object_acl_body() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "acl-body").await;
+
+    client
+        .put(format!("{}/acl-body/file.txt", server.base_url))
+        .body("data")
+        .send()
+        .await
+        .unwrap();
+
+    let public_policy = r#"<AccessControlPolicy xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Owner><ID>simples3</ID></Owner>
+  <AccessControlList>
+    <Grant>
+      <Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="CanonicalUser"><ID>simples3</ID></Grantee>
+      <Permission>FULL_CONTROL</Permission>
+    </Grant>
+    <Grant>
+      <Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Group"><URI>http://acs.amazonaws.com/groups/global/AllUsers</URI></Grantee>
+      <Permission>READ</Permission>
+    </Grant>
+  </AccessControlList>
+</AccessControlPolicy>"#;
+    let resp = client
+        .put(format!("{}/acl-body/file.txt?acl", server.base_url))
+        .body(public_policy)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(server.metadata.get_object_meta("acl-body", "file.txt").unwrap().public);
+
+    // Unsupported grants are rejected and leave the ACL unchanged
+    let write_policy = public_policy.replace("<Permission>READ</Permission>", "<Permission>WRITE</Permission>");
+    let resp = client
+        .put(format!("{}/acl-body/file.txt?acl", server.base_url))
+        .body(write_policy)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(server.metadata.get_object_meta("acl-body", "file.txt").unwrap().public);
+
+    let owner_only = r#"<AccessControlPolicy><AccessControlList><Grant>
+      <Grantee xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="CanonicalUser"><ID>simples3</ID></Grantee>
+      <Permission>FULL_CONTROL</Permission></Grant></AccessControlList></AccessControlPolicy>"#;
+    let resp = client
+        .put(format!("{}/acl-body/file.txt?acl", server.base_url))
+        .body(owner_only)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!server.metadata.get_object_meta("acl-body", "file.txt").unwrap().public);
+}
+
 #[tokio::test]
 async fn test_invalid_acl_value_rejected() {
     let server = TestServer::start_anonymous().await;
@@ -616,3 +1050,214 @@ async fn test_copy_object_inherits_source_acl() {
     let body = resp.text().await.unwrap();
     assert!(!body.contains("AllUsers"));
 }
+
+#[tokio::test]
+async fn test_prefix_storage_routes() {
+    let fast_dir = tempfile::tempdir().unwrap();
+    let fast_path = fast_dir.path().to_path_buf();
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("routes-token".into());
+        c.storage_backends = vec![("fast".into(), fast_path)];
+    })
+    .await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "routed").await;
+    let routes_url = format!("{}/_admin/buckets/routed/storage-routes", server.admin_base_url);
+
+    // Unknown backends are rejected
+    let resp = client
+        .put(&routes_url)
+        .bearer_auth("routes-token")
+        .json(&serde_json::json!({ "routes": [{ "prefix": "raw/", "backend": "gateway" }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .put(&routes_url)
+        .bearer_auth("routes-token")
+        .json(&serde_json::json!({ "routes": [{ "prefix": "thumbnails/", "backend": "fast" }] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    for key in ["thumbnails/a.png", "raw/a.bin"] {
+        let resp = client
+            .put(format!("{}/routed/{}", server.base_url, key))
+            .body(format!("data for {}", key))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+    assert!(fast_dir.path().join("routed/thumbnails/a.png").exists());
+    assert!(!fast_dir.path().join("routed/raw/a.bin").exists());
+
+    for key in ["thumbnails/a.png", "raw/a.bin"] {
+        let resp = client
+            .get(format!("{}/routed/{}", server.base_url, key))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.text().await.unwrap(), format!("data for {}", key));
+    }
+
+    // Routes cannot change while objects exist
+    let resp = client
+        .delete(&routes_url)
+        .bearer_auth("routes-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+
+    let resp = client
+        .delete(format!("{}/routed/thumbnails/a.png", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 204);
+    assert!(!fast_dir.path().join("routed/thumbnails/a.png").exists());
+}
+
+#[tokio::test]
+async fn test_put_object_additional_checksums() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "checksum-bucket").await;
+    let url = format!("{}/checksum-bucket/hello.txt", server.base_url);
+
+    let resp = client
+        .put(&url)
+        .header("x-amz-checksum-sha256", "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=")
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers()["x-amz-checksum-sha256"],
+        "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ="
+    );
+    let meta = server.metadata.get_object_meta("checksum-bucket", "hello.txt").unwrap();
+    assert_eq!(meta.checksum.unwrap().value, "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=");
+
+    // Algorithm only: the server computes the value
+    let resp = client
+        .put(&url)
+        .header("x-amz-sdk-checksum-algorithm", "CRC32")
+        .body("123456789")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-amz-checksum-crc32"], "y/Q5Jg==");
+
+    // Mismatch is rejected and leaves the previous object in place
+    let resp = client
+        .put(&url)
+        .header("x-amz-checksum-crc32c", "AAAAAA==")
+        .body("corrupted")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    assert!(resp.text().await.unwrap().contains("<Code>BadDigest</Code>"));
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "123456789");
+}
+
+#[tokio::test]
+async fn test_get_and_head_object_checksum_mode() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "checksum-mode").await;
+    let url = format!("{}/checksum-mode/data.txt", server.base_url);
+
+    let resp = client
+        .put(&url)
+        .header("x-amz-sdk-checksum-algorithm", "CRC32")
+        .body("123456789")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Not returned unless requested
+    let resp = client.get(&url).send().await.unwrap();
+    assert!(resp.headers().get("x-amz-checksum-crc32").is_none());
+
+    let resp = client
+        .get(&url)
+        .header("x-amz-checksum-mode", "ENABLED")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-amz-checksum-crc32"], "y/Q5Jg==");
+
+    let resp = client
+        .head(&url)
+        .header("x-amz-checksum-mode", "ENABLED")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-amz-checksum-crc32"], "y/Q5Jg==");
+
+    // Objects uploaded without a checksum have none to return
+    client.put(format!("{}/checksum-mode/plain.txt", server.base_url)).body("x").send().await.unwrap();
+    let resp = client
+        .head(format!("{}/checksum-mode/plain.txt", server.base_url))
+        .header("x-amz-checksum-mode", "ENABLED")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(!resp.headers().keys().any(|k| k.as_str().starts_with("x-amz-checksum-")));
+}
+
+#[tokio::test]
+async fn test_md5_skip_threshold() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.md5_skip_threshold = Some(4);
+    })
+    .await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "no-md5").await;
+
+    // At or below the threshold the ETag is the content MD5
+    let resp = client.put(format!("{}/no-md5/small", server.base_url)).body("abcd").send().await.unwrap();
+    assert_eq!(resp.headers()["etag"], "\"e2fc714c4727ee9395f324cd2e7f331f\"");
+
+    let resp = client.put(format!("{}/no-md5/big", server.base_url)).body("abcdefgh").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = resp.headers()["etag"].clone();
+    assert_ne!(etag, "\"e8dc4081b13434b45189a720b77b6818\"");
+
+    let resp = client.get(format!("{}/no-md5/big", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.headers()["etag"], etag);
+    assert_eq!(resp.text().await.unwrap(), "abcdefgh");
+}
+
+#[tokio::test]
+async fn test_dedicated_io_pool() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.io_threads = 2;
+    })
+    .await;
+    let client = reqwest::Client::new();
+    create_bucket(&client, &server.base_url, "io-pool").await;
+
+    let body = vec![b'x'; 256 * 1024];
+    let resp = client.put(format!("{}/io-pool/big", server.base_url)).body(body.clone()).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client.get(format!("{}/io-pool/big", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.bytes().await.unwrap().as_ref(), body.as_slice());
+
+    let resp = client.delete(format!("{}/io-pool/big", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+}
@@ -84,6 +84,9 @@ async fn test_policy_anonymous_access_via_policy() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
@@ -154,6 +157,9 @@ async fn test_policy_explicit_deny() {
             content_type: "text/plain".into(),
             last_modified: chrono::Utc::now(),
             public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
         })
         .unwrap();
 
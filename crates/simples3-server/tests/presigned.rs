@@ -20,6 +20,7 @@ fn signing_key(secret: &str, date: &str, region: &str) -> Vec<u8> {
     hmac_sha256(&k_service, b"aws4_request")
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_presigned_url(
     method: &str,
     base_url: &str,
@@ -38,7 +39,7 @@ fn generate_presigned_url(
     let signed_headers = "host";
 
     // Build canonical query string (without signature, sorted)
-    let mut params = vec![
+    let mut params = [
         ("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
         (
             "X-Amz-Credential".to_string(),
@@ -92,7 +93,7 @@ async fn create_bucket(client: &reqwest::Client, base_url: &str, name: &str) {
 
 #[tokio::test]
 async fn test_presigned_get_object() {
-    let server = TestServer::start().await;
+    let _server = TestServer::start().await;
     let client = reqwest::Client::new();
 
     // Create bucket and upload object using anonymous-free helper
@@ -190,7 +191,7 @@ async fn test_presigned_expired() {
     let credential = format!("TESTAKID/{}/us-east-1/s3/aws4_request", date);
     let path = "/presign-exp/file.txt";
 
-    let mut params = vec![
+    let mut params = [
         ("X-Amz-Algorithm".to_string(), "AWS4-HMAC-SHA256".to_string()),
         (
             "X-Amz-Credential".to_string(),
@@ -232,3 +233,76 @@ async fn test_presigned_expired() {
     let resp = client.get(&url).send().await.unwrap();
     assert_eq!(resp.status(), 403);
 }
+
+#[tokio::test]
+async fn test_presigned_region_validation() {
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server.metadata.create_bucket("region-bucket").unwrap();
+    let host = server.addr.to_string();
+
+    let url = generate_presigned_url(
+        "GET",
+        &server.base_url,
+        "/region-bucket",
+        "TESTAKID",
+        "TESTSECRET",
+        "eu-west-1",
+        300,
+        &host,
+    );
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+    assert_eq!(resp.headers()["x-amz-bucket-region"], "us-east-1");
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Code>AuthorizationHeaderMalformed</Code>"));
+    assert!(body.contains("<Region>us-east-1</Region>"));
+
+    // Additional regions can be accepted explicitly
+    let server = TestServer::start_with_config(|c| {
+        c.accepted_regions = Some(vec!["us-east-1".into(), "eu-west-1".into()]);
+    })
+    .await;
+    server.metadata.create_bucket("region-bucket").unwrap();
+    let url = generate_presigned_url(
+        "GET",
+        &server.base_url,
+        "/region-bucket",
+        "TESTAKID",
+        "TESTSECRET",
+        "eu-west-1",
+        300,
+        &server.addr.to_string(),
+    );
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_presigned_expiry_follows_server_clock() {
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server.metadata.create_bucket("presign-clock").unwrap();
+    let url = generate_presigned_url(
+        "GET",
+        &server.base_url,
+        "/presign-clock/missing.txt",
+        "TESTAKID",
+        "TESTSECRET",
+        "us-east-1",
+        300,
+        &server.addr.to_string(),
+    );
+
+    // Authorized: the object is simply missing
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    server.state.clock.freeze(Utc::now() + chrono::Duration::seconds(301));
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    server.state.clock.resume();
+    let resp = client.get(&url).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
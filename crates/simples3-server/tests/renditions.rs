@@ -0,0 +1,197 @@
+mod common;
+
+use common::TestServer;
+use simples3_server::renditions::{Rendition, Transformer};
+
+/// Keeps the first `width` bytes of the image, which is enough to tell
+/// renditions apart without an image library.
+const COMMAND: &str = "head -c {width}";
+
+async fn start() -> TestServer {
+    TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("rendition-admin".into());
+        c.rendition_command = Some(COMMAND.into());
+    })
+    .await
+}
+
+async fn enable(server: &TestServer, bucket: &str, cache_limit_bytes: u64) {
+    let resp = reqwest::Client::new()
+        .put(format!("{}/_admin/buckets/{}/renditions", server.admin_base_url, bucket))
+        .bearer_auth("rendition-admin")
+        .json(&serde_json::json!({
+            "max_width": 8,
+            "max_height": 8,
+            "cache_limit_bytes": cache_limit_bytes,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+async fn put_image(client: &reqwest::Client, url: &str, body: &'static str) {
+    let resp = client
+        .put(url)
+        .header("content-type", "image/png")
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_renditions() {
+    let server = start().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/img-bkt", base)).send().await.unwrap();
+    put_image(&client, &format!("{}/img-bkt/cat.png", base), "0123456789").await;
+    client
+        .put(format!("{}/img-bkt/notes.txt", base))
+        .body("plain")
+        .send()
+        .await
+        .unwrap();
+
+    // Buckets opt in
+    let resp = client
+        .get(format!("{}/img-bkt/cat.png?x-simples3-resize=4x4", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    enable(&server, "img-bkt", 1024).await;
+
+    let resp = client
+        .get(format!("{}/img-bkt/cat.png?x-simples3-resize=4x4", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "image/png");
+    assert_eq!(resp.headers()["x-simples3-rendition"], "4x4");
+    assert_eq!(resp.headers()["x-simples3-rendition-cache"], "miss");
+    assert_eq!(resp.text().await.unwrap(), "0123");
+
+    let resp = client
+        .get(format!("{}/img-bkt/cat.png?x-simples3-resize=4x4", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-simples3-rendition-cache"], "hit");
+    assert_eq!(resp.text().await.unwrap(), "0123");
+
+    // Sizes are bounded by the bucket configuration
+    for spec in ["16x4", "0x4", "4", "axb"] {
+        let resp = client
+            .get(format!("{}/img-bkt/cat.png?x-simples3-resize={}", base, spec))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 400, "{}", spec);
+    }
+
+    // Only images are transformed
+    let resp = client
+        .get(format!("{}/img-bkt/notes.txt?x-simples3-resize=4x4", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    // Overwriting the object drops its renditions
+    put_image(&client, &format!("{}/img-bkt/cat.png", base), "abcdefghij").await;
+    let resp = client
+        .get(format!("{}/img-bkt/cat.png?x-simples3-resize=4x4", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-simples3-rendition-cache"], "miss");
+    assert_eq!(resp.text().await.unwrap(), "abcd");
+
+    // The original is still served without the parameter
+    let resp = client.get(format!("{}/img-bkt/cat.png", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "abcdefghij");
+
+    client.delete(format!("{}/img-bkt/cat.png", base)).send().await.unwrap();
+    assert!(server.metadata.get_renditions("img-bkt", "cat.png").unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_renditions_cache_limit() {
+    let server = start().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/img-evict", base)).send().await.unwrap();
+    put_image(&client, &format!("{}/img-evict/a.png", base), "0123456789").await;
+    enable(&server, "img-evict", 6).await;
+
+    for spec in ["4x4", "3x3"] {
+        let resp = client
+            .get(format!("{}/img-evict/a.png?x-simples3-resize={}", base, spec))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()["x-simples3-rendition-cache"], "miss");
+    }
+
+    // The 4x4 rendition was evicted to fit the 3x3 one in 6 bytes
+    let cached = server.metadata.get_renditions("img-evict", "a.png").unwrap();
+    assert_eq!(cached.len(), 1);
+    assert_eq!((cached[0].width, cached[0].height), (3, 3));
+    let resp = client
+        .get(format!("{}/img-evict/a.png?x-simples3-resize=3x3", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.headers()["x-simples3-rendition-cache"], "hit");
+}
+
+struct Uppercase;
+
+impl Transformer for Uppercase {
+    fn resize(&self, source: &[u8], _content_type: &str, width: u32, _height: u32) -> Result<Rendition, String> {
+        Ok(Rendition {
+            data: source[..width as usize].to_ascii_uppercase(),
+            content_type: "image/webp".into(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_renditions_custom_transformer() {
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("rendition-admin".into());
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/img-custom", base)).send().await.unwrap();
+    put_image(&client, &format!("{}/img-custom/a.png", base), "abcdef").await;
+    enable(&server, "img-custom", 1024).await;
+
+    // No transformer configured
+    let resp = client
+        .get(format!("{}/img-custom/a.png?x-simples3-resize=2x2", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 501);
+
+    simples3_server::renditions::enable(&server.state, Uppercase);
+    let resp = client
+        .get(format!("{}/img-custom/a.png?x-simples3-resize=2x2", base))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "image/webp");
+    assert_eq!(resp.text().await.unwrap(), "AB");
+}
@@ -0,0 +1,368 @@
+mod common;
+
+use common::TestServer;
+use std::time::Duration;
+
+const ADMIN_TOKEN: &str = "test-admin-token";
+
+async fn start_source() -> TestServer {
+    TestServer::start_with_config(|c| {
+        c.admin_token = Some(ADMIN_TOKEN.into());
+        c.anonymous_global = true;
+        c.replication_interval_secs = 1;
+    })
+    .await
+}
+
+async fn put_replication(server: &TestServer, bucket: &str, config: serde_json::Value) -> reqwest::Response {
+    reqwest::Client::new()
+        .put(format!("{}/_admin/buckets/{}/replication", server.admin_base_url, bucket))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&config)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_replication_configuration() {
+    let server = start_source().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/_admin/buckets/repl-config/replication", server.admin_base_url);
+    let auth = format!("Bearer {}", ADMIN_TOKEN);
+    server.metadata.create_bucket("repl-config").unwrap();
+
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let config = serde_json::json!({
+        "endpoint": "https://backup.example.com",
+        "target_bucket": "backup",
+        "access_key_id": "AKID",
+        "secret_access_key": "SECRET",
+        "prefix": "docs/",
+    });
+    let resp = put_replication(&server, "repl-config", config).await;
+    assert_eq!(resp.status(), 200);
+
+    // The secret key is never returned
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["target_bucket"], "backup");
+    assert_eq!(body["region"], "us-east-1");
+    assert_eq!(body["prefix"], "docs/");
+    assert_eq!(body["secret_access_key"], "********");
+
+    let invalid = serde_json::json!({
+        "endpoint": "ftp://backup.example.com",
+        "target_bucket": "backup",
+        "access_key_id": "AKID",
+        "secret_access_key": "SECRET",
+    });
+    let resp = put_replication(&server, "repl-config", invalid).await;
+    assert_eq!(resp.status(), 400);
+
+    let resp = client.delete(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(&url).header("Authorization", &auth).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_replication_to_remote_endpoint() {
+    let source = start_source().await;
+    let target = TestServer::start().await;
+    let client = reqwest::Client::new();
+    let base = &source.base_url;
+
+    source.metadata.create_bucket("repl-source").unwrap();
+    target.metadata.create_bucket("repl-target").unwrap();
+    let config = serde_json::json!({
+        "endpoint": target.base_url,
+        "target_bucket": "repl-target",
+        "access_key_id": "TESTAKID",
+        "secret_access_key": "TESTSECRET",
+        "prefix": "docs/",
+    });
+    let resp = put_replication(&source, "repl-source", config).await;
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .put(format!("{}/repl-source/docs/report+final.txt", base))
+        .header("content-type", "text/plain")
+        .body("quarterly numbers")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = resp.headers().get("etag").unwrap().to_str().unwrap().trim_matches('"').to_string();
+    client.put(format!("{}/repl-source/tmp/scratch.txt", base)).body("x").send().await.unwrap();
+
+    let mut replicated = None;
+    for _ in 0..50 {
+        if let Ok(meta) = target.metadata.get_object_meta("repl-target", "docs/report+final.txt") {
+            replicated = Some(meta);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let meta = replicated.expect("Object was not replicated");
+    assert_eq!(meta.size, 17);
+    assert_eq!(meta.etag, etag);
+    assert_eq!(meta.content_type, "text/plain");
+
+    // The source reports the copy once it is recorded
+    let url = format!("{}/repl-source/docs/report+final.txt", base);
+    let mut status = None;
+    for _ in 0..50 {
+        let resp = client.head(&url).send().await.unwrap();
+        status = resp.headers().get("x-amz-replication-status").cloned();
+        if status.as_ref().is_some_and(|s| s == "COMPLETED") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status.unwrap(), "COMPLETED");
+
+    // Keys outside the prefix are not replicated
+    assert!(target.metadata.get_object_meta("repl-target", "tmp/scratch.txt").is_err());
+    let resp = client.get(format!("{}/repl-source/tmp/scratch.txt", base)).send().await.unwrap();
+    assert!(resp.headers().get("x-amz-replication-status").is_none());
+    let far_future = chrono::Utc::now() + chrono::Duration::days(1);
+    assert!(source.metadata.list_replication_tasks(far_future, 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_replication_failures_are_retried() {
+    let source = start_source().await;
+    let target = TestServer::start().await;
+    let client = reqwest::Client::new();
+
+    source.metadata.create_bucket("repl-retry").unwrap();
+    target.metadata.create_bucket("repl-retry-target").unwrap();
+    let config = serde_json::json!({
+        "endpoint": target.base_url,
+        "target_bucket": "repl-retry-target",
+        "access_key_id": "TESTAKID",
+        "secret_access_key": "WRONGSECRET",
+    });
+    put_replication(&source, "repl-retry", config).await;
+    client
+        .put(format!("{}/repl-retry/a.txt", source.base_url))
+        .body("a")
+        .send()
+        .await
+        .unwrap();
+
+    // The rejected copy stays queued with its error
+    let far_future = chrono::Utc::now() + chrono::Duration::days(1);
+    let mut task = None;
+    for _ in 0..50 {
+        let tasks = source.metadata.list_replication_tasks(far_future, 10).unwrap();
+        if let Some(t) = tasks.into_iter().find(|t| t.attempts > 0) {
+            task = Some(t);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let task = task.expect("Replication was not attempted");
+    assert_eq!(task.key, "a.txt");
+    assert!(task.last_error.unwrap().contains("403"));
+    assert!(task.retry_at.unwrap() > chrono::Utc::now() - chrono::Duration::seconds(1));
+    assert!(target.metadata.get_object_meta("repl-retry-target", "a.txt").is_err());
+
+    let resp = client.head(format!("{}/repl-retry/a.txt", source.base_url)).send().await.unwrap();
+    assert_eq!(resp.headers()["x-amz-replication-status"], "FAILED");
+
+    let resp = client.get(format!("{}/metrics", source.admin_base_url)).send().await.unwrap();
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("simples3_replication_queue_depth 1"));
+    assert!(body.contains("simples3_replication_failed_objects 1"));
+    assert!(body.contains("simples3_replication_lag_seconds"));
+    assert!(body.contains("simples3_replication_failures_total"));
+}
+
+#[tokio::test]
+async fn test_replication_filters_and_deletes() {
+    let source = start_source().await;
+    let target = TestServer::start().await;
+    let client = reqwest::Client::new();
+    let base = &source.base_url;
+
+    source.metadata.create_bucket("repl-filter").unwrap();
+    target.metadata.create_bucket("repl-filter-target").unwrap();
+    let config = serde_json::json!({
+        "endpoint": target.base_url,
+        "target_bucket": "repl-filter-target",
+        "access_key_id": "TESTAKID",
+        "secret_access_key": "TESTSECRET",
+        "prefix": "docs/",
+        "tags": {"class": "backup"},
+        "min_size": 8,
+        "replicate_deletes": true,
+    });
+    let resp = put_replication(&source, "repl-filter", config).await;
+    assert_eq!(resp.status(), 200);
+
+    for (key, tagging, body) in [
+        ("docs/kept.txt", Some("class=backup&owner=ops"), "large enough"),
+        ("docs/small.txt", Some("class=backup"), "tiny"),
+        ("docs/untagged.txt", None, "large enough"),
+        ("docs/other-class.txt", Some("class=scratch"), "large enough"),
+    ] {
+        let mut request = client.put(format!("{}/repl-filter/{}", base, key)).body(body);
+        if let Some(tagging) = tagging {
+            request = request.header("x-amz-tagging", tagging);
+        }
+        assert_eq!(request.send().await.unwrap().status(), 200);
+    }
+
+    // Only the object passing every filter is queued
+    let far_future = chrono::Utc::now() + chrono::Duration::days(1);
+    let mut replicated = false;
+    for _ in 0..50 {
+        if target.metadata.get_object_meta("repl-filter-target", "docs/kept.txt").is_ok() {
+            replicated = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(replicated, "Object was not replicated");
+    for key in ["docs/small.txt", "docs/untagged.txt", "docs/other-class.txt"] {
+        assert!(target.metadata.get_object_meta("repl-filter-target", key).is_err());
+        let resp = client.head(format!("{}/repl-filter/{}", base, key)).send().await.unwrap();
+        assert!(resp.headers().get("x-amz-replication-status").is_none());
+    }
+
+    // Deletes are propagated to the target
+    let resp = client.delete(format!("{}/repl-filter/docs/kept.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let mut deleted = false;
+    for _ in 0..50 {
+        if target.metadata.get_object_meta("repl-filter-target", "docs/kept.txt").is_err() {
+            deleted = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(deleted, "Delete was not replicated");
+    for _ in 0..50 {
+        if source.metadata.list_replication_tasks(far_future, 10).unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(source.metadata.list_replication_tasks(far_future, 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_replication_deletes_not_propagated_by_default() {
+    let source = start_source().await;
+    let target = TestServer::start().await;
+    let client = reqwest::Client::new();
+
+    source.metadata.create_bucket("repl-keep").unwrap();
+    target.metadata.create_bucket("repl-keep-target").unwrap();
+    let config = serde_json::json!({
+        "endpoint": target.base_url,
+        "target_bucket": "repl-keep-target",
+        "access_key_id": "TESTAKID",
+        "secret_access_key": "TESTSECRET",
+    });
+    put_replication(&source, "repl-keep", config).await;
+    client.put(format!("{}/repl-keep/a.txt", source.base_url)).body("a").send().await.unwrap();
+    for _ in 0..50 {
+        if target.metadata.get_object_meta("repl-keep-target", "a.txt").is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(target.metadata.get_object_meta("repl-keep-target", "a.txt").is_ok());
+
+    let resp = client.delete(format!("{}/repl-keep/a.txt", source.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let far_future = chrono::Utc::now() + chrono::Duration::days(1);
+    assert!(source.metadata.list_replication_tasks(far_future, 10).unwrap().is_empty());
+    assert!(target.metadata.get_object_meta("repl-keep-target", "a.txt").is_ok());
+}
+
+#[tokio::test]
+async fn test_replication_skips_quarantined_objects() {
+    use simples3_core::s3::types::{ModerationState, ObjectModeration, ObjectScan, ScanStatus};
+
+    let source = start_source().await;
+    let target = TestServer::start().await;
+    let client = reqwest::Client::new();
+    let base = &source.base_url;
+
+    source.metadata.create_bucket("repl-held").unwrap();
+    target.metadata.create_bucket("repl-held-target").unwrap();
+    for key in ["held.txt", "scanning.txt", "plain.txt"] {
+        client.put(format!("{}/repl-held/{}", base, key)).body(key).send().await.unwrap();
+    }
+    let moderation = ObjectModeration {
+        state: ModerationState::Quarantined,
+        reason: None,
+        updated: chrono::Utc::now(),
+    };
+    source.metadata.set_object_moderation("repl-held", "held.txt", &moderation).unwrap();
+    let scan = ObjectScan {
+        etag: source.metadata.get_object_meta("repl-held", "scanning.txt").unwrap().etag,
+        status: ScanStatus::Pending,
+        detail: None,
+        updated: chrono::Utc::now(),
+    };
+    source.metadata.put_object_scan("repl-held", "scanning.txt", &scan).unwrap();
+
+    let config = serde_json::json!({
+        "endpoint": target.base_url,
+        "target_bucket": "repl-held-target",
+        "access_key_id": "TESTAKID",
+        "secret_access_key": "TESTSECRET",
+    });
+    put_replication(&source, "repl-held", config).await;
+    for key in ["held.txt", "scanning.txt", "plain.txt"] {
+        source.metadata.queue_replication("repl-held", key).unwrap();
+    }
+    for _ in 0..50 {
+        if target.metadata.get_object_meta("repl-held-target", "plain.txt").is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(target.metadata.get_object_meta("repl-held-target", "plain.txt").is_ok());
+
+    // The quarantined object is dropped, the one being scanned waits without failing
+    let far_future = chrono::Utc::now() + chrono::Duration::days(1);
+    let mut tasks = Vec::new();
+    for _ in 0..50 {
+        tasks = source.metadata.list_replication_tasks(far_future, 10).unwrap();
+        if tasks.len() == 1 && tasks[0].retry_at.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].key, "scanning.txt");
+    assert_eq!(tasks[0].attempts, 0);
+    assert!(tasks[0].retry_at.is_some());
+    assert!(target.metadata.get_object_meta("repl-held-target", "held.txt").is_err());
+    assert!(target.metadata.get_object_meta("repl-held-target", "scanning.txt").is_err());
+
+    // Lifting the quarantine queues the object again
+    let resp = client
+        .put(format!("{}/_admin/buckets/repl-held/moderation/held.txt", source.admin_base_url))
+        .header("Authorization", format!("Bearer {}", ADMIN_TOKEN))
+        .json(&serde_json::json!({ "state": "active" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    for _ in 0..50 {
+        if target.metadata.get_object_meta("repl-held-target", "held.txt").is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(target.metadata.get_object_meta("repl-held-target", "held.txt").is_ok());
+}
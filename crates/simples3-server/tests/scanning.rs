@@ -0,0 +1,163 @@
+mod common;
+
+use common::TestServer;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const EICAR: &str = "X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+/// Start a minimal clamd answering `zINSTREAM` scans, flagging any stream
+/// containing the EICAR test string.
+async fn start_clamd() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut command = [0u8; 10];
+                stream.read_exact(&mut command).await.unwrap();
+                assert_eq!(&command, b"zINSTREAM\0");
+                let mut data = Vec::new();
+                loop {
+                    let len = stream.read_u32().await.unwrap() as usize;
+                    if len == 0 {
+                        break;
+                    }
+                    let mut chunk = vec![0u8; len];
+                    stream.read_exact(&mut chunk).await.unwrap();
+                    data.extend_from_slice(&chunk);
+                }
+                let reply: &[u8] = if String::from_utf8_lossy(&data).contains("EICAR") {
+                    b"stream: Eicar-Test-Signature FOUND\0"
+                } else {
+                    b"stream: OK\0"
+                };
+                stream.write_all(reply).await.unwrap();
+            });
+        }
+    });
+    format!("clamd://{}", addr)
+}
+
+/// Start an HTTP scanner with the webhook contract: `403` and the threat
+/// name for infected bodies, `200` otherwise.
+async fn start_webhook() -> String {
+    let app = axum::Router::new().route(
+        "/scan",
+        axum::routing::post(|headers: http::HeaderMap, body: String| async move {
+            assert!(headers.contains_key("x-simples3-key"));
+            if body.contains("EICAR") {
+                (http::StatusCode::FORBIDDEN, "Eicar-Test-Signature")
+            } else {
+                (http::StatusCode::OK, "")
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}/scan", addr)
+}
+
+/// Wait until the scan of `url` is no longer pending and return its status.
+async fn wait_for_scan(client: &reqwest::Client, url: &str) -> String {
+    for _ in 0..50 {
+        let resp = client.head(url).send().await.unwrap();
+        let status = resp
+            .headers()
+            .get("x-simples3-scan-status")
+            .map(|v| v.to_str().unwrap().to_string());
+        if let Some(status) = status
+            && status != "pending"
+        {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Scan of {} did not finish", url);
+}
+
+async fn check_quarantine(server: &TestServer) {
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/scan-bkt", base)).send().await.unwrap();
+    client.put(format!("{}/scan-bkt/clean.txt", base)).body("hello").send().await.unwrap();
+    client.put(format!("{}/scan-bkt/bad.txt", base)).body(EICAR).send().await.unwrap();
+
+    assert_eq!(wait_for_scan(&client, &format!("{}/scan-bkt/clean.txt", base)).await, "clean");
+    assert_eq!(wait_for_scan(&client, &format!("{}/scan-bkt/bad.txt", base)).await, "infected");
+
+    let resp = client.get(format!("{}/scan-bkt/clean.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.text().await.unwrap(), "hello");
+
+    // Infected objects cannot be read, copied or concatenated
+    let resp = client.get(format!("{}/scan-bkt/bad.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("ObjectQuarantined"));
+    let resp = client
+        .put(format!("{}/scan-bkt/copy.txt", base))
+        .header("x-amz-copy-source", "/scan-bkt/bad.txt")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    let resp = client
+        .post(format!("{}/_admin/buckets/scan-bkt/concatenate", server.admin_base_url))
+        .bearer_auth("scan-admin")
+        .json(&serde_json::json!({ "key": "joined.txt", "sources": ["clean.txt", "bad.txt"] }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+    assert!(resp.text().await.unwrap().contains("ObjectQuarantined"));
+    let resp = client.get(format!("{}/scan-bkt/joined.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .get(format!("{}/_admin/buckets/scan-bkt/scans?status=infected", server.admin_base_url))
+        .bearer_auth("scan-admin")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let scans: Vec<serde_json::Value> = resp.json().await.unwrap();
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans[0]["key"], "bad.txt");
+    assert_eq!(scans[0]["status"], "infected");
+    assert_eq!(scans[0]["detail"], "Eicar-Test-Signature");
+
+    // Overwriting with clean content lifts the quarantine
+    client.put(format!("{}/scan-bkt/bad.txt", base)).body("fixed").send().await.unwrap();
+    assert_eq!(wait_for_scan(&client, &format!("{}/scan-bkt/bad.txt", base)).await, "clean");
+    let resp = client.get(format!("{}/scan-bkt/bad.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn test_scan_with_clamd() {
+    let endpoint = start_clamd().await;
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("scan-admin".into());
+        c.scan_endpoint = Some(endpoint);
+    })
+    .await;
+    check_quarantine(&server).await;
+}
+
+#[tokio::test]
+async fn test_scan_with_webhook() {
+    let endpoint = start_webhook().await;
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("scan-admin".into());
+        c.scan_endpoint = Some(endpoint);
+    })
+    .await;
+    check_quarantine(&server).await;
+}
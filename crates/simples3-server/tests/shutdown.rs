@@ -0,0 +1,124 @@
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+/// Start the server binary on a free port with anonymous access, returning
+/// the process, its S3 address and its remaining log lines.
+fn start_server(dir: &std::path::Path, shutdown_timeout: u64) -> (Child, String, std::sync::mpsc::Receiver<String>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_simples3-server"))
+        .env("SIMPLES3_BIND", "127.0.0.1:0")
+        .env("SIMPLES3_ADMIN_ENABLED", "false")
+        .env("SIMPLES3_ANONYMOUS_GLOBAL", "true")
+        .env("SIMPLES3_SHUTDOWN_TIMEOUT", shutdown_timeout.to_string())
+        .env("SIMPLES3_DATA_DIR", dir.join("data"))
+        .env("SIMPLES3_METADATA_DIR", dir.join("metadata"))
+        .env("NO_COLOR", "1")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    let (lines_tx, lines) = std::sync::mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = lines_tx.send(line);
+        }
+    });
+    let addr = loop {
+        let line = lines.recv_timeout(Duration::from_secs(30)).expect("server did not start");
+        if let Some((_, rest)) = line.split_once("S3 API listening on ") {
+            break rest.split_whitespace().next().unwrap().to_string();
+        }
+    };
+    (child, addr, lines)
+}
+
+fn terminate(child: &Child) {
+    let status = Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[tokio::test]
+async fn test_sigterm_drains_uploads() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut child, addr, lines) = start_server(dir.path(), 30);
+    let base = format!("http://{}", addr);
+    let client = reqwest::Client::new();
+    assert_eq!(client.put(format!("{}/drain", base)).send().await.unwrap().status(), 200);
+
+    // An upload whose body is still arriving when the signal comes
+    let (body_tx, body_rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(1);
+    let stream = futures_util::stream::unfold(body_rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+    let upload = tokio::spawn(
+        client
+            .put(format!("{}/drain/slow.bin", base))
+            .header("content-length", "8")
+            .body(reqwest::Body::wrap_stream(stream))
+            .send(),
+    );
+    body_tx.send(Ok(b"1234".to_vec())).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    terminate(&child);
+    let waiting = loop {
+        let line = lines.recv_timeout(Duration::from_secs(10)).expect("no shutdown log");
+        if line.contains("Waiting for requests in flight") {
+            break line;
+        }
+    };
+    assert!(waiting.contains("requests=1"), "{}", waiting);
+    // No new connections are accepted while draining
+    assert!(reqwest::get(format!("{}/drain", base)).await.is_err());
+
+    body_tx.send(Ok(b"5678".to_vec())).await.unwrap();
+    drop(body_tx);
+    let resp = upload.await.unwrap().unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let exit = tokio::task::spawn_blocking(move || child.wait().unwrap()).await.unwrap();
+    assert!(exit.success());
+    let rest: Vec<String> = lines.try_iter().collect();
+    assert!(rest.iter().any(|l| l.contains("All requests finished")));
+    assert!(rest.iter().any(|l| l.contains("Metadata store flushed")));
+
+    // The upload was recorded before exit
+    let (mut child, addr, _lines) = start_server(dir.path(), 30);
+    let resp = reqwest::get(format!("http://{}/drain/slow.bin", addr)).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.bytes().await.unwrap().as_ref(), b"12345678");
+    terminate(&child);
+    child.wait().unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_timeout_cuts_off_requests() {
+    let dir = tempfile::tempdir().unwrap();
+    let (mut child, addr, lines) = start_server(dir.path(), 1);
+    let base = format!("http://{}", addr);
+    let client = reqwest::Client::new();
+    client.put(format!("{}/stuck", base)).send().await.unwrap();
+
+    // An upload that never completes
+    let (body_tx, body_rx) = tokio::sync::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(1);
+    let stream = futures_util::stream::unfold(body_rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+    let _upload = tokio::spawn(
+        client
+            .put(format!("{}/stuck/never.bin", base))
+            .header("content-length", "8")
+            .body(reqwest::Body::wrap_stream(stream))
+            .send(),
+    );
+    body_tx.send(Ok(b"1234".to_vec())).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    terminate(&child);
+    let exit = tokio::task::spawn_blocking(move || child.wait().unwrap()).await.unwrap();
+    assert!(exit.success());
+    let rest: Vec<String> = lines.try_iter().collect();
+    assert!(rest.iter().any(|l| l.contains("Shutdown timeout reached")));
+}
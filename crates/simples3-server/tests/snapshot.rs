@@ -0,0 +1,73 @@
+mod common;
+
+use common::TestServer;
+use simples3_core::Config;
+use simples3_core::storage::MetadataStore;
+use simples3_core::storage::snapshot;
+use simples3_server::snapshot_shipper::{self, SnapshotTarget};
+
+#[tokio::test]
+async fn test_ship_snapshot_to_directory_and_restore() {
+    let server = TestServer::start().await;
+    server.metadata.create_bucket("shipped-bucket").unwrap();
+    let target_dir = tempfile::tempdir().unwrap();
+    let target = SnapshotTarget::Directory(target_dir.path().to_path_buf());
+
+    let mut shipped = snapshot_shipper::shipped_files(&target).await.unwrap();
+    assert!(shipped.is_empty());
+    let first = snapshot_shipper::ship_once(&server.metadata, &target, &mut shipped).await.unwrap();
+    assert!(first.files_uploaded > 0);
+    assert!(target_dir.path().join(&first.manifest).exists());
+
+    // Nothing changed, so a fresh shipper picks up the shipped set and uploads nothing
+    let mut shipped = snapshot_shipper::shipped_files(&target).await.unwrap();
+    let second = snapshot_shipper::ship_once(&server.metadata, &target, &mut shipped).await.unwrap();
+    assert_eq!(second.files_uploaded, 0);
+
+    // Only the changed tree is uploaded
+    server.metadata.set_bucket_anonymous_read("shipped-bucket", true).unwrap();
+    let third = snapshot_shipper::ship_once(&server.metadata, &target, &mut shipped).await.unwrap();
+    assert_eq!(third.files_uploaded, 1);
+
+    let restored_dir = tempfile::tempdir().unwrap();
+    let manifest = snapshot::read_manifest(target_dir.path(), None).unwrap();
+    snapshot::restore(target_dir.path(), &manifest, restored_dir.path()).unwrap();
+    let restored = MetadataStore::open(restored_dir.path()).unwrap();
+    assert!(restored.get_bucket("shipped-bucket").unwrap().anonymous_read);
+    assert!(restored.get_credential("TESTAKID").is_ok());
+}
+
+#[tokio::test]
+async fn test_ship_snapshot_to_s3_target() {
+    let server = TestServer::start().await;
+    server.metadata.create_bucket("standby").unwrap();
+
+    let config = Config {
+        snapshot_target: Some("s3://standby/simples3/".into()),
+        snapshot_s3_endpoint: Some(server.base_url.clone()),
+        snapshot_s3_access_key: Some("TESTAKID".into()),
+        snapshot_s3_secret_key: Some("TESTSECRET".into()),
+        ..Config::default()
+    };
+    let target = SnapshotTarget::from_config(&config).unwrap().unwrap();
+
+    let mut shipped = snapshot_shipper::shipped_files(&target).await.unwrap();
+    let report = snapshot_shipper::ship_once(&server.metadata, &target, &mut shipped).await.unwrap();
+    assert!(report.files_uploaded > 0);
+    assert!(server.metadata.get_object_meta("standby", "simples3/latest.json").is_ok());
+    assert!(server.metadata.get_object_meta("standby", &format!("simples3/{}", report.manifest)).is_ok());
+
+    // The previous manifest is read back over S3
+    let shipped = snapshot_shipper::shipped_files(&target).await.unwrap();
+    assert_eq!(shipped.len(), report.files_uploaded);
+}
+
+#[test]
+fn test_s3_snapshot_target_requires_credentials() {
+    let config = Config {
+        snapshot_target: Some("s3://standby".into()),
+        snapshot_s3_endpoint: Some("http://127.0.0.1:9000".into()),
+        ..Config::default()
+    };
+    assert!(SnapshotTarget::from_config(&config).is_err());
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::TestServer;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const TOKEN: &str = "sync-admin";
+
+async fn start() -> TestServer {
+    TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some(TOKEN.into());
+    })
+    .await
+}
+
+async fn put(client: &reqwest::Client, url: String, body: &'static str) {
+    let resp = client.put(url).body(body).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+/// Start a sync job and wait for it to finish.
+async fn sync(server: &TestServer, request: serde_json::Value) -> serde_json::Value {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/_admin/sync-jobs", server.admin_base_url))
+        .bearer_auth(TOKEN)
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 202);
+    let job: serde_json::Value = resp.json().await.unwrap();
+    let id = job["id"].as_str().unwrap();
+    for _ in 0..100 {
+        let job: serde_json::Value = client
+            .get(format!("{}/_admin/sync-jobs/{}", server.admin_base_url, id))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if job["status"] != "running" {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("sync job {} did not finish", id);
+}
+
+#[tokio::test]
+async fn test_sync_job() {
+    let server = start().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    for bucket in ["sync-blue", "sync-green"] {
+        put(&client, format!("{}/{}", base, bucket), "").await;
+    }
+    put(&client, format!("{}/sync-blue/same.txt", base), "same").await;
+    put(&client, format!("{}/sync-blue/dir/new.txt", base), "new data").await;
+    put(&client, format!("{}/sync-green/same.txt", base), "same").await;
+    put(&client, format!("{}/sync-green/stale.txt", base), "stale").await;
+    let tags = HashMap::from([("team".to_string(), "blue".to_string())]);
+    server.metadata.put_object_tagging("sync-blue", "same.txt", &tags).unwrap();
+    server.metadata.put_object_tagging("sync-blue", "dir/new.txt", &tags).unwrap();
+
+    let request = serde_json::json!({
+        "source_bucket": "sync-blue",
+        "target_bucket": "sync-green",
+        "delete": true,
+        "copy_tags": true,
+    });
+    let job = sync(&server, request.clone()).await;
+    assert_eq!(job["status"], "completed");
+    let progress = &job["progress"];
+    assert_eq!(progress["scanned"], 2);
+    assert_eq!(progress["copied"], 1);
+    assert_eq!(progress["bytes_copied"], 8);
+    assert_eq!(progress["skipped"], 1);
+    assert_eq!(progress["tags_updated"], 1);
+    assert_eq!(progress["deleted"], 1);
+    assert_eq!(progress["failed"], 0);
+
+    let resp = client.get(format!("{}/sync-green/dir/new.txt", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "new data");
+    let resp = client.get(format!("{}/sync-green/stale.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    for key in ["same.txt", "dir/new.txt"] {
+        assert_eq!(server.metadata.get_object_tagging("sync-green", key).unwrap(), tags);
+    }
+    let source = server.metadata.get_object_meta("sync-blue", "dir/new.txt").unwrap();
+    let target = server.metadata.get_object_meta("sync-green", "dir/new.txt").unwrap();
+    assert_eq!(target.etag, source.etag);
+
+    // A second run finds nothing to do
+    let job = sync(&server, request).await;
+    assert_eq!(job["progress"]["skipped"], 2);
+    assert_eq!(job["progress"]["copied"], 0);
+    assert_eq!(job["progress"]["tags_updated"], 0);
+
+    let jobs: Vec<serde_json::Value> = client
+        .get(format!("{}/_admin/sync-jobs", server.admin_base_url))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(jobs.len(), 2);
+}
+
+#[tokio::test]
+async fn test_sync_job_prefixes() {
+    let server = start().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    put(&client, format!("{}/sync-prefix", base), "").await;
+    put(&client, format!("{}/sync-prefix/old/a.txt", base), "a").await;
+    put(&client, format!("{}/sync-prefix/other.txt", base), "other").await;
+    put(&client, format!("{}/sync-prefix/new/orphan.txt", base), "orphan").await;
+
+    let job = sync(
+        &server,
+        serde_json::json!({
+            "source_bucket": "sync-prefix",
+            "source_prefix": "old/",
+            "target_bucket": "sync-prefix",
+            "target_prefix": "new/",
+        }),
+    )
+    .await;
+    assert_eq!(job["progress"]["copied"], 1);
+    let resp = client.get(format!("{}/sync-prefix/new/a.txt", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "a");
+    // Without delete, target objects missing from the source are kept
+    let resp = client.get(format!("{}/sync-prefix/new/orphan.txt", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Overlapping prefixes and unknown buckets are rejected
+    for request in [
+        serde_json::json!({ "source_bucket": "sync-prefix", "source_prefix": "old/", "target_bucket": "sync-prefix", "target_prefix": "old/x/" }),
+        serde_json::json!({ "source_bucket": "sync-prefix", "target_bucket": "sync-missing" }),
+    ] {
+        let resp = client
+            .post(format!("{}/_admin/sync-jobs", server.admin_base_url))
+            .bearer_auth(TOKEN)
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert!(resp.status().is_client_error(), "{}", request);
+    }
+
+    let resp = client
+        .get(format!("{}/_admin/sync-jobs/unknown", server.admin_base_url))
+        .bearer_auth(TOKEN)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
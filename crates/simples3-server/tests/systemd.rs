@@ -0,0 +1,44 @@
+#![cfg(unix)]
+
+use std::os::unix::net::UnixDatagram;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+fn recv(socket: &UnixDatagram) -> String {
+    let mut buf = [0u8; 256];
+    let n = socket.recv(&mut buf).expect("no notification from the server");
+    String::from_utf8_lossy(&buf[..n]).into_owned()
+}
+
+#[test]
+fn test_notifies_systemd_of_readiness_and_shutdown() {
+    let dir = tempfile::tempdir().unwrap();
+    let notify_path = dir.path().join("notify.sock");
+    let socket = UnixDatagram::bind(&notify_path).unwrap();
+    socket.set_read_timeout(Some(Duration::from_secs(30))).unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_simples3-server"))
+        .env("NOTIFY_SOCKET", &notify_path)
+        .env("SIMPLES3_BIND", "127.0.0.1:0")
+        .env("SIMPLES3_ADMIN_ENABLED", "false")
+        .env("SIMPLES3_DATA_DIR", dir.path().join("data"))
+        .env("SIMPLES3_METADATA_DIR", dir.path().join("metadata"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let ready = recv(&socket);
+
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let stopping = recv(&socket);
+    let exit = child.wait().unwrap();
+
+    assert_eq!(ready, "READY=1");
+    assert_eq!(stopping, "STOPPING=1");
+    assert!(exit.success());
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::TestServer;
+
+#[tokio::test]
+async fn test_tls_listeners() {
+    let dir = tempfile::tempdir().unwrap();
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.path().join("cert.pem");
+    let key_path = dir.path().join("key.pem");
+    std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+    std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.admin_token = Some("tls-admin".into());
+        c.tls_cert = Some(cert_path.clone());
+        c.tls_key = Some(key_path.clone());
+    })
+    .await;
+    assert!(server.base_url.starts_with("https://"));
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(certified.cert.pem().as_bytes()).unwrap())
+        .build()
+        .unwrap();
+    client.put(format!("{}/tls-bucket", server.base_url)).send().await.unwrap();
+    let resp = client
+        .put(format!("{}/tls-bucket/hello.txt", server.base_url))
+        .body("over tls")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(format!("{}/tls-bucket/hello.txt", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "over tls");
+
+    let resp = client
+        .get(format!("{}/_admin/buckets", server.admin_base_url))
+        .bearer_auth("tls-admin")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Plain HTTP is not served, and a stalled handshake does not block others
+    let _silent = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    let plain = reqwest::get(format!("http://{}/tls-bucket/hello.txt", server.addr)).await;
+    assert!(plain.is_err() || !plain.unwrap().status().is_success());
+    let resp = client
+        .get(format!("{}/tls-bucket/hello.txt", server.base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+fn client_ca() -> rcgen::CertifiedIssuer<'static, rcgen::KeyPair> {
+    let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    rcgen::CertifiedIssuer::self_signed(params, rcgen::KeyPair::generate().unwrap()).unwrap()
+}
+
+/// A client certificate for `name` signed by `ca`, as a reqwest identity.
+fn client_identity(ca: &rcgen::CertifiedIssuer<'static, rcgen::KeyPair>, name: &str) -> reqwest::Identity {
+    let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.distinguished_name.push(rcgen::DnType::CommonName, name);
+    let key = rcgen::KeyPair::generate().unwrap();
+    let cert = params.signed_by(&key, ca).unwrap();
+    reqwest::Identity::from_pem(format!("{}{}", cert.pem(), key.serialize_pem()).as_bytes()).unwrap()
+}
+
+#[tokio::test]
+async fn test_admin_mutual_tls() {
+    let dir = tempfile::tempdir().unwrap();
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let ca = client_ca();
+    let (cert_path, key_path, ca_path) = (dir.path().join("cert.pem"), dir.path().join("key.pem"), dir.path().join("ca.pem"));
+    std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+    std::fs::write(&key_path, certified.signing_key.serialize_pem()).unwrap();
+    std::fs::write(&ca_path, ca.pem()).unwrap();
+
+    // No admin token: the client certificate is the only credential
+    let server = TestServer::start_with_config(|c| {
+        c.anonymous_global = true;
+        c.tls_cert = Some(cert_path.clone());
+        c.tls_key = Some(key_path.clone());
+        c.admin_client_ca = Some(ca_path.clone());
+    })
+    .await;
+    let server_root = reqwest::Certificate::from_pem(certified.cert.pem().as_bytes()).unwrap();
+    let client = |identity: Option<reqwest::Identity>| {
+        let builder = reqwest::Client::builder().add_root_certificate(server_root.clone());
+        match identity {
+            Some(identity) => builder.identity(identity),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    };
+
+    let trusted = client(Some(client_identity(&ca, "ops-laptop")));
+    let resp = trusted
+        .get(format!("{}/_admin/buckets", server.admin_base_url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Clients without a certificate, or with one from another CA, are refused
+    let anonymous = client(None);
+    let other = client(Some(client_identity(&client_ca(), "intruder")));
+    for client in [&anonymous, &other] {
+        let resp = client.get(format!("{}/_admin/buckets", server.admin_base_url)).send().await;
+        assert!(resp.is_err(), "{:?}", resp);
+    }
+
+    // The S3 listener does not ask for one
+    let resp = anonymous.put(format!("{}/mtls-bucket", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[test]
+fn test_tls_config_errors() {
+    let config = simples3_core::Config {
+        tls_cert: Some("/nonexistent/cert.pem".into()),
+        tls_key: Some("/nonexistent/key.pem".into()),
+        ..Default::default()
+    };
+    let err = simples3_server::tls::server_config(&config).unwrap_err();
+    assert!(err.contains("/nonexistent/cert.pem"), "{}", err);
+    assert!(simples3_server::tls::server_config(&Default::default()).unwrap().is_none());
+}
@@ -46,3 +46,31 @@ async fn test_virtual_host_put_and_get() {
     let body = resp.text().await.unwrap();
     assert_eq!(body, "virtual host data");
 }
+
+#[tokio::test]
+async fn test_virtual_host_in_flight_operation() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = TestServer::start_anonymous().await;
+    server.metadata.create_bucket("vh-load").unwrap();
+    let client = reqwest::Client::new();
+
+    // A stalled upload stays in flight under its object operation
+    let mut stalled = tokio::net::TcpStream::connect(server.addr).await.unwrap();
+    let request = format!(
+        "PUT /slow.bin HTTP/1.1\r\nHost: vh-load.s3.localhost:{}\r\nContent-Length: 8\r\n\r\n0123",
+        server.addr.port()
+    );
+    stalled.write_all(request.as_bytes()).await.unwrap();
+    let mut body = String::new();
+    for _ in 0..100 {
+        let resp = client.get(format!("{}/metrics", server.admin_base_url)).send().await.unwrap();
+        body = resp.text().await.unwrap();
+        if body.contains("simples3_requests_in_flight{operation=\"PutObject\"} 1") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(body.contains("simples3_requests_in_flight{operation=\"PutObject\"} 1"));
+    assert!(!body.contains("simples3_requests_in_flight{operation=\"CreateBucket\"} 1"));
+}
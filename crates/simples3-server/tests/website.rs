@@ -0,0 +1,197 @@
+mod common;
+
+use common::TestServer;
+
+#[tokio::test]
+async fn test_website_crud() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::new();
+    let base = &server.base_url;
+
+    client.put(format!("{}/site-bkt", base)).send().await.unwrap();
+
+    // No website config initially
+    let resp = client.get(format!("{}/site-bkt?website", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert!(resp.text().await.unwrap().contains("NoSuchWebsiteConfiguration"));
+
+    let website_xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<WebsiteConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <IndexDocument><Suffix>index.html</Suffix></IndexDocument>
+  <ErrorDocument><Key>error.html</Key></ErrorDocument>
+</WebsiteConfiguration>"#;
+    let resp = client
+        .put(format!("{}/site-bkt?website", base))
+        .body(website_xml)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = client.get(format!("{}/site-bkt?website", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body = resp.text().await.unwrap();
+    assert!(body.contains("<Suffix>index.html</Suffix>"));
+    assert!(body.contains("<Key>error.html</Key>"));
+
+    // An index suffix with a slash is rejected
+    let resp = client
+        .put(format!("{}/site-bkt?website", base))
+        .body("<WebsiteConfiguration><IndexDocument><Suffix>a/index.html</Suffix></IndexDocument></WebsiteConfiguration>")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let resp = client.delete(format!("{}/site-bkt?website", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 204);
+    let resp = client.get(format!("{}/site-bkt?website", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+
+    // Unknown bucket
+    let resp = client.get(format!("{}/no-such-site?website", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert!(resp.text().await.unwrap().contains("NoSuchBucket"));
+}
+
+const SITE_XML: &str = r#"<WebsiteConfiguration>
+  <IndexDocument><Suffix>index.html</Suffix></IndexDocument>
+  <ErrorDocument><Key>error.html</Key></ErrorDocument>
+  <RoutingRules>
+    <RoutingRule>
+      <Condition><KeyPrefixEquals>old/</KeyPrefixEquals></Condition>
+      <Redirect><ReplaceKeyPrefixWith>new/</ReplaceKeyPrefixWith></Redirect>
+    </RoutingRule>
+    <RoutingRule>
+      <Condition><KeyPrefixEquals>archive/</KeyPrefixEquals><HttpErrorCodeReturnedEquals>404</HttpErrorCodeReturnedEquals></Condition>
+      <Redirect><HostName>archive.example.com</HostName><Protocol>https</Protocol><HttpRedirectCode>302</HttpRedirectCode></Redirect>
+    </RoutingRule>
+  </RoutingRules>
+</WebsiteConfiguration>"#;
+
+#[tokio::test]
+async fn test_website_serving() {
+    let server = TestServer::start_anonymous().await;
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let base = &server.base_url;
+
+    client.put(format!("{}/www", base)).send().await.unwrap();
+    for (key, body) in [
+        ("index.html", "home"),
+        ("docs/index.html", "docs home"),
+        ("error.html", "not here"),
+        ("archive/kept.html", "kept"),
+    ] {
+        client.put(format!("{}/www/{}", base, key)).body(body).send().await.unwrap();
+    }
+    let resp = client.put(format!("{}/www?website", base)).body(SITE_XML).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // Index documents
+    for path in ["/www", "/www/"] {
+        let resp = client.get(format!("{}{}", base, path)).send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.text().await.unwrap(), "home");
+    }
+    let resp = client.get(format!("{}/www/docs/", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "docs home");
+    let resp = client.get(format!("{}/www/docs", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 302);
+    assert_eq!(resp.headers().get("location").unwrap(), "/www/docs/");
+
+    // Missing keys get the error document
+    let resp = client.get(format!("{}/www/nope.html", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.text().await.unwrap(), "not here");
+
+    // Routing rules
+    let resp = client.get(format!("{}/www/old/page.html", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 301);
+    assert_eq!(resp.headers().get("location").unwrap(), "/www/new/page.html");
+    let resp = client.get(format!("{}/www/archive/kept.html", base)).send().await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "kept");
+    let resp = client.get(format!("{}/www/archive/gone.html", base)).send().await.unwrap();
+    assert_eq!(resp.status(), 302);
+    assert_eq!(resp.headers().get("location").unwrap(), "https://archive.example.com/archive/gone.html");
+
+    // Virtual-host requests are redirected within their own host
+    let host = format!("www.s3.localhost:{}", server.addr.port());
+    let resp = client.get(format!("{}/docs", base)).header("host", &host).send().await.unwrap();
+    assert_eq!(resp.status(), 302);
+    assert_eq!(resp.headers().get("location").unwrap(), "/docs/");
+    let resp = client.get(format!("{}/old/page.html", base)).header("host", &host).send().await.unwrap();
+    assert_eq!(resp.status(), 301);
+    assert_eq!(resp.headers().get("location").unwrap(), "/new/page.html");
+
+    // Requests with S3 query parameters are not website requests
+    let resp = client.get(format!("{}/www?list-type=2", base)).send().await.unwrap();
+    assert!(resp.text().await.unwrap().contains("<ListBucketResult"));
+}
+
+#[tokio::test]
+async fn test_website_requires_read_access() {
+    let server = TestServer::start().await;
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    server.metadata.create_bucket("private-www").unwrap();
+    let config = simples3_core::s3::xml::parse_website_configuration_xml(SITE_XML.as_bytes()).unwrap();
+    server.metadata.put_website_configuration("private-www", &config, None).unwrap();
+
+    let resp = client.get(format!("{}/private-www/", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 403);
+
+    // Prefix redirects do not need read access
+    let resp = client.get(format!("{}/private-www/old/a.html", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 301);
+}
+
+#[tokio::test]
+async fn test_website_error_document_requires_read_access() {
+    use simples3_core::s3::types::{BucketPolicy, ObjectMeta, OneOrMany, PolicyEffect, PolicyPrincipal, PolicyStatement};
+
+    let server = TestServer::start().await;
+    let client = reqwest::Client::new();
+    server.metadata.create_bucket("half-www").unwrap();
+    let config = simples3_core::s3::xml::parse_website_configuration_xml(SITE_XML.as_bytes()).unwrap();
+    server.metadata.put_website_configuration("half-www", &config, None).unwrap();
+    let store = server.state.storage.default_store();
+    store.create_bucket_dir("half-www").await.unwrap();
+    let (size, etag) = store.write_object("half-www", "error.html", b"internal").await.unwrap();
+    server
+        .metadata
+        .put_object_meta(&ObjectMeta {
+            bucket: "half-www".into(),
+            key: "error.html".into(),
+            size,
+            etag,
+            content_type: "text/html".into(),
+            last_modified: chrono::Utc::now(),
+            public: false,
+            checksum: None,
+            legal_hold: false,
+            storage_class: None,
+        })
+        .unwrap();
+    // Only the pages are readable anonymously, not the error document
+    let policy = BucketPolicy {
+        version: "2012-10-17".into(),
+        statements: vec![PolicyStatement {
+            sid: None,
+            effect: PolicyEffect::Allow,
+            principal: PolicyPrincipal::Wildcard("*".into()),
+            action: OneOrMany::One("s3:GetObject".into()),
+            resource: OneOrMany::One("arn:aws:s3:::half-www/pages/*".into()),
+            condition: None,
+        }],
+    };
+    server.metadata.put_bucket_policy("half-www", &policy).unwrap();
+
+    let resp = client.get(format!("{}/half-www/pages/missing.html", server.base_url)).send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    assert!(resp.text().await.unwrap().contains("<Code>NoSuchKey</Code>"));
+}